@@ -1,41 +1,63 @@
-use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
+};
+use matching_engine::loadgen::{run_load, LoadProfile, LoadTarget};
 use matching_engine::{
-    MatchingEngine, Order, OrderBook, OrderSide, OrderType, SafeOrderBook, Symbol, Trade,
+    MatchingEngine, Order, OrderBook, OrderSide, OrderType, Symbol, Trade, TradeType,
 };
 use std::sync::Arc;
 use std::time::Duration;
 
+fn symbol() -> Symbol {
+    Symbol::new("BTC", "USDT")
+}
+
+fn limit_order(side: OrderSide, price: f64, user_id: String) -> Order {
+    Order::new(symbol(), side, OrderType::Limit, 1.0, Some(price), user_id)
+}
+
+/// 互不成交的买卖挂单：买单在 50000 以下，卖单在 50000 以上
+fn resting_orders(count: usize) -> Vec<Order> {
+    (0..count)
+        .map(|i| {
+            let offset = (i / 2 % 500) as f64 + 1.0;
+            if i % 2 == 0 {
+                limit_order(OrderSide::Buy, 50000.0 - offset, format!("user_{}", i))
+            } else {
+                limit_order(OrderSide::Sell, 50000.0 + offset, format!("user_{}", i))
+            }
+        })
+        .collect()
+}
+
+/// 预填充挂单的新引擎
+fn engine_with(orders: Vec<Order>) -> MatchingEngine {
+    let engine = MatchingEngine::new();
+    for order in orders {
+        engine.submit_order_blocking(order).unwrap();
+    }
+    engine
+}
+
 /// 基准测试：订单提交性能
+///
+/// 每次迭代使用新的引擎，避免订单簿和订单存储跨迭代增长
 fn bench_order_submission(c: &mut Criterion) {
     let mut group = c.benchmark_group("order_submission");
     group.measurement_time(Duration::from_secs(10));
 
     for size in [100, 1000, 10000].iter() {
         group.bench_with_input(BenchmarkId::new("submit_orders", size), size, |b, &size| {
-            let engine = Arc::new(MatchingEngine::new());
-            let symbol = Symbol::new("BTC", "USDT");
-
-            b.iter(|| {
-                for i in 0..size {
-                    let order = Order::new(
-                        symbol.clone(),
-                        if i % 2 == 0 {
-                            OrderSide::Buy
-                        } else {
-                            OrderSide::Sell
-                        },
-                        OrderType::Limit,
-                        1.0,
-                        Some(50000.0 + (i as f64)),
-                        format!("user_{}", i),
-                    );
-
-                    let rt = tokio::runtime::Runtime::new().unwrap();
-                    rt.block_on(async {
-                        let _ = engine.submit_order(order).await;
-                    });
-                }
-            });
+            b.iter_batched(
+                || (MatchingEngine::new(), resting_orders(size)),
+                |(engine, orders)| {
+                    for order in orders {
+                        engine.submit_order_blocking(order).unwrap();
+                    }
+                    engine
+                },
+                BatchSize::PerIteration,
+            );
         });
     }
     group.finish();
@@ -45,41 +67,28 @@ fn bench_order_submission(c: &mut Criterion) {
 fn bench_orderbook_operations(c: &mut Criterion) {
     let mut group = c.benchmark_group("orderbook_operations");
 
-    // 测试添加订单
-    group.bench_function("add_order", |b| {
-        let mut orderbook = OrderBook::new(Symbol::new("BTC", "USDT"));
+    // 在 1000 笔挂单的订单簿上挂单再撤单，订单簿规模保持不变
+    group.bench_function("add_remove_order", |b| {
+        let mut orderbook = OrderBook::new(symbol());
+        for order in resting_orders(1000) {
+            orderbook.add_order(order).unwrap();
+        }
 
-        b.iter(|| {
-            let order = Order::new(
-                Symbol::new("BTC", "USDT"),
-                OrderSide::Buy,
-                OrderType::Limit,
-                1.0,
-                Some(50000.0),
-                "user".to_string(),
-            );
-            black_box(orderbook.add_order(order).unwrap());
-        });
+        b.iter_batched(
+            || limit_order(OrderSide::Buy, 49900.0, "user".to_string()),
+            |order| {
+                let order_id = order.id;
+                orderbook.add_order(black_box(order)).unwrap();
+                orderbook.remove_order(order_id).unwrap()
+            },
+            BatchSize::SmallInput,
+        );
     });
 
     // 测试获取最佳价格
     group.bench_function("get_best_prices", |b| {
-        let mut orderbook = OrderBook::new(Symbol::new("BTC", "USDT"));
-
-        // 预填充一些订单
-        for i in 0..1000 {
-            let order = Order::new(
-                Symbol::new("BTC", "USDT"),
-                if i % 2 == 0 {
-                    OrderSide::Buy
-                } else {
-                    OrderSide::Sell
-                },
-                OrderType::Limit,
-                1.0,
-                Some(50000.0 + (i as f64)),
-                format!("user_{}", i),
-            );
+        let mut orderbook = OrderBook::new(symbol());
+        for order in resting_orders(1000) {
             orderbook.add_order(order).unwrap();
         }
 
@@ -92,22 +101,8 @@ fn bench_orderbook_operations(c: &mut Criterion) {
 
     // 测试获取订单簿深度
     group.bench_function("get_depth", |b| {
-        let mut orderbook = OrderBook::new(Symbol::new("BTC", "USDT"));
-
-        // 预填充一些订单
-        for i in 0..1000 {
-            let order = Order::new(
-                Symbol::new("BTC", "USDT"),
-                if i % 2 == 0 {
-                    OrderSide::Buy
-                } else {
-                    OrderSide::Sell
-                },
-                OrderType::Limit,
-                1.0,
-                Some(50000.0 + (i as f64)),
-                format!("user_{}", i),
-            );
+        let mut orderbook = OrderBook::new(symbol());
+        for order in resting_orders(1000) {
             orderbook.add_order(order).unwrap();
         }
 
@@ -120,54 +115,49 @@ fn bench_orderbook_operations(c: &mut Criterion) {
 }
 
 /// 基准测试：撮合性能
+///
+/// 每次迭代在预填充 `size` 笔卖单的新引擎上提交同样数量的买单，每笔买单吃掉一笔卖单
 fn bench_matching_performance(c: &mut Criterion) {
     let mut group = c.benchmark_group("matching_performance");
     group.measurement_time(Duration::from_secs(15));
 
     for size in [100, 500, 1000].iter() {
         group.bench_with_input(BenchmarkId::new("match_orders", size), size, |b, &size| {
-            let engine = Arc::new(MatchingEngine::new());
-            let symbol = Symbol::new("BTC", "USDT");
-
-            // 预填充卖单
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            for i in 0..size {
-                let sell_order = Order::new(
-                    symbol.clone(),
-                    OrderSide::Sell,
-                    OrderType::Limit,
-                    1.0,
-                    Some(50000.0 + (i as f64)),
-                    format!("seller_{}", i),
-                );
-                rt.block_on(async {
-                    let _ = engine.submit_order(sell_order).await;
-                });
-            }
-
-            b.iter(|| {
-                // 提交买单进行撮合
-                for i in 0..size {
-                    let buy_order = Order::new(
-                        symbol.clone(),
-                        OrderSide::Buy,
-                        OrderType::Limit,
-                        1.0,
-                        Some(50000.0 + (i as f64) + 100.0), // 确保能匹配
-                        format!("buyer_{}", i),
-                    );
-
-                    rt.block_on(async {
-                        let _ = engine.submit_order(buy_order).await;
-                    });
-                }
-            });
+            b.iter_batched(
+                || {
+                    let sells = (0..size)
+                        .map(|i| {
+                            limit_order(
+                                OrderSide::Sell,
+                                50000.0 + i as f64,
+                                format!("seller_{}", i),
+                            )
+                        })
+                        .collect();
+                    let buys: Vec<_> = (0..size)
+                        .map(|i| {
+                            limit_order(OrderSide::Buy, 50000.0 + i as f64, format!("buyer_{}", i))
+                        })
+                        .collect();
+                    (engine_with(sells), buys)
+                },
+                |(engine, buys)| {
+                    for buy in buys {
+                        let trades = engine.submit_order_blocking(buy).unwrap();
+                        debug_assert_eq!(trades.len(), 1);
+                    }
+                    engine
+                },
+                BatchSize::PerIteration,
+            );
         });
     }
     group.finish();
 }
 
-/// 基准测试：并发性能
+/// 基准测试：多线程同步提交
+///
+/// 每个线程通过同步接口提交 100 笔订单，线程各自复用自己的单线程运行时
 fn bench_concurrent_performance(c: &mut Criterion) {
     let mut group = c.benchmark_group("concurrent_performance");
     group.measurement_time(Duration::from_secs(20));
@@ -177,45 +167,178 @@ fn bench_concurrent_performance(c: &mut Criterion) {
             BenchmarkId::new("concurrent_orders", num_threads),
             num_threads,
             |b, &num_threads| {
-                let engine = Arc::new(MatchingEngine::new());
-                let symbol = Symbol::new("BTC", "USDT");
-
-                b.iter(|| {
-                    let handles: Vec<_> = (0..num_threads)
-                        .map(|thread_id| {
-                            let engine = engine.clone();
-                            let symbol = symbol.clone();
-
-                            std::thread::spawn(move || {
-                                let rt = tokio::runtime::Runtime::new().unwrap();
-
-                                for i in 0..100 {
-                                    let order = Order::new(
-                                        symbol.clone(),
-                                        if (thread_id + i) % 2 == 0 {
+                b.iter_batched(
+                    || Arc::new(MatchingEngine::new()),
+                    |engine| {
+                        std::thread::scope(|scope| {
+                            for thread_id in 0..num_threads {
+                                let engine = &engine;
+                                scope.spawn(move || {
+                                    for i in 0..100 {
+                                        let side = if (thread_id + i) % 2 == 0 {
                                             OrderSide::Buy
                                         } else {
                                             OrderSide::Sell
-                                        },
-                                        OrderType::Limit,
-                                        1.0,
-                                        Some(50000.0 + (thread_id as f64 * 100.0) + (i as f64)),
-                                        format!("user_{}_{}", thread_id, i),
-                                    );
-
-                                    rt.block_on(async {
-                                        let _ = engine.submit_order(order).await;
-                                    });
+                                        };
+                                        let price =
+                                            50000.0 + (thread_id as f64 * 100.0) + (i as f64);
+                                        let order = limit_order(
+                                            side,
+                                            price,
+                                            format!("user_{}_{}", thread_id, i),
+                                        );
+                                        let _ = engine.submit_order_blocking(order);
+                                    }
+                                });
+                            }
+                        });
+                        engine
+                    },
+                    BatchSize::PerIteration,
+                );
+            },
+        );
+    }
+    group.finish();
+}
+
+/// 并发提交基准中第 `task_id` 个任务的第 `i` 笔订单：买卖交替，价格在 20 个档位内循环
+fn task_order(symbol: Symbol, task_id: usize, i: usize) -> Order {
+    let side = if (task_id + i).is_multiple_of(2) {
+        OrderSide::Buy
+    } else {
+        OrderSide::Sell
+    };
+    Order::new(
+        symbol,
+        side,
+        OrderType::Limit,
+        1.0,
+        Some(50000.0 + (i % 20) as f64),
+        format!("user_{}", task_id),
+    )
+}
+
+/// 基准测试：多线程运行时内并发提交的吞吐量
+///
+/// 所有任务共享同一个 tokio 多线程运行时，每次迭代使用新的引擎，按订单数报告吞吐量：
+/// - `sequential`：单个任务依次提交全部订单，作为基线
+/// - `same_symbol`：多个任务并发提交到同一交易对，撮合按订单簿的撮合锁串行
+/// - `per_symbol`：每个任务提交到各自的交易对，订单簿之间不等待，但仍共享订单存储、成交历史等全局状态
+fn bench_async_concurrent_submission(c: &mut Criterion) {
+    const ORDERS_PER_TASK: usize = 100;
+
+    let mut group = c.benchmark_group("async_concurrent_submission");
+    group.measurement_time(Duration::from_secs(20));
+
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(8)
+        .enable_all()
+        .build()
+        .unwrap();
+    let task_symbols: Vec<Symbol> = (0..64)
+        .map(|task_id| Symbol::new(&format!("T{}", task_id), "USDT"))
+        .collect();
+
+    for &num_tasks in [1, 4, 16, 64].iter() {
+        group.throughput(Throughput::Elements((num_tasks * ORDERS_PER_TASK) as u64));
+
+        group.bench_with_input(
+            BenchmarkId::new("sequential", num_tasks),
+            &num_tasks,
+            |b, &num_tasks| {
+                b.iter_batched(
+                    || Arc::new(MatchingEngine::new()),
+                    |engine| {
+                        rt.block_on(async {
+                            for task_id in 0..num_tasks {
+                                for i in 0..ORDERS_PER_TASK {
+                                    let order = task_order(symbol(), task_id, i);
+                                    let _ = engine.submit_order(order).await;
                                 }
-                            })
-                        })
-                        .collect();
+                            }
+                        });
+                        engine
+                    },
+                    BatchSize::PerIteration,
+                );
+            },
+        );
 
-                    // 等待所有线程完成
-                    for handle in handles {
-                        handle.join().unwrap();
-                    }
-                });
+        for (name, per_symbol) in [("same_symbol", false), ("per_symbol", true)] {
+            group.bench_with_input(
+                BenchmarkId::new(name, num_tasks),
+                &num_tasks,
+                |b, &num_tasks| {
+                    b.iter_batched(
+                        || Arc::new(MatchingEngine::new()),
+                        |engine| {
+                            rt.block_on(async {
+                                let handles: Vec<_> = (0..num_tasks)
+                                    .map(|task_id| {
+                                        let engine = engine.clone();
+                                        let symbol = if per_symbol {
+                                            task_symbols[task_id]
+                                        } else {
+                                            symbol()
+                                        };
+
+                                        tokio::spawn(async move {
+                                            for i in 0..ORDERS_PER_TASK {
+                                                let order = task_order(symbol, task_id, i);
+                                                let _ = engine.submit_order(order).await;
+                                            }
+                                        })
+                                    })
+                                    .collect();
+
+                                for handle in handles {
+                                    handle.await.unwrap();
+                                }
+                            });
+                            engine
+                        },
+                        BatchSize::PerIteration,
+                    );
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+/// 基准测试：合成订单流
+///
+/// 交易对按 Zipf 分布、中间价随机游走、挂单/吃单和撤单混合的订单流，比逐笔交替买卖更接近真实负载
+fn bench_realistic_flow(c: &mut Criterion) {
+    let mut group = c.benchmark_group("realistic_flow");
+    group.measurement_time(Duration::from_secs(20));
+    group.sample_size(10);
+
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(4)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    for concurrency in [1, 4].iter() {
+        group.bench_with_input(
+            BenchmarkId::new("workers", concurrency),
+            concurrency,
+            |b, &concurrency| {
+                b.iter_batched(
+                    || Arc::new(MatchingEngine::new()),
+                    |engine| {
+                        let profile = LoadProfile {
+                            commands: 10_000,
+                            concurrency,
+                            ..LoadProfile::default()
+                        };
+                        rt.block_on(run_load(Arc::new(LoadTarget::InProcess(engine)), profile))
+                            .unwrap()
+                    },
+                    BatchSize::PerIteration,
+                );
             },
         );
     }
@@ -294,15 +417,19 @@ fn bench_serialization(c: &mut Criterion) {
     // 测试交易序列化
     group.bench_function("serialize_trade", |b| {
         let trade = Trade {
-            id: uuid::Uuid::new_v4(),
+            id: 1,
             symbol: Symbol::new("BTC", "USDT"),
-            buy_order_id: uuid::Uuid::new_v4(),
-            sell_order_id: uuid::Uuid::new_v4(),
+            buy_order_id: 2,
+            sell_order_id: 3,
             quantity: 1.0,
             price: 50000.0,
             timestamp: chrono::Utc::now(),
             buyer_id: "buyer".to_string(),
             seller_id: "seller".to_string(),
+            trade_type: TradeType::Regular,
+            taker_side: None,
+            buy_tag: None,
+            sell_tag: None,
         };
 
         b.iter(|| {
@@ -319,6 +446,8 @@ criterion_group!(
     bench_orderbook_operations,
     bench_matching_performance,
     bench_concurrent_performance,
+    bench_async_concurrent_submission,
+    bench_realistic_flow,
     bench_memory_usage,
     bench_serialization
 );
@@ -0,0 +1,356 @@
+//! 订单簿价格档位数据结构对比
+//!
+//! 在同一组预生成的操作序列上比较几种单边（卖盘）档位存储：
+//! - `btree_vec`：`BTreeMap<价格键, Vec>`，与 `OrderBook` 当前实现相同
+//! - `btree_deque`：`BTreeMap<价格键, VecDeque>`，档位头部成交为 O(1)
+//! - `sorted_vec`：按价格降序排列的 `Vec`，最优价在末尾，二分查找插入
+//! - `tick_ladder`：按 tick 下标直接寻址的稠密数组，记录最优价下标
+//!
+//! 各实现都用订单ID到档位的哈希索引支持撤单；挂单/撤单负载另以真实的 `OrderBook` 作为对照。
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use matching_engine::{Order, OrderBook, OrderSide, OrderType, Symbol};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// 卖盘价格键范围：[BASE_KEY, BASE_KEY + LADDER_TICKS)
+const BASE_KEY: i64 = 5_000_000;
+const LADDER_TICKS: usize = 4096;
+/// 每组操作的数量
+const OPS: usize = 20_000;
+
+#[derive(Clone, Copy)]
+enum Op {
+    Insert { id: u64, key: i64 },
+    Cancel { id: u64 },
+    MatchBest,
+}
+
+/// 单边价格档位存储，最优价为最低价
+trait PriceLevels: Default {
+    fn insert(&mut self, id: u64, key: i64);
+    fn cancel(&mut self, id: u64) -> bool;
+    /// 移除最优价档位中最早的订单
+    fn match_best(&mut self) -> Option<u64>;
+}
+
+#[derive(Default)]
+struct BTreeVec {
+    levels: BTreeMap<i64, Vec<u64>>,
+    index: HashMap<u64, i64>,
+}
+
+impl PriceLevels for BTreeVec {
+    fn insert(&mut self, id: u64, key: i64) {
+        self.levels.entry(key).or_default().push(id);
+        self.index.insert(id, key);
+    }
+
+    fn cancel(&mut self, id: u64) -> bool {
+        let Some(key) = self.index.remove(&id) else {
+            return false;
+        };
+        let level = self.levels.get_mut(&key).unwrap();
+        let position = level.iter().position(|&entry| entry == id).unwrap();
+        level.remove(position);
+        if level.is_empty() {
+            self.levels.remove(&key);
+        }
+        true
+    }
+
+    fn match_best(&mut self) -> Option<u64> {
+        let mut best = self.levels.first_entry()?;
+        let id = best.get_mut().remove(0);
+        if best.get().is_empty() {
+            best.remove();
+        }
+        self.index.remove(&id);
+        Some(id)
+    }
+}
+
+#[derive(Default)]
+struct BTreeDeque {
+    levels: BTreeMap<i64, VecDeque<u64>>,
+    index: HashMap<u64, i64>,
+}
+
+impl PriceLevels for BTreeDeque {
+    fn insert(&mut self, id: u64, key: i64) {
+        self.levels.entry(key).or_default().push_back(id);
+        self.index.insert(id, key);
+    }
+
+    fn cancel(&mut self, id: u64) -> bool {
+        let Some(key) = self.index.remove(&id) else {
+            return false;
+        };
+        let level = self.levels.get_mut(&key).unwrap();
+        let position = level.iter().position(|&entry| entry == id).unwrap();
+        level.remove(position);
+        if level.is_empty() {
+            self.levels.remove(&key);
+        }
+        true
+    }
+
+    fn match_best(&mut self) -> Option<u64> {
+        let mut best = self.levels.first_entry()?;
+        let id = best.get_mut().pop_front().unwrap();
+        if best.get().is_empty() {
+            best.remove();
+        }
+        self.index.remove(&id);
+        Some(id)
+    }
+}
+
+#[derive(Default)]
+struct SortedVec {
+    /// 按价格降序，最优价在末尾
+    levels: Vec<(i64, VecDeque<u64>)>,
+    index: HashMap<u64, i64>,
+}
+
+impl SortedVec {
+    fn find(&self, key: i64) -> Result<usize, usize> {
+        self.levels
+            .binary_search_by(|(level_key, _)| key.cmp(level_key))
+    }
+}
+
+impl PriceLevels for SortedVec {
+    fn insert(&mut self, id: u64, key: i64) {
+        match self.find(key) {
+            Ok(position) => self.levels[position].1.push_back(id),
+            Err(position) => self.levels.insert(position, (key, VecDeque::from([id]))),
+        }
+        self.index.insert(id, key);
+    }
+
+    fn cancel(&mut self, id: u64) -> bool {
+        let Some(key) = self.index.remove(&id) else {
+            return false;
+        };
+        let position = self.find(key).unwrap();
+        let level = &mut self.levels[position].1;
+        let entry = level.iter().position(|&entry| entry == id).unwrap();
+        level.remove(entry);
+        if level.is_empty() {
+            self.levels.remove(position);
+        }
+        true
+    }
+
+    fn match_best(&mut self) -> Option<u64> {
+        let (_, level) = self.levels.last_mut()?;
+        let id = level.pop_front().unwrap();
+        if level.is_empty() {
+            self.levels.pop();
+        }
+        self.index.remove(&id);
+        Some(id)
+    }
+}
+
+struct TickLadder {
+    levels: Vec<VecDeque<u64>>,
+    /// 最优价下标，盘口为空时为 `levels.len()`
+    best: usize,
+    index: HashMap<u64, usize>,
+}
+
+impl Default for TickLadder {
+    fn default() -> Self {
+        Self {
+            levels: vec![VecDeque::new(); LADDER_TICKS],
+            best: LADDER_TICKS,
+            index: HashMap::new(),
+        }
+    }
+}
+
+impl TickLadder {
+    /// 最优价档位清空后向更差的价格扫描下一个非空档位
+    fn advance_best(&mut self) {
+        while self.best < self.levels.len() && self.levels[self.best].is_empty() {
+            self.best += 1;
+        }
+    }
+}
+
+impl PriceLevels for TickLadder {
+    fn insert(&mut self, id: u64, key: i64) {
+        let slot = (key - BASE_KEY) as usize;
+        self.levels[slot].push_back(id);
+        self.best = self.best.min(slot);
+        self.index.insert(id, slot);
+    }
+
+    fn cancel(&mut self, id: u64) -> bool {
+        let Some(slot) = self.index.remove(&id) else {
+            return false;
+        };
+        let level = &mut self.levels[slot];
+        let entry = level.iter().position(|&entry| entry == id).unwrap();
+        level.remove(entry);
+        if slot == self.best {
+            self.advance_best();
+        }
+        true
+    }
+
+    fn match_best(&mut self) -> Option<u64> {
+        let id = self.levels.get_mut(self.best)?.pop_front()?;
+        self.advance_best();
+        self.index.remove(&id);
+        Some(id)
+    }
+}
+
+/// 与 `loadgen` 相同的 SplitMix64，保证各实现使用完全相同的操作序列
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn below(&mut self, n: u64) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        (z ^ (z >> 31)) % n
+    }
+}
+
+/// 生成操作序列：新挂单集中在盘口附近，按 `cancel_pct`/`match_pct` 的比例撤单和吃掉最优价
+fn generate_ops(cancel_pct: u64, match_pct: u64) -> Vec<Op> {
+    let mut rng = SplitMix(7);
+    let mut live: Vec<u64> = Vec::new();
+    let mut next_id = 0;
+    (0..OPS)
+        .map(|_| {
+            let roll = rng.below(100);
+            if !live.is_empty() && roll < cancel_pct {
+                let index = rng.below(live.len() as u64) as usize;
+                Op::Cancel {
+                    id: live.swap_remove(index),
+                }
+            } else if roll < cancel_pct + match_pct {
+                Op::MatchBest
+            } else {
+                next_id += 1;
+                live.push(next_id);
+                // 距盘口的 tick 数近似几何分布：大部分挂单落在前几十档
+                let distance = rng.below(64) * rng.below(64) / 16;
+                Op::Insert {
+                    id: next_id,
+                    key: BASE_KEY + 100 + distance as i64,
+                }
+            }
+        })
+        .collect()
+}
+
+fn run_ops<L: PriceLevels>(levels: &mut L, ops: &[Op]) -> usize {
+    let mut touched = 0;
+    for op in ops {
+        match *op {
+            Op::Insert { id, key } => {
+                levels.insert(id, key);
+                touched += 1;
+            }
+            Op::Cancel { id } => touched += usize::from(levels.cancel(id)),
+            Op::MatchBest => touched += usize::from(levels.match_best().is_some()),
+        }
+    }
+    touched
+}
+
+fn bench_variants(c: &mut Criterion, group_name: &str, ops: &[Op]) {
+    let mut group = c.benchmark_group(group_name);
+    group.bench_with_input(BenchmarkId::new("btree_vec", OPS), ops, |b, ops| {
+        b.iter_batched(
+            BTreeVec::default,
+            |mut levels| black_box(run_ops(&mut levels, ops)),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_with_input(BenchmarkId::new("btree_deque", OPS), ops, |b, ops| {
+        b.iter_batched(
+            BTreeDeque::default,
+            |mut levels| black_box(run_ops(&mut levels, ops)),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_with_input(BenchmarkId::new("sorted_vec", OPS), ops, |b, ops| {
+        b.iter_batched(
+            SortedVec::default,
+            |mut levels| black_box(run_ops(&mut levels, ops)),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_with_input(BenchmarkId::new("tick_ladder", OPS), ops, |b, ops| {
+        b.iter_batched(
+            TickLadder::default,
+            |mut levels| black_box(run_ops(&mut levels, ops)),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+/// 挂单和撤单各半，另含真实 `OrderBook` 作为对照
+fn bench_insert_cancel(c: &mut Criterion) {
+    let ops = generate_ops(45, 0);
+    bench_variants(c, "price_levels_insert_cancel", &ops);
+
+    let symbol = Symbol::new("BTC", "USDT");
+    c.bench_function("price_levels_insert_cancel/orderbook", |b| {
+        b.iter_batched(
+            || {
+                // 订单ID由引擎生成，把操作序列中的ID映射为预先创建的订单
+                let mut orders = HashMap::new();
+                for op in &ops {
+                    if let Op::Insert { id, key } = *op {
+                        let price = key as f64 / 100.0;
+                        let order = Order::new(
+                            symbol,
+                            OrderSide::Sell,
+                            OrderType::Limit,
+                            1.0,
+                            Some(price),
+                            "user".to_string(),
+                        );
+                        orders.insert(id, order);
+                    }
+                }
+                (OrderBook::new(symbol), orders)
+            },
+            |(mut orderbook, mut orders)| {
+                let mut ids = HashMap::new();
+                for op in &ops {
+                    match *op {
+                        Op::Insert { id, .. } => {
+                            let order = orders.remove(&id).unwrap();
+                            ids.insert(id, order.id);
+                            orderbook.add_order(order).unwrap();
+                        }
+                        Op::Cancel { id } => {
+                            let _ = orderbook.remove_order(ids[&id]);
+                        }
+                        Op::MatchBest => {}
+                    }
+                }
+                orderbook
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+/// 挂单、撤单和吃掉最优价混合，接近持续交易时的负载
+fn bench_mixed(c: &mut Criterion) {
+    let ops = generate_ops(30, 20);
+    bench_variants(c, "price_levels_mixed", &ops);
+}
+
+criterion_group!(benches, bench_insert_cancel, bench_mixed);
+criterion_main!(benches);
@@ -0,0 +1,77 @@
+use axum::extract::ws::Message;
+use chrono::Utc;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use matching_engine::ws_codec::WireEncoding;
+use matching_engine::{OrderBookDepth, PriceLevel, Symbol, WebSocketMessage};
+
+const ENCODINGS: [WireEncoding; 3] = [
+    WireEncoding::Json,
+    WireEncoding::MessagePack,
+    WireEncoding::Cbor,
+];
+
+/// 构造买卖各 `levels` 档的深度消息
+fn depth_message(levels: usize) -> WebSocketMessage {
+    let level = |i: usize, sign: f64| PriceLevel {
+        price: 50000.0 + sign * (i as f64 * 0.5 + 0.5),
+        total_quantity: 1.0 + i as f64 * 0.125,
+        order_count: i % 7 + 1,
+    };
+    WebSocketMessage::OrderBook(OrderBookDepth {
+        symbol: Symbol::new("BTC", "USDT"),
+        bids: (0..levels).map(|i| level(i, -1.0)).collect(),
+        asks: (0..levels).map(|i| level(i, 1.0)).collect(),
+        timestamp: Utc::now(),
+    })
+}
+
+fn frame_len(frame: &Message) -> usize {
+    match frame {
+        Message::Text(text) => text.len(),
+        Message::Binary(bytes) => bytes.len(),
+        _ => 0,
+    }
+}
+
+/// 基准测试：深度消息在各编码下的编码、解码耗时，并输出帧大小
+fn bench_depth_encoding(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ws_depth_encoding");
+
+    for levels in [20, 500] {
+        let message = depth_message(levels);
+        for encoding in ENCODINGS {
+            let frame = encoding.encode(&message).unwrap();
+            println!(
+                "depth {} levels, {}: {} bytes",
+                levels,
+                encoding.as_str(),
+                frame_len(&frame)
+            );
+
+            group.bench_with_input(
+                BenchmarkId::new(format!("encode_{}", encoding.as_str()), levels),
+                &message,
+                |b, message| {
+                    b.iter(|| black_box(encoding.encode(black_box(message)).unwrap()));
+                },
+            );
+            group.bench_with_input(
+                BenchmarkId::new(format!("decode_{}", encoding.as_str()), levels),
+                &frame,
+                |b, frame| {
+                    b.iter(|| {
+                        black_box(
+                            encoding
+                                .decode::<WebSocketMessage>(black_box(frame))
+                                .unwrap(),
+                        )
+                    });
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_depth_encoding);
+criterion_main!(benches);
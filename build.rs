@@ -0,0 +1,32 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    compile_protos();
+}
+
+/// 编译 gRPC 接口定义
+///
+/// 用纯 Rust 的 protobuf 解析器生成描述符，构建时不需要安装 protoc
+#[cfg(feature = "grpc")]
+fn compile_protos() {
+    use protobuf::Message as _;
+
+    const PROTO: &str = "proto/matching_engine.proto";
+    println!("cargo:rerun-if-changed={}", PROTO);
+
+    let descriptors = protobuf_parse::Parser::new()
+        .pure()
+        .include("proto")
+        .input(PROTO)
+        .file_descriptor_set()
+        .expect("failed to parse proto definitions");
+    let bytes = descriptors
+        .write_to_bytes()
+        .expect("failed to encode proto descriptors");
+    let descriptors = <prost_types::FileDescriptorSet as prost::Message>::decode(bytes.as_slice())
+        .expect("failed to decode proto descriptors");
+
+    tonic_build::configure()
+        .build_client(true)
+        .compile_fds(descriptors)
+        .expect("failed to generate gRPC code");
+}
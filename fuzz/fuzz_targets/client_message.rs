@@ -0,0 +1,23 @@
+#![no_main]
+
+//! 将任意字节作为 WebSocket 文本帧和交易对字符串解析：不允许 panic，
+//! 解析成功的交易对只包含合法的币种代码
+
+use libfuzzer_sys::fuzz_target;
+use matching_engine::websocket::parse_client_message;
+use matching_engine::{Symbol, MAX_ASSET_LEN};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    let _ = parse_client_message(text);
+
+    if let Ok(symbol) = text.parse::<Symbol>() {
+        for asset in [symbol.base(), symbol.quote()] {
+            assert!(!asset.is_empty() && asset.len() <= MAX_ASSET_LEN);
+            assert!(asset.bytes().all(|byte| byte.is_ascii_alphanumeric()));
+        }
+    }
+});
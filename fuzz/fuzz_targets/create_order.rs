@@ -0,0 +1,44 @@
+#![no_main]
+
+//! 每行输入按 JSON 解析为 `CreateOrderRequest` 后依次提交到同一个引擎：
+//! 不允许 panic，订单簿中不能出现 NaN 或无穷大，每次提交后订单簿不变量保持成立
+
+use libfuzzer_sys::fuzz_target;
+use matching_engine::{CreateOrderRequest, MatchingEngine};
+
+/// 单个输入最多处理的请求数
+const MAX_REQUESTS: usize = 64;
+
+fuzz_target!(|data: &[u8]| {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let engine = MatchingEngine::new();
+
+    runtime.block_on(async {
+        for line in data.split(|&byte| byte == b'\n').take(MAX_REQUESTS) {
+            let Ok(request) = serde_json::from_slice::<CreateOrderRequest>(line) else {
+                continue;
+            };
+            let symbol = request.symbol;
+
+            if let Ok(trades) = engine.submit_order(request.into_order()).await {
+                for trade in trades {
+                    assert!(trade.price.is_finite() && trade.quantity.is_finite());
+                    assert!(trade.quantity > 0.0);
+                }
+            }
+
+            if let Err(e) = engine.verify_invariants() {
+                panic!("invariant violated: {}", e);
+            }
+            if let Some(snapshot) = engine.export_orderbook(&symbol) {
+                for entry in snapshot.entries {
+                    assert!(entry.order.remaining_quantity.is_finite());
+                    assert!(entry.order.price.is_some_and(f64::is_finite));
+                }
+            }
+        }
+    });
+});
@@ -0,0 +1,511 @@
+use crate::types::*;
+use chrono::Utc;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use tokio::sync::broadcast;
+use tracing::info;
+use uuid::Uuid;
+
+/// 一笔记账：用户某资产的余额变动
+#[derive(Debug, Clone, PartialEq)]
+pub struct Posting {
+    pub user_id: String,
+    pub asset: String,
+    /// 正数入账，负数出账
+    pub amount: f64,
+    pub entry_type: LedgerEntryType,
+}
+
+/// 余额操作失败的原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum BalanceError {
+    /// 参数不合法或余额不足
+    Rejected(String),
+    /// 幂等键已用于内容不同的请求
+    IdempotencyConflict(String),
+}
+
+impl std::fmt::Display for BalanceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rejected(message) | Self::IdempotencyConflict(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<String> for BalanceError {
+    fn from(message: String) -> Self {
+        Self::Rejected(message)
+    }
+}
+
+/// 账户余额子系统
+///
+/// 维护用户各资产余额，并以流水（ledger）记录每一次余额变动。
+/// 充值、提现、划转均支持幂等键，重复提交同一幂等键直接返回首次结果；
+/// 同一幂等键用于操作类型、资产、金额或对手方不同的请求时返回 [`BalanceError::IdempotencyConflict`]。
+#[derive(Debug)]
+pub struct AccountManager {
+    state: RwLock<AccountState>,
+    /// 余额变动广播通道
+    balance_sender: broadcast::Sender<BalanceUpdate>,
+}
+
+#[derive(Debug, Default)]
+struct AccountState {
+    /// (用户ID, 资产) -> 余额
+    balances: HashMap<(String, String), Balance>,
+    /// 余额变动流水，按发生顺序追加
+    ledger: Vec<LedgerEntry>,
+    /// (用户ID, 幂等键) -> 首次执行的请求和产生的流水
+    idempotency: HashMap<(String, String), IdempotencyRecord>,
+}
+
+/// 幂等键对应的首次请求
+#[derive(Debug)]
+struct IdempotencyRecord {
+    request: BalanceRequest,
+    entries: Vec<LedgerEntry>,
+}
+
+/// 余额操作的内容，同一幂等键重复提交时必须一致
+#[derive(Debug, Clone, PartialEq)]
+struct BalanceRequest {
+    entry_type: LedgerEntryType,
+    asset: String,
+    amount: f64,
+    counterparty: Option<String>,
+}
+
+impl BalanceRequest {
+    fn new(entry_type: LedgerEntryType, asset: &str, amount: f64) -> Self {
+        Self {
+            entry_type,
+            asset: asset.to_string(),
+            amount,
+            counterparty: None,
+        }
+    }
+}
+
+impl AccountManager {
+    pub fn new() -> Self {
+        let (balance_sender, _) = broadcast::channel(10000);
+
+        Self {
+            state: RwLock::new(AccountState::default()),
+            balance_sender,
+        }
+    }
+
+    /// 充值
+    pub fn deposit(
+        &self,
+        user_id: &str,
+        asset: &str,
+        amount: f64,
+        idempotency_key: &str,
+    ) -> Result<Vec<LedgerEntry>, BalanceError> {
+        validate_request(user_id, asset, amount, idempotency_key)?;
+        let asset = asset.to_uppercase();
+        let request = BalanceRequest::new(LedgerEntryType::Deposit, &asset, amount);
+
+        self.apply(user_id, idempotency_key, request, |state| {
+            Ok(vec![state.post(
+                user_id,
+                &asset,
+                amount,
+                LedgerEntryType::Deposit,
+                idempotency_key,
+                None,
+            )])
+        })
+    }
+
+    /// 提现
+    pub fn withdraw(
+        &self,
+        user_id: &str,
+        asset: &str,
+        amount: f64,
+        idempotency_key: &str,
+    ) -> Result<Vec<LedgerEntry>, BalanceError> {
+        validate_request(user_id, asset, amount, idempotency_key)?;
+        let asset = asset.to_uppercase();
+        let request = BalanceRequest::new(LedgerEntryType::Withdrawal, &asset, amount);
+
+        self.apply(user_id, idempotency_key, request, |state| {
+            state.ensure_available(user_id, &asset, amount)?;
+            Ok(vec![state.post(
+                user_id,
+                &asset,
+                -amount,
+                LedgerEntryType::Withdrawal,
+                idempotency_key,
+                None,
+            )])
+        })
+    }
+
+    /// 用户间内部划转
+    pub fn transfer(
+        &self,
+        from_user_id: &str,
+        to_user_id: &str,
+        asset: &str,
+        amount: f64,
+        idempotency_key: &str,
+    ) -> Result<Vec<LedgerEntry>, BalanceError> {
+        validate_request(from_user_id, asset, amount, idempotency_key)?;
+        if to_user_id.is_empty() {
+            return Err("Target user ID cannot be empty".to_string().into());
+        }
+        if from_user_id == to_user_id {
+            return Err("Cannot transfer to the same user".to_string().into());
+        }
+        let asset = asset.to_uppercase();
+        let request = BalanceRequest {
+            counterparty: Some(to_user_id.to_string()),
+            ..BalanceRequest::new(LedgerEntryType::TransferOut, &asset, amount)
+        };
+
+        self.apply(from_user_id, idempotency_key, request, |state| {
+            state.ensure_available(from_user_id, &asset, amount)?;
+            Ok(vec![
+                state.post(
+                    from_user_id,
+                    &asset,
+                    -amount,
+                    LedgerEntryType::TransferOut,
+                    idempotency_key,
+                    Some(to_user_id),
+                ),
+                state.post(
+                    to_user_id,
+                    &asset,
+                    amount,
+                    LedgerEntryType::TransferIn,
+                    idempotency_key,
+                    Some(from_user_id),
+                ),
+            ])
+        })
+    }
+
+    /// 直接记账，不检查可用余额，`reference` 作为流水的幂等键字段记录来源（如成交ID）
+    ///
+    /// 用于沙盒模式下的成交结算，结算的合法性已在下单前检查
+    pub fn settle(&self, reference: &str, postings: &[Posting]) -> Vec<LedgerEntry> {
+        let entries: Vec<LedgerEntry> = {
+            let mut state = self.state.write();
+            postings
+                .iter()
+                .filter(|posting| posting.amount != 0.0)
+                .map(|posting| {
+                    state.post(
+                        &posting.user_id,
+                        &posting.asset,
+                        posting.amount,
+                        posting.entry_type,
+                        reference,
+                        None,
+                    )
+                })
+                .collect()
+        };
+        self.broadcast(&entries);
+        entries
+    }
+
+    /// 删除用户的全部余额、流水和幂等记录
+    pub fn reset_user(&self, user_id: &str) {
+        let mut state = self.state.write();
+        state.balances.retain(|(user, _), _| user != user_id);
+        state.ledger.retain(|entry| entry.user_id != user_id);
+        state.idempotency.retain(|(user, _), _| user != user_id);
+        info!("Reset balances and ledger of user {}", user_id);
+    }
+
+    /// 获取用户某资产余额
+    pub fn get_balance(&self, user_id: &str, asset: &str) -> Balance {
+        let state = self.state.read();
+        state
+            .balances
+            .get(&(user_id.to_string(), asset.to_uppercase()))
+            .cloned()
+            .unwrap_or_else(|| Balance::empty(user_id, &asset.to_uppercase()))
+    }
+
+    /// 获取用户所有资产余额
+    pub fn get_balances(&self, user_id: &str) -> Vec<Balance> {
+        let state = self.state.read();
+        let mut balances: Vec<Balance> = state
+            .balances
+            .values()
+            .filter(|balance| balance.user_id == user_id)
+            .cloned()
+            .collect();
+        balances.sort_by(|a, b| a.asset.cmp(&b.asset));
+        balances
+    }
+
+    /// 获取全部用户的资产余额，按用户和资产排序
+    pub fn all_balances(&self) -> Vec<Balance> {
+        let state = self.state.read();
+        let mut balances: Vec<Balance> = state.balances.values().cloned().collect();
+        balances.sort_by(|a, b| (&a.user_id, &a.asset).cmp(&(&b.user_id, &b.asset)));
+        balances
+    }
+
+    /// 获取用户余额变动流水（最新的在前）
+    pub fn get_ledger(&self, user_id: &str, limit: Option<usize>) -> Vec<LedgerEntry> {
+        let state = self.state.read();
+        let entries = state
+            .ledger
+            .iter()
+            .rev()
+            .filter(|entry| entry.user_id == user_id)
+            .cloned();
+
+        match limit {
+            Some(limit) => entries.take(limit).collect(),
+            None => entries.collect(),
+        }
+    }
+
+    /// 获取余额变动广播接收器
+    pub fn subscribe_balances(&self) -> broadcast::Receiver<BalanceUpdate> {
+        self.balance_sender.subscribe()
+    }
+
+    /// 在写锁内执行余额操作，并处理幂等键
+    fn apply<F>(
+        &self,
+        user_id: &str,
+        idempotency_key: &str,
+        request: BalanceRequest,
+        operation: F,
+    ) -> Result<Vec<LedgerEntry>, BalanceError>
+    where
+        F: FnOnce(&mut AccountState) -> Result<Vec<LedgerEntry>, String>,
+    {
+        let idempotency_id = (user_id.to_string(), idempotency_key.to_string());
+
+        let entries = {
+            let mut state = self.state.write();
+            if let Some(record) = state.idempotency.get(&idempotency_id) {
+                if record.request != request {
+                    return Err(BalanceError::IdempotencyConflict(format!(
+                        "Idempotency key {} was already used for a different request",
+                        idempotency_key
+                    )));
+                }
+                info!(
+                    "Idempotent replay of {} for user {}",
+                    idempotency_key, user_id
+                );
+                return Ok(record.entries.clone());
+            }
+
+            let entries = operation(&mut state)?;
+            state.idempotency.insert(
+                idempotency_id,
+                IdempotencyRecord {
+                    request,
+                    entries: entries.clone(),
+                },
+            );
+            entries
+        };
+
+        self.broadcast(&entries);
+        Ok(entries)
+    }
+
+    /// 广播余额变动
+    fn broadcast(&self, entries: &[LedgerEntry]) {
+        for entry in entries {
+            let _ = self.balance_sender.send(BalanceUpdate {
+                user_id: entry.user_id.clone(),
+                asset: entry.asset.clone(),
+                delta: entry.amount,
+                balance: entry.balance_after,
+                reason: entry.entry_type,
+                timestamp: entry.timestamp,
+            });
+        }
+    }
+}
+
+impl AccountState {
+    fn ensure_available(&self, user_id: &str, asset: &str, amount: f64) -> Result<(), String> {
+        let available = self
+            .balances
+            .get(&(user_id.to_string(), asset.to_string()))
+            .map(|balance| balance.available)
+            .unwrap_or(0.0);
+
+        if available < amount {
+            return Err(format!(
+                "Insufficient {} balance: available {}, requested {}",
+                asset, available, amount
+            ));
+        }
+        Ok(())
+    }
+
+    /// 记账：更新余额并追加一条流水
+    fn post(
+        &mut self,
+        user_id: &str,
+        asset: &str,
+        amount: f64,
+        entry_type: LedgerEntryType,
+        idempotency_key: &str,
+        counterparty: Option<&str>,
+    ) -> LedgerEntry {
+        let balance = self
+            .balances
+            .entry((user_id.to_string(), asset.to_string()))
+            .or_insert_with(|| Balance::empty(user_id, asset));
+        balance.available += amount;
+
+        let entry = LedgerEntry {
+            id: Uuid::new_v4(),
+            user_id: user_id.to_string(),
+            asset: asset.to_string(),
+            amount,
+            balance_after: balance.available,
+            entry_type,
+            idempotency_key: idempotency_key.to_string(),
+            counterparty: counterparty.map(str::to_string),
+            timestamp: Utc::now(),
+        };
+
+        self.ledger.push(entry.clone());
+        info!(
+            "Ledger {:?}: user {} {} {} (balance {})",
+            entry_type, user_id, amount, asset, entry.balance_after
+        );
+        entry
+    }
+}
+
+impl Default for AccountManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 校验余额操作参数
+fn validate_request(
+    user_id: &str,
+    asset: &str,
+    amount: f64,
+    idempotency_key: &str,
+) -> Result<(), String> {
+    if user_id.is_empty() {
+        return Err("User ID cannot be empty".to_string());
+    }
+
+    if asset.is_empty() {
+        return Err("Asset cannot be empty".to_string());
+    }
+
+    if !amount.is_finite() || amount <= 0.0 {
+        return Err("Amount must be a positive number".to_string());
+    }
+
+    if idempotency_key.is_empty() {
+        return Err("Idempotency key cannot be empty".to_string());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deposit_and_withdraw() {
+        let accounts = AccountManager::new();
+
+        accounts.deposit("alice", "usdt", 1000.0, "dep-1").unwrap();
+        assert_eq!(accounts.get_balance("alice", "USDT").available, 1000.0);
+
+        accounts.withdraw("alice", "USDT", 400.0, "wd-1").unwrap();
+        assert_eq!(accounts.get_balance("alice", "USDT").available, 600.0);
+
+        // 余额不足
+        assert!(accounts.withdraw("alice", "USDT", 601.0, "wd-2").is_err());
+        assert_eq!(accounts.get_ledger("alice", None).len(), 2);
+    }
+
+    #[test]
+    fn test_idempotent_deposit() {
+        let accounts = AccountManager::new();
+
+        let first = accounts.deposit("alice", "BTC", 1.0, "dep-1").unwrap();
+        let replay = accounts.deposit("alice", "BTC", 1.0, "dep-1").unwrap();
+
+        assert_eq!(first[0].id, replay[0].id);
+        assert_eq!(accounts.get_balance("alice", "BTC").available, 1.0);
+        assert_eq!(accounts.get_ledger("alice", None).len(), 1);
+    }
+
+    #[test]
+    fn test_idempotency_key_reused_for_different_request() {
+        let accounts = AccountManager::new();
+        accounts.deposit("alice", "USDT", 100.0, "dep-1").unwrap();
+
+        let conflicts = [
+            accounts.withdraw("alice", "USDT", 100.0, "dep-1"),
+            accounts.deposit("alice", "USDT", 50.0, "dep-1"),
+            accounts.deposit("alice", "BTC", 100.0, "dep-1"),
+            accounts.transfer("alice", "bob", "USDT", 100.0, "dep-1"),
+        ];
+        for result in conflicts {
+            assert!(matches!(result, Err(BalanceError::IdempotencyConflict(_))));
+        }
+        assert_eq!(accounts.get_balance("alice", "USDT").available, 100.0);
+        assert_eq!(accounts.get_ledger("alice", None).len(), 1);
+
+        // 不同用户的幂等键互不影响
+        accounts.deposit("bob", "USDT", 1.0, "dep-1").unwrap();
+    }
+
+    #[test]
+    fn test_transfer() {
+        let accounts = AccountManager::new();
+        accounts.deposit("alice", "USDT", 100.0, "dep-1").unwrap();
+
+        let entries = accounts
+            .transfer("alice", "bob", "USDT", 30.0, "tr-1")
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].entry_type, LedgerEntryType::TransferOut);
+        assert_eq!(entries[1].counterparty.as_deref(), Some("alice"));
+
+        assert_eq!(accounts.get_balance("alice", "USDT").available, 70.0);
+        assert_eq!(accounts.get_balance("bob", "USDT").available, 30.0);
+
+        assert!(accounts
+            .transfer("alice", "bob", "USDT", 100.0, "tr-2")
+            .is_err());
+        assert!(accounts
+            .transfer("alice", "alice", "USDT", 1.0, "tr-3")
+            .is_err());
+    }
+
+    #[test]
+    fn test_invalid_amount_rejected() {
+        let accounts = AccountManager::new();
+        assert!(accounts.deposit("alice", "USDT", 0.0, "dep-1").is_err());
+        assert!(accounts.deposit("alice", "USDT", -1.0, "dep-2").is_err());
+        assert!(accounts
+            .deposit("alice", "USDT", f64::NAN, "dep-3")
+            .is_err());
+        assert!(accounts.deposit("alice", "USDT", 1.0, "").is_err());
+    }
+}
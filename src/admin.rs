@@ -0,0 +1,214 @@
+//! 管理接口权限
+//!
+//! `/admin/*` 接口只接受 `Authorization: Bearer <管理令牌>`，与用户的 JWT 和 API key 互不通用。
+//! 令牌对应的操作人随每次管理操作（GET/HEAD 以外的请求）写入审计日志，
+//! 可通过 `GET /admin/audit` 查询最近的管理操作。
+use crate::api::{ApiError, FieldError, ValidQuery, Validate};
+use crate::audit::{AdminAuditEvent, AdminAuditLog};
+use crate::auth::bearer_token;
+use crate::config::AdminConfig;
+use axum::{
+    extract::{OriginalUri, Request, State},
+    http::{Method, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{error, info};
+use utoipa::IntoParams;
+
+/// 查询审计事件的默认条数
+const DEFAULT_AUDIT_LIMIT: usize = 100;
+/// 查询审计事件的最大条数
+const MAX_AUDIT_LIMIT: usize = 1000;
+
+/// 管理令牌校验和操作审计
+#[derive(Debug)]
+struct AdminGuard {
+    /// 令牌 -> 操作人
+    actors: HashMap<String, String>,
+    audit: AdminAuditLog,
+}
+
+/// 审计事件查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AdminAuditQuery {
+    /// 返回条数，默认 100
+    pub limit: Option<usize>,
+}
+
+impl Validate for AdminAuditQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if let Some(limit) = self.limit {
+            if !(1..=MAX_AUDIT_LIMIT).contains(&limit) {
+                errors.push(FieldError::new(
+                    "limit",
+                    &format!("must be between 1 and {}", MAX_AUDIT_LIMIT),
+                ));
+            }
+        }
+        errors
+    }
+}
+
+/// 为管理路由加上令牌校验和操作审计，并挂载 `GET /admin/audit`
+///
+/// 审计日志写入 `audit_dir/admin.jsonl`；没有配置管理令牌时所有管理请求返回 401
+pub fn protect_admin_router(
+    router: Router,
+    config: &AdminConfig,
+    audit_dir: impl AsRef<Path>,
+) -> Result<Router, String> {
+    let guard = Arc::new(AdminGuard {
+        actors: config
+            .tokens
+            .iter()
+            .map(|token| (token.token.clone(), token.actor.clone()))
+            .collect(),
+        audit: AdminAuditLog::open(audit_dir)?,
+    });
+
+    let audit = Router::new()
+        .route("/admin/audit", get(list_audit_events))
+        .with_state(Arc::clone(&guard));
+    Ok(router
+        .merge(audit)
+        .layer(middleware::from_fn_with_state(guard, require_admin)))
+}
+
+/// 校验管理令牌，并在响应后记录非只读的管理操作
+async fn require_admin(
+    State(guard): State<Arc<AdminGuard>>,
+    OriginalUri(uri): OriginalUri,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(actor) = bearer_token(request.headers())
+        .and_then(|token| guard.actors.get(token))
+        .cloned()
+    else {
+        return ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            "unauthorized",
+            "Missing or invalid admin token",
+        )
+        .into_response();
+    };
+
+    let method = request.method().clone();
+    let response = next.run(request).await;
+    if method != Method::GET && method != Method::HEAD {
+        let status = response.status().as_u16();
+        info!("Admin {} {} {} by {}", method, uri, status, actor);
+        if let Err(e) = guard
+            .audit
+            .record(&actor, method.as_str(), &uri.to_string(), status)
+        {
+            error!("Failed to record admin operation: {}", e);
+        }
+    }
+    response
+}
+
+async fn list_audit_events(
+    State(guard): State<Arc<AdminGuard>>,
+    ValidQuery(query): ValidQuery<AdminAuditQuery>,
+) -> Result<Json<Vec<AdminAuditEvent>>, ApiError> {
+    guard
+        .audit
+        .recent(query.limit.unwrap_or(DEFAULT_AUDIT_LIMIT))
+        .map(Json)
+        .map_err(|e| {
+            error!("Failed to read admin audit log: {}", e);
+            ApiError::internal()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AdminToken;
+    use axum::{body::Body, routing::post};
+    use tower::ServiceExt;
+
+    async fn send(app: &Router, method: &str, uri: &str, token: Option<&str>) -> Response {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            request = request.header("authorization", format!("Bearer {}", token));
+        }
+        app.clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_admin_requests_require_token_and_are_audited() {
+        let dir = std::env::temp_dir().join(format!("admin-audit-{}", uuid::Uuid::new_v4()));
+        let config = AdminConfig {
+            tokens: vec![AdminToken {
+                actor: "ops-alice".to_string(),
+                token: "admin-secret".to_string(),
+            }],
+            ..Default::default()
+        };
+        let router = Router::new().route(
+            "/admin/symbols/:symbol/halt",
+            post(|| async { StatusCode::OK }),
+        );
+        let app = Router::new().nest(
+            "/api/v1",
+            protect_admin_router(router, &config, &dir).unwrap(),
+        );
+
+        let path = "/api/v1/admin/symbols/BTCUSDT/halt";
+        assert_eq!(
+            send(&app, "POST", path, None).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            send(&app, "POST", path, Some("user-jwt")).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            send(&app, "POST", path, Some("admin-secret"))
+                .await
+                .status(),
+            StatusCode::OK
+        );
+
+        // 只有通过校验的写操作被记录，查询审计本身不记录
+        let response = send(&app, "GET", "/api/v1/admin/audit", Some("admin-secret")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let events: Vec<AdminAuditEvent> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].actor, "ops-alice");
+        assert_eq!(events[0].method, "POST");
+        assert_eq!(events[0].path, path);
+        assert_eq!(events[0].status, 200);
+
+        assert_eq!(
+            send(
+                &app,
+                "GET",
+                "/api/v1/admin/audit?limit=0",
+                Some("admin-secret")
+            )
+            .await
+            .status(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,218 @@
+use crate::config::{AllocationAlgorithm, AllocationConfig};
+use crate::types::Order;
+
+/// 分配数量的最小精度，按比例分配的份额向下取整到该精度
+const QUANTITY_PRECISION: f64 = 100_000_000.0;
+
+/// 计算同一价位上各挂单的成交数量
+///
+/// `level` 为按时间优先排序的挂单，返回与之一一对应的分配数量，总和不超过 `quantity`。
+/// 分配数量低于挂单最小成交数量的挂单被跳过（保留队列位置），其份额重新分配给其余挂单
+pub fn allocate(
+    algorithm: AllocationAlgorithm,
+    config: &AllocationConfig,
+    level: &[&Order],
+    quantity: f64,
+) -> Vec<f64> {
+    let mut eligible = vec![true; level.len()];
+
+    loop {
+        let candidates: Vec<&Order> = level
+            .iter()
+            .zip(&eligible)
+            .filter(|(_, eligible)| **eligible)
+            .map(|(order, _)| *order)
+            .collect();
+        let mut candidate_allocations =
+            allocate_level(algorithm, config, &candidates, quantity).into_iter();
+
+        let mut allocations = vec![0.0; level.len()];
+        let mut skipped = false;
+        for (index, order) in level.iter().enumerate() {
+            if !eligible[index] {
+                continue;
+            }
+            let allocation = candidate_allocations.next().unwrap_or(0.0);
+            if allocation > 0.0 && allocation < order.min_fill_threshold() {
+                eligible[index] = false;
+                skipped = true;
+            }
+            allocations[index] = allocation;
+        }
+
+        if !skipped {
+            return allocations;
+        }
+    }
+}
+
+/// 按分配算法计算价位内各挂单的成交数量
+fn allocate_level(
+    algorithm: AllocationAlgorithm,
+    config: &AllocationConfig,
+    level: &[&Order],
+    quantity: f64,
+) -> Vec<f64> {
+    let mut allocations = vec![0.0; level.len()];
+
+    match algorithm {
+        AllocationAlgorithm::Fifo => {
+            allocate_fifo(level, &mut allocations, quantity);
+        }
+        AllocationAlgorithm::ProRata => {
+            allocate_pro_rata(level, &mut allocations, quantity, config.min_allocation);
+        }
+        AllocationAlgorithm::TimeProRata => {
+            let fifo_quantity = floor_quantity(quantity * config.fifo_percentage / 100.0);
+            let allocated = allocate_fifo(level, &mut allocations, fifo_quantity);
+            allocate_pro_rata(
+                level,
+                &mut allocations,
+                quantity - allocated,
+                config.min_allocation,
+            );
+        }
+    }
+
+    allocations
+}
+
+/// 按时间优先依次填满挂单，返回实际分配的数量
+fn allocate_fifo(level: &[&Order], allocations: &mut [f64], quantity: f64) -> f64 {
+    let mut remaining = quantity;
+    for (order, allocation) in level.iter().zip(allocations.iter_mut()) {
+        if remaining <= 0.0 {
+            break;
+        }
+        let fill = (order.remaining_quantity - *allocation).min(remaining);
+        if fill > 0.0 {
+            *allocation += fill;
+            remaining -= fill;
+        }
+    }
+    quantity - remaining
+}
+
+/// 按挂单未分配数量的比例分配
+///
+/// 低于 `min_allocation` 的份额不分配，取整和最小分配留下的余量按时间优先补足
+fn allocate_pro_rata(
+    level: &[&Order],
+    allocations: &mut [f64],
+    quantity: f64,
+    min_allocation: f64,
+) {
+    if quantity <= 0.0 {
+        return;
+    }
+
+    let open: Vec<f64> = level
+        .iter()
+        .zip(allocations.iter())
+        .map(|(order, allocation)| (order.remaining_quantity - allocation).max(0.0))
+        .collect();
+    let total_open: f64 = open.iter().sum();
+    if total_open <= 0.0 {
+        return;
+    }
+
+    // 数量足够吃掉整个价位时全部成交
+    if quantity >= total_open {
+        for (allocation, open) in allocations.iter_mut().zip(&open) {
+            *allocation += open;
+        }
+        return;
+    }
+
+    let mut allocated = 0.0;
+    for (allocation, open) in allocations.iter_mut().zip(&open) {
+        let share = floor_quantity(quantity * open / total_open);
+        if share > 0.0 && share >= min_allocation {
+            *allocation += share;
+            allocated += share;
+        }
+    }
+
+    allocate_fifo(level, allocations, quantity - allocated);
+}
+
+/// 将数量向下取整到分配精度
+pub(crate) fn floor_quantity(quantity: f64) -> f64 {
+    (quantity * QUANTITY_PRECISION).floor() / QUANTITY_PRECISION
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::*;
+
+    fn level(quantities: &[f64]) -> Vec<Order> {
+        quantities
+            .iter()
+            .map(|&quantity| {
+                Order::new(
+                    Symbol::new("BTC", "USDT"),
+                    OrderSide::Sell,
+                    OrderType::Limit,
+                    quantity,
+                    Some(100.0),
+                    "maker".to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fifo_and_pro_rata_allocation() {
+        let orders = level(&[2.0, 6.0, 2.0]);
+        let refs: Vec<&Order> = orders.iter().collect();
+        let config = AllocationConfig::default();
+
+        assert_eq!(
+            allocate(AllocationAlgorithm::Fifo, &config, &refs, 5.0),
+            vec![2.0, 3.0, 0.0]
+        );
+        assert_eq!(
+            allocate(AllocationAlgorithm::ProRata, &config, &refs, 5.0),
+            vec![1.0, 3.0, 1.0]
+        );
+        // 时间优先 40%（2.0）给第一笔，剩余 3.0 按 [0, 6, 2] 比例分配
+        assert_eq!(
+            allocate(AllocationAlgorithm::TimeProRata, &config, &refs, 5.0),
+            vec![2.0, 2.25, 0.75]
+        );
+    }
+
+    #[test]
+    fn test_min_fill_quantity_skips_resting_order() {
+        let mut orders = level(&[5.0, 2.0]);
+        orders[0] = orders[0].clone().all_or_none();
+        let refs: Vec<&Order> = orders.iter().collect();
+        let config = AllocationConfig::default();
+
+        // AON 挂单无法完全成交，被跳过，数量分配给后面的挂单
+        assert_eq!(
+            allocate(AllocationAlgorithm::Fifo, &config, &refs, 3.0),
+            vec![0.0, 2.0]
+        );
+        assert_eq!(
+            allocate(AllocationAlgorithm::Fifo, &config, &refs, 7.0),
+            vec![5.0, 2.0]
+        );
+    }
+
+    #[test]
+    fn test_pro_rata_minimum_allocation() {
+        let orders = level(&[1.0, 9.0]);
+        let refs: Vec<&Order> = orders.iter().collect();
+        let config = AllocationConfig {
+            min_allocation: 0.5,
+            ..Default::default()
+        };
+
+        // 第一笔份额 0.2 低于最小分配，余量按时间优先补给第一笔
+        let allocations = allocate(AllocationAlgorithm::ProRata, &config, &refs, 2.0);
+        assert!((allocations[0] - 0.2).abs() < 1e-9);
+        assert!((allocations[1] - 1.8).abs() < 1e-9);
+    }
+}
@@ -1,76 +1,531 @@
+use crate::account::BalanceError;
+use crate::api_keys::{ApiKey, ApiKeyScope, ApiKeyStore, CreatedApiKey, NewApiKey};
+use crate::audit::AuditEvent;
+use crate::auth::{authenticate, Authenticator, Caller};
+use crate::backpressure::is_engine_busy;
+use crate::config::{CorsConfig, ServerConfig};
+use crate::export::{ExportFormat, MAX_EXPORT_ROWS};
+use crate::fees::{FeeRevenueReport, UserFeeTier, UserRebates};
+use crate::id::{OrderId, TradeId};
+use crate::latency::LatencyReport;
+use crate::logging::LogLevelHandle;
 use crate::matching_engine::MatchingEngine;
+use crate::orderbook::OrderBookSnapshot;
+use crate::request_id::{self, propagate_request_id};
+use crate::risk::UserLimitStatus;
+use crate::sandbox::SandboxReset;
+use crate::session::SessionInfo;
+use crate::settlement::{SettlementReport, UserSettlement, SYMBOLS_CSV, USERS_CSV};
+use crate::store::{
+    FillFilter, OrderFilter, Page, PageRequest, TradeFilter, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE,
+};
+use crate::telemetry::make_request_span;
 use crate::types::*;
+use crate::volume::{VolumeInterval, VolumeSeries};
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
-    routing::{delete, get, post},
-    Router,
+    async_trait,
+    body::Bytes,
+    extract::{DefaultBodyLimit, FromRequest, FromRequestParts, OriginalUri, Path, Request, State},
+    http::{header, request::Parts, HeaderName, HeaderValue, Method, StatusCode},
+    middleware,
+    response::{Html, IntoResponse, Json, Response},
+    routing::{delete, get, post, put},
+    Extension, Router,
 };
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::timeout::TimeoutLayer;
+use tower_http::trace::TraceLayer;
 use tracing::{error, info, warn};
+use utoipa::openapi::server::Server;
+use utoipa::{IntoParams, OpenApi, ToSchema};
 use uuid::Uuid;
 
 /// API 状态
 #[derive(Clone)]
 pub struct ApiState {
     pub engine: Arc<MatchingEngine>,
+    /// 运行时日志级别控制，未使用可重载日志系统时为空
+    pub log_level: Option<LogLevelHandle>,
 }
 
-/// 创建 API 路由
+/// 创建 API 路由（不含管理接口，见 [`create_admin_router`]）
 pub fn create_router(engine: Arc<MatchingEngine>) -> Router {
-    let state = ApiState { engine };
+    let state = ApiState {
+        engine,
+        log_level: None,
+    };
 
     Router::new()
         .route("/health", get(health_check))
+        .route("/time", get(server_time))
+        .route("/ping", get(ping))
         .route("/stats", get(get_engine_stats))
+        .route("/stats/latency", get(get_latency_stats))
+        .route("/stats/:symbol", get(get_symbol_stats))
         .route("/orders", post(create_order))
+        .route("/orders/test", post(test_order))
+        .route("/orders/query", post(query_orders))
         .route("/orders/:order_id", get(get_order))
         .route("/orders/:order_id", delete(cancel_order))
         .route("/orders/user/:user_id", get(get_user_orders))
+        .route("/openOrders", get(get_open_orders))
+        .route("/audit/orders/:order_id", get(get_order_audit))
         .route("/orderbook/:symbol", get(get_orderbook))
+        .route("/analytics/:symbol/book", get(get_book_analytics))
+        .route("/analytics/:symbol/volume", get(get_volume_analytics))
         .route("/market-data", get(get_all_market_data))
+        .route("/ticker/24hr", get(get_tickers))
         .route("/market-data/:symbol", get(get_market_data))
+        .route("/mark-price", get(get_all_mark_prices))
+        .route("/mark-price/:symbol", get(get_mark_price))
+        .route("/open-interest/:symbol", get(get_open_interest))
         .route("/trades", get(get_trades))
         .route("/trades/:symbol", get(get_symbol_trades))
+        .route("/trades/user/:user_id", get(get_user_fills))
+        .route("/agg-trades/:symbol", get(get_agg_trades))
+        .route("/export/fills/:user_id", get(export_user_fills))
+        .route("/export/orders/:user_id", get(export_user_orders))
+        .route("/symbols", get(get_symbol_listings))
+        .route("/symbols/:symbol/status", get(get_symbol_status))
+        .route("/symbols/:symbol/auction", get(get_auction_indicative))
+        .route("/limits/:user_id", get(get_user_limits))
+        .route("/fees/:user_id", get(get_user_fees))
+        .route("/fees/:user_id/rebates", get(get_user_rebates))
+        .route(
+            "/settlements/:trading_day/users/:user_id",
+            get(get_user_settlement),
+        )
+        .route("/accounts/:user_id/balances", get(get_balances))
+        .route("/positions/:user_id", get(get_positions))
+        .route("/margin/:user_id", get(get_margin_account))
+        .route("/funding/:symbol", get(get_funding_history))
+        .route("/sessions/:symbol", get(get_session))
+        .route("/accounts/:user_id/ledger", get(get_ledger))
+        .route("/accounts/:user_id/withdraw", post(withdraw))
+        .route("/accounts/:user_id/transfer", post(transfer))
+        .route("/sandbox/reset", post(reset_sandbox))
+        .route("/account/api-keys", get(list_api_keys).post(create_api_key))
+        .route("/account/api-keys/:key_id", delete(delete_api_key))
+        .route("/openapi.json", get(openapi_json))
+        .route("/docs", get(swagger_ui))
+        .layer(TraceLayer::new_for_http().make_span_with(make_request_span))
+        .with_state(state)
+}
+
+/// 创建管理接口路由（`/admin/*`），与 API 路由共用前缀
+///
+/// 路由本身不做权限校验，服务器通过 [`crate::admin::protect_admin_router`] 要求管理令牌后再对外提供
+pub fn create_admin_router(
+    engine: Arc<MatchingEngine>,
+    log_level: Option<LogLevelHandle>,
+) -> Router {
+    let state = ApiState { engine, log_level };
+
+    Router::new()
+        .route("/admin/symbols", post(list_symbol))
+        .route("/admin/symbols/:symbol/delist", post(delist_symbol))
+        .route("/admin/trades/:trade_id/bust", post(bust_trade))
+        .route("/admin/symbols/:symbol/halt", post(halt_symbol))
+        .route("/admin/symbols/:symbol/resume", post(resume_symbol))
+        .route("/admin/symbols/:symbol/auction/start", post(start_auction))
+        .route("/admin/symbols/:symbol/auction/end", post(end_auction))
+        .route(
+            "/admin/orderbook/:symbol/snapshot",
+            get(export_orderbook).post(import_orderbook),
+        )
+        .route("/admin/fees/revenue", get(get_fee_revenue))
+        .route("/admin/settlements", get(get_settlement_days))
+        .route(
+            "/admin/settlements/:trading_day",
+            get(get_settlement_report),
+        )
+        .route(
+            "/admin/settlements/:trading_day/:file",
+            get(get_settlement_csv),
+        )
+        .route("/admin/log-level", put(set_log_level))
+        .route("/admin/accounts/:user_id/deposit", post(deposit))
+        .route("/admin/trades/report", post(report_trade))
+        .route("/admin/export/trades", get(export_trades))
+        .route("/admin/export/orders", get(export_orders))
+        .route(
+            "/admin/margin/liquidations",
+            get(get_liquidation_candidates),
+        )
+        .route("/admin/liquidations", get(get_liquidations))
+        .layer(TraceLayer::new_for_http().make_span_with(make_request_span))
         .with_state(state)
 }
 
+/// 按服务器配置为路由加上请求ID、JWT 认证、CORS、请求超时和请求体大小限制
+///
+/// 未启用认证时把 [`Caller::Unrestricted`] 放入请求扩展，按用户的接口不限制访问。超时返回 408，请求体超过 `max_request_size` 返回 413；CORS 在认证和限制之外，错误响应同样带 CORS 头。
+/// 请求ID在最外层，所有响应都带 `X-Request-Id`
+pub fn apply_server_layers(router: Router, config: &ServerConfig) -> Result<Router, String> {
+    let authenticator = if config.auth.enabled {
+        Some(Arc::new(Authenticator::new(&config.auth)?))
+    } else {
+        None
+    };
+    apply_server_layers_with(router, config, authenticator)
+}
+
+/// 同 [`apply_server_layers`]，使用调用方创建的校验器，与 gRPC 等其他入口共享 API key 存储；
+/// `authenticator` 为空时不启用认证
+pub fn apply_server_layers_with(
+    router: Router,
+    config: &ServerConfig,
+    authenticator: Option<Arc<Authenticator>>,
+) -> Result<Router, String> {
+    let router = match authenticator {
+        Some(authenticator) => {
+            router.layer(middleware::from_fn_with_state(authenticator, authenticate))
+        }
+        None => router.layer(Extension(Caller::Unrestricted)),
+    };
+    Ok(router
+        // 由 RequestBodyLimitLayer 统一限制请求体，关闭 axum 提取器自带的默认上限
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(config.max_request_size))
+        .layer(TimeoutLayer::new(Duration::from_secs(
+            config.request_timeout,
+        )))
+        .layer(cors_layer(&config.cors)?)
+        .layer(middleware::from_fn(propagate_request_id)))
+}
+
+/// 按配置构建 CORS 层
+///
+/// 允许携带凭据时浏览器不接受通配符响应头，此时 `*` 改为回显请求中的来源、方法和请求头
+fn cors_layer(config: &CorsConfig) -> Result<CorsLayer, String> {
+    let wildcard = |values: &[String]| values.iter().any(|value| value == "*");
+    let mirror = config.allow_credentials;
+
+    let origins = if wildcard(&config.allowed_origins) {
+        if mirror {
+            AllowOrigin::mirror_request()
+        } else {
+            AllowOrigin::any()
+        }
+    } else {
+        let origins = config
+            .allowed_origins
+            .iter()
+            .map(|origin| HeaderValue::from_str(origin))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Invalid CORS origin: {}", e))?;
+        AllowOrigin::list(origins)
+    };
+
+    let methods = if wildcard(&config.allowed_methods) {
+        if mirror {
+            AllowMethods::mirror_request()
+        } else {
+            AllowMethods::any()
+        }
+    } else {
+        let methods = config
+            .allowed_methods
+            .iter()
+            .map(|method| Method::from_bytes(method.as_bytes()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Invalid CORS method: {}", e))?;
+        AllowMethods::list(methods)
+    };
+
+    let headers = if wildcard(&config.allowed_headers) {
+        if mirror {
+            AllowHeaders::mirror_request()
+        } else {
+            AllowHeaders::any()
+        }
+    } else {
+        let headers = config
+            .allowed_headers
+            .iter()
+            .map(|header| HeaderName::from_bytes(header.as_bytes()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Invalid CORS header: {}", e))?;
+        AllowHeaders::list(headers)
+    };
+
+    Ok(CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods(methods)
+        .allow_headers(headers)
+        // 浏览器客户端需要读取请求ID用于反馈问题
+        .expose_headers([HeaderName::from_static(request_id::REQUEST_ID_HEADER)])
+        .allow_credentials(config.allow_credentials))
+}
+
+/// API 的 OpenAPI 3 文档
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Matching Engine API"),
+    paths(
+        health_check,
+        server_time,
+        ping,
+        get_engine_stats,
+        get_latency_stats,
+        get_symbol_stats,
+        create_order,
+        test_order,
+        get_order,
+        query_orders,
+        cancel_order,
+        get_order_audit,
+        get_user_orders,
+        get_open_orders,
+        get_orderbook,
+        get_book_analytics,
+        get_volume_analytics,
+        get_all_market_data,
+        get_tickers,
+        get_market_data,
+        get_all_mark_prices,
+        get_mark_price,
+        get_open_interest,
+        get_trades,
+        get_symbol_trades,
+        get_user_fills,
+        get_agg_trades,
+        export_user_fills,
+        export_user_orders,
+        export_trades,
+        export_orders,
+        get_symbol_listings,
+        get_symbol_status,
+        list_symbol,
+        delist_symbol,
+        bust_trade,
+        report_trade,
+        halt_symbol,
+        resume_symbol,
+        get_auction_indicative,
+        start_auction,
+        end_auction,
+        export_orderbook,
+        import_orderbook,
+        get_user_limits,
+        get_user_fees,
+        get_user_rebates,
+        get_fee_revenue,
+        get_settlement_days,
+        get_settlement_report,
+        get_settlement_csv,
+        get_user_settlement,
+        set_log_level,
+        get_balances,
+        get_positions,
+        get_margin_account,
+        get_liquidation_candidates,
+        get_liquidations,
+        get_funding_history,
+        get_session,
+        get_ledger,
+        deposit,
+        withdraw,
+        transfer,
+        reset_sandbox,
+        create_api_key,
+        list_api_keys,
+        delete_api_key,
+    ),
+    tags(
+        (name = "system", description = "健康检查和引擎统计"),
+        (name = "orders", description = "下单、撤单和订单查询"),
+        (name = "market", description = "行情、订单簿和成交"),
+        (name = "audit", description = "订单生命周期审计"),
+        (name = "accounts", description = "余额、流水、持仓和挂单限额"),
+        (name = "admin", description = "管理接口"),
+    )
+)]
+pub struct ApiDoc;
+
+/// 返回 OpenAPI 文档，服务器地址取自请求路径，与 API 实际挂载的前缀一致
+async fn openapi_json(OriginalUri(uri): OriginalUri) -> Json<utoipa::openapi::OpenApi> {
+    let base = uri.path().strip_suffix("/openapi.json").unwrap_or_default();
+    let mut doc = ApiDoc::openapi();
+    doc.servers = Some(vec![Server::new(if base.is_empty() { "/" } else { base })]);
+    Json(doc)
+}
+
+/// Swagger UI 页面，静态资源从 CDN 加载，文档读取同级的 `openapi.json`
+async fn swagger_ui() -> Html<&'static str> {
+    Html(SWAGGER_UI_HTML)
+}
+
+const SWAGGER_UI_HTML: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8" />
+  <title>Matching Engine API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js" crossorigin></script>
+  <script>
+    window.onload = () => {
+      window.ui = SwaggerUIBundle({ url: "openapi.json", dom_id: "#swagger-ui" });
+    };
+  </script>
+</body>
+</html>
+"##;
+
 /// 健康检查
-async fn health_check(State(state): State<ApiState>) -> Result<Json<Value>, StatusCode> {
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "system",
+    responses(
+        (status = 200, description = "服务健康", body = Object),
+    )
+)]
+async fn health_check(State(state): State<ApiState>) -> Json<Value> {
     let stats = state.engine.get_stats();
 
-    Ok(Json(json!({
+    Json(json!({
         "status": "healthy",
         "uptime_seconds": stats.uptime_seconds,
         "total_orders": stats.total_orders,
         "total_trades": stats.total_trades,
         "active_orders": stats.active_orders
-    })))
+    }))
+}
+
+/// 获取服务器时间，供客户端校准时钟偏差
+#[utoipa::path(
+    get,
+    path = "/time",
+    tag = "system",
+    responses(
+        (status = 200, description = "服务器时间", body = ServerTimeResponse),
+    )
+)]
+async fn server_time(State(state): State<ApiState>) -> Json<ServerTimeResponse> {
+    let now = state.engine.now();
+    Json(ServerTimeResponse {
+        server_time: now,
+        server_time_ms: now.timestamp_millis(),
+        monotonic_ns: state.engine.monotonic_nanos(),
+    })
+}
+
+/// 延迟探测：原样返回客户端 nonce 以及服务端收到和发出响应的时间
+#[utoipa::path(
+    get,
+    path = "/ping",
+    tag = "system",
+    params(
+        PingQuery,
+    ),
+    responses(
+        (status = 200, description = "探测响应", body = PingResponse),
+        (status = 422, description = "查询参数不合法", body = ErrorResponse),
+    )
+)]
+async fn ping(ValidQuery(query): ValidQuery<PingQuery>) -> Json<PingResponse> {
+    let receive_time_ms = Utc::now().timestamp_millis();
+    Json(PingResponse {
+        nonce: query.nonce,
+        receive_time_ms,
+        send_time_ms: Utc::now().timestamp_millis(),
+    })
 }
 
 /// 获取引擎统计信息
-async fn get_engine_stats(State(state): State<ApiState>) -> Result<Json<EngineStats>, StatusCode> {
-    Ok(Json(state.engine.get_stats()))
+#[utoipa::path(
+    get,
+    path = "/stats",
+    tag = "system",
+    responses(
+        (status = 200, description = "引擎统计信息", body = EngineStats),
+    )
+)]
+async fn get_engine_stats(State(state): State<ApiState>) -> Json<EngineStats> {
+    Json(state.engine.get_stats())
+}
+
+/// 获取撮合延迟分位数
+#[utoipa::path(
+    get,
+    path = "/stats/latency",
+    tag = "system",
+    responses(
+        (status = 200, description = "下单到受理和下单到首笔成交的延迟分位数（微秒）", body = LatencyReport),
+        (status = 404, description = "未启用延迟统计", body = ErrorResponse),
+    )
+)]
+async fn get_latency_stats(State(state): State<ApiState>) -> Result<Json<LatencyReport>, ApiError> {
+    state
+        .engine
+        .latency_report()
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found("Latency tracking is not enabled"))
+}
+
+/// 获取交易对统计信息
+#[utoipa::path(
+    get,
+    path = "/stats/{symbol}",
+    tag = "system",
+    params(
+        ("symbol" = String, Path, description = "交易对，如 BTCUSDT、BTC-USDT 或 BTC/USDT"),
+    ),
+    responses(
+        (status = 200, description = "交易对统计信息", body = SymbolStats),
+        (status = 400, description = "交易对格式错误", body = ErrorResponse),
+        (status = 404, description = "交易对没有任何订单", body = ErrorResponse),
+    )
+)]
+async fn get_symbol_stats(
+    State(state): State<ApiState>,
+    Path(symbol_str): Path<String>,
+) -> Result<Json<SymbolStats>, ApiError> {
+    let symbol = parse_symbol(&symbol_str)?;
+
+    state
+        .engine
+        .get_symbol_stats(&symbol)
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("No orders for {}", symbol)))
 }
 
 /// 创建订单
+#[utoipa::path(
+    post,
+    path = "/orders",
+    tag = "orders",
+    request_body = CreateOrderRequest,
+    responses(
+        (status = 200, description = "订单已受理", body = CreateOrderResponse),
+        (status = 400, description = "订单被引擎拒绝", body = ErrorResponse),
+        (status = 422, description = "请求字段不合法", body = ErrorResponse),
+        (status = 503, description = "交易对下单队列已满，按 Retry-After 重试", body = ErrorResponse),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+    )
+)]
 async fn create_order(
     State(state): State<ApiState>,
-    Json(request): Json<CreateOrderRequest>,
-) -> Result<Json<CreateOrderResponse>, StatusCode> {
+    caller: Caller,
+    ValidJson(request): ValidJson<CreateOrderRequest>,
+) -> Result<Json<CreateOrderResponse>, ApiError> {
+    caller.authorize(&request.user_id, ApiKeyScope::Trade)?;
     info!("Creating order for user {}: {:?}", request.user_id, request);
 
-    let order = Order::new(
-        request.symbol,
-        request.side,
-        request.order_type,
-        request.quantity,
-        request.price,
-        request.user_id.clone(),
-    );
+    let order = request.into_order().with_channel(OrderChannel::Rest);
 
     match state.engine.submit_order(order.clone()).await {
         Ok(trades) => {
@@ -97,185 +552,4546 @@ async fn create_order(
                 ),
             }))
         }
+        Err(e) if is_engine_busy(&e) => {
+            warn!("Order {} rejected: {}", order.id, e);
+            let retry_after = state
+                .engine
+                .submission_retry_after()
+                .unwrap_or_default()
+                .as_secs();
+            Err(
+                ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "engine_busy", e)
+                    .with_retry_after(retry_after),
+            )
+        }
         Err(e) => {
             error!("Failed to create order: {}", e);
-            Err(StatusCode::BAD_REQUEST)
+            Err(ApiError::bad_request("order_rejected", e))
         }
     }
 }
 
-/// 获取订单信息
+/// 模拟下单（what-if）：按当前订单簿估算成交、均价、滑点和剩余挂单，不修改任何状态
+#[utoipa::path(
+    post,
+    path = "/orders/test",
+    tag = "orders",
+    request_body = CreateOrderRequest,
+    responses(
+        (status = 200, description = "模拟撮合结果", body = OrderTestResult),
+        (status = 400, description = "订单会被引擎拒绝", body = ErrorResponse),
+        (status = 422, description = "请求字段不合法", body = ErrorResponse),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+    )
+)]
+async fn test_order(
+    State(state): State<ApiState>,
+    caller: Caller,
+    ValidJson(request): ValidJson<CreateOrderRequest>,
+) -> Result<Json<OrderTestResult>, ApiError> {
+    caller.authorize(&request.user_id, ApiKeyScope::Trade)?;
+    state
+        .engine
+        .test_order(request.into_order())
+        .map(Json)
+        .map_err(|e| ApiError::bad_request("order_rejected", e))
+}
+
+/// 解析路径中的订单ID，既可以是数字ID，也可以是下单时提供的外部 UUID 别名
+fn resolve_order_id(state: &ApiState, order_id: &str) -> Result<OrderId, ApiError> {
+    if let Ok(id) = order_id.parse::<OrderId>() {
+        return Ok(id);
+    }
+
+    let external_id = Uuid::parse_str(order_id).map_err(|_| {
+        ApiError::bad_request(
+            "invalid_order_id",
+            format!("Invalid order id {}, expected a number or UUID", order_id),
+        )
+    })?;
+    state
+        .engine
+        .resolve_external_id(external_id)
+        .ok_or_else(|| order_not_found(order_id))
+}
+
+fn order_not_found(order_id: impl std::fmt::Display) -> ApiError {
+    ApiError::new(
+        StatusCode::NOT_FOUND,
+        "order_not_found",
+        format!("Order {} not found", order_id),
+    )
+}
+
+/// 获取订单信息和逐笔成交明细
+#[utoipa::path(
+    get,
+    path = "/orders/{order_id}",
+    tag = "orders",
+    params(
+        ("order_id" = String, Path, description = "数字订单ID或下单时提供的外部 UUID"),
+        GetOrderQuery,
+    ),
+    responses(
+        (status = 200, description = "订单详情及逐笔成交", body = OrderDetail),
+        (status = 400, description = "订单ID格式错误", body = ErrorResponse),
+        (status = 404, description = "订单不存在", body = ErrorResponse),
+        (status = 422, description = "查询参数不合法", body = ErrorResponse),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+    )
+)]
 async fn get_order(
     State(state): State<ApiState>,
+    caller: Caller,
     Path(order_id): Path<String>,
-) -> Result<Json<Order>, StatusCode> {
-    let order_id = match Uuid::parse_str(&order_id) {
-        Ok(id) => id,
-        Err(_) => return Err(StatusCode::BAD_REQUEST),
+    ValidQuery(query): ValidQuery<GetOrderQuery>,
+) -> Result<Json<OrderDetail>, ApiError> {
+    let order_id = resolve_order_id(&state, &order_id)?;
+
+    let detail = state
+        .engine
+        .get_order_detail(order_id)
+        .ok_or_else(|| order_not_found(order_id))?;
+    caller.authorize(&detail.order.user_id, ApiKeyScope::Read)?;
+    let Some(wait) = query.wait_duration() else {
+        return Ok(Json(detail));
     };
 
-    match state.engine.get_order(order_id) {
-        Some(order) => Ok(Json(order)),
-        None => Err(StatusCode::NOT_FOUND),
+    state
+        .engine
+        .wait_for_order(order_id, detail.order.filled_quantity, wait)
+        .await;
+    state
+        .engine
+        .get_order_detail(order_id)
+        .map(Json)
+        .ok_or_else(|| order_not_found(order_id))
+}
+
+/// 批量查询订单的当前状态，供对账任务一次同步大量订单
+#[utoipa::path(
+    post,
+    path = "/orders/query",
+    tag = "orders",
+    request_body = OrderQueryRequest,
+    responses(
+        (status = 200, description = "查到的订单和不存在的订单ID", body = OrderQueryResponse),
+        (status = 422, description = "请求体不合法", body = ErrorResponse),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+    )
+)]
+async fn query_orders(
+    State(state): State<ApiState>,
+    caller: Caller,
+    ValidJson(request): ValidJson<OrderQueryRequest>,
+) -> Result<Json<OrderQueryResponse>, ApiError> {
+    // 格式已校验，解析失败只可能是外部 UUID 不存在
+    let requested: Vec<(String, Option<OrderId>)> = request
+        .order_ids
+        .into_iter()
+        .map(|order_id| {
+            let resolved = resolve_order_id(&state, &order_id).ok();
+            (order_id, resolved)
+        })
+        .collect();
+    let order_ids: Vec<OrderId> = requested.iter().filter_map(|(_, id)| *id).collect();
+
+    let orders = state.engine.get_orders(&order_ids);
+    for order in &orders {
+        caller.authorize(&order.user_id, ApiKeyScope::Read)?;
     }
+    let found: HashSet<OrderId> = orders.iter().map(|order| order.id).collect();
+    let not_found = requested
+        .into_iter()
+        .filter(|(_, id)| !id.is_some_and(|id| found.contains(&id)))
+        .map(|(order_id, _)| order_id)
+        .collect();
+    Ok(Json(OrderQueryResponse { orders, not_found }))
 }
 
 /// 取消订单
+#[utoipa::path(
+    delete,
+    path = "/orders/{order_id}",
+    tag = "orders",
+    params(
+        ("order_id" = String, Path, description = "数字订单ID或下单时提供的外部 UUID"),
+        CancelOrderQuery,
+    ),
+    responses(
+        (status = 200, description = "撤单结果", body = CancelOrderResponse),
+        (status = 400, description = "订单ID格式错误", body = ErrorResponse),
+        (status = 422, description = "查询参数不合法", body = ErrorResponse),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+    )
+)]
 async fn cancel_order(
     State(state): State<ApiState>,
+    caller: Caller,
     Path(order_id): Path<String>,
-    Query(params): Query<HashMap<String, String>>,
-) -> Result<Json<CancelOrderResponse>, StatusCode> {
-    let order_id = match Uuid::parse_str(&order_id) {
-        Ok(id) => id,
-        Err(_) => return Err(StatusCode::BAD_REQUEST),
-    };
-
-    let user_id = match params.get("user_id") {
-        Some(id) => id.clone(),
-        None => return Err(StatusCode::BAD_REQUEST),
-    };
+    ValidQuery(query): ValidQuery<CancelOrderQuery>,
+) -> Result<Json<CancelOrderResponse>, ApiError> {
+    caller.authorize(&query.user_id, ApiKeyScope::Trade)?;
+    let order_id = resolve_order_id(&state, &order_id)?;
 
-    match state.engine.cancel_order(order_id, user_id).await {
+    match state.engine.cancel_order(order_id, query.user_id).await {
         Ok(_) => Ok(Json(CancelOrderResponse {
             success: true,
             message: "Order cancelled successfully".to_string(),
         })),
+        Err(e) if state.engine.get_order(order_id).is_none() => {
+            warn!("Failed to cancel order {}: {}", order_id, e);
+            Err(order_not_found(order_id))
+        }
         Err(e) => {
             warn!("Failed to cancel order {}: {}", order_id, e);
-            Ok(Json(CancelOrderResponse {
-                success: false,
-                message: e,
-            }))
+            Err(ApiError::bad_request("cancel_rejected", e))
+        }
+    }
+}
+
+/// 获取订单的完整审计记录
+#[utoipa::path(
+    get,
+    path = "/audit/orders/{order_id}",
+    tag = "audit",
+    params(
+        ("order_id" = String, Path, description = "数字订单ID或下单时提供的外部 UUID"),
+    ),
+    responses(
+        (status = 200, description = "按发生顺序排列的审计事件", body = Vec<AuditEvent>),
+        (status = 404, description = "没有该订单的审计记录", body = ErrorResponse),
+        (status = 503, description = "未启用订单审计", body = ErrorResponse),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+    )
+)]
+async fn get_order_audit(
+    State(state): State<ApiState>,
+    caller: Caller,
+    Path(order_id): Path<String>,
+) -> Result<Json<Vec<AuditEvent>>, ApiError> {
+    if !state.engine.is_audit_enabled() {
+        return Err(ApiError::unavailable("Order audit is not enabled"));
+    }
+    let order_id = resolve_order_id(&state, &order_id)?;
+    if caller != Caller::Unrestricted {
+        let order = state
+            .engine
+            .get_order(order_id)
+            .ok_or_else(|| order_not_found(order_id))?;
+        caller.authorize(&order.user_id, ApiKeyScope::Read)?;
+    }
+
+    match state.engine.get_order_audit(order_id) {
+        Ok(events) if events.is_empty() => Err(ApiError::not_found(format!(
+            "No audit events for order {}",
+            order_id
+        ))),
+        Ok(events) => Ok(Json(events)),
+        Err(e) => {
+            error!("Failed to read audit trail for order {}: {}", order_id, e);
+            Err(ApiError::internal())
         }
     }
 }
 
 /// 获取用户订单
+#[utoipa::path(
+    get,
+    path = "/orders/user/{user_id}",
+    tag = "orders",
+    params(
+        ("user_id" = String, Path, description = "用户ID"),
+        OrderQuery,
+    ),
+    responses(
+        (status = 200, description = "用户订单，按订单ID从新到旧", body = Page<Order>),
+        (status = 422, description = "查询参数不合法", body = ErrorResponse),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+    )
+)]
 async fn get_user_orders(
     State(state): State<ApiState>,
+    caller: Caller,
     Path(user_id): Path<String>,
-) -> Result<Json<Vec<Order>>, StatusCode> {
-    let orders = state.engine.get_user_orders(&user_id);
+    ValidQuery(query): ValidQuery<OrderQuery>,
+) -> Result<Json<Page<Order>>, ApiError> {
+    caller.authorize(&user_id, ApiKeyScope::Read)?;
+    let orders = state
+        .engine
+        .query_user_orders(&user_id, &query.filter(), query.page());
     Ok(Json(orders))
 }
 
+/// 获取用户的未完成订单
+#[utoipa::path(
+    get,
+    path = "/openOrders",
+    tag = "orders",
+    params(OpenOrdersQuery),
+    responses(
+        (status = 200, description = "未完成订单，按订单ID从旧到新", body = Vec<Order>),
+        (status = 422, description = "查询参数不合法", body = ErrorResponse),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+    )
+)]
+async fn get_open_orders(
+    State(state): State<ApiState>,
+    caller: Caller,
+    ValidQuery(query): ValidQuery<OpenOrdersQuery>,
+) -> Result<Json<Vec<Order>>, ApiError> {
+    caller.authorize(&query.user_id, ApiKeyScope::Read)?;
+    let symbol = query.symbol.as_deref().map(parse_symbol).transpose()?;
+    Ok(Json(
+        state
+            .engine
+            .get_open_orders(&query.user_id, symbol.as_ref()),
+    ))
+}
+
 /// 获取订单簿深度
+#[utoipa::path(
+    get,
+    path = "/orderbook/{symbol}",
+    tag = "market",
+    params(
+        ("symbol" = String, Path, description = "交易对，如 BTCUSDT、BTC-USDT 或 BTC/USDT"),
+        DepthQuery,
+    ),
+    responses(
+        (status = 200, description = "订单簿深度", body = OrderBookDepth),
+        (status = 400, description = "交易对格式错误", body = ErrorResponse),
+        (status = 404, description = "交易对不存在", body = ErrorResponse),
+        (status = 422, description = "查询参数不合法", body = ErrorResponse),
+    )
+)]
 async fn get_orderbook(
     State(state): State<ApiState>,
     Path(symbol_str): Path<String>,
-    Query(params): Query<HashMap<String, String>>,
-) -> Result<Json<OrderBookDepth>, StatusCode> {
+    ValidQuery(query): ValidQuery<DepthQuery>,
+) -> Result<Json<OrderBookDepth>, ApiError> {
     // 解析交易对符号
     let symbol = parse_symbol(&symbol_str)?;
 
-    let depth = params.get("depth").and_then(|d| d.parse::<usize>().ok());
-
-    match state.engine.get_orderbook_depth(&symbol, depth) {
+    match state.engine.get_orderbook_depth(&symbol, query.depth) {
         Some(orderbook) => Ok(Json(orderbook)),
-        None => Err(StatusCode::NOT_FOUND),
+        None => Err(symbol_not_found(&symbol)),
     }
 }
 
-/// 获取所有市场数据
-async fn get_all_market_data(
+/// 获取盘口分析指标：中间价、微观价格、价差基点和买卖失衡度
+#[utoipa::path(
+    get,
+    path = "/analytics/{symbol}/book",
+    tag = "market",
+    params(
+        ("symbol" = String, Path, description = "交易对，如 BTCUSDT、BTC-USDT 或 BTC/USDT"),
+        BookAnalyticsQuery,
+    ),
+    responses(
+        (status = 200, description = "盘口分析指标", body = BookAnalytics),
+        (status = 400, description = "交易对格式错误", body = ErrorResponse),
+        (status = 404, description = "交易对不存在", body = ErrorResponse),
+        (status = 422, description = "查询参数不合法", body = ErrorResponse),
+    )
+)]
+async fn get_book_analytics(
+    State(state): State<ApiState>,
+    Path(symbol_str): Path<String>,
+    ValidQuery(query): ValidQuery<BookAnalyticsQuery>,
+) -> Result<Json<BookAnalytics>, ApiError> {
+    let symbol = parse_symbol(&symbol_str)?;
+
+    state
+        .engine
+        .get_book_analytics(&symbol, query.depth.unwrap_or(DEFAULT_ANALYTICS_DEPTH))
+        .map(Json)
+        .ok_or_else(|| symbol_not_found(&symbol))
+}
+
+/// 获取按周期汇总的成交量、成交笔数、VWAP 和主动买卖成交量
+#[utoipa::path(
+    get,
+    path = "/analytics/{symbol}/volume",
+    tag = "market",
+    params(
+        ("symbol" = String, Path, description = "交易对，如 BTCUSDT、BTC-USDT 或 BTC/USDT"),
+        VolumeQuery,
+    ),
+    responses(
+        (status = 200, description = "成交量统计", body = VolumeSeries),
+        (status = 400, description = "交易对格式错误", body = ErrorResponse),
+        (status = 404, description = "交易对不存在", body = ErrorResponse),
+        (status = 422, description = "查询参数不合法", body = ErrorResponse),
+    )
+)]
+async fn get_volume_analytics(
     State(state): State<ApiState>,
-) -> Result<Json<HashMap<Symbol, MarketData>>, StatusCode> {
-    Ok(Json(state.engine.get_all_market_data()))
+    Path(symbol_str): Path<String>,
+    ValidQuery(query): ValidQuery<VolumeQuery>,
+) -> Result<Json<VolumeSeries>, ApiError> {
+    let symbol = parse_symbol(&symbol_str)?;
+    let to = query.to.unwrap_or_else(|| state.engine.now());
+    let from = query
+        .from
+        .unwrap_or(to - chrono::Duration::hours(DEFAULT_VOLUME_RANGE_HOURS));
+
+    state
+        .engine
+        .get_volume_series(&symbol, query.interval, from, to)
+        .map(Json)
+        .ok_or_else(|| symbol_not_found(&symbol))
+}
+
+/// 获取所有市场数据
+#[utoipa::path(
+    get,
+    path = "/market-data",
+    tag = "market",
+    responses(
+        (status = 200, description = "所有交易对的市场数据", body = HashMap<String, MarketData>),
+    )
+)]
+async fn get_all_market_data(State(state): State<ApiState>) -> Json<HashMap<Symbol, MarketData>> {
+    Json(state.engine.get_all_market_data())
+}
+
+/// 获取所有未暂停交易对的 24 小时行情：最新价、涨跌幅、最高最低价、成交量和最优买卖价
+#[utoipa::path(
+    get,
+    path = "/ticker/24hr",
+    tag = "market",
+    responses(
+        (status = 200, description = "按交易对排序的行情列表", body = Vec<Ticker>),
+    )
+)]
+async fn get_tickers(State(state): State<ApiState>) -> Json<Vec<Ticker>> {
+    Json(state.engine.get_tickers())
 }
 
 /// 获取特定交易对的市场数据
+#[utoipa::path(
+    get,
+    path = "/market-data/{symbol}",
+    tag = "market",
+    params(
+        ("symbol" = String, Path, description = "交易对，如 BTCUSDT、BTC-USDT 或 BTC/USDT"),
+    ),
+    responses(
+        (status = 200, description = "市场数据", body = MarketData),
+        (status = 400, description = "交易对格式错误", body = ErrorResponse),
+        (status = 404, description = "交易对不存在", body = ErrorResponse),
+    )
+)]
 async fn get_market_data(
     State(state): State<ApiState>,
     Path(symbol_str): Path<String>,
-) -> Result<Json<MarketData>, StatusCode> {
+) -> Result<Json<MarketData>, ApiError> {
     let symbol = parse_symbol(&symbol_str)?;
 
     match state.engine.get_market_data(&symbol) {
         Some(market_data) => Ok(Json(market_data)),
-        None => Err(StatusCode::NOT_FOUND),
+        None => Err(symbol_not_found(&symbol)),
     }
 }
 
+/// 获取所有交易对的指数价格和标记价格
+#[utoipa::path(
+    get,
+    path = "/mark-price",
+    tag = "market",
+    responses(
+        (status = 200, description = "各交易对的指数价格和标记价格", body = Vec<MarkPrice>),
+    )
+)]
+async fn get_all_mark_prices(State(state): State<ApiState>) -> Json<Vec<MarkPrice>> {
+    Json(state.engine.get_all_mark_prices())
+}
+
+/// 获取交易对的指数价格、标记价格及成分报价
+#[utoipa::path(
+    get,
+    path = "/mark-price/{symbol}",
+    tag = "market",
+    params(
+        ("symbol" = String, Path, description = "交易对，如 BTCUSDT、BTC-USDT 或 BTC/USDT"),
+    ),
+    responses(
+        (status = 200, description = "指数价格和标记价格", body = MarkPrice),
+        (status = 400, description = "交易对格式错误", body = ErrorResponse),
+        (status = 404, description = "交易对没有标记价格", body = ErrorResponse),
+    )
+)]
+async fn get_mark_price(
+    State(state): State<ApiState>,
+    Path(symbol_str): Path<String>,
+) -> Result<Json<MarkPrice>, ApiError> {
+    let symbol = parse_symbol(&symbol_str)?;
+    state
+        .engine
+        .get_mark_price(&symbol)
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("No mark price for {}", symbol)))
+}
+
+/// 获取交易对的未平仓量
+#[utoipa::path(
+    get,
+    path = "/open-interest/{symbol}",
+    tag = "market",
+    params(
+        ("symbol" = String, Path, description = "交易对，如 BTCUSDT、BTC-USDT 或 BTC/USDT"),
+    ),
+    responses(
+        (status = 200, description = "未平仓量", body = OpenInterest),
+        (status = 400, description = "交易对格式错误", body = ErrorResponse),
+    )
+)]
+async fn get_open_interest(
+    State(state): State<ApiState>,
+    Path(symbol_str): Path<String>,
+) -> Result<Json<OpenInterest>, ApiError> {
+    let symbol = parse_symbol(&symbol_str)?;
+    Ok(Json(state.engine.get_open_interest(&symbol)))
+}
+
+/// 获取交易对的历史资金费率
+#[utoipa::path(
+    get,
+    path = "/funding/{symbol}",
+    tag = "market",
+    params(
+        ("symbol" = String, Path, description = "交易对，如 BTCUSDT、BTC-USDT 或 BTC/USDT"),
+        LimitQuery,
+    ),
+    responses(
+        (status = 200, description = "资金费结算记录，最新的在前", body = Vec<FundingRate>),
+        (status = 400, description = "交易对格式错误", body = ErrorResponse),
+        (status = 404, description = "未启用资金费率", body = ErrorResponse),
+        (status = 422, description = "查询参数不合法", body = ErrorResponse),
+    )
+)]
+async fn get_funding_history(
+    State(state): State<ApiState>,
+    Path(symbol_str): Path<String>,
+    ValidQuery(query): ValidQuery<LimitQuery>,
+) -> Result<Json<Vec<FundingRate>>, ApiError> {
+    let symbol = parse_symbol(&symbol_str)?;
+    state
+        .engine
+        .get_funding_history(&symbol, query.limit)
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found("Funding rates are not enabled"))
+}
+
+/// 获取交易对的交易时段、当前阶段和下一次开盘、收盘时间
+#[utoipa::path(
+    get,
+    path = "/sessions/{symbol}",
+    tag = "market",
+    params(
+        ("symbol" = String, Path, description = "交易对，如 BTCUSDT、BTC-USDT 或 BTC/USDT"),
+    ),
+    responses(
+        (status = 200, description = "交易时段", body = SessionInfo),
+        (status = 400, description = "交易对格式错误", body = ErrorResponse),
+        (status = 404, description = "未启用交易时段或交易对没有时段", body = ErrorResponse),
+    )
+)]
+async fn get_session(
+    State(state): State<ApiState>,
+    Path(symbol_str): Path<String>,
+) -> Result<Json<SessionInfo>, ApiError> {
+    let symbol = parse_symbol(&symbol_str)?;
+    state
+        .engine
+        .get_session(&symbol)
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("No trading session for {}", symbol)))
+}
+
 /// 获取交易历史
+#[utoipa::path(
+    get,
+    path = "/trades",
+    tag = "market",
+    params(
+        TradeQuery,
+    ),
+    responses(
+        (status = 200, description = "成交记录（不含双方用户ID和订单ID），按成交ID从新到旧", body = Page<PublicTrade>),
+        (status = 422, description = "查询参数不合法", body = ErrorResponse),
+    )
+)]
 async fn get_trades(
     State(state): State<ApiState>,
-    Query(params): Query<HashMap<String, String>>,
-) -> Result<Json<Vec<Trade>>, StatusCode> {
-    let limit = params.get("limit").and_then(|l| l.parse::<usize>().ok());
+    ValidQuery(query): ValidQuery<TradeQuery>,
+) -> Json<Page<PublicTrade>> {
+    let trades = state.engine.query_trades(&query.filter(), query.page());
+    Json(public_trades(trades))
+}
+
+/// 导出用户自己的成交明细，不含对手方
+#[utoipa::path(
+    get,
+    path = "/export/fills/{user_id}",
+    tag = "market",
+    params(
+        ("user_id" = String, Path, description = "用户ID"),
+        ExportQuery,
+    ),
+    responses(
+        (status = 200, description = "成交明细文件（CSV 或 Parquet），按成交ID从旧到新", body = String, content_type = "text/csv"),
+        (status = 400, description = "匹配的成交超过单次导出上限", body = ErrorResponse),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+        (status = 422, description = "查询参数不合法", body = ErrorResponse),
+    )
+)]
+async fn export_user_fills(
+    State(state): State<ApiState>,
+    caller: Caller,
+    Path(user_id): Path<String>,
+    ValidQuery(query): ValidQuery<ExportQuery>,
+) -> Result<Response, ApiError> {
+    caller.authorize(&user_id, ApiKeyScope::Read)?;
+    let filter = FillFilter {
+        symbol: query.symbol(),
+        start_time: query.from,
+        end_time: query.to,
+        ..Default::default()
+    };
+    let page = state.engine.query_user_fills(
+        &user_id,
+        &filter,
+        PageRequest {
+            cursor: None,
+            limit: MAX_EXPORT_ROWS,
+        },
+    );
+    if page.next_cursor.is_some() {
+        return Ok(export_too_large());
+    }
+    let mut fills = page.items;
+    fills.reverse();
+    Ok(export_file(
+        "fills",
+        query.format,
+        crate::export::export_fills(&fills, query.format),
+    ))
+}
+
+/// 导出用户自己的订单（不含已归档的终态订单）
+#[utoipa::path(
+    get,
+    path = "/export/orders/{user_id}",
+    tag = "orders",
+    params(
+        ("user_id" = String, Path, description = "用户ID"),
+        ExportQuery,
+    ),
+    responses(
+        (status = 200, description = "订单文件（CSV 或 Parquet），按订单ID从旧到新，时间范围按下单时间", body = String, content_type = "text/csv"),
+        (status = 400, description = "匹配的订单超过单次导出上限", body = ErrorResponse),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+        (status = 422, description = "查询参数不合法", body = ErrorResponse),
+    )
+)]
+async fn export_user_orders(
+    State(state): State<ApiState>,
+    caller: Caller,
+    Path(user_id): Path<String>,
+    ValidQuery(query): ValidQuery<ExportQuery>,
+) -> Result<Response, ApiError> {
+    caller.authorize(&user_id, ApiKeyScope::Read)?;
+    let filter = OrderFilter {
+        symbol: query.symbol(),
+        start_time: query.from,
+        end_time: query.to,
+        ..Default::default()
+    };
+    let page = state.engine.query_user_orders(
+        &user_id,
+        &filter,
+        PageRequest {
+            cursor: None,
+            limit: MAX_EXPORT_ROWS,
+        },
+    );
+    if page.next_cursor.is_some() {
+        return Ok(export_too_large());
+    }
+    let mut orders = page.items;
+    orders.reverse();
+    Ok(export_file(
+        "orders",
+        query.format,
+        crate::export::export_orders(&orders, query.format),
+    ))
+}
+
+/// 导出全部成交（管理接口），包含买卖双方的用户ID
+#[utoipa::path(
+    get,
+    path = "/admin/export/trades",
+    tag = "admin",
+    params(
+        ExportQuery,
+    ),
+    responses(
+        (status = 200, description = "成交文件（CSV 或 Parquet），按成交ID从旧到新", body = String, content_type = "text/csv"),
+        (status = 400, description = "匹配的成交超过单次导出上限", body = ErrorResponse),
+        (status = 422, description = "查询参数不合法", body = ErrorResponse),
+    )
+)]
+async fn export_trades(
+    State(state): State<ApiState>,
+    ValidQuery(query): ValidQuery<ExportQuery>,
+) -> Response {
+    let filter = TradeFilter {
+        symbol: query.symbol(),
+        side: None,
+        start_time: query.from,
+        end_time: query.to,
+    };
+    let page = state.engine.query_trades(
+        &filter,
+        PageRequest {
+            cursor: None,
+            limit: MAX_EXPORT_ROWS,
+        },
+    );
+    if page.next_cursor.is_some() {
+        return export_too_large();
+    }
+    let mut trades = page.items;
+    trades.reverse();
+    export_file(
+        "trades",
+        query.format,
+        crate::export::export_trades(&trades, query.format),
+    )
+}
+
+/// 导出全部用户的订单（管理接口，不含已归档的终态订单）
+#[utoipa::path(
+    get,
+    path = "/admin/export/orders",
+    tag = "admin",
+    params(
+        ExportQuery,
+    ),
+    responses(
+        (status = 200, description = "订单文件（CSV 或 Parquet），按订单ID从旧到新，时间范围按下单时间", body = String, content_type = "text/csv"),
+        (status = 400, description = "匹配的订单超过单次导出上限", body = ErrorResponse),
+        (status = 422, description = "查询参数不合法", body = ErrorResponse),
+    )
+)]
+async fn export_orders(
+    State(state): State<ApiState>,
+    ValidQuery(query): ValidQuery<ExportQuery>,
+) -> Response {
+    let filter = OrderFilter {
+        symbol: query.symbol(),
+        start_time: query.from,
+        end_time: query.to,
+        ..Default::default()
+    };
+    let page = state.engine.query_orders(
+        &filter,
+        PageRequest {
+            cursor: None,
+            limit: MAX_EXPORT_ROWS,
+        },
+    );
+    if page.next_cursor.is_some() {
+        return export_too_large();
+    }
+    let mut orders = page.items;
+    orders.reverse();
+    export_file(
+        "orders",
+        query.format,
+        crate::export::export_orders(&orders, query.format),
+    )
+}
+
+/// 导出文件作为附件返回，文件名为 `<name>.<扩展名>`
+fn export_file(name: &str, format: ExportFormat, data: Result<Vec<u8>, String>) -> Response {
+    match data {
+        Ok(data) => (
+            [
+                (header::CONTENT_TYPE, format.content_type().to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}.{}\"", name, format.extension()),
+                ),
+            ],
+            data,
+        )
+            .into_response(),
+        Err(e) => {
+            error!("Failed to export {}: {}", name, e);
+            ApiError::internal().into_response()
+        }
+    }
+}
 
-    let trades = state.engine.get_trades(None, limit);
-    Ok(Json(trades))
+fn export_too_large() -> Response {
+    ApiError::bad_request(
+        "export_too_large",
+        format!(
+            "More than {} rows match, narrow the time range",
+            MAX_EXPORT_ROWS
+        ),
+    )
+    .into_response()
 }
 
 /// 获取特定交易对的交易历史
+#[utoipa::path(
+    get,
+    path = "/trades/{symbol}",
+    tag = "market",
+    params(
+        ("symbol" = String, Path, description = "交易对，如 BTCUSDT、BTC-USDT 或 BTC/USDT"),
+        TradeQuery,
+    ),
+    responses(
+        (status = 200, description = "交易对成交记录（不含双方用户ID和订单ID），按成交ID从新到旧", body = Page<PublicTrade>),
+        (status = 400, description = "交易对格式错误", body = ErrorResponse),
+        (status = 422, description = "查询参数不合法", body = ErrorResponse),
+    )
+)]
 async fn get_symbol_trades(
     State(state): State<ApiState>,
     Path(symbol_str): Path<String>,
-    Query(params): Query<HashMap<String, String>>,
-) -> Result<Json<Vec<Trade>>, StatusCode> {
+    ValidQuery(query): ValidQuery<TradeQuery>,
+) -> Result<Json<Page<PublicTrade>>, ApiError> {
+    let symbol = parse_symbol(&symbol_str)?;
+
+    let mut filter = query.filter();
+    filter.symbol = Some(symbol);
+    let trades = state.engine.query_trades(&filter, query.page());
+    Ok(Json(public_trades(trades)))
+}
+
+/// 成交分页的公开视图，去掉双方用户ID和订单ID
+fn public_trades(page: Page<Trade>) -> Page<PublicTrade> {
+    Page {
+        items: page.items.iter().map(PublicTrade::from).collect(),
+        next_cursor: page.next_cursor,
+    }
+}
+
+/// 获取用户自己的成交记录
+#[utoipa::path(
+    get,
+    path = "/trades/user/{user_id}",
+    tag = "market",
+    params(
+        ("user_id" = String, Path, description = "用户ID"),
+        FillQuery,
+    ),
+    responses(
+        (status = 200, description = "用户成交，按成交ID从新到旧", body = Page<Fill>),
+        (status = 422, description = "查询参数不合法", body = ErrorResponse),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+    )
+)]
+async fn get_user_fills(
+    State(state): State<ApiState>,
+    caller: Caller,
+    Path(user_id): Path<String>,
+    ValidQuery(query): ValidQuery<FillQuery>,
+) -> Result<Json<Page<Fill>>, ApiError> {
+    caller.authorize(&user_id, ApiKeyScope::Read)?;
+    let fills = state
+        .engine
+        .query_user_fills(&user_id, &query.filter(), query.page());
+    Ok(Json(fills))
+}
+
+/// 获取交易对的聚合成交
+///
+/// 同一吃单订单在同一价格上连续成交的多笔成交合并为一条，`side` 按主动方方向过滤
+#[utoipa::path(
+    get,
+    path = "/agg-trades/{symbol}",
+    tag = "market",
+    params(
+        ("symbol" = String, Path, description = "交易对，如 BTCUSDT、BTC-USDT 或 BTC/USDT"),
+        TradeQuery,
+    ),
+    responses(
+        (status = 200, description = "聚合成交，按第一笔成交ID从新到旧", body = Page<AggTrade>),
+        (status = 400, description = "交易对格式错误", body = ErrorResponse),
+        (status = 422, description = "查询参数不合法", body = ErrorResponse),
+    )
+)]
+async fn get_agg_trades(
+    State(state): State<ApiState>,
+    Path(symbol_str): Path<String>,
+    ValidQuery(query): ValidQuery<TradeQuery>,
+) -> Result<Json<Page<AggTrade>>, ApiError> {
+    let symbol = parse_symbol(&symbol_str)?;
+
+    let mut filter = query.filter();
+    filter.symbol = Some(symbol);
+    let agg_trades = state.engine.query_agg_trades(&filter, query.page());
+    Ok(Json(agg_trades))
+}
+
+/// 获取交易对交易状态
+#[utoipa::path(
+    get,
+    path = "/symbols/{symbol}/status",
+    tag = "market",
+    params(
+        ("symbol" = String, Path, description = "交易对，如 BTCUSDT、BTC-USDT 或 BTC/USDT"),
+    ),
+    responses(
+        (status = 200, description = "交易对交易状态", body = Object),
+        (status = 400, description = "交易对格式错误", body = ErrorResponse),
+    )
+)]
+async fn get_symbol_status(
+    State(state): State<ApiState>,
+    Path(symbol_str): Path<String>,
+) -> Result<Json<Value>, ApiError> {
     let symbol = parse_symbol(&symbol_str)?;
-    let limit = params.get("limit").and_then(|l| l.parse::<usize>().ok());
+    let trading_state = state.engine.get_trading_state(&symbol);
+
+    Ok(Json(json!({
+        "symbol": symbol,
+        "state": trading_state
+    })))
+}
+
+/// 撤销一笔已执行的成交（管理接口）
+#[utoipa::path(
+    post,
+    path = "/admin/trades/{trade_id}/bust",
+    tag = "admin",
+    params(
+        ("trade_id" = u64, Path, description = "成交ID"),
+    ),
+    request_body = BustTradeRequest,
+    responses(
+        (status = 200, description = "成交已撤销", body = TradeBust),
+        (status = 404, description = "成交不存在或已被撤销", body = ErrorResponse),
+        (status = 422, description = "请求体不合法", body = ErrorResponse),
+    )
+)]
+async fn bust_trade(
+    State(state): State<ApiState>,
+    Path(trade_id): Path<TradeId>,
+    ValidJson(request): ValidJson<BustTradeRequest>,
+) -> Result<Json<TradeBust>, ApiError> {
+    warn!("Admin busting trade {}: {}", trade_id, request.reason);
+    state
+        .engine
+        .bust_trade(trade_id, request.reason, request.restore_quantity)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            warn!("Failed to bust trade {}: {}", trade_id, e);
+            ApiError::new(StatusCode::NOT_FOUND, "trade_not_found", e)
+        })
+}
+
+/// 申报双方协商的场外成交（管理接口），只能通过管理令牌调用
+#[utoipa::path(
+    post,
+    path = "/admin/trades/report",
+    tag = "admin",
+    request_body = ReportTradeRequest,
+    responses(
+        (status = 200, description = "成交已申报", body = TradeReport),
+        (status = 400, description = "交易对暂停、价格超出价格带或没有参考价格", body = ErrorResponse),
+        (status = 404, description = "未启用场外成交申报", body = ErrorResponse),
+        (status = 422, description = "请求体不合法", body = ErrorResponse),
+    )
+)]
+async fn report_trade(
+    State(state): State<ApiState>,
+    ValidJson(request): ValidJson<ReportTradeRequest>,
+) -> Result<Json<TradeReport>, ApiError> {
+    if !state.engine.is_otc_reporting_enabled() {
+        return Err(ApiError::not_found("OTC trade reporting is not enabled"));
+    }
+
+    state
+        .engine
+        .report_trade(request)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            warn!("Trade report rejected: {}", e);
+            ApiError::bad_request("trade_rejected", e)
+        })
+}
+
+/// 查询全部交易对的上市记录
+#[utoipa::path(
+    get,
+    path = "/symbols",
+    tag = "market",
+    responses(
+        (status = 200, description = "交易对上市记录，按交易对排序", body = Vec<SymbolListing>),
+    )
+)]
+async fn get_symbol_listings(State(state): State<ApiState>) -> Json<Vec<SymbolListing>> {
+    Json(state.engine.get_symbol_listings())
+}
+
+/// 上市交易对（管理接口）
+#[utoipa::path(
+    post,
+    path = "/admin/symbols",
+    tag = "admin",
+    request_body = ListSymbolRequest,
+    responses(
+        (status = 200, description = "交易对已上市", body = SymbolListing),
+        (status = 400, description = "交易对已上市或过滤规则不合法", body = ErrorResponse),
+        (status = 422, description = "请求体不合法", body = ErrorResponse),
+    )
+)]
+async fn list_symbol(
+    State(state): State<ApiState>,
+    ValidJson(request): ValidJson<ListSymbolRequest>,
+) -> Result<Json<SymbolListing>, ApiError> {
+    info!("Admin listing symbol {}", request.symbol);
+    state
+        .engine
+        .list_symbol(request)
+        .map(Json)
+        .map_err(|e| ApiError::bad_request("listing_rejected", e))
+}
 
-    let trades = state.engine.get_trades(Some(&symbol), limit);
-    Ok(Json(trades))
+/// 下市交易对（管理接口），撤销全部挂单并返回最终统计
+#[utoipa::path(
+    post,
+    path = "/admin/symbols/{symbol}/delist",
+    tag = "admin",
+    params(
+        ("symbol" = String, Path, description = "交易对，如 BTCUSDT、BTC-USDT 或 BTC/USDT"),
+    ),
+    responses(
+        (status = 200, description = "交易对已下市", body = SymbolListing),
+        (status = 400, description = "交易对格式错误或已下市", body = ErrorResponse),
+    )
+)]
+async fn delist_symbol(
+    State(state): State<ApiState>,
+    Path(symbol_str): Path<String>,
+) -> Result<Json<SymbolListing>, ApiError> {
+    let symbol = parse_symbol(&symbol_str)?;
+    warn!("Admin delisting {}", symbol);
+    state
+        .engine
+        .delist_symbol(&symbol)
+        .await
+        .map(Json)
+        .map_err(|e| ApiError::bad_request("delisting_rejected", e))
 }
 
-/// 解析交易对符号
-fn parse_symbol(symbol_str: &str) -> Result<Symbol, StatusCode> {
-    // 支持格式: BTCUSDT, BTC-USDT, BTC/USDT
-    let parts: Vec<&str> = if symbol_str.contains('-') {
-        symbol_str.split('-').collect()
-    } else if symbol_str.contains('/') {
-        symbol_str.split('/').collect()
+/// 暂停交易对交易（管理接口）
+#[utoipa::path(
+    post,
+    path = "/admin/symbols/{symbol}/halt",
+    tag = "admin",
+    params(
+        ("symbol" = String, Path, description = "交易对，如 BTCUSDT、BTC-USDT 或 BTC/USDT"),
+    ),
+    request_body = Option<HaltSymbolRequest>,
+    responses(
+        (status = 200, description = "交易对已暂停", body = SymbolStatus),
+        (status = 400, description = "交易对格式错误", body = ErrorResponse),
+    )
+)]
+async fn halt_symbol(
+    State(state): State<ApiState>,
+    Path(symbol_str): Path<String>,
+    request: Option<Json<HaltSymbolRequest>>,
+) -> Result<Json<SymbolStatus>, ApiError> {
+    let symbol = parse_symbol(&symbol_str)?;
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+
+    let trading_state = if request.cancel_only {
+        TradingState::CancelOnly
     } else {
-        // 假设是 BTCUSDT 格式，需要智能分割
-        // 这里简化处理，假设前3个字符是基础货币
-        if symbol_str.len() >= 6 {
-            vec![&symbol_str[..3], &symbol_str[3..]]
-        } else {
-            return Err(StatusCode::BAD_REQUEST);
-        }
+        TradingState::Halted
     };
 
-    if parts.len() != 2 {
-        return Err(StatusCode::BAD_REQUEST);
+    warn!("Admin halting {} ({:?})", symbol, trading_state);
+    Ok(Json(state.engine.set_trading_state(
+        &symbol,
+        trading_state,
+        request.reason,
+    )))
+}
+
+/// 运行时修改日志级别（管理接口）
+#[utoipa::path(
+    put,
+    path = "/admin/log-level",
+    tag = "admin",
+    request_body = SetLogLevelRequest,
+    responses(
+        (status = 200, description = "当前日志级别", body = Object),
+        (status = 400, description = "过滤规则不合法", body = ErrorResponse),
+        (status = 503, description = "日志系统不支持运行时修改", body = ErrorResponse),
+    )
+)]
+async fn set_log_level(
+    State(state): State<ApiState>,
+    ValidJson(request): ValidJson<SetLogLevelRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let log_level = state.log_level.ok_or_else(|| {
+        ApiError::unavailable("The logging system does not support runtime changes")
+    })?;
+
+    if let Err(e) = log_level.set_level(&request.level) {
+        warn!("Failed to change log level: {}", e);
+        return Err(ApiError::bad_request("invalid_log_level", e));
     }
 
-    Ok(Symbol::new(parts[0], parts[1]))
+    warn!("Admin changed log level to {}", request.level);
+    Ok(Json(json!({
+        "level": log_level.current_level()
+    })))
 }
 
-/// 错误响应
-#[derive(Debug, serde::Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-    pub message: String,
+/// 恢复交易对交易（管理接口）
+#[utoipa::path(
+    post,
+    path = "/admin/symbols/{symbol}/resume",
+    tag = "admin",
+    params(
+        ("symbol" = String, Path, description = "交易对，如 BTCUSDT、BTC-USDT 或 BTC/USDT"),
+    ),
+    responses(
+        (status = 200, description = "交易对已恢复交易", body = SymbolStatus),
+        (status = 400, description = "交易对格式错误", body = ErrorResponse),
+    )
+)]
+async fn resume_symbol(
+    State(state): State<ApiState>,
+    Path(symbol_str): Path<String>,
+) -> Result<Json<SymbolStatus>, ApiError> {
+    let symbol = parse_symbol(&symbol_str)?;
+
+    info!("Admin resuming trading for {}", symbol);
+    Ok(Json(state.engine.set_trading_state(
+        &symbol,
+        TradingState::Trading,
+        None,
+    )))
 }
 
-/// 将错误转换为 JSON 响应
-pub fn error_response(error: &str, message: &str) -> Json<ErrorResponse> {
-    Json(ErrorResponse {
-        error: error.to_string(),
-        message: message.to_string(),
-    })
+/// 获取集合竞价参考价和参考成交量
+#[utoipa::path(
+    get,
+    path = "/symbols/{symbol}/auction",
+    tag = "market",
+    params(
+        ("symbol" = String, Path, description = "交易对，如 BTCUSDT、BTC-USDT 或 BTC/USDT"),
+    ),
+    responses(
+        (status = 200, description = "集合竞价参考价", body = AuctionIndicative),
+        (status = 400, description = "交易对格式错误", body = ErrorResponse),
+    )
+)]
+async fn get_auction_indicative(
+    State(state): State<ApiState>,
+    Path(symbol_str): Path<String>,
+) -> Result<Json<AuctionIndicative>, ApiError> {
+    let symbol = parse_symbol(&symbol_str)?;
+    Ok(Json(state.engine.get_auction_indicative(&symbol)))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// 开始集合竞价（管理接口）
+#[utoipa::path(
+    post,
+    path = "/admin/symbols/{symbol}/auction/start",
+    tag = "admin",
+    params(
+        ("symbol" = String, Path, description = "交易对，如 BTCUSDT、BTC-USDT 或 BTC/USDT"),
+    ),
+    responses(
+        (status = 200, description = "交易对进入集合竞价", body = SymbolStatus),
+        (status = 400, description = "交易对格式错误", body = ErrorResponse),
+    )
+)]
+async fn start_auction(
+    State(state): State<ApiState>,
+    Path(symbol_str): Path<String>,
+) -> Result<Json<SymbolStatus>, ApiError> {
+    let symbol = parse_symbol(&symbol_str)?;
 
-    #[test]
-    fn test_parse_symbol() {
-        assert_eq!(parse_symbol("BTCUSDT").unwrap(), Symbol::new("BTC", "USDT"));
-        assert_eq!(
-            parse_symbol("BTC-USDT").unwrap(),
-            Symbol::new("BTC", "USDT")
-        );
-        assert_eq!(
-            parse_symbol("BTC/USDT").unwrap(),
-            Symbol::new("BTC", "USDT")
-        );
-        assert_eq!(parse_symbol("ETHUSDT").unwrap(), Symbol::new("ETH", "USDT"));
+    info!("Admin starting auction for {}", symbol);
+    Ok(Json(state.engine.start_auction(
+        &symbol,
+        Some("Auction started".to_string()),
+    )))
+}
+
+/// 结束集合竞价并撮合（管理接口）
+#[utoipa::path(
+    post,
+    path = "/admin/symbols/{symbol}/auction/end",
+    tag = "admin",
+    params(
+        ("symbol" = String, Path, description = "交易对，如 BTCUSDT、BTC-USDT 或 BTC/USDT"),
+    ),
+    responses(
+        (status = 200, description = "集合竞价撮合产生的成交", body = Vec<Trade>),
+        (status = 400, description = "交易对格式错误", body = ErrorResponse),
+        (status = 409, description = "交易对不在集合竞价中", body = ErrorResponse),
+    )
+)]
+async fn end_auction(
+    State(state): State<ApiState>,
+    Path(symbol_str): Path<String>,
+) -> Result<Json<Vec<Trade>>, ApiError> {
+    let symbol = parse_symbol(&symbol_str)?;
+
+    match state.engine.end_auction(&symbol).await {
+        Ok(trades) => {
+            info!(
+                "Admin ended auction for {} with {} trades",
+                symbol,
+                trades.len()
+            );
+            Ok(Json(trades))
+        }
+        Err(e) => {
+            warn!("Failed to end auction for {}: {}", symbol, e);
+            Err(ApiError::new(
+                StatusCode::CONFLICT,
+                "auction_not_running",
+                e,
+            ))
+        }
+    }
+}
+
+/// 导出订单簿快照（管理接口）
+#[utoipa::path(
+    get,
+    path = "/admin/orderbook/{symbol}/snapshot",
+    tag = "admin",
+    params(
+        ("symbol" = String, Path, description = "交易对，如 BTCUSDT、BTC-USDT 或 BTC/USDT"),
+    ),
+    responses(
+        (status = 200, description = "订单簿快照，含全部挂单及其时间优先级", body = OrderBookSnapshot),
+        (status = 400, description = "交易对格式错误", body = ErrorResponse),
+        (status = 404, description = "交易对不存在", body = ErrorResponse),
+    )
+)]
+async fn export_orderbook(
+    State(state): State<ApiState>,
+    Path(symbol_str): Path<String>,
+) -> Result<Json<OrderBookSnapshot>, ApiError> {
+    let symbol = parse_symbol(&symbol_str)?;
+
+    state
+        .engine
+        .export_orderbook(&symbol)
+        .map(Json)
+        .ok_or_else(|| symbol_not_found(&symbol))
+}
+
+/// 将快照导入空订单簿（管理接口）
+#[utoipa::path(
+    post,
+    path = "/admin/orderbook/{symbol}/snapshot",
+    tag = "admin",
+    params(
+        ("symbol" = String, Path, description = "交易对，如 BTCUSDT、BTC-USDT 或 BTC/USDT"),
+    ),
+    request_body = OrderBookSnapshot,
+    responses(
+        (status = 200, description = "导入的订单数", body = Object),
+        (status = 400, description = "交易对格式错误或与快照不一致、快照不合法、订单簿非空或订单与现有订单冲突", body = ErrorResponse),
+    )
+)]
+async fn import_orderbook(
+    State(state): State<ApiState>,
+    Path(symbol_str): Path<String>,
+    ValidJson(snapshot): ValidJson<OrderBookSnapshot>,
+) -> Result<Json<Value>, ApiError> {
+    let symbol = parse_symbol(&symbol_str)?;
+    if snapshot.symbol != symbol {
+        warn!(
+            "Rejected orderbook snapshot for {} posted to {}",
+            snapshot.symbol, symbol
+        );
+        return Err(ApiError::bad_request(
+            "snapshot_rejected",
+            format!("Snapshot is for {}, not {}", snapshot.symbol, symbol),
+        ));
+    }
+
+    match state.engine.import_orderbook(snapshot).await {
+        Ok(imported) => {
+            warn!("Admin imported {} orders into {}", imported, symbol);
+            Ok(Json(json!({
+                "symbol": symbol,
+                "imported_orders": imported
+            })))
+        }
+        Err(e) => {
+            warn!("Failed to import orderbook snapshot for {}: {}", symbol, e);
+            Err(ApiError::bad_request("snapshot_rejected", e))
+        }
+    }
+}
+
+/// 获取用户挂单限额及当前占用
+#[utoipa::path(
+    get,
+    path = "/limits/{user_id}",
+    tag = "accounts",
+    params(
+        ("user_id" = String, Path, description = "用户ID"),
+    ),
+    responses(
+        (status = 200, description = "挂单限额及当前占用", body = UserLimitStatus),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+    )
+)]
+async fn get_user_limits(
+    State(state): State<ApiState>,
+    caller: Caller,
+    Path(user_id): Path<String>,
+) -> Result<Json<UserLimitStatus>, ApiError> {
+    caller.authorize(&user_id, ApiKeyScope::Read)?;
+    Ok(Json(state.engine.get_user_limits(&user_id)))
+}
+
+/// 获取用户手续费档位、滚动成交额和下一档门槛
+#[utoipa::path(
+    get,
+    path = "/fees/{user_id}",
+    tag = "accounts",
+    params(
+        ("user_id" = String, Path, description = "用户ID"),
+    ),
+    responses(
+        (status = 200, description = "当前手续费档位", body = UserFeeTier),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+    )
+)]
+async fn get_user_fees(
+    State(state): State<ApiState>,
+    caller: Caller,
+    Path(user_id): Path<String>,
+) -> Result<Json<UserFeeTier>, ApiError> {
+    caller.authorize(&user_id, ApiKeyScope::Read)?;
+    Ok(Json(state.engine.get_user_fees(&user_id)))
+}
+
+/// 获取用户的 maker 返佣
+#[utoipa::path(
+    get,
+    path = "/fees/{user_id}/rebates",
+    tag = "accounts",
+    params(
+        ("user_id" = String, Path, description = "用户ID"),
+    ),
+    responses(
+        (status = 200, description = "当期应计和已结算的返佣", body = UserRebates),
+        (status = 404, description = "未启用手续费结算", body = ErrorResponse),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+    )
+)]
+async fn get_user_rebates(
+    State(state): State<ApiState>,
+    caller: Caller,
+    Path(user_id): Path<String>,
+) -> Result<Json<UserRebates>, ApiError> {
+    caller.authorize(&user_id, ApiKeyScope::Read)?;
+    state
+        .engine
+        .get_user_rebates(&user_id)
+        .map(Json)
+        .ok_or_else(fee_settlement_disabled)
+}
+
+/// 获取手续费收入和返佣结算报表
+#[utoipa::path(
+    get,
+    path = "/admin/fees/revenue",
+    tag = "admin",
+    params(LimitQuery),
+    responses(
+        (status = 200, description = "当期累计、返佣池余额和最近的结算", body = FeeRevenueReport),
+        (status = 404, description = "未启用手续费结算", body = ErrorResponse),
+        (status = 422, description = "查询参数不合法", body = ErrorResponse),
+    )
+)]
+async fn get_fee_revenue(
+    State(state): State<ApiState>,
+    ValidQuery(query): ValidQuery<LimitQuery>,
+) -> Result<Json<FeeRevenueReport>, ApiError> {
+    state
+        .engine
+        .get_fee_revenue(query.limit)
+        .map(Json)
+        .ok_or_else(fee_settlement_disabled)
+}
+
+/// 获取已生成日终结算报表的交易日
+#[utoipa::path(
+    get,
+    path = "/admin/settlements",
+    tag = "admin",
+    responses(
+        (status = 200, description = "交易日（YYYY-MM-DD），最新的在前", body = Vec<String>),
+        (status = 404, description = "未启用日终结算", body = ErrorResponse),
+    )
+)]
+async fn get_settlement_days(
+    State(state): State<ApiState>,
+) -> Result<Json<Vec<NaiveDate>>, ApiError> {
+    match state.engine.get_settlement_days() {
+        Ok(days) => days.map(Json).ok_or_else(settlement_disabled),
+        Err(e) => {
+            error!("Failed to list settlement reports: {}", e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+/// 获取交易日的日终结算报表
+#[utoipa::path(
+    get,
+    path = "/admin/settlements/{trading_day}",
+    tag = "admin",
+    params(
+        ("trading_day" = String, Path, description = "交易日，如 2024-01-05"),
+    ),
+    responses(
+        (status = 200, description = "场所级和用户级结算报表", body = SettlementReport),
+        (status = 404, description = "未启用日终结算或该交易日没有报表", body = ErrorResponse),
+    )
+)]
+async fn get_settlement_report(
+    State(state): State<ApiState>,
+    Path(trading_day): Path<NaiveDate>,
+) -> Result<Json<SettlementReport>, ApiError> {
+    load_settlement_report(&state, trading_day)?
+        .map(Json)
+        .ok_or_else(|| settlement_not_found(trading_day))
+}
+
+/// 下载交易日日终结算报表的 CSV 文件
+#[utoipa::path(
+    get,
+    path = "/admin/settlements/{trading_day}/{file}",
+    tag = "admin",
+    params(
+        ("trading_day" = String, Path, description = "交易日，如 2024-01-05"),
+        ("file" = String, Path, description = "symbols.csv（按交易对）或 users.csv（按用户和资产）"),
+    ),
+    responses(
+        (status = 200, description = "CSV 报表", body = String, content_type = "text/csv"),
+        (status = 404, description = "未启用日终结算、文件名未知或该交易日没有报表", body = ErrorResponse),
+    )
+)]
+async fn get_settlement_csv(
+    State(state): State<ApiState>,
+    Path((trading_day, file)): Path<(NaiveDate, String)>,
+) -> Result<Response, ApiError> {
+    if file != SYMBOLS_CSV && file != USERS_CSV {
+        return Err(ApiError::not_found(format!(
+            "Unknown settlement file {}, expected {} or {}",
+            file, SYMBOLS_CSV, USERS_CSV
+        )));
+    }
+    match state.engine.get_settlement_csv(trading_day, &file) {
+        Ok(Some(csv)) => {
+            Ok(([(header::CONTENT_TYPE, "text/csv; charset=utf-8")], csv).into_response())
+        }
+        Ok(None) => Err(settlement_not_found(trading_day)),
+        Err(e) => {
+            error!(
+                "Failed to read settlement {} for {}: {}",
+                file, trading_day, e
+            );
+            Err(ApiError::internal())
+        }
+    }
+}
+
+/// 获取用户在交易日的日终结算
+#[utoipa::path(
+    get,
+    path = "/settlements/{trading_day}/users/{user_id}",
+    tag = "accounts",
+    params(
+        ("trading_day" = String, Path, description = "交易日，如 2024-01-05"),
+        ("user_id" = String, Path, description = "用户ID"),
+    ),
+    responses(
+        (status = 200, description = "用户的成交额、手续费、余额和持仓", body = UserSettlement),
+        (status = 404, description = "未启用日终结算、该交易日没有报表或用户不在报表中", body = ErrorResponse),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+    )
+)]
+async fn get_user_settlement(
+    State(state): State<ApiState>,
+    caller: Caller,
+    Path((trading_day, user_id)): Path<(NaiveDate, String)>,
+) -> Result<Json<UserSettlement>, ApiError> {
+    caller.authorize(&user_id, ApiKeyScope::Read)?;
+    load_settlement_report(&state, trading_day)?
+        .and_then(|report| {
+            report
+                .users
+                .into_iter()
+                .find(|user| user.user_id == user_id)
+        })
+        .map(Json)
+        .ok_or_else(|| {
+            ApiError::not_found(format!(
+                "No settlement for user {} on {}",
+                user_id, trading_day
+            ))
+        })
+}
+
+fn load_settlement_report(
+    state: &ApiState,
+    trading_day: NaiveDate,
+) -> Result<Option<SettlementReport>, ApiError> {
+    state
+        .engine
+        .get_settlement_report(trading_day)
+        .map_err(|e| {
+            error!(
+                "Failed to read settlement report for {}: {}",
+                trading_day, e
+            );
+            ApiError::internal()
+        })
+}
+
+fn settlement_disabled() -> ApiError {
+    ApiError::not_found("End-of-day settlement is not enabled")
+}
+
+fn settlement_not_found(trading_day: NaiveDate) -> ApiError {
+    ApiError::not_found(format!("No settlement report for {}", trading_day))
+}
+
+fn fee_settlement_disabled() -> ApiError {
+    ApiError::not_found("Fee settlement is not enabled")
+}
+
+/// 获取用户余额
+#[utoipa::path(
+    get,
+    path = "/accounts/{user_id}/balances",
+    tag = "accounts",
+    params(
+        ("user_id" = String, Path, description = "用户ID"),
+    ),
+    responses(
+        (status = 200, description = "用户余额", body = Vec<Balance>),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+    )
+)]
+async fn get_balances(
+    State(state): State<ApiState>,
+    caller: Caller,
+    Path(user_id): Path<String>,
+) -> Result<Json<Vec<Balance>>, ApiError> {
+    caller.authorize(&user_id, ApiKeyScope::Read)?;
+    state.engine.fund_sandbox_account(&user_id);
+    Ok(Json(state.engine.accounts().get_balances(&user_id)))
+}
+
+/// 获取用户持仓
+#[utoipa::path(
+    get,
+    path = "/positions/{user_id}",
+    tag = "accounts",
+    params(
+        ("user_id" = String, Path, description = "用户ID"),
+    ),
+    responses(
+        (status = 200, description = "用户在各交易对上的持仓，含已平仓但有已实现盈亏的交易对", body = Vec<Position>),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+    )
+)]
+async fn get_positions(
+    State(state): State<ApiState>,
+    caller: Caller,
+    Path(user_id): Path<String>,
+) -> Result<Json<Vec<Position>>, ApiError> {
+    caller.authorize(&user_id, ApiKeyScope::Read)?;
+    Ok(Json(state.engine.get_user_positions(&user_id)))
+}
+
+/// 获取账户保证金状况
+#[utoipa::path(
+    get,
+    path = "/margin/{user_id}",
+    tag = "accounts",
+    params(
+        ("user_id" = String, Path, description = "用户ID"),
+    ),
+    responses(
+        (status = 200, description = "账户权益、保证金占用和保证金率", body = MarginAccount),
+        (status = 404, description = "未启用保证金模式", body = ErrorResponse),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+    )
+)]
+async fn get_margin_account(
+    State(state): State<ApiState>,
+    caller: Caller,
+    Path(user_id): Path<String>,
+) -> Result<Json<MarginAccount>, ApiError> {
+    caller.authorize(&user_id, ApiKeyScope::Read)?;
+    state
+        .engine
+        .get_margin_account(&user_id)
+        .map(Json)
+        .ok_or_else(margin_disabled)
+}
+
+/// 获取待强平账户
+#[utoipa::path(
+    get,
+    path = "/admin/margin/liquidations",
+    tag = "admin",
+    responses(
+        (status = 200, description = "最近一次监控中维持保证金不低于权益的账户", body = Vec<MarginAccount>),
+        (status = 404, description = "未启用保证金模式", body = ErrorResponse),
+    )
+)]
+async fn get_liquidation_candidates(
+    State(state): State<ApiState>,
+) -> Result<Json<Vec<MarginAccount>>, ApiError> {
+    state
+        .engine
+        .get_liquidation_candidates()
+        .map(Json)
+        .ok_or_else(margin_disabled)
+}
+
+/// 获取最近的强平事件
+#[utoipa::path(
+    get,
+    path = "/admin/liquidations",
+    tag = "admin",
+    params(LimitQuery),
+    responses(
+        (status = 200, description = "强平事件，最新的在前", body = Vec<LiquidationEvent>),
+        (status = 404, description = "未启用强平", body = ErrorResponse),
+        (status = 422, description = "查询参数不合法", body = ErrorResponse),
+    )
+)]
+async fn get_liquidations(
+    State(state): State<ApiState>,
+    ValidQuery(query): ValidQuery<LimitQuery>,
+) -> Result<Json<Vec<LiquidationEvent>>, ApiError> {
+    state
+        .engine
+        .get_liquidations(query.limit)
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found("Liquidation is not enabled"))
+}
+
+fn margin_disabled() -> ApiError {
+    ApiError::not_found("Margin trading is not enabled")
+}
+
+/// 获取用户余额流水
+#[utoipa::path(
+    get,
+    path = "/accounts/{user_id}/ledger",
+    tag = "accounts",
+    params(
+        ("user_id" = String, Path, description = "用户ID"),
+        LimitQuery,
+    ),
+    responses(
+        (status = 200, description = "余额流水", body = Vec<LedgerEntry>),
+        (status = 422, description = "查询参数不合法", body = ErrorResponse),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+    )
+)]
+async fn get_ledger(
+    State(state): State<ApiState>,
+    caller: Caller,
+    Path(user_id): Path<String>,
+    ValidQuery(query): ValidQuery<LimitQuery>,
+) -> Result<Json<Vec<LedgerEntry>>, ApiError> {
+    caller.authorize(&user_id, ApiKeyScope::Read)?;
+    state.engine.fund_sandbox_account(&user_id);
+    Ok(Json(
+        state.engine.accounts().get_ledger(&user_id, query.limit),
+    ))
+}
+
+/// 充值（管理接口），只能通过管理令牌调用，保证金模式下余额即抵押品
+#[utoipa::path(
+    post,
+    path = "/admin/accounts/{user_id}/deposit",
+    tag = "admin",
+    params(
+        ("user_id" = String, Path, description = "用户ID"),
+    ),
+    request_body = BalanceChangeRequest,
+    responses(
+        (status = 200, description = "充值产生的流水", body = Vec<LedgerEntry>),
+        (status = 400, description = "充值失败", body = ErrorResponse),
+        (status = 409, description = "幂等键已用于不同的请求", body = ErrorResponse),
+    )
+)]
+async fn deposit(
+    State(state): State<ApiState>,
+    Path(user_id): Path<String>,
+    ValidJson(request): ValidJson<BalanceChangeRequest>,
+) -> Result<Json<Vec<LedgerEntry>>, ApiError> {
+    state
+        .engine
+        .accounts()
+        .deposit(
+            &user_id,
+            &request.asset,
+            request.amount,
+            &request.idempotency_key,
+        )
+        .map(Json)
+        .map_err(|e| {
+            warn!("Deposit for user {} failed: {}", user_id, e);
+            balance_change_error(e)
+        })
+}
+
+/// 提现（管理/测试接口），启用认证时只能从调用方自己的账户提现，API key 须有 `withdraw` 权限
+#[utoipa::path(
+    post,
+    path = "/accounts/{user_id}/withdraw",
+    tag = "accounts",
+    params(
+        ("user_id" = String, Path, description = "用户ID"),
+    ),
+    request_body = BalanceChangeRequest,
+    responses(
+        (status = 200, description = "提现产生的流水", body = Vec<LedgerEntry>),
+        (status = 400, description = "提现失败", body = ErrorResponse),
+        (status = 409, description = "幂等键已用于不同的请求", body = ErrorResponse),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+    )
+)]
+async fn withdraw(
+    State(state): State<ApiState>,
+    caller: Caller,
+    Path(user_id): Path<String>,
+    ValidJson(request): ValidJson<BalanceChangeRequest>,
+) -> Result<Json<Vec<LedgerEntry>>, ApiError> {
+    caller.authorize(&user_id, ApiKeyScope::Withdraw)?;
+    state
+        .engine
+        .accounts()
+        .withdraw(
+            &user_id,
+            &request.asset,
+            request.amount,
+            &request.idempotency_key,
+        )
+        .map(Json)
+        .map_err(|e| {
+            warn!("Withdrawal for user {} failed: {}", user_id, e);
+            balance_change_error(e)
+        })
+}
+
+/// 内部划转（管理/测试接口），启用认证时只能从调用方自己的账户转出，API key 须有 `withdraw` 权限
+#[utoipa::path(
+    post,
+    path = "/accounts/{user_id}/transfer",
+    tag = "accounts",
+    params(
+        ("user_id" = String, Path, description = "用户ID"),
+    ),
+    request_body = TransferRequest,
+    responses(
+        (status = 200, description = "划转产生的流水", body = Vec<LedgerEntry>),
+        (status = 400, description = "划转失败", body = ErrorResponse),
+        (status = 409, description = "幂等键已用于不同的请求", body = ErrorResponse),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+    )
+)]
+async fn transfer(
+    State(state): State<ApiState>,
+    caller: Caller,
+    Path(user_id): Path<String>,
+    ValidJson(request): ValidJson<TransferRequest>,
+) -> Result<Json<Vec<LedgerEntry>>, ApiError> {
+    caller.authorize(&user_id, ApiKeyScope::Withdraw)?;
+    state
+        .engine
+        .accounts()
+        .transfer(
+            &user_id,
+            &request.to_user_id,
+            &request.asset,
+            request.amount,
+            &request.idempotency_key,
+        )
+        .map(Json)
+        .map_err(|e| {
+            warn!("Transfer from user {} failed: {}", user_id, e);
+            balance_change_error(e)
+        })
+}
+
+fn balance_change_error(error: BalanceError) -> ApiError {
+    match error {
+        BalanceError::Rejected(message) => {
+            ApiError::bad_request("balance_change_rejected", message)
+        }
+        BalanceError::IdempotencyConflict(message) => {
+            ApiError::new(StatusCode::CONFLICT, "idempotency_conflict", message)
+        }
+    }
+}
+
+/// 重置沙盒账户：撤销全部挂单，余额和持仓恢复为初始虚拟资金
+#[utoipa::path(
+    post,
+    path = "/sandbox/reset",
+    tag = "accounts",
+    request_body = SandboxResetRequest,
+    responses(
+        (status = 200, description = "重置结果", body = SandboxReset),
+        (status = 400, description = "用户ID为空", body = ErrorResponse),
+        (status = 404, description = "未启用沙盒模式", body = ErrorResponse),
+        (status = 401, description = "启用认证时缺少有效令牌", body = ErrorResponse),
+        (status = 403, description = "无权访问其他用户的数据", body = ErrorResponse),
+    )
+)]
+async fn reset_sandbox(
+    State(state): State<ApiState>,
+    caller: Caller,
+    ValidJson(request): ValidJson<SandboxResetRequest>,
+) -> Result<Json<SandboxReset>, ApiError> {
+    if !state.engine.is_sandbox() {
+        return Err(ApiError::not_found("Sandbox mode is not enabled"));
+    }
+    caller.authorize(&request.user_id, ApiKeyScope::Trade)?;
+
+    state
+        .engine
+        .reset_sandbox_account(&request.user_id)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            warn!("Sandbox reset for user {} failed: {}", request.user_id, e);
+            ApiError::bad_request("sandbox_reset_failed", e)
+        })
+}
+
+/// 为调用方创建 API key，明文只在响应中返回一次
+#[utoipa::path(
+    post,
+    path = "/account/api-keys",
+    tag = "accounts",
+    request_body = NewApiKey,
+    responses(
+        (status = 200, description = "新建的 API key 及其明文", body = CreatedApiKey),
+        (status = 401, description = "缺少有效的登录令牌", body = ErrorResponse),
+        (status = 403, description = "API key 不能管理 API key", body = ErrorResponse),
+        (status = 404, description = "未启用认证", body = ErrorResponse),
+        (status = 422, description = "请求字段不合法", body = ErrorResponse),
+    )
+)]
+async fn create_api_key(
+    store: Option<Extension<Arc<ApiKeyStore>>>,
+    caller: Caller,
+    ValidJson(request): ValidJson<NewApiKey>,
+) -> Result<Json<CreatedApiKey>, ApiError> {
+    let Extension(store) = store.ok_or_else(api_keys_disabled)?;
+    let user_id = caller.session_user()?;
+    store
+        .create(user_id, request, Utc::now())
+        .map(Json)
+        .map_err(|e| {
+            error!("Failed to create API key for user {}: {}", user_id, e);
+            ApiError::internal()
+        })
+}
+
+/// 列出调用方的 API key（不含明文）
+#[utoipa::path(
+    get,
+    path = "/account/api-keys",
+    tag = "accounts",
+    responses(
+        (status = 200, description = "API key，按创建时间从早到晚", body = Vec<ApiKey>),
+        (status = 401, description = "缺少有效的登录令牌", body = ErrorResponse),
+        (status = 403, description = "API key 不能管理 API key", body = ErrorResponse),
+        (status = 404, description = "未启用认证", body = ErrorResponse),
+    )
+)]
+async fn list_api_keys(
+    store: Option<Extension<Arc<ApiKeyStore>>>,
+    caller: Caller,
+) -> Result<Json<Vec<ApiKey>>, ApiError> {
+    let Extension(store) = store.ok_or_else(api_keys_disabled)?;
+    Ok(Json(store.list(caller.session_user()?)))
+}
+
+/// 删除调用方的 API key，立即失效
+#[utoipa::path(
+    delete,
+    path = "/account/api-keys/{key_id}",
+    tag = "accounts",
+    params(
+        ("key_id" = String, Path, description = "API key ID"),
+    ),
+    responses(
+        (status = 204, description = "已删除"),
+        (status = 401, description = "缺少有效的登录令牌", body = ErrorResponse),
+        (status = 403, description = "API key 不能管理 API key", body = ErrorResponse),
+        (status = 404, description = "未启用认证或 key 不存在", body = ErrorResponse),
+    )
+)]
+async fn delete_api_key(
+    store: Option<Extension<Arc<ApiKeyStore>>>,
+    caller: Caller,
+    Path(key_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let Extension(store) = store.ok_or_else(api_keys_disabled)?;
+    let user_id = caller.session_user()?;
+    match store.delete(user_id, &key_id) {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err(ApiError::not_found(format!("API key {} not found", key_id))),
+        Err(e) => {
+            error!("Failed to delete API key {}: {}", key_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+/// 用户 API key 依赖认证，未启用认证时相关接口返回 404
+fn api_keys_disabled() -> ApiError {
+    ApiError::not_found("Authentication is not enabled")
+}
+
+/// 解析交易对符号，支持 BTCUSDT、BTC-USDT、BTC/USDT
+///
+/// 连写格式按已上市交易对和常见计价货币的最长后缀拆分，无法识别时返回 400 并说明可用的格式
+pub fn parse_symbol(symbol_str: &str) -> Result<Symbol, ApiError> {
+    symbol_str
+        .parse()
+        .map_err(|e: String| ApiError::bad_request("invalid_symbol", e))
+}
+
+fn symbol_not_found(symbol: &Symbol) -> ApiError {
+    ApiError::new(
+        StatusCode::NOT_FOUND,
+        "symbol_not_found",
+        format!("Unknown symbol {}", symbol),
+    )
+}
+
+/// 批量查询订单的最大ID数
+const MAX_ORDER_QUERY_IDS: usize = 1000;
+
+/// 长轮询订单的最长等待时间（秒），应小于 `server.request_timeout`
+const MAX_ORDER_WAIT_SECONDS: u64 = 20;
+
+/// 查询订单参数
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetOrderQuery {
+    /// 长轮询等待时间，如 `5s`、`500ms` 或秒数 `5`，最长 20 秒：订单进入终态或比请求时成交更多时立即返回，否则超时后返回当前状态
+    pub wait: Option<String>,
+}
+
+impl GetOrderQuery {
+    fn wait_duration(&self) -> Option<Duration> {
+        self.wait.as_deref().and_then(parse_wait)
+    }
+}
+
+/// 解析 `5s`、`500ms` 或不带单位的秒数
+fn parse_wait(wait: &str) -> Option<Duration> {
+    if let Some(millis) = wait.strip_suffix("ms") {
+        return millis.parse().ok().map(Duration::from_millis);
+    }
+    wait.strip_suffix('s')
+        .unwrap_or(wait)
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// 撤单查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CancelOrderQuery {
+    /// 下单用户ID，只能撤销自己的订单
+    pub user_id: String,
+}
+
+impl Validate for SetLogLevelRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        if self.level.trim().is_empty() {
+            return vec![FieldError::new("level", "must not be empty")];
+        }
+        Vec::new()
+    }
+}
+
+/// 快照内容由引擎在导入时校验
+impl Validate for OrderBookSnapshot {
+    fn validate(&self) -> Vec<FieldError> {
+        Vec::new()
+    }
+}
+
+impl Validate for BalanceChangeRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.asset.is_empty() {
+            errors.push(FieldError::new("asset", "must not be empty"));
+        }
+        if self.idempotency_key.is_empty() {
+            errors.push(FieldError::new("idempotency_key", "must not be empty"));
+        }
+        errors
+    }
+}
+
+impl Validate for TransferRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.to_user_id.is_empty() {
+            errors.push(FieldError::new("to_user_id", "must not be empty"));
+        }
+        if self.asset.is_empty() {
+            errors.push(FieldError::new("asset", "must not be empty"));
+        }
+        if self.idempotency_key.is_empty() {
+            errors.push(FieldError::new("idempotency_key", "must not be empty"));
+        }
+        errors
+    }
+}
+
+impl Validate for SandboxResetRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        if self.user_id.is_empty() {
+            return vec![FieldError::new("user_id", "must not be empty")];
+        }
+        Vec::new()
+    }
+}
+
+impl Validate for NewApiKey {
+    fn validate(&self) -> Vec<FieldError> {
+        if self.scopes.is_empty() {
+            return vec![FieldError::new("scopes", "must not be empty")];
+        }
+        Vec::new()
+    }
+}
+
+impl Validate for CreateOrderRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        let mut check = |field: &str, result: Result<(), String>| {
+            if let Err(message) = result {
+                errors.push(FieldError::new(field, &message));
+            }
+        };
+
+        match self.quote_quantity {
+            Some(quote_quantity) => check("quote_quantity", check_quantity(quote_quantity)),
+            None => check("quantity", check_quantity(self.quantity)),
+        }
+        if let Some(price) = self.price {
+            check("price", check_price(price));
+        }
+        if let Some(stop_price) = self.stop_price {
+            check("stop_price", check_price(stop_price));
+        }
+        if let Some(min_fill_qty) = self.min_fill_qty {
+            check("min_fill_qty", check_quantity(min_fill_qty));
+        }
+        match self.trailing_offset {
+            Some(TrailingOffset::Amount(amount)) => check("trailing_offset", check_price(amount)),
+            Some(TrailingOffset::Percent(percent)) if !percent.is_finite() => check(
+                "trailing_offset",
+                Err("must be a finite number".to_string()),
+            ),
+            _ => {}
+        }
+        if self.max_slippage_bps.is_some_and(|bps| !bps.is_finite()) {
+            check(
+                "max_slippage_bps",
+                Err("must be a finite number".to_string()),
+            );
+        }
+        if let Some(tag) = &self.tag {
+            check("tag", check_tag(tag));
+        }
+        if self.user_id.is_empty() {
+            check("user_id", Err("must not be empty".to_string()));
+        }
+
+        errors
+    }
+}
+
+impl Validate for ReportTradeRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        let mut check = |field: &str, result: Result<(), String>| {
+            if let Err(message) = result {
+                errors.push(FieldError::new(field, &message));
+            }
+        };
+
+        check("quantity", check_quantity(self.quantity));
+        check("price", check_price(self.price));
+        for (field, user_id) in [("buyer_id", &self.buyer_id), ("seller_id", &self.seller_id)] {
+            if user_id.is_empty() {
+                check(field, Err("must not be empty".to_string()));
+            }
+        }
+        if !self.buyer_id.is_empty() && self.buyer_id == self.seller_id {
+            check("seller_id", Err("must differ from buyer_id".to_string()));
+        }
+
+        errors
+    }
+}
+
+impl Validate for ListSymbolRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let filters = &self.filters;
+        [
+            ("filters.tick_size", filters.tick_size),
+            ("filters.min_quantity", filters.min_quantity),
+            ("filters.max_quantity", filters.max_quantity),
+            ("filters.min_notional", filters.min_notional),
+        ]
+        .into_iter()
+        .filter(|(_, value)| value.is_some_and(|value| !(value.is_finite() && value > 0.0)))
+        .map(|(field, _)| FieldError::new(field, "must be a positive number"))
+        .collect()
+    }
+}
+
+impl Validate for BustTradeRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.reason.trim().is_empty() {
+            errors.push(FieldError::new("reason", "must not be empty"));
+        }
+        errors
+    }
+}
+
+impl Validate for GetOrderQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        match self.wait.as_deref().map(parse_wait) {
+            Some(None) => vec![FieldError::new(
+                "wait",
+                "must be a duration such as 5s or 500ms",
+            )],
+            Some(Some(wait)) if wait > Duration::from_secs(MAX_ORDER_WAIT_SECONDS) => {
+                vec![FieldError::new(
+                    "wait",
+                    &format!("must not exceed {}s", MAX_ORDER_WAIT_SECONDS),
+                )]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Validate for OrderQueryRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        if self.order_ids.is_empty() || self.order_ids.len() > MAX_ORDER_QUERY_IDS {
+            return vec![FieldError::new(
+                "order_ids",
+                &format!("must contain 1 to {} ids", MAX_ORDER_QUERY_IDS),
+            )];
+        }
+        self.order_ids
+            .iter()
+            .enumerate()
+            .filter(|(_, order_id)| {
+                order_id.parse::<OrderId>().is_err() && Uuid::parse_str(order_id).is_err()
+            })
+            .map(|(index, _)| {
+                FieldError::new(&format!("order_ids[{}]", index), "must be a number or UUID")
+            })
+            .collect()
+    }
+}
+
+impl Validate for CancelOrderQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.user_id.trim().is_empty() {
+            errors.push(FieldError::new("user_id", "must not be empty"));
+        }
+        errors
+    }
+}
+
+/// 订单簿深度查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DepthQuery {
+    /// 每侧返回的价格档位数，不传返回全部档位
+    #[param(minimum = 1)]
+    pub depth: Option<usize>,
+}
+
+impl Validate for DepthQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.depth == Some(0) {
+            errors.push(FieldError::new("depth", "must be at least 1"));
+        }
+        errors
+    }
+}
+
+/// 未指定 `depth` 时计算失衡度使用的每侧档位数
+const DEFAULT_ANALYTICS_DEPTH: usize = 5;
+
+/// 盘口分析查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BookAnalyticsQuery {
+    /// 计算买卖失衡度的每侧档位数，默认 5
+    #[param(minimum = 1)]
+    pub depth: Option<usize>,
+}
+
+impl Validate for BookAnalyticsQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.depth == Some(0) {
+            errors.push(FieldError::new("depth", "must be at least 1"));
+        }
+        errors
+    }
+}
+
+/// 未指定 `from` 时成交量统计向前覆盖的小时数
+const DEFAULT_VOLUME_RANGE_HOURS: i64 = 24;
+
+/// 成交量统计查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VolumeQuery {
+    /// 统计周期：1m、5m、15m、1h、4h、1d，默认 1h
+    #[serde(default)]
+    pub interval: VolumeInterval,
+    /// 时间下限（含），RFC 3339 格式，默认 `to` 之前 24 小时
+    pub from: Option<DateTime<Utc>>,
+    /// 时间上限（不含），RFC 3339 格式，默认当前时间
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl Validate for VolumeQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from >= to {
+                errors.push(FieldError::new("to", "must be after from"));
+            }
+        }
+        errors
+    }
+}
+
+/// nonce 的最大长度
+const MAX_NONCE_LENGTH: usize = 128;
+
+/// 延迟探测查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PingQuery {
+    /// 客户端生成的随机串，原样返回，用于匹配请求与响应
+    #[param(max_length = 128)]
+    pub nonce: Option<String>,
+}
+
+impl Validate for PingQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self
+            .nonce
+            .as_ref()
+            .is_some_and(|nonce| nonce.len() > MAX_NONCE_LENGTH)
+        {
+            errors.push(FieldError::new(
+                "nonce",
+                &format!("must be at most {} bytes", MAX_NONCE_LENGTH),
+            ));
+        }
+        errors
+    }
+}
+
+/// 返回条数查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LimitQuery {
+    /// 最多返回的条数，不传返回全部
+    #[param(minimum = 1)]
+    pub limit: Option<usize>,
+}
+
+impl Validate for LimitQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.limit == Some(0) {
+            errors.push(FieldError::new("limit", "must be at least 1"));
+        }
+        errors
+    }
+}
+
+/// 用户订单查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OrderQuery {
+    /// 按订单状态过滤
+    pub status: Option<OrderStatus>,
+    /// 按交易对过滤，如 BTCUSDT
+    pub symbol: Option<String>,
+    /// 按买卖方向过滤
+    pub side: Option<OrderSide>,
+    /// 按订单自定义标签过滤
+    pub tag: Option<String>,
+    /// 下单时间下限（含），RFC 3339 格式
+    pub start_time: Option<DateTime<Utc>>,
+    /// 下单时间上限（不含），RFC 3339 格式
+    pub end_time: Option<DateTime<Utc>>,
+    /// 上一页返回的 `next_cursor`
+    pub cursor: Option<u64>,
+    /// 每页条数，默认 100
+    #[param(minimum = 1, maximum = 1000)]
+    pub limit: Option<usize>,
+}
+
+/// 未完成订单查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OpenOrdersQuery {
+    /// 用户ID
+    pub user_id: String,
+    /// 按交易对过滤，如 BTCUSDT
+    pub symbol: Option<String>,
+}
+
+impl OrderQuery {
+    fn filter(&self) -> OrderFilter {
+        OrderFilter {
+            status: self.status,
+            symbol: self.symbol.as_deref().and_then(|s| parse_symbol(s).ok()),
+            side: self.side,
+            tag: self.tag.clone(),
+            start_time: self.start_time,
+            end_time: self.end_time,
+        }
+    }
+
+    fn page(&self) -> PageRequest {
+        page_request(self.cursor, self.limit)
+    }
+}
+
+impl Validate for OpenOrdersQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.user_id.trim().is_empty() {
+            errors.push(FieldError::new("user_id", "must not be empty"));
+        }
+        validate_symbol(self.symbol.as_deref(), &mut errors);
+        errors
+    }
+}
+
+impl Validate for OrderQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        validate_symbol(self.symbol.as_deref(), &mut errors);
+        validate_page(self.start_time, self.end_time, self.limit, &mut errors);
+        errors
+    }
+}
+
+/// 成交查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TradeQuery {
+    /// 按交易对过滤，如 BTCUSDT；路径中带交易对时以路径为准
+    pub symbol: Option<String>,
+    /// 按主动方方向过滤
+    pub side: Option<OrderSide>,
+    /// 成交时间下限（含），RFC 3339 格式，也可写作 `from`
+    #[serde(alias = "from")]
+    pub start_time: Option<DateTime<Utc>>,
+    /// 成交时间上限（不含），RFC 3339 格式，也可写作 `to`
+    #[serde(alias = "to")]
+    pub end_time: Option<DateTime<Utc>>,
+    /// 上一页返回的 `next_cursor`
+    pub cursor: Option<u64>,
+    /// 每页条数，默认 100
+    #[param(minimum = 1, maximum = 1000)]
+    pub limit: Option<usize>,
+}
+
+impl TradeQuery {
+    fn filter(&self) -> TradeFilter {
+        TradeFilter {
+            symbol: self.symbol.as_deref().and_then(|s| parse_symbol(s).ok()),
+            side: self.side,
+            start_time: self.start_time,
+            end_time: self.end_time,
+        }
+    }
+
+    fn page(&self) -> PageRequest {
+        page_request(self.cursor, self.limit)
+    }
+}
+
+impl Validate for TradeQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        validate_symbol(self.symbol.as_deref(), &mut errors);
+        validate_page(self.start_time, self.end_time, self.limit, &mut errors);
+        errors
+    }
+}
+
+/// 用户成交查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FillQuery {
+    /// 按交易对过滤，如 BTCUSDT
+    pub symbol: Option<String>,
+    /// 按用户自己的买卖方向过滤
+    pub side: Option<OrderSide>,
+    /// 按流动性角色过滤
+    pub role: Option<LiquidityRole>,
+    /// 按成交所属订单的自定义标签过滤
+    pub tag: Option<String>,
+    /// 成交时间下限（含），RFC 3339 格式
+    pub start_time: Option<DateTime<Utc>>,
+    /// 成交时间上限（不含），RFC 3339 格式
+    pub end_time: Option<DateTime<Utc>>,
+    /// 上一页返回的 `next_cursor`
+    pub cursor: Option<u64>,
+    /// 每页条数，默认 100
+    #[param(minimum = 1, maximum = 1000)]
+    pub limit: Option<usize>,
+}
+
+impl FillQuery {
+    fn filter(&self) -> FillFilter {
+        FillFilter {
+            symbol: self.symbol.as_deref().and_then(|s| parse_symbol(s).ok()),
+            side: self.side,
+            role: self.role,
+            tag: self.tag.clone(),
+            start_time: self.start_time,
+            end_time: self.end_time,
+        }
+    }
+
+    fn page(&self) -> PageRequest {
+        page_request(self.cursor, self.limit)
+    }
+}
+
+impl Validate for FillQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        validate_symbol(self.symbol.as_deref(), &mut errors);
+        validate_page(self.start_time, self.end_time, self.limit, &mut errors);
+        errors
+    }
+}
+
+/// 成交和订单导出参数
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    /// 按交易对过滤，如 BTCUSDT
+    pub symbol: Option<String>,
+    /// 时间下限（含），RFC 3339 格式
+    pub from: Option<DateTime<Utc>>,
+    /// 时间上限（不含），RFC 3339 格式
+    pub to: Option<DateTime<Utc>>,
+    /// 文件格式，默认 csv；parquet 需服务以 `parquet` 特性编译
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+impl ExportQuery {
+    fn symbol(&self) -> Option<Symbol> {
+        self.symbol.as_deref().and_then(|s| parse_symbol(s).ok())
+    }
+}
+
+impl Validate for ExportQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        validate_symbol(self.symbol.as_deref(), &mut errors);
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from >= to {
+                errors.push(FieldError::new("to", "must be after from"));
+            }
+        }
+        if !self.format.is_supported() {
+            errors.push(FieldError::new(
+                "format",
+                "not supported by this server build",
+            ));
+        }
+        errors
+    }
+}
+
+fn page_request(cursor: Option<u64>, limit: Option<usize>) -> PageRequest {
+    PageRequest {
+        cursor,
+        limit: limit.unwrap_or(DEFAULT_PAGE_SIZE),
+    }
+}
+
+fn validate_symbol(symbol: Option<&str>, errors: &mut Vec<FieldError>) {
+    if symbol.is_some_and(|symbol| parse_symbol(symbol).is_err()) {
+        errors.push(FieldError::new("symbol", "unrecognized symbol"));
+    }
+}
+
+fn validate_page(
+    start_time: Option<DateTime<Utc>>,
+    end_time: Option<DateTime<Utc>>,
+    limit: Option<usize>,
+    errors: &mut Vec<FieldError>,
+) {
+    if let (Some(start), Some(end)) = (start_time, end_time) {
+        if start >= end {
+            errors.push(FieldError::new("end_time", "must be after start_time"));
+        }
+    }
+    if let Some(limit) = limit {
+        if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+            errors.push(FieldError {
+                field: "limit".to_string(),
+                message: format!("must be between 1 and {}", MAX_PAGE_SIZE),
+            });
+        }
+    }
+}
+
+/// 查询参数和请求体的语义校验，在反序列化成功后执行
+pub trait Validate {
+    /// 返回所有不合法的字段，为空表示校验通过
+    fn validate(&self) -> Vec<FieldError>;
+}
+
+/// 类型化的查询参数提取器
+///
+/// 参数缺失、类型错误或未通过 [`Validate`] 校验时返回 422，响应体列出出错的字段
+pub struct ValidQuery<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ValidQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        let deserializer =
+            serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes()));
+        let value: T = serde_path_to_error::deserialize(deserializer).map_err(|e| {
+            let field = e.path().to_string();
+            let message = e.into_inner().to_string();
+            // 缺少必填参数时错误位于顶层，字段名只出现在错误信息中
+            let field = match message
+                .strip_prefix("missing field `")
+                .and_then(|rest| rest.strip_suffix('`'))
+            {
+                Some(missing) => missing.to_string(),
+                None => field,
+            };
+            ApiError::validation(
+                "Invalid query parameters",
+                vec![FieldError { field, message }],
+            )
+        })?;
+
+        let errors = value.validate();
+        if !errors.is_empty() {
+            return Err(ApiError::validation("Invalid query parameters", errors));
+        }
+        Ok(ValidQuery(value))
+    }
+}
+
+/// 类型化的 JSON 请求体提取器
+///
+/// 请求体无法解析或未通过 [`Validate`] 校验时返回 422，响应体列出出错的字段
+pub struct ValidJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let body = Bytes::from_request(request, state)
+            .await
+            .map_err(|e| ApiError::new(e.status(), "invalid_request_body", e.body_text()))?;
+        let mut deserializer = serde_json::Deserializer::from_slice(&body);
+        let value: T = serde_path_to_error::deserialize(&mut deserializer).map_err(|e| {
+            let field = e.path().to_string();
+            ApiError::validation(
+                "Invalid request body",
+                vec![FieldError {
+                    field,
+                    message: e.into_inner().to_string(),
+                }],
+            )
+        })?;
+
+        let errors = value.validate();
+        if !errors.is_empty() {
+            return Err(ApiError::validation("Invalid request body", errors));
+        }
+        Ok(ValidJson(value))
+    }
+}
+
+/// 单个字段的校验错误
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// 服务器时间
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ServerTimeResponse {
+    pub server_time: DateTime<Utc>,
+    /// Unix 毫秒时间戳
+    pub server_time_ms: i64,
+    /// 引擎启动以来的单调时钟读数（纳秒），两次读数之差不受系统时间调整影响
+    pub monotonic_ns: u64,
+}
+
+/// 延迟探测响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PingResponse {
+    /// 客户端传入的 nonce
+    pub nonce: Option<String>,
+    /// 服务端收到请求的 Unix 毫秒时间戳
+    pub receive_time_ms: i64,
+    /// 服务端发出响应的 Unix 毫秒时间戳
+    pub send_time_ms: i64,
+}
+
+/// 错误响应
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ErrorResponse {
+    /// 机器可读的错误码，如 `order_rejected`、`validation_error`
+    pub code: String,
+    pub message: String,
+    /// 错误的补充信息，如校验失败时的 `{"fields": [...]}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub details: Option<Value>,
+    /// 请求ID，与响应头 `X-Request-Id` 相同
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// API 错误，响应为对应的 HTTP 状态码和 [`ErrorResponse`] 响应体
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+    pub details: Option<Value>,
+    /// 需要客户端稍后重试时的 `Retry-After` 秒数
+    pub retry_after: Option<u64>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+            details: None,
+            retry_after: None,
+        }
+    }
+
+    pub fn bad_request(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, code, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, "not_found", message)
+    }
+
+    /// 功能未启用等服务暂不可用的情况
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::SERVICE_UNAVAILABLE, "unavailable", message)
+    }
+
+    /// 内部错误，原因只写入日志，响应中不暴露细节
+    pub fn internal() -> Self {
+        Self::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            "Internal server error",
+        )
+    }
+
+    /// 查询参数或请求体校验失败，响应为 422 并列出出错的字段
+    pub fn validation(message: impl Into<String>, fields: Vec<FieldError>) -> Self {
+        Self::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "validation_error",
+            message,
+        )
+        .with_details(json!({ "fields": fields }))
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn with_retry_after(mut self, seconds: u64) -> Self {
+        self.retry_after = Some(seconds);
+        self
+    }
+}
+
+/// 只有状态码的错误使用状态码对应的通用错误码和说明，如认证失败的 401 和 403
+impl From<StatusCode> for ApiError {
+    fn from(status: StatusCode) -> Self {
+        let code = match status {
+            StatusCode::BAD_REQUEST => "bad_request",
+            StatusCode::UNAUTHORIZED => "unauthorized",
+            StatusCode::FORBIDDEN => "forbidden",
+            StatusCode::NOT_FOUND => "not_found",
+            StatusCode::CONFLICT => "conflict",
+            StatusCode::TOO_MANY_REQUESTS => "rate_limited",
+            StatusCode::SERVICE_UNAVAILABLE => "unavailable",
+            _ if status.is_server_error() => "internal_error",
+            _ => "error",
+        };
+        Self::new(status, code, status.canonical_reason().unwrap_or("Error"))
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = Json(ErrorResponse {
+            code: self.code.to_string(),
+            message: self.message,
+            details: self.details,
+            request_id: request_id::current(),
+        });
+        match self.retry_after {
+            Some(seconds) => (
+                self.status,
+                [(header::RETRY_AFTER, seconds.to_string())],
+                body,
+            )
+                .into_response(),
+            None => (self.status, body).into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EngineConfig;
+    use axum::body::Body;
+    use axum::http::{header, Request};
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_parse_symbol() {
+        let btc_usdt = Symbol::new("BTC", "USDT");
+        assert_eq!(parse_symbol("BTCUSDT").unwrap(), btc_usdt);
+        assert_eq!(parse_symbol("BTC-USDT").unwrap(), btc_usdt);
+        assert_eq!(parse_symbol("BTC/USDT").unwrap(), btc_usdt);
+        for (base, quote, symbol_str) in [
+            ("ETH", "USDT", "ETHUSDT"),
+            ("ETH", "BTC", "ethbtc"),
+            ("DOGE", "USDT", "DOGEUSDT"),
+            ("BTC", "USDC", "BTCUSDC"),
+        ] {
+            let symbol = Symbol::new(base, quote);
+            assert_eq!(parse_symbol(symbol_str).unwrap(), symbol);
+        }
+
+        // 未注册的交易对返回 400，且不会被注册
+        let error = parse_symbol("NEVERLISTEDUSDT").unwrap_err();
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+        assert_eq!(error.message, "Unknown symbol NEVERLISTEDUSDT");
+        assert!(crate::symbol::SymbolRegistry::global()
+            .lookup("NEVERLISTED", "USDT")
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_symbols_in_requests_are_rejected_without_registering() {
+        let router = open_router(Arc::new(MatchingEngine::new()));
+
+        let (status, body) =
+            json_response(router.clone(), "/orderbook/UNSEENASSETUSDT", Method::GET).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_symbol");
+
+        let order = json!({
+            "symbol": {"base": "UNSEENASSET", "quote": "ZZZ"},
+            "side": "buy",
+            "order_type": "limit",
+            "quantity": 1.0,
+            "price": 100.0,
+            "user_id": "user1",
+        });
+        let request = Request::post("/orders")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(order.to_string()))
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let registry = crate::symbol::SymbolRegistry::global();
+        assert!(registry.lookup("UNSEENASSET", "USDT").is_none());
+        assert!(registry.lookup("UNSEENASSET", "ZZZ").is_none());
+    }
+
+    #[test]
+    fn test_parse_symbol_uses_listed_quote_assets() {
+        let error = parse_symbol("PEPETRY").unwrap_err();
+        assert_eq!(error.code, "invalid_symbol");
+        assert!(error.message.contains("BASE-QUOTE"));
+
+        let engine = MatchingEngine::new();
+        for quote in ["TRY", "FDUSD"] {
+            engine
+                .list_symbol(ListSymbolRequest {
+                    symbol: Symbol::new("PEPE", quote),
+                    filters: Default::default(),
+                })
+                .unwrap();
+        }
+        assert_eq!(parse_symbol("PEPETRY").unwrap(), Symbol::new("PEPE", "TRY"));
+        // 按最长的计价货币后缀拆分，不会拆成 PEPEFD/USD
+        assert_eq!(
+            parse_symbol("PEPEFDUSD").unwrap(),
+            Symbol::new("PEPE", "FDUSD")
+        );
+    }
+
+    /// 未经服务器中间件的 API 路由，与未启用认证的服务器一样不限制按用户的接口
+    fn open_router(engine: Arc<MatchingEngine>) -> Router {
+        create_router(engine).layer(Extension(Caller::Unrestricted))
+    }
+
+    /// API 路由加上管理路由
+    fn api_and_admin_router(engine: Arc<MatchingEngine>) -> Router {
+        open_router(engine.clone()).merge(create_admin_router(engine, None))
+    }
+
+    fn layered_router(config: &ServerConfig) -> Router {
+        let router = create_router(Arc::new(MatchingEngine::new()));
+        apply_server_layers(router, config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_rejected() {
+        let config = ServerConfig {
+            max_request_size: 64,
+            ..ServerConfig::default()
+        };
+        let request = Request::post("/orders")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(vec![b' '; 65]))
+            .unwrap();
+
+        let response = layered_router(&config).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_cors_allows_only_configured_origins() {
+        let config = ServerConfig {
+            cors: CorsConfig {
+                allowed_origins: vec!["https://app.example.com".to_string()],
+                allow_credentials: false,
+                ..CorsConfig::default()
+            },
+            ..ServerConfig::default()
+        };
+        let preflight = |origin: &str| {
+            Request::options("/orders")
+                .header(header::ORIGIN, origin)
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let allowed = layered_router(&config)
+            .oneshot(preflight("https://app.example.com"))
+            .await
+            .unwrap();
+        assert_eq!(
+            allowed.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://app.example.com"
+        );
+
+        // 未配置的来源不返回允许头，浏览器据此拒绝跨域请求
+        let disallowed = layered_router(&config)
+            .oneshot(preflight("https://evil.example.com"))
+            .await
+            .unwrap();
+        assert!(!disallowed
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+
+        let invalid = CorsConfig {
+            allowed_methods: vec!["NOT A METHOD".to_string()],
+            ..CorsConfig::default()
+        };
+        assert!(cors_layer(&invalid).is_err());
+    }
+
+    async fn json_response(router: Router, uri: &str, method: Method) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_query_returns_field_errors() {
+        let router = || open_router(Arc::new(MatchingEngine::new()));
+
+        let (status, body) = json_response(router(), "/trades?limit=abc", Method::GET).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "validation_error");
+        assert_eq!(body["details"]["fields"][0]["field"], "limit");
+
+        let (status, body) =
+            json_response(router(), "/orderbook/BTCUSDT?depth=0", Method::GET).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["details"]["fields"][0]["field"], "depth");
+        assert_eq!(
+            body["details"]["fields"][0]["message"],
+            "must be at least 1"
+        );
+
+        let (status, body) = json_response(router(), "/orders/1", Method::DELETE).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["details"]["fields"][0]["field"], "user_id");
+
+        let (status, _) = json_response(router(), "/trades?limit=5", Method::GET).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_errors_use_common_envelope() {
+        let router = || open_router(Arc::new(MatchingEngine::new()));
+
+        let (status, body) =
+            json_response(router(), "/orders/42?user_id=alice", Method::DELETE).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "order_not_found");
+        assert_eq!(body["message"], "Order 42 not found");
+        assert!(body["details"].is_null());
+
+        let (status, body) = json_response(router(), "/orderbook/BTC$", Method::GET).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_symbol");
+    }
+
+    #[tokio::test]
+    async fn test_user_orders_and_trades_are_paginated() {
+        let engine = Arc::new(MatchingEngine::new());
+        let symbol = Symbol::new("BTC", "USDT");
+        for (side, user) in [
+            (OrderSide::Sell, "maker"),
+            (OrderSide::Sell, "maker"),
+            (OrderSide::Buy, "taker"),
+        ] {
+            let quantity = if side == OrderSide::Buy { 2.0 } else { 1.0 };
+            let order = Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                quantity,
+                Some(100.0),
+                user.to_string(),
+            );
+            engine.submit_order(order).await.unwrap();
+        }
+        let router = || open_router(Arc::clone(&engine));
+
+        let (status, page) =
+            json_response(router(), "/orders/user/maker?limit=1", Method::GET).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page["items"].as_array().unwrap().len(), 1);
+        let cursor = page["next_cursor"].as_u64().unwrap();
+
+        let uri = format!("/orders/user/maker?limit=1&cursor={}", cursor);
+        let (_, page) = json_response(router(), &uri, Method::GET).await;
+        assert_eq!(page["items"].as_array().unwrap().len(), 1);
+        assert!(page["items"][0]["id"].as_u64().unwrap() < cursor);
+        assert!(page["next_cursor"].is_null());
+
+        let (_, page) = json_response(router(), "/orders/user/maker?status=new", Method::GET).await;
+        assert!(page["items"].as_array().unwrap().is_empty());
+
+        let (_, page) = json_response(router(), "/trades/BTCUSDT?side=buy", Method::GET).await;
+        assert_eq!(page["items"].as_array().unwrap().len(), 2);
+        let (_, page) = json_response(router(), "/trades?side=sell", Method::GET).await;
+        assert!(page["items"].as_array().unwrap().is_empty());
+
+        let (status, body) = json_response(
+            router(),
+            "/trades?start_time=2024-02-01T00:00:00Z&end_time=2024-01-01T00:00:00Z&limit=5000",
+            Method::GET,
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let fields: Vec<&str> = body["details"]["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|field| field["field"].as_str().unwrap())
+            .collect();
+        assert_eq!(fields, ["end_time", "limit"]);
+    }
+
+    #[tokio::test]
+    async fn test_openapi_document_lists_routes() {
+        let app = Router::new().nest("/api/v1", open_router(Arc::new(MatchingEngine::new())));
+        let (status, doc) = json_response(app, "/api/v1/openapi.json", Method::GET).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(doc["servers"][0]["url"], "/api/v1");
+        assert!(doc["paths"]["/orders/{order_id}"]["delete"].is_object());
+        assert!(doc["paths"]["/accounts/{user_id}/ledger"]["get"].is_object());
+        assert!(doc["components"]["schemas"]["Order"].is_object());
+        assert!(doc["components"]["schemas"]["ErrorResponse"].is_object());
+    }
+
+    #[tokio::test]
+    async fn test_time_and_ping() {
+        let router = || open_router(Arc::new(MatchingEngine::new()));
+        let before = Utc::now().timestamp_millis();
+
+        let (status, time) = json_response(router(), "/time", Method::GET).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(time["server_time_ms"].as_i64().unwrap() >= before);
+        assert!(time["monotonic_ns"].is_u64());
+
+        let (status, pong) = json_response(router(), "/ping?nonce=abc123", Method::GET).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(pong["nonce"], "abc123");
+        assert!(pong["send_time_ms"].as_i64() >= pong["receive_time_ms"].as_i64());
+
+        let long_nonce = format!("/ping?nonce={}", "x".repeat(MAX_NONCE_LENGTH + 1));
+        let (status, body) = json_response(router(), &long_nonce, Method::GET).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["details"]["fields"][0]["field"], "nonce");
+    }
+
+    #[tokio::test]
+    async fn test_latency_stats() {
+        let engine = Arc::new(MatchingEngine::new());
+        let symbol = Symbol::new("BTC", "USDT");
+        for (side, user) in [(OrderSide::Sell, "seller"), (OrderSide::Buy, "buyer")] {
+            let order = Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                1.0,
+                Some(50000.0),
+                user.to_string(),
+            );
+            engine.submit_order(order).await.unwrap();
+        }
+
+        let (status, latency) =
+            json_response(open_router(engine), "/stats/latency", Method::GET).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(latency["overall"]["submit_to_ack"]["count"], 2);
+        assert_eq!(latency["overall"]["submit_to_first_fill"]["count"], 1);
+        assert!(
+            latency["overall"]["submit_to_ack"]["p999_us"]
+                .as_f64()
+                .unwrap()
+                > 0.0
+        );
+        assert!(latency["symbols"].as_object().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_order_test_endpoint_does_not_mutate() {
+        let engine = Arc::new(MatchingEngine::new());
+        let symbol = Symbol::new("BTC", "USDT");
+        for (quantity, price) in [(1.0, 100.0), (1.0, 102.0)] {
+            let order = Order::new(
+                symbol,
+                OrderSide::Sell,
+                OrderType::Limit,
+                quantity,
+                Some(price),
+                "maker".to_string(),
+            );
+            engine.submit_order(order).await.unwrap();
+        }
+
+        let body = json!({
+            "symbol": {"base": "BTC", "quote": "USDT"},
+            "side": "buy",
+            "order_type": "limit",
+            "quantity": 3.0,
+            "price": 101.0,
+            "user_id": "taker"
+        });
+        let request = Request::post("/orders/test")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = open_router(engine.clone()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let result: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(result["filled_quantity"], 1.0);
+        assert_eq!(result["average_price"], 100.0);
+        assert_eq!(result["resting_quantity"], 2.0);
+
+        assert!(engine.get_user_orders("taker").is_empty());
+        assert_eq!(
+            engine
+                .get_orderbook_depth(&symbol, None)
+                .unwrap()
+                .asks
+                .len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_report_trade_endpoint() {
+        let report = |engine: Arc<MatchingEngine>, seller_id: &'static str| async move {
+            let body = json!({
+                "symbol": {"base": "BTC", "quote": "USDT"},
+                "buyer_id": "alice",
+                "seller_id": seller_id,
+                "quantity": 1.0,
+                "price": 100.0
+            });
+            let request = Request::post("/admin/trades/report")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            let response = create_admin_router(engine, None)
+                .oneshot(request)
+                .await
+                .unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            (
+                status,
+                serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null),
+            )
+        };
+
+        let (status, _) = report(Arc::new(MatchingEngine::new()), "bob").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let mut config = EngineConfig::default();
+        config.otc.enabled = true;
+        let engine = Arc::new(MatchingEngine::with_config(config));
+        let (status, _) = report(engine.clone(), "alice").await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        engine
+            .update_mark_price(MarkPrice {
+                symbol: Symbol::new("BTC", "USDT"),
+                index_price: 100.0,
+                mark_price: 100.0,
+                components: Vec::new(),
+                timestamp: Utc::now(),
+            })
+            .await;
+        let (status, body) = report(engine, "bob").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["sequence"], 1);
+        assert_eq!(body["trade"]["trade_type"], "otc");
+    }
+
+    #[tokio::test]
+    async fn test_report_trade_requires_admin_token() {
+        let dir = std::env::temp_dir().join(format!("report-admin-{}", Uuid::new_v4()));
+        let config = crate::config::AdminConfig {
+            tokens: vec![crate::config::AdminToken {
+                actor: "ops".to_string(),
+                token: "admin-secret".to_string(),
+            }],
+            ..Default::default()
+        };
+        let mut engine_config = EngineConfig::default();
+        engine_config.otc.enabled = true;
+        let engine = Arc::new(MatchingEngine::with_config(engine_config));
+        engine
+            .update_mark_price(MarkPrice {
+                symbol: Symbol::new("BTC", "USDT"),
+                index_price: 100.0,
+                mark_price: 100.0,
+                components: Vec::new(),
+                timestamp: Utc::now(),
+            })
+            .await;
+        let admin = crate::admin::protect_admin_router(
+            create_admin_router(engine.clone(), None),
+            &config,
+            &dir,
+        )
+        .unwrap();
+        let app = open_router(engine.clone()).merge(admin);
+        let report = |uri: &str, token: Option<&str>| {
+            let mut request = Request::post(uri).header(header::CONTENT_TYPE, "application/json");
+            if let Some(token) = token {
+                request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+            }
+            let body = json!({
+                "symbol": {"base": "BTC", "quote": "USDT"},
+                "buyer_id": "alice",
+                "seller_id": "bob",
+                "quantity": 1.0,
+                "price": 100.0
+            });
+            app.clone()
+                .oneshot(request.body(Body::from(body.to_string())).unwrap())
+        };
+
+        // 公开路由上没有申报接口，管理路由要求管理令牌，被拒绝的申报不产生成交
+        let response = report("/trades/report", None).await.unwrap();
+        assert!(response.status().is_client_error());
+        let response = report("/admin/trades/report", None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(engine.get_trades(None, None).is_empty());
+
+        let response = report("/admin/trades/report", Some("admin-secret"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(engine.get_trades(None, None).len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_symbol_listing_endpoints() {
+        let router = api_and_admin_router(Arc::new(MatchingEngine::new()));
+        let send = |request: Request<Body>| {
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (
+                    status,
+                    serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null),
+                )
+            }
+        };
+        let list = |filters: Value| {
+            Request::post("/admin/symbols")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(
+                    json!({"symbol": {"base": "SOL", "quote": "USDT"}, "filters": filters})
+                        .to_string(),
+                ))
+                .unwrap()
+        };
+
+        let (status, _) = send(list(json!({"min_quantity": -1.0}))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let (status, body) = send(list(json!({"tick_size": 0.01}))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "listed");
+        let (status, body) = send(list(json!({}))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "listing_rejected");
+
+        let delist = || {
+            Request::post("/admin/symbols/SOLUSDT/delist")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let (status, body) = send(delist()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "delisted");
+        let (status, _) = send(delist()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (_, body) = send(Request::get("/symbols").body(Body::empty()).unwrap()).await;
+        assert!(body
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|listing| listing["symbol"]["base"] == "SOL" && listing["status"] == "delisted"));
+    }
+
+    #[tokio::test]
+    async fn test_settlement_report_endpoints() {
+        let dir = std::env::temp_dir().join(format!("settlement-api-{}", uuid::Uuid::new_v4()));
+        let mut config = EngineConfig::default();
+        config.end_of_day.enabled = true;
+        config.end_of_day.report_dir = dir.to_string_lossy().into_owned();
+        let engine = Arc::new(MatchingEngine::with_config(config));
+        let symbol = Symbol::new("BTC", "USDT");
+        for (side, user) in [(OrderSide::Sell, "bob"), (OrderSide::Buy, "alice")] {
+            engine
+                .submit_order(Order::new(
+                    symbol,
+                    side,
+                    OrderType::Limit,
+                    1.0,
+                    Some(100.0),
+                    user.to_string(),
+                ))
+                .await
+                .unwrap();
+        }
+        let cutoff = Utc::now() + chrono::Duration::minutes(1);
+        let trading_day = engine.run_end_of_day(cutoff).unwrap().trading_day;
+
+        let router = api_and_admin_router(engine);
+        let get = |uri: String| {
+            let router = router.clone();
+            async move {
+                let response = router
+                    .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (status, String::from_utf8(body.to_vec()).unwrap())
+            }
+        };
+
+        let (status, body) = get("/admin/settlements".to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, format!("[\"{}\"]", trading_day));
+        let (status, body) = get(format!("/admin/settlements/{}", trading_day)).await;
+        assert_eq!(status, StatusCode::OK);
+        let report: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(report["venue"]["total_trades"], 1);
+        let (status, body) = get(format!("/admin/settlements/{}/symbols.csv", trading_day)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains(&format!("{},BTCUSDT,1,1,100,", trading_day)));
+        let (status, body) = get(format!("/settlements/{}/users/alice", trading_day)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["trades"], 1);
+
+        let (status, _) = get(format!("/admin/settlements/{}/orders.csv", trading_day)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = get(format!("/settlements/{}/users/carol", trading_day)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = get("/admin/settlements/2000-01-01".to_string()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_export_endpoints() {
+        let engine = Arc::new(MatchingEngine::new());
+        let start = Utc::now();
+        for (side, user, price) in [
+            (OrderSide::Sell, "bob", 100.0),
+            (OrderSide::Buy, "alice", 100.0),
+            (OrderSide::Buy, "alice", 90.0),
+        ] {
+            engine
+                .submit_order(Order::new(
+                    Symbol::new("BTC", "USDT"),
+                    side,
+                    OrderType::Limit,
+                    1.0,
+                    Some(price),
+                    user.to_string(),
+                ))
+                .await
+                .unwrap();
+        }
+
+        let router = api_and_admin_router(engine);
+        let get = |uri: String| {
+            let router = router.clone();
+            async move {
+                let response = router
+                    .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let disposition = response
+                    .headers()
+                    .get(header::CONTENT_DISPOSITION)
+                    .map(|value| value.to_str().unwrap().to_string());
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (
+                    status,
+                    disposition,
+                    String::from_utf8_lossy(&body).into_owned(),
+                )
+            }
+        };
+
+        let from =
+            form_urlencoded::byte_serialize(start.to_rfc3339().as_bytes()).collect::<String>();
+        let (status, disposition, body) =
+            get(format!("/admin/export/trades?symbol=BTCUSDT&from={}", from)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            disposition.as_deref(),
+            Some("attachment; filename=\"trades.csv\"")
+        );
+        assert_eq!(body.lines().count(), 2);
+        assert!(body
+            .lines()
+            .nth(1)
+            .unwrap()
+            .contains(",BTCUSDT,regular,100,1,100,buy,"));
+
+        // 订单按ID从旧到新：已成交的卖单、买单和挂单中的买单
+        let (status, _, body) = get("/admin/export/orders?format=csv".to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        let statuses: Vec<&str> = body
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').nth(7).unwrap())
+            .collect();
+        assert_eq!(statuses, ["filled", "filled", "new"]);
+
+        let (status, _, _) = get("/admin/export/trades?symbol=ETHUSDT".to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _, _) = get("/admin/export/trades?format=xlsx".to_string()).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let (status, _, _) = get(format!("/admin/export/orders?from={}&to={}", from, from)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let (status, _, _) = get("/admin/export/trades?format=parquet".to_string()).await;
+        let expected = if cfg!(feature = "parquet") {
+            StatusCode::OK
+        } else {
+            StatusCode::UNPROCESSABLE_ENTITY
+        };
+        assert_eq!(status, expected);
+
+        // 用户导出只包含自己的成交和订单，不含对手方
+        let (status, disposition, body) = get("/export/fills/alice".to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            disposition.as_deref(),
+            Some("attachment; filename=\"fills.csv\"")
+        );
+        assert_eq!(body.lines().count(), 2);
+        assert!(body
+            .lines()
+            .nth(1)
+            .unwrap()
+            .contains(",BTCUSDT,buy,taker,100,1,100,"));
+        assert!(!body.contains("bob"));
+        let (status, _, body) = get("/export/orders/alice".to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.lines().count(), 3);
+        assert!(!body.contains("bob"));
+        let (status, _, _) = get("/export/trades".to_string()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_exports_require_admin_or_own_user() {
+        let engine = Arc::new(MatchingEngine::new());
+        let dir = std::env::temp_dir().join(format!("export-admin-{}", Uuid::new_v4()));
+        let admin = crate::admin::protect_admin_router(
+            create_admin_router(engine.clone(), None),
+            &crate::config::AdminConfig {
+                tokens: vec![crate::config::AdminToken {
+                    actor: "ops".to_string(),
+                    token: "admin-secret".to_string(),
+                }],
+                ..Default::default()
+            },
+            &dir,
+        )
+        .unwrap();
+        let router =
+            apply_server_layers(create_router(engine).merge(admin), &auth_server_config()).unwrap();
+        let alice = jwt("alice");
+        let get = |uri: &str, token: Option<&str>| {
+            let mut request = Request::get(uri);
+            if let Some(token) = token {
+                request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+            }
+            router.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        let response = get("/export/fills/alice", None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = get("/export/fills/alice", Some(&alice)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = get("/export/orders/bob", Some(&alice)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // 全量导出只接受管理令牌，用户令牌不能调用
+        let response = get("/admin/export/trades", Some(&alice)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = get("/admin/export/trades", Some("admin-secret"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_volume_analytics_endpoint() {
+        let start = "2024-01-05T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let clock = Arc::new(crate::clock::MockClock::new(start));
+        let engine = Arc::new(MatchingEngine::with_clock(
+            EngineConfig::default(),
+            clock.clone(),
+        ));
+        let btc = Symbol::new("BTC", "USDT");
+        let submit = |side, price, user: &str| {
+            engine.submit_order(Order::new(
+                btc,
+                side,
+                OrderType::Limit,
+                1.0,
+                Some(price),
+                user.to_string(),
+            ))
+        };
+        submit(OrderSide::Sell, 100.0, "bob").await.unwrap();
+        submit(OrderSide::Buy, 100.0, "alice").await.unwrap();
+        clock.advance(Duration::from_secs(3630));
+        submit(OrderSide::Buy, 200.0, "alice").await.unwrap();
+        submit(OrderSide::Sell, 200.0, "bob").await.unwrap();
+        submit(OrderSide::Buy, 300.0, "alice").await.unwrap();
+        submit(OrderSide::Sell, 300.0, "bob").await.unwrap();
+
+        let router = open_router(engine);
+        let get = |uri: &str| {
+            router
+                .clone()
+                .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        };
+
+        let response = get("/analytics/BTCUSDT/volume?interval=1h").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let series: VolumeSeries = serde_json::from_slice(
+            &axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(series.interval, VolumeInterval::OneHour);
+        assert_eq!(series.buckets.len(), 2);
+        assert_eq!(series.buckets[0].open_time, start);
+        assert_eq!(series.buckets[0].taker_buy_volume, 1.0);
+        let last = &series.buckets[1];
+        assert_eq!(last.trade_count, 2);
+        assert_eq!(last.volume, 2.0);
+        assert_eq!(last.vwap, 250.0);
+        assert_eq!(last.taker_sell_volume, 2.0);
+
+        let response = get("/analytics/BTCUSDT/volume?interval=1d&from=2024-01-05T10:30:00Z")
+            .await
+            .unwrap();
+        let series: VolumeSeries = serde_json::from_slice(
+            &axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(series.buckets.len(), 1);
+        assert_eq!(series.buckets[0].trade_count, 2);
+
+        let response = get("/analytics/ETHUSDT/volume").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = get("/analytics/BTCUSDT/volume?interval=2h").await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let response =
+            get("/analytics/BTCUSDT/volume?from=2024-01-05T10:00:00Z&to=2024-01-05T10:00:00Z")
+                .await
+                .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_public_trades_omit_user_and_order_ids() {
+        let engine = Arc::new(MatchingEngine::new());
+        for (side, user) in [(OrderSide::Sell, "bob"), (OrderSide::Buy, "alice")] {
+            engine
+                .submit_order(Order::new(
+                    Symbol::new("BTC", "USDT"),
+                    side,
+                    OrderType::Limit,
+                    1.0,
+                    Some(100.0),
+                    user.to_string(),
+                ))
+                .await
+                .unwrap();
+        }
+
+        let router = open_router(engine);
+        for uri in ["/trades", "/trades/BTCUSDT"] {
+            let response = router
+                .clone()
+                .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let page: Value = serde_json::from_slice(
+                &axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap(),
+            )
+            .unwrap();
+            let trade = page["items"][0].as_object().unwrap();
+            assert_eq!(trade["price"], 100.0);
+            assert_eq!(trade["taker_side"], "buy");
+            for field in ["buyer_id", "seller_id", "buy_order_id", "sell_order_id"] {
+                assert!(!trade.contains_key(field), "{} leaked in {}", field, uri);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_order_includes_fills() {
+        let engine = Arc::new(MatchingEngine::new());
+        let btc = Symbol::new("BTC", "USDT");
+        let maker = Order::new(
+            btc,
+            OrderSide::Sell,
+            OrderType::Limit,
+            2.0,
+            Some(100.0),
+            "maker".to_string(),
+        );
+        let maker_id = maker.id;
+        engine.submit_order(maker).await.unwrap();
+        let mut taker_id = 0;
+        for _ in 0..2 {
+            let taker = Order::new(
+                btc,
+                OrderSide::Buy,
+                OrderType::Limit,
+                1.0,
+                Some(100.0),
+                "taker".to_string(),
+            );
+            taker_id = taker.id;
+            engine.submit_order(taker).await.unwrap();
+        }
+
+        let router = open_router(engine);
+        let get = |order_id: OrderId| {
+            router.clone().oneshot(
+                Request::get(format!("/orders/{}", order_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        let response = get(maker_id).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let detail: OrderDetail = serde_json::from_slice(
+            &axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(detail.order.status, OrderStatus::Filled);
+        assert_eq!(detail.fills.len(), 2);
+        assert!(detail.fills[0].trade_id < detail.fills[1].trade_id);
+        assert!(detail
+            .fills
+            .iter()
+            .all(|fill| fill.role == LiquidityRole::Maker && fill.quantity == 1.0));
+
+        let response = get(taker_id).await.unwrap();
+        let detail: Value = serde_json::from_slice(
+            &axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(detail["id"], taker_id);
+        assert_eq!(detail["fills"][0]["role"], "taker");
+        assert_eq!(detail["fills"][0]["price"], 100.0);
+    }
+
+    #[tokio::test]
+    async fn test_get_order_long_polls_until_filled() {
+        let engine = Arc::new(MatchingEngine::new());
+        let btc = Symbol::new("BTC", "USDT");
+        let maker = Order::new(
+            btc,
+            OrderSide::Sell,
+            OrderType::Limit,
+            2.0,
+            Some(100.0),
+            "maker".to_string(),
+        );
+        let maker_id = maker.id;
+        engine.submit_order(maker).await.unwrap();
+        let router = open_router(engine.clone());
+
+        let (status, body) = json_response(
+            router.clone(),
+            &format!("/orders/{}?wait=100ms", maker_id),
+            Method::GET,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "new");
+
+        let started = std::time::Instant::now();
+        let uri = format!("/orders/{}?wait=5s", maker_id);
+        let poll = tokio::spawn({
+            let router = router.clone();
+            async move { json_response(router, &uri, Method::GET).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let taker = Order::new(
+            btc,
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            "taker".to_string(),
+        );
+        engine.submit_order(taker).await.unwrap();
+
+        let (status, body) = poll.await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "partiallyfilled");
+        assert_eq!(body["filled_quantity"], 1.0);
+        assert_eq!(body["fills"].as_array().unwrap().len(), 1);
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        for wait in ["soon", "60s"] {
+            let (status, body) = json_response(
+                router.clone(),
+                &format!("/orders/{}?wait={}", maker_id, wait),
+                Method::GET,
+            )
+            .await;
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(body["details"]["fields"][0]["field"], "wait");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_open_orders_follow_fills_and_cancels() {
+        let engine = Arc::new(MatchingEngine::new());
+        let btc = Symbol::new("BTC", "USDT");
+        let eth = Symbol::new("ETH", "USDT");
+        let limit = |symbol, side, quantity, price, user_id: &str| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                quantity,
+                Some(price),
+                user_id.to_string(),
+            )
+        };
+        let partial = limit(btc, OrderSide::Buy, 2.0, 99.0, "alice");
+        let cancelled = limit(btc, OrderSide::Buy, 1.0, 98.0, "alice");
+        let other_symbol = limit(eth, OrderSide::Buy, 1.0, 10.0, "alice");
+        let ids = [partial.id, cancelled.id, other_symbol.id];
+        for order in [partial, cancelled, other_symbol] {
+            engine.submit_order(order).await.unwrap();
+        }
+        engine
+            .submit_order(limit(btc, OrderSide::Sell, 1.0, 99.0, "bob"))
+            .await
+            .unwrap();
+        engine
+            .cancel_order(ids[1], "alice".to_string())
+            .await
+            .unwrap();
+        let router = || open_router(engine.clone());
+
+        let (status, body) =
+            json_response(router(), "/openOrders?user_id=alice", Method::GET).await;
+        assert_eq!(status, StatusCode::OK);
+        let orders: Vec<Order> = serde_json::from_value(body).unwrap();
+        let open: Vec<(OrderId, OrderStatus)> = orders
+            .iter()
+            .map(|order| (order.id, order.status))
+            .collect();
+        assert_eq!(
+            open,
+            [
+                (ids[0], OrderStatus::PartiallyFilled),
+                (ids[2], OrderStatus::New)
+            ]
+        );
+
+        let (_, body) = json_response(
+            router(),
+            "/openOrders?user_id=alice&symbol=ETH-USDT",
+            Method::GET,
+        )
+        .await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["id"], ids[2]);
+        let (_, body) = json_response(router(), "/openOrders?user_id=bob", Method::GET).await;
+        assert_eq!(body, json!([]));
+        let (status, _) = json_response(router(), "/openOrders?user_id=", Method::GET).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_query_orders_in_bulk() {
+        let engine = Arc::new(MatchingEngine::new());
+        let btc = Symbol::new("BTC", "USDT");
+        let mut order_ids = Vec::new();
+        let external_id = Uuid::new_v4();
+        for price in [99.0, 98.0] {
+            let mut order = Order::new(
+                btc,
+                OrderSide::Buy,
+                OrderType::Limit,
+                1.0,
+                Some(price),
+                "alice".to_string(),
+            );
+            if price == 98.0 {
+                order.external_id = Some(external_id);
+            }
+            order_ids.push(order.id);
+            engine.submit_order(order).await.unwrap();
+        }
+        let router = open_router(engine);
+        let query = |body: Value| {
+            router.clone().oneshot(
+                Request::post("/orders/query")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+        };
+
+        let unknown_uuid = Uuid::new_v4().to_string();
+        let response = query(json!({"order_ids": [
+            order_ids[0].to_string(), "1", external_id.to_string(), unknown_uuid,
+        ]}))
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: OrderQueryResponse = serde_json::from_slice(
+            &axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        let found: Vec<OrderId> = body.orders.iter().map(|order| order.id).collect();
+        assert_eq!(found, order_ids);
+        assert_eq!(body.not_found, vec!["1".to_string(), unknown_uuid]);
+
+        let response = query(json!({"order_ids": ["1", "not-an-id"]}))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let response = query(json!({"order_ids": []})).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    /// 启用认证的服务器配置，API key 写入临时文件
+    fn auth_server_config() -> ServerConfig {
+        let mut config = ServerConfig::default();
+        config.auth.enabled = true;
+        config.auth.jwt_secret = "secret".to_string();
+        config.auth.api_keys_path = std::env::temp_dir()
+            .join(format!("api-keys-{}.json", Uuid::new_v4()))
+            .to_string_lossy()
+            .into_owned();
+        config
+    }
+
+    fn jwt(user_id: &str) -> String {
+        use crate::auth::Claims;
+        use jsonwebtoken::{encode, EncodingKey, Header};
+
+        encode(
+            &Header::default(),
+            &Claims {
+                sub: user_id.to_string(),
+                exp: Utc::now().timestamp() as u64 + 60,
+            },
+            &EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_auth_restricts_user_endpoints_to_caller() {
+        let engine = Arc::new(MatchingEngine::new());
+        let order = Order::new(
+            Symbol::new("BTC", "USDT"),
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            "alice".to_string(),
+        );
+        let order_id = order.id;
+        engine.submit_order(order).await.unwrap();
+
+        let router = apply_server_layers(create_router(engine), &auth_server_config()).unwrap();
+        let alice = jwt("alice");
+        let call = |method: Method, uri: String, token: Option<&str>| {
+            let mut request = Request::builder().method(method).uri(uri);
+            if let Some(token) = token {
+                request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+            }
+            router.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        // 公开接口不需要令牌
+        let response = call(Method::GET, "/orderbook/BTCUSDT".to_string(), None)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let orders = "/orders/user/alice".to_string();
+        let response = call(Method::GET, orders.clone(), None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = call(Method::GET, orders, Some("forged")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = call(Method::GET, "/orders/user/alice".to_string(), Some(&alice))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = call(
+            Method::GET,
+            "/accounts/bob/balances".to_string(),
+            Some(&alice),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // 撤单的用户ID必须是调用方
+        let cancel = |user_id: &str| format!("/orders/{}?user_id={}", order_id, user_id);
+        let response = call(Method::DELETE, cancel("bob"), Some(&alice))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = call(Method::DELETE, cancel("alice"), Some(&alice))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = call(Method::GET, format!("/orders/{}", order_id), Some(&alice))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = call(Method::GET, format!("/orders/{}", order_id), None)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_api_keys_are_scoped_and_revocable() {
+        use axum::extract::ConnectInfo;
+        use std::net::SocketAddr;
+
+        let config = auth_server_config();
+        let router =
+            apply_server_layers(create_router(Arc::new(MatchingEngine::new())), &config).unwrap();
+        let alice = jwt("alice");
+        let call = |method: Method, uri: &str, auth: (&str, &str), body: Value| {
+            let mut request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(auth.0, auth.1)
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo("10.0.0.1:5000".parse::<SocketAddr>().unwrap()));
+            router.clone().oneshot(request)
+        };
+        let bearer = format!("Bearer {}", alice);
+        let session = (header::AUTHORIZATION.as_str(), bearer.as_str());
+
+        let response = call(
+            Method::POST,
+            "/account/api-keys",
+            session,
+            json!({"scopes": []}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let response = call(
+            Method::POST,
+            "/account/api-keys",
+            session,
+            json!({"label": "bot", "scopes": ["read"], "ip_allowlist": ["10.0.0.1"]}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let created: CreatedApiKey = serde_json::from_slice(
+            &axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        let key = (
+            config.auth.api_key_header.as_str(),
+            created.api_key.as_str(),
+        );
+
+        let response = call(Method::GET, "/orders/user/alice", key, Value::Null)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        // 只读 key 不能下单，也不能管理 key
+        let order = json!({
+            "symbol": {"base": "BTC", "quote": "USDT"},
+            "side": "buy",
+            "order_type": "limit",
+            "quantity": 1.0,
+            "price": 100.0,
+            "user_id": "alice"
+        });
+        let response = call(Method::POST, "/orders", key, order).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = call(Method::GET, "/account/api-keys", key, Value::Null)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = call(Method::GET, "/account/api-keys", session, Value::Null)
+            .await
+            .unwrap();
+        let keys: Vec<ApiKey> = serde_json::from_slice(
+            &axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(keys.len(), 1);
+        assert!(keys[0].last_used_at.is_some());
+
+        let uri = format!("/account/api-keys/{}", created.key.id);
+        let response = call(Method::DELETE, &uri, session, Value::Null)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let response = call(Method::GET, "/orders/user/alice", key, Value::Null)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        std::fs::remove_file(&config.auth.api_keys_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tickers_endpoint() {
+        let engine = Arc::new(MatchingEngine::new());
+        let eth = Symbol::new("ETH", "USDT");
+        for (symbol, side, price) in [
+            (Symbol::new("BTC", "USDT"), OrderSide::Sell, 100.0),
+            (Symbol::new("BTC", "USDT"), OrderSide::Buy, 100.0),
+            (Symbol::new("BTC", "USDT"), OrderSide::Buy, 99.0),
+            (eth, OrderSide::Sell, 10.0),
+            (eth, OrderSide::Buy, 10.0),
+            (Symbol::new("SOL", "USDT"), OrderSide::Sell, 5.0),
+            (Symbol::new("SOL", "USDT"), OrderSide::Buy, 5.0),
+        ] {
+            engine
+                .submit_order(Order::new(
+                    symbol,
+                    side,
+                    OrderType::Limit,
+                    2.0,
+                    Some(price),
+                    "alice".to_string(),
+                ))
+                .await
+                .unwrap();
+        }
+        engine.set_trading_state(&Symbol::new("SOL", "USDT"), TradingState::Halted, None);
+
+        let response = open_router(engine)
+            .oneshot(Request::get("/ticker/24hr").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let tickers: Vec<Ticker> = serde_json::from_slice(
+            &axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        let symbols: Vec<String> = tickers
+            .iter()
+            .map(|ticker| ticker.symbol.to_string())
+            .collect();
+        assert_eq!(symbols, ["BTCUSDT", "ETHUSDT"]);
+        assert_eq!(tickers[0].last_price, 100.0);
+        assert_eq!(tickers[0].volume, 2.0);
+        assert_eq!(tickers[0].quote_volume, 200.0);
+        assert_eq!(tickers[0].trade_count, 1);
+        assert_eq!(tickers[0].bid_price, Some(99.0));
+        assert_eq!(tickers[0].ask_price, None);
+        assert_eq!(tickers[1].trade_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_funding_history_endpoint() {
+        let response = open_router(Arc::new(MatchingEngine::new()))
+            .oneshot(
+                Request::get("/funding/BTCUSDT")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let mut config = EngineConfig::default();
+        config.margin.enabled = true;
+        config.funding.enabled = true;
+        let engine = Arc::new(MatchingEngine::with_config(config));
+        engine
+            .update_mark_price(MarkPrice {
+                symbol: Symbol::new("BTC", "USDT"),
+                index_price: 100.0,
+                mark_price: 100.0,
+                components: Vec::new(),
+                timestamp: Utc::now(),
+            })
+            .await;
+        engine.settle_funding(Utc::now());
+
+        let (status, body) = json_response(
+            open_router(engine),
+            "/funding/BTCUSDT?limit=10",
+            Method::GET,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["funding_rate"], 0.0001);
+        assert_eq!(body[0]["positions"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_positions_and_open_interest() {
+        let engine = Arc::new(MatchingEngine::new());
+        let symbol = Symbol::new("BTC", "USDT");
+        for (side, price, user) in [
+            (OrderSide::Sell, 100.0, "bob"),
+            (OrderSide::Buy, 100.0, "alice"),
+            (OrderSide::Sell, 110.0, "bob"),
+            (OrderSide::Buy, 110.0, "carol"),
+        ] {
+            let order = Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                1.0,
+                Some(price),
+                user.to_string(),
+            );
+            engine.submit_order(order).await.unwrap();
+        }
+
+        let (status, body) =
+            json_response(open_router(engine.clone()), "/positions/alice", Method::GET).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body[0]["quantity"], 1.0);
+        assert_eq!(body[0]["entry_price"], 100.0);
+        assert_eq!(body[0]["mark_price"], 110.0);
+        assert_eq!(body[0]["unrealized_pnl"], 10.0);
+
+        let (_, body) =
+            json_response(open_router(engine.clone()), "/positions/bob", Method::GET).await;
+        assert_eq!(body[0]["quantity"], -2.0);
+        assert_eq!(body[0]["entry_price"], 105.0);
+
+        // 没有经过服务器中间件的路由不信任请求中的用户
+        let (status, _) =
+            json_response(create_router(engine.clone()), "/positions/bob", Method::GET).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, body) =
+            json_response(open_router(engine), "/open-interest/BTCUSDT", Method::GET).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["open_interest"], 2.0);
+    }
+
+    #[tokio::test]
+    async fn test_margin_endpoints() {
+        let response = open_router(Arc::new(MatchingEngine::new()))
+            .oneshot(Request::get("/margin/alice").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let mut config = EngineConfig::default();
+        config.margin.enabled = true;
+        let engine = Arc::new(MatchingEngine::with_config(config));
+        engine
+            .accounts()
+            .deposit("alice", "USDT", 500.0, "dep-001")
+            .unwrap();
+        let (status, body) =
+            json_response(open_router(engine.clone()), "/margin/alice", Method::GET).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["equity"], 500.0);
+        assert_eq!(body["liquidation"], false);
+
+        let (status, body) = json_response(
+            api_and_admin_router(engine.clone()),
+            "/admin/margin/liquidations",
+            Method::GET,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.as_array().unwrap().is_empty());
+
+        // 未启用强平时强平记录返回 404
+        let response = api_and_admin_router(engine)
+            .oneshot(
+                Request::get("/admin/liquidations")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_deposit_requires_admin_token() {
+        let dir = std::env::temp_dir().join(format!("deposit-admin-{}", Uuid::new_v4()));
+        let config = crate::config::AdminConfig {
+            tokens: vec![crate::config::AdminToken {
+                actor: "ops".to_string(),
+                token: "admin-secret".to_string(),
+            }],
+            ..Default::default()
+        };
+        let engine = Arc::new(MatchingEngine::new());
+        let admin = crate::admin::protect_admin_router(
+            create_admin_router(engine.clone(), None),
+            &config,
+            &dir,
+        )
+        .unwrap();
+        let app = open_router(engine.clone()).merge(admin);
+        let deposit = |uri: &str, token: Option<&str>, amount: f64| {
+            let mut request = Request::post(uri).header(header::CONTENT_TYPE, "application/json");
+            if let Some(token) = token {
+                request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+            }
+            let body = json!({"asset": "USDT", "amount": amount, "idempotency_key": "dep-1"});
+            app.clone()
+                .oneshot(request.body(Body::from(body.to_string())).unwrap())
+        };
+
+        // 公开路由上没有充值接口，管理路由要求管理令牌
+        let response = deposit("/accounts/alice/deposit", None, 1_000.0)
+            .await
+            .unwrap();
+        assert!(response.status().is_client_error());
+        let response = deposit("/admin/accounts/alice/deposit", None, 1_000.0)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            engine.accounts().get_balance("alice", "USDT").available,
+            0.0
+        );
+
+        let response = deposit(
+            "/admin/accounts/alice/deposit",
+            Some("admin-secret"),
+            1_000.0,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            engine.accounts().get_balance("alice", "USDT").available,
+            1_000.0
+        );
+
+        // 同一幂等键用于不同金额返回 409
+        let response = deposit("/admin/accounts/alice/deposit", Some("admin-secret"), 5.0)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(
+            engine.accounts().get_balance("alice", "USDT").available,
+            1_000.0
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_mark_price_endpoints() {
+        let engine = Arc::new(MatchingEngine::new());
+        let symbol = Symbol::new("BTC", "USDT");
+        let response = open_router(engine.clone())
+            .oneshot(
+                Request::get("/mark-price/BTCUSDT")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        engine
+            .update_mark_price(MarkPrice {
+                symbol,
+                index_price: 100.0,
+                mark_price: 100.5,
+                components: vec![IndexComponent {
+                    source: "spot".to_string(),
+                    price: 100.0,
+                    weight: 1.0,
+                    timestamp: chrono::Utc::now(),
+                }],
+                timestamp: chrono::Utc::now(),
+            })
+            .await;
+        let (status, body) = json_response(
+            open_router(engine.clone()),
+            "/mark-price/BTCUSDT",
+            Method::GET,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["mark_price"], 100.5);
+        assert_eq!(body["components"][0]["source"], "spot");
+
+        let (_, body) = json_response(open_router(engine), "/mark-price", Method::GET).await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sandbox_reset() {
+        let post_reset = |router: Router| async move {
+            let request = Request::post("/sandbox/reset")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(json!({"user_id": "alice"}).to_string()))
+                .unwrap();
+            let response = router.oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            (
+                status,
+                serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null),
+            )
+        };
+
+        let (status, _) = post_reset(open_router(Arc::new(MatchingEngine::new()))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let mut config = EngineConfig::default();
+        config.sandbox.enabled = true;
+        config.sandbox.initial_balances = HashMap::from([("USDT".to_string(), 1_000.0)]);
+        let engine = Arc::new(MatchingEngine::with_config(config));
+        let order = Order::new(
+            Symbol::new("BTC", "USDT"),
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(500.0),
+            "alice".to_string(),
+        );
+        engine.submit_order(order).await.unwrap();
+
+        let (status, balances) = json_response(
+            open_router(engine.clone()),
+            "/accounts/alice/balances",
+            Method::GET,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(balances[0]["available"], 1_000.0);
+
+        let (status, reset) = post_reset(open_router(engine.clone())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(reset["cancelled_orders"], 1);
+        assert_eq!(reset["balances"][0]["asset"], "USDT");
+        assert_eq!(reset["balances"][0]["available"], 1_000.0);
+        assert!(engine.get_user_orders("alice")[0].status.is_terminal());
+    }
+
+    #[tokio::test]
+    async fn test_create_order_rejects_invalid_numbers() {
+        let router = || open_router(Arc::new(MatchingEngine::new()));
+        let post = |body: Value| async move {
+            let request = Request::post("/orders")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            let response = router().oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            (status, serde_json::from_slice::<Value>(&body).unwrap())
+        };
+        let order = |quantity: Value, price: Value| {
+            json!({
+                "symbol": {"base": "BTC", "quote": "USDT"},
+                "side": "buy",
+                "order_type": "limit",
+                "quantity": quantity,
+                "price": price,
+                "user_id": "user1",
+            })
+        };
+
+        let (status, body) = post(order(json!(-1.0), json!(1e13))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["message"], "Invalid request body");
+        assert_eq!(body["details"]["fields"][0]["field"], "quantity");
+        assert_eq!(body["details"]["fields"][0]["message"], "must be positive");
+        assert_eq!(body["details"]["fields"][1]["field"], "price");
+        assert_eq!(
+            body["details"]["fields"][1]["message"],
+            "must not exceed 1000000000000"
+        );
+
+        let (status, body) = post(order(json!("1"), json!(100.0))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["details"]["fields"][0]["field"], "quantity");
+
+        // 字段合法但被引擎拒绝时返回 400 和拒绝原因
+        let mut rejected = order(json!(1.0), json!(100.0));
+        rejected["min_fill_qty"] = json!(2.0);
+        let (status, body) = post(rejected).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "order_rejected");
+        assert_eq!(
+            body["message"],
+            "Minimum fill quantity must not exceed the order quantity"
+        );
+
+        // 价格是否落在 tick 上取决于交易对配置，由引擎检查
+        let (status, body) = post(order(json!(1.0), json!(100.0000001))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["message"],
+            "Price 100.0000001 is not a multiple of tick size 0.000001"
+        );
+
+        let (status, _) = post(order(json!(1.5), json!(100.25))).await;
+        assert_eq!(status, StatusCode::OK);
     }
 
     #[test]
@@ -283,5 +5099,9 @@ mod tests {
         assert!(parse_symbol("INVALID").is_err());
         assert!(parse_symbol("").is_err());
         assert!(parse_symbol("BTC").is_err());
+        assert!(parse_symbol("-USDT").is_err());
+        assert!(parse_symbol("BTC/US DT").is_err());
+        assert!(parse_symbol("BTC-ETH-USDT").is_err());
+        assert!(serde_json::from_str::<Symbol>(r#"{"base": "", "quote": "USDT"}"#).is_err());
     }
 }
@@ -0,0 +1,347 @@
+//! 用户 API key
+//!
+//! 用户登录后（JWT）可以为自己创建多个 API key，每个 key 带权限范围、可选的每分钟请求上限
+//! 和来源 IP 白名单。key 的明文只在创建时返回一次，存储中只保留其 SHA-256 摘要；
+//! 全部 key 以 JSON 文件保存，创建、删除和最近使用时间变化时整体重写。
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// 最近使用时间变化超过该秒数才写回文件，避免每个请求都重写
+const LAST_USED_PERSIST_INTERVAL_SECONDS: i64 = 60;
+
+/// API key 权限范围
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyScope {
+    /// 查询订单、成交、余额等
+    Read,
+    /// 下单和撤单
+    Trade,
+    /// 提现和划转
+    Withdraw,
+}
+
+/// API key 信息（不含明文）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ApiKey {
+    pub id: String,
+    pub user_id: String,
+    /// 用户填写的备注
+    pub label: String,
+    pub scopes: BTreeSet<ApiKeyScope>,
+    /// 每分钟请求上限，为空时使用服务配置的默认值
+    pub rate_limit_per_minute: Option<u32>,
+    /// 允许的来源 IP，为空时不限制
+    #[schema(value_type = Vec<String>)]
+    pub ip_allowlist: Vec<IpAddr>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    pub fn allows(&self, scope: ApiKeyScope) -> bool {
+        self.scopes.contains(&scope)
+    }
+}
+
+/// 创建 API key 的参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct NewApiKey {
+    #[serde(default)]
+    pub label: String,
+    pub scopes: BTreeSet<ApiKeyScope>,
+    #[serde(default)]
+    pub rate_limit_per_minute: Option<u32>,
+    #[serde(default)]
+    #[schema(value_type = Vec<String>)]
+    pub ip_allowlist: Vec<IpAddr>,
+}
+
+/// 新创建的 API key，`api_key` 为明文，只在创建时返回
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreatedApiKey {
+    #[serde(flatten)]
+    pub key: ApiKey,
+    pub api_key: String,
+}
+
+/// API key 认证失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiKeyRejection {
+    /// key 不存在或已删除
+    Invalid,
+    /// 来源 IP 不在白名单中
+    IpNotAllowed,
+    /// 超过每分钟请求上限，附带距下一分钟的秒数
+    RateLimited(u64),
+}
+
+/// 持久化的 key：信息和明文摘要
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredKey {
+    #[serde(flatten)]
+    key: ApiKey,
+    secret_hash: String,
+}
+
+#[derive(Debug, Default)]
+struct StoreState {
+    /// 明文摘要 -> key
+    keys: HashMap<String, StoredKey>,
+    /// key ID -> (当前分钟, 该分钟内的请求数)
+    usage: HashMap<String, (DateTime<Utc>, u32)>,
+}
+
+/// 全部用户的 API key
+#[derive(Debug)]
+pub struct ApiKeyStore {
+    path: PathBuf,
+    /// 未单独设置上限的 key 每分钟的请求上限，0 表示不限制
+    default_rate_limit: u32,
+    state: Mutex<StoreState>,
+}
+
+impl ApiKeyStore {
+    /// 打开 key 文件，文件不存在时从空开始
+    pub fn open(path: impl Into<PathBuf>, default_rate_limit: u32) -> Result<Self, String> {
+        let path = path.into();
+        let stored: Vec<StoredKey> = match fs::read(&path) {
+            Ok(content) => serde_json::from_slice(&content)
+                .map_err(|e| format!("Failed to parse API keys {}: {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(format!("Failed to read API keys {}: {}", path.display(), e)),
+        };
+        info!("Loaded {} API keys from {}", stored.len(), path.display());
+
+        let keys = stored
+            .into_iter()
+            .map(|stored| (stored.secret_hash.clone(), stored))
+            .collect();
+        Ok(Self {
+            path,
+            default_rate_limit,
+            state: Mutex::new(StoreState {
+                keys,
+                usage: HashMap::new(),
+            }),
+        })
+    }
+
+    /// 为用户创建 API key，返回 key 信息和明文
+    pub fn create(
+        &self,
+        user_id: &str,
+        request: NewApiKey,
+        now: DateTime<Utc>,
+    ) -> Result<CreatedApiKey, String> {
+        if request.scopes.is_empty() {
+            return Err("API key must have at least one scope".to_string());
+        }
+
+        let secret = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let key = ApiKey {
+            id: Uuid::new_v4().simple().to_string(),
+            user_id: user_id.to_string(),
+            label: request.label,
+            scopes: request.scopes,
+            rate_limit_per_minute: request.rate_limit_per_minute,
+            ip_allowlist: request.ip_allowlist,
+            created_at: now,
+            last_used_at: None,
+        };
+
+        let mut state = self.state.lock();
+        state.keys.insert(
+            hash_secret(&secret),
+            StoredKey {
+                key: key.clone(),
+                secret_hash: hash_secret(&secret),
+            },
+        );
+        self.save(&state)?;
+        info!("API key {} created for user {}", key.id, user_id);
+        Ok(CreatedApiKey {
+            key,
+            api_key: secret,
+        })
+    }
+
+    /// 用户的全部 API key，按创建时间从早到晚
+    pub fn list(&self, user_id: &str) -> Vec<ApiKey> {
+        let state = self.state.lock();
+        let mut keys: Vec<ApiKey> = state
+            .keys
+            .values()
+            .filter(|stored| stored.key.user_id == user_id)
+            .map(|stored| stored.key.clone())
+            .collect();
+        keys.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        keys
+    }
+
+    /// 删除用户的 API key，key 不存在或不属于该用户时返回 false
+    pub fn delete(&self, user_id: &str, key_id: &str) -> Result<bool, String> {
+        let mut state = self.state.lock();
+        let Some(hash) = state
+            .keys
+            .iter()
+            .find(|(_, stored)| stored.key.id == key_id && stored.key.user_id == user_id)
+            .map(|(hash, _)| hash.clone())
+        else {
+            return Ok(false);
+        };
+        state.keys.remove(&hash);
+        state.usage.remove(key_id);
+        self.save(&state)?;
+        info!("API key {} deleted for user {}", key_id, user_id);
+        Ok(true)
+    }
+
+    /// 校验请求使用的 API key：来源 IP 须在白名单中且未超过每分钟请求上限，通过后记录最近使用时间
+    pub fn authenticate(
+        &self,
+        secret: &str,
+        ip: Option<IpAddr>,
+        now: DateTime<Utc>,
+    ) -> Result<ApiKey, ApiKeyRejection> {
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        let stored = state
+            .keys
+            .get_mut(&hash_secret(secret))
+            .ok_or(ApiKeyRejection::Invalid)?;
+        let key = &mut stored.key;
+
+        if !key.ip_allowlist.is_empty() && !ip.is_some_and(|ip| key.ip_allowlist.contains(&ip)) {
+            return Err(ApiKeyRejection::IpNotAllowed);
+        }
+
+        let limit = key.rate_limit_per_minute.unwrap_or(self.default_rate_limit);
+        if limit > 0 {
+            let minute = now.duration_trunc(Duration::minutes(1)).unwrap_or(now);
+            let usage = state.usage.entry(key.id.clone()).or_insert((minute, 0));
+            if usage.0 != minute {
+                *usage = (minute, 0);
+            }
+            if usage.1 >= limit {
+                let retry_after = (minute + Duration::minutes(1) - now).num_seconds().max(1);
+                return Err(ApiKeyRejection::RateLimited(retry_after as u64));
+            }
+            usage.1 += 1;
+        }
+
+        let persist = key.last_used_at.is_none_or(|last_used| {
+            now - last_used >= Duration::seconds(LAST_USED_PERSIST_INTERVAL_SECONDS)
+        });
+        key.last_used_at = Some(now);
+        let key = key.clone();
+        if persist {
+            if let Err(e) = self.save(state) {
+                warn!("Failed to persist API key usage: {}", e);
+            }
+        }
+        Ok(key)
+    }
+
+    /// 整体重写 key 文件
+    fn save(&self, state: &StoreState) -> Result<(), String> {
+        let mut stored: Vec<&StoredKey> = state.keys.values().collect();
+        stored.sort_by_key(|stored| stored.key.created_at);
+        let content = serde_json::to_vec_pretty(&stored)
+            .map_err(|e| format!("Failed to serialize API keys: {}", e))?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let partial = self.path.with_extension("json.partial");
+        fs::write(&partial, content)
+            .and_then(|_| fs::rename(&partial, &self.path))
+            .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))
+    }
+}
+
+fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_api_key_lifecycle_survives_reopen() {
+        let dir = std::env::temp_dir().join(format!("api-keys-{}", Uuid::new_v4()));
+        let path = dir.join("api_keys.json");
+        let now = Utc.with_ymd_and_hms(2024, 1, 5, 12, 0, 30).unwrap();
+        let office: IpAddr = "10.0.0.1".parse().unwrap();
+
+        let store = ApiKeyStore::open(&path, 0).unwrap();
+        assert!(store.create("alice", NewApiKey::default(), now).is_err());
+        let created = store
+            .create(
+                "alice",
+                NewApiKey {
+                    label: "bot".to_string(),
+                    scopes: [ApiKeyScope::Read, ApiKeyScope::Trade].into(),
+                    rate_limit_per_minute: Some(2),
+                    ip_allowlist: vec![office],
+                },
+                now,
+            )
+            .unwrap();
+        assert_eq!(
+            store.authenticate("unknown", Some(office), now),
+            Err(ApiKeyRejection::Invalid)
+        );
+        assert_eq!(
+            store.authenticate(&created.api_key, None, now),
+            Err(ApiKeyRejection::IpNotAllowed)
+        );
+        let key = store
+            .authenticate(&created.api_key, Some(office), now)
+            .unwrap();
+        assert!(key.allows(ApiKeyScope::Trade) && !key.allows(ApiKeyScope::Withdraw));
+        store
+            .authenticate(&created.api_key, Some(office), now)
+            .unwrap();
+        assert_eq!(
+            store.authenticate(&created.api_key, Some(office), now),
+            Err(ApiKeyRejection::RateLimited(30))
+        );
+        let next_minute = now + Duration::seconds(30);
+        assert!(store
+            .authenticate(&created.api_key, Some(office), next_minute)
+            .is_ok());
+
+        // 重新打开后 key 和最近使用时间仍在，明文不落盘
+        let store = ApiKeyStore::open(&path, 0).unwrap();
+        let keys = store.list("alice");
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].last_used_at, Some(now));
+        assert!(!fs::read_to_string(&path)
+            .unwrap()
+            .contains(&created.api_key));
+        assert!(store.list("bob").is_empty());
+
+        assert!(!store.delete("bob", &created.key.id).unwrap());
+        assert!(store.delete("alice", &created.key.id).unwrap());
+        assert_eq!(
+            store.authenticate(&created.api_key, Some(office), next_minute),
+            Err(ApiKeyRejection::Invalid)
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,242 @@
+use crate::id::OrderId;
+use crate::symbol::interning;
+use crate::types::Order;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// 终态订单归档
+///
+/// 订单以 JSON Lines 追加写入分段文件 `orders-<序号>.jsonl`，每段写满 `segment_max_orders`
+/// 条后滚动到下一段。内存中只保留 订单ID -> (段序号, 文件偏移) 的索引，按需从磁盘读取订单。
+#[derive(Debug)]
+pub struct OrderArchive {
+    dir: PathBuf,
+    segment_max_orders: usize,
+    state: Mutex<ArchiveState>,
+}
+
+#[derive(Debug, Default)]
+struct ArchiveState {
+    index: HashMap<OrderId, (u32, u64)>,
+    writer: Option<SegmentWriter>,
+    next_segment: u32,
+}
+
+#[derive(Debug)]
+struct SegmentWriter {
+    segment: u32,
+    file: BufWriter<File>,
+    offset: u64,
+    orders: usize,
+}
+
+impl OrderArchive {
+    /// 打开归档目录，已有的分段文件会被重新索引
+    pub fn open(dir: impl AsRef<Path>, segment_max_orders: usize) -> Result<Self, String> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create archive dir {}: {}", dir.display(), e))?;
+
+        let mut state = ArchiveState::default();
+        let entries = fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to read archive dir {}: {}", dir.display(), e))?;
+        for entry in entries.flatten() {
+            let Some(segment) = parse_segment_name(&entry.file_name().to_string_lossy()) else {
+                continue;
+            };
+            index_segment(&entry.path(), segment, &mut state.index)?;
+            state.next_segment = state.next_segment.max(segment + 1);
+        }
+
+        info!(
+            "Opened order archive at {} with {} orders",
+            dir.display(),
+            state.index.len()
+        );
+
+        Ok(Self {
+            dir,
+            segment_max_orders: segment_max_orders.max(1),
+            state: Mutex::new(state),
+        })
+    }
+
+    /// 追加归档订单
+    pub fn append(&self, orders: &[Order]) -> Result<(), String> {
+        let mut state = self.state.lock();
+
+        for order in orders {
+            let rolled = state
+                .writer
+                .as_ref()
+                .is_none_or(|writer| writer.orders >= self.segment_max_orders);
+            if rolled {
+                let segment = state.next_segment;
+                state.next_segment += 1;
+                state.writer = Some(self.create_segment(segment)?);
+            }
+
+            let writer = state.writer.as_mut().unwrap();
+            let mut line = serde_json::to_vec(order)
+                .map_err(|e| format!("Failed to serialize order {}: {}", order.id, e))?;
+            line.push(b'\n');
+            writer
+                .file
+                .write_all(&line)
+                .map_err(|e| format!("Failed to write archive segment: {}", e))?;
+
+            let location = (writer.segment, writer.offset);
+            writer.offset += line.len() as u64;
+            writer.orders += 1;
+            state.index.insert(order.id, location);
+        }
+
+        if let Some(writer) = state.writer.as_mut() {
+            writer
+                .file
+                .flush()
+                .map_err(|e| format!("Failed to flush archive segment: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// 从归档中读取订单
+    pub fn get(&self, order_id: OrderId) -> Result<Option<Order>, String> {
+        let location = self.state.lock().index.get(&order_id).copied();
+        let Some((segment, offset)) = location else {
+            return Ok(None);
+        };
+
+        let mut file = File::open(self.segment_path(segment))
+            .map_err(|e| format!("Failed to open archive segment {}: {}", segment, e))?;
+        file.seek(SeekFrom::Start(offset))
+            .map_err(|e| format!("Failed to seek archive segment {}: {}", segment, e))?;
+
+        let mut line = String::new();
+        BufReader::new(file)
+            .read_line(&mut line)
+            .map_err(|e| format!("Failed to read archive segment {}: {}", segment, e))?;
+        interning(|| serde_json::from_str(&line))
+            .map(Some)
+            .map_err(|e| format!("Corrupt archived order {}: {}", order_id, e))
+    }
+
+    /// 刷新当前分段并同步到磁盘
+    pub fn flush(&self) -> Result<(), String> {
+        let mut state = self.state.lock();
+        let Some(writer) = state.writer.as_mut() else {
+            return Ok(());
+        };
+        writer
+            .file
+            .flush()
+            .and_then(|_| writer.file.get_ref().sync_all())
+            .map_err(|e| format!("Failed to sync archive segment {}: {}", writer.segment, e))
+    }
+
+    /// 归档订单数量
+    pub fn len(&self) -> usize {
+        self.state.lock().index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn create_segment(&self, segment: u32) -> Result<SegmentWriter, String> {
+        let path = self.segment_path(segment);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to create archive segment {}: {}", path.display(), e))?;
+        let offset = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+
+        Ok(SegmentWriter {
+            segment,
+            file: BufWriter::new(file),
+            offset,
+            orders: 0,
+        })
+    }
+
+    fn segment_path(&self, segment: u32) -> PathBuf {
+        self.dir.join(format!("orders-{:08}.jsonl", segment))
+    }
+}
+
+fn parse_segment_name(name: &str) -> Option<u32> {
+    name.strip_prefix("orders-")?
+        .strip_suffix(".jsonl")?
+        .parse()
+        .ok()
+}
+
+/// 扫描分段文件，重建订单索引
+fn index_segment(
+    path: &Path,
+    segment: u32,
+    index: &mut HashMap<OrderId, (u32, u64)>,
+) -> Result<(), String> {
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to open archive segment {}: {}", path.display(), e))?;
+    let mut reader = BufReader::new(file);
+    let mut offset = 0u64;
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        let read = reader
+            .read_line(&mut line)
+            .map_err(|e| format!("Failed to read archive segment {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        if let Ok(order) = interning(|| serde_json::from_str::<Order>(&line)) {
+            index.insert(order.id, (segment, offset));
+        }
+        offset += read as u64;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::*;
+
+    #[test]
+    fn test_archive_roundtrip_and_reopen() {
+        let dir = std::env::temp_dir().join(format!("order-archive-{}", uuid::Uuid::new_v4()));
+        let orders: Vec<Order> = (0..3)
+            .map(|i| {
+                Order::new(
+                    Symbol::new("BTC", "USDT"),
+                    OrderSide::Buy,
+                    OrderType::Limit,
+                    1.0 + i as f64,
+                    Some(100.0),
+                    "user1".to_string(),
+                )
+            })
+            .collect();
+
+        {
+            let archive = OrderArchive::open(&dir, 2).unwrap();
+            archive.append(&orders).unwrap();
+            assert_eq!(archive.get(orders[2].id).unwrap().unwrap().quantity, 3.0);
+        }
+
+        // 重新打开后从分段文件重建索引
+        let archive = OrderArchive::open(&dir, 2).unwrap();
+        assert_eq!(archive.len(), 3);
+        assert_eq!(archive.get(orders[0].id).unwrap().unwrap().id, orders[0].id);
+        assert!(archive.get(u64::MAX).unwrap().is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
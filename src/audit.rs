@@ -0,0 +1,471 @@
+use crate::id::{OrderId, TradeId};
+use crate::symbol::interning;
+use crate::types::OrderChannel;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use tracing::info;
+use utoipa::ToSchema;
+
+const AUDIT_FILE: &str = "audit.jsonl";
+const ADMIN_AUDIT_FILE: &str = "admin.jsonl";
+
+/// 订单生命周期审计事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AuditEvent {
+    /// 全局递增序号，反映事件发生的先后顺序
+    pub sequence: u64,
+    pub order_id: OrderId,
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub kind: AuditEventKind,
+}
+
+/// 订单状态变化
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEventKind {
+    /// 订单通过校验并被引擎接受
+    Accepted {
+        /// 提交订单的 HTTP 请求ID，非 HTTP 提交（如强平单）时为空
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        request_id: Option<String>,
+        /// 订单的提交渠道
+        #[serde(default)]
+        channel: OrderChannel,
+    },
+    /// 订单被拒绝
+    Rejected {
+        reason: String,
+        /// 提交订单的 HTTP 请求ID，挂单后被拒绝时为空
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        request_id: Option<String>,
+        /// 订单的提交渠道
+        #[serde(default)]
+        channel: OrderChannel,
+    },
+    /// 条件单被成交价触发
+    Triggered { stop_price: Option<f64> },
+    /// 部分成交
+    PartiallyFilled(AuditFill),
+    /// 完全成交
+    Filled(AuditFill),
+    /// 挂单剩余数量被修改
+    Amended {
+        old_remaining_quantity: f64,
+        new_remaining_quantity: f64,
+        reason: String,
+    },
+    /// 订单参与的成交被管理员撤销
+    FillBusted {
+        trade_id: TradeId,
+        quantity: f64,
+        /// 被撤销的数量是否恢复为剩余数量
+        restored: bool,
+        reason: String,
+    },
+    /// 强平引擎代用户提交的平仓单
+    Liquidation {
+        equity: f64,
+        maintenance_margin: f64,
+    },
+    /// 订单被撤销
+    Cancelled { reason: String },
+    /// GTD 订单到期
+    Expired,
+}
+
+/// 一笔成交对单个订单的影响
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AuditFill {
+    pub trade_id: TradeId,
+    /// 对手方订单ID
+    pub counterparty_order_id: OrderId,
+    pub quantity: f64,
+    pub price: f64,
+    /// 成交后的剩余数量
+    pub remaining_quantity: f64,
+}
+
+/// 订单审计日志
+///
+/// 事件以 JSON Lines 只追加写入 `audit.jsonl`，每条事件写入后立即刷盘。内存中只保留
+/// 订单ID -> 事件文件偏移 的索引，查询时按偏移从磁盘读取。
+#[derive(Debug)]
+pub struct AuditLog {
+    path: PathBuf,
+    state: Mutex<AuditState>,
+}
+
+#[derive(Debug)]
+struct AuditState {
+    index: HashMap<OrderId, Vec<u64>>,
+    file: BufWriter<File>,
+    offset: u64,
+    next_sequence: u64,
+}
+
+impl AuditLog {
+    /// 打开审计目录，已有的审计文件会被重新索引，序号在其后继续递增
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, String> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create audit dir {}: {}", dir.display(), e))?;
+
+        let path = dir.join(AUDIT_FILE);
+        let mut index = HashMap::new();
+        let mut next_sequence = 0;
+        if path.exists() {
+            next_sequence = index_audit_file(&path, &mut index)?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open audit file {}: {}", path.display(), e))?;
+        let offset = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+
+        info!(
+            "Opened audit log at {} with {} orders",
+            path.display(),
+            index.len()
+        );
+
+        Ok(Self {
+            path,
+            state: Mutex::new(AuditState {
+                index,
+                file: BufWriter::new(file),
+                offset,
+                next_sequence,
+            }),
+        })
+    }
+
+    /// 追加一条事件，返回分配的序号
+    pub fn record(&self, order_id: OrderId, kind: AuditEventKind) -> Result<u64, String> {
+        let mut state = self.state.lock();
+
+        let event = AuditEvent {
+            sequence: state.next_sequence,
+            order_id,
+            timestamp: Utc::now(),
+            kind,
+        };
+        let mut line = serde_json::to_vec(&event)
+            .map_err(|e| format!("Failed to serialize audit event: {}", e))?;
+        line.push(b'\n');
+        state
+            .file
+            .write_all(&line)
+            .and_then(|_| state.file.flush())
+            .map_err(|e| format!("Failed to write audit event: {}", e))?;
+
+        let offset = state.offset;
+        state.offset += line.len() as u64;
+        state.next_sequence += 1;
+        state.index.entry(order_id).or_default().push(offset);
+        Ok(event.sequence)
+    }
+
+    /// 按发生顺序返回订单的全部事件，未知订单返回空列表
+    pub fn get(&self, order_id: OrderId) -> Result<Vec<AuditEvent>, String> {
+        let offsets = match self.state.lock().index.get(&order_id) {
+            Some(offsets) => offsets.clone(),
+            None => return Ok(Vec::new()),
+        };
+
+        let file = File::open(&self.path)
+            .map_err(|e| format!("Failed to open audit file {}: {}", self.path.display(), e))?;
+        let mut reader = BufReader::new(file);
+        let mut line = String::new();
+        let mut events = Vec::with_capacity(offsets.len());
+        for offset in offsets {
+            line.clear();
+            reader
+                .seek(SeekFrom::Start(offset))
+                .and_then(|_| reader.read_line(&mut line))
+                .map_err(|e| format!("Failed to read audit file: {}", e))?;
+            let event = interning(|| serde_json::from_str(&line))
+                .map_err(|e| format!("Corrupt audit event for order {}: {}", order_id, e))?;
+            events.push(event);
+        }
+        Ok(events)
+    }
+
+    /// 将已写入的事件同步到磁盘
+    pub fn flush(&self) -> Result<(), String> {
+        let mut state = self.state.lock();
+        state
+            .file
+            .flush()
+            .and_then(|_| state.file.get_ref().sync_all())
+            .map_err(|e| format!("Failed to sync audit file: {}", e))
+    }
+
+    /// 有审计记录的订单数量
+    pub fn len(&self) -> usize {
+        self.state.lock().index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// 管理操作审计事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AdminAuditEvent {
+    /// 递增序号，反映操作的先后顺序
+    pub sequence: u64,
+    pub timestamp: DateTime<Utc>,
+    /// 执行操作的管理员（管理令牌对应的操作人）
+    pub actor: String,
+    pub method: String,
+    /// 请求路径（含 API 前缀和查询参数）
+    pub path: String,
+    /// 响应状态码，非 2xx 表示操作未成功
+    pub status: u16,
+}
+
+/// 管理操作审计日志
+///
+/// 事件以 JSON Lines 只追加写入 `admin.jsonl`，每条事件写入后立即刷盘；查询时从文件读取最近的事件
+#[derive(Debug)]
+pub struct AdminAuditLog {
+    path: PathBuf,
+    state: Mutex<AdminAuditState>,
+}
+
+#[derive(Debug)]
+struct AdminAuditState {
+    file: BufWriter<File>,
+    next_sequence: u64,
+}
+
+impl AdminAuditLog {
+    /// 打开审计目录，序号在已有事件之后继续递增
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, String> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create audit dir {}: {}", dir.display(), e))?;
+
+        let path = dir.join(ADMIN_AUDIT_FILE);
+        let next_sequence = read_admin_events(&path)?
+            .last()
+            .map_or(0, |event| event.sequence + 1);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open audit file {}: {}", path.display(), e))?;
+
+        Ok(Self {
+            path,
+            state: Mutex::new(AdminAuditState {
+                file: BufWriter::new(file),
+                next_sequence,
+            }),
+        })
+    }
+
+    /// 追加一条管理操作，返回分配的序号
+    pub fn record(
+        &self,
+        actor: &str,
+        method: &str,
+        path: &str,
+        status: u16,
+    ) -> Result<u64, String> {
+        let mut state = self.state.lock();
+
+        let event = AdminAuditEvent {
+            sequence: state.next_sequence,
+            timestamp: Utc::now(),
+            actor: actor.to_string(),
+            method: method.to_string(),
+            path: path.to_string(),
+            status,
+        };
+        let mut line = serde_json::to_vec(&event)
+            .map_err(|e| format!("Failed to serialize admin audit event: {}", e))?;
+        line.push(b'\n');
+        state
+            .file
+            .write_all(&line)
+            .and_then(|_| state.file.flush())
+            .map_err(|e| format!("Failed to write admin audit event: {}", e))?;
+
+        state.next_sequence += 1;
+        Ok(event.sequence)
+    }
+
+    /// 最近的管理操作，最新的在前
+    pub fn recent(&self, limit: usize) -> Result<Vec<AdminAuditEvent>, String> {
+        let _state = self.state.lock();
+        let mut events = read_admin_events(&self.path)?;
+        events.reverse();
+        events.truncate(limit);
+        Ok(events)
+    }
+}
+
+/// 读取管理审计文件中的全部事件，文件不存在时为空；无法解析的行被跳过
+fn read_admin_events(path: &Path) -> Result<Vec<AdminAuditEvent>, String> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(format!(
+                "Failed to open audit file {}: {}",
+                path.display(),
+                e
+            ))
+        }
+    };
+    let mut events = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line =
+            line.map_err(|e| format!("Failed to read audit file {}: {}", path.display(), e))?;
+        if let Ok(event) = interning(|| serde_json::from_str(&line)) {
+            events.push(event);
+        }
+    }
+    Ok(events)
+}
+
+/// 扫描审计文件重建索引，返回下一个可用序号
+fn index_audit_file(path: &Path, index: &mut HashMap<OrderId, Vec<u64>>) -> Result<u64, String> {
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to open audit file {}: {}", path.display(), e))?;
+    let mut reader = BufReader::new(file);
+    let mut offset = 0u64;
+    let mut next_sequence = 0;
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        let read = reader
+            .read_line(&mut line)
+            .map_err(|e| format!("Failed to read audit file {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        if let Ok(event) = interning(|| serde_json::from_str::<AuditEvent>(&line)) {
+            index.entry(event.order_id).or_default().push(offset);
+            next_sequence = next_sequence.max(event.sequence + 1);
+        }
+        offset += read as u64;
+    }
+    Ok(next_sequence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_log_roundtrip_and_reopen() {
+        let dir = std::env::temp_dir().join(format!("order-audit-{}", uuid::Uuid::new_v4()));
+        let fill = AuditFill {
+            trade_id: 10,
+            counterparty_order_id: 2,
+            quantity: 0.5,
+            price: 100.0,
+            remaining_quantity: 0.5,
+        };
+
+        {
+            let audit = AuditLog::open(&dir).unwrap();
+            audit
+                .record(
+                    1,
+                    AuditEventKind::Accepted {
+                        request_id: None,
+                        channel: OrderChannel::Rest,
+                    },
+                )
+                .unwrap();
+            audit
+                .record(
+                    2,
+                    AuditEventKind::Accepted {
+                        request_id: None,
+                        channel: OrderChannel::Rest,
+                    },
+                )
+                .unwrap();
+            audit
+                .record(1, AuditEventKind::PartiallyFilled(fill.clone()))
+                .unwrap();
+        }
+
+        // 重新打开后从审计文件重建索引，序号继续递增
+        let audit = AuditLog::open(&dir).unwrap();
+        assert_eq!(audit.len(), 2);
+        let sequence = audit
+            .record(
+                1,
+                AuditEventKind::Cancelled {
+                    reason: "user request".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(sequence, 3);
+
+        let events = audit.get(1).unwrap();
+        let kinds: Vec<&AuditEventKind> = events.iter().map(|event| &event.kind).collect();
+        assert_eq!(
+            events
+                .iter()
+                .map(|event| event.sequence)
+                .collect::<Vec<_>>(),
+            [0, 2, 3]
+        );
+        assert_eq!(
+            kinds[0],
+            &AuditEventKind::Accepted {
+                request_id: None,
+                channel: OrderChannel::Rest,
+            }
+        );
+        assert_eq!(kinds[1], &AuditEventKind::PartiallyFilled(fill));
+        assert!(audit.get(99).unwrap().is_empty());
+
+        let json = serde_json::to_value(&events[0]).unwrap();
+        assert_eq!(json["event"], "accepted");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_admin_audit_log_recent_and_reopen() {
+        let dir = std::env::temp_dir().join(format!("admin-audit-{}", uuid::Uuid::new_v4()));
+        {
+            let audit = AdminAuditLog::open(&dir).unwrap();
+            audit
+                .record("ops", "POST", "/api/v1/admin/symbols/BTCUSDT/halt", 200)
+                .unwrap();
+        }
+
+        let audit = AdminAuditLog::open(&dir).unwrap();
+        let sequence = audit
+            .record("ops", "POST", "/api/v1/admin/trades/7/bust", 404)
+            .unwrap();
+        assert_eq!(sequence, 1);
+
+        let events = audit.recent(10).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].path, "/api/v1/admin/trades/7/bust");
+        assert_eq!(events[0].status, 404);
+        assert_eq!(events[1].actor, "ops");
+        assert_eq!(audit.recent(1).unwrap().len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
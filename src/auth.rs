@@ -0,0 +1,296 @@
+//! JWT 认证
+//!
+//! 启用认证后，服务器在全部路由外层校验 `Authorization: Bearer <JWT>` 或用户 API key，
+//! 把调用方放入请求扩展。按用户访问的接口通过 [`Caller`] 提取器校验只能操作调用方自己的订单、
+//! 成交和资金，API key 还须带有接口所需的权限范围；没有令牌或令牌无效的请求仍可访问行情等公开接口，
+//! 访问按用户的接口时返回 401。API key 无效、来源 IP 不在白名单或超过请求上限时直接拒绝。
+use crate::api::ApiError;
+use crate::api_keys::{ApiKey, ApiKeyRejection, ApiKeyScope, ApiKeyStore};
+use crate::config::AuthConfig;
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts, Request, State},
+    http::{header, request::Parts, HeaderMap, HeaderName, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tracing::debug;
+
+/// 令牌声明
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    /// 用户ID
+    pub sub: String,
+    /// 过期时间（Unix 秒）
+    pub exp: u64,
+}
+
+/// 发起请求的调用方
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Caller {
+    /// 未启用认证，不限制访问
+    Unrestricted,
+    /// 启用了认证，但请求没有有效令牌
+    Anonymous,
+    /// 登录令牌（JWT）中的用户，拥有全部权限
+    User(String),
+    /// API key 的所属用户，只拥有 key 的权限范围
+    ApiKey(ApiKey),
+}
+
+impl Caller {
+    /// 校验调用方能否以该权限访问用户的数据：没有有效令牌返回 401，
+    /// 访问其他用户的数据或 API key 没有该权限返回 403
+    pub fn authorize(&self, user_id: &str, scope: ApiKeyScope) -> Result<(), StatusCode> {
+        match self {
+            Caller::Unrestricted => Ok(()),
+            Caller::Anonymous => Err(StatusCode::UNAUTHORIZED),
+            Caller::User(caller) if caller == user_id => Ok(()),
+            Caller::ApiKey(key) if key.user_id == user_id && key.allows(scope) => Ok(()),
+            Caller::User(_) | Caller::ApiKey(_) => Err(StatusCode::FORBIDDEN),
+        }
+    }
+
+    /// 以登录令牌认证的用户，用于管理 API key；API key 不能管理 key，返回 403
+    pub fn session_user(&self) -> Result<&str, StatusCode> {
+        match self {
+            Caller::User(user_id) => Ok(user_id),
+            Caller::ApiKey(_) => Err(StatusCode::FORBIDDEN),
+            Caller::Unrestricted | Caller::Anonymous => Err(StatusCode::UNAUTHORIZED),
+        }
+    }
+}
+
+/// 请求扩展中没有调用方时按未认证处理，没有经过 [`apply_server_layers`](crate::api::apply_server_layers)
+/// 的路由不会获得不受限的访问
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Caller {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<Caller>()
+            .cloned()
+            .unwrap_or(Caller::Anonymous))
+    }
+}
+
+/// JWT 和 API key 校验器
+#[derive(Clone)]
+pub struct Authenticator {
+    key: DecodingKey,
+    validation: Validation,
+    api_key_header: HeaderName,
+    api_keys: Arc<ApiKeyStore>,
+}
+
+impl Authenticator {
+    /// 按配置创建校验器并打开 API key 文件
+    pub fn new(config: &AuthConfig) -> Result<Self, String> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.leeway = config.leeway_seconds;
+        let api_key_header = HeaderName::try_from(config.api_key_header.as_str())
+            .map_err(|e| format!("Invalid API key header {}: {}", config.api_key_header, e))?;
+        let api_keys =
+            ApiKeyStore::open(&config.api_keys_path, config.api_key_rate_limit_per_minute)?;
+        Ok(Self {
+            key: DecodingKey::from_secret(config.jwt_secret.as_bytes()),
+            validation,
+            api_key_header,
+            api_keys: Arc::new(api_keys),
+        })
+    }
+
+    /// 校验令牌的签名和过期时间，返回其中的用户ID
+    pub fn verify(&self, token: &str) -> Result<String, String> {
+        decode::<Claims>(token, &self.key, &self.validation)
+            .map(|data| data.claims.sub)
+            .map_err(|e| format!("Invalid token: {}", e))
+    }
+
+    /// 携带用户 API key 的请求头
+    pub fn api_key_header(&self) -> &HeaderName {
+        &self.api_key_header
+    }
+
+    /// 按请求携带的 API key 或 Bearer 令牌确定调用方，API key 优先
+    ///
+    /// API key 无效、来源 IP 不在白名单或超过请求上限时返回拒绝原因；令牌无效时为匿名调用方
+    pub fn resolve(
+        &self,
+        api_key: Option<&str>,
+        bearer: Option<&str>,
+        ip: Option<IpAddr>,
+    ) -> Result<Caller, ApiKeyRejection> {
+        if let Some(api_key) = api_key {
+            return self
+                .api_keys
+                .authenticate(api_key, ip, Utc::now())
+                .map(Caller::ApiKey);
+        }
+        Ok(match bearer {
+            // drop copy 等接口使用其他 Bearer 令牌，校验失败时不直接拒绝，由按用户的接口返回 401
+            Some(token) => self.verify(token).map_or_else(
+                |e| {
+                    debug!("{}", e);
+                    Caller::Anonymous
+                },
+                Caller::User,
+            ),
+            None => Caller::Anonymous,
+        })
+    }
+}
+
+/// 认证中间件：校验请求的令牌或 API key，把调用方和 API key 存储放入请求扩展
+pub async fn authenticate(
+    State(authenticator): State<Arc<Authenticator>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let api_key = request
+        .headers()
+        .get(&authenticator.api_key_header)
+        .map(|value| value.to_str().unwrap_or_default());
+    let ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let caller = match authenticator.resolve(api_key, bearer_token(request.headers()), ip) {
+        Ok(caller) => caller,
+        Err(rejection) => return reject_api_key(rejection),
+    };
+    request.extensions_mut().insert(caller);
+    request
+        .extensions_mut()
+        .insert(authenticator.api_keys.clone());
+    next.run(request).await
+}
+
+fn reject_api_key(rejection: ApiKeyRejection) -> Response {
+    match rejection {
+        ApiKeyRejection::Invalid => {
+            ApiError::new(StatusCode::UNAUTHORIZED, "unauthorized", "Invalid API key")
+        }
+        ApiKeyRejection::IpNotAllowed => ApiError::new(
+            StatusCode::FORBIDDEN,
+            "ip_not_allowed",
+            "Source IP is not in the API key allowlist",
+        ),
+        ApiKeyRejection::RateLimited(retry_after) => ApiError::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            "rate_limited",
+            "API key rate limit exceeded",
+        )
+        .with_retry_after(retry_after),
+    }
+    .into_response()
+}
+
+/// 比较两个令牌，耗时只与长度有关，不随第一个不同字节的位置变化
+pub(crate) fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+/// 从 `Authorization: Bearer <token>` 请求头中取出令牌
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+
+    fn token(secret: &str, sub: &str, exp: u64) -> String {
+        let claims = Claims {
+            sub: sub.to_string(),
+            exp,
+        };
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(secret.as_bytes()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq("secret", "secret"));
+        assert!(!constant_time_eq("secret", "secreT"));
+        assert!(!constant_time_eq("secret", "secret1"));
+        assert!(constant_time_eq("", ""));
+    }
+
+    #[test]
+    fn test_verify_checks_signature_and_expiry() {
+        let authenticator = Authenticator::new(&AuthConfig {
+            enabled: true,
+            jwt_secret: "secret".to_string(),
+            leeway_seconds: 0,
+            api_keys_path: std::env::temp_dir()
+                .join(format!("api-keys-{}.json", uuid::Uuid::new_v4()))
+                .to_string_lossy()
+                .into_owned(),
+            ..Default::default()
+        })
+        .unwrap();
+        let exp = chrono::Utc::now().timestamp() as u64 + 60;
+
+        assert_eq!(
+            authenticator.verify(&token("secret", "alice", exp)),
+            Ok("alice".to_string())
+        );
+        assert!(authenticator.verify(&token("other", "alice", exp)).is_err());
+        assert!(authenticator
+            .verify(&token("secret", "alice", exp - 120))
+            .is_err());
+        assert!(authenticator.verify("not-a-jwt").is_err());
+    }
+
+    #[test]
+    fn test_authorize_only_allows_own_data() {
+        use ApiKeyScope::*;
+        assert_eq!(Caller::Unrestricted.authorize("alice", Withdraw), Ok(()));
+        assert_eq!(
+            Caller::Anonymous.authorize("alice", Read),
+            Err(StatusCode::UNAUTHORIZED)
+        );
+        let alice = Caller::User("alice".to_string());
+        assert_eq!(alice.authorize("alice", Withdraw), Ok(()));
+        assert_eq!(alice.authorize("bob", Read), Err(StatusCode::FORBIDDEN));
+        assert_eq!(alice.session_user(), Ok("alice"));
+
+        // API key 只拥有自身的权限范围，且不能管理 key
+        let key = Caller::ApiKey(ApiKey {
+            id: "key".to_string(),
+            user_id: "alice".to_string(),
+            label: String::new(),
+            scopes: [Read, Trade].into(),
+            rate_limit_per_minute: None,
+            ip_allowlist: Vec::new(),
+            created_at: Utc::now(),
+            last_used_at: None,
+        });
+        assert_eq!(key.authorize("alice", Trade), Ok(()));
+        assert_eq!(key.authorize("alice", Withdraw), Err(StatusCode::FORBIDDEN));
+        assert_eq!(key.authorize("bob", Read), Err(StatusCode::FORBIDDEN));
+        assert_eq!(key.session_user(), Err(StatusCode::FORBIDDEN));
+    }
+}
@@ -0,0 +1,154 @@
+//! 按交易对的有界下单队列
+//!
+//! 同一交易对的下单按到达顺序排队、逐个撮合；排队（含正在撮合）的订单数达到容量时立即拒绝，
+//! 过载时调用方得到明确的繁忙响应并按建议的间隔重试，而不是在订单簿锁上越积越多。
+use crate::config::SubmissionQueueConfig;
+use crate::symbol::SymbolId;
+use crate::types::Symbol;
+use metrics::{counter, gauge};
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+/// 队列已满时拒绝原因的前缀
+pub const ENGINE_BUSY: &str = "Engine busy";
+
+/// 错误是否为下单队列已满导致的繁忙拒绝
+pub fn is_engine_busy(error: &str) -> bool {
+    error.starts_with(ENGINE_BUSY)
+}
+
+/// 全部交易对的下单队列
+pub struct SubmissionQueues {
+    config: SubmissionQueueConfig,
+    queues: RwLock<HashMap<SymbolId, Arc<SymbolQueue>>>,
+}
+
+struct SymbolQueue {
+    symbol: Symbol,
+    /// 排队和正在撮合的订单数
+    depth: AtomicUsize,
+    /// 公平锁，等待者按到达顺序获得撮合机会
+    turn: Arc<Mutex<()>>,
+}
+
+impl SymbolQueue {
+    fn set_depth_gauge(&self, depth: usize) {
+        gauge!("matching_engine_submission_queue_depth", "symbol" => self.symbol.to_string())
+            .set(depth as f64);
+    }
+}
+
+/// 队列中的位置，离开作用域时深度减一
+struct Reservation(Arc<SymbolQueue>);
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        let depth = self.0.depth.fetch_sub(1, Ordering::SeqCst) - 1;
+        self.0.set_depth_gauge(depth);
+    }
+}
+
+/// 轮到撮合的订单持有的凭证，离开作用域时让出给下一个排队的订单
+pub struct QueueSlot {
+    _turn: OwnedMutexGuard<()>,
+    _reservation: Reservation,
+}
+
+impl SubmissionQueues {
+    pub fn new(config: SubmissionQueueConfig) -> Self {
+        Self {
+            config,
+            queues: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 排队等待交易对的撮合机会，队列已满时立即返回繁忙错误
+    pub async fn enter(&self, symbol: &Symbol) -> Result<QueueSlot, String> {
+        let queue = self.queue(symbol);
+        let depth = queue.depth.fetch_add(1, Ordering::SeqCst);
+        let reservation = Reservation(Arc::clone(&queue));
+
+        if depth >= self.config.capacity {
+            counter!("matching_engine_engine_busy_total", "symbol" => symbol.to_string())
+                .increment(1);
+            return Err(format!(
+                "{}: submission queue for {} is full ({} pending), retry after {}s",
+                ENGINE_BUSY, symbol, depth, self.config.retry_after_seconds
+            ));
+        }
+        queue.set_depth_gauge(depth + 1);
+
+        let turn = Arc::clone(&queue.turn).lock_owned().await;
+        Ok(QueueSlot {
+            _turn: turn,
+            _reservation: reservation,
+        })
+    }
+
+    /// 交易对当前排队和正在撮合的订单数
+    pub fn depth(&self, symbol: &Symbol) -> usize {
+        self.queues
+            .read()
+            .get(&symbol.id())
+            .map_or(0, |queue| queue.depth.load(Ordering::SeqCst))
+    }
+
+    /// 繁忙拒绝后建议的重试间隔
+    pub fn retry_after(&self) -> Duration {
+        Duration::from_secs(self.config.retry_after_seconds)
+    }
+
+    fn queue(&self, symbol: &Symbol) -> Arc<SymbolQueue> {
+        if let Some(queue) = self.queues.read().get(&symbol.id()) {
+            return Arc::clone(queue);
+        }
+        let mut queues = self.queues.write();
+        Arc::clone(queues.entry(symbol.id()).or_insert_with(|| {
+            Arc::new(SymbolQueue {
+                symbol: *symbol,
+                depth: AtomicUsize::new(0),
+                turn: Arc::new(Mutex::new(())),
+            })
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_full_queue_rejects_and_waiters_run_in_order() {
+        let queues = Arc::new(SubmissionQueues::new(SubmissionQueueConfig {
+            enabled: true,
+            capacity: 2,
+            retry_after_seconds: 1,
+        }));
+        let symbol = Symbol::new("BTC", "USDT");
+
+        let first = queues.enter(&symbol).await.unwrap();
+        let waiting = tokio::spawn({
+            let queues = Arc::clone(&queues);
+            async move { queues.enter(&symbol).await.map(|_| ()) }
+        });
+        tokio::task::yield_now().await;
+        assert_eq!(queues.depth(&symbol), 2);
+
+        // 队列已满，其它交易对不受影响
+        let error = queues.enter(&symbol).await.err().unwrap();
+        assert!(is_engine_busy(&error));
+        assert_eq!(queues.depth(&symbol), 2);
+        let other = queues.enter(&Symbol::new("ETH", "USDT")).await.unwrap();
+        drop(other);
+
+        // 排队的订单在前一个订单完成后才能撮合
+        assert!(!waiting.is_finished());
+        drop(first);
+        waiting.await.unwrap().unwrap();
+        assert_eq!(queues.depth(&symbol), 0);
+    }
+}
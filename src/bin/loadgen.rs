@@ -0,0 +1,108 @@
+use anyhow::{anyhow, bail, Result};
+use std::sync::Arc;
+use std::time::Duration;
+
+use matching_engine::loadgen::{
+    run_load, HttpTarget, LoadProfile, LoadReport, LoadTarget, TradeStreamCounter,
+};
+use matching_engine::{MatchingEngine, Symbol};
+
+const USAGE: &str = "Usage: loadgen [--url <api base url>] [--ws-url <ws base url>] \
+[--commands N] [--concurrency N] [--rate N] [--symbols BTCUSDT,ETHUSDT] [--users N] \
+[--zipf S] [--maker-ratio R] [--cancel-rate R] [--start-price P] [--tick-size T] [--seed N] [--json]";
+
+/// 生成合成订单流并报告吞吐量和延迟
+///
+/// 默认压测进程内引擎；指定 `--url`（如 `http://127.0.0.1:8080/api/v1`）时经 REST 接口压测运行中的服务，
+/// 同时指定 `--ws-url`（如 `ws://127.0.0.1:8080/ws`）时从成交流统计推送的成交笔数
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut profile = LoadProfile::default();
+    let mut url = None;
+    let mut ws_url = None;
+    let mut json = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--json" {
+            json = true;
+            continue;
+        }
+        let value = args.next().ok_or_else(|| anyhow!(USAGE))?;
+        match arg.as_str() {
+            "--url" => url = Some(value),
+            "--ws-url" => ws_url = Some(value),
+            "--commands" => profile.commands = value.parse()?,
+            "--concurrency" => profile.concurrency = value.parse()?,
+            "--rate" => profile.rate = Some(value.parse()?),
+            "--users" => profile.users = value.parse()?,
+            "--zipf" => profile.zipf_exponent = value.parse()?,
+            "--maker-ratio" => profile.maker_ratio = value.parse()?,
+            "--cancel-rate" => profile.cancel_rate = value.parse()?,
+            "--start-price" => profile.start_price = value.parse()?,
+            "--tick-size" => profile.tick_size = value.parse()?,
+            "--seed" => profile.seed = value.parse()?,
+            "--symbols" => {
+                profile.symbols = value
+                    .split(',')
+                    .map(|symbol| symbol.trim().parse::<Symbol>())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| anyhow!("Invalid symbol list: {}", value))?
+            }
+            _ => bail!(USAGE),
+        }
+    }
+    if ws_url.is_some() && url.is_none() {
+        bail!("--ws-url requires --url");
+    }
+
+    let target = match &url {
+        Some(url) => LoadTarget::Http(HttpTarget::new(url).map_err(|e| anyhow!(e))?),
+        None => LoadTarget::InProcess(Arc::new(MatchingEngine::new())),
+    };
+    let trade_stream = ws_url
+        .as_deref()
+        .map(TradeStreamCounter::connect)
+        .transpose()
+        .map_err(|e| anyhow!(e))?;
+
+    let mut report = run_load(Arc::new(target), profile)
+        .await
+        .map_err(|e| anyhow!(e))?;
+    if let Some(trade_stream) = trade_stream {
+        // 等待最后几笔成交推送到达
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        report.trades = Some(trade_stream.stop());
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&report);
+    }
+    Ok(())
+}
+
+fn print_report(report: &LoadReport) {
+    println!(
+        "{} commands in {:.2}s: {:.0} commands/s",
+        report.commands, report.elapsed_seconds, report.throughput_per_second
+    );
+    println!(
+        "orders {} (filled {}), cancels {}, rejected {}, busy {}, cancel misses {}",
+        report.orders,
+        report.filled_orders,
+        report.cancels,
+        report.rejected,
+        report.busy,
+        report.cancel_misses
+    );
+    if let Some(trades) = report.trades {
+        println!("trades {}", trades);
+    }
+    let latency = &report.latency;
+    println!(
+        "latency us: p50 {:.1}  p95 {:.1}  p99 {:.1}  p999 {:.1}  max {:.1}",
+        latency.p50_us, latency.p95_us, latency.p99_us, latency.p999_us, latency.max_us
+    );
+}
@@ -0,0 +1,70 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::fs;
+use std::io::{self, Write};
+
+use matching_engine::replay::{first_difference, load_commands, replay, ReplayEvent};
+
+const USAGE: &str = "Usage: replay <commands.csv|commands.jsonl> [--output <events.jsonl>] [--expect <events.jsonl>]";
+
+/// 回放录制的命令日志，以 JSON Lines 输出成交和被拒绝的命令
+///
+/// 指定 `--expect` 时与之前保存的输出逐条比较，有差异时以非零状态退出
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let mut input = None;
+    let mut output = None;
+    let mut expect = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => output = Some(args.next().ok_or_else(|| anyhow!(USAGE))?),
+            "--expect" => expect = Some(args.next().ok_or_else(|| anyhow!(USAGE))?),
+            _ if input.is_none() && !arg.starts_with("--") => input = Some(arg),
+            _ => bail!(USAGE),
+        }
+    }
+    let input = input.ok_or_else(|| anyhow!(USAGE))?;
+
+    let commands = load_commands(&input).map_err(|e| anyhow!(e))?;
+    let events = replay(&commands).await;
+
+    let mut lines = String::new();
+    for event in &events {
+        lines.push_str(&serde_json::to_string(event)?);
+        lines.push('\n');
+    }
+    match &output {
+        Some(path) => {
+            fs::write(path, &lines).with_context(|| format!("Failed to write {}", path))?
+        }
+        None => io::stdout().write_all(lines.as_bytes())?,
+    }
+
+    let trades = events
+        .iter()
+        .filter(|event| matches!(event, ReplayEvent::Trade(_)))
+        .count();
+    eprintln!(
+        "Replayed {} commands: {} trades, {} rejected",
+        commands.len(),
+        trades,
+        events.len() - trades
+    );
+
+    if let Some(path) = expect {
+        let content =
+            fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path))?;
+        let expected = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<ReplayEvent>, _>>()
+            .with_context(|| format!("Failed to parse {}", path))?;
+        if let Some(difference) = first_difference(&expected, &events) {
+            bail!("Replay differs from {}: {}", path, difference);
+        }
+        eprintln!("Replay matches {}", path);
+    }
+
+    Ok(())
+}
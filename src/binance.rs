@@ -0,0 +1,1267 @@
+//! Binance 现货 API 兼容层
+//!
+//! 以 Binance 的路径、参数和响应格式提供下单、查单、撤单、深度、K线和 24 小时行情，
+//! 现有交易所 SDK 和交易机器人只需修改服务地址即可接入。启用认证时请求头 `X-MBX-APIKEY`
+//! 为用户创建的 API key，经 API key 存储校验后以其所属用户下单、查单和撤单，同样受权限范围、
+//! IP 白名单和请求上限约束；也可以使用服务器认证的令牌。API key 本身即凭证，不校验 `signature`。
+//! 未启用认证时该请求头直接作为用户ID，只应部署在可信网络内。
+use crate::api_keys::{ApiKeyRejection, ApiKeyScope, ApiKeyStore};
+use crate::auth::Caller;
+use crate::id::OrderId;
+use crate::matching_engine::MatchingEngine;
+use crate::store::{taker_side, FillFilter, PageRequest, TradeFilter};
+use crate::types::*;
+use axum::async_trait;
+use axum::body::Bytes;
+use axum::extract::{ConnectInfo, FromRequestParts, RawQuery, State};
+use axum::http::{request::Parts, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 标识用户的请求头
+const API_KEY_HEADER: &str = "X-MBX-APIKEY";
+
+/// 深度接口默认和最大档位数
+const DEFAULT_DEPTH_LIMIT: usize = 100;
+const MAX_DEPTH_LIMIT: usize = 5000;
+
+/// K线接口默认和最大条数
+const DEFAULT_KLINE_LIMIT: usize = 500;
+const MAX_KLINE_LIMIT: usize = 1000;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// 创建 Binance 兼容路由，路径自带 `/api/v3` 前缀，应合并到根路由
+pub fn create_binance_router(engine: Arc<MatchingEngine>) -> Router {
+    Router::new()
+        .route("/api/v3/ping", get(ping))
+        .route("/api/v3/time", get(server_time))
+        .route(
+            "/api/v3/order",
+            get(query_order).post(new_order).delete(cancel_order),
+        )
+        .route("/api/v3/depth", get(depth))
+        .route("/api/v3/klines", get(klines))
+        .route("/api/v3/ticker/24hr", get(ticker_24hr))
+        .with_state(engine)
+}
+
+/// Binance 格式的错误：`{"code": -1121, "msg": "Invalid symbol."}`
+#[derive(Debug)]
+pub struct BinanceError {
+    status: StatusCode,
+    code: i32,
+    msg: String,
+}
+
+impl BinanceError {
+    fn bad_request(code: i32, msg: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            code,
+            msg: msg.into(),
+        }
+    }
+
+    fn mandatory(param: &str) -> Self {
+        Self::bad_request(
+            -1102,
+            format!(
+                "Mandatory parameter '{}' was not sent, was empty/null, or malformed.",
+                param
+            ),
+        )
+    }
+
+    fn illegal(param: &str, legal_range: &str) -> Self {
+        Self::bad_request(
+            -1100,
+            format!(
+                "Illegal characters found in parameter '{}'; legal range is '{}'.",
+                param, legal_range
+            ),
+        )
+    }
+
+    fn no_such_order() -> Self {
+        Self::bad_request(-2013, "Order does not exist.")
+    }
+
+    fn api_key_format() -> Self {
+        Self {
+            status: StatusCode::UNAUTHORIZED,
+            code: -2014,
+            msg: "API-key format invalid.".to_string(),
+        }
+    }
+
+    fn permission_denied(status: StatusCode) -> Self {
+        Self {
+            status,
+            code: -2015,
+            msg: "Invalid API-key, IP, or permissions for action.".to_string(),
+        }
+    }
+}
+
+impl From<ApiKeyRejection> for BinanceError {
+    fn from(rejection: ApiKeyRejection) -> Self {
+        match rejection {
+            ApiKeyRejection::Invalid => Self::permission_denied(StatusCode::UNAUTHORIZED),
+            ApiKeyRejection::IpNotAllowed => Self::permission_denied(StatusCode::FORBIDDEN),
+            ApiKeyRejection::RateLimited(_) => Self {
+                status: StatusCode::TOO_MANY_REQUESTS,
+                code: -1003,
+                msg: "Too many requests.".to_string(),
+            },
+        }
+    }
+}
+
+impl IntoResponse for BinanceError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(json!({ "code": self.code, "msg": self.msg })),
+        )
+            .into_response()
+    }
+}
+
+type BinanceResult<T> = Result<Json<T>, BinanceError>;
+
+/// 请求参数：查询字符串与 `application/x-www-form-urlencoded` 请求体合并
+struct Params(HashMap<String, String>);
+
+impl Params {
+    fn parse(query: Option<&str>, body: &[u8]) -> Self {
+        let query = form_urlencoded::parse(query.unwrap_or_default().as_bytes());
+        let body = form_urlencoded::parse(body);
+        Self(
+            query
+                .chain(body)
+                .map(|(name, value)| (name.into_owned(), value.into_owned()))
+                .collect(),
+        )
+    }
+
+    fn optional(&self, name: &str) -> Option<&str> {
+        self.0
+            .get(name)
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+    }
+
+    fn required(&self, name: &str) -> Result<&str, BinanceError> {
+        self.optional(name)
+            .ok_or_else(|| BinanceError::mandatory(name))
+    }
+
+    fn decimal(&self, name: &str) -> Result<Option<f64>, BinanceError> {
+        self.optional(name)
+            .map(|value| {
+                if is_decimal(value) {
+                    value.parse().map_err(|_| BinanceError::mandatory(name))
+                } else {
+                    Err(BinanceError::illegal(
+                        name,
+                        "^([0-9]{1,20})(\\.[0-9]{1,20})?$",
+                    ))
+                }
+            })
+            .transpose()
+    }
+
+    fn integer<T: FromStr>(&self, name: &str) -> Result<Option<T>, BinanceError> {
+        self.optional(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| BinanceError::illegal(name, "^[0-9]{1,20}$"))
+            })
+            .transpose()
+    }
+
+    fn time(&self, name: &str) -> Result<Option<DateTime<Utc>>, BinanceError> {
+        self.integer::<i64>(name)?
+            .map(|ms| {
+                DateTime::from_timestamp_millis(ms)
+                    .ok_or_else(|| BinanceError::illegal(name, "^[0-9]{1,20}$"))
+            })
+            .transpose()
+    }
+
+    fn symbol(&self) -> Result<Symbol, BinanceError> {
+        self.required("symbol")?
+            .parse()
+            .map_err(|_| BinanceError::bad_request(-1121, "Invalid symbol."))
+    }
+}
+
+/// Binance 对数值参数的格式要求：整数部分和小数部分各 1 到 20 位数字
+fn is_decimal(value: &str) -> bool {
+    let digits =
+        |part: &str| (1..=20).contains(&part.len()) && part.bytes().all(|b| b.is_ascii_digit());
+    let mut parts = value.splitn(2, '.');
+    parts.next().is_some_and(digits) && parts.next().is_none_or(digits)
+}
+
+/// Binance 接口的调用方
+///
+/// 服务器认证中间件没有认出调用方时，用 API key 存储校验 `X-MBX-APIKEY`
+struct BinanceCaller {
+    caller: Caller,
+    api_key: Option<String>,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for BinanceCaller {
+    type Rejection = BinanceError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let api_key = parts
+            .headers
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string);
+        let caller = parts
+            .extensions
+            .get::<Caller>()
+            .cloned()
+            .unwrap_or(Caller::Anonymous);
+        let store = parts.extensions.get::<Arc<ApiKeyStore>>();
+        let caller = match (caller, &api_key, store) {
+            (Caller::Anonymous, Some(api_key), Some(store)) => {
+                let ip = parts
+                    .extensions
+                    .get::<ConnectInfo<SocketAddr>>()
+                    .map(|ConnectInfo(addr)| addr.ip());
+                store
+                    .authenticate(api_key, ip, Utc::now())
+                    .map(Caller::ApiKey)
+                    .map_err(BinanceError::from)?
+            }
+            (caller, _, _) => caller,
+        };
+        Ok(Self { caller, api_key })
+    }
+}
+
+impl BinanceCaller {
+    /// 调用方对应的用户，须有接口所需的权限范围；未启用认证时为 `X-MBX-APIKEY` 的值
+    fn user_id(&self, scope: ApiKeyScope) -> Result<String, BinanceError> {
+        let user_id = match &self.caller {
+            Caller::Unrestricted => self
+                .api_key
+                .clone()
+                .ok_or_else(BinanceError::api_key_format)?,
+            Caller::Anonymous => return Err(BinanceError::api_key_format()),
+            Caller::User(user_id) => user_id.clone(),
+            Caller::ApiKey(key) => key.user_id.clone(),
+        };
+        self.caller
+            .authorize(&user_id, scope)
+            .map_err(BinanceError::permission_denied)?;
+        Ok(user_id)
+    }
+}
+
+/// 数量和价格统一输出 8 位小数的字符串
+fn decimal(value: f64) -> String {
+    format!("{:.8}", value)
+}
+
+fn side_name(side: OrderSide) -> &'static str {
+    match side {
+        OrderSide::Buy => "BUY",
+        OrderSide::Sell => "SELL",
+    }
+}
+
+fn status_name(status: OrderStatus) -> &'static str {
+    match status {
+        OrderStatus::New => "NEW",
+        OrderStatus::PartiallyFilled => "PARTIALLY_FILLED",
+        OrderStatus::Filled => "FILLED",
+        OrderStatus::Cancelled => "CANCELED",
+        OrderStatus::Rejected => "REJECTED",
+        OrderStatus::Expired => "EXPIRED",
+    }
+}
+
+fn type_name(order: &Order) -> &'static str {
+    match (order.order_type, order.price.is_some()) {
+        (OrderType::Limit, _) => "LIMIT",
+        (OrderType::Market, _) => "MARKET",
+        (OrderType::StopLoss | OrderType::TrailingStop, false) => "STOP_LOSS",
+        (OrderType::StopLoss | OrderType::TrailingStop, true) => "STOP_LOSS_LIMIT",
+        (OrderType::TakeProfit, false) => "TAKE_PROFIT",
+        (OrderType::TakeProfit, true) => "TAKE_PROFIT_LIMIT",
+    }
+}
+
+/// 客户端订单ID：下单时传入的 `newClientOrderId`，未传时为订单ID
+fn client_order_id(order: &Order) -> String {
+    order
+        .external_id
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| order.id.to_string())
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FillResponse {
+    price: String,
+    qty: String,
+    commission: String,
+    commission_asset: String,
+    trade_id: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OrderResponse {
+    symbol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    orig_client_order_id: Option<String>,
+    order_id: OrderId,
+    order_list_id: i64,
+    client_order_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transact_time: Option<i64>,
+    price: String,
+    orig_qty: String,
+    executed_qty: String,
+    cummulative_quote_qty: String,
+    status: &'static str,
+    time_in_force: &'static str,
+    #[serde(rename = "type")]
+    order_type: &'static str,
+    side: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_price: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    update_time: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    is_working: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    orig_quote_order_qty: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fills: Option<Vec<FillResponse>>,
+}
+
+impl OrderResponse {
+    fn new(order: &Order, fills: &[Fill]) -> Self {
+        Self {
+            symbol: order.symbol.to_string(),
+            orig_client_order_id: None,
+            order_id: order.id,
+            order_list_id: -1,
+            client_order_id: client_order_id(order),
+            transact_time: None,
+            price: decimal(order.price.unwrap_or(0.0)),
+            orig_qty: decimal(order.quantity),
+            executed_qty: decimal(order.filled_quantity),
+            cummulative_quote_qty: decimal(
+                fills.iter().map(|fill| fill.price * fill.quantity).sum(),
+            ),
+            status: status_name(order.status),
+            time_in_force: "GTC",
+            order_type: type_name(order),
+            side: side_name(order.side),
+            stop_price: None,
+            time: None,
+            update_time: None,
+            is_working: None,
+            orig_quote_order_qty: None,
+            fills: None,
+        }
+    }
+
+    /// 查单接口的完整订单信息
+    fn detailed(order: &Order, fills: &[Fill]) -> Self {
+        let time = order.timestamp.timestamp_millis();
+        let update_time = fills
+            .iter()
+            .map(|fill| fill.timestamp.timestamp_millis())
+            .max()
+            .unwrap_or(time)
+            .max(time);
+        Self {
+            stop_price: Some(decimal(order.stop_price.unwrap_or(0.0))),
+            time: Some(time),
+            update_time: Some(update_time),
+            // 未触发的条件单不在订单簿中
+            is_working: Some(!order.order_type.is_trigger()),
+            orig_quote_order_qty: Some(decimal(order.quote_quantity.unwrap_or(0.0))),
+            ..Self::new(order, fills)
+        }
+    }
+}
+
+/// 订单的所有成交，按成交ID从旧到新排列
+fn order_fills(engine: &MatchingEngine, order: &Order) -> Vec<Fill> {
+    let filter = FillFilter {
+        symbol: Some(order.symbol),
+        ..Default::default()
+    };
+    let page = PageRequest {
+        cursor: None,
+        limit: usize::MAX,
+    };
+    let mut fills: Vec<Fill> = engine
+        .query_user_fills(&order.user_id, &filter, page)
+        .items
+        .into_iter()
+        .filter(|fill| fill.order_id == order.id)
+        .collect();
+    fills.reverse();
+    fills
+}
+
+/// 按 `orderId` 或 `origClientOrderId` 查找当前用户在该交易对上的订单
+fn find_order(
+    engine: &MatchingEngine,
+    params: &Params,
+    user_id: &str,
+) -> Result<Order, BinanceError> {
+    let symbol = params.symbol()?;
+    let order_id = match params.integer::<OrderId>("orderId")? {
+        Some(order_id) => order_id,
+        None => {
+            let client_order_id = params.optional("origClientOrderId").ok_or_else(|| {
+                BinanceError::bad_request(
+                    -1102,
+                    "Param 'origClientOrderId' or 'orderId' must be sent, but both were empty/null!",
+                )
+            })?;
+            Uuid::parse_str(client_order_id)
+                .ok()
+                .and_then(|external_id| engine.resolve_external_id(external_id))
+                .ok_or_else(BinanceError::no_such_order)?
+        }
+    };
+    engine
+        .get_order(order_id)
+        .filter(|order| order.user_id == user_id && order.symbol == symbol)
+        .ok_or_else(BinanceError::no_such_order)
+}
+
+/// 把 Binance 下单参数转换为引擎订单
+fn parse_new_order(params: &Params, user_id: String) -> Result<Order, BinanceError> {
+    let symbol = params.symbol()?;
+    let side = match params.required("side")? {
+        "BUY" => OrderSide::Buy,
+        "SELL" => OrderSide::Sell,
+        _ => return Err(BinanceError::bad_request(-1117, "Invalid side.")),
+    };
+    match params.optional("timeInForce") {
+        None | Some("GTC") => {}
+        Some(_) => return Err(BinanceError::bad_request(-1115, "Invalid timeInForce.")),
+    }
+
+    let order_type = params.required("type")?;
+    let (engine_type, limit_price, stop_price) = match order_type {
+        "LIMIT" => (OrderType::Limit, true, false),
+        "MARKET" => (OrderType::Market, false, false),
+        "STOP_LOSS" => (OrderType::StopLoss, false, true),
+        "STOP_LOSS_LIMIT" => (OrderType::StopLoss, true, true),
+        "TAKE_PROFIT" => (OrderType::TakeProfit, false, true),
+        "TAKE_PROFIT_LIMIT" => (OrderType::TakeProfit, true, true),
+        _ => return Err(BinanceError::bad_request(-1116, "Invalid orderType.")),
+    };
+    let price = if limit_price {
+        Some(
+            params
+                .decimal("price")?
+                .ok_or_else(|| BinanceError::mandatory("price"))?,
+        )
+    } else {
+        None
+    };
+
+    let quantity = params.decimal("quantity")?;
+    let quote_quantity = params.decimal("quoteOrderQty")?;
+    let mut order = match (quantity, quote_quantity) {
+        (Some(quantity), None) => Order::new(symbol, side, engine_type, quantity, price, user_id),
+        (None, Some(quote_quantity)) if engine_type == OrderType::Market => {
+            Order::new(symbol, side, engine_type, 0.0, None, user_id)
+                .with_quote_quantity(quote_quantity)
+        }
+        _ => return Err(BinanceError::mandatory("quantity")),
+    };
+
+    if stop_price {
+        let stop_price = params
+            .decimal("stopPrice")?
+            .ok_or_else(|| BinanceError::mandatory("stopPrice"))?;
+        order = order.with_stop_price(stop_price);
+    }
+    if let Some(client_order_id) = params.optional("newClientOrderId") {
+        let external_id = Uuid::parse_str(client_order_id)
+            .map_err(|_| BinanceError::illegal("newClientOrderId", "UUID"))?;
+        order = order.with_external_id(external_id);
+    }
+    Ok(order.with_channel(OrderChannel::Rest))
+}
+
+/// 下单（`POST /api/v3/order`）
+///
+/// 支持 `LIMIT`、`MARKET`、`STOP_LOSS(_LIMIT)` 和 `TAKE_PROFIT(_LIMIT)`，只支持 `GTC`。
+/// `newClientOrderId` 必须是 UUID。`newOrderRespType` 默认对限价单和市价单为 `FULL`，
+/// 其余为 `ACK`
+async fn new_order(
+    State(engine): State<Arc<MatchingEngine>>,
+    caller: BinanceCaller,
+    RawQuery(query): RawQuery,
+    body: Bytes,
+) -> BinanceResult<Value> {
+    let user_id = caller.user_id(ApiKeyScope::Trade)?;
+    let params = Params::parse(query.as_deref(), &body);
+    let order = parse_new_order(&params, user_id)?;
+    let response_type = match params.optional("newOrderRespType") {
+        Some(response_type @ ("ACK" | "RESULT" | "FULL")) => response_type,
+        Some(_) => {
+            return Err(BinanceError::illegal(
+                "newOrderRespType",
+                "ACK, RESULT, FULL",
+            ))
+        }
+        None if order.order_type.is_trigger() => "ACK",
+        None => "FULL",
+    };
+
+    let order_id = order.id;
+    engine
+        .submit_order(order)
+        .await
+        .map_err(|e| BinanceError::bad_request(-2010, e))?;
+    let order = engine
+        .get_order(order_id)
+        .ok_or_else(BinanceError::no_such_order)?;
+    let transact_time = engine.now().timestamp_millis();
+
+    if response_type == "ACK" {
+        return Ok(Json(json!({
+            "symbol": order.symbol.to_string(),
+            "orderId": order.id,
+            "orderListId": -1,
+            "clientOrderId": client_order_id(&order),
+            "transactTime": transact_time,
+        })));
+    }
+
+    let fills = order_fills(&engine, &order);
+    let mut response = OrderResponse::new(&order, &fills);
+    response.transact_time = Some(transact_time);
+    if response_type == "FULL" {
+        response.fills = Some(
+            fills
+                .iter()
+                .map(|fill| FillResponse {
+                    price: decimal(fill.price),
+                    qty: decimal(fill.quantity),
+                    commission: decimal(fill.fee),
+                    commission_asset: fill.fee_asset.clone(),
+                    trade_id: fill.trade_id,
+                })
+                .collect(),
+        );
+    }
+    Ok(Json(serde_json::to_value(response).unwrap_or_default()))
+}
+
+/// 查单（`GET /api/v3/order`），按 `orderId` 或 `origClientOrderId` 查找
+async fn query_order(
+    State(engine): State<Arc<MatchingEngine>>,
+    caller: BinanceCaller,
+    RawQuery(query): RawQuery,
+) -> BinanceResult<OrderResponse> {
+    let user_id = caller.user_id(ApiKeyScope::Read)?;
+    let params = Params::parse(query.as_deref(), &[]);
+    let order = find_order(&engine, &params, &user_id)?;
+    let fills = order_fills(&engine, &order);
+    Ok(Json(OrderResponse::detailed(&order, &fills)))
+}
+
+/// 撤单（`DELETE /api/v3/order`）
+async fn cancel_order(
+    State(engine): State<Arc<MatchingEngine>>,
+    caller: BinanceCaller,
+    RawQuery(query): RawQuery,
+    body: Bytes,
+) -> BinanceResult<OrderResponse> {
+    let user_id = caller.user_id(ApiKeyScope::Trade)?;
+    let params = Params::parse(query.as_deref(), &body);
+    let order = find_order(&engine, &params, &user_id)?;
+    let order = engine
+        .cancel_order(order.id, user_id)
+        .await
+        .map_err(|e| BinanceError::bad_request(-2011, e))?;
+
+    let fills = order_fills(&engine, &order);
+    let mut response = OrderResponse::new(&order, &fills);
+    response.orig_client_order_id = Some(response.client_order_id.clone());
+    response.transact_time = Some(engine.now().timestamp_millis());
+    Ok(Json(response))
+}
+
+async fn ping() -> Json<Value> {
+    Json(json!({}))
+}
+
+async fn server_time(State(engine): State<Arc<MatchingEngine>>) -> Json<Value> {
+    Json(json!({ "serverTime": engine.now().timestamp_millis() }))
+}
+
+fn price_levels(levels: &[PriceLevel]) -> Vec<[String; 2]> {
+    levels
+        .iter()
+        .map(|level| [decimal(level.price), decimal(level.total_quantity)])
+        .collect()
+}
+
+/// 订单簿深度（`GET /api/v3/depth`），`limit` 默认 100，最大 5000
+///
+/// `lastUpdateId` 取引擎单调时钟读数，只保证单调递增，不是逐笔更新序号
+async fn depth(
+    State(engine): State<Arc<MatchingEngine>>,
+    RawQuery(query): RawQuery,
+) -> BinanceResult<Value> {
+    let params = Params::parse(query.as_deref(), &[]);
+    let symbol = params.symbol()?;
+    let limit = params
+        .integer::<usize>("limit")?
+        .unwrap_or(DEFAULT_DEPTH_LIMIT)
+        .clamp(1, MAX_DEPTH_LIMIT);
+
+    let (bids, asks) = engine
+        .get_orderbook_depth(&symbol, Some(limit))
+        .map(|depth| (price_levels(&depth.bids), price_levels(&depth.asks)))
+        .unwrap_or_default();
+    Ok(Json(json!({
+        "lastUpdateId": engine.monotonic_nanos(),
+        "bids": bids,
+        "asks": asks,
+    })))
+}
+
+/// K线周期（毫秒）
+fn interval_ms(interval: &str) -> Option<i64> {
+    const MINUTE: i64 = 60 * 1000;
+    let minutes = match interval {
+        "1m" => 1,
+        "3m" => 3,
+        "5m" => 5,
+        "15m" => 15,
+        "30m" => 30,
+        "1h" => 60,
+        "2h" => 2 * 60,
+        "4h" => 4 * 60,
+        "6h" => 6 * 60,
+        "8h" => 8 * 60,
+        "12h" => 12 * 60,
+        "1d" => 24 * 60,
+        "3d" => 3 * 24 * 60,
+        "1w" => 7 * 24 * 60,
+        _ => return None,
+    };
+    Some(minutes * MINUTE)
+}
+
+/// 一根K线的汇总数据
+#[derive(Debug, Clone, PartialEq)]
+struct Kline {
+    open_time: i64,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+    quote_volume: f64,
+    count: u64,
+    taker_buy_volume: f64,
+    taker_buy_quote_volume: f64,
+}
+
+impl Kline {
+    fn to_row(&self, interval: i64) -> Value {
+        json!([
+            self.open_time,
+            decimal(self.open),
+            decimal(self.high),
+            decimal(self.low),
+            decimal(self.close),
+            decimal(self.volume),
+            self.open_time + interval - 1,
+            decimal(self.quote_volume),
+            self.count,
+            decimal(self.taker_buy_volume),
+            decimal(self.taker_buy_quote_volume),
+            "0",
+        ])
+    }
+}
+
+/// 把按时间升序排列的成交聚合为K线，没有成交的周期不生成K线
+///
+/// 周期按 UTC 对齐，周线从周一开始
+fn build_klines(trades: &[Trade], interval: i64) -> Vec<Kline> {
+    // 1970-01-01 是周四，周线向后偏移 4 天对齐到周一
+    let offset = if interval == 7 * DAY_MS {
+        4 * DAY_MS
+    } else {
+        0
+    };
+    let mut klines: Vec<Kline> = Vec::new();
+    for trade in trades {
+        let time = trade.timestamp.timestamp_millis();
+        let open_time = (time - offset).div_euclid(interval) * interval + offset;
+        let quote = trade.price * trade.quantity;
+        let (taker_buy_volume, taker_buy_quote_volume) = if taker_side(trade) == OrderSide::Buy {
+            (trade.quantity, quote)
+        } else {
+            (0.0, 0.0)
+        };
+        match klines.last_mut() {
+            Some(kline) if kline.open_time == open_time => {
+                kline.high = kline.high.max(trade.price);
+                kline.low = kline.low.min(trade.price);
+                kline.close = trade.price;
+                kline.volume += trade.quantity;
+                kline.quote_volume += quote;
+                kline.count += 1;
+                kline.taker_buy_volume += taker_buy_volume;
+                kline.taker_buy_quote_volume += taker_buy_quote_volume;
+            }
+            _ => klines.push(Kline {
+                open_time,
+                open: trade.price,
+                high: trade.price,
+                low: trade.price,
+                close: trade.price,
+                volume: trade.quantity,
+                quote_volume: quote,
+                count: 1,
+                taker_buy_volume,
+                taker_buy_quote_volume,
+            }),
+        }
+    }
+    klines
+}
+
+/// 按时间升序返回交易对在 [start_time, end_time) 内的成交
+fn trades_between(
+    engine: &MatchingEngine,
+    symbol: Symbol,
+    start_time: Option<DateTime<Utc>>,
+    end_time: Option<DateTime<Utc>>,
+) -> Vec<Trade> {
+    let filter = TradeFilter {
+        symbol: Some(symbol),
+        start_time,
+        end_time,
+        ..Default::default()
+    };
+    let page = PageRequest {
+        cursor: None,
+        limit: usize::MAX,
+    };
+    let mut trades = engine.query_trades(&filter, page).items;
+    trades.reverse();
+    trades
+}
+
+/// K线（`GET /api/v3/klines`），由成交记录聚合
+///
+/// 传入 `startTime` 时返回从该时间开始的前 `limit` 根，否则返回最近的 `limit` 根
+async fn klines(
+    State(engine): State<Arc<MatchingEngine>>,
+    RawQuery(query): RawQuery,
+) -> BinanceResult<Vec<Value>> {
+    let params = Params::parse(query.as_deref(), &[]);
+    let symbol = params.symbol()?;
+    let interval = interval_ms(params.required("interval")?)
+        .ok_or_else(|| BinanceError::bad_request(-1120, "Invalid interval."))?;
+    let start_time = params.time("startTime")?;
+    // Binance 的 endTime 包含端点
+    let end_time = params
+        .time("endTime")?
+        .map(|end| end + chrono::Duration::milliseconds(1));
+    let limit = params
+        .integer::<usize>("limit")?
+        .unwrap_or(DEFAULT_KLINE_LIMIT)
+        .clamp(1, MAX_KLINE_LIMIT);
+
+    let trades = trades_between(&engine, symbol, start_time, end_time);
+    let mut klines = build_klines(&trades, interval);
+    if start_time.is_some() {
+        klines.truncate(limit);
+    } else {
+        klines.drain(..klines.len().saturating_sub(limit));
+    }
+    Ok(Json(
+        klines.iter().map(|kline| kline.to_row(interval)).collect(),
+    ))
+}
+
+/// 交易对最近 24 小时的滚动行情
+fn ticker(engine: &MatchingEngine, symbol: Symbol, now: DateTime<Utc>) -> Value {
+    let open_time = now - chrono::Duration::milliseconds(DAY_MS);
+    let trades = trades_between(engine, symbol, Some(open_time), None);
+    let prev_close = engine
+        .query_trades(
+            &TradeFilter {
+                symbol: Some(symbol),
+                end_time: Some(open_time),
+                ..Default::default()
+            },
+            PageRequest {
+                cursor: None,
+                limit: 1,
+            },
+        )
+        .items
+        .first()
+        .map_or(0.0, |trade| trade.price);
+    let book = engine.get_book_ticker(&symbol);
+
+    let open = trades.first().map_or(0.0, |trade| trade.price);
+    let last = trades.last().map_or(0.0, |trade| trade.price);
+    let high = trades.iter().map(|trade| trade.price).fold(0.0, f64::max);
+    let low = trades
+        .iter()
+        .map(|trade| trade.price)
+        .reduce(f64::min)
+        .unwrap_or(0.0);
+    let volume: f64 = trades.iter().map(|trade| trade.quantity).sum();
+    let quote_volume: f64 = trades
+        .iter()
+        .map(|trade| trade.price * trade.quantity)
+        .sum();
+    let change = last - open;
+    let change_percent = if open > 0.0 {
+        change / open * 100.0
+    } else {
+        0.0
+    };
+    let weighted_avg = if volume > 0.0 {
+        quote_volume / volume
+    } else {
+        0.0
+    };
+
+    json!({
+        "symbol": symbol.to_string(),
+        "priceChange": decimal(change),
+        "priceChangePercent": format!("{:.3}", change_percent),
+        "weightedAvgPrice": decimal(weighted_avg),
+        "prevClosePrice": decimal(prev_close),
+        "lastPrice": decimal(last),
+        "lastQty": decimal(trades.last().map_or(0.0, |trade| trade.quantity)),
+        "bidPrice": decimal(book.as_ref().and_then(|book| book.bid_price).unwrap_or(0.0)),
+        "bidQty": decimal(book.as_ref().map_or(0.0, |book| book.bid_quantity)),
+        "askPrice": decimal(book.as_ref().and_then(|book| book.ask_price).unwrap_or(0.0)),
+        "askQty": decimal(book.as_ref().map_or(0.0, |book| book.ask_quantity)),
+        "openPrice": decimal(open),
+        "highPrice": decimal(high),
+        "lowPrice": decimal(low),
+        "volume": decimal(volume),
+        "quoteVolume": decimal(quote_volume),
+        "openTime": open_time.timestamp_millis(),
+        "closeTime": now.timestamp_millis(),
+        "firstId": trades.first().map_or(-1, |trade| trade.id as i64),
+        "lastId": trades.last().map_or(-1, |trade| trade.id as i64),
+        "count": trades.len(),
+    })
+}
+
+/// 24 小时滚动行情（`GET /api/v3/ticker/24hr`），不传 `symbol` 时返回所有交易对
+async fn ticker_24hr(
+    State(engine): State<Arc<MatchingEngine>>,
+    RawQuery(query): RawQuery,
+) -> BinanceResult<Value> {
+    let params = Params::parse(query.as_deref(), &[]);
+    let now = engine.now();
+    if params.optional("symbol").is_some() {
+        return Ok(Json(ticker(&engine, params.symbol()?, now)));
+    }
+
+    let mut symbols: Vec<Symbol> = engine.get_all_market_data().into_keys().collect();
+    symbols.sort_by_key(|symbol| symbol.to_string());
+    Ok(Json(Value::Array(
+        symbols
+            .into_iter()
+            .map(|symbol| ticker(&engine, symbol, now))
+            .collect(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{header, Method, Request};
+    use tower::ServiceExt;
+
+    /// 未经服务器中间件的路由，与未启用认证的服务器一样以 `X-MBX-APIKEY` 作为用户
+    fn open_router(engine: Arc<MatchingEngine>) -> Router {
+        create_binance_router(engine).layer(axum::Extension(Caller::Unrestricted))
+    }
+
+    async fn call(
+        router: &Router,
+        method: Method,
+        uri: &str,
+        api_key: Option<&str>,
+        body: &str,
+    ) -> (StatusCode, Value) {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded");
+        if let Some(api_key) = api_key {
+            request = request.header(API_KEY_HEADER, api_key);
+        }
+        let response = router
+            .clone()
+            .oneshot(request.body(Body::from(body.to_string())).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[test]
+    fn test_is_decimal() {
+        assert!(is_decimal("1"));
+        assert!(is_decimal("50000.12345678"));
+        assert!(!is_decimal("1e5"));
+        assert!(!is_decimal("-1"));
+        assert!(!is_decimal(".5"));
+        assert!(!is_decimal("1."));
+        assert!(!is_decimal("1.2.3"));
+    }
+
+    #[tokio::test]
+    async fn test_order_lifecycle() {
+        let engine = Arc::new(MatchingEngine::new());
+        let router = open_router(Arc::clone(&engine));
+
+        let (status, maker) = call(
+            &router,
+            Method::POST,
+            "/api/v3/order",
+            Some("alice"),
+            "symbol=BTCUSDT&side=SELL&type=LIMIT&timeInForce=GTC&quantity=2&price=50000",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(maker["status"], "NEW");
+        assert_eq!(maker["origQty"], "2.00000000");
+        let maker_id = maker["orderId"].as_u64().unwrap();
+
+        let (status, taker) = call(
+            &router,
+            Method::POST,
+            "/api/v3/order?symbol=BTCUSDT&side=BUY&type=MARKET&quantity=2",
+            Some("bob"),
+            "",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(taker["status"], "FILLED");
+        assert_eq!(taker["executedQty"], "2.00000000");
+        assert_eq!(taker["cummulativeQuoteQty"], "100000.00000000");
+        assert_eq!(taker["fills"].as_array().unwrap().len(), 1);
+        assert_eq!(taker["fills"][0]["price"], "50000.00000000");
+        assert_eq!(taker["fills"][0]["commissionAsset"], "USDT");
+
+        let uri = format!("/api/v3/order?symbol=BTCUSDT&orderId={}", maker_id);
+        let (status, queried) = call(&router, Method::GET, &uri, Some("alice"), "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(queried["status"], "FILLED");
+        assert_eq!(queried["executedQty"], "2.00000000");
+        assert_eq!(queried["cummulativeQuoteQty"], "100000.00000000");
+
+        // 其他用户查不到该订单
+        let (status, error) = call(&router, Method::GET, &uri, Some("bob"), "").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["code"], -2013);
+
+        let (_, resting) = call(
+            &router,
+            Method::POST,
+            "/api/v3/order",
+            Some("alice"),
+            "symbol=BTCUSDT&side=SELL&type=LIMIT&quantity=1&price=51000",
+        )
+        .await;
+        let resting_id = resting["orderId"].as_u64().unwrap();
+        let uri = format!("/api/v3/order?symbol=BTCUSDT&orderId={}", resting_id);
+        let (status, cancelled) = call(&router, Method::DELETE, &uri, Some("alice"), "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(cancelled["status"], "CANCELED");
+        assert_eq!(
+            engine.get_order(resting_id).unwrap().status,
+            OrderStatus::Cancelled
+        );
+
+        let (status, error) = call(&router, Method::DELETE, &uri, Some("alice"), "").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["code"], -2011);
+    }
+
+    #[tokio::test]
+    async fn test_client_order_id_and_errors() {
+        let engine = Arc::new(MatchingEngine::new());
+        let router = open_router(engine);
+        let client_order_id = Uuid::new_v4();
+
+        let body = format!(
+            "symbol=ETHUSDT&side=BUY&type=LIMIT&quantity=1&price=3000&newClientOrderId={}&newOrderRespType=ACK",
+            client_order_id
+        );
+        let (status, ack) =
+            call(&router, Method::POST, "/api/v3/order", Some("alice"), &body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ack["clientOrderId"], client_order_id.to_string());
+        assert!(ack.get("status").is_none());
+
+        let uri = format!(
+            "/api/v3/order?symbol=ETHUSDT&origClientOrderId={}",
+            client_order_id
+        );
+        let (status, queried) = call(&router, Method::GET, &uri, Some("alice"), "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(queried["orderId"], ack["orderId"]);
+
+        let cases = [
+            (
+                None,
+                "symbol=ETHUSDT&side=BUY&type=MARKET&quantity=1",
+                -2014,
+            ),
+            (
+                Some("alice"),
+                "symbol=ETH&side=BUY&type=MARKET&quantity=1",
+                -1121,
+            ),
+            (
+                Some("alice"),
+                "symbol=ETHUSDT&side=LONG&type=MARKET&quantity=1",
+                -1117,
+            ),
+            (
+                Some("alice"),
+                "symbol=ETHUSDT&side=BUY&type=OCO&quantity=1",
+                -1116,
+            ),
+            (
+                Some("alice"),
+                "symbol=ETHUSDT&side=BUY&type=LIMIT&timeInForce=IOC&quantity=1&price=1",
+                -1115,
+            ),
+            (
+                Some("alice"),
+                "symbol=ETHUSDT&side=BUY&type=LIMIT&quantity=1",
+                -1102,
+            ),
+            (
+                Some("alice"),
+                "symbol=ETHUSDT&side=BUY&type=MARKET&quantity=1e3",
+                -1100,
+            ),
+            (
+                Some("alice"),
+                "symbol=ETHUSDT&side=SELL&type=LIMIT&quantity=1&price=0",
+                -2010,
+            ),
+        ];
+        for (api_key, body, code) in cases {
+            let (_, error) = call(&router, Method::POST, "/api/v3/order", api_key, body).await;
+            assert_eq!(error["code"], code, "{}", body);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_market_data() {
+        let engine = Arc::new(MatchingEngine::new());
+        let symbol = Symbol::new("BTC", "USDT");
+        for (price, quantity) in [(100.0, 1.0), (110.0, 2.0)] {
+            let maker = Order::new(
+                symbol,
+                OrderSide::Sell,
+                OrderType::Limit,
+                quantity,
+                Some(price),
+                "maker".to_string(),
+            );
+            engine.submit_order(maker).await.unwrap();
+            let taker = Order::new(
+                symbol,
+                OrderSide::Buy,
+                OrderType::Market,
+                quantity,
+                None,
+                "taker".to_string(),
+            );
+            engine.submit_order(taker).await.unwrap();
+        }
+        let resting = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            3.0,
+            Some(90.0),
+            "maker".to_string(),
+        );
+        engine.submit_order(resting).await.unwrap();
+        let router = create_binance_router(engine);
+
+        let (status, depth) = call(
+            &router,
+            Method::GET,
+            "/api/v3/depth?symbol=BTCUSDT&limit=5",
+            None,
+            "",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(depth["bids"], json!([["90.00000000", "3.00000000"]]));
+        assert_eq!(depth["asks"], json!([]));
+
+        let (status, klines) = call(
+            &router,
+            Method::GET,
+            "/api/v3/klines?symbol=BTCUSDT&interval=1w",
+            None,
+            "",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let row = &klines[0];
+        assert_eq!(klines.as_array().unwrap().len(), 1);
+        assert_eq!(row[1], "100.00000000");
+        assert_eq!(row[2], "110.00000000");
+        assert_eq!(row[4], "110.00000000");
+        assert_eq!(row[5], "3.00000000");
+        assert_eq!(row[8], 2);
+        assert_eq!(row[9], "3.00000000");
+        // 周线从周一 00:00 UTC 开始
+        let open_time = DateTime::from_timestamp_millis(row[0].as_i64().unwrap()).unwrap();
+        assert_eq!(open_time.format("%a %H:%M").to_string(), "Mon 00:00");
+
+        let (_, error) = call(
+            &router,
+            Method::GET,
+            "/api/v3/klines?symbol=BTCUSDT&interval=7m",
+            None,
+            "",
+        )
+        .await;
+        assert_eq!(error["code"], -1120);
+
+        let (status, ticker) = call(
+            &router,
+            Method::GET,
+            "/api/v3/ticker/24hr?symbol=BTCUSDT",
+            None,
+            "",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ticker["openPrice"], "100.00000000");
+        assert_eq!(ticker["lastPrice"], "110.00000000");
+        assert_eq!(ticker["priceChangePercent"], "10.000");
+        assert_eq!(ticker["volume"], "3.00000000");
+        assert_eq!(ticker["bidPrice"], "90.00000000");
+        assert_eq!(ticker["count"], 2);
+
+        let (_, all) = call(&router, Method::GET, "/api/v3/ticker/24hr", None, "").await;
+        assert_eq!(all.as_array().unwrap().len(), 1);
+        assert_eq!(all[0]["symbol"], "BTCUSDT");
+    }
+
+    #[tokio::test]
+    async fn test_api_key_cannot_act_for_other_users() {
+        use crate::api_keys::NewApiKey;
+        use crate::config::ServerConfig;
+
+        let mut config = ServerConfig::default();
+        config.auth.enabled = true;
+        config.auth.jwt_secret = "secret".to_string();
+        config.auth.api_keys_path = std::env::temp_dir()
+            .join(format!("binance-keys-{}.json", Uuid::new_v4()))
+            .to_string_lossy()
+            .into_owned();
+        let store = ApiKeyStore::open(&config.auth.api_keys_path, 0).unwrap();
+        let key = |user_id: &str, scopes: &[ApiKeyScope]| {
+            let request = NewApiKey {
+                scopes: scopes.iter().copied().collect(),
+                ..Default::default()
+            };
+            store.create(user_id, request, Utc::now()).unwrap().api_key
+        };
+        let alice = key("alice", &[ApiKeyScope::Read, ApiKeyScope::Trade]);
+        let bob = key("bob", &[ApiKeyScope::Read, ApiKeyScope::Trade]);
+        let bob_read_only = key("bob", &[ApiKeyScope::Read]);
+
+        let engine = Arc::new(MatchingEngine::new());
+        let router =
+            crate::api::apply_server_layers(create_binance_router(Arc::clone(&engine)), &config)
+                .unwrap();
+
+        let (status, order) = call(
+            &router,
+            Method::POST,
+            "/api/v3/order",
+            Some(&bob),
+            "symbol=BTCUSDT&side=SELL&type=LIMIT&quantity=1&price=51000",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let order_id = order["orderId"].as_u64().unwrap();
+        assert_eq!(engine.get_order(order_id).unwrap().user_id, "bob");
+
+        // 用户ID不能当作 API key，alice 的 key 也撤不了 bob 的订单
+        let uri = format!("/api/v3/order?symbol=BTCUSDT&orderId={}", order_id);
+        let (status, error) = call(&router, Method::DELETE, &uri, Some("bob"), "").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(error["code"], -2015);
+        let (status, error) = call(&router, Method::DELETE, &uri, Some(&alice), "").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["code"], -2013);
+        let (status, _) = call(&router, Method::DELETE, &uri, None, "").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // 只读 key 不能撤单
+        let (status, error) = call(&router, Method::DELETE, &uri, Some(&bob_read_only), "").await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(error["code"], -2015);
+        assert_eq!(engine.get_order(order_id).unwrap().status, OrderStatus::New);
+
+        let (status, cancelled) = call(&router, Method::DELETE, &uri, Some(&bob), "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(cancelled["status"], "CANCELED");
+
+        std::fs::remove_file(&config.auth.api_keys_path).unwrap();
+    }
+}
@@ -0,0 +1,205 @@
+use chrono::{DateTime, TimeZone, Utc};
+use parking_lot::Mutex;
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// 时钟：引擎中所有与时间相关的逻辑（到期、熔断窗口、成交时间戳等）都通过它取当前时间
+pub trait Clock: Debug + Send + Sync {
+    /// 当前墙钟时间
+    fn now(&self) -> DateTime<Utc>;
+
+    /// 当前单调时钟读数，用于计算时间间隔
+    fn instant(&self) -> Instant;
+
+    /// 等待到墙钟时间 `deadline`，已过时立即完成
+    ///
+    /// 到期、结算和熔断冷却等调度任务都通过它等待，使用 `MockClock` 时随时钟推进完成
+    fn sleep_until(&self, deadline: DateTime<Utc>) -> SleepFuture<'_>;
+}
+
+/// [`Clock::sleep_until`] 返回的等待
+pub type SleepFuture<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+
+/// 共享的时钟实例
+pub type SharedClock = Arc<dyn Clock>;
+
+/// 系统时钟
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl SystemClock {
+    pub fn shared() -> SharedClock {
+        Arc::new(SystemClock)
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep_until(&self, deadline: DateTime<Utc>) -> SleepFuture<'_> {
+        let wait = (deadline - Utc::now()).to_std().unwrap_or(Duration::ZERO);
+        Box::pin(tokio::time::sleep(wait))
+    }
+}
+
+/// 手动推进的时钟，供测试和回放使用
+///
+/// 墙钟时间和单调时钟一起推进，只能向前，不会回拨；推进时唤醒等待中的 [`Clock::sleep_until`]
+#[derive(Debug)]
+pub struct MockClock {
+    base: Instant,
+    state: Mutex<MockState>,
+    advanced: Notify,
+}
+
+#[derive(Debug)]
+struct MockState {
+    now: DateTime<Utc>,
+    elapsed: Duration,
+}
+
+impl MockClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            base: Instant::now(),
+            state: Mutex::new(MockState {
+                now: start,
+                elapsed: Duration::ZERO,
+            }),
+            advanced: Notify::new(),
+        }
+    }
+
+    /// 将时钟向前推进 `duration`
+    pub fn advance(&self, duration: Duration) {
+        {
+            let mut state = self.state.lock();
+            state.now += chrono::Duration::from_std(duration).unwrap_or(chrono::Duration::MAX);
+            state.elapsed += duration;
+        }
+        self.advanced.notify_waiters();
+    }
+
+    /// 将时钟推进到 `time`，早于当前时间时保持不变
+    pub fn set(&self, time: DateTime<Utc>) {
+        let delta = {
+            let state = self.state.lock();
+            (time - state.now).to_std()
+        };
+        if let Ok(delta) = delta {
+            self.advance(delta);
+        }
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.state.lock().now
+    }
+
+    fn instant(&self) -> Instant {
+        self.base + self.state.lock().elapsed
+    }
+
+    fn sleep_until(&self, deadline: DateTime<Utc>) -> SleepFuture<'_> {
+        Box::pin(async move {
+            loop {
+                // 先登记唤醒再读时间，读取之后的推进不会漏掉
+                let advanced = self.advanced.notified();
+                tokio::pin!(advanced);
+                advanced.as_mut().enable();
+                if self.now() >= deadline {
+                    return;
+                }
+                advanced.await;
+            }
+        })
+    }
+}
+
+/// `now` 之后（不含）的下一个时间边界，按 UTC 纪元对齐到 `interval_seconds` 的整数倍
+///
+/// 间隔为 0 或边界超出可表示范围时返回 None
+pub fn next_aligned_boundary(now: DateTime<Utc>, interval_seconds: u64) -> Option<DateTime<Utc>> {
+    let interval = i64::try_from(interval_seconds)
+        .ok()
+        .filter(|&interval| interval > 0)?;
+    let next = (now.timestamp().div_euclid(interval) + 1).checked_mul(interval)?;
+    Utc.timestamp_opt(next, 0).single()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock_only_moves_forward() {
+        let start = "2024-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let clock = MockClock::new(start);
+        let instant = clock.instant();
+
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(clock.now(), start + chrono::Duration::seconds(5));
+        assert_eq!(clock.instant() - instant, Duration::from_secs(5));
+
+        // 回拨被忽略
+        clock.set(start);
+        assert_eq!(clock.now(), start + chrono::Duration::seconds(5));
+
+        clock.set(start + chrono::Duration::seconds(60));
+        assert_eq!(clock.instant() - instant, Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn test_mock_clock_sleep_completes_on_advance() {
+        let start = "2024-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let clock = Arc::new(MockClock::new(start));
+        let deadline = start + chrono::Duration::seconds(10);
+
+        let sleeper = tokio::spawn({
+            let clock = Arc::clone(&clock);
+            async move { clock.sleep_until(deadline).await }
+        });
+        tokio::task::yield_now().await;
+
+        clock.advance(Duration::from_secs(5));
+        tokio::task::yield_now().await;
+        assert!(!sleeper.is_finished());
+
+        clock.advance(Duration::from_secs(5));
+        tokio::time::timeout(Duration::from_secs(1), sleeper)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // 已过的时间立即完成
+        clock.sleep_until(start).await;
+    }
+
+    #[test]
+    fn test_next_aligned_boundary() {
+        let at = |time: &str| time.parse::<DateTime<Utc>>().unwrap();
+        let now = at("2024-01-01T10:30:00Z");
+        assert_eq!(
+            next_aligned_boundary(now, 3600),
+            Some(at("2024-01-01T11:00:00Z"))
+        );
+        // 正好在边界上时取下一个
+        assert_eq!(
+            next_aligned_boundary(at("2024-01-01T11:00:00Z"), 3600),
+            Some(at("2024-01-01T12:00:00Z"))
+        );
+        assert_eq!(next_aligned_boundary(now, 0), None);
+        assert_eq!(next_aligned_boundary(now, u64::MAX), None);
+    }
+}
@@ -0,0 +1,524 @@
+use crate::config::EngineConfig;
+use crate::engine_api::MatchingEngineApi;
+use crate::id::OrderId;
+use crate::matching_engine::MatchingEngine;
+use crate::types::*;
+use std::collections::hash_map::DefaultHasher;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tracing::{error, info};
+
+/// 每个 worker 命令队列的容量，队列已满时调用方等待
+const COMMAND_QUEUE_CAPACITY: usize = 1024;
+
+/// 发送给 worker 的写操作
+enum Command {
+    Submit {
+        order: Order,
+        reply: oneshot::Sender<Result<Vec<Trade>, String>>,
+    },
+    Cancel {
+        order_id: OrderId,
+        user_id: String,
+        reply: oneshot::Sender<Result<Order, String>>,
+    },
+    Amend {
+        order_id: OrderId,
+        user_id: String,
+        new_quantity: f64,
+        reply: oneshot::Sender<Result<Order, String>>,
+    },
+}
+
+/// 单线程撮合 worker：独占一个线程和单线程 tokio 运行时，按到达顺序逐条执行命令
+struct Worker {
+    engine: Arc<MatchingEngine>,
+    commands: mpsc::Sender<Command>,
+}
+
+/// 按交易对分片的撮合集群
+///
+/// 持有 N 个单线程撮合 worker，交易对按名称哈希固定分配到某个 worker，下单、撤单、改单
+/// 经该 worker 的命令队列串行执行，不同 worker 上的交易对并行撮合。查询直接读取对应 worker 的引擎；
+/// 各 worker 的成交、订单更新和盘口推送汇总到集群的广播通道。实现 [`MatchingEngineApi`]，
+/// 对该 trait 编程的调用方可以直接替换单个 [`MatchingEngine`]。
+///
+/// 各 worker 使用相同的引擎配置，启用审计或归档时目录按 worker 细分为 `<dir>/worker-<i>`。
+/// 集群释放后命令队列关闭，worker 执行完已入队的命令后退出
+pub struct EngineCluster {
+    workers: Vec<Worker>,
+    trade_sender: broadcast::Sender<Trade>,
+    order_sender: broadcast::Sender<Order>,
+    book_ticker_sender: broadcast::Sender<BookTicker>,
+}
+
+impl EngineCluster {
+    /// 创建 `workers` 个 worker 并启动各自的线程
+    pub fn new(config: EngineConfig, workers: usize) -> Result<Self, String> {
+        if workers == 0 {
+            return Err("Engine cluster needs at least one worker".to_string());
+        }
+
+        let (trade_sender, _) = broadcast::channel(10000);
+        let (order_sender, _) = broadcast::channel(10000);
+        let (book_ticker_sender, _) = broadcast::channel(10000);
+        let workers = (0..workers)
+            .map(|index| {
+                let engine = Arc::new(MatchingEngine::try_with_config(worker_config(
+                    &config, index,
+                ))?);
+                let (commands, receiver) = mpsc::channel(COMMAND_QUEUE_CAPACITY);
+                let forward = Forwarders {
+                    trades: (engine.subscribe_trades(), trade_sender.clone()),
+                    orders: (engine.subscribe_orders(), order_sender.clone()),
+                    book_tickers: (engine.subscribe_book_ticker(), book_ticker_sender.clone()),
+                };
+                spawn_worker(index, Arc::clone(&engine), receiver, forward)?;
+                Ok(Worker { engine, commands })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        info!("Engine cluster started with {} workers", workers.len());
+
+        Ok(Self {
+            workers,
+            trade_sender,
+            order_sender,
+            book_ticker_sender,
+        })
+    }
+
+    /// worker 数量
+    pub fn worker_count(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// 交易对分配到的 worker 序号
+    pub fn worker_index(&self, symbol: &Symbol) -> usize {
+        let mut hasher = DefaultHasher::new();
+        symbol.to_string().hash(&mut hasher);
+        (hasher.finish() % self.workers.len() as u64) as usize
+    }
+
+    /// 交易对所在 worker 的引擎，用于管理操作和集群未汇总的查询
+    pub fn engine_for(&self, symbol: &Symbol) -> &Arc<MatchingEngine> {
+        &self.workers[self.worker_index(symbol)].engine
+    }
+
+    /// 所有 worker 的引擎
+    pub fn engines(&self) -> impl Iterator<Item = &Arc<MatchingEngine>> {
+        self.workers.iter().map(|worker| &worker.engine)
+    }
+
+    /// 提交订单到交易对所在的 worker
+    pub async fn submit_order(&self, order: Order) -> Result<Vec<Trade>, String> {
+        let index = self.worker_index(&order.symbol);
+        let (reply, response) = oneshot::channel();
+        self.send(index, Command::Submit { order, reply }, response)
+            .await
+    }
+
+    /// 撤销订单，由持有该订单的 worker 执行
+    pub async fn cancel_order(&self, order_id: OrderId, user_id: String) -> Result<Order, String> {
+        let index = self.worker_for_order(order_id)?;
+        let (reply, response) = oneshot::channel();
+        self.send(
+            index,
+            Command::Cancel {
+                order_id,
+                user_id,
+                reply,
+            },
+            response,
+        )
+        .await
+    }
+
+    /// 缩减挂单的订单总量，由持有该订单的 worker 执行
+    pub async fn amend_order(
+        &self,
+        order_id: OrderId,
+        user_id: String,
+        new_quantity: f64,
+    ) -> Result<Order, String> {
+        let index = self.worker_for_order(order_id)?;
+        let (reply, response) = oneshot::channel();
+        self.send(
+            index,
+            Command::Amend {
+                order_id,
+                user_id,
+                new_quantity,
+                reply,
+            },
+            response,
+        )
+        .await
+    }
+
+    pub fn get_order(&self, order_id: OrderId) -> Option<Order> {
+        self.engines().find_map(|engine| engine.get_order(order_id))
+    }
+
+    /// 用户在所有 worker 上的订单，按订单ID排列
+    pub fn get_user_orders(&self, user_id: &str) -> Vec<Order> {
+        let mut orders: Vec<Order> = self
+            .engines()
+            .flat_map(|engine| engine.get_user_orders(user_id))
+            .collect();
+        orders.sort_unstable_by_key(|order| order.id);
+        orders
+    }
+
+    pub fn get_orderbook_depth(
+        &self,
+        symbol: &Symbol,
+        depth: Option<usize>,
+    ) -> Option<OrderBookDepth> {
+        self.engine_for(symbol).get_orderbook_depth(symbol, depth)
+    }
+
+    pub fn get_book_ticker(&self, symbol: &Symbol) -> Option<BookTicker> {
+        self.engine_for(symbol).get_book_ticker(symbol)
+    }
+
+    /// 最近成交，未指定交易对时合并所有 worker 的成交，按成交ID从新到旧排列
+    pub fn get_trades(&self, symbol: Option<&Symbol>, limit: Option<usize>) -> Vec<Trade> {
+        if let Some(symbol) = symbol {
+            return self.engine_for(symbol).get_trades(Some(symbol), limit);
+        }
+        let mut trades: Vec<Trade> = self
+            .engines()
+            .flat_map(|engine| engine.get_trades(None, limit))
+            .collect();
+        trades.sort_unstable_by_key(|trade| std::cmp::Reverse(trade.id));
+        if let Some(limit) = limit {
+            trades.truncate(limit);
+        }
+        trades
+    }
+
+    pub fn get_market_data(&self, symbol: &Symbol) -> Option<MarketData> {
+        self.engine_for(symbol).get_market_data(symbol)
+    }
+
+    /// 所有 worker 的统计之和，运行时间取最长的 worker
+    pub fn get_stats(&self) -> EngineStats {
+        self.engines().map(|engine| engine.get_stats()).fold(
+            EngineStats {
+                total_orders: 0,
+                total_trades: 0,
+                total_volume: 0.0,
+                active_orders: 0,
+                uptime_seconds: 0,
+            },
+            |mut total, stats| {
+                total.total_orders += stats.total_orders;
+                total.total_trades += stats.total_trades;
+                total.total_volume += stats.total_volume;
+                total.active_orders += stats.active_orders;
+                total.uptime_seconds = total.uptime_seconds.max(stats.uptime_seconds);
+                total
+            },
+        )
+    }
+
+    pub fn subscribe_trades(&self) -> broadcast::Receiver<Trade> {
+        self.trade_sender.subscribe()
+    }
+
+    pub fn subscribe_orders(&self) -> broadcast::Receiver<Order> {
+        self.order_sender.subscribe()
+    }
+
+    pub fn subscribe_book_ticker(&self) -> broadcast::Receiver<BookTicker> {
+        self.book_ticker_sender.subscribe()
+    }
+
+    /// 订单所在的 worker，订单ID由进程级生成器分配，在各 worker 间唯一
+    fn worker_for_order(&self, order_id: OrderId) -> Result<usize, String> {
+        self.workers
+            .iter()
+            .position(|worker| worker.engine.get_order(order_id).is_some())
+            .ok_or_else(|| "Order not found".to_string())
+    }
+
+    async fn send<T>(
+        &self,
+        index: usize,
+        command: Command,
+        response: oneshot::Receiver<Result<T, String>>,
+    ) -> Result<T, String> {
+        let stopped = || format!("Matching worker {} is not running", index);
+        self.workers[index]
+            .commands
+            .send(command)
+            .await
+            .map_err(|_| stopped())?;
+        response.await.map_err(|_| stopped())?
+    }
+}
+
+/// 把 worker 引擎的广播转发到集群通道
+struct Forwarders {
+    trades: (broadcast::Receiver<Trade>, broadcast::Sender<Trade>),
+    orders: (broadcast::Receiver<Order>, broadcast::Sender<Order>),
+    book_tickers: (
+        broadcast::Receiver<BookTicker>,
+        broadcast::Sender<BookTicker>,
+    ),
+}
+
+fn spawn_worker(
+    index: usize,
+    engine: Arc<MatchingEngine>,
+    mut commands: mpsc::Receiver<Command>,
+    forward: Forwarders,
+) -> Result<(), String> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| {
+            format!(
+                "Failed to build runtime for matching worker {}: {}",
+                index, e
+            )
+        })?;
+
+    std::thread::Builder::new()
+        .name(format!("matching-worker-{}", index))
+        .spawn(move || {
+            runtime.block_on(async move {
+                engine.start_expiry_scheduler();
+                engine.start_market_data_publisher();
+                engine.start_order_archiver();
+                engine.start_trade_evictor();
+                engine.start_margin_monitor();
+                engine.start_funding_scheduler();
+                engine.start_fee_settlement_scheduler();
+                engine.start_end_of_day_scheduler();
+                engine.start_session_scheduler();
+                spawn_forwarder(forward.trades);
+                spawn_forwarder(forward.orders);
+                spawn_forwarder(forward.book_tickers);
+
+                while let Some(command) = commands.recv().await {
+                    match command {
+                        Command::Submit { order, reply } => {
+                            let _ = reply.send(engine.submit_order(order).await);
+                        }
+                        Command::Cancel {
+                            order_id,
+                            user_id,
+                            reply,
+                        } => {
+                            let _ = reply.send(engine.cancel_order(order_id, user_id).await);
+                        }
+                        Command::Amend {
+                            order_id,
+                            user_id,
+                            new_quantity,
+                            reply,
+                        } => {
+                            let result = engine.amend_order(order_id, user_id, new_quantity).await;
+                            let _ = reply.send(result);
+                        }
+                    }
+                }
+                info!("Matching worker {} stopped", index);
+            });
+        })
+        .map(|_| ())
+        .map_err(|e| format!("Failed to spawn matching worker {}: {}", index, e))
+}
+
+fn spawn_forwarder<T: Clone + Send + 'static>(
+    (mut receiver, sender): (broadcast::Receiver<T>, broadcast::Sender<T>),
+) {
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(item) => {
+                    let _ = sender.send(item);
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    error!("Engine cluster forwarder lagged, {} updates lost", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// worker 的引擎配置，审计和归档目录按 worker 细分，避免多个 worker 写同一文件
+fn worker_config(config: &EngineConfig, index: usize) -> EngineConfig {
+    let subdir = |dir: &str| {
+        Path::new(dir)
+            .join(format!("worker-{}", index))
+            .to_string_lossy()
+            .into_owned()
+    };
+    let mut config = config.clone();
+    config.audit.dir = subdir(&config.audit.dir);
+    config.order_retention.archive_dir = subdir(&config.order_retention.archive_dir);
+    config.trade_history.dir = subdir(&config.trade_history.dir);
+    config
+}
+
+impl MatchingEngineApi for EngineCluster {
+    fn submit_order(
+        &self,
+        order: Order,
+    ) -> impl Future<Output = Result<Vec<Trade>, String>> + Send {
+        EngineCluster::submit_order(self, order)
+    }
+
+    fn cancel_order(
+        &self,
+        order_id: OrderId,
+        user_id: String,
+    ) -> impl Future<Output = Result<Order, String>> + Send {
+        EngineCluster::cancel_order(self, order_id, user_id)
+    }
+
+    fn amend_order(
+        &self,
+        order_id: OrderId,
+        user_id: String,
+        new_quantity: f64,
+    ) -> impl Future<Output = Result<Order, String>> + Send {
+        EngineCluster::amend_order(self, order_id, user_id, new_quantity)
+    }
+
+    fn get_order(&self, order_id: OrderId) -> Option<Order> {
+        EngineCluster::get_order(self, order_id)
+    }
+
+    fn get_user_orders(&self, user_id: &str) -> Vec<Order> {
+        EngineCluster::get_user_orders(self, user_id)
+    }
+
+    fn get_orderbook_depth(&self, symbol: &Symbol, depth: Option<usize>) -> Option<OrderBookDepth> {
+        EngineCluster::get_orderbook_depth(self, symbol, depth)
+    }
+
+    fn get_book_ticker(&self, symbol: &Symbol) -> Option<BookTicker> {
+        EngineCluster::get_book_ticker(self, symbol)
+    }
+
+    fn get_trades(&self, symbol: Option<&Symbol>, limit: Option<usize>) -> Vec<Trade> {
+        EngineCluster::get_trades(self, symbol, limit)
+    }
+
+    fn subscribe_trades(&self) -> broadcast::Receiver<Trade> {
+        EngineCluster::subscribe_trades(self)
+    }
+
+    fn subscribe_orders(&self) -> broadcast::Receiver<Order> {
+        EngineCluster::subscribe_orders(self)
+    }
+
+    fn subscribe_book_ticker(&self) -> broadcast::Receiver<BookTicker> {
+        EngineCluster::subscribe_book_ticker(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(symbol: Symbol, side: OrderSide, user: &str) -> Order {
+        Order::new(
+            symbol,
+            side,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            user.to_string(),
+        )
+    }
+
+    /// 找出分配到不同 worker 的两个交易对
+    fn symbols_on_different_workers(cluster: &EngineCluster) -> (Symbol, Symbol) {
+        let first = Symbol::new("BTC", "USDT");
+        let second = ["ETH", "BNB", "SOL", "XRP", "ADA", "DOGE"]
+            .into_iter()
+            .map(|base| Symbol::new(base, "USDT"))
+            .find(|symbol| cluster.worker_index(symbol) != cluster.worker_index(&first))
+            .unwrap();
+        (first, second)
+    }
+
+    #[tokio::test]
+    async fn test_cluster_routes_and_aggregates() {
+        let cluster = EngineCluster::new(EngineConfig::default(), 2).unwrap();
+        let (btc, eth) = symbols_on_different_workers(&cluster);
+        let mut trades = cluster.subscribe_trades();
+
+        for symbol in [btc, eth] {
+            cluster
+                .submit_order(order(symbol, OrderSide::Sell, "maker"))
+                .await
+                .unwrap();
+            let filled = cluster
+                .submit_order(order(symbol, OrderSide::Buy, "taker"))
+                .await
+                .unwrap();
+            assert_eq!(filled.len(), 1);
+        }
+
+        // 每个交易对只存在于自己的 worker 上
+        assert!(cluster.engine_for(&btc).get_book_ticker(&eth).is_none());
+        let mut streamed = Vec::new();
+        for _ in 0..2 {
+            streamed.push(trades.recv().await.unwrap().symbol);
+        }
+        assert!(streamed.contains(&btc) && streamed.contains(&eth));
+
+        let stats = cluster.get_stats();
+        assert_eq!(stats.total_orders, 4);
+        assert_eq!(stats.total_trades, 2);
+        assert_eq!(cluster.get_trades(None, None).len(), 2);
+        assert_eq!(cluster.get_trades(Some(&eth), None).len(), 1);
+        assert_eq!(cluster.get_user_orders("taker").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cluster_cancel_and_amend_by_order_id() {
+        let cluster = EngineCluster::new(EngineConfig::default(), 3).unwrap();
+        let (btc, eth) = symbols_on_different_workers(&cluster);
+
+        let bid = order(eth, OrderSide::Buy, "user1");
+        let bid_id = bid.id;
+        cluster.submit_order(bid).await.unwrap();
+        cluster
+            .submit_order(order(btc, OrderSide::Buy, "user1"))
+            .await
+            .unwrap();
+
+        let amended = cluster
+            .amend_order(bid_id, "user1".to_string(), 0.5)
+            .await
+            .unwrap();
+        assert_eq!(amended.remaining_quantity, 0.5);
+        let cancelled = cluster
+            .cancel_order(bid_id, "user1".to_string())
+            .await
+            .unwrap();
+        assert_eq!(cancelled.status, OrderStatus::Cancelled);
+        assert!(cluster
+            .get_orderbook_depth(&eth, None)
+            .unwrap()
+            .bids
+            .is_empty());
+        assert_eq!(
+            cluster.get_orderbook_depth(&btc, None).unwrap().bids.len(),
+            1
+        );
+
+        assert!(cluster.cancel_order(42, "user1".to_string()).await.is_err());
+        assert!(EngineCluster::new(EngineConfig::default(), 0).is_err());
+    }
+}
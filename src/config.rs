@@ -1,10 +1,18 @@
+use crate::events::EventKind;
+use crate::id::MAX_SHARD;
+use crate::types::{OrderChannel, PriceScale, Symbol};
+use chrono::{NaiveTime, Timelike};
 use config::{Config, ConfigError, Environment, File};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::env;
-use tracing::{info, warn};
+use std::path::Path;
+use tracing::info;
+use utoipa::ToSchema;
 
 /// 应用配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AppConfig {
     /// 服务器配置
     pub server: ServerConfig,
@@ -16,8 +24,29 @@ pub struct AppConfig {
     pub engine: EngineConfig,
     /// 数据库配置（预留）
     pub database: Option<DatabaseConfig>,
-    /// Redis配置（预留）
+    /// Redis配置，为空时不推送到 Redis
     pub redis: Option<RedisConfig>,
+    /// 事件发布配置
+    #[serde(default)]
+    pub events: EventsConfig,
+    /// Webhook 回调配置
+    #[serde(default)]
+    pub webhooks: WebhookConfig,
+    /// 成交监控配置
+    #[serde(default)]
+    pub surveillance: SurveillanceConfig,
+    /// 主备复制配置
+    #[serde(default)]
+    pub replication: ReplicationConfig,
+    /// 指数价格和标记价格配置
+    #[serde(default)]
+    pub price_index: PriceIndexConfig,
+    /// 成交和订单 Parquet 归档配置
+    #[serde(default)]
+    pub export: ExportConfig,
+    /// 多租户配置
+    #[serde(default)]
+    pub tenancy: TenancyConfig,
 }
 
 /// 服务器配置
@@ -37,6 +66,144 @@ pub struct ServerConfig {
     pub request_timeout: u64,
     /// 最大请求体大小（字节）
     pub max_request_size: usize,
+    /// WebSocket配置
+    #[serde(default)]
+    pub websocket: WebSocketConfig,
+    /// TLS配置
+    #[serde(default)]
+    pub tls: TlsConfig,
+    /// gRPC 配置
+    #[serde(default)]
+    pub grpc: GrpcConfig,
+    /// Binance 兼容接口配置
+    #[serde(default)]
+    pub binance: BinanceCompatConfig,
+    /// JWT 认证配置
+    #[serde(default)]
+    pub auth: AuthConfig,
+    /// 管理接口配置
+    #[serde(default)]
+    pub admin: AdminConfig,
+}
+
+/// 管理接口配置
+///
+/// `/admin/*` 接口只接受 `Authorization: Bearer <管理令牌>`，令牌对应的操作人随每次管理操作
+/// 写入审计日志；没有配置令牌时拒绝所有管理请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdminConfig {
+    /// 管理接口单独监听的端口，0 表示与公开 API 共用监听端口
+    pub port: u16,
+    /// 管理令牌
+    pub tokens: Vec<AdminToken>,
+    /// 管理操作审计日志目录，多租户模式下按租户细分到 `tenant-<id>` 子目录
+    pub audit_dir: String,
+}
+
+/// 管理令牌及其操作人
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminToken {
+    /// 操作人，记录在审计日志中
+    pub actor: String,
+    pub token: String,
+}
+
+/// JWT 认证配置
+///
+/// 启用后请求以 `Authorization: Bearer <JWT>` 认证，令牌用 `jwt_secret` 以 HS256 签名，
+/// `sub` 为用户ID；也可以用用户创建的 API key（`api_key_header` 请求头）认证，只拥有 key 的权限范围。
+/// 下单、撤单以及订单、成交、资金等按用户查询的接口只允许访问调用方自己的数据；
+/// 行情等公开接口不需要令牌
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    /// 是否启用认证，未启用时按请求中的用户ID访问，不做校验
+    pub enabled: bool,
+    /// HS256 签名密钥
+    pub jwt_secret: String,
+    /// 校验过期时间时允许的时钟偏差（秒）
+    pub leeway_seconds: u64,
+    /// 携带用户 API key 的请求头，不能与多租户的 API key 请求头相同
+    pub api_key_header: String,
+    /// 用户 API key 的保存文件
+    pub api_keys_path: String,
+    /// 未单独设置上限的 API key 每分钟的请求上限，0 表示不限制
+    pub api_key_rate_limit_per_minute: u32,
+}
+
+/// Binance 现货 API 兼容接口配置
+///
+/// 启用后在根路径下挂载 `/api/v3/*`。启用认证时请求头 `X-MBX-APIKEY` 为用户的 API key，
+/// 未启用认证时直接作为用户ID，此时只应在可信网络内启用；不校验签名
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BinanceCompatConfig {
+    /// 是否启用兼容接口
+    pub enabled: bool,
+}
+
+/// gRPC 服务配置（需以 `grpc` 特性编译）
+///
+/// 与 REST API 共用监听地址，使用独立端口
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcConfig {
+    /// 是否启用 gRPC 服务
+    pub enabled: bool,
+    /// 监听端口
+    pub port: u16,
+}
+
+/// TLS配置
+///
+/// 启用后 HTTP 和 WebSocket 均只通过 HTTPS/WSS 提供
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsConfig {
+    /// 是否启用 TLS
+    pub enabled: bool,
+    /// PEM 格式证书链路径
+    pub cert_path: String,
+    /// PEM 格式私钥路径
+    pub key_path: String,
+    /// 客户端证书 CA（PEM），设置后要求客户端出示由其签发的证书
+    #[serde(default)]
+    pub client_ca_path: Option<String>,
+    /// 检查证书文件变化并重新加载的间隔（秒），0 表示不自动重载
+    pub reload_interval_seconds: u64,
+}
+
+/// WebSocket配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSocketConfig {
+    /// 心跳（Ping）发送间隔（秒）
+    pub heartbeat_interval: u64,
+    /// 心跳超时时间（秒），超过该时间未收到 Pong 的连接会被关闭
+    pub heartbeat_timeout: u64,
+    /// 每个连接的出站消息队列容量，写满的慢消费者会被断开
+    pub outbound_queue_size: usize,
+    /// 允许连接 drop copy 数据流的访问令牌，为空时拒绝所有连接
+    #[serde(default)]
+    pub drop_copy_tokens: Vec<String>,
+    /// 每个频道保留的最近消息数，供断线重连的客户端按序号补发
+    #[serde(default = "default_replay_buffer_size")]
+    pub replay_buffer_size: usize,
+    /// 每个连接最多订阅的交易对数量
+    #[serde(default = "default_max_symbols_per_connection")]
+    pub max_symbols_per_connection: usize,
+    /// 连接可设置的最小合并间隔（毫秒）
+    #[serde(default = "default_min_conflation_ms")]
+    pub min_conflation_ms: u64,
+}
+
+fn default_max_symbols_per_connection() -> usize {
+    50
+}
+
+fn default_min_conflation_ms() -> u64 {
+    10
+}
+
+fn default_replay_buffer_size() -> usize {
+    1024
 }
 
 /// CORS配置
@@ -65,6 +232,22 @@ pub struct LoggingConfig {
     pub json_format: bool,
     /// 日志轮转配置
     pub rotation: LogRotationConfig,
+    /// OTLP 链路追踪配置
+    #[serde(default)]
+    pub otlp: OtlpConfig,
+}
+
+/// OTLP 链路追踪配置，需启用 `otel` 特性编译
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OtlpConfig {
+    /// 是否导出链路追踪数据
+    pub enabled: bool,
+    /// OTLP/HTTP 接收端地址
+    pub endpoint: String,
+    /// 上报的服务名
+    pub service_name: String,
+    /// 采样比例（0-1），上游请求已携带采样决定时以上游为准
+    pub sample_ratio: f64,
 }
 
 /// 日志轮转配置
@@ -95,6 +278,16 @@ pub struct MonitoringConfig {
     pub enable_performance_metrics: bool,
     /// 是否启用业务指标
     pub enable_business_metrics: bool,
+    /// 在主 HTTP 服务上挂载 `metrics_path`，不再单独监听 `metrics_port`
+    #[serde(default)]
+    pub serve_on_api: bool,
+    /// 交易对统计指标的刷新间隔（秒）
+    #[serde(default = "default_symbol_metrics_interval")]
+    pub symbol_metrics_interval_seconds: u64,
+}
+
+fn default_symbol_metrics_interval() -> u64 {
+    5
 }
 
 /// 撮合引擎配置
@@ -116,8 +309,671 @@ pub struct EngineConfig {
     pub max_trade_quantity: f64,
     /// 单日最大交易量
     pub max_daily_volume: f64,
-    /// 支持的交易对
+    /// 启动时上市的交易对，运行中可通过管理接口上市和下市
     pub supported_symbols: Vec<String>,
+    /// 为 true 时只接受已上市交易对的订单和场外成交申报
+    #[serde(default)]
+    pub restrict_symbols: bool,
+    /// 熔断配置
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// 成交价规则
+    #[serde(default)]
+    pub trade_price_rule: TradePriceRule,
+    /// 条件单触发和熔断使用的参考价格
+    #[serde(default)]
+    pub price_reference: PriceReferenceConfig,
+    /// 同价位挂单的成交分配配置
+    #[serde(default)]
+    pub allocation: AllocationConfig,
+    /// 用户挂单限额
+    #[serde(default)]
+    pub user_limits: UserLimitsConfig,
+    /// 终态订单保留与归档
+    #[serde(default)]
+    pub order_retention: OrderRetentionConfig,
+    /// 成交历史持久化
+    #[serde(default)]
+    pub trade_history: TradeHistoryConfig,
+    /// 成交量统计
+    #[serde(default)]
+    pub volume_stats: VolumeStatsConfig,
+    /// 订单/成交ID生成器的分片编号（0-1023），多实例部署时各实例需不同
+    #[serde(default)]
+    pub id_shard: u16,
+    /// 订单生命周期审计
+    #[serde(default)]
+    pub audit: AuditConfig,
+    /// 优雅停机
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
+    /// 成交手续费率
+    #[serde(default)]
+    pub fees: FeeConfig,
+    /// 交易对最小价格变动单位
+    #[serde(default)]
+    pub tick_sizes: TickSizeConfig,
+    /// 面向合规和风控的 drop copy 事件流
+    #[serde(default)]
+    pub drop_copy: DropCopyConfig,
+    /// 用户消息/成交比限制
+    #[serde(default)]
+    pub message_ratios: MessageRatioConfig,
+    /// 按交易对的有界下单队列
+    #[serde(default)]
+    pub submission_queue: SubmissionQueueConfig,
+    /// 撮合延迟统计
+    #[serde(default)]
+    pub latency: LatencyConfig,
+    /// 沙盒（模拟交易）模式
+    #[serde(default)]
+    pub sandbox: SandboxConfig,
+    /// 保证金检查和维持保证金监控
+    #[serde(default)]
+    pub margin: MarginConfig,
+    /// 强平引擎
+    #[serde(default)]
+    pub liquidation: LiquidationConfig,
+    /// 永续合约资金费率
+    #[serde(default)]
+    pub funding: FundingConfig,
+    /// 场外成交申报
+    #[serde(default)]
+    pub otc: OtcConfig,
+    /// 按交易对的交易时段
+    #[serde(default)]
+    pub sessions: SessionConfig,
+    /// 日终结算和报表
+    #[serde(default)]
+    pub end_of_day: EndOfDayConfig,
+    /// 市场数据推送
+    #[serde(default)]
+    pub market_data: MarketDataConfig,
+    /// 事件总线的回放缓冲区
+    #[serde(default)]
+    pub event_bus: EventBusConfig,
+    /// 事件总线的持久化日志
+    #[serde(default)]
+    pub event_journal: EventJournalConfig,
+}
+
+/// 最小价格变动单位（tick）配置：默认值和按交易对覆盖的值
+///
+/// 限价单和止损单的价格必须是 tick 的整数倍；tick 同时决定订单簿价格键的精度，最多 12 位小数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TickSizeConfig {
+    pub default: f64,
+    /// 交易对（如 "BTCUSDT"）-> tick
+    #[serde(default)]
+    pub overrides: HashMap<String, f64>,
+}
+
+impl TickSizeConfig {
+    /// 获取交易对适用的 tick
+    pub fn tick_size_for(&self, symbol: &Symbol) -> f64 {
+        self.overrides
+            .get(&symbol.to_string())
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+impl Default for TickSizeConfig {
+    fn default() -> Self {
+        Self {
+            default: 0.000001,
+            overrides: HashMap::new(),
+        }
+    }
+}
+
+/// 成交手续费率，按成交金额（计价货币）计算
+///
+/// 挂单方（maker）费率可以为负数表示返佣；集合竞价成交的双方均按 maker 计费。
+/// 配置 `tiers` 后按用户最近 `volume_window_days` 天的成交额选择费率档位，成交额未达到第一档时使用基础费率
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeConfig {
+    pub maker_rate: f64,
+    pub taker_rate: f64,
+    /// 费率档位，按 `min_volume` 从小到大排列
+    #[serde(default)]
+    pub tiers: Vec<FeeTier>,
+    /// 统计成交额的滚动窗口（天），按 UTC 自然日分桶
+    #[serde(default = "default_volume_window_days")]
+    pub volume_window_days: u32,
+    /// 手续费收入和 maker 返佣的周期结算
+    #[serde(default)]
+    pub settlement: FeeSettlementConfig,
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        Self {
+            maker_rate: 0.0,
+            taker_rate: 0.0,
+            tiers: Vec::new(),
+            volume_window_days: default_volume_window_days(),
+            settlement: FeeSettlementConfig::default(),
+        }
+    }
+}
+
+/// 手续费结算配置
+///
+/// 启用后按资产累计每个结算周期收取的 taker/maker 手续费和支付的 maker 返佣，以及每个用户的应计返佣。
+/// 每 `interval_seconds` 秒（按 UTC 整点对齐）结算一次：收入扣除返佣后的净额记入 `fee_account` 账户，
+/// 该账户即返佣池，返佣超出当期收入时由其余额支付
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeeSettlementConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 结算间隔（秒）
+    pub interval_seconds: u64,
+    /// 手续费收入（返佣池）账户的用户ID
+    pub fee_account: String,
+    /// 在内存中保留的结算记录条数
+    pub history_size: usize,
+}
+
+fn default_volume_window_days() -> u32 {
+    30
+}
+
+/// 费率档位：滚动成交额（计价货币）达到 `min_volume` 时适用的费率
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FeeTier {
+    pub min_volume: f64,
+    pub maker_rate: f64,
+    pub taker_rate: f64,
+}
+
+/// 优雅停机配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShutdownConfig {
+    /// 等待进行中的撮合完成的最长时间（秒）
+    pub drain_timeout_seconds: u64,
+    /// 退出前写入引擎快照的路径，未设置时不写快照
+    #[serde(default)]
+    pub snapshot_path: Option<String>,
+}
+
+/// 订单审计配置
+///
+/// 启用后，订单的接受、拒绝、成交、修改、撤销、到期等状态变化逐条追加写入审计目录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditConfig {
+    /// 是否记录审计事件
+    pub enabled: bool,
+    /// 审计目录
+    pub dir: String,
+}
+
+/// 事件日志配置
+///
+/// 启用后，事件总线上的全部事件按序号由后台线程追加写入日志目录，同时记录每条事件的偏移索引，
+/// 重启后事件序号接着日志继续
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventJournalConfig {
+    /// 是否记录事件日志
+    pub enabled: bool,
+    /// 日志目录
+    pub dir: String,
+}
+
+/// 事件总线配置
+///
+/// 总线在内存中保留最近的事件，drop copy 订阅方和重连的备用实例从保留的序号开始回放
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventBusConfig {
+    /// 回放缓冲区保留的最近事件数，为 0 时不保留
+    pub replay_capacity: usize,
+}
+
+/// Drop copy 配置
+///
+/// 启用后，授权的订阅方可以接收事件总线上全体用户的订单事件和成交，并从总线回放缓冲区中任意保留的序号开始回放
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DropCopyConfig {
+    /// 是否提供 drop copy 事件流
+    pub enabled: bool,
+}
+
+/// 用户消息/成交比限制
+///
+/// 启用后引擎按用户统计滚动窗口内的下单、改单、撤单和成交次数，窗口内消息数达到 `min_messages`
+/// 后若消息数或撤单数与成交数之比超过上限，拒绝该用户的新订单（撤单和改单不受影响），
+/// 用于防范报价堆积（quote stuffing）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MessageRatioConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 滚动窗口（秒）
+    pub window_seconds: u64,
+    /// 窗口内下单、改单和撤单合计低于该值时不检查比例
+    pub min_messages: u64,
+    /// 每笔成交允许的最大消息数，窗口内没有成交时按 1 笔计算
+    pub max_messages_per_fill: f64,
+    /// 每笔成交允许的最大撤单数
+    pub max_cancels_per_fill: f64,
+    /// 不受限制的用户（如做市商）
+    pub exempt_users: Vec<String>,
+}
+
+/// 按交易对的有界下单队列配置
+///
+/// 启用后同一交易对的下单按到达顺序逐个撮合，排队（含正在撮合）的订单数达到 `capacity` 时
+/// 新订单以繁忙错误拒绝（HTTP 503，附 `Retry-After`），撤单和改单不排队
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubmissionQueueConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 每个交易对的队列容量
+    pub capacity: usize,
+    /// 繁忙拒绝时建议的重试间隔（秒）
+    pub retry_after_seconds: u64,
+}
+
+/// 撮合延迟统计配置
+///
+/// 启用后统计下单到受理和下单到首笔成交的耗时分位数，`per_symbol` 为 true 时另按交易对统计，
+/// Prometheus 直方图同时带上 `symbol` 标签
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LatencyConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 是否按交易对分别统计
+    pub per_symbol: bool,
+}
+
+/// 沙盒（模拟交易）模式配置
+///
+/// 启用后用户首次下单或查询余额时自动注入 `initial_balances` 中的虚拟资金，下单前按虚拟余额检查，
+/// 成交结算到双方余额；撮合规则与正常模式相同，`POST /sandbox/reset` 可把用户恢复到初始状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SandboxConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 资产 -> 每个用户的初始虚拟余额
+    pub initial_balances: HashMap<String, f64>,
+}
+
+/// 交易对的保证金率（占持仓名义价值的百分比）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MarginRequirement {
+    /// 开仓所需的初始保证金率
+    pub initial_margin_pct: f64,
+    /// 低于该比例时账户被标记为待强平
+    pub maintenance_margin_pct: f64,
+}
+
+/// 保证金配置
+///
+/// 启用后下单前检查账户权益（`collateral_asset` 余额加上持仓的已实现和未实现盈亏）能否覆盖成交后
+/// 最坏情况持仓的初始保证金；后台任务每 `check_interval_ms` 毫秒按标记价格计算各账户的保证金率，
+/// 维持保证金不低于权益的账户被标记为待强平。盈亏按计价货币计算，视为与 `collateral_asset` 等值
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarginConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 作为保证金的资产
+    pub collateral_asset: String,
+    /// 维持保证金监控的间隔（毫秒）
+    pub check_interval_ms: u64,
+    /// 默认保证金率
+    pub default: MarginRequirement,
+    /// 按交易对覆盖的保证金率，如 `BTCUSDT = { initial_margin_pct = 5.0, maintenance_margin_pct = 2.5 }`
+    pub symbols: HashMap<String, MarginRequirement>,
+}
+
+impl MarginConfig {
+    /// 获取交易对适用的保证金率
+    pub fn requirement_for(&self, symbol: &Symbol) -> MarginRequirement {
+        self.symbols
+            .get(&symbol.to_string())
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+/// 强平配置
+///
+/// 启用后（需要同时启用保证金），维持保证金监控发现待强平账户时撤销其全部挂单，
+/// 按标记价格上下 `price_band_pct` 的价格带提交只减仓限价单平掉持仓，价格带内未成交的部分撤销，
+/// 留待下一次监控继续强平。平仓后权益为正时收取 `fee_pct` 的强平手续费（不超过剩余权益）转入保险基金账户；
+/// 权益为负（穿仓）时由保险基金账户补足亏损
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LiquidationConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 强平单相对标记价格的价格带（百分比）
+    pub price_band_pct: f64,
+    /// 强平手续费率（占平仓名义价值的百分比）
+    pub fee_pct: f64,
+    /// 保险基金账户的用户ID
+    pub insurance_fund_account: String,
+    /// 内存中保留的强平事件条数
+    pub history_size: usize,
+}
+
+/// 资金费率配置
+///
+/// 启用后（需要同时启用保证金），每次更新标记价格时记录永续交易对的溢价 (标记价格 − 指数价格) / 指数价格，
+/// 每 `interval_seconds` 秒（按 UTC 整点对齐）结算一次：资金费率为周期内平均溢价加上 `interest_rate_pct`，
+/// 限制在 ±`max_rate_pct` 以内。费率为正时多头按持仓名义价值向空头支付资金费，为负时反之，
+/// 通过 `collateral_asset` 余额记账
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FundingConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 结算间隔（秒）
+    pub interval_seconds: u64,
+    /// 每个结算周期的利率（百分比）
+    pub interest_rate_pct: f64,
+    /// 资金费率绝对值上限（百分比）
+    pub max_rate_pct: f64,
+    /// 永续交易对，如 `["BTCUSDT"]`；为空时所有有标记价格的交易对都结算资金费
+    pub symbols: Vec<String>,
+    /// 每个交易对在内存中保留的资金费率记录条数
+    pub history_size: usize,
+}
+
+impl FundingConfig {
+    /// 交易对是否为永续合约
+    pub fn is_perpetual(&self, symbol: &Symbol) -> bool {
+        self.symbols.is_empty() || self.symbols.contains(&symbol.to_string())
+    }
+}
+
+/// 场外成交申报配置
+///
+/// 启用后可通过管理接口 `POST /admin/trades/report` 申报双方协商的场外成交，成交价必须在参考价格（标记价格，
+/// 没有时为最新撮合成交价）上下 `price_band_pct` 以内
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OtcConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 成交价相对参考价格的最大偏离（百分比）
+    pub price_band_pct: f64,
+    /// 是否计入成交笔数和成交量统计
+    pub include_in_volume: bool,
+    /// 是否作为最新成交价，参与最新价、条件单触发和熔断判断
+    pub update_last_price: bool,
+}
+
+/// 交易时段
+///
+/// 时间为 `utc_offset_minutes` 对应时区的本地时间，开盘时间必须早于收盘时间（不支持跨午夜的时段）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct TradingSession {
+    /// 开盘时间，如 `09:30`
+    #[schema(value_type = String)]
+    pub open: NaiveTime,
+    /// 收盘时间
+    #[schema(value_type = String)]
+    pub close: NaiveTime,
+    /// 开盘前集合竞价的分钟数，0 表示直接开盘
+    pub pre_open_minutes: u32,
+    /// 周六、周日休市
+    pub weekend_closed: bool,
+    /// 本地时间相对 UTC 的偏移（分钟）
+    pub utc_offset_minutes: i32,
+    /// 休市期间接受订单并保留到开盘集合竞价，为 false 时拒绝新订单（撤单仍受理）
+    pub queue_when_closed: bool,
+}
+
+/// 交易时段配置
+///
+/// 启用后引擎在时段边界自动切换交易对状态：集合竞价阶段只挂单不撮合，开盘时一次性撮合交叉部分并恢复连续交易，
+/// 收盘后只撤单（或继续接受订单等待下一次开盘）。没有设置时段的交易对不受影响
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 默认交易时段，为空时只有 `symbols` 中的交易对有时段
+    pub default: Option<TradingSession>,
+    /// 按交易对覆盖的交易时段
+    pub symbols: HashMap<String, TradingSession>,
+}
+
+/// 日终结算配置
+///
+/// 启用后每天在本地时间 `cutoff` 生成上一交易日的结算报表（场所级和用户级），
+/// 以 JSON 和 CSV 写入 `report_dir` 下以交易日命名的子目录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EndOfDayConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 每日结算截止时间（本地时间），截止前 24 小时为一个交易日
+    pub cutoff: NaiveTime,
+    /// 本地时间相对 UTC 的偏移（分钟）
+    pub utc_offset_minutes: i32,
+    /// 结算报表目录
+    pub report_dir: String,
+}
+
+/// 市场数据推送配置
+///
+/// 市场数据任务只在最新价和 24 小时统计变化时推送，同一交易对两次推送至少间隔
+/// 最小推送间隔，间隔内的变化合并为一次推送
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarketDataConfig {
+    /// 同一交易对两次推送的最小间隔（毫秒），0 表示有变化即推送
+    pub min_publish_interval_ms: u64,
+    /// 交易对（如 "BTCUSDT"）-> 最小推送间隔（毫秒）
+    pub overrides: HashMap<String, u64>,
+}
+
+impl MarketDataConfig {
+    /// 获取交易对适用的最小推送间隔
+    pub fn min_publish_interval_for(&self, symbol: &Symbol) -> std::time::Duration {
+        let millis = self
+            .overrides
+            .get(&symbol.to_string())
+            .copied()
+            .unwrap_or(self.min_publish_interval_ms);
+        std::time::Duration::from_millis(millis)
+    }
+}
+
+impl SessionConfig {
+    /// 获取交易对适用的交易时段
+    pub fn session_for(&self, symbol: &Symbol) -> Option<&TradingSession> {
+        self.symbols
+            .get(&symbol.to_string())
+            .or(self.default.as_ref())
+    }
+}
+
+/// 终态订单保留配置
+///
+/// 启用后，成交、撤销、过期等终态订单在内存中保留 `retention_seconds` 秒后写入归档目录并移出内存
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderRetentionConfig {
+    /// 是否启用归档
+    pub enabled: bool,
+    /// 终态订单在内存中的保留时间（秒）
+    pub retention_seconds: u64,
+    /// 归档目录
+    pub archive_dir: String,
+    /// 每个归档分段文件的最大订单数
+    pub segment_max_orders: usize,
+}
+
+/// 成交历史持久化配置
+///
+/// 启用后每笔成交同时写入按交易对和日期分区的历史文件，内存中只保留最近 `retention_seconds` 秒的成交；
+/// 查询范围超出内存时从历史文件读取
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TradeHistoryConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 成交在内存中的保留时间（秒）
+    pub retention_seconds: u64,
+    /// 历史文件目录
+    pub dir: String,
+}
+
+/// 成交量统计配置
+///
+/// 成交按 1 分钟桶累加，用于 `/analytics/{symbol}/volume` 按周期汇总
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VolumeStatsConfig {
+    /// 分钟桶的保留时长（小时），更早的统计被丢弃
+    pub retention_hours: u64,
+}
+
+/// 用户挂单限额，未设置的项不限制
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct UserLimits {
+    /// 最大未完成订单数
+    #[serde(default)]
+    pub max_open_orders: Option<usize>,
+    /// 单个交易对最大挂单名义价值
+    #[serde(default)]
+    pub max_open_notional_per_symbol: Option<f64>,
+    /// 所有交易对合计最大挂单名义价值
+    #[serde(default)]
+    pub max_open_notional: Option<f64>,
+}
+
+impl UserLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_open_orders.is_none()
+            && self.max_open_notional_per_symbol.is_none()
+            && self.max_open_notional.is_none()
+    }
+}
+
+/// 用户限额配置：默认限额和按用户覆盖的限额
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserLimitsConfig {
+    #[serde(default)]
+    pub default: UserLimits,
+    /// 用户ID -> 覆盖限额（整体替换默认限额）
+    #[serde(default)]
+    pub overrides: HashMap<String, UserLimits>,
+    /// 提交渠道 -> 渠道限额，只统计用户经该渠道提交的未完成订单，与用户限额同时生效
+    #[serde(default)]
+    pub channels: HashMap<OrderChannel, UserLimits>,
+}
+
+impl UserLimitsConfig {
+    /// 获取用户适用的限额
+    pub fn limits_for(&self, user_id: &str) -> &UserLimits {
+        self.overrides.get(user_id).unwrap_or(&self.default)
+    }
+
+    /// 是否配置了任何限额
+    pub fn is_enabled(&self) -> bool {
+        std::iter::once(&self.default)
+            .chain(self.overrides.values())
+            .chain(self.channels.values())
+            .any(|limits| !limits.is_unlimited())
+    }
+}
+
+/// 同价位挂单的成交分配算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AllocationAlgorithm {
+    /// 严格时间优先
+    #[default]
+    Fifo,
+    /// 按挂单剩余数量比例分配
+    ProRata,
+    /// 先按时间优先分配一部分，剩余按比例分配
+    TimeProRata,
+}
+
+/// 成交分配配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllocationConfig {
+    /// 默认分配算法
+    pub algorithm: AllocationAlgorithm,
+    /// 按比例分配时单个挂单的最小分配数量，低于该值的份额改为按时间优先分配
+    pub min_allocation: f64,
+    /// 混合算法中先按时间优先分配的百分比
+    pub fifo_percentage: f64,
+    /// 按交易对覆盖的分配算法，如 `BTCUSDT = "pro_rata"`
+    #[serde(default)]
+    pub symbols: HashMap<String, AllocationAlgorithm>,
+}
+
+impl AllocationConfig {
+    /// 获取交易对使用的分配算法
+    pub fn algorithm_for(&self, symbol: &Symbol) -> AllocationAlgorithm {
+        self.symbols
+            .get(&symbol.to_string())
+            .copied()
+            .unwrap_or(self.algorithm)
+    }
+}
+
+/// 成交价规则：决定吃单与挂单交叉时的成交价格
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradePriceRule {
+    /// 以挂单（maker）价格成交
+    #[default]
+    Maker,
+    /// 以吃单（taker）限价成交
+    Taker,
+    /// 以双方价格的中间价成交
+    Midpoint,
+}
+
+/// 参考价格来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceReference {
+    /// 最新成交价
+    #[default]
+    LastTrade,
+    /// 标记价格，交易对还没有标记价格时使用最新成交价
+    Mark,
+}
+
+/// 条件单触发和价格熔断的参考价格
+///
+/// 使用标记价格可以避免单笔异常成交（如薄盘口上的大额市价单）触发止损或熔断
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PriceReferenceConfig {
+    /// 条件单触发价比较的价格：为 `mark` 时只在标记价格更新时检查触发，成交价不再触发
+    pub triggers: PriceReference,
+    /// 熔断的参考价：为 `mark` 时成交价相对当前标记价格的偏离超过上限即熔断
+    pub circuit_breaker: PriceReference,
+}
+
+/// 价格熔断配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    /// 是否启用熔断
+    pub enabled: bool,
+    /// 触发熔断的最大价格波动百分比（相对参考价）
+    pub max_price_move: f64,
+    /// 参考价窗口（秒），窗口结束后以最新成交价作为新的参考价
+    pub window_seconds: u64,
+    /// 熔断冷却时间（秒），到期后自动恢复交易
+    pub cooldown_seconds: u64,
+    /// 为 true 时熔断进入只撤单状态，否则完全暂停
+    pub cancel_only: bool,
 }
 
 /// 数据库配置（预留）
@@ -130,13 +986,370 @@ pub struct DatabaseConfig {
     pub idle_timeout: u64,
 }
 
-/// Redis配置（预留）
+/// Redis配置
+///
+/// 以 `redis` 特性编译时，成交和盘口变化发布到 Redis 频道，最新深度、市场数据和盘口
+/// 以带过期时间的键缓存，供前端等读多的服务直接从 Redis 扇出
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RedisConfig {
     pub url: String,
+    /// 预留，目前使用单个多路复用连接
     pub max_connections: u32,
+    /// 连接超时（秒）
     pub connection_timeout: u64,
+    /// 命令超时（秒）
     pub command_timeout: u64,
+    /// 频道和键的前缀，如 `matching_engine:trades:BTCUSDT`
+    #[serde(default = "default_redis_key_prefix")]
+    pub key_prefix: String,
+    /// 快照键的过期时间（秒），引擎停止更新后快照自动失效
+    #[serde(default = "default_redis_snapshot_ttl")]
+    pub snapshot_ttl_seconds: u64,
+    /// 变化过的交易对快照的写入间隔（毫秒）
+    #[serde(default = "default_redis_snapshot_interval")]
+    pub snapshot_interval_ms: u64,
+    /// 深度快照包含的档位数
+    #[serde(default = "default_redis_depth_levels")]
+    pub depth_levels: usize,
+}
+
+fn default_redis_key_prefix() -> String {
+    "matching_engine".to_string()
+}
+
+fn default_redis_snapshot_ttl() -> u64 {
+    30
+}
+
+fn default_redis_snapshot_interval() -> u64 {
+    200
+}
+
+fn default_redis_depth_levels() -> usize {
+    20
+}
+
+/// 事件发布配置
+///
+/// 启用后把成交、订单更新和订单簿深度变化编号后发布到 Kafka 和/或 NATS。
+/// Kafka 需以 `kafka` 特性编译，NATS 需以 `nats` 特性编译
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventsConfig {
+    /// 是否启用事件发布
+    pub enabled: bool,
+    /// 各类事件的 Kafka 主题，NATS 以此为前缀、按交易对细分主题（如 `engine.trades.BTCUSDT`）
+    #[serde(default)]
+    pub topics: EventTopicsConfig,
+    /// 深度事件包含的档位数
+    pub depth_levels: usize,
+    /// 投递失败后的重试次数，重试耗尽后丢弃该事件并记录错误
+    pub max_retries: u32,
+    /// 重试间隔（毫秒），每次重试翻倍
+    pub retry_backoff_ms: u64,
+    /// Kafka 配置，为空时不发布到 Kafka
+    #[serde(default)]
+    pub kafka: Option<KafkaSinkConfig>,
+    /// NATS 配置，为空时不发布到 NATS
+    #[serde(default)]
+    pub nats: Option<NatsSinkConfig>,
+}
+
+/// Webhook 回调配置
+///
+/// 以 `webhooks` 特性编译并启用后，用户可以注册回调地址，成交和订单终态经投递队列 POST 到
+/// 回调地址。队列只在内存中，停机时未完成的投递被丢弃
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    /// 是否启用 Webhook
+    pub enabled: bool,
+    /// 每个用户最多注册的回调数
+    pub max_webhooks_per_user: usize,
+    /// 投递队列容量，队列已满时丢弃新事件并记录指标
+    pub queue_size: usize,
+    /// 同时进行的投递数
+    pub max_concurrent_deliveries: usize,
+    /// 单次请求超时（毫秒）
+    pub request_timeout_ms: u64,
+    /// 投递失败后的重试次数，重试耗尽后丢弃该事件
+    pub max_retries: u32,
+    /// 首次重试间隔（毫秒），每次重试翻倍
+    pub retry_backoff_ms: u64,
+    /// 重试间隔上限（毫秒）
+    pub max_retry_backoff_ms: u64,
+}
+
+/// 成交监控配置
+///
+/// 启用后后台检测自成交、关联账户对倒、乒乓交易和近盘口挂撤单（幌骗），
+/// 告警保存在内存中，可通过管理接口查询
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SurveillanceConfig {
+    /// 是否启用成交监控
+    pub enabled: bool,
+    /// 内存中保留的告警数，超过后淘汰最早的告警
+    pub max_alerts: usize,
+    /// 关联账户组，同组账户之间的成交视为对倒
+    pub correlated_accounts: Vec<Vec<String>>,
+    /// 乒乓交易的检测窗口（毫秒）
+    pub ping_pong_window_ms: u64,
+    /// 窗口内同一对用户互换买卖方向的次数达到该值时告警
+    pub ping_pong_min_reversals: usize,
+    /// 近盘口挂撤单的检测窗口（毫秒）
+    pub layering_window_ms: u64,
+    /// 价格与同方向最优价相差不超过该基点数的挂单视为近盘口
+    pub layering_near_touch_bps: f64,
+    /// 窗口内近盘口挂单数达到该值才检测撤单比例
+    pub layering_min_orders: usize,
+    /// 撤单数占挂单数的比例达到该值时告警
+    pub layering_cancel_ratio: f64,
+}
+
+/// 指数价格和标记价格配置
+///
+/// 以 `price-index` 特性编译并启用后，后台任务从外部价格源（HTTP 轮询或 WebSocket 推送）采集报价，
+/// 每个交易对按权重计算指数价格，再加上平滑后的基差（订单簿中间价 - 指数价格）得到标记价格，
+/// 交给引擎供条件单触发和熔断参考
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PriceIndexConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 计算和发布指数价格、标记价格的间隔（毫秒）
+    pub publish_interval_ms: u64,
+    /// 成分报价超过该时间（毫秒）未更新视为过期，不参与计算
+    pub stale_after_ms: u64,
+    /// 参与计算的最少有效成分数，不足时该交易对暂停发布
+    pub min_sources: usize,
+    /// 偏离成分中位数超过该百分比的报价被剔除，0 表示不剔除
+    pub max_deviation_pct: f64,
+    /// 基差指数移动平均的平滑系数，取值 (0, 1]，越小越平滑
+    pub basis_ema_alpha: f64,
+    /// 标记价格相对指数价格的最大偏离百分比
+    pub max_basis_pct: f64,
+    /// 外部价格源
+    pub feeds: Vec<PriceFeedConfig>,
+}
+
+/// 成交和订单 Parquet 归档配置
+///
+/// 以 `parquet` 特性编译并启用后，后台任务订阅已完成的成交和进入终态的订单，按 `flush_interval_seconds`
+/// 或 `max_rows_per_file` 滚动写入 `dir` 下按日期分区的 Parquet 文件；配置了 `s3` 时（需 `s3` 特性）
+/// 文件同时上传到 S3 兼容存储
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportConfig {
+    /// 是否启用后台归档
+    pub enabled: bool,
+    /// 本地归档目录
+    pub dir: String,
+    /// 缓冲的记录写出为文件的间隔（秒）
+    pub flush_interval_seconds: u64,
+    /// 单个文件的最大行数，缓冲达到该行数时立即写出
+    pub max_rows_per_file: usize,
+    /// S3 兼容存储，为空时只写本地文件
+    pub s3: Option<S3ExportConfig>,
+}
+
+/// 归档文件上传的 S3 兼容存储
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct S3ExportConfig {
+    pub bucket: String,
+    /// 对象键前缀，如 `matching-engine/`
+    pub prefix: String,
+    pub region: String,
+    /// 自定义端点（MinIO 等），为空时使用 AWS
+    pub endpoint: Option<String>,
+    /// 访问密钥，为空时从 `AWS_ACCESS_KEY_ID` 等环境变量读取
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+    /// 允许 `http://` 端点
+    pub allow_http: bool,
+    /// 上传成功后是否保留本地文件
+    pub keep_local: bool,
+}
+
+/// 外部价格源类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceFeedKind {
+    /// 定时 GET 轮询
+    #[default]
+    Http,
+    /// WebSocket 推送，每条文本消息解析一次价格；只支持 `ws://`，TLS 需由本地代理终结
+    Websocket,
+}
+
+/// 外部价格源配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PriceFeedConfig {
+    /// 价格源名称，同一交易对内唯一
+    pub name: String,
+    /// 交易对，如 `BTCUSDT`
+    pub symbol: String,
+    pub kind: PriceFeedKind,
+    pub url: String,
+    /// 价格字段的 JSON Pointer（如 `/price`、`/data/0/last`），字段可以是数字或数字字符串，为空时整个响应即价格
+    pub price_pointer: String,
+    /// 计算指数时的权重
+    pub weight: f64,
+    /// HTTP 轮询间隔（毫秒）
+    pub poll_interval_ms: u64,
+    /// WebSocket 连接建立后发送的订阅消息
+    pub subscribe_message: Option<String>,
+}
+
+/// 实例在主备复制中的角色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplicationRole {
+    /// 不参与复制
+    #[default]
+    Disabled,
+    /// 主实例：受理订单并向备用实例推送事件
+    Primary,
+    /// 备用实例：只应用主实例的事件，提升后开始受理订单
+    Standby,
+}
+
+/// 主备复制配置
+///
+/// 主实例在 `listen_addr` 上把事件总线的事件流推送给备用实例，需要 `engine.event_bus` 保留事件用于续传；
+/// 备用实例连接 `primary_addr`，断线后按 `reconnect_backoff_ms` 重连并从断点续传
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplicationConfig {
+    /// 复制角色
+    pub role: ReplicationRole,
+    /// 主实例的复制监听地址
+    pub listen_addr: String,
+    /// 备用实例连接的主实例复制地址
+    pub primary_addr: String,
+    /// 复制连接的共享令牌，为空时不校验
+    pub token: String,
+    /// 主实例的心跳间隔（毫秒），备用实例超过 3 个心跳间隔没有收到消息时断开重连
+    pub heartbeat_interval_ms: u64,
+    /// 备用实例的重连间隔（毫秒）
+    pub reconnect_backoff_ms: u64,
+}
+
+/// 多租户配置
+///
+/// 启用后一个进程承载多个相互隔离的租户，每个租户使用独立的撮合引擎（订单簿、订单、成交、
+/// 账户和统计），请求按 `api_key_header` 请求头（WebSocket 也可用 `api_key` 查询参数）中的
+/// API key 路由到对应租户
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TenancyConfig {
+    pub enabled: bool,
+    /// 携带 API key 的请求头
+    pub api_key_header: String,
+    pub tenants: Vec<TenantConfig>,
+}
+
+/// 租户配置，未设置的项沿用 `engine` 中的配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantConfig {
+    /// 租户ID
+    pub id: String,
+    /// 映射到该租户的 API key
+    pub api_keys: Vec<String>,
+    /// 租户可交易的交易对，为空时沿用引擎配置
+    #[serde(default)]
+    pub symbols: Vec<String>,
+    /// 租户的手续费率
+    #[serde(default)]
+    pub fees: Option<FeeConfig>,
+    /// 租户的用户挂单限额
+    #[serde(default)]
+    pub user_limits: Option<UserLimitsConfig>,
+}
+
+impl TenantConfig {
+    /// 在 `base` 引擎配置上应用租户的覆盖项
+    ///
+    /// 设置了交易对时只接受这些交易对；审计、归档、结算报表目录和停机快照按租户细分到 `tenant-<id>` 子目录
+    pub fn engine_config(&self, base: &EngineConfig) -> EngineConfig {
+        let subdir = |path: &Path| path.join(format!("tenant-{}", self.id));
+        let mut config = base.clone();
+        if !self.symbols.is_empty() {
+            config.supported_symbols = self.symbols.clone();
+            config.restrict_symbols = true;
+        }
+        if let Some(fees) = &self.fees {
+            config.fees = fees.clone();
+        }
+        if let Some(user_limits) = &self.user_limits {
+            config.user_limits = user_limits.clone();
+        }
+        config.audit.dir = subdir(Path::new(&config.audit.dir))
+            .to_string_lossy()
+            .into_owned();
+        config.order_retention.archive_dir = subdir(Path::new(&config.order_retention.archive_dir))
+            .to_string_lossy()
+            .into_owned();
+        config.trade_history.dir = subdir(Path::new(&config.trade_history.dir))
+            .to_string_lossy()
+            .into_owned();
+        config.end_of_day.report_dir = subdir(Path::new(&config.end_of_day.report_dir))
+            .to_string_lossy()
+            .into_owned();
+        config.shutdown.snapshot_path = config.shutdown.snapshot_path.as_deref().map(|path| {
+            let path = Path::new(path);
+            let dir = subdir(path.parent().unwrap_or(Path::new("")));
+            dir.join(path.file_name().unwrap_or_default())
+                .to_string_lossy()
+                .into_owned()
+        });
+        config
+    }
+}
+
+/// 事件主题
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventTopicsConfig {
+    pub trades: String,
+    pub orders: String,
+    pub depth: String,
+}
+
+impl EventTopicsConfig {
+    /// 事件类型对应的主题
+    pub fn topic(&self, kind: EventKind) -> &str {
+        match kind {
+            EventKind::Trade | EventKind::TradeBust => &self.trades,
+            EventKind::OrderUpdate => &self.orders,
+            EventKind::Depth => &self.depth,
+        }
+    }
+}
+
+/// Kafka 事件发布配置，未设置的字段取默认值
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KafkaSinkConfig {
+    /// `bootstrap.servers`，逗号分隔
+    pub brokers: String,
+    /// 客户端ID
+    pub client_id: String,
+    /// 单条消息的投递超时（毫秒）
+    pub message_timeout_ms: u64,
+    /// 额外的 librdkafka 配置项（如 `security.protocol`），覆盖默认值
+    #[serde(default)]
+    pub properties: HashMap<String, String>,
+}
+
+/// NATS 事件发布配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NatsSinkConfig {
+    /// 服务器地址，如 `nats://localhost:4222`
+    pub url: String,
+    /// 为 true 时通过 JetStream 发布并等待确认（主题需已被某个 stream 捕获）
+    #[serde(default)]
+    pub jetstream: bool,
 }
 
 impl AppConfig {
@@ -173,14 +1386,40 @@ impl AppConfig {
         format!("{}:{}", self.server.host, self.server.port)
     }
 
-    /// 获取API基础URL
-    pub fn api_base_url(&self) -> String {
-        format!("http://{}/{}", self.server_addr(), self.server.api_prefix)
+    /// 获取 gRPC 服务器地址
+    pub fn grpc_addr(&self) -> String {
+        format!("{}:{}", self.server.host, self.server.grpc.port)
+    }
+
+    /// 获取管理接口单独监听的地址
+    pub fn admin_addr(&self) -> String {
+        format!("{}:{}", self.server.host, self.server.admin.port)
+    }
+
+    /// 获取API基础URL
+    pub fn api_base_url(&self) -> String {
+        let scheme = if self.server.tls.enabled {
+            "https"
+        } else {
+            "http"
+        };
+        format!(
+            "{}://{}/{}",
+            scheme,
+            self.server_addr(),
+            self.server.api_prefix
+        )
     }
 
     /// 获取WebSocket基础URL
     pub fn ws_base_url(&self) -> String {
-        format!("ws://{}/{}", self.server_addr(), self.server.ws_prefix)
+        let scheme = if self.server.tls.enabled { "wss" } else { "ws" };
+        format!(
+            "{}://{}/{}",
+            scheme,
+            self.server_addr(),
+            self.server.ws_prefix
+        )
     }
 
     /// 验证配置
@@ -194,14 +1433,109 @@ impl AppConfig {
             return Err("Request timeout cannot be 0".to_string());
         }
 
+        let tls = &self.server.tls;
+        if tls.enabled && (tls.cert_path.is_empty() || tls.key_path.is_empty()) {
+            return Err("TLS certificate and key paths cannot be empty".to_string());
+        }
+
+        let grpc = &self.server.grpc;
+        if grpc.enabled && (grpc.port == 0 || grpc.port == self.server.port) {
+            return Err("gRPC port must be non-zero and differ from the server port".to_string());
+        }
+
+        let admin = &self.server.admin;
+        if admin.port != 0
+            && (admin.port == self.server.port
+                || (self.server.grpc.enabled && admin.port == self.server.grpc.port))
+        {
+            return Err("Admin port must differ from the server and gRPC ports".to_string());
+        }
+        if admin.audit_dir.is_empty() {
+            return Err("Admin audit dir cannot be empty".to_string());
+        }
+        let mut admin_tokens = HashSet::new();
+        for token in &admin.tokens {
+            if token.actor.is_empty() || token.token.is_empty() {
+                return Err("Admin token and actor cannot be empty".to_string());
+            }
+            if !admin_tokens.insert(token.token.as_str()) {
+                return Err(format!("Duplicate admin token for actor {}", token.actor));
+            }
+        }
+
+        let auth = &self.server.auth;
+        if auth.enabled {
+            if auth.jwt_secret.is_empty() {
+                return Err("JWT secret cannot be empty when auth is enabled".to_string());
+            }
+            if auth.api_key_header.is_empty() || auth.api_keys_path.is_empty() {
+                return Err("API key header and path cannot be empty".to_string());
+            }
+            if self.tenancy.enabled
+                && auth
+                    .api_key_header
+                    .eq_ignore_ascii_case(&self.tenancy.api_key_header)
+            {
+                return Err(
+                    "User API key header must differ from the tenancy API key header".to_string(),
+                );
+            }
+        }
+
+        if self.server.websocket.heartbeat_interval == 0 {
+            return Err("WebSocket heartbeat interval cannot be 0".to_string());
+        }
+
+        if self
+            .server
+            .websocket
+            .drop_copy_tokens
+            .iter()
+            .any(|token| token.is_empty())
+        {
+            return Err("WebSocket drop copy tokens cannot be empty".to_string());
+        }
+
+        if self.server.websocket.outbound_queue_size == 0 {
+            return Err("WebSocket outbound queue size cannot be 0".to_string());
+        }
+
+        if self.server.websocket.replay_buffer_size == 0 {
+            return Err("WebSocket replay buffer size cannot be 0".to_string());
+        }
+
+        if self.server.websocket.max_symbols_per_connection == 0 {
+            return Err("WebSocket max symbols per connection cannot be 0".to_string());
+        }
+
+        if self.server.websocket.heartbeat_timeout <= self.server.websocket.heartbeat_interval {
+            return Err(
+                "WebSocket heartbeat timeout must be greater than heartbeat interval".to_string(),
+            );
+        }
+
         // 验证日志配置
         let valid_log_levels = ["trace", "debug", "info", "warn", "error"];
         if !valid_log_levels.contains(&self.logging.level.as_str()) {
             return Err(format!("Invalid log level: {}", self.logging.level));
         }
 
+        let otlp = &self.logging.otlp;
+        if otlp.enabled {
+            if otlp.endpoint.is_empty() {
+                return Err("OTLP endpoint cannot be empty".to_string());
+            }
+
+            if !(0.0..=1.0).contains(&otlp.sample_ratio) {
+                return Err("OTLP sample ratio must be between 0 and 1".to_string());
+            }
+        }
+
         // 验证监控配置
-        if self.monitoring.enabled && self.monitoring.metrics_port == 0 {
+        if self.monitoring.enabled
+            && !self.monitoring.serve_on_api
+            && self.monitoring.metrics_port == 0
+        {
             return Err("Metrics port cannot be 0 when monitoring is enabled".to_string());
         }
 
@@ -218,21 +1552,577 @@ impl AppConfig {
             return Err("Max trade quantity must be positive".to_string());
         }
 
+        let allocation = &self.engine.allocation;
+        if allocation.min_allocation < 0.0 {
+            return Err("Minimum allocation cannot be negative".to_string());
+        }
+
+        if allocation.fifo_percentage < 0.0 || allocation.fifo_percentage > 100.0 {
+            return Err("FIFO percentage must be between 0 and 100".to_string());
+        }
+
+        validate_user_limits(&self.engine.user_limits)?;
+
+        let order_retention = &self.engine.order_retention;
+        if order_retention.enabled {
+            if order_retention.archive_dir.is_empty() {
+                return Err("Order archive directory cannot be empty".to_string());
+            }
+
+            if order_retention.segment_max_orders == 0 {
+                return Err("Order archive segment size cannot be 0".to_string());
+            }
+        }
+
+        let trade_history = &self.engine.trade_history;
+        if trade_history.enabled {
+            if trade_history.dir.is_empty() {
+                return Err("Trade history directory cannot be empty".to_string());
+            }
+            if trade_history.retention_seconds == 0 {
+                return Err("Trade history retention cannot be 0".to_string());
+            }
+        }
+
+        if self.engine.volume_stats.retention_hours == 0 {
+            return Err("Volume stats retention cannot be 0".to_string());
+        }
+
+        validate_fees(&self.engine.fees)?;
+        if self.engine.restrict_symbols {
+            validate_symbols(&self.engine.supported_symbols)?;
+        }
+
+        let tick_sizes = &self.engine.tick_sizes;
+        for tick_size in std::iter::once(&tick_sizes.default).chain(tick_sizes.overrides.values()) {
+            PriceScale::from_tick_size(*tick_size)?;
+        }
+
+        if self.engine.audit.enabled && self.engine.audit.dir.is_empty() {
+            return Err("Audit directory cannot be empty".to_string());
+        }
+
+        if self.engine.drop_copy.enabled && self.engine.event_bus.replay_capacity == 0 {
+            return Err(
+                "Drop copy requires an event bus replay capacity greater than 0".to_string(),
+            );
+        }
+
+        if self.engine.id_shard >= MAX_SHARD {
+            return Err(format!("Engine id_shard must be below {}", MAX_SHARD));
+        }
+
+        let circuit_breaker = &self.engine.circuit_breaker;
+        if circuit_breaker.enabled {
+            if circuit_breaker.max_price_move <= 0.0 || circuit_breaker.max_price_move > 100.0 {
+                return Err("Circuit breaker max price move must be between 0 and 100".to_string());
+            }
+
+            if circuit_breaker.window_seconds == 0 || circuit_breaker.cooldown_seconds == 0 {
+                return Err("Circuit breaker window and cooldown cannot be 0".to_string());
+            }
+        }
+
+        let sandbox = &self.engine.sandbox;
+        if sandbox.enabled
+            && (sandbox.initial_balances.is_empty()
+                || sandbox.initial_balances.iter().any(|(asset, amount)| {
+                    asset.is_empty() || !(amount.is_finite() && *amount > 0.0)
+                }))
+        {
+            return Err(
+                "Sandbox initial balances must be non-empty with positive amounts".to_string(),
+            );
+        }
+
+        let margin = &self.engine.margin;
+        if margin.enabled {
+            if sandbox.enabled {
+                return Err("Margin and sandbox modes cannot both be enabled".to_string());
+            }
+            if margin.collateral_asset.is_empty() || margin.check_interval_ms == 0 {
+                return Err(
+                    "Margin collateral asset and check interval cannot be empty".to_string()
+                );
+            }
+            if std::iter::once(&margin.default)
+                .chain(margin.symbols.values())
+                .any(|requirement| {
+                    !(requirement.maintenance_margin_pct > 0.0
+                        && requirement.maintenance_margin_pct <= requirement.initial_margin_pct
+                        && requirement.initial_margin_pct <= 100.0)
+                })
+            {
+                return Err(
+                    "Margin requirements must satisfy 0 < maintenance <= initial <= 100"
+                        .to_string(),
+                );
+            }
+        }
+
+        let liquidation = &self.engine.liquidation;
+        if liquidation.enabled {
+            if !margin.enabled {
+                return Err("Liquidation requires margin to be enabled".to_string());
+            }
+            if !(liquidation.price_band_pct > 0.0 && liquidation.price_band_pct < 100.0) {
+                return Err("Liquidation price band must be between 0 and 100".to_string());
+            }
+            if !(liquidation.fee_pct >= 0.0 && liquidation.fee_pct < 100.0) {
+                return Err("Liquidation fee must be between 0 and 100".to_string());
+            }
+            if liquidation.insurance_fund_account.is_empty() || liquidation.history_size == 0 {
+                return Err(
+                    "Liquidation insurance fund account and history size cannot be empty"
+                        .to_string(),
+                );
+            }
+        }
+
+        let funding = &self.engine.funding;
+        if funding.enabled {
+            if !margin.enabled {
+                return Err("Funding requires margin to be enabled".to_string());
+            }
+            if funding.interval_seconds == 0 || funding.history_size == 0 {
+                return Err("Funding interval and history size cannot be 0".to_string());
+            }
+            if !(funding.max_rate_pct > 0.0
+                && funding.max_rate_pct < 100.0
+                && funding.interest_rate_pct.is_finite())
+            {
+                return Err("Funding max rate must be between 0 and 100".to_string());
+            }
+        }
+
+        let otc = &self.engine.otc;
+        if otc.enabled && !(otc.price_band_pct > 0.0 && otc.price_band_pct < 100.0) {
+            return Err("OTC price band must be between 0 and 100".to_string());
+        }
+
+        let sessions = &self.engine.sessions;
+        if sessions.enabled {
+            for (symbol, session) in &sessions.symbols {
+                Symbol::register(symbol)
+                    .map_err(|_| format!("Invalid trading session symbol {}", symbol))?;
+                validate_session(session)?;
+            }
+            if let Some(session) = &sessions.default {
+                validate_session(session)?;
+            }
+        }
+
+        let end_of_day = &self.engine.end_of_day;
+        if end_of_day.enabled {
+            if end_of_day.report_dir.is_empty() {
+                return Err("End-of-day report dir cannot be empty".to_string());
+            }
+            if end_of_day.utc_offset_minutes.abs() > 18 * 60 {
+                return Err("End-of-day UTC offset must be within 18 hours".to_string());
+            }
+        }
+
+        let submission_queue = &self.engine.submission_queue;
+        if submission_queue.enabled
+            && (submission_queue.capacity == 0 || submission_queue.retry_after_seconds == 0)
+        {
+            return Err("Submission queue capacity and retry-after cannot be 0".to_string());
+        }
+
+        let message_ratios = &self.engine.message_ratios;
+        if message_ratios.enabled {
+            if message_ratios.window_seconds == 0 {
+                return Err("Message ratio window cannot be 0".to_string());
+            }
+
+            if !(message_ratios.max_messages_per_fill > 0.0
+                && message_ratios.max_cancels_per_fill > 0.0)
+            {
+                return Err("Message and cancel ratios must be positive".to_string());
+            }
+        }
+
+        if let Some(redis) = &self.redis {
+            if redis.url.is_empty() {
+                return Err("Redis url cannot be empty".to_string());
+            }
+
+            if redis.snapshot_ttl_seconds == 0
+                || redis.snapshot_interval_ms == 0
+                || redis.depth_levels == 0
+            {
+                return Err("Redis snapshot TTL, interval and depth levels cannot be 0".to_string());
+            }
+        }
+
+        // 验证事件发布配置
+        let events = &self.events;
+        if events.enabled {
+            if events.kafka.is_none() && events.nats.is_none() {
+                return Err("Event publishing requires a Kafka or NATS sink".to_string());
+            }
+
+            let topics = &events.topics;
+            if [&topics.trades, &topics.orders, &topics.depth]
+                .iter()
+                .any(|topic| topic.is_empty())
+            {
+                return Err("Event topics cannot be empty".to_string());
+            }
+
+            if events.depth_levels == 0 {
+                return Err("Event depth levels cannot be 0".to_string());
+            }
+
+            if events
+                .kafka
+                .as_ref()
+                .is_some_and(|kafka| kafka.brokers.is_empty())
+            {
+                return Err("Kafka brokers cannot be empty".to_string());
+            }
+
+            if events.nats.as_ref().is_some_and(|nats| nats.url.is_empty()) {
+                return Err("NATS url cannot be empty".to_string());
+            }
+        }
+
+        let webhooks = &self.webhooks;
+        if webhooks.enabled {
+            if webhooks.max_webhooks_per_user == 0
+                || webhooks.queue_size == 0
+                || webhooks.max_concurrent_deliveries == 0
+                || webhooks.request_timeout_ms == 0
+            {
+                return Err(
+                    "Webhook limits, queue size, concurrency and timeout cannot be 0".to_string(),
+                );
+            }
+
+            if webhooks.retry_backoff_ms == 0
+                || webhooks.retry_backoff_ms > webhooks.max_retry_backoff_ms
+            {
+                return Err(
+                    "Webhook retry backoff must be positive and not exceed the maximum backoff"
+                        .to_string(),
+                );
+            }
+        }
+
+        let surveillance = &self.surveillance;
+        if surveillance.enabled {
+            if surveillance.max_alerts == 0
+                || surveillance.ping_pong_window_ms == 0
+                || surveillance.ping_pong_min_reversals == 0
+                || surveillance.layering_window_ms == 0
+                || surveillance.layering_min_orders == 0
+            {
+                return Err(
+                    "Surveillance alert capacity, windows and thresholds cannot be 0".to_string(),
+                );
+            }
+
+            if !(surveillance.layering_near_touch_bps >= 0.0
+                && surveillance.layering_near_touch_bps.is_finite())
+            {
+                return Err("Surveillance near-touch band must be non-negative".to_string());
+            }
+
+            if !(surveillance.layering_cancel_ratio > 0.0
+                && surveillance.layering_cancel_ratio <= 1.0)
+            {
+                return Err("Surveillance cancel ratio must be in (0, 1]".to_string());
+            }
+
+            if surveillance
+                .correlated_accounts
+                .iter()
+                .any(|group| group.len() < 2)
+            {
+                return Err(
+                    "Surveillance correlated account groups need at least two accounts".to_string(),
+                );
+            }
+        }
+
+        self.validate_price_index()?;
+
+        let export = &self.export;
+        if export.enabled {
+            if export.dir.is_empty() {
+                return Err("Export directory cannot be empty".to_string());
+            }
+            if export.flush_interval_seconds == 0 || export.max_rows_per_file == 0 {
+                return Err("Export flush interval and rows per file cannot be 0".to_string());
+            }
+            if export.s3.as_ref().is_some_and(|s3| s3.bucket.is_empty()) {
+                return Err("Export S3 bucket cannot be empty".to_string());
+            }
+        }
+
+        let replication = &self.replication;
+        if replication.role != ReplicationRole::Disabled
+            && (replication.heartbeat_interval_ms == 0 || replication.reconnect_backoff_ms == 0)
+        {
+            return Err(
+                "Replication heartbeat interval and reconnect backoff cannot be 0".to_string(),
+            );
+        }
+        match replication.role {
+            ReplicationRole::Primary => {
+                if self.engine.event_bus.replay_capacity == 0 {
+                    return Err(
+                        "Replication primary requires an event bus replay capacity greater than 0"
+                            .to_string(),
+                    );
+                }
+                if replication
+                    .listen_addr
+                    .parse::<std::net::SocketAddr>()
+                    .is_err()
+                {
+                    return Err(format!(
+                        "Invalid replication listen address {}",
+                        replication.listen_addr
+                    ));
+                }
+            }
+            ReplicationRole::Standby if replication.primary_addr.is_empty() => {
+                return Err("Replication standby requires a primary address".to_string());
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn validate_price_index(&self) -> Result<(), String> {
+        if self.tenancy.enabled {
+            self.validate_tenancy()?;
+        }
+
+        let price_index = &self.price_index;
+        if !price_index.enabled {
+            return Ok(());
+        }
+        if price_index.publish_interval_ms == 0
+            || price_index.stale_after_ms == 0
+            || price_index.min_sources == 0
+        {
+            return Err(
+                "Price index publish interval, staleness and minimum sources cannot be 0"
+                    .to_string(),
+            );
+        }
+        if !(price_index.basis_ema_alpha > 0.0 && price_index.basis_ema_alpha <= 1.0) {
+            return Err("Price index basis smoothing factor must be in (0, 1]".to_string());
+        }
+        if ![price_index.max_deviation_pct, price_index.max_basis_pct]
+            .iter()
+            .all(|pct| pct.is_finite() && *pct >= 0.0)
+        {
+            return Err("Price index deviation and basis limits must be non-negative".to_string());
+        }
+
+        let mut sources: HashMap<Symbol, Vec<&str>> = HashMap::new();
+        for feed in &price_index.feeds {
+            let symbol = Symbol::register(&feed.symbol)
+                .map_err(|_| format!("Invalid price feed symbol {}", feed.symbol))?;
+            if feed.name.is_empty() || feed.url.is_empty() {
+                return Err(format!(
+                    "Price feeds for {} need a name and a URL",
+                    feed.symbol
+                ));
+            }
+            if !(feed.price_pointer.is_empty() || feed.price_pointer.starts_with('/')) {
+                return Err(format!(
+                    "Price feed {} pointer must be empty or start with '/'",
+                    feed.name
+                ));
+            }
+            if !(feed.weight.is_finite() && feed.weight > 0.0) {
+                return Err(format!("Price feed {} weight must be positive", feed.name));
+            }
+            if feed.kind == PriceFeedKind::Websocket && !feed.url.starts_with("ws://") {
+                return Err(format!(
+                    "Price feed {} WebSocket URL must use ws://",
+                    feed.name
+                ));
+            }
+            if feed.kind == PriceFeedKind::Http && feed.poll_interval_ms == 0 {
+                return Err(format!(
+                    "Price feed {} poll interval cannot be 0",
+                    feed.name
+                ));
+            }
+            let names = sources.entry(symbol).or_default();
+            if names.contains(&feed.name.as_str()) {
+                return Err(format!("Duplicate price feed {} for {}", feed.name, symbol));
+            }
+            names.push(&feed.name);
+        }
+        if let Some((symbol, _)) = sources
+            .iter()
+            .find(|(_, names)| names.len() < price_index.min_sources)
+        {
+            return Err(format!(
+                "Price index for {} has fewer feeds than min_sources",
+                symbol
+            ));
+        }
+        Ok(())
+    }
+
+    /// 验证多租户配置：租户ID和 API key 不能为空或重复，覆盖项按引擎配置的规则检查；
+    /// 与单个引擎绑定的集成（复制、事件发布、Redis、Webhook、成交监控、指数价格、Parquet 归档、gRPC）不能同时启用
+    fn validate_tenancy(&self) -> Result<(), String> {
+        let tenancy = &self.tenancy;
+        if tenancy.api_key_header.is_empty() {
+            return Err("Tenant API key header cannot be empty".to_string());
+        }
+        if tenancy.tenants.is_empty() {
+            return Err("Multi-tenancy is enabled but no tenants are configured".to_string());
+        }
+
+        let unsupported = [
+            (
+                self.replication.role != ReplicationRole::Disabled,
+                "replication",
+            ),
+            (self.events.enabled, "event publishing"),
+            (self.redis.is_some(), "Redis publishing"),
+            (self.webhooks.enabled, "webhooks"),
+            (self.surveillance.enabled, "trade surveillance"),
+            (self.price_index.enabled, "the price index"),
+            (self.export.enabled, "the Parquet archiver"),
+            (self.server.grpc.enabled, "gRPC"),
+        ];
+        if let Some((_, name)) = unsupported.iter().find(|(enabled, _)| *enabled) {
+            return Err(format!("Multi-tenancy cannot be combined with {}", name));
+        }
+
+        let mut ids = HashSet::new();
+        let mut api_keys = HashSet::new();
+        for tenant in &tenancy.tenants {
+            if tenant.id.is_empty()
+                || !tenant
+                    .id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                return Err(format!(
+                    "Invalid tenant id {:?}, use letters, digits, '-' or '_'",
+                    tenant.id
+                ));
+            }
+            if !ids.insert(tenant.id.as_str()) {
+                return Err(format!("Duplicate tenant id {}", tenant.id));
+            }
+            if tenant.api_keys.is_empty() {
+                return Err(format!("Tenant {} has no API keys", tenant.id));
+            }
+            for api_key in &tenant.api_keys {
+                if api_key.is_empty() {
+                    return Err(format!("Tenant {} has an empty API key", tenant.id));
+                }
+                if !api_keys.insert(api_key.as_str()) {
+                    return Err(format!(
+                        "API key of tenant {} is already assigned to another tenant",
+                        tenant.id
+                    ));
+                }
+            }
+            validate_symbols(&tenant.symbols)
+                .and_then(|_| tenant.fees.as_ref().map_or(Ok(()), validate_fees))
+                .and_then(|_| {
+                    tenant
+                        .user_limits
+                        .as_ref()
+                        .map_or(Ok(()), validate_user_limits)
+                })
+                .map_err(|e| format!("Tenant {}: {}", tenant.id, e))?;
+        }
         Ok(())
     }
 }
 
-impl Default for AppConfig {
-    fn default() -> Self {
-        Self {
-            server: ServerConfig::default(),
-            logging: LoggingConfig::default(),
-            monitoring: MonitoringConfig::default(),
-            engine: EngineConfig::default(),
-            database: None,
-            redis: None,
+/// 手续费率必须小于 1，taker 费率不能为负数；档位门槛必须为正数且严格递增
+fn validate_fees(fees: &FeeConfig) -> Result<(), String> {
+    let rates = std::iter::once((fees.maker_rate, fees.taker_rate)).chain(
+        fees.tiers
+            .iter()
+            .map(|tier| (tier.maker_rate, tier.taker_rate)),
+    );
+    for (maker_rate, taker_rate) in rates {
+        if maker_rate.abs() >= 1.0 || !(0.0..1.0).contains(&taker_rate) {
+            return Err(
+                "Fee rates must be below 1, and the taker fee rate cannot be negative".to_string(),
+            );
+        }
+    }
+    if fees.volume_window_days == 0 {
+        return Err("Fee volume window must be at least 1 day".to_string());
+    }
+    let settlement = &fees.settlement;
+    if settlement.enabled
+        && (settlement.interval_seconds == 0
+            || settlement.history_size == 0
+            || settlement.fee_account.is_empty())
+    {
+        return Err(
+            "Fee settlement needs a fee account, a non-zero interval and history size".to_string(),
+        );
+    }
+    let mut previous = 0.0;
+    for tier in &fees.tiers {
+        if !(tier.min_volume.is_finite() && tier.min_volume > previous) {
+            return Err(
+                "Fee tier volume thresholds must be positive and strictly increasing".to_string(),
+            );
+        }
+        previous = tier.min_volume;
+    }
+    Ok(())
+}
+
+/// 挂单名义价值限额必须为正数
+fn validate_user_limits(user_limits: &UserLimitsConfig) -> Result<(), String> {
+    for limits in std::iter::once(&user_limits.default)
+        .chain(user_limits.overrides.values())
+        .chain(user_limits.channels.values())
+    {
+        let notional_limits = [
+            limits.max_open_notional_per_symbol,
+            limits.max_open_notional,
+        ];
+        if notional_limits.iter().flatten().any(|limit| *limit <= 0.0) {
+            return Err("User open notional limits must be positive".to_string());
         }
     }
+    Ok(())
+}
+
+/// 开盘时间必须早于收盘时间，集合竞价不能早于当天零点，时区偏移在 ±18 小时以内
+fn validate_session(session: &TradingSession) -> Result<(), String> {
+    if session.open >= session.close {
+        return Err("Trading session open time must be before close time".to_string());
+    }
+    let open_minutes = session.open.num_seconds_from_midnight() / 60;
+    if session.pre_open_minutes > open_minutes {
+        return Err("Trading session pre-open cannot start before midnight".to_string());
+    }
+    if session.utc_offset_minutes.abs() > 18 * 60 {
+        return Err("Trading session UTC offset must be within 18 hours".to_string());
+    }
+    Ok(())
+}
+
+/// 交易对必须能解析
+fn validate_symbols(symbols: &[String]) -> Result<(), String> {
+    for symbol in symbols {
+        Symbol::register(symbol).map_err(|_| format!("Invalid symbol {}", symbol))?;
+    }
+    Ok(())
 }
 
 impl Default for ServerConfig {
@@ -245,6 +2135,70 @@ impl Default for ServerConfig {
             cors: CorsConfig::default(),
             request_timeout: 30,
             max_request_size: 1024 * 1024, // 1MB
+            websocket: WebSocketConfig::default(),
+            tls: TlsConfig::default(),
+            grpc: GrpcConfig::default(),
+            binance: BinanceCompatConfig::default(),
+            auth: AuthConfig::default(),
+            admin: AdminConfig::default(),
+        }
+    }
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self {
+            port: 0,
+            tokens: Vec::new(),
+            audit_dir: "data/audit".to_string(),
+        }
+    }
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            jwt_secret: String::new(),
+            leeway_seconds: 30,
+            api_key_header: "x-user-api-key".to_string(),
+            api_keys_path: "data/api_keys.json".to_string(),
+            api_key_rate_limit_per_minute: 1200,
+        }
+    }
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cert_path: "certs/server.crt".to_string(),
+            key_path: "certs/server.key".to_string(),
+            client_ca_path: None,
+            reload_interval_seconds: 60,
+        }
+    }
+}
+
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 50051,
+        }
+    }
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            heartbeat_interval: 30,
+            heartbeat_timeout: 90,
+            outbound_queue_size: 1024,
+            drop_copy_tokens: Vec::new(),
+            replay_buffer_size: default_replay_buffer_size(),
+            max_symbols_per_connection: default_max_symbols_per_connection(),
+            min_conflation_ms: default_min_conflation_ms(),
         }
     }
 }
@@ -274,6 +2228,18 @@ impl Default for LoggingConfig {
             console: true,
             json_format: false,
             rotation: LogRotationConfig::default(),
+            otlp: OtlpConfig::default(),
+        }
+    }
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: "http://localhost:4318/v1/traces".to_string(),
+            service_name: "matching-engine".to_string(),
+            sample_ratio: 1.0,
         }
     }
 }
@@ -298,6 +2264,8 @@ impl Default for MonitoringConfig {
             health_path: "/health".to_string(),
             enable_performance_metrics: true,
             enable_business_metrics: true,
+            serve_on_api: false,
+            symbol_metrics_interval_seconds: default_symbol_metrics_interval(),
         }
     }
 }
@@ -318,75 +2286,520 @@ impl Default for EngineConfig {
                 "ETHUSDT".to_string(),
                 "BNBUSDT".to_string(),
             ],
+            restrict_symbols: false,
+            circuit_breaker: CircuitBreakerConfig::default(),
+            trade_price_rule: TradePriceRule::default(),
+            price_reference: PriceReferenceConfig::default(),
+            allocation: AllocationConfig::default(),
+            user_limits: UserLimitsConfig::default(),
+            order_retention: OrderRetentionConfig::default(),
+            trade_history: TradeHistoryConfig::default(),
+            volume_stats: VolumeStatsConfig::default(),
+            id_shard: 0,
+            audit: AuditConfig::default(),
+            shutdown: ShutdownConfig::default(),
+            fees: FeeConfig::default(),
+            tick_sizes: TickSizeConfig::default(),
+            drop_copy: DropCopyConfig::default(),
+            message_ratios: MessageRatioConfig::default(),
+            submission_queue: SubmissionQueueConfig::default(),
+            latency: LatencyConfig::default(),
+            sandbox: SandboxConfig::default(),
+            margin: MarginConfig::default(),
+            liquidation: LiquidationConfig::default(),
+            funding: FundingConfig::default(),
+            otc: OtcConfig::default(),
+            sessions: SessionConfig::default(),
+            end_of_day: EndOfDayConfig::default(),
+            market_data: MarketDataConfig::default(),
+            event_bus: EventBusConfig::default(),
+            event_journal: EventJournalConfig::default(),
         }
     }
 }
 
-/// 配置构建器
-pub struct ConfigBuilder {
-    config: AppConfig,
+impl Default for RedisConfig {
+    fn default() -> Self {
+        Self {
+            url: "redis://127.0.0.1:6379".to_string(),
+            max_connections: 10,
+            connection_timeout: 5,
+            command_timeout: 5,
+            key_prefix: default_redis_key_prefix(),
+            snapshot_ttl_seconds: default_redis_snapshot_ttl(),
+            snapshot_interval_ms: default_redis_snapshot_interval(),
+            depth_levels: default_redis_depth_levels(),
+        }
+    }
 }
 
-impl ConfigBuilder {
-    pub fn new() -> Self {
+impl Default for EventsConfig {
+    fn default() -> Self {
         Self {
-            config: AppConfig::default(),
+            enabled: false,
+            topics: EventTopicsConfig::default(),
+            depth_levels: 20,
+            max_retries: 3,
+            retry_backoff_ms: 100,
+            kafka: None,
+            nats: None,
         }
     }
+}
 
-    pub fn server(mut self, server: ServerConfig) -> Self {
-        self.config.server = server;
-        self
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_webhooks_per_user: 5,
+            queue_size: 10000,
+            max_concurrent_deliveries: 64,
+            request_timeout_ms: 5000,
+            max_retries: 5,
+            retry_backoff_ms: 500,
+            max_retry_backoff_ms: 60_000,
+        }
     }
+}
 
-    pub fn logging(mut self, logging: LoggingConfig) -> Self {
-        self.config.logging = logging;
-        self
+impl Default for SurveillanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_alerts: 10000,
+            correlated_accounts: Vec::new(),
+            ping_pong_window_ms: 60_000,
+            ping_pong_min_reversals: 4,
+            layering_window_ms: 60_000,
+            layering_near_touch_bps: 10.0,
+            layering_min_orders: 10,
+            layering_cancel_ratio: 0.9,
+        }
     }
+}
 
-    pub fn monitoring(mut self, monitoring: MonitoringConfig) -> Self {
-        self.config.monitoring = monitoring;
-        self
+impl Default for PriceIndexConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            publish_interval_ms: 1000,
+            stale_after_ms: 10_000,
+            min_sources: 1,
+            max_deviation_pct: 0.0,
+            basis_ema_alpha: 0.1,
+            max_basis_pct: 0.5,
+            feeds: Vec::new(),
+        }
     }
+}
 
-    pub fn engine(mut self, engine: EngineConfig) -> Self {
-        self.config.engine = engine;
-        self
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: "data/export".to_string(),
+            flush_interval_seconds: 300,
+            max_rows_per_file: 100_000,
+            s3: None,
+        }
     }
+}
 
-    pub fn database(mut self, database: DatabaseConfig) -> Self {
-        self.config.database = Some(database);
-        self
+impl Default for S3ExportConfig {
+    fn default() -> Self {
+        Self {
+            bucket: String::new(),
+            prefix: String::new(),
+            region: "us-east-1".to_string(),
+            endpoint: None,
+            access_key_id: None,
+            secret_access_key: None,
+            allow_http: false,
+            keep_local: true,
+        }
     }
+}
 
-    pub fn redis(mut self, redis: RedisConfig) -> Self {
-        self.config.redis = Some(redis);
-        self
+impl Default for PriceFeedConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            symbol: String::new(),
+            kind: PriceFeedKind::Http,
+            url: String::new(),
+            price_pointer: String::new(),
+            weight: 1.0,
+            poll_interval_ms: 1000,
+            subscribe_message: None,
+        }
     }
+}
 
-    pub fn build(self) -> Result<AppConfig, String> {
-        self.config.validate()?;
-        Ok(self.config)
+impl Default for ReplicationConfig {
+    fn default() -> Self {
+        Self {
+            role: ReplicationRole::Disabled,
+            listen_addr: "0.0.0.0:9200".to_string(),
+            primary_addr: "127.0.0.1:9200".to_string(),
+            token: String::new(),
+            heartbeat_interval_ms: 1000,
+            reconnect_backoff_ms: 1000,
+        }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl Default for TenancyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            api_key_header: "x-api-key".to_string(),
+            tenants: Vec::new(),
+        }
+    }
+}
 
-    #[test]
-    fn test_default_config() {
-        let config = AppConfig::default();
-        assert_eq!(config.server.port, 8080);
-        assert_eq!(config.logging.level, "info");
-        assert!(config.monitoring.enabled);
-        assert!(config.engine.enable_price_protection);
+impl Default for EventTopicsConfig {
+    fn default() -> Self {
+        Self {
+            trades: "engine.trades".to_string(),
+            orders: "engine.orders".to_string(),
+            depth: "engine.depth".to_string(),
+        }
     }
+}
 
-    #[test]
-    fn test_config_validation() {
-        let mut config = AppConfig::default();
-        assert!(config.validate().is_ok());
+impl Default for KafkaSinkConfig {
+    fn default() -> Self {
+        Self {
+            brokers: "localhost:9092".to_string(),
+            client_id: "matching-engine".to_string(),
+            message_timeout_ms: 5000,
+            properties: HashMap::new(),
+        }
+    }
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            drain_timeout_seconds: 10,
+            snapshot_path: None,
+        }
+    }
+}
+
+impl Default for EventJournalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: "data/events".to_string(),
+        }
+    }
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: "data/audit".to_string(),
+        }
+    }
+}
+
+impl Default for EventBusConfig {
+    fn default() -> Self {
+        Self {
+            replay_capacity: 100_000,
+        }
+    }
+}
+
+impl Default for MessageRatioConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_seconds: 60,
+            min_messages: 100,
+            max_messages_per_fill: 100.0,
+            max_cancels_per_fill: 50.0,
+            exempt_users: Vec::new(),
+        }
+    }
+}
+
+impl Default for SubmissionQueueConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            capacity: 1000,
+            retry_after_seconds: 1,
+        }
+    }
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            initial_balances: HashMap::from([
+                ("USDT".to_string(), 100_000.0),
+                ("BTC".to_string(), 10.0),
+                ("ETH".to_string(), 100.0),
+            ]),
+        }
+    }
+}
+
+impl Default for MarginConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            collateral_asset: "USDT".to_string(),
+            check_interval_ms: 1000,
+            default: MarginRequirement {
+                initial_margin_pct: 10.0,
+                maintenance_margin_pct: 5.0,
+            },
+            symbols: HashMap::new(),
+        }
+    }
+}
+
+impl Default for LiquidationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            price_band_pct: 5.0,
+            fee_pct: 0.5,
+            insurance_fund_account: "insurance-fund".to_string(),
+            history_size: 1000,
+        }
+    }
+}
+
+impl Default for EndOfDayConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cutoff: NaiveTime::MIN,
+            utc_offset_minutes: 0,
+            report_dir: "data/settlement".to_string(),
+        }
+    }
+}
+
+impl Default for MarketDataConfig {
+    fn default() -> Self {
+        Self {
+            min_publish_interval_ms: 100,
+            overrides: HashMap::new(),
+        }
+    }
+}
+
+impl Default for FeeSettlementConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_seconds: 24 * 60 * 60,
+            fee_account: "fee-revenue".to_string(),
+            history_size: 100,
+        }
+    }
+}
+
+impl Default for FundingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_seconds: 8 * 60 * 60,
+            interest_rate_pct: 0.01,
+            max_rate_pct: 0.75,
+            symbols: Vec::new(),
+            history_size: 1000,
+        }
+    }
+}
+
+impl Default for TradingSession {
+    fn default() -> Self {
+        Self {
+            open: NaiveTime::from_hms_opt(9, 30, 0).unwrap_or_default(),
+            close: NaiveTime::from_hms_opt(16, 0, 0).unwrap_or_default(),
+            pre_open_minutes: 0,
+            weekend_closed: true,
+            utc_offset_minutes: 0,
+            queue_when_closed: false,
+        }
+    }
+}
+
+impl Default for OtcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            price_band_pct: 10.0,
+            include_in_volume: true,
+            update_last_price: false,
+        }
+    }
+}
+
+impl Default for LatencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            per_symbol: false,
+        }
+    }
+}
+
+impl Default for TradeHistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention_seconds: 3600,
+            dir: "data/trades".to_string(),
+        }
+    }
+}
+
+impl Default for VolumeStatsConfig {
+    fn default() -> Self {
+        Self {
+            retention_hours: 168,
+        }
+    }
+}
+
+impl Default for OrderRetentionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention_seconds: 600,
+            archive_dir: "data/archive".to_string(),
+            segment_max_orders: 100_000,
+        }
+    }
+}
+
+impl Default for AllocationConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: AllocationAlgorithm::Fifo,
+            min_allocation: 0.0,
+            fifo_percentage: 40.0,
+            symbols: HashMap::new(),
+        }
+    }
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_price_move: 10.0, // 10%
+            window_seconds: 300,
+            cooldown_seconds: 60,
+            cancel_only: false,
+        }
+    }
+}
+
+/// 配置构建器
+#[derive(Default)]
+pub struct ConfigBuilder {
+    config: AppConfig,
+}
+
+impl ConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn server(mut self, server: ServerConfig) -> Self {
+        self.config.server = server;
+        self
+    }
+
+    pub fn logging(mut self, logging: LoggingConfig) -> Self {
+        self.config.logging = logging;
+        self
+    }
+
+    pub fn monitoring(mut self, monitoring: MonitoringConfig) -> Self {
+        self.config.monitoring = monitoring;
+        self
+    }
+
+    pub fn engine(mut self, engine: EngineConfig) -> Self {
+        self.config.engine = engine;
+        self
+    }
+
+    pub fn database(mut self, database: DatabaseConfig) -> Self {
+        self.config.database = Some(database);
+        self
+    }
+
+    pub fn redis(mut self, redis: RedisConfig) -> Self {
+        self.config.redis = Some(redis);
+        self
+    }
+
+    pub fn events(mut self, events: EventsConfig) -> Self {
+        self.config.events = events;
+        self
+    }
+
+    pub fn webhooks(mut self, webhooks: WebhookConfig) -> Self {
+        self.config.webhooks = webhooks;
+        self
+    }
+
+    pub fn surveillance(mut self, surveillance: SurveillanceConfig) -> Self {
+        self.config.surveillance = surveillance;
+        self
+    }
+
+    pub fn price_index(mut self, price_index: PriceIndexConfig) -> Self {
+        self.config.price_index = price_index;
+        self
+    }
+
+    pub fn export(mut self, export: ExportConfig) -> Self {
+        self.config.export = export;
+        self
+    }
+
+    pub fn replication(mut self, replication: ReplicationConfig) -> Self {
+        self.config.replication = replication;
+        self
+    }
+
+    pub fn build(self) -> Result<AppConfig, String> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config() {
+        let config = AppConfig::default();
+        assert_eq!(config.server.port, 8080);
+        assert_eq!(config.logging.level, "info");
+        assert!(config.monitoring.enabled);
+        assert!(config.engine.enable_price_protection);
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let mut config = AppConfig::default();
+        assert!(config.validate().is_ok());
 
         // 测试无效端口
         config.server.port = 0;
@@ -398,6 +2811,421 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_websocket_heartbeat_validation() {
+        let mut config = AppConfig::default();
+
+        config.server.websocket.heartbeat_interval = 0;
+        assert!(config.validate().is_err());
+
+        // 超时时间必须大于心跳间隔
+        config.server.websocket.heartbeat_interval = 30;
+        config.server.websocket.heartbeat_timeout = 30;
+        assert!(config.validate().is_err());
+
+        config.server.websocket.heartbeat_timeout = 60;
+        assert!(config.validate().is_ok());
+
+        config.server.websocket.drop_copy_tokens = vec![String::new()];
+        assert!(config.validate().is_err());
+
+        config.server.websocket.drop_copy_tokens.clear();
+        config.server.auth.enabled = true;
+        assert!(config.validate().is_err());
+
+        config.server.auth.jwt_secret = "secret".to_string();
+        assert!(config.validate().is_ok());
+
+        config.server.admin.port = config.server.port;
+        assert!(config.validate().is_err());
+        config.server.admin.port = 9443;
+        let token = |actor: &str| AdminToken {
+            actor: actor.to_string(),
+            token: "admin-secret".to_string(),
+        };
+        config.server.admin.tokens = vec![token("ops"), token("risk")];
+        assert!(config.validate().is_err());
+        config.server.admin.tokens.pop();
+        assert!(config.validate().is_ok());
+
+        config.server.websocket.replay_buffer_size = 0;
+        assert!(config.validate().is_err());
+
+        config.server.websocket.replay_buffer_size = 1024;
+        config.server.websocket.max_symbols_per_connection = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_drop_copy_validation() {
+        let mut config = AppConfig::default();
+        config.engine.event_bus.replay_capacity = 0;
+        // 未启用时不校验
+        assert!(config.validate().is_ok());
+
+        config.engine.drop_copy.enabled = true;
+        assert!(config.validate().is_err());
+
+        config.engine.event_bus.replay_capacity = 1000;
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_circuit_breaker_validation() {
+        let mut config = AppConfig::default();
+        config.engine.circuit_breaker.max_price_move = 0.0;
+        // 未启用时不校验
+        assert!(config.validate().is_ok());
+
+        config.engine.circuit_breaker.enabled = true;
+        assert!(config.validate().is_err());
+
+        config.engine.circuit_breaker.max_price_move = 5.0;
+        assert!(config.validate().is_ok());
+
+        config.engine.circuit_breaker.cooldown_seconds = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_allocation_algorithm_per_symbol() {
+        let mut config = AppConfig::default();
+        config
+            .engine
+            .allocation
+            .symbols
+            .insert("ETHUSDT".to_string(), AllocationAlgorithm::ProRata);
+
+        let allocation = &config.engine.allocation;
+        assert_eq!(
+            allocation.algorithm_for(&Symbol::new("BTC", "USDT")),
+            AllocationAlgorithm::Fifo
+        );
+        assert_eq!(
+            allocation.algorithm_for(&Symbol::new("ETH", "USDT")),
+            AllocationAlgorithm::ProRata
+        );
+
+        config.engine.allocation.fifo_percentage = 120.0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_tick_size_validation() {
+        let mut config = AppConfig::default();
+        config
+            .engine
+            .tick_sizes
+            .overrides
+            .insert("BTCUSDT".to_string(), 0.01);
+        assert!(config.validate().is_ok());
+
+        config.engine.tick_sizes.default = 0.0;
+        assert!(config.validate().is_err());
+
+        // 超过 12 位小数的 tick 无法精确表示
+        config.engine.tick_sizes.default = 1e-13;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_redis_validation() {
+        let mut config = AppConfig {
+            redis: Some(RedisConfig::default()),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        config.redis.as_mut().unwrap().snapshot_ttl_seconds = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_export_validation() {
+        let mut config = AppConfig::default();
+        config.export.enabled = true;
+        config.export.s3 = Some(S3ExportConfig {
+            bucket: "archive".to_string(),
+            ..Default::default()
+        });
+        assert!(config.validate().is_ok());
+
+        config.export.s3.as_mut().unwrap().bucket.clear();
+        assert!(config.validate().is_err());
+
+        config.export.s3 = None;
+        config.export.max_rows_per_file = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_price_index_validation() {
+        let feed = |name: &str| PriceFeedConfig {
+            name: name.to_string(),
+            symbol: "BTCUSDT".to_string(),
+            url: "https://example.com/ticker".to_string(),
+            price_pointer: "/price".to_string(),
+            ..Default::default()
+        };
+        let mut config = AppConfig::default();
+        config.price_index.enabled = true;
+        config.price_index.min_sources = 2;
+        config.price_index.feeds = vec![feed("a"), feed("b")];
+        assert!(config.validate().is_ok());
+
+        config.price_index.feeds[1].name = "a".to_string();
+        assert!(config.validate().is_err());
+
+        config.price_index.feeds.pop();
+        assert!(config.validate().is_err());
+
+        config.price_index.min_sources = 1;
+        config.price_index.feeds[0].price_pointer = "price".to_string();
+        assert!(config.validate().is_err());
+
+        config.price_index.feeds[0].price_pointer = "/price".to_string();
+        config.price_index.feeds[0].kind = PriceFeedKind::Websocket;
+        assert!(config.validate().is_err());
+        config.price_index.feeds[0].url = "ws://127.0.0.1:9000/ticker".to_string();
+        assert!(config.validate().is_ok());
+
+        config.price_index.basis_ema_alpha = 0.0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_webhook_validation() {
+        let mut config = AppConfig::default();
+        config.webhooks.enabled = true;
+        assert!(config.validate().is_ok());
+
+        config.webhooks.retry_backoff_ms = config.webhooks.max_retry_backoff_ms + 1;
+        assert!(config.validate().is_err());
+
+        config.webhooks = WebhookConfig {
+            enabled: true,
+            queue_size: 0,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_message_ratio_validation() {
+        let mut config = AppConfig::default();
+        config.engine.message_ratios.enabled = true;
+        assert!(config.validate().is_ok());
+
+        config.engine.message_ratios.max_cancels_per_fill = 0.0;
+        assert!(config.validate().is_err());
+
+        config.engine.message_ratios = MessageRatioConfig {
+            enabled: true,
+            window_seconds: 0,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_surveillance_validation() {
+        let mut config = AppConfig::default();
+        config.surveillance.enabled = true;
+        assert!(config.validate().is_ok());
+
+        config.surveillance.layering_cancel_ratio = 1.5;
+        assert!(config.validate().is_err());
+
+        config.surveillance = SurveillanceConfig {
+            enabled: true,
+            correlated_accounts: vec![vec!["alice".to_string()]],
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_sandbox_validation() {
+        let mut config = AppConfig::default();
+        config.engine.sandbox.enabled = true;
+        assert!(config.validate().is_ok());
+        config
+            .engine
+            .sandbox
+            .initial_balances
+            .insert("USDT".to_string(), 0.0);
+        assert!(config.validate().is_err());
+        config.engine.sandbox.initial_balances.clear();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_margin_validation() {
+        let mut config = AppConfig::default();
+        config.engine.margin.enabled = true;
+        assert!(config.validate().is_ok());
+
+        config.engine.margin.symbols.insert(
+            "BTCUSDT".to_string(),
+            MarginRequirement {
+                initial_margin_pct: 2.0,
+                maintenance_margin_pct: 5.0,
+            },
+        );
+        assert!(config.validate().is_err());
+
+        config.engine.margin.symbols.clear();
+        config.engine.sandbox.enabled = true;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_liquidation_validation() {
+        let mut config = AppConfig::default();
+        config.engine.liquidation.enabled = true;
+        assert!(config.validate().is_err());
+
+        config.engine.margin.enabled = true;
+        assert!(config.validate().is_ok());
+
+        config.engine.liquidation.price_band_pct = 0.0;
+        assert!(config.validate().is_err());
+        config.engine.liquidation.price_band_pct = 5.0;
+        config.engine.liquidation.insurance_fund_account.clear();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_funding_validation() {
+        let mut config = AppConfig::default();
+        config.engine.funding.enabled = true;
+        assert!(config.validate().is_err());
+
+        config.engine.margin.enabled = true;
+        assert!(config.validate().is_ok());
+
+        config.engine.funding.interval_seconds = 0;
+        assert!(config.validate().is_err());
+        config.engine.funding.interval_seconds = 3600;
+        config.engine.funding.max_rate_pct = 0.0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_submission_queue_validation() {
+        let mut config = AppConfig::default();
+        config.engine.submission_queue.capacity = 0;
+        assert!(config.validate().is_ok());
+
+        config.engine.submission_queue.enabled = true;
+        assert!(config.validate().is_err());
+        config.engine.submission_queue.capacity = 10;
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_replication_validation() {
+        let mut config = AppConfig::default();
+        config.replication.role = ReplicationRole::Standby;
+        assert!(config.validate().is_ok());
+
+        // 主实例从事件总线的回放缓冲区续传事件
+        config.replication.role = ReplicationRole::Primary;
+        assert!(config.validate().is_ok());
+        config.engine.event_bus.replay_capacity = 0;
+        assert!(config.validate().is_err());
+        config.engine.event_bus.replay_capacity = 1000;
+
+        config.replication.listen_addr = "localhost".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_events_validation() {
+        let mut config = AppConfig::default();
+        config.events.enabled = true;
+        // 启用时至少需要一个投递目标
+        assert!(config.validate().is_err());
+
+        config.events.kafka = Some(KafkaSinkConfig::default());
+        assert!(config.validate().is_ok());
+
+        config.events.topics.depth = String::new();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_fee_tier_validation() {
+        let tier = |min_volume: f64, taker_rate: f64| FeeTier {
+            min_volume,
+            maker_rate: 0.0,
+            taker_rate,
+        };
+        let mut config = AppConfig::default();
+        config.engine.fees.tiers = vec![tier(1_000.0, 0.001), tier(10_000.0, 0.0005)];
+        assert!(config.validate().is_ok());
+
+        // 门槛必须严格递增
+        config.engine.fees.tiers[1].min_volume = 1_000.0;
+        assert!(config.validate().is_err());
+
+        config.engine.fees.tiers = vec![tier(1_000.0, -0.001)];
+        assert!(config.validate().is_err());
+
+        config.engine.fees.tiers = Vec::new();
+        config.engine.fees.volume_window_days = 0;
+        assert!(config.validate().is_err());
+        config.engine.fees.volume_window_days = 30;
+
+        config.engine.fees.settlement.enabled = true;
+        assert!(config.validate().is_ok());
+        config.engine.fees.settlement.fee_account.clear();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_tenancy_validation() {
+        let tenant = |id: &str, api_key: &str| TenantConfig {
+            id: id.to_string(),
+            api_keys: vec![api_key.to_string()],
+            symbols: vec!["BTCUSDT".to_string()],
+            fees: None,
+            user_limits: None,
+        };
+        let mut config = AppConfig::default();
+        config.tenancy.enabled = true;
+        assert!(config.validate().is_err());
+
+        config.tenancy.tenants = vec![tenant("venue-a", "key-a"), tenant("venue-b", "key-b")];
+        assert!(config.validate().is_ok());
+
+        // API key 不能分配给多个租户
+        config.tenancy.tenants[1].api_keys = vec!["key-a".to_string()];
+        assert!(config.validate().is_err());
+        config.tenancy.tenants[1].api_keys = vec!["key-b".to_string()];
+
+        config.tenancy.tenants[1].fees = Some(FeeConfig {
+            maker_rate: 0.0,
+            taker_rate: -0.001,
+            ..Default::default()
+        });
+        assert!(config.validate().is_err());
+        config.tenancy.tenants[1].fees = None;
+
+        config.events.enabled = true;
+        config.events.kafka = Some(KafkaSinkConfig::default());
+        assert!(config.validate().is_err());
+        config.events.enabled = false;
+
+        // 审计目录按租户细分，只开放配置的交易对
+        let engine = config.tenancy.tenants[0].engine_config(&config.engine);
+        assert!(engine.restrict_symbols);
+        assert_eq!(
+            Path::new(&engine.audit.dir),
+            Path::new(&config.engine.audit.dir).join("tenant-venue-a")
+        );
+    }
+
     #[test]
     fn test_config_builder() {
         let config = ConfigBuilder::new()
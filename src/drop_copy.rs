@@ -0,0 +1,109 @@
+use crate::event_bus::{EventSubscription, SequencedEvent};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Drop copy 订阅：事件总线订阅中只保留订单事件（含被拒订单）、成交（含场外成交）和成交撤销
+///
+/// 事件沿用总线的全局序号，相邻两条 drop copy 事件之间的序号属于其它类型的总线事件。
+/// 先发送 `replay` 中的历史事件，再用 [`DropCopySubscription::recv`] 接收后续事件，两者之间没有缺口和重复
+#[derive(Debug)]
+pub struct DropCopySubscription {
+    /// 该订阅可能收到的第一条事件的序号
+    pub next_sequence: u64,
+    pub replay: Vec<SequencedEvent>,
+    live: broadcast::Receiver<SequencedEvent>,
+}
+
+impl DropCopySubscription {
+    pub fn new(subscription: EventSubscription) -> Self {
+        let EventSubscription {
+            next_sequence,
+            mut replay,
+            live,
+        } = subscription;
+        replay.retain(|event| event.event.is_drop_copy());
+        Self {
+            next_sequence,
+            replay,
+            live,
+        }
+    }
+
+    /// 接收下一条实时 drop copy 事件，跳过其它类型的总线事件
+    pub async fn recv(&mut self) -> Result<SequencedEvent, RecvError> {
+        loop {
+            let event = self.live.recv().await?;
+            if event.event.is_drop_copy() {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// 不等待地接收下一条实时 drop copy 事件
+    pub fn try_recv(&mut self) -> Option<SequencedEvent> {
+        while let Ok(event) = self.live.try_recv() {
+            if event.event.is_drop_copy() {
+                return Some(event);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_bus::{EngineEvent, EventBus};
+    use crate::types::{MarketData, Order, OrderSide, OrderType, Symbol};
+    use chrono::Utc;
+
+    fn order() -> Order {
+        Order::new(
+            Symbol::new("BTC", "USDT"),
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            "user1".to_string(),
+        )
+    }
+
+    fn market_data() -> MarketData {
+        MarketData {
+            symbol: Symbol::new("BTC", "USDT"),
+            last_price: 100.0,
+            volume_24h: 0.0,
+            price_change_24h: 0.0,
+            high_24h: 100.0,
+            low_24h: 100.0,
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_filters_bus_events_and_keeps_bus_sequence() {
+        let bus = EventBus::new().with_replay_capacity(10);
+        bus.publish(EngineEvent::OrderUpdate(order()), Utc::now());
+        bus.publish(EngineEvent::MarketData(market_data()), Utc::now());
+        bus.publish(
+            EngineEvent::OrderRejected {
+                order: order(),
+                reason: "Invalid quantity".to_string(),
+            },
+            Utc::now(),
+        );
+
+        let mut subscription = DropCopySubscription::new(bus.subscribe_from(Some(1)).unwrap());
+        let replayed: Vec<u64> = subscription
+            .replay
+            .iter()
+            .map(|event| event.sequence)
+            .collect();
+        assert_eq!(replayed, vec![1, 3]);
+
+        bus.publish(EngineEvent::MarketData(market_data()), Utc::now());
+        bus.publish(EngineEvent::OrderUpdate(order()), Utc::now());
+        assert_eq!(subscription.try_recv().unwrap().sequence, 5);
+        assert!(subscription.try_recv().is_none());
+    }
+}
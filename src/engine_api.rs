@@ -0,0 +1,112 @@
+use crate::id::OrderId;
+use crate::matching_engine::MatchingEngine;
+use crate::types::*;
+use std::future::Future;
+use tokio::sync::broadcast;
+
+/// 撮合引擎对外接口：下单、撤单、改单、查询和行情订阅
+///
+/// 嵌入本库的服务可以对该 trait 编程，测试时用 [`MockMatchingEngine`](crate::mock_engine::MockMatchingEngine)
+/// 替代真实引擎。异步方法返回 `Send` 的 future，可以在 tokio 多线程运行时中 spawn
+pub trait MatchingEngineApi: Send + Sync {
+    /// 提交订单，返回本次撮合产生的成交
+    fn submit_order(&self, order: Order)
+        -> impl Future<Output = Result<Vec<Trade>, String>> + Send;
+
+    /// 撤销订单，返回撤销后的订单
+    fn cancel_order(
+        &self,
+        order_id: OrderId,
+        user_id: String,
+    ) -> impl Future<Output = Result<Order, String>> + Send;
+
+    /// 缩减挂单的订单总量（保留时间优先级），返回修改后的订单
+    fn amend_order(
+        &self,
+        order_id: OrderId,
+        user_id: String,
+        new_quantity: f64,
+    ) -> impl Future<Output = Result<Order, String>> + Send;
+
+    /// 按订单ID查询订单
+    fn get_order(&self, order_id: OrderId) -> Option<Order>;
+
+    /// 查询用户的所有订单
+    fn get_user_orders(&self, user_id: &str) -> Vec<Order>;
+
+    /// 查询订单簿深度
+    fn get_orderbook_depth(&self, symbol: &Symbol, depth: Option<usize>) -> Option<OrderBookDepth>;
+
+    /// 查询交易对当前盘口
+    fn get_book_ticker(&self, symbol: &Symbol) -> Option<BookTicker>;
+
+    /// 查询最近成交，按成交ID从新到旧排列
+    fn get_trades(&self, symbol: Option<&Symbol>, limit: Option<usize>) -> Vec<Trade>;
+
+    /// 订阅成交
+    fn subscribe_trades(&self) -> broadcast::Receiver<Trade>;
+
+    /// 订阅订单状态更新
+    fn subscribe_orders(&self) -> broadcast::Receiver<Order>;
+
+    /// 订阅盘口变化
+    fn subscribe_book_ticker(&self) -> broadcast::Receiver<BookTicker>;
+}
+
+impl MatchingEngineApi for MatchingEngine {
+    fn submit_order(
+        &self,
+        order: Order,
+    ) -> impl Future<Output = Result<Vec<Trade>, String>> + Send {
+        MatchingEngine::submit_order(self, order)
+    }
+
+    fn cancel_order(
+        &self,
+        order_id: OrderId,
+        user_id: String,
+    ) -> impl Future<Output = Result<Order, String>> + Send {
+        MatchingEngine::cancel_order(self, order_id, user_id)
+    }
+
+    fn amend_order(
+        &self,
+        order_id: OrderId,
+        user_id: String,
+        new_quantity: f64,
+    ) -> impl Future<Output = Result<Order, String>> + Send {
+        MatchingEngine::amend_order(self, order_id, user_id, new_quantity)
+    }
+
+    fn get_order(&self, order_id: OrderId) -> Option<Order> {
+        MatchingEngine::get_order(self, order_id)
+    }
+
+    fn get_user_orders(&self, user_id: &str) -> Vec<Order> {
+        MatchingEngine::get_user_orders(self, user_id)
+    }
+
+    fn get_orderbook_depth(&self, symbol: &Symbol, depth: Option<usize>) -> Option<OrderBookDepth> {
+        MatchingEngine::get_orderbook_depth(self, symbol, depth)
+    }
+
+    fn get_book_ticker(&self, symbol: &Symbol) -> Option<BookTicker> {
+        MatchingEngine::get_book_ticker(self, symbol)
+    }
+
+    fn get_trades(&self, symbol: Option<&Symbol>, limit: Option<usize>) -> Vec<Trade> {
+        MatchingEngine::get_trades(self, symbol, limit)
+    }
+
+    fn subscribe_trades(&self) -> broadcast::Receiver<Trade> {
+        MatchingEngine::subscribe_trades(self)
+    }
+
+    fn subscribe_orders(&self) -> broadcast::Receiver<Order> {
+        MatchingEngine::subscribe_orders(self)
+    }
+
+    fn subscribe_book_ticker(&self) -> broadcast::Receiver<BookTicker> {
+        MatchingEngine::subscribe_book_ticker(self)
+    }
+}
@@ -0,0 +1,910 @@
+//! 引擎事件总线
+//!
+//! 引擎对外的全部事件（成交、场外成交、成交撤销、订单更新和拒绝、行情、盘口、交易状态、
+//! 集合竞价参考价、标记价格、强平和上下市）统一经 [`EventBus`] 发出：每条事件分配全局递增序号，
+//! 按序号顺序依次交给进程内监听器（[`EventListener`]）、回放缓冲区、序号化广播订阅方和按类型的
+//! 广播订阅方。持久化（[`EventJournal`]）、指标（[`EventMetrics`]）、drop copy、主备复制、
+//! WebSocket 推送和外部事件发布看到的是同一个有序的事件流。
+use crate::symbol::interning;
+use crate::types::*;
+use chrono::{DateTime, Utc};
+use metrics::counter;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+const JOURNAL_FILE: &str = "events.jsonl";
+const INDEX_FILE: &str = "events.idx";
+/// 索引文件每条记录的字节数：序号和文件偏移，各 8 字节小端
+const INDEX_ENTRY_SIZE: usize = 16;
+
+/// 总线上的引擎事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum EngineEvent {
+    Trade(Trade),
+    /// 场外成交，不进入撮合成交的订阅方
+    OtcTrade(Trade),
+    TradeBust(TradeBust),
+    OrderUpdate(Order),
+    /// 未被引擎接受的订单及拒绝原因
+    OrderRejected {
+        order: Order,
+        reason: String,
+    },
+    AggTrade(AggTrade),
+    MarketData(MarketData),
+    BookTicker(BookTicker),
+    SymbolStatus(SymbolStatus),
+    Auction(AuctionIndicative),
+    MarkPrice(MarkPrice),
+    Liquidation(LiquidationEvent),
+    SymbolListing(SymbolListing),
+}
+
+impl EngineEvent {
+    /// 事件类型名称，用于日志和指标
+    pub fn kind(&self) -> &'static str {
+        match self {
+            EngineEvent::Trade(_) => "trade",
+            EngineEvent::OtcTrade(_) => "otc_trade",
+            EngineEvent::TradeBust(_) => "trade_bust",
+            EngineEvent::OrderUpdate(_) => "order_update",
+            EngineEvent::OrderRejected { .. } => "order_rejected",
+            EngineEvent::AggTrade(_) => "agg_trade",
+            EngineEvent::MarketData(_) => "market_data",
+            EngineEvent::BookTicker(_) => "book_ticker",
+            EngineEvent::SymbolStatus(_) => "symbol_status",
+            EngineEvent::Auction(_) => "auction",
+            EngineEvent::MarkPrice(_) => "mark_price",
+            EngineEvent::Liquidation(_) => "liquidation",
+            EngineEvent::SymbolListing(_) => "symbol_listing",
+        }
+    }
+
+    /// 事件所属的交易对，强平事件针对整个账户，没有交易对
+    pub fn symbol(&self) -> Option<Symbol> {
+        match self {
+            EngineEvent::Trade(trade) | EngineEvent::OtcTrade(trade) => Some(trade.symbol),
+            EngineEvent::TradeBust(bust) => Some(bust.trade.symbol),
+            EngineEvent::OrderUpdate(order) | EngineEvent::OrderRejected { order, .. } => {
+                Some(order.symbol)
+            }
+            EngineEvent::AggTrade(agg_trade) => Some(agg_trade.symbol),
+            EngineEvent::MarketData(market_data) => Some(market_data.symbol),
+            EngineEvent::BookTicker(ticker) => Some(ticker.symbol),
+            EngineEvent::SymbolStatus(status) => Some(status.symbol),
+            EngineEvent::Auction(auction) => Some(auction.symbol),
+            EngineEvent::MarkPrice(mark_price) => Some(mark_price.symbol),
+            EngineEvent::Liquidation(_) => None,
+            EngineEvent::SymbolListing(listing) => Some(listing.symbol),
+        }
+    }
+
+    /// 是否属于 drop copy 事件：订单事件（含被拒订单）、成交（含场外成交）和成交撤销
+    pub fn is_drop_copy(&self) -> bool {
+        matches!(
+            self,
+            EngineEvent::Trade(_)
+                | EngineEvent::OtcTrade(_)
+                | EngineEvent::TradeBust(_)
+                | EngineEvent::OrderUpdate(_)
+                | EngineEvent::OrderRejected { .. }
+        )
+    }
+}
+
+/// 带序号的引擎事件
+///
+/// `sequence` 在一个总线内从 1 开始连续递增；启用事件日志时重启后接着日志中的最大序号继续
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequencedEvent {
+    pub sequence: u64,
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: EngineEvent,
+}
+
+/// 进程内事件监听器
+///
+/// 在发布事件的线程上按序号顺序同步调用，实现应尽快返回，不能做磁盘或网络 IO；
+/// 不能在回调中再向同一总线发布事件
+pub trait EventListener: Send + Sync {
+    /// 监听器名称，用于日志
+    fn name(&self) -> &str;
+
+    /// 处理一条事件
+    fn on_event(&self, event: &SequencedEvent);
+}
+
+/// 从某个序号开始的订阅：先处理 `replay` 中的历史事件，再从 `live` 接收后续事件，两者之间没有缺口和重复
+#[derive(Debug)]
+pub struct EventSubscription {
+    /// 该订阅收到的第一条事件的序号
+    pub next_sequence: u64,
+    pub replay: Vec<SequencedEvent>,
+    pub live: broadcast::Receiver<SequencedEvent>,
+}
+
+/// 引擎事件总线
+pub struct EventBus {
+    /// 最近分配的序号和回放缓冲区，发布期间持有锁，保证所有订阅方看到相同的顺序
+    state: Mutex<BusState>,
+    /// 回放缓冲区保留的最近事件数，为 0 时不保留
+    replay_capacity: usize,
+    listeners: RwLock<Vec<Arc<dyn EventListener>>>,
+    events: broadcast::Sender<SequencedEvent>,
+    trades: broadcast::Sender<Trade>,
+    otc_trades: broadcast::Sender<Trade>,
+    trade_busts: broadcast::Sender<TradeBust>,
+    orders: broadcast::Sender<Order>,
+    agg_trades: broadcast::Sender<AggTrade>,
+    market_data: broadcast::Sender<MarketData>,
+    book_tickers: broadcast::Sender<BookTicker>,
+    symbol_status: broadcast::Sender<SymbolStatus>,
+    auctions: broadcast::Sender<AuctionIndicative>,
+    mark_prices: broadcast::Sender<MarkPrice>,
+    liquidations: broadcast::Sender<LiquidationEvent>,
+    listings: broadcast::Sender<SymbolListing>,
+}
+
+#[derive(Debug)]
+struct BusState {
+    sequence: u64,
+    /// 回放缓冲区中最早事件的前一个序号，不大于它的事件已无法回放
+    replay_floor: u64,
+    /// 序号为 `replay_floor + 1` 到 `sequence` 的事件
+    replay: VecDeque<SequencedEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::starting_after(0)
+    }
+
+    /// 创建总线，第一条事件的序号为 `sequence + 1`
+    pub fn starting_after(sequence: u64) -> Self {
+        let (events, _) = broadcast::channel(10000);
+        let (trades, _) = broadcast::channel(10000);
+        let (otc_trades, _) = broadcast::channel(1000);
+        let (trade_busts, _) = broadcast::channel(1000);
+        let (orders, _) = broadcast::channel(10000);
+        let (agg_trades, _) = broadcast::channel(10000);
+        let (market_data, _) = broadcast::channel(1000);
+        let (book_tickers, _) = broadcast::channel(10000);
+        let (symbol_status, _) = broadcast::channel(1000);
+        let (auctions, _) = broadcast::channel(1000);
+        let (mark_prices, _) = broadcast::channel(1000);
+        let (liquidations, _) = broadcast::channel(1000);
+        let (listings, _) = broadcast::channel(1000);
+        Self {
+            state: Mutex::new(BusState {
+                sequence,
+                replay_floor: sequence,
+                replay: VecDeque::new(),
+            }),
+            replay_capacity: 0,
+            listeners: RwLock::new(Vec::new()),
+            events,
+            trades,
+            otc_trades,
+            trade_busts,
+            orders,
+            agg_trades,
+            market_data,
+            book_tickers,
+            symbol_status,
+            auctions,
+            mark_prices,
+            liquidations,
+            listings,
+        }
+    }
+
+    /// 在内存中保留最近 `capacity` 条事件，供 [`EventBus::subscribe_from`] 回放
+    pub fn with_replay_capacity(mut self, capacity: usize) -> Self {
+        self.replay_capacity = capacity;
+        self
+    }
+
+    /// 注册进程内监听器，只收到注册之后发布的事件
+    pub fn register(&self, listener: Arc<dyn EventListener>) {
+        info!("Registered event listener {}", listener.name());
+        self.listeners.write().push(listener);
+    }
+
+    /// 发布事件，返回分配的序号
+    pub fn publish(&self, event: EngineEvent, timestamp: DateTime<Utc>) -> u64 {
+        let mut state = self.state.lock();
+        state.sequence += 1;
+        let event = SequencedEvent {
+            sequence: state.sequence,
+            timestamp,
+            event,
+        };
+
+        for listener in self.listeners.read().iter() {
+            listener.on_event(&event);
+        }
+
+        // 没有订阅方时不复制事件
+        match &event.event {
+            EngineEvent::Trade(trade) => send(&self.trades, trade),
+            EngineEvent::OtcTrade(trade) => send(&self.otc_trades, trade),
+            EngineEvent::TradeBust(bust) => send(&self.trade_busts, bust),
+            EngineEvent::OrderUpdate(order) => send(&self.orders, order),
+            EngineEvent::OrderRejected { .. } => {}
+            EngineEvent::AggTrade(agg_trade) => send(&self.agg_trades, agg_trade),
+            EngineEvent::MarketData(market_data) => send(&self.market_data, market_data),
+            EngineEvent::BookTicker(ticker) => send(&self.book_tickers, ticker),
+            EngineEvent::SymbolStatus(status) => send(&self.symbol_status, status),
+            EngineEvent::Auction(auction) => send(&self.auctions, auction),
+            EngineEvent::MarkPrice(mark_price) => send(&self.mark_prices, mark_price),
+            EngineEvent::Liquidation(liquidation) => send(&self.liquidations, liquidation),
+            EngineEvent::SymbolListing(listing) => send(&self.listings, listing),
+        }
+
+        if self.replay_capacity == 0 {
+            state.replay_floor = state.sequence;
+        } else {
+            if state.replay.len() == self.replay_capacity {
+                if let Some(evicted) = state.replay.pop_front() {
+                    state.replay_floor = evicted.sequence;
+                }
+            }
+            state.replay.push_back(event.clone());
+        }
+        let sequence = event.sequence;
+        if self.events.receiver_count() > 0 {
+            let _ = self.events.send(event);
+        }
+        sequence
+    }
+
+    /// 最近发布的事件序号，尚未发布过事件时为起始序号
+    pub fn last_sequence(&self) -> u64 {
+        self.state.lock().sequence
+    }
+
+    /// 订阅全部事件，按序号顺序接收
+    pub fn subscribe(&self) -> broadcast::Receiver<SequencedEvent> {
+        self.events.subscribe()
+    }
+
+    /// 从 `from_sequence`（含）开始订阅全部事件，为空时只接收之后的新事件
+    ///
+    /// 请求的序号已移出回放缓冲区或超过下一个序号时返回错误
+    pub fn subscribe_from(&self, from_sequence: Option<u64>) -> Result<EventSubscription, String> {
+        // 持有发布锁订阅，回放缓冲区和实时通道恰好在当前序号处衔接
+        let state = self.state.lock();
+        let live = self.events.subscribe();
+        let next_sequence = state.sequence + 1;
+        let Some(from_sequence) = from_sequence else {
+            return Ok(EventSubscription {
+                next_sequence,
+                replay: Vec::new(),
+                live,
+            });
+        };
+
+        if from_sequence > next_sequence {
+            return Err(format!(
+                "Sequence {} is ahead of the next sequence {}",
+                from_sequence, next_sequence
+            ));
+        }
+        if from_sequence <= state.replay_floor {
+            return Err(format!(
+                "Sequence {} is no longer available, oldest retained sequence is {}",
+                from_sequence,
+                state.replay_floor + 1
+            ));
+        }
+
+        let skip = (from_sequence - state.replay_floor - 1) as usize;
+        Ok(EventSubscription {
+            next_sequence: from_sequence,
+            replay: state.replay.iter().skip(skip).cloned().collect(),
+            live,
+        })
+    }
+
+    pub fn subscribe_trades(&self) -> broadcast::Receiver<Trade> {
+        self.trades.subscribe()
+    }
+
+    pub fn subscribe_otc_trades(&self) -> broadcast::Receiver<Trade> {
+        self.otc_trades.subscribe()
+    }
+
+    pub fn subscribe_trade_busts(&self) -> broadcast::Receiver<TradeBust> {
+        self.trade_busts.subscribe()
+    }
+
+    pub fn subscribe_orders(&self) -> broadcast::Receiver<Order> {
+        self.orders.subscribe()
+    }
+
+    pub fn subscribe_agg_trades(&self) -> broadcast::Receiver<AggTrade> {
+        self.agg_trades.subscribe()
+    }
+
+    pub fn subscribe_market_data(&self) -> broadcast::Receiver<MarketData> {
+        self.market_data.subscribe()
+    }
+
+    pub fn subscribe_book_tickers(&self) -> broadcast::Receiver<BookTicker> {
+        self.book_tickers.subscribe()
+    }
+
+    pub fn subscribe_symbol_status(&self) -> broadcast::Receiver<SymbolStatus> {
+        self.symbol_status.subscribe()
+    }
+
+    pub fn subscribe_auctions(&self) -> broadcast::Receiver<AuctionIndicative> {
+        self.auctions.subscribe()
+    }
+
+    pub fn subscribe_mark_prices(&self) -> broadcast::Receiver<MarkPrice> {
+        self.mark_prices.subscribe()
+    }
+
+    pub fn subscribe_liquidations(&self) -> broadcast::Receiver<LiquidationEvent> {
+        self.liquidations.subscribe()
+    }
+
+    pub fn subscribe_listings(&self) -> broadcast::Receiver<SymbolListing> {
+        self.listings.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let listeners = self.listeners.read();
+        f.debug_struct("EventBus")
+            .field("sequence", &self.state.lock().sequence)
+            .field("replay_capacity", &self.replay_capacity)
+            .field(
+                "listeners",
+                &listeners
+                    .iter()
+                    .map(|listener| listener.name())
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+fn send<T: Clone>(sender: &broadcast::Sender<T>, value: &T) {
+    if sender.receiver_count() > 0 {
+        let _ = sender.send(value.clone());
+    }
+}
+
+/// 按事件类型统计事件数
+#[derive(Debug, Default)]
+pub struct EventMetrics;
+
+impl EventListener for EventMetrics {
+    fn name(&self) -> &str {
+        "metrics"
+    }
+
+    fn on_event(&self, event: &SequencedEvent) {
+        counter!("matching_engine_bus_events_total", "kind" => event.event.kind()).increment(1);
+    }
+}
+
+/// 事件日志：总线的持久化订阅方
+///
+/// 事件以 JSON Lines 只追加写入 `events.jsonl`。总线发布时只把事件放入队列，由后台写入线程
+/// 批量写入并刷盘，磁盘 IO 不占用发布锁。每条事件在文件中的起始偏移同时追加到 `events.idx`，
+/// 重新打开时读取索引并只解析索引之后的部分，按序号回放时直接定位到起始偏移
+#[derive(Debug)]
+pub struct EventJournal {
+    path: PathBuf,
+    last_sequence: u64,
+    index: Arc<RwLock<JournalIndex>>,
+    /// 写入线程的事件队列，关闭时置空让写入线程退出
+    queue: Option<mpsc::Sender<SequencedEvent>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+/// 已刷盘事件的偏移索引
+#[derive(Debug, Default)]
+struct JournalIndex {
+    /// 按序号递增的（序号，起始偏移）
+    entries: Vec<(u64, u64)>,
+    /// 已刷盘的文件长度
+    len: u64,
+}
+
+/// 只读取事件序号，重建索引时不解析事件内容
+#[derive(Deserialize)]
+struct JournalSequence {
+    sequence: u64,
+}
+
+impl EventJournal {
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, String> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir).map_err(|e| {
+            format!(
+                "Failed to create event journal dir {}: {}",
+                dir.display(),
+                e
+            )
+        })?;
+
+        let path = dir.join(JOURNAL_FILE);
+        let index_path = dir.join(INDEX_FILE);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open event journal {}: {}", path.display(), e))?;
+        let len = file
+            .metadata()
+            .map_err(|e| format!("Failed to read event journal {}: {}", path.display(), e))?
+            .len();
+
+        let (entries, rebuilt) = load_index(&path, &index_path, len)?;
+        if rebuilt {
+            write_index(&index_path, &entries)?;
+        }
+        let index_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&index_path)
+            .map_err(|e| {
+                format!(
+                    "Failed to open event journal index {}: {}",
+                    index_path.display(),
+                    e
+                )
+            })?;
+
+        let last_sequence = entries.last().map_or(0, |&(sequence, _)| sequence);
+        info!(
+            "Opened event journal at {}, last sequence {}",
+            path.display(),
+            last_sequence
+        );
+
+        let index = Arc::new(RwLock::new(JournalIndex { entries, len }));
+        let (queue, events) = mpsc::channel();
+        let writer = JournalWriter {
+            file: BufWriter::new(file),
+            index_file: BufWriter::new(index_file),
+            offset: len,
+            index: Arc::clone(&index),
+        };
+        let writer = std::thread::Builder::new()
+            .name("event-journal".to_string())
+            .spawn(move || writer.run(events))
+            .map_err(|e| format!("Failed to start event journal writer: {}", e))?;
+
+        Ok(Self {
+            path,
+            last_sequence,
+            index,
+            queue: Some(queue),
+            writer: Some(writer),
+        })
+    }
+
+    /// 打开时日志中的最大序号
+    pub fn last_sequence(&self) -> u64 {
+        self.last_sequence
+    }
+
+    /// 已写入并刷盘的最大序号
+    pub fn written_sequence(&self) -> u64 {
+        self.index
+            .read()
+            .entries
+            .last()
+            .map_or(self.last_sequence, |&(sequence, _)| sequence)
+    }
+
+    /// 把一条事件放入写入队列
+    pub fn append(&self, event: &SequencedEvent) -> Result<(), String> {
+        self.queue
+            .as_ref()
+            .and_then(|queue| queue.send(event.clone()).ok())
+            .ok_or_else(|| {
+                format!(
+                    "Event journal writer stopped, dropped event {}",
+                    event.sequence
+                )
+            })
+    }
+
+    /// 读取已刷盘的、序号不小于 `from_sequence` 的事件，按序号从小到大
+    pub fn read_from(&self, from_sequence: u64) -> Result<Vec<SequencedEvent>, String> {
+        let (start, end) = {
+            let index = self.index.read();
+            let position = index
+                .entries
+                .partition_point(|&(sequence, _)| sequence < from_sequence);
+            match index.entries.get(position) {
+                Some(&(_, offset)) => (offset, index.len),
+                None => return Ok(Vec::new()),
+            }
+        };
+
+        let mut file = File::open(&self.path).map_err(|e| {
+            format!(
+                "Failed to open event journal {}: {}",
+                self.path.display(),
+                e
+            )
+        })?;
+        file.seek(SeekFrom::Start(start))
+            .map_err(|e| format!("Failed to read event journal: {}", e))?;
+        let mut events = Vec::new();
+        for line in BufReader::new(file.take(end - start)).lines() {
+            let line = line.map_err(|e| format!("Failed to read event journal: {}", e))?;
+            if line.is_empty() {
+                continue;
+            }
+            let event = interning(|| serde_json::from_str(&line))
+                .map_err(|e| format!("Corrupt event journal {}: {}", self.path.display(), e))?;
+            events.push(event);
+        }
+        Ok(events)
+    }
+}
+
+impl EventListener for EventJournal {
+    fn name(&self) -> &str {
+        "journal"
+    }
+
+    fn on_event(&self, event: &SequencedEvent) {
+        if let Err(e) = self.append(event) {
+            warn!("{}", e);
+        }
+    }
+}
+
+impl Drop for EventJournal {
+    /// 关闭队列并等待写入线程写完已排队的事件
+    fn drop(&mut self) {
+        self.queue.take();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// 后台写入线程：批量写入排队的事件，日志刷盘后再追加索引
+struct JournalWriter {
+    file: BufWriter<File>,
+    index_file: BufWriter<File>,
+    offset: u64,
+    index: Arc<RwLock<JournalIndex>>,
+}
+
+impl JournalWriter {
+    fn run(mut self, events: mpsc::Receiver<SequencedEvent>) {
+        while let Ok(event) = events.recv() {
+            let batch: Vec<SequencedEvent> =
+                std::iter::once(event).chain(events.try_iter()).collect();
+            if let Err(e) = self.write(&batch) {
+                // 写入失败后偏移不再可信，停止写入，后续事件在入队时报错
+                warn!("Event journal writer stopped: {}", e);
+                return;
+            }
+        }
+    }
+
+    fn write(&mut self, batch: &[SequencedEvent]) -> Result<(), String> {
+        let mut entries = Vec::with_capacity(batch.len());
+        let mut offset = self.offset;
+        for event in batch {
+            let mut line = match serde_json::to_vec(event) {
+                Ok(line) => line,
+                Err(e) => {
+                    warn!("Failed to serialize event {}: {}", event.sequence, e);
+                    continue;
+                }
+            };
+            line.push(b'\n');
+            self.file
+                .write_all(&line)
+                .map_err(|e| format!("Failed to write event {}: {}", event.sequence, e))?;
+            entries.push((event.sequence, offset));
+            offset += line.len() as u64;
+        }
+        self.file
+            .flush()
+            .map_err(|e| format!("Failed to flush event journal: {}", e))?;
+
+        for &(sequence, entry_offset) in &entries {
+            self.index_file
+                .write_all(&encode_index_entry(sequence, entry_offset))
+                .map_err(|e| format!("Failed to write event journal index: {}", e))?;
+        }
+        self.index_file
+            .flush()
+            .map_err(|e| format!("Failed to flush event journal index: {}", e))?;
+
+        self.offset = offset;
+        let mut index = self.index.write();
+        index.entries.extend(entries);
+        index.len = offset;
+        Ok(())
+    }
+}
+
+fn encode_index_entry(sequence: u64, offset: u64) -> [u8; INDEX_ENTRY_SIZE] {
+    let mut entry = [0; INDEX_ENTRY_SIZE];
+    entry[..8].copy_from_slice(&sequence.to_le_bytes());
+    entry[8..].copy_from_slice(&offset.to_le_bytes());
+    entry
+}
+
+/// 读取索引文件，丢弃指向日志末尾之外的记录，再解析最后一条记录之后的日志补齐索引
+///
+/// 返回的布尔值表示索引与文件内容不一致、需要重写索引文件
+fn load_index(path: &Path, index_path: &Path, len: u64) -> Result<(Vec<(u64, u64)>, bool), String> {
+    let bytes = match fs::read(index_path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => {
+            return Err(format!(
+                "Failed to read event journal index {}: {}",
+                index_path.display(),
+                e
+            ))
+        }
+    };
+    let mut entries: Vec<(u64, u64)> = bytes
+        .chunks_exact(INDEX_ENTRY_SIZE)
+        .map(|entry| {
+            let sequence = u64::from_le_bytes(entry[..8].try_into().unwrap());
+            let offset = u64::from_le_bytes(entry[8..].try_into().unwrap());
+            (sequence, offset)
+        })
+        .take_while(|&(_, offset)| offset < len)
+        .collect();
+    let stored = bytes.len() / INDEX_ENTRY_SIZE;
+
+    // 最后一条记录的行尾未知，从它开始重新解析
+    let last = entries.pop();
+    let start = last.map_or(0, |(_, offset)| offset);
+    let tail = scan_journal(path, start)?;
+    let complete = entries.len() + tail.len();
+    let unchanged = tail.first().copied() == last && complete == stored;
+    entries.extend(tail);
+    Ok((entries, !unchanged))
+}
+
+/// 从 `start` 开始逐行读取事件序号和起始偏移
+fn scan_journal(path: &Path, start: u64) -> Result<Vec<(u64, u64)>, String> {
+    let mut file = File::open(path)
+        .map_err(|e| format!("Failed to open event journal {}: {}", path.display(), e))?;
+    file.seek(SeekFrom::Start(start))
+        .map_err(|e| format!("Failed to read event journal: {}", e))?;
+    let mut reader = BufReader::new(file);
+    let mut entries = Vec::new();
+    let mut offset = start;
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = reader
+            .read_until(b'\n', &mut line)
+            .map_err(|e| format!("Failed to read event journal: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        if line.trim_ascii().is_empty() {
+            offset += read as u64;
+            continue;
+        }
+        let event: JournalSequence = serde_json::from_slice(&line).map_err(|e| {
+            format!(
+                "Corrupt event journal {} at offset {}: {}",
+                path.display(),
+                offset,
+                e
+            )
+        })?;
+        entries.push((event.sequence, offset));
+        offset += read as u64;
+    }
+    Ok(entries)
+}
+
+/// 用内存中的索引覆盖索引文件
+fn write_index(index_path: &Path, entries: &[(u64, u64)]) -> Result<(), String> {
+    let bytes: Vec<u8> = entries
+        .iter()
+        .flat_map(|&(sequence, offset)| encode_index_entry(sequence, offset))
+        .collect();
+    fs::write(index_path, bytes).map_err(|e| {
+        format!(
+            "Failed to write event journal index {}: {}",
+            index_path.display(),
+            e
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder {
+        sequences: Mutex<Vec<(u64, &'static str)>>,
+    }
+
+    impl EventListener for Recorder {
+        fn name(&self) -> &str {
+            "recorder"
+        }
+
+        fn on_event(&self, event: &SequencedEvent) {
+            self.sequences
+                .lock()
+                .push((event.sequence, event.event.kind()));
+        }
+    }
+
+    fn order() -> Order {
+        Order::new(
+            Symbol::new("BTC", "USDT"),
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            "user1".to_string(),
+        )
+    }
+
+    fn sequences(events: &[SequencedEvent]) -> Vec<u64> {
+        events.iter().map(|event| event.sequence).collect()
+    }
+
+    fn publish_orders(journal: EventJournal, count: usize) {
+        let journal = Arc::new(journal);
+        let bus = EventBus::starting_after(journal.last_sequence());
+        bus.register(journal);
+        for _ in 0..count {
+            bus.publish(EngineEvent::OrderUpdate(order()), Utc::now());
+        }
+        // 释放总线即关闭日志，等待写入线程写完
+    }
+
+    #[test]
+    fn test_listeners_and_subscribers_share_sequence() {
+        let bus = EventBus::new();
+        let recorder = Arc::new(Recorder::default());
+        bus.register(recorder.clone());
+        let mut events = bus.subscribe();
+        let mut orders = bus.subscribe_orders();
+        let mut trades = bus.subscribe_trades();
+
+        let order = order();
+        let market_data = MarketData {
+            symbol: order.symbol,
+            last_price: 100.0,
+            volume_24h: 0.0,
+            price_change_24h: 0.0,
+            high_24h: 100.0,
+            low_24h: 100.0,
+            timestamp: Utc::now(),
+        };
+        assert_eq!(
+            bus.publish(EngineEvent::OrderUpdate(order.clone()), Utc::now()),
+            1
+        );
+        assert_eq!(
+            bus.publish(EngineEvent::MarketData(market_data), Utc::now()),
+            2
+        );
+
+        assert_eq!(
+            *recorder.sequences.lock(),
+            [(1, "order_update"), (2, "market_data")]
+        );
+        assert_eq!(events.try_recv().unwrap().sequence, 1);
+        assert_eq!(events.try_recv().unwrap().sequence, 2);
+        assert_eq!(orders.try_recv().unwrap().id, order.id);
+        assert!(orders.try_recv().is_err());
+        assert!(trades.try_recv().is_err());
+        assert_eq!(bus.last_sequence(), 2);
+    }
+
+    #[test]
+    fn test_subscribe_from_replays_then_streams_without_gaps() {
+        let bus = EventBus::new().with_replay_capacity(3);
+        for _ in 0..5 {
+            bus.publish(EngineEvent::OrderUpdate(order()), Utc::now());
+        }
+
+        // 只保留最近 3 条
+        assert!(bus.subscribe_from(Some(2)).is_err());
+        assert!(bus.subscribe_from(Some(7)).is_err());
+
+        let mut subscription = bus.subscribe_from(Some(4)).unwrap();
+        assert_eq!(subscription.next_sequence, 4);
+        assert_eq!(sequences(&subscription.replay), [4, 5]);
+        bus.publish(EngineEvent::OrderUpdate(order()), Utc::now());
+        assert_eq!(subscription.live.try_recv().unwrap().sequence, 6);
+
+        // 从下一个序号订阅等同于只接收新事件
+        let subscription = bus.subscribe_from(Some(7)).unwrap();
+        assert!(subscription.replay.is_empty());
+
+        // 不保留事件时只能接收新事件
+        let bus = EventBus::starting_after(10);
+        bus.publish(EngineEvent::OrderUpdate(order()), Utc::now());
+        assert!(bus.subscribe_from(Some(11)).is_err());
+        assert_eq!(bus.subscribe_from(None).unwrap().next_sequence, 12);
+    }
+
+    #[test]
+    fn test_journal_persists_and_resumes_sequence() {
+        let dir = std::env::temp_dir().join(format!("event-journal-{}", uuid::Uuid::new_v4()));
+        publish_orders(EventJournal::open(&dir).unwrap(), 3);
+
+        let journal = EventJournal::open(&dir).unwrap();
+        assert_eq!(journal.last_sequence(), 3);
+        let replayed = journal.read_from(2).unwrap();
+        assert_eq!(sequences(&replayed), [2, 3]);
+        assert!(matches!(replayed[0].event, EngineEvent::OrderUpdate(_)));
+        assert!(journal.read_from(4).unwrap().is_empty());
+
+        let json = serde_json::to_value(&replayed[0]).unwrap();
+        assert_eq!(json["type"], "order_update");
+        assert_eq!(json["sequence"], 2);
+
+        // 重新打开后追加的事件同样进入索引
+        publish_orders(journal, 2);
+        let journal = EventJournal::open(&dir).unwrap();
+        assert_eq!(journal.last_sequence(), 5);
+        assert_eq!(sequences(&journal.read_from(3).unwrap()), [3, 4, 5]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_journal_rebuilds_missing_index_entries() {
+        let dir = std::env::temp_dir().join(format!("event-journal-{}", uuid::Uuid::new_v4()));
+        publish_orders(EventJournal::open(&dir).unwrap(), 4);
+
+        // 模拟索引落后于日志：只保留第一条索引记录
+        let index_path = dir.join(INDEX_FILE);
+        let index = std::fs::read(&index_path).unwrap();
+        std::fs::write(&index_path, &index[..INDEX_ENTRY_SIZE]).unwrap();
+
+        let journal = EventJournal::open(&dir).unwrap();
+        assert_eq!(journal.last_sequence(), 4);
+        assert_eq!(sequences(&journal.read_from(3).unwrap()), [3, 4]);
+        drop(journal);
+        assert_eq!(std::fs::read(&index_path).unwrap(), index);
+
+        // 没有索引文件时从头重建
+        std::fs::remove_file(&index_path).unwrap();
+        let journal = EventJournal::open(&dir).unwrap();
+        assert_eq!(sequences(&journal.read_from(1).unwrap()), [1, 2, 3, 4]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
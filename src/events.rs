@@ -0,0 +1,425 @@
+//! 引擎事件发布
+//!
+//! [`EventPublisher`] 订阅引擎事件总线上的成交、成交撤销和订单更新，跟踪订单簿变化，
+//! 为每条事件分配递增序号后交给已注册的 [`EventSink`]，由其投递到 Kafka、NATS 等外部系统，
+//! 供清算、分析等下游服务消费。
+use crate::config::EventsConfig;
+use crate::event_bus::{self, SequencedEvent};
+use crate::matching_engine::MatchingEngine;
+use crate::types::*;
+use chrono::{DateTime, Utc};
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError, error::TryRecvError};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// 事件格式版本，事件结构发生不兼容变化时递增
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// 事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Trade,
+    TradeBust,
+    OrderUpdate,
+    Depth,
+}
+
+impl EventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::Trade => "trade",
+            EventKind::TradeBust => "trade_bust",
+            EventKind::OrderUpdate => "order_update",
+            EventKind::Depth => "depth",
+        }
+    }
+}
+
+/// 事件内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum EventPayload {
+    Trade(Trade),
+    /// 成交被撤销，与成交发布到同一主题
+    TradeBust(TradeBust),
+    OrderUpdate(Order),
+    /// 订单簿变化后的深度快照
+    Depth(OrderBookDepth),
+}
+
+/// 发布到外部系统的引擎事件
+///
+/// `sequence` 在一个发布器内从 1 开始连续递增，下游可据此检测丢失和乱序；进程重启后重新计数。
+/// `event_id` 在重试时保持不变，可用于去重
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineEvent {
+    pub schema_version: u32,
+    pub event_id: Uuid,
+    pub sequence: u64,
+    pub symbol: Symbol,
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub payload: EventPayload,
+}
+
+impl EngineEvent {
+    pub fn kind(&self) -> EventKind {
+        match self.payload {
+            EventPayload::Trade(_) => EventKind::Trade,
+            EventPayload::TradeBust(_) => EventKind::TradeBust,
+            EventPayload::OrderUpdate(_) => EventKind::OrderUpdate,
+            EventPayload::Depth(_) => EventKind::Depth,
+        }
+    }
+
+    /// 序列化为 JSON 消息体
+    pub fn to_json(&self) -> Result<Vec<u8>, String> {
+        serde_json::to_vec(self).map_err(|e| format!("Failed to serialize event: {}", e))
+    }
+}
+
+/// [`EventSink`] 方法返回的 future
+pub type SinkFuture<'a> = Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>>;
+
+/// 事件投递目标
+///
+/// 同一目标的事件按序号顺序逐条投递，`publish` 返回 `Ok` 表示下游已确认收到。
+/// 返回 `Err` 时发布器按配置重试，重试耗尽后丢弃该事件
+pub trait EventSink: Send + Sync {
+    /// 目标名称，用于日志和指标
+    fn name(&self) -> &str;
+
+    /// 投递一条事件
+    fn publish<'a>(&'a self, event: &'a EngineEvent) -> SinkFuture<'a>;
+
+    /// 等待已投递的事件全部发出，发布器退出前调用
+    fn flush(&self) -> SinkFuture<'_> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// 事件发布器：订阅引擎广播并投递到所有已注册的目标
+///
+/// 深度事件在订单簿变化后合并发送：积压的成交和订单更新处理完后，每个变化过的交易对
+/// 发送一次最新快照，快照与上次发送的相同时跳过
+pub struct EventPublisher {
+    config: EventsConfig,
+    sinks: Vec<Arc<dyn EventSink>>,
+    sequence: u64,
+    last_depth: HashMap<Symbol, (Vec<PriceLevel>, Vec<PriceLevel>)>,
+}
+
+impl EventPublisher {
+    pub fn new(config: EventsConfig) -> Self {
+        Self {
+            config,
+            sinks: Vec::new(),
+            sequence: 0,
+            last_depth: HashMap::new(),
+        }
+    }
+
+    /// 注册投递目标
+    pub fn with_sink(mut self, sink: Arc<dyn EventSink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+
+    /// 订阅引擎事件总线并在后台发布事件
+    ///
+    /// `shutdown` 完成后投递已缓冲的事件、刷新所有目标，然后退出
+    pub fn start(
+        self,
+        engine: &Arc<MatchingEngine>,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> tokio::task::JoinHandle<()> {
+        let events = engine.subscribe_events();
+        let engine = Arc::clone(engine);
+        tokio::spawn(self.run(engine, events, shutdown))
+    }
+
+    async fn run(
+        mut self,
+        engine: Arc<MatchingEngine>,
+        mut events: broadcast::Receiver<SequencedEvent>,
+        shutdown: impl Future<Output = ()>,
+    ) {
+        let names: Vec<&str> = self.sinks.iter().map(|sink| sink.name()).collect();
+        info!("Event publisher started with sinks {:?}", names);
+        tokio::pin!(shutdown);
+        let mut dirty = HashSet::new();
+
+        loop {
+            tokio::select! {
+                biased;
+                _ = &mut shutdown => break,
+                result = events.recv() => match result {
+                    Ok(event) => self.handle(&engine, event.event, &mut dirty).await,
+                    Err(RecvError::Lagged(skipped)) => lagged("events", skipped),
+                    Err(RecvError::Closed) => break,
+                },
+            }
+
+            if events.is_empty() {
+                self.publish_depth(&engine, &mut dirty).await;
+            }
+        }
+
+        // 退出前投递已缓冲的事件
+        loop {
+            match events.try_recv() {
+                Ok(event) => self.handle(&engine, event.event, &mut dirty).await,
+                Err(TryRecvError::Lagged(skipped)) => lagged("events", skipped),
+                Err(_) => break,
+            }
+        }
+        self.publish_depth(&engine, &mut dirty).await;
+
+        for sink in &self.sinks {
+            if let Err(e) = sink.flush().await {
+                error!("Failed to flush event sink {}: {}", sink.name(), e);
+            }
+        }
+        info!("Event publisher stopped after {} events", self.sequence);
+    }
+
+    /// 发布总线事件，成交和订单更新同时标记交易对的深度需要更新；场外成交、行情等其它事件不对外发布
+    async fn handle(
+        &mut self,
+        engine: &MatchingEngine,
+        event: event_bus::EngineEvent,
+        dirty: &mut HashSet<Symbol>,
+    ) {
+        let (symbol, payload) = match event {
+            event_bus::EngineEvent::Trade(trade) => {
+                dirty.insert(trade.symbol);
+                (trade.symbol, EventPayload::Trade(trade))
+            }
+            event_bus::EngineEvent::TradeBust(bust) => {
+                (bust.trade.symbol, EventPayload::TradeBust(bust))
+            }
+            event_bus::EngineEvent::OrderUpdate(order) => {
+                dirty.insert(order.symbol);
+                (order.symbol, EventPayload::OrderUpdate(order))
+            }
+            _ => return,
+        };
+        self.publish(engine, symbol, payload).await;
+    }
+
+    /// 为变化过的交易对发送深度快照
+    async fn publish_depth(&mut self, engine: &MatchingEngine, dirty: &mut HashSet<Symbol>) {
+        for symbol in dirty.drain().collect::<Vec<_>>() {
+            let Some(depth) = engine.get_orderbook_depth(&symbol, Some(self.config.depth_levels))
+            else {
+                continue;
+            };
+            let levels = (depth.bids.clone(), depth.asks.clone());
+            if self.last_depth.get(&symbol) == Some(&levels) {
+                continue;
+            }
+            self.last_depth.insert(symbol, levels);
+            self.publish(engine, symbol, EventPayload::Depth(depth))
+                .await;
+        }
+    }
+
+    async fn publish(&mut self, engine: &MatchingEngine, symbol: Symbol, payload: EventPayload) {
+        self.sequence += 1;
+        let event = EngineEvent {
+            schema_version: EVENT_SCHEMA_VERSION,
+            event_id: Uuid::new_v4(),
+            sequence: self.sequence,
+            symbol,
+            timestamp: engine.now(),
+            payload,
+        };
+        for sink in &self.sinks {
+            self.deliver(sink.as_ref(), &event).await;
+        }
+    }
+
+    /// 投递到单个目标，失败时按指数退避重试
+    async fn deliver(&self, sink: &dyn EventSink, event: &EngineEvent) {
+        let mut backoff = Duration::from_millis(self.config.retry_backoff_ms);
+        let mut attempt = 0;
+        loop {
+            match sink.publish(event).await {
+                Ok(()) => return,
+                Err(e) if attempt < self.config.max_retries => {
+                    attempt += 1;
+                    warn!(
+                        "Failed to publish event {} to {} (attempt {}): {}",
+                        event.sequence,
+                        sink.name(),
+                        attempt,
+                        e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(e) => {
+                    error!(
+                        "Dropping event {} for {} after {} retries: {}",
+                        event.sequence,
+                        sink.name(),
+                        attempt,
+                        e
+                    );
+                    counter!("matching_engine_events_failed_total", "sink" => sink.name().to_string())
+                        .increment(1);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// 发布跟不上引擎广播时丢失的事件只能记录
+fn lagged(stream: &'static str, skipped: u64) {
+    warn!(
+        "Event publisher lagged, {} {} updates skipped",
+        skipped, stream
+    );
+    counter!("matching_engine_events_skipped_total", "stream" => stream).increment(skipped);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+    use tokio::sync::oneshot;
+
+    /// 记录收到的事件，前 `failures` 次投递失败
+    #[derive(Default)]
+    struct RecordingSink {
+        events: Mutex<Vec<EngineEvent>>,
+        failures: Mutex<u32>,
+        flushed: Mutex<bool>,
+    }
+
+    impl EventSink for RecordingSink {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        fn publish<'a>(&'a self, event: &'a EngineEvent) -> SinkFuture<'a> {
+            Box::pin(async move {
+                let mut failures = self.failures.lock();
+                if *failures > 0 {
+                    *failures -= 1;
+                    return Err("unavailable".to_string());
+                }
+                self.events.lock().push(event.clone());
+                Ok(())
+            })
+        }
+
+        fn flush(&self) -> SinkFuture<'_> {
+            Box::pin(async move {
+                *self.flushed.lock() = true;
+                Ok(())
+            })
+        }
+    }
+
+    #[test]
+    fn test_event_json_format() {
+        let symbol = Symbol::new("BTC", "USDT");
+        let order = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            "user1".to_string(),
+        );
+        let event = EngineEvent {
+            schema_version: EVENT_SCHEMA_VERSION,
+            event_id: Uuid::new_v4(),
+            sequence: 7,
+            symbol,
+            timestamp: Utc::now(),
+            payload: EventPayload::OrderUpdate(order.clone()),
+        };
+        let json: serde_json::Value = serde_json::from_slice(&event.to_json().unwrap()).unwrap();
+        assert_eq!(json["schema_version"], EVENT_SCHEMA_VERSION);
+        assert_eq!(json["sequence"], 7);
+        assert_eq!(json["type"], "order_update");
+        assert_eq!(json["data"]["id"], order.id);
+
+        let decoded: EngineEvent = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.kind(), EventKind::OrderUpdate);
+    }
+
+    #[tokio::test]
+    async fn test_publisher_sequences_and_retries() {
+        let engine = Arc::new(MatchingEngine::new());
+        let sink = Arc::new(RecordingSink::default());
+        *sink.failures.lock() = 1;
+        let config = EventsConfig {
+            retry_backoff_ms: 1,
+            ..Default::default()
+        };
+        let (stop, stopped) = oneshot::channel::<()>();
+        let handle =
+            EventPublisher::new(config)
+                .with_sink(sink.clone())
+                .start(&engine, async move {
+                    let _ = stopped.await;
+                });
+
+        let symbol = Symbol::new("BTC", "USDT");
+        let maker = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            "maker".to_string(),
+        );
+        engine.submit_order(maker).await.unwrap();
+        let taker = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            "taker".to_string(),
+        );
+        engine.submit_order(taker).await.unwrap();
+
+        // 等待发布器追上引擎后再关闭
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        stop.send(()).unwrap();
+        handle.await.unwrap();
+
+        let events = sink.events.lock();
+        let sequences: Vec<u64> = events.iter().map(|event| event.sequence).collect();
+        assert_eq!(sequences, (1..=events.len() as u64).collect::<Vec<_>>());
+        assert_eq!(
+            events
+                .iter()
+                .filter(|event| event.kind() == EventKind::Trade)
+                .count(),
+            1
+        );
+        assert!(events
+            .iter()
+            .any(|event| event.kind() == EventKind::OrderUpdate));
+        assert!(events.iter().any(|event| event.kind() == EventKind::Depth));
+        assert!(*sink.flushed.lock());
+    }
+}
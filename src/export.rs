@@ -0,0 +1,693 @@
+//! 成交和订单导出
+//!
+//! 成交和订单按固定列导出为 CSV，以 `parquet` 特性编译时也可以导出为 Parquet，供离线分析使用。
+//! 全量成交包含买卖双方的用户ID，只通过管理接口导出；用户只能导出自己的成交明细（[`Fill`]，
+//! 不含对手方）和自己的订单。
+//! 启用 `[export]` 后由 `ExportArchiver` 在后台订阅成交和进入终态的订单，按时间或行数滚动写入
+//! `<dir>/<trades|orders>/date=<UTC 日期>/` 下的 Parquet 文件；配置了 S3 时（需 `s3` 特性）
+//! 文件同时上传到 S3 兼容存储，对象键为 `<prefix>` 加上与本地相同的相对路径。
+use crate::settlement::csv_field;
+use crate::store::taker_side;
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[cfg(feature = "parquet")]
+pub use self::parquet_format::{fills_parquet, orders_parquet, trades_parquet, ExportArchiver};
+
+/// 单次导出的最大行数，超过时需缩小时间范围
+pub const MAX_EXPORT_ROWS: usize = 100_000;
+
+const TRADE_COLUMNS: &str = "id,symbol,trade_type,price,quantity,quote_quantity,taker_side,\
+buy_order_id,sell_order_id,buyer_id,seller_id,timestamp";
+const FILL_COLUMNS: &str = "trade_id,order_id,symbol,side,role,price,quantity,quote_quantity,fee,\
+fee_asset,tag,timestamp";
+const ORDER_COLUMNS: &str = "id,external_id,symbol,user_id,side,order_type,time_in_force,status,\
+price,stop_price,quantity,filled_quantity,remaining_quantity,timestamp,expires_at";
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    /// 需以 `parquet` 特性编译
+    Parquet,
+}
+
+impl ExportFormat {
+    /// 当前构建是否支持该格式
+    pub fn is_supported(self) -> bool {
+        self == ExportFormat::Csv || cfg!(feature = "parquet")
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+/// 按格式导出成交
+pub fn export_trades(trades: &[Trade], format: ExportFormat) -> Result<Vec<u8>, String> {
+    match format {
+        ExportFormat::Csv => Ok(trades_csv(trades).into_bytes()),
+        #[cfg(feature = "parquet")]
+        ExportFormat::Parquet => trades_parquet(trades),
+        #[cfg(not(feature = "parquet"))]
+        ExportFormat::Parquet => Err(PARQUET_UNSUPPORTED.to_string()),
+    }
+}
+
+/// 按格式导出用户的成交明细
+pub fn export_fills(fills: &[Fill], format: ExportFormat) -> Result<Vec<u8>, String> {
+    match format {
+        ExportFormat::Csv => Ok(fills_csv(fills).into_bytes()),
+        #[cfg(feature = "parquet")]
+        ExportFormat::Parquet => fills_parquet(fills),
+        #[cfg(not(feature = "parquet"))]
+        ExportFormat::Parquet => Err(PARQUET_UNSUPPORTED.to_string()),
+    }
+}
+
+/// 按格式导出订单
+pub fn export_orders(orders: &[Order], format: ExportFormat) -> Result<Vec<u8>, String> {
+    match format {
+        ExportFormat::Csv => Ok(orders_csv(orders).into_bytes()),
+        #[cfg(feature = "parquet")]
+        ExportFormat::Parquet => orders_parquet(orders),
+        #[cfg(not(feature = "parquet"))]
+        ExportFormat::Parquet => Err(PARQUET_UNSUPPORTED.to_string()),
+    }
+}
+
+#[cfg(not(feature = "parquet"))]
+const PARQUET_UNSUPPORTED: &str = "Parquet export requires the parquet feature";
+
+/// 成交导出为 CSV，每笔成交一行，按传入顺序
+pub fn trades_csv(trades: &[Trade]) -> String {
+    let rows = trades.iter().map(|trade| {
+        [
+            trade.id.to_string(),
+            trade.symbol.to_string(),
+            name(&trade.trade_type),
+            trade.price.to_string(),
+            trade.quantity.to_string(),
+            (trade.price * trade.quantity).to_string(),
+            name(&taker_side(trade)),
+            trade.buy_order_id.to_string(),
+            trade.sell_order_id.to_string(),
+            csv_field(&trade.buyer_id),
+            csv_field(&trade.seller_id),
+            trade.timestamp.to_rfc3339(),
+        ]
+        .join(",")
+    });
+    to_csv(TRADE_COLUMNS, rows)
+}
+
+/// 用户成交明细导出为 CSV，每笔成交一行，按传入顺序
+pub fn fills_csv(fills: &[Fill]) -> String {
+    let rows = fills.iter().map(|fill| {
+        [
+            fill.trade_id.to_string(),
+            fill.order_id.to_string(),
+            fill.symbol.to_string(),
+            name(&fill.side),
+            name(&fill.role),
+            fill.price.to_string(),
+            fill.quantity.to_string(),
+            (fill.price * fill.quantity).to_string(),
+            fill.fee.to_string(),
+            csv_field(&fill.fee_asset),
+            fill.tag.as_deref().map(csv_field).unwrap_or_default(),
+            fill.timestamp.to_rfc3339(),
+        ]
+        .join(",")
+    });
+    to_csv(FILL_COLUMNS, rows)
+}
+
+/// 订单导出为 CSV，每个订单一行，按传入顺序
+pub fn orders_csv(orders: &[Order]) -> String {
+    let rows = orders.iter().map(|order| {
+        [
+            order.id.to_string(),
+            optional(order.external_id),
+            order.symbol.to_string(),
+            csv_field(&order.user_id),
+            name(&order.side),
+            name(&order.order_type),
+            name(&order.time_in_force),
+            name(&order.status),
+            optional(order.price),
+            optional(order.stop_price),
+            order.quantity.to_string(),
+            order.filled_quantity.to_string(),
+            order.remaining_quantity.to_string(),
+            order.timestamp.to_rfc3339(),
+            optional(order.expires_at.map(|expires_at| expires_at.to_rfc3339())),
+        ]
+        .join(",")
+    });
+    to_csv(ORDER_COLUMNS, rows)
+}
+
+fn to_csv(header: &str, rows: impl Iterator<Item = String>) -> String {
+    let mut csv = String::from(header);
+    csv.push('\n');
+    for row in rows {
+        csv.push_str(&row);
+        csv.push('\n');
+    }
+    csv
+}
+
+/// 枚举按序列化名称输出，与 JSON 接口一致
+fn name(value: &impl Serialize) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// 空值输出为空字段
+fn optional(value: Option<impl ToString>) -> String {
+    value.map_or_else(String::new, |value| value.to_string())
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_format {
+    use crate::config::ExportConfig;
+    use crate::matching_engine::MatchingEngine;
+    use crate::store::taker_side;
+    use crate::types::*;
+    use arrow_array::{
+        ArrayRef, Float64Array, RecordBatch, StringArray, TimestampMicrosecondArray, UInt64Array,
+    };
+    use arrow_schema::{Field, Schema};
+    use chrono::{DateTime, Utc};
+    use metrics::counter;
+    use parquet::arrow::ArrowWriter;
+    use parquet::basic::Compression;
+    use parquet::file::properties::WriterProperties;
+    use std::future::Future;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::broadcast::{self, error::RecvError};
+    use tokio::time::Instant;
+    use tracing::{error, info, warn};
+
+    /// 可能为空的列，其余列不可空，保证各文件的 schema 一致
+    const NULLABLE_COLUMNS: [&str; 5] = ["external_id", "price", "stop_price", "expires_at", "tag"];
+
+    /// 成交导出为 Parquet，列与 CSV 相同，时间为 UTC 微秒时间戳
+    pub fn trades_parquet(trades: &[Trade]) -> Result<Vec<u8>, String> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(UInt64Array::from_iter_values(trades.iter().map(|t| t.id))),
+            strings(trades.iter().map(|t| Some(t.symbol.to_string()))),
+            strings(trades.iter().map(|t| Some(super::name(&t.trade_type)))),
+            Arc::new(Float64Array::from_iter_values(
+                trades.iter().map(|t| t.price),
+            )),
+            Arc::new(Float64Array::from_iter_values(
+                trades.iter().map(|t| t.quantity),
+            )),
+            Arc::new(Float64Array::from_iter_values(
+                trades.iter().map(|t| t.price * t.quantity),
+            )),
+            strings(trades.iter().map(|t| Some(super::name(&taker_side(t))))),
+            Arc::new(UInt64Array::from_iter_values(
+                trades.iter().map(|t| t.buy_order_id),
+            )),
+            Arc::new(UInt64Array::from_iter_values(
+                trades.iter().map(|t| t.sell_order_id),
+            )),
+            strings(trades.iter().map(|t| Some(t.buyer_id.clone()))),
+            strings(trades.iter().map(|t| Some(t.seller_id.clone()))),
+            timestamps(trades.iter().map(|t| Some(t.timestamp))),
+        ];
+        encode(super::TRADE_COLUMNS, columns)
+    }
+
+    /// 用户成交明细导出为 Parquet，列与 CSV 相同，时间为 UTC 微秒时间戳
+    pub fn fills_parquet(fills: &[Fill]) -> Result<Vec<u8>, String> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(UInt64Array::from_iter_values(
+                fills.iter().map(|f| f.trade_id),
+            )),
+            Arc::new(UInt64Array::from_iter_values(
+                fills.iter().map(|f| f.order_id),
+            )),
+            strings(fills.iter().map(|f| Some(f.symbol.to_string()))),
+            strings(fills.iter().map(|f| Some(super::name(&f.side)))),
+            strings(fills.iter().map(|f| Some(super::name(&f.role)))),
+            Arc::new(Float64Array::from_iter_values(
+                fills.iter().map(|f| f.price),
+            )),
+            Arc::new(Float64Array::from_iter_values(
+                fills.iter().map(|f| f.quantity),
+            )),
+            Arc::new(Float64Array::from_iter_values(
+                fills.iter().map(|f| f.price * f.quantity),
+            )),
+            Arc::new(Float64Array::from_iter_values(fills.iter().map(|f| f.fee))),
+            strings(fills.iter().map(|f| Some(f.fee_asset.clone()))),
+            strings(fills.iter().map(|f| f.tag.clone())),
+            timestamps(fills.iter().map(|f| Some(f.timestamp))),
+        ];
+        encode(super::FILL_COLUMNS, columns)
+    }
+
+    /// 订单导出为 Parquet，列与 CSV 相同，时间为 UTC 微秒时间戳
+    pub fn orders_parquet(orders: &[Order]) -> Result<Vec<u8>, String> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(UInt64Array::from_iter_values(orders.iter().map(|o| o.id))),
+            strings(
+                orders
+                    .iter()
+                    .map(|o| o.external_id.map(|id| id.to_string())),
+            ),
+            strings(orders.iter().map(|o| Some(o.symbol.to_string()))),
+            strings(orders.iter().map(|o| Some(o.user_id.clone()))),
+            strings(orders.iter().map(|o| Some(super::name(&o.side)))),
+            strings(orders.iter().map(|o| Some(super::name(&o.order_type)))),
+            strings(orders.iter().map(|o| Some(super::name(&o.time_in_force)))),
+            strings(orders.iter().map(|o| Some(super::name(&o.status)))),
+            Arc::new(Float64Array::from_iter(orders.iter().map(|o| o.price))),
+            Arc::new(Float64Array::from_iter(orders.iter().map(|o| o.stop_price))),
+            Arc::new(Float64Array::from_iter_values(
+                orders.iter().map(|o| o.quantity),
+            )),
+            Arc::new(Float64Array::from_iter_values(
+                orders.iter().map(|o| o.filled_quantity),
+            )),
+            Arc::new(Float64Array::from_iter_values(
+                orders.iter().map(|o| o.remaining_quantity),
+            )),
+            timestamps(orders.iter().map(|o| Some(o.timestamp))),
+            timestamps(orders.iter().map(|o| o.expires_at)),
+        ];
+        encode(super::ORDER_COLUMNS, columns)
+    }
+
+    fn strings(values: impl Iterator<Item = Option<String>>) -> ArrayRef {
+        Arc::new(StringArray::from_iter(values))
+    }
+
+    fn timestamps(values: impl Iterator<Item = Option<DateTime<Utc>>>) -> ArrayRef {
+        let micros = values.map(|value| value.map(|timestamp| timestamp.timestamp_micros()));
+        Arc::new(TimestampMicrosecondArray::from_iter(micros).with_timezone("UTC"))
+    }
+
+    /// 按列名和列数据生成单个行组的 Parquet 文件
+    fn encode(header: &str, columns: Vec<ArrayRef>) -> Result<Vec<u8>, String> {
+        let fields: Vec<Field> = header
+            .split(',')
+            .zip(&columns)
+            .map(|(name, column)| {
+                let nullable = NULLABLE_COLUMNS.contains(&name);
+                Field::new(name, column.data_type().clone(), nullable)
+            })
+            .collect();
+        let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+            .map_err(|e| e.to_string())?;
+
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let mut buffer = Vec::new();
+        let mut writer = ArrowWriter::try_new(&mut buffer, batch.schema(), Some(properties))
+            .map_err(|e| e.to_string())?;
+        writer.write(&batch).map_err(|e| e.to_string())?;
+        writer.close().map_err(|e| e.to_string())?;
+        Ok(buffer)
+    }
+
+    /// 成交和终态订单的 Parquet 后台归档
+    pub struct ExportArchiver {
+        config: ExportConfig,
+        /// 本进程写出的文件序号，保证同一毫秒内的文件名不重复
+        sequence: u64,
+        #[cfg(feature = "s3")]
+        store: Option<Arc<dyn object_store::ObjectStore>>,
+    }
+
+    impl ExportArchiver {
+        pub fn new(config: ExportConfig) -> Result<Self, String> {
+            std::fs::create_dir_all(&config.dir)
+                .map_err(|e| format!("Failed to create export directory {}: {}", config.dir, e))?;
+            #[cfg(feature = "s3")]
+            let store = config.s3.as_ref().map(s3_store).transpose()?;
+            #[cfg(not(feature = "s3"))]
+            if config.s3.is_some() {
+                warn!("Export S3 upload is configured but the server was built without the s3 feature; files are kept locally");
+            }
+            Ok(Self {
+                config,
+                sequence: 0,
+                #[cfg(feature = "s3")]
+                store,
+            })
+        }
+
+        /// 订阅引擎的成交和订单更新，在后台写出归档文件直到 `shutdown` 完成，停止前写出缓冲的记录
+        pub fn start(
+            self,
+            engine: &Arc<MatchingEngine>,
+            shutdown: impl Future<Output = ()> + Send + 'static,
+        ) -> tokio::task::JoinHandle<()> {
+            let trades = engine.subscribe_trades();
+            let otc_trades = engine.subscribe_otc_trades();
+            let orders = engine.subscribe_orders();
+            tokio::spawn(self.run(trades, otc_trades, orders, shutdown))
+        }
+
+        async fn run(
+            mut self,
+            mut trades: broadcast::Receiver<Trade>,
+            mut otc_trades: broadcast::Receiver<Trade>,
+            mut orders: broadcast::Receiver<Order>,
+            shutdown: impl Future<Output = ()>,
+        ) {
+            info!("Parquet archiver started, writing to {}", self.config.dir);
+            tokio::pin!(shutdown);
+            let period = Duration::from_secs(self.config.flush_interval_seconds);
+            let mut flush = tokio::time::interval_at(Instant::now() + period, period);
+            let mut trade_rows = Vec::new();
+            let mut order_rows = Vec::new();
+
+            loop {
+                tokio::select! {
+                    biased;
+                    _ = &mut shutdown => break,
+                    result = trades.recv() => match result {
+                        Ok(trade) => trade_rows.push(trade),
+                        Err(RecvError::Lagged(skipped)) => lagged("trades", skipped),
+                        Err(RecvError::Closed) => break,
+                    },
+                    result = otc_trades.recv() => match result {
+                        Ok(trade) => trade_rows.push(trade),
+                        Err(RecvError::Lagged(skipped)) => lagged("otc_trades", skipped),
+                        Err(RecvError::Closed) => break,
+                    },
+                    result = orders.recv() => match result {
+                        Ok(order) if order.status.is_terminal() => order_rows.push(order),
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(skipped)) => lagged("orders", skipped),
+                        Err(RecvError::Closed) => break,
+                    },
+                    _ = flush.tick() => {
+                        self.flush_trades(&mut trade_rows).await;
+                        self.flush_orders(&mut order_rows).await;
+                    }
+                }
+                if trade_rows.len() >= self.config.max_rows_per_file {
+                    self.flush_trades(&mut trade_rows).await;
+                }
+                if order_rows.len() >= self.config.max_rows_per_file {
+                    self.flush_orders(&mut order_rows).await;
+                }
+            }
+
+            // 停止前收下已广播但尚未处理的记录
+            while let Ok(trade) = trades.try_recv() {
+                trade_rows.push(trade);
+            }
+            while let Ok(trade) = otc_trades.try_recv() {
+                trade_rows.push(trade);
+            }
+            while let Ok(order) = orders.try_recv() {
+                if order.status.is_terminal() {
+                    order_rows.push(order);
+                }
+            }
+            for rows in trade_rows.chunks(self.config.max_rows_per_file) {
+                self.write("trades", rows.len(), trades_parquet(rows)).await;
+            }
+            for rows in order_rows.chunks(self.config.max_rows_per_file) {
+                self.write("orders", rows.len(), orders_parquet(rows)).await;
+            }
+            info!("Parquet archiver stopped");
+        }
+
+        async fn flush_trades(&mut self, rows: &mut Vec<Trade>) {
+            if !rows.is_empty() {
+                let trades = std::mem::take(rows);
+                self.write("trades", trades.len(), trades_parquet(&trades))
+                    .await;
+            }
+        }
+
+        async fn flush_orders(&mut self, rows: &mut Vec<Order>) {
+            if !rows.is_empty() {
+                let orders = std::mem::take(rows);
+                self.write("orders", orders.len(), orders_parquet(&orders))
+                    .await;
+            }
+        }
+
+        /// 写出一个归档文件，失败时记录日志并丢弃该批记录
+        async fn write(&mut self, kind: &'static str, rows: usize, data: Result<Vec<u8>, String>) {
+            let data = match data {
+                Ok(data) => data,
+                Err(e) => {
+                    error!("Failed to encode {} {} as Parquet: {}", rows, kind, e);
+                    return;
+                }
+            };
+            self.sequence += 1;
+            let now = Utc::now();
+            let relative = format!(
+                "{kind}/date={}/{kind}-{}-{:06}.parquet",
+                now.format("%Y-%m-%d"),
+                now.format("%Y%m%dT%H%M%S%3fZ"),
+                self.sequence
+            );
+            let path = Path::new(&self.config.dir).join(&relative);
+            if let Err(e) = write_file(&path, &data).await {
+                error!("Failed to write {}: {}", path.display(), e);
+                return;
+            }
+            counter!("matching_engine_export_rows_total", "kind" => kind).increment(rows as u64);
+            info!("Archived {} {} to {}", rows, kind, path.display());
+
+            #[cfg(feature = "s3")]
+            self.upload(&relative, &path, data).await;
+        }
+
+        /// 上传到 S3 兼容存储，失败时保留本地文件
+        #[cfg(feature = "s3")]
+        async fn upload(&self, relative: &str, path: &Path, data: Vec<u8>) {
+            let (Some(store), Some(s3)) = (&self.store, &self.config.s3) else {
+                return;
+            };
+            let key = object_store::path::Path::from(format!("{}{}", s3.prefix, relative));
+            match store.put(&key, data.into()).await {
+                Ok(_) => {
+                    if !s3.keep_local {
+                        if let Err(e) = tokio::fs::remove_file(path).await {
+                            warn!("Failed to remove uploaded file {}: {}", path.display(), e);
+                        }
+                    }
+                }
+                Err(e) => warn!(
+                    "Failed to upload {} to S3, keeping the local file: {}",
+                    key, e
+                ),
+            }
+        }
+    }
+
+    /// 先写临时文件再改名，读取方不会看到写了一半的文件
+    async fn write_file(path: &Path, data: &[u8]) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let partial = path.with_extension("parquet.partial");
+        tokio::fs::write(&partial, data).await?;
+        tokio::fs::rename(&partial, path).await
+    }
+
+    #[cfg(feature = "s3")]
+    fn s3_store(
+        config: &crate::config::S3ExportConfig,
+    ) -> Result<Arc<dyn object_store::ObjectStore>, String> {
+        let mut builder = object_store::aws::AmazonS3Builder::from_env()
+            .with_bucket_name(&config.bucket)
+            .with_region(&config.region)
+            .with_allow_http(config.allow_http);
+        if let Some(endpoint) = &config.endpoint {
+            builder = builder.with_endpoint(endpoint);
+        }
+        if let Some(access_key_id) = &config.access_key_id {
+            builder = builder.with_access_key_id(access_key_id);
+        }
+        if let Some(secret_access_key) = &config.secret_access_key {
+            builder = builder.with_secret_access_key(secret_access_key);
+        }
+        let store = builder
+            .build()
+            .map_err(|e| format!("Invalid export S3 configuration: {}", e))?;
+        Ok(Arc::new(store))
+    }
+
+    fn lagged(stream: &'static str, skipped: u64) {
+        warn!(
+            "Parquet archiver lagged, {} {} updates skipped",
+            skipped, stream
+        );
+        counter!("matching_engine_export_skipped_total", "stream" => stream).increment(skipped);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(buyer: &str) -> Trade {
+        let btc = Symbol::new("BTC", "USDT");
+        let buy = Order::new(
+            btc,
+            OrderSide::Buy,
+            OrderType::Limit,
+            2.0,
+            Some(100.0),
+            buyer.to_string(),
+        );
+        let sell = Order::new(
+            btc,
+            OrderSide::Sell,
+            OrderType::Limit,
+            2.0,
+            Some(100.0),
+            "bob".to_string(),
+        );
+        Trade::new(btc, &buy, &sell, 1.5, 100.0)
+    }
+
+    #[test]
+    fn test_trades_csv() {
+        let csv = trades_csv(&[trade("alice"), trade("a,b")]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], TRADE_COLUMNS);
+        assert!(lines[1].contains(",BTCUSDT,regular,100,1.5,150,sell,"));
+        assert!(lines[2].contains(",\"a,b\",bob,"));
+    }
+
+    #[test]
+    fn test_fills_csv_has_no_counterparty() {
+        let fill = Fill {
+            trade_id: 7,
+            order_id: 3,
+            symbol: Symbol::new("BTC", "USDT"),
+            side: OrderSide::Buy,
+            price: 100.0,
+            quantity: 1.5,
+            role: LiquidityRole::Taker,
+            fee: 0.15,
+            fee_asset: "USDT".to_string(),
+            timestamp: chrono::Utc::now(),
+            tag: Some("a,b".to_string()),
+        };
+        let csv = fills_csv(&[fill]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], FILL_COLUMNS);
+        assert!(lines[1].starts_with("7,3,BTCUSDT,buy,taker,100,1.5,150,0.15,USDT,\"a,b\","));
+        assert!(!FILL_COLUMNS.contains("buyer_id") && !FILL_COLUMNS.contains("seller_id"));
+    }
+
+    #[cfg(feature = "parquet")]
+    #[tokio::test]
+    async fn test_archiver_writes_parquet_files() {
+        use crate::config::ExportConfig;
+        use crate::matching_engine::MatchingEngine;
+        use arrow_schema::{DataType, TimeUnit};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+        use std::sync::Arc;
+
+        let dir = std::env::temp_dir().join(format!("export-{}", uuid::Uuid::new_v4()));
+        let engine = Arc::new(MatchingEngine::new());
+        let archiver = ExportArchiver::new(ExportConfig {
+            enabled: true,
+            dir: dir.to_string_lossy().into_owned(),
+            max_rows_per_file: 2,
+            ..Default::default()
+        })
+        .unwrap();
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let handle = archiver.start(&engine, async {
+            let _ = stopped.await;
+        });
+
+        let btc = Symbol::new("BTC", "USDT");
+        let order = |side, user: &str| {
+            Order::new(
+                btc,
+                side,
+                OrderType::Limit,
+                1.0,
+                Some(100.0),
+                user.to_string(),
+            )
+        };
+        for _ in 0..3 {
+            engine
+                .submit_order(order(OrderSide::Sell, "bob"))
+                .await
+                .unwrap();
+            engine
+                .submit_order(order(OrderSide::Buy, "alice"))
+                .await
+                .unwrap();
+        }
+        stop.send(()).unwrap();
+        handle.await.unwrap();
+
+        // 3 笔成交按每个文件 2 行滚动为两个文件，6 个已成交订单为三个文件
+        let files = |kind: &str| -> Vec<std::path::PathBuf> {
+            let mut files: Vec<_> = std::fs::read_dir(dir.join(kind))
+                .unwrap()
+                .flat_map(|partition| std::fs::read_dir(partition.unwrap().path()).unwrap())
+                .map(|file| file.unwrap().path())
+                .collect();
+            files.sort();
+            files
+        };
+        let rows = |path: &std::path::Path| {
+            let reader =
+                ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(path).unwrap())
+                    .unwrap()
+                    .build()
+                    .unwrap();
+            let batches: Vec<_> = reader.map(Result::unwrap).collect();
+            assert_eq!(
+                batches[0]
+                    .schema()
+                    .field_with_name("timestamp")
+                    .unwrap()
+                    .data_type(),
+                &DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+            );
+            batches.iter().map(|batch| batch.num_rows()).sum::<usize>()
+        };
+        let trades = files("trades");
+        assert_eq!(
+            trades.iter().map(|path| rows(path)).collect::<Vec<_>>(),
+            [2, 1]
+        );
+        assert_eq!(files("orders").len(), 3);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,441 @@
+//! 手续费档位和手续费结算
+//!
+//! 按用户最近 `volume_window_days` 个 UTC 自然日（含当天）的成交额（计价货币，买卖双方各自计入）
+//! 选择 maker/taker 费率。成交按成交前的档位计费，成交额在计费后才累加。
+//!
+//! 启用结算后，按结算周期累计各资产的手续费收入和 maker 返佣，周期结束时以收入冲抵返佣，
+//! 净额记入手续费收入账户（返佣池）。
+use crate::account::Posting;
+use crate::clock::next_aligned_boundary;
+use crate::config::{FeeConfig, FeeSettlementConfig};
+use crate::types::*;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use utoipa::ToSchema;
+
+/// 用户当前的费率档位和滚动成交额
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserFeeTier {
+    pub user_id: String,
+    /// 档位序号，0 为基础费率，1 起对应配置中的第 N 档
+    pub tier: usize,
+    pub maker_rate: f64,
+    pub taker_rate: f64,
+    /// 滚动窗口内的成交额
+    pub volume: f64,
+    pub volume_window_days: u32,
+    /// 下一档的成交额门槛，已是最高档时为空
+    pub next_tier_volume: Option<f64>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 按用户滚动成交额计算的手续费率
+#[derive(Debug)]
+pub struct FeeSchedule {
+    config: FeeConfig,
+    /// 用户ID -> 按日期排列的 (UTC 日期, 成交额)
+    volumes: Mutex<HashMap<String, VecDeque<(NaiveDate, f64)>>>,
+}
+
+impl FeeSchedule {
+    pub fn new(config: FeeConfig) -> Self {
+        Self {
+            config,
+            volumes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 用户在 `now` 适用的 (maker, taker) 费率
+    pub fn rates(&self, user_id: &str, now: DateTime<Utc>) -> (f64, f64) {
+        if self.config.tiers.is_empty() {
+            return (self.config.maker_rate, self.config.taker_rate);
+        }
+        self.tier_rates(self.tier_for(self.volume(user_id, now)))
+    }
+
+    /// 累加用户在 `at` 当天的成交额，撤销成交时传入负数；窗口外的日期忽略
+    pub fn record(&self, user_id: &str, notional: f64, at: DateTime<Utc>) {
+        let date = at.date_naive();
+        let mut volumes = self.volumes.lock();
+        let days = volumes.entry(user_id.to_string()).or_default();
+        self.evict(days, at);
+        match days.binary_search_by_key(&date, |(day, _)| *day) {
+            Ok(index) => days[index].1 = (days[index].1 + notional).max(0.0),
+            Err(index) if notional > 0.0 && date >= self.window_start(at) => {
+                days.insert(index, (date, notional))
+            }
+            Err(_) => {}
+        }
+        if days.is_empty() {
+            volumes.remove(user_id);
+        }
+    }
+
+    /// 用户在窗口内的合计成交额
+    pub fn volume(&self, user_id: &str, now: DateTime<Utc>) -> f64 {
+        let mut volumes = self.volumes.lock();
+        let Some(days) = volumes.get_mut(user_id) else {
+            return 0.0;
+        };
+        self.evict(days, now);
+        let total = days.iter().map(|(_, volume)| volume).sum();
+        if days.is_empty() {
+            volumes.remove(user_id);
+        }
+        total
+    }
+
+    /// 用户当前档位、成交额和下一档门槛
+    pub fn user_tier(&self, user_id: &str, now: DateTime<Utc>) -> UserFeeTier {
+        let volume = self.volume(user_id, now);
+        let tier = self.tier_for(volume);
+        let (maker_rate, taker_rate) = self.tier_rates(tier);
+        UserFeeTier {
+            user_id: user_id.to_string(),
+            tier,
+            maker_rate,
+            taker_rate,
+            volume,
+            volume_window_days: self.config.volume_window_days,
+            next_tier_volume: self.config.tiers.get(tier).map(|next| next.min_volume),
+            timestamp: now,
+        }
+    }
+
+    /// 成交额达到的最高档位
+    fn tier_for(&self, volume: f64) -> usize {
+        self.config
+            .tiers
+            .iter()
+            .take_while(|tier| volume >= tier.min_volume)
+            .count()
+    }
+
+    fn tier_rates(&self, tier: usize) -> (f64, f64) {
+        match tier
+            .checked_sub(1)
+            .and_then(|index| self.config.tiers.get(index))
+        {
+            Some(tier) => (tier.maker_rate, tier.taker_rate),
+            None => (self.config.maker_rate, self.config.taker_rate),
+        }
+    }
+
+    /// 窗口内最早的日期
+    fn window_start(&self, now: DateTime<Utc>) -> NaiveDate {
+        now.date_naive() - Duration::days(self.config.volume_window_days as i64 - 1)
+    }
+
+    fn evict(&self, days: &mut VecDeque<(NaiveDate, f64)>, now: DateTime<Utc>) {
+        let window_start = self.window_start(now);
+        while days.front().is_some_and(|(day, _)| *day < window_start) {
+            days.pop_front();
+        }
+    }
+}
+
+/// 一种资产在结算周期内的手续费收入和返佣
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FeeRevenue {
+    pub asset: String,
+    /// 收取的 taker 手续费
+    pub taker_fees: f64,
+    /// 收取的 maker 手续费（maker 费率为正时）
+    pub maker_fees: f64,
+    /// 支付的 maker 返佣
+    pub rebates: f64,
+    /// 收入扣除返佣后的净额，为负时由返佣池支付
+    pub net_revenue: f64,
+}
+
+/// 一个结算周期的手续费结算，未结算的当期 `period_end` 为查询时间
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FeeSettlement {
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    /// 按资产排列的收入和返佣
+    pub assets: Vec<FeeRevenue>,
+    /// 本期获得返佣的用户数
+    pub rebated_users: usize,
+}
+
+/// 手续费收入报表：当期累计、返佣池余额和历史结算
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FeeRevenueReport {
+    pub fee_account: String,
+    pub current_period: FeeSettlement,
+    /// 返佣池（手续费收入账户）各资产余额
+    pub rebate_pool: Vec<Balance>,
+    /// 最近的结算，最新的在前
+    pub settlements: Vec<FeeSettlement>,
+}
+
+/// 用户的 maker 返佣
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserRebates {
+    pub user_id: String,
+    /// 当期应计、尚未结算的返佣，资产 -> 金额
+    pub accrued: HashMap<String, f64>,
+    /// 已结算的返佣累计，资产 -> 金额
+    pub settled: HashMap<String, f64>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 当前结算周期的累计
+struct FeePeriod {
+    start: DateTime<Utc>,
+    assets: BTreeMap<String, FeeRevenue>,
+    /// 用户ID -> 资产 -> 应计返佣
+    rebates: HashMap<String, HashMap<String, f64>>,
+}
+
+impl FeePeriod {
+    fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            start,
+            assets: BTreeMap::new(),
+            rebates: HashMap::new(),
+        }
+    }
+
+    fn summary(&self, period_end: DateTime<Utc>) -> FeeSettlement {
+        FeeSettlement {
+            period_start: self.start,
+            period_end,
+            assets: self.assets.values().cloned().collect(),
+            rebated_users: self.rebates.len(),
+        }
+    }
+}
+
+/// 手续费收入和返佣的周期结算
+pub struct FeeLedger {
+    config: FeeSettlementConfig,
+    period: Mutex<FeePeriod>,
+    /// 用户ID -> 资产 -> 已结算的返佣累计
+    settled_rebates: RwLock<HashMap<String, HashMap<String, f64>>>,
+    /// 最近的结算，按结算顺序
+    history: RwLock<VecDeque<FeeSettlement>>,
+}
+
+impl FeeLedger {
+    pub fn new(config: FeeSettlementConfig, now: DateTime<Utc>) -> Self {
+        Self {
+            config,
+            period: Mutex::new(FeePeriod::new(now)),
+            settled_rebates: RwLock::new(HashMap::new()),
+            history: RwLock::new(VecDeque::new()),
+        }
+    }
+
+    /// 手续费收入（返佣池）账户的用户ID
+    pub fn fee_account(&self) -> &str {
+        &self.config.fee_account
+    }
+
+    /// `now` 之后（不含）的下一个手续费结算时间，结算间隔为 0 时为空
+    pub fn next_settlement(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        next_aligned_boundary(now, self.config.interval_seconds)
+    }
+
+    /// 计入一笔用户成交的手续费或返佣
+    pub fn record_fill(&self, user_id: &str, fill: &Fill) {
+        self.apply(user_id, fill, 1.0);
+    }
+
+    /// 冲回被撤销成交的手续费或返佣，已结算的返佣在当期冲减
+    pub fn reverse_fill(&self, user_id: &str, fill: &Fill) {
+        self.apply(user_id, fill, -1.0);
+    }
+
+    fn apply(&self, user_id: &str, fill: &Fill, direction: f64) {
+        if fill.fee == 0.0 {
+            return;
+        }
+        let amount = fill.fee * direction;
+        let mut period = self.period.lock();
+        let revenue = period
+            .assets
+            .entry(fill.fee_asset.clone())
+            .or_insert_with(|| FeeRevenue {
+                asset: fill.fee_asset.clone(),
+                ..Default::default()
+            });
+        revenue.net_revenue += amount;
+        if fill.fee < 0.0 {
+            revenue.rebates -= amount;
+            *period
+                .rebates
+                .entry(user_id.to_string())
+                .or_default()
+                .entry(fill.fee_asset.clone())
+                .or_default() -= amount;
+        } else if fill.role == LiquidityRole::Taker {
+            revenue.taker_fees += amount;
+        } else {
+            revenue.maker_fees += amount;
+        }
+    }
+
+    /// 结束当期并开始新周期，返回本期结算和把净收入记入收入账户的记账
+    ///
+    /// 用户的应计返佣转入已结算累计；返佣超出收入的资产记账为负数，由返佣池余额支付
+    pub fn settle(&self, period_end: DateTime<Utc>) -> (FeeSettlement, Vec<Posting>) {
+        let period = std::mem::replace(&mut *self.period.lock(), FeePeriod::new(period_end));
+        let settlement = period.summary(period_end);
+
+        let mut settled_rebates = self.settled_rebates.write();
+        for (user_id, rebates) in period.rebates {
+            let settled = settled_rebates.entry(user_id).or_default();
+            for (asset, amount) in rebates {
+                *settled.entry(asset).or_default() += amount;
+            }
+        }
+        drop(settled_rebates);
+
+        let postings = settlement
+            .assets
+            .iter()
+            .map(|revenue| Posting {
+                user_id: self.config.fee_account.clone(),
+                asset: revenue.asset.clone(),
+                amount: revenue.net_revenue,
+                entry_type: LedgerEntryType::FeeRevenue,
+            })
+            .collect();
+
+        let mut history = self.history.write();
+        history.push_back(settlement.clone());
+        while history.len() > self.config.history_size {
+            history.pop_front();
+        }
+        (settlement, postings)
+    }
+
+    /// 当期截至 `now` 的累计
+    pub fn current_period(&self, now: DateTime<Utc>) -> FeeSettlement {
+        self.period.lock().summary(now)
+    }
+
+    /// 最近的结算，最新的在前
+    pub fn history(&self, limit: Option<usize>) -> Vec<FeeSettlement> {
+        let history = self.history.read();
+        history
+            .iter()
+            .rev()
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    /// 用户的当期应计返佣和已结算返佣
+    pub fn user_rebates(&self, user_id: &str, now: DateTime<Utc>) -> UserRebates {
+        UserRebates {
+            user_id: user_id.to_string(),
+            accrued: self
+                .period
+                .lock()
+                .rebates
+                .get(user_id)
+                .cloned()
+                .unwrap_or_default(),
+            settled: self
+                .settled_rebates
+                .read()
+                .get(user_id)
+                .cloned()
+                .unwrap_or_default(),
+            timestamp: now,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FeeTier;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_tier_follows_rolling_volume() {
+        let schedule = FeeSchedule::new(FeeConfig {
+            maker_rate: 0.001,
+            taker_rate: 0.002,
+            tiers: vec![
+                FeeTier {
+                    min_volume: 1_000.0,
+                    maker_rate: 0.0005,
+                    taker_rate: 0.001,
+                },
+                FeeTier {
+                    min_volume: 10_000.0,
+                    maker_rate: -0.0001,
+                    taker_rate: 0.0005,
+                },
+            ],
+            ..Default::default()
+        });
+        let day = |day| Utc.with_ymd_and_hms(2024, 1, day, 12, 0, 0).unwrap();
+
+        assert_eq!(schedule.rates("alice", day(1)), (0.001, 0.002));
+        schedule.record("alice", 600.0, day(1));
+        schedule.record("alice", 600.0, day(10));
+        let tier = schedule.user_tier("alice", day(10));
+        assert_eq!((tier.tier, tier.volume), (1, 1_200.0));
+        assert_eq!(tier.next_tier_volume, Some(10_000.0));
+        assert_eq!(schedule.rates("alice", day(10)), (0.0005, 0.001));
+
+        // 1 月 1 日的成交额在 1 月 31 日移出窗口
+        assert_eq!(schedule.volume("alice", day(30)), 1_200.0);
+        assert_eq!(schedule.user_tier("alice", day(31)).tier, 0);
+
+        // 撤销成交扣回当天的成交额
+        schedule.record("bob", 12_000.0, day(5));
+        assert_eq!(schedule.user_tier("bob", day(5)).next_tier_volume, None);
+        schedule.record("bob", -3_000.0, day(5));
+        assert_eq!(schedule.rates("bob", day(6)), (0.0005, 0.001));
+    }
+
+    #[test]
+    fn test_settlement_nets_rebates_against_fees() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let ledger = FeeLedger::new(FeeSettlementConfig::default(), start);
+        let fill = |role, fee: f64| Fill {
+            trade_id: 1,
+            order_id: 1,
+            symbol: Symbol::new("BTC", "USDT"),
+            side: OrderSide::Buy,
+            price: 100.0,
+            quantity: 1.0,
+            role,
+            fee,
+            fee_asset: "USDT".to_string(),
+            timestamp: start,
+            tag: None,
+        };
+        ledger.record_fill("taker", &fill(LiquidityRole::Taker, 0.1));
+        ledger.record_fill("maker", &fill(LiquidityRole::Maker, -0.02));
+        ledger.record_fill("maker", &fill(LiquidityRole::Maker, -0.02));
+        ledger.reverse_fill("maker", &fill(LiquidityRole::Maker, -0.02));
+        assert_eq!(ledger.user_rebates("maker", start).accrued["USDT"], 0.02);
+
+        let end = ledger.next_settlement(start).unwrap();
+        assert_eq!(end, start + Duration::days(1));
+        let (settlement, postings) = ledger.settle(end);
+        let revenue = &settlement.assets[0];
+        assert_eq!((revenue.taker_fees, revenue.rebates), (0.1, 0.02));
+        assert!((revenue.net_revenue - 0.08).abs() < 1e-12);
+        assert_eq!(settlement.rebated_users, 1);
+        assert_eq!(postings[0].user_id, "fee-revenue");
+        assert_eq!(postings[0].entry_type, LedgerEntryType::FeeRevenue);
+
+        // 应计返佣转入已结算累计，新周期从结算时间开始
+        let rebates = ledger.user_rebates("maker", end);
+        assert!(rebates.accrued.is_empty());
+        assert_eq!(rebates.settled["USDT"], 0.02);
+        assert_eq!(ledger.current_period(end).period_start, end);
+        assert_eq!(ledger.history(None).len(), 1);
+    }
+}
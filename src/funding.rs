@@ -0,0 +1,203 @@
+//! 永续合约资金费率
+//!
+//! 每次更新标记价格时累计永续交易对的溢价，结算时以周期内平均溢价加上利率得到资金费率，
+//! 按持仓名义价值在多空之间交换资金费。多空净持仓总和为零，资金费在用户之间收付相抵。
+use crate::account::Posting;
+use crate::clock::next_aligned_boundary;
+use crate::config::FundingConfig;
+use crate::position::PositionEntry;
+use crate::types::*;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use std::collections::{HashMap, VecDeque};
+
+/// 结算周期内累计的溢价
+#[derive(Default)]
+struct PremiumAccumulator {
+    sum: f64,
+    count: usize,
+}
+
+/// 资金费率计算、结算记账和历史记录
+pub struct FundingEngine {
+    config: FundingConfig,
+    /// 当前结算周期内按交易对累计的溢价
+    premiums: RwLock<HashMap<Symbol, PremiumAccumulator>>,
+    /// 按交易对的资金费率记录，按结算时间顺序
+    history: RwLock<HashMap<Symbol, VecDeque<FundingRate>>>,
+}
+
+impl FundingEngine {
+    pub fn new(config: FundingConfig) -> Self {
+        Self {
+            config,
+            premiums: RwLock::new(HashMap::new()),
+            history: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// `now` 之后（不含）的下一个结算时间，结算间隔为 0 时为空
+    pub fn next_settlement(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        next_aligned_boundary(now, self.config.interval_seconds)
+    }
+
+    /// 记录一次标记价格更新的溢价，非永续交易对和没有指数价格时忽略
+    pub fn record_premium(&self, mark_price: &MarkPrice) {
+        if !self.config.is_perpetual(&mark_price.symbol) || mark_price.index_price <= 0.0 {
+            return;
+        }
+        let premium = (mark_price.mark_price - mark_price.index_price) / mark_price.index_price;
+        let mut premiums = self.premiums.write();
+        let accumulator = premiums.entry(mark_price.symbol).or_default();
+        accumulator.sum += premium;
+        accumulator.count += 1;
+    }
+
+    /// 有溢价样本的交易对，按交易对排序
+    pub fn sampled_symbols(&self) -> Vec<Symbol> {
+        let mut symbols: Vec<Symbol> = self.premiums.read().keys().copied().collect();
+        symbols.sort_by_key(|symbol| symbol.to_string());
+        symbols
+    }
+
+    /// 取出交易对本周期的平均溢价并开始新周期，返回 (资金费率, 平均溢价)
+    ///
+    /// 资金费率为平均溢价加上利率，限制在 ±`max_rate_pct` 以内；没有溢价样本时为空
+    pub fn take_rate(&self, symbol: &Symbol) -> Option<(f64, f64)> {
+        let accumulator = self.premiums.write().remove(symbol)?;
+        if accumulator.count == 0 {
+            return None;
+        }
+        let premium_index = accumulator.sum / accumulator.count as f64;
+        let max_rate = self.config.max_rate_pct / 100.0;
+        let funding_rate =
+            (premium_index + self.config.interest_rate_pct / 100.0).clamp(-max_rate, max_rate);
+        Some((funding_rate, premium_index))
+    }
+
+    /// 按资金费率计算各持仓的资金费记账，费率为正时多头支付、空头收取
+    pub fn payments(
+        &self,
+        funding_rate: f64,
+        mark_price: f64,
+        positions: &[PositionEntry],
+        asset: &str,
+    ) -> Vec<Posting> {
+        positions
+            .iter()
+            .map(|position| Posting {
+                user_id: position.user_id.clone(),
+                asset: asset.to_string(),
+                amount: -position.quantity * mark_price * funding_rate,
+                entry_type: LedgerEntryType::Funding,
+            })
+            .collect()
+    }
+
+    /// 记录一次结算，超出保留条数时丢弃该交易对最早的记录
+    pub fn record(&self, funding: FundingRate) {
+        let mut history = self.history.write();
+        let records = history.entry(funding.symbol).or_default();
+        records.push_back(funding);
+        while records.len() > self.config.history_size {
+            records.pop_front();
+        }
+    }
+
+    /// 交易对的资金费率记录，最新的在前
+    pub fn history(&self, symbol: &Symbol, limit: Option<usize>) -> Vec<FundingRate> {
+        self.history
+            .read()
+            .get(symbol)
+            .map(|records| {
+                records
+                    .iter()
+                    .rev()
+                    .take(limit.unwrap_or(usize::MAX))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn btc() -> Symbol {
+        Symbol::new("BTC", "USDT")
+    }
+
+    fn funding() -> FundingEngine {
+        FundingEngine::new(FundingConfig {
+            enabled: true,
+            interval_seconds: 3600,
+            interest_rate_pct: 0.01,
+            max_rate_pct: 0.5,
+            ..Default::default()
+        })
+    }
+
+    fn mark(index_price: f64, mark_price: f64) -> MarkPrice {
+        MarkPrice {
+            symbol: btc(),
+            index_price,
+            mark_price,
+            components: Vec::new(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_rate_averages_premium_and_clamps() {
+        let funding = funding();
+        assert!(funding.take_rate(&btc()).is_none());
+
+        // 溢价 0.1% 和 0.3%，平均 0.2%，加利率 0.01%
+        funding.record_premium(&mark(100.0, 100.1));
+        funding.record_premium(&mark(100.0, 100.3));
+        let (rate, premium) = funding.take_rate(&btc()).unwrap();
+        assert!((premium - 0.002).abs() < 1e-12);
+        assert!((rate - 0.0021).abs() < 1e-12);
+        // 取出后开始新周期
+        assert!(funding.take_rate(&btc()).is_none());
+
+        funding.record_premium(&mark(100.0, 90.0));
+        let (rate, _) = funding.take_rate(&btc()).unwrap();
+        assert_eq!(rate, -0.005);
+    }
+
+    #[test]
+    fn test_payments_and_schedule() {
+        let funding = funding();
+        let position = |user_id: &str, quantity| PositionEntry {
+            user_id: user_id.to_string(),
+            symbol: btc(),
+            quantity,
+            entry_price: 100.0,
+            realized_pnl: 0.0,
+        };
+        let postings = funding.payments(
+            0.001,
+            200.0,
+            &[position("alice", 2.0), position("bob", -2.0)],
+            "USDT",
+        );
+        assert_eq!(postings[0].amount, -0.4);
+        assert_eq!(postings[1].amount, 0.4);
+
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 10, 30, 0).unwrap();
+        assert_eq!(
+            funding.next_settlement(now),
+            Utc.with_ymd_and_hms(2024, 1, 1, 11, 0, 0).single()
+        );
+        assert_eq!(
+            funding
+                .next_settlement(now)
+                .and_then(|next| funding.next_settlement(next)),
+            Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).single()
+        );
+    }
+}
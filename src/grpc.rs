@@ -0,0 +1,744 @@
+use crate::api_keys::{ApiKeyRejection, ApiKeyScope};
+use crate::auth::{Authenticator, Caller};
+use crate::backpressure::is_engine_busy;
+use crate::matching_engine::MatchingEngine;
+use crate::types::{self, CreateOrderRequest, TrailingOffset};
+use chrono::{DateTime, Utc};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
+use tonic::service::interceptor::InterceptedService;
+use tonic::service::Interceptor;
+use tonic::{Request, Response, Status};
+use tracing::info;
+use uuid::Uuid;
+
+/// 由 `proto/matching_engine.proto` 生成的消息、服务端和客户端代码
+#[allow(clippy::all)]
+pub mod proto {
+    tonic::include_proto!("matching_engine.v1");
+}
+
+use proto::matching_engine_service_server::{MatchingEngineService, MatchingEngineServiceServer};
+
+/// 每个流的发送队列容量，客户端读取跟不上时推送任务等待
+const STREAM_QUEUE_SIZE: usize = 1024;
+
+type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// gRPC 服务，与 REST API 共享同一个撮合引擎
+///
+/// 下单、撤单和订单状态流只能操作调用方自己的订单，调用方由 [`GrpcAuth`] 拦截器放入请求扩展，
+/// 没有经过拦截器的请求按未认证处理
+#[derive(Clone)]
+pub struct GrpcService {
+    engine: Arc<MatchingEngine>,
+    shutdown: watch::Receiver<bool>,
+}
+
+impl GrpcService {
+    /// 创建服务；`shutdown` 变为 true 时结束所有推送流
+    pub fn new(engine: Arc<MatchingEngine>, shutdown: watch::Receiver<bool>) -> Self {
+        Self { engine, shutdown }
+    }
+
+    /// 加上认证拦截器的服务端
+    pub fn into_server(
+        self,
+        auth: GrpcAuth,
+    ) -> InterceptedService<MatchingEngineServiceServer<Self>, GrpcAuth> {
+        MatchingEngineServiceServer::with_interceptor(self, auth)
+    }
+
+    /// 启动推送任务：从广播接收消息，经 `forward` 过滤和转换后发给客户端
+    ///
+    /// 客户端断开或服务停机时结束；广播落后导致消息丢失时以 `DATA_LOSS` 结束流，由客户端重新订阅
+    fn spawn_stream<M, T>(
+        &self,
+        mut receiver: broadcast::Receiver<M>,
+        mut forward: impl FnMut(M) -> Option<T> + Send + 'static,
+    ) -> ResponseStream<T>
+    where
+        M: Clone + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel(STREAM_QUEUE_SIZE);
+        let mut shutdown = self.shutdown.clone();
+        tokio::spawn(async move {
+            loop {
+                let message = tokio::select! {
+                    message = receiver.recv() => message,
+                    _ = shutdown.wait_for(|stopped| *stopped) => break,
+                };
+                let item = match message {
+                    Ok(message) => match forward(message) {
+                        Some(item) => Ok(item),
+                        None => continue,
+                    },
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => Err(Status::data_loss(
+                        format!("Stream lagged behind, {} messages skipped", skipped),
+                    )),
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let lagged = item.is_err();
+                if tx.send(item).await.is_err() || lagged {
+                    break;
+                }
+            }
+        });
+        Box::pin(ReceiverStream::new(rx))
+    }
+}
+
+/// gRPC 认证拦截器：与 REST API 使用同一个校验器，按元数据中的 API key 或
+/// `authorization: Bearer <JWT>` 确定调用方并放入请求扩展
+///
+/// 未启用认证时调用方不受限制；API key 无效、来源 IP 不在白名单或超过请求上限时直接拒绝
+#[derive(Clone, Default)]
+pub struct GrpcAuth {
+    authenticator: Option<Arc<Authenticator>>,
+}
+
+impl GrpcAuth {
+    /// `authenticator` 为空时不启用认证
+    pub fn new(authenticator: Option<Arc<Authenticator>>) -> Self {
+        Self { authenticator }
+    }
+}
+
+impl Interceptor for GrpcAuth {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let caller = match &self.authenticator {
+            Some(authenticator) => {
+                let metadata = request.metadata();
+                let api_key = metadata
+                    .get(authenticator.api_key_header().as_str())
+                    .map(|value| value.to_str().unwrap_or_default());
+                let bearer = metadata
+                    .get("authorization")
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.strip_prefix("Bearer "));
+                let ip = request.remote_addr().map(|addr| addr.ip());
+                authenticator
+                    .resolve(api_key, bearer, ip)
+                    .map_err(rejection_status)?
+            }
+            None => Caller::Unrestricted,
+        };
+        request.extensions_mut().insert(caller);
+        Ok(request)
+    }
+}
+
+fn rejection_status(rejection: ApiKeyRejection) -> Status {
+    match rejection {
+        ApiKeyRejection::Invalid => Status::unauthenticated("Invalid API key"),
+        ApiKeyRejection::IpNotAllowed => {
+            Status::permission_denied("Source IP is not in the API key allowlist")
+        }
+        ApiKeyRejection::RateLimited(_) => {
+            Status::resource_exhausted("API key rate limit exceeded")
+        }
+    }
+}
+
+/// 拦截器放入请求扩展的调用方，没有时按未认证处理
+fn caller<T>(request: &Request<T>) -> Caller {
+    request
+        .extensions()
+        .get::<Caller>()
+        .cloned()
+        .unwrap_or(Caller::Anonymous)
+}
+
+/// 调用方无权操作订单时的状态：未认证为 `UNAUTHENTICATED`，其余为 `PERMISSION_DENIED`
+fn denied(status: axum::http::StatusCode) -> Status {
+    let message = status.canonical_reason().unwrap_or("Access denied");
+    if status == axum::http::StatusCode::UNAUTHORIZED {
+        Status::unauthenticated(message)
+    } else {
+        Status::permission_denied(message)
+    }
+}
+
+#[tonic::async_trait]
+impl MatchingEngineService for GrpcService {
+    async fn submit_order(
+        &self,
+        request: Request<proto::SubmitOrderRequest>,
+    ) -> Result<Response<proto::SubmitOrderResponse>, Status> {
+        caller(&request)
+            .authorize(&request.get_ref().user_id, ApiKeyScope::Trade)
+            .map_err(denied)?;
+        let order = create_order_request(request.into_inner())
+            .map_err(Status::invalid_argument)?
+            .into_order()
+            .with_channel(types::OrderChannel::Grpc);
+        let order_id = order.id;
+
+        let trades = self.engine.submit_order(order).await.map_err(|e| {
+            if is_engine_busy(&e) {
+                Status::unavailable(e)
+            } else {
+                Status::invalid_argument(e)
+            }
+        })?;
+        let order = self
+            .engine
+            .get_order(order_id)
+            .ok_or_else(|| Status::internal("Submitted order not found"))?;
+
+        Ok(Response::new(proto::SubmitOrderResponse {
+            order: Some(order.into()),
+            trades: trades.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn cancel_order(
+        &self,
+        request: Request<proto::CancelOrderRequest>,
+    ) -> Result<Response<proto::Order>, Status> {
+        caller(&request)
+            .authorize(&request.get_ref().user_id, ApiKeyScope::Trade)
+            .map_err(denied)?;
+        let request = request.into_inner();
+        match self
+            .engine
+            .cancel_order(request.order_id, request.user_id)
+            .await
+        {
+            Ok(order) => Ok(Response::new(order.into())),
+            Err(e) if e == "Order not found" => Err(Status::not_found(e)),
+            Err(e) if e.starts_with("Unauthorized") => Err(Status::permission_denied(e)),
+            Err(e) => Err(Status::failed_precondition(e)),
+        }
+    }
+
+    type StreamTradesStream = ResponseStream<proto::Trade>;
+
+    async fn stream_trades(
+        &self,
+        request: Request<proto::StreamTradesRequest>,
+    ) -> Result<Response<Self::StreamTradesStream>, Status> {
+        let request = request.into_inner();
+        let symbol = match request.symbol {
+            Some(_) => Some(symbol(request.symbol).map_err(Status::invalid_argument)?),
+            None => None,
+        };
+        let stream = self.spawn_stream(self.engine.subscribe_trades(), move |trade| {
+            symbol
+                .is_none_or(|symbol| trade.symbol == symbol)
+                .then(|| trade.into())
+        });
+        Ok(Response::new(stream))
+    }
+
+    type StreamDepthStream = ResponseStream<proto::OrderBookDepth>;
+
+    async fn stream_depth(
+        &self,
+        request: Request<proto::StreamDepthRequest>,
+    ) -> Result<Response<Self::StreamDepthStream>, Status> {
+        let request = request.into_inner();
+        let symbol = symbol(request.symbol).map_err(Status::invalid_argument)?;
+        let depth = (request.depth > 0).then_some(request.depth as usize);
+
+        // 订单簿的每次变化（挂单、成交、撤单、改单）都伴随订单状态更新，以此触发快照推送；
+        // 积压的更新合并为一次快照
+        let (tx, rx) = mpsc::channel(STREAM_QUEUE_SIZE);
+        let engine = Arc::clone(&self.engine);
+        let mut updates = engine.subscribe_orders();
+        let mut shutdown = self.shutdown.clone();
+        tokio::spawn(async move {
+            loop {
+                let snapshot = engine.get_orderbook_depth(&symbol, depth).map_or_else(
+                    || empty_depth(symbol, engine.now()),
+                    proto::OrderBookDepth::from,
+                );
+                if tx.send(Ok(snapshot)).await.is_err() {
+                    break;
+                }
+
+                let changed = loop {
+                    let update = tokio::select! {
+                        update = updates.recv() => update,
+                        _ = shutdown.wait_for(|stopped| *stopped) => break false,
+                    };
+                    match update {
+                        Ok(order) if order.symbol != symbol => continue,
+                        Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => break true,
+                        Err(broadcast::error::RecvError::Closed) => break false,
+                    }
+                };
+                if !changed {
+                    break;
+                }
+                while let Ok(_) | Err(broadcast::error::TryRecvError::Lagged(_)) =
+                    updates.try_recv()
+                {}
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    type StreamOrderUpdatesStream = ResponseStream<proto::Order>;
+
+    async fn stream_order_updates(
+        &self,
+        request: Request<proto::StreamOrderUpdatesRequest>,
+    ) -> Result<Response<Self::StreamOrderUpdatesStream>, Status> {
+        if request.get_ref().user_id.is_empty() {
+            return Err(Status::invalid_argument("user_id is required"));
+        }
+        caller(&request)
+            .authorize(&request.get_ref().user_id, ApiKeyScope::Read)
+            .map_err(denied)?;
+        let user_id = request.into_inner().user_id;
+        let stream = self.spawn_stream(self.engine.subscribe_orders(), move |order| {
+            (order.user_id == user_id).then(|| order.into())
+        });
+        Ok(Response::new(stream))
+    }
+}
+
+/// 在 `addr` 上提供 gRPC 服务，`shutdown` 完成后结束推送流并停止服务
+///
+/// `authenticator` 为空时不启用认证，启用时应与 REST API 共用同一个校验器
+pub async fn serve(
+    engine: Arc<MatchingEngine>,
+    addr: SocketAddr,
+    authenticator: Option<Arc<Authenticator>>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<(), tonic::transport::Error> {
+    let (stop_tx, stop_rx) = watch::channel(false);
+    let service = GrpcService::new(engine, stop_rx);
+
+    info!("gRPC server listening on {}", addr);
+    tonic::transport::Server::builder()
+        .add_service(service.into_server(GrpcAuth::new(authenticator)))
+        .serve_with_shutdown(addr, async move {
+            shutdown.await;
+            let _ = stop_tx.send(true);
+        })
+        .await
+}
+
+/// 将请求转换为与 REST 接口相同的下单请求，枚举未指定或字段格式错误时返回错误
+fn create_order_request(request: proto::SubmitOrderRequest) -> Result<CreateOrderRequest, String> {
+    let side = match request.side() {
+        proto::OrderSide::Buy => types::OrderSide::Buy,
+        proto::OrderSide::Sell => types::OrderSide::Sell,
+        proto::OrderSide::Unspecified => return Err("side is required".to_string()),
+    };
+    let order_type = match request.order_type() {
+        proto::OrderType::Limit => types::OrderType::Limit,
+        proto::OrderType::Market => types::OrderType::Market,
+        proto::OrderType::StopLoss => types::OrderType::StopLoss,
+        proto::OrderType::TakeProfit => types::OrderType::TakeProfit,
+        proto::OrderType::TrailingStop => types::OrderType::TrailingStop,
+        proto::OrderType::Unspecified => return Err("order_type is required".to_string()),
+    };
+    let time_in_force = match request.time_in_force() {
+        proto::TimeInForce::Unspecified | proto::TimeInForce::GoodTillCancel => {
+            types::TimeInForce::GoodTillCancel
+        }
+        proto::TimeInForce::GoodTillDate => types::TimeInForce::GoodTillDate,
+    };
+    let trailing_offset = request.trailing_offset.map(|offset| match offset {
+        proto::submit_order_request::TrailingOffset::TrailingAmount(amount) => {
+            TrailingOffset::Amount(amount)
+        }
+        proto::submit_order_request::TrailingOffset::TrailingPercent(percent) => {
+            TrailingOffset::Percent(percent)
+        }
+    });
+    let external_id = request
+        .external_id
+        .map(|id| {
+            id.parse::<Uuid>()
+                .map_err(|_| "external_id must be a UUID".to_string())
+        })
+        .transpose()?;
+
+    Ok(CreateOrderRequest {
+        symbol: symbol(request.symbol)?,
+        side,
+        order_type,
+        quantity: request.quantity,
+        price: request.price,
+        user_id: request.user_id,
+        time_in_force,
+        expires_at: request.expires_at_ns.map(DateTime::from_timestamp_nanos),
+        stop_price: request.stop_price,
+        trailing_offset,
+        reduce_only: request.reduce_only,
+        max_slippage_bps: request.max_slippage_bps,
+        quote_quantity: request.quote_quantity,
+        min_fill_qty: request.min_fill_qty,
+        external_id,
+        tag: request.tag,
+    })
+}
+
+fn symbol(symbol: Option<proto::Symbol>) -> Result<types::Symbol, String> {
+    let symbol = symbol.ok_or_else(|| "symbol is required".to_string())?;
+    types::Symbol::lookup(&symbol.base, &symbol.quote)
+}
+
+fn timestamp_ns(timestamp: DateTime<Utc>) -> i64 {
+    timestamp.timestamp_nanos_opt().unwrap_or(i64::MAX)
+}
+
+fn empty_depth(symbol: types::Symbol, now: DateTime<Utc>) -> proto::OrderBookDepth {
+    proto::OrderBookDepth {
+        symbol: Some(symbol.into()),
+        bids: Vec::new(),
+        asks: Vec::new(),
+        timestamp_ns: timestamp_ns(now),
+    }
+}
+
+impl From<types::Symbol> for proto::Symbol {
+    fn from(symbol: types::Symbol) -> Self {
+        Self {
+            base: symbol.base().to_string(),
+            quote: symbol.quote().to_string(),
+        }
+    }
+}
+
+impl From<types::Order> for proto::Order {
+    fn from(order: types::Order) -> Self {
+        let side = match order.side {
+            types::OrderSide::Buy => proto::OrderSide::Buy,
+            types::OrderSide::Sell => proto::OrderSide::Sell,
+        };
+        let order_type = match order.order_type {
+            types::OrderType::Limit => proto::OrderType::Limit,
+            types::OrderType::Market => proto::OrderType::Market,
+            types::OrderType::StopLoss => proto::OrderType::StopLoss,
+            types::OrderType::TakeProfit => proto::OrderType::TakeProfit,
+            types::OrderType::TrailingStop => proto::OrderType::TrailingStop,
+        };
+        let status = match order.status {
+            types::OrderStatus::New => proto::OrderStatus::New,
+            types::OrderStatus::PartiallyFilled => proto::OrderStatus::PartiallyFilled,
+            types::OrderStatus::Filled => proto::OrderStatus::Filled,
+            types::OrderStatus::Cancelled => proto::OrderStatus::Cancelled,
+            types::OrderStatus::Rejected => proto::OrderStatus::Rejected,
+            types::OrderStatus::Expired => proto::OrderStatus::Expired,
+        };
+        let time_in_force = match order.time_in_force {
+            types::TimeInForce::GoodTillCancel => proto::TimeInForce::GoodTillCancel,
+            types::TimeInForce::GoodTillDate => proto::TimeInForce::GoodTillDate,
+        };
+
+        Self {
+            id: order.id,
+            symbol: Some(order.symbol.into()),
+            side: side.into(),
+            order_type: order_type.into(),
+            quantity: order.quantity,
+            price: order.price,
+            status: status.into(),
+            filled_quantity: order.filled_quantity,
+            remaining_quantity: order.remaining_quantity,
+            timestamp_ns: timestamp_ns(order.timestamp),
+            user_id: order.user_id,
+            time_in_force: time_in_force.into(),
+            expires_at_ns: order.expires_at.map(timestamp_ns),
+            stop_price: order.stop_price,
+            external_id: order.external_id.map(|id| id.to_string()),
+            tag: order.tag,
+        }
+    }
+}
+
+impl From<types::Trade> for proto::Trade {
+    fn from(trade: types::Trade) -> Self {
+        Self {
+            id: trade.id,
+            symbol: Some(trade.symbol.into()),
+            buy_order_id: trade.buy_order_id,
+            sell_order_id: trade.sell_order_id,
+            quantity: trade.quantity,
+            price: trade.price,
+            timestamp_ns: timestamp_ns(trade.timestamp),
+            buyer_id: trade.buyer_id,
+            seller_id: trade.seller_id,
+        }
+    }
+}
+
+impl From<types::OrderBookDepth> for proto::OrderBookDepth {
+    fn from(depth: types::OrderBookDepth) -> Self {
+        let levels = |levels: Vec<types::PriceLevel>| {
+            levels
+                .into_iter()
+                .map(|level| proto::PriceLevel {
+                    price: level.price,
+                    total_quantity: level.total_quantity,
+                    order_count: level.order_count as u64,
+                })
+                .collect()
+        };
+        Self {
+            symbol: Some(depth.symbol.into()),
+            bids: levels(depth.bids),
+            asks: levels(depth.asks),
+            timestamp_ns: timestamp_ns(depth.timestamp),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_stream::StreamExt;
+
+    fn btc() -> proto::Symbol {
+        proto::Symbol {
+            base: "BTC".to_string(),
+            quote: "USDT".to_string(),
+        }
+    }
+
+    fn limit(side: proto::OrderSide, quantity: f64, user_id: &str) -> proto::SubmitOrderRequest {
+        proto::SubmitOrderRequest {
+            symbol: Some(btc()),
+            side: side.into(),
+            order_type: proto::OrderType::Limit.into(),
+            quantity,
+            price: Some(100.0),
+            user_id: user_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// 经过认证拦截器的请求，`token` 为 Bearer 令牌
+    fn intercept<T>(auth: &GrpcAuth, message: T, token: Option<&str>) -> Request<T> {
+        let mut request = Request::new(());
+        if let Some(token) = token {
+            request.metadata_mut().insert(
+                "authorization",
+                format!("Bearer {}", token).parse().unwrap(),
+            );
+        }
+        let (metadata, extensions, ()) = auth.clone().call(request).unwrap().into_parts();
+        Request::from_parts(metadata, extensions, message)
+    }
+
+    /// 未启用认证时的请求
+    fn open<T>(message: T) -> Request<T> {
+        intercept(&GrpcAuth::default(), message, None)
+    }
+
+    #[tokio::test]
+    async fn test_submit_cancel_and_streams() {
+        let (stop_tx, stop_rx) = watch::channel(false);
+        let service = GrpcService::new(Arc::new(MatchingEngine::new()), stop_rx);
+
+        let mut trades = service
+            .stream_trades(open(proto::StreamTradesRequest {
+                symbol: Some(btc()),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let mut depth = service
+            .stream_depth(open(proto::StreamDepthRequest {
+                symbol: Some(btc()),
+                depth: 5,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let mut updates = service
+            .stream_order_updates(open(proto::StreamOrderUpdatesRequest {
+                user_id: "maker".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(depth.next().await.unwrap().unwrap().asks.is_empty());
+
+        let maker = service
+            .submit_order(open(limit(proto::OrderSide::Sell, 2.0, "maker")))
+            .await
+            .unwrap()
+            .into_inner();
+        let maker = maker.order.unwrap();
+        assert_eq!(maker.status(), proto::OrderStatus::New);
+        let book = depth.next().await.unwrap().unwrap();
+        assert_eq!(book.asks[0].total_quantity, 2.0);
+
+        let taker = service
+            .submit_order(open(limit(proto::OrderSide::Buy, 0.5, "taker")))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(taker.order.unwrap().status(), proto::OrderStatus::Filled);
+        assert_eq!(taker.trades.len(), 1);
+        let trade = trades.next().await.unwrap().unwrap();
+        assert_eq!(trade.sell_order_id, maker.id);
+        assert_eq!(trade.quantity, 0.5);
+
+        let cancelled = service
+            .cancel_order(open(proto::CancelOrderRequest {
+                order_id: maker.id,
+                user_id: "maker".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(cancelled.status(), proto::OrderStatus::Cancelled);
+
+        let mut statuses = Vec::new();
+        for _ in 0..3 {
+            statuses.push(updates.next().await.unwrap().unwrap().status());
+        }
+        assert_eq!(
+            statuses,
+            vec![
+                proto::OrderStatus::New,
+                proto::OrderStatus::PartiallyFilled,
+                proto::OrderStatus::Cancelled,
+            ]
+        );
+
+        // 停机后推送流结束
+        stop_tx.send(true).unwrap();
+        assert!(updates.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_requests() {
+        let (_stop_tx, stop_rx) = watch::channel(false);
+        let service = GrpcService::new(Arc::new(MatchingEngine::new()), stop_rx);
+
+        let mut request = limit(proto::OrderSide::Unspecified, 1.0, "user1");
+        let status = service
+            .submit_order(open(request.clone()))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(status.message(), "side is required");
+
+        request.side = proto::OrderSide::Buy.into();
+        request.quantity = -1.0;
+        let status = service.submit_order(open(request)).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(status.message(), "Order quantity must be positive");
+
+        let status = service
+            .cancel_order(open(proto::CancelOrderRequest {
+                order_id: 42,
+                user_id: "user1".to_string(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_authorized_for_the_caller() {
+        use jsonwebtoken::{encode, EncodingKey, Header};
+
+        let authenticator = Authenticator::new(&crate::config::AuthConfig {
+            enabled: true,
+            jwt_secret: "secret".to_string(),
+            api_keys_path: std::env::temp_dir()
+                .join(format!("api-keys-{}.json", Uuid::new_v4()))
+                .to_string_lossy()
+                .into_owned(),
+            ..Default::default()
+        })
+        .unwrap();
+        let auth = GrpcAuth::new(Some(Arc::new(authenticator)));
+        let token = |user_id: &str| {
+            let claims = crate::auth::Claims {
+                sub: user_id.to_string(),
+                exp: Utc::now().timestamp() as u64 + 60,
+            };
+            encode(
+                &Header::default(),
+                &claims,
+                &EncodingKey::from_secret(b"secret"),
+            )
+            .unwrap()
+        };
+        let (_stop_tx, stop_rx) = watch::channel(false);
+        let service = GrpcService::new(Arc::new(MatchingEngine::new()), stop_rx);
+
+        let bob = service
+            .submit_order(intercept(
+                &auth,
+                limit(proto::OrderSide::Sell, 1.0, "bob"),
+                Some(&token("bob")),
+            ))
+            .await
+            .unwrap()
+            .into_inner()
+            .order
+            .unwrap();
+
+        // 没有令牌、令牌无效或没有经过拦截器的请求未认证
+        for request in [
+            intercept(&auth, limit(proto::OrderSide::Buy, 1.0, "alice"), None),
+            intercept(
+                &auth,
+                limit(proto::OrderSide::Buy, 1.0, "alice"),
+                Some("invalid"),
+            ),
+            Request::new(limit(proto::OrderSide::Buy, 1.0, "alice")),
+        ] {
+            let status = service.submit_order(request).await.unwrap_err();
+            assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        }
+
+        // 不能以其他用户下单、撤销或订阅其他用户的订单
+        let alice = token("alice");
+        let status = service
+            .submit_order(intercept(
+                &auth,
+                limit(proto::OrderSide::Buy, 1.0, "bob"),
+                Some(&alice),
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+        let cancel = proto::CancelOrderRequest {
+            order_id: bob.id,
+            user_id: "bob".to_string(),
+        };
+        let status = service
+            .cancel_order(intercept(&auth, cancel.clone(), Some(&alice)))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+        let updates = proto::StreamOrderUpdatesRequest {
+            user_id: "bob".to_string(),
+        };
+        let status = service
+            .stream_order_updates(intercept(&auth, updates.clone(), Some(&alice)))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+
+        assert!(service
+            .stream_order_updates(intercept(&auth, updates, Some(&token("bob"))))
+            .await
+            .is_ok());
+        let cancelled = service
+            .cancel_order(intercept(&auth, cancel, Some(&token("bob"))))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(cancelled.status(), proto::OrderStatus::Cancelled);
+    }
+}
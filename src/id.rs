@@ -0,0 +1,190 @@
+use crate::clock::{SharedClock, SystemClock};
+use chrono::{DateTime, TimeZone, Utc};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+
+/// 订单ID
+pub type OrderId = u64;
+/// 成交ID
+pub type TradeId = u64;
+
+/// 自定义纪元：2024-01-01T00:00:00Z（毫秒）
+const EPOCH_MILLIS: u64 = 1_704_067_200_000;
+const SHARD_BITS: u32 = 10;
+const SEQUENCE_BITS: u32 = 12;
+const SEQUENCE_MASK: u64 = (1 << SEQUENCE_BITS) - 1;
+/// 分片编号上限（不含）
+pub const MAX_SHARD: u16 = 1 << SHARD_BITS;
+
+/// 雪花算法 ID 生成器
+///
+/// ID 布局（从高到低）：41 位毫秒时间戳（相对自定义纪元）、10 位分片、12 位序号。
+/// 时间戳取自生成器的时钟，使用模拟时钟时 ID 只取决于时钟读数和分配顺序。
+/// 同一生成器产生的 ID 严格单调递增；单毫秒内序号用尽或时钟回拨时，
+/// 时间戳部分会借用下一毫秒，而不是等待时钟。
+#[derive(Debug)]
+pub struct IdGenerator {
+    shard: u64,
+    clock: SharedClock,
+    /// 上一次分配的 (时间戳 << 序号位数 | 序号)
+    last: AtomicU64,
+}
+
+impl IdGenerator {
+    /// 使用系统时钟的生成器
+    pub fn new(shard: u16) -> Result<Self, String> {
+        Self::with_clock(shard, SystemClock::shared())
+    }
+
+    /// 使用指定时钟的生成器
+    pub fn with_clock(shard: u16, clock: SharedClock) -> Result<Self, String> {
+        if shard >= MAX_SHARD {
+            return Err(format!(
+                "ID shard must be below {}, got {}",
+                MAX_SHARD, shard
+            ));
+        }
+        Ok(Self {
+            shard: shard as u64,
+            clock,
+            last: AtomicU64::new(0),
+        })
+    }
+
+    /// 进程级全局生成器，使用系统时钟
+    ///
+    /// 未调用 [`IdGenerator::init_global`] 时使用分片 0。
+    pub fn global() -> &'static Arc<IdGenerator> {
+        GLOBAL.get_or_init(|| Arc::new(IdGenerator::new(0).expect("shard 0 is valid")))
+    }
+
+    /// 以指定分片初始化全局生成器，已用其它分片初始化时返回错误
+    pub fn init_global(shard: u16) -> Result<(), String> {
+        init_in(&GLOBAL, shard)
+    }
+
+    /// 生成器的分片编号
+    pub fn shard(&self) -> u16 {
+        self.shard as u16
+    }
+
+    /// 分配下一个 ID
+    pub fn next_id(&self) -> u64 {
+        let now = (self.clock.now().timestamp_millis().max(0) as u64).saturating_sub(EPOCH_MILLIS);
+        let candidate = now << SEQUENCE_BITS;
+        let mut last = self.last.load(Ordering::Relaxed);
+        loop {
+            let next = candidate.max(last + 1);
+            match self
+                .last
+                .compare_exchange_weak(last, next, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                Ok(_) => {
+                    let millis = next >> SEQUENCE_BITS;
+                    let sequence = next & SEQUENCE_MASK;
+                    return (millis << (SHARD_BITS + SEQUENCE_BITS))
+                        | (self.shard << SEQUENCE_BITS)
+                        | sequence;
+                }
+                Err(actual) => last = actual,
+            }
+        }
+    }
+}
+
+static GLOBAL: OnceLock<Arc<IdGenerator>> = OnceLock::new();
+
+fn init_in(cell: &OnceLock<Arc<IdGenerator>>, shard: u16) -> Result<(), String> {
+    let generator = IdGenerator::new(shard)?;
+    let current = cell.get_or_init(|| Arc::new(generator));
+    if current.shard != shard as u64 {
+        return Err(format!(
+            "ID generator already initialized with shard {}",
+            current.shard
+        ));
+    }
+    Ok(())
+}
+
+/// 分配下一个订单ID
+pub fn next_order_id() -> OrderId {
+    IdGenerator::global().next_id()
+}
+
+/// 分配下一个成交ID
+pub fn next_trade_id() -> TradeId {
+    IdGenerator::global().next_id()
+}
+
+/// 解析 ID 中的时间戳
+pub fn timestamp_of(id: u64) -> DateTime<Utc> {
+    let millis = (id >> (SHARD_BITS + SEQUENCE_BITS)) + EPOCH_MILLIS;
+    Utc.timestamp_millis_opt(millis as i64)
+        .single()
+        .unwrap_or_default()
+}
+
+/// 给定时刻之后生成的 ID 都不小于该值
+pub fn min_id_at(time: DateTime<Utc>) -> u64 {
+    let millis = (time.timestamp_millis().max(0) as u64).saturating_sub(EPOCH_MILLIS);
+    millis << (SHARD_BITS + SEQUENCE_BITS)
+}
+
+/// 解析 ID 中的分片编号
+pub fn shard_of(id: u64) -> u16 {
+    ((id >> SEQUENCE_BITS) & ((1 << SHARD_BITS) - 1)) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use std::time::Duration;
+
+    #[test]
+    fn test_ids_are_monotonic_and_carry_shard() {
+        let generator = IdGenerator::new(7).unwrap();
+        let before = Utc::now().timestamp_millis();
+
+        // 超过单毫秒序号容量，验证借用下一毫秒后仍然递增
+        let ids: Vec<u64> = (0..10_000).map(|_| generator.next_id()).collect();
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(ids.iter().all(|&id| shard_of(id) == 7));
+        assert!(timestamp_of(ids[0]).timestamp_millis() >= before);
+
+        assert!(IdGenerator::new(MAX_SHARD).is_err());
+    }
+
+    #[test]
+    fn test_ids_follow_generator_clock() {
+        let start = Utc.with_ymd_and_hms(2025, 6, 1, 0, 0, 0).unwrap();
+        let run = || {
+            let clock = Arc::new(MockClock::new(start));
+            let generator = IdGenerator::with_clock(3, clock.clone()).unwrap();
+            (0..4)
+                .map(|_| {
+                    clock.advance(Duration::from_millis(1));
+                    generator.next_id()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // 相同时钟读数序列得到相同的 ID，与系统时间无关
+        let ids = run();
+        assert_eq!(ids, run());
+        assert_eq!(
+            timestamp_of(ids[0]),
+            start + chrono::Duration::milliseconds(1)
+        );
+        assert!(ids.iter().all(|&id| shard_of(id) == 3));
+    }
+
+    #[test]
+    fn test_init_rejects_conflicting_shard() {
+        let cell = OnceLock::new();
+        assert!(init_in(&cell, 2).is_ok());
+        assert!(init_in(&cell, 2).is_ok());
+        assert!(init_in(&cell, 5).is_err());
+        assert!(init_in(&cell, MAX_SHARD).is_err());
+    }
+}
@@ -0,0 +1,90 @@
+//! Kafka 事件投递（`kafka` 特性）
+use crate::config::{EventTopicsConfig, KafkaSinkConfig};
+use crate::events::{EngineEvent, EventSink, SinkFuture, EVENT_SCHEMA_VERSION};
+use rdkafka::config::ClientConfig;
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
+use std::time::Duration;
+
+/// 发布到 Kafka 的事件目标
+///
+/// 消息以交易对为键，同一交易对的事件进入同一分区并保持顺序；消息体为 JSON，
+/// 头部带 `schema_version`、`event_type` 和 `event_id`。默认开启幂等生产者并要求所有副本确认
+pub struct KafkaEventSink {
+    producer: FutureProducer,
+    topics: EventTopicsConfig,
+    timeout: Duration,
+}
+
+impl KafkaEventSink {
+    pub fn new(config: &KafkaSinkConfig, topics: EventTopicsConfig) -> Result<Self, String> {
+        let mut client = ClientConfig::new();
+        client
+            .set("bootstrap.servers", &config.brokers)
+            .set("client.id", &config.client_id)
+            .set("message.timeout.ms", config.message_timeout_ms.to_string())
+            .set("enable.idempotence", "true")
+            .set("acks", "all");
+        for (key, value) in &config.properties {
+            client.set(key, value);
+        }
+        let producer = client
+            .create()
+            .map_err(|e| format!("Failed to create Kafka producer: {}", e))?;
+
+        Ok(Self {
+            producer,
+            topics,
+            timeout: Duration::from_millis(config.message_timeout_ms),
+        })
+    }
+}
+
+impl EventSink for KafkaEventSink {
+    fn name(&self) -> &str {
+        "kafka"
+    }
+
+    fn publish<'a>(&'a self, event: &'a EngineEvent) -> SinkFuture<'a> {
+        Box::pin(async move {
+            let payload = event.to_json()?;
+            let key = event.symbol.to_string();
+            let schema_version = EVENT_SCHEMA_VERSION.to_string();
+            let event_id = event.event_id.to_string();
+            let headers = OwnedHeaders::new()
+                .insert(Header {
+                    key: "schema_version",
+                    value: Some(&schema_version),
+                })
+                .insert(Header {
+                    key: "event_type",
+                    value: Some(event.kind().as_str()),
+                })
+                .insert(Header {
+                    key: "event_id",
+                    value: Some(&event_id),
+                });
+            let record = FutureRecord::to(self.topics.topic(event.kind()))
+                .key(&key)
+                .payload(&payload)
+                .headers(headers);
+
+            self.producer
+                .send(record, self.timeout)
+                .await
+                .map(|_| ())
+                .map_err(|(e, _)| format!("Kafka delivery failed: {}", e))
+        })
+    }
+
+    fn flush(&self) -> SinkFuture<'_> {
+        Box::pin(async move {
+            let producer = self.producer.clone();
+            let timeout = self.timeout;
+            tokio::task::spawn_blocking(move || producer.flush(timeout))
+                .await
+                .map_err(|e| format!("Kafka flush task failed: {}", e))?
+                .map_err(|e| format!("Failed to flush Kafka producer: {}", e))
+        })
+    }
+}
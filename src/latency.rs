@@ -0,0 +1,218 @@
+//! 撮合路径延迟统计
+//!
+//! 用 HDR 直方图记录下单到受理（submit-to-ack）和下单到首笔成交（submit-to-first-fill）的耗时，
+//! 提供 p50/p95/p99/p999 分位数，同时写入 Prometheus 直方图；可按交易对分别统计。
+use crate::config::LatencyConfig;
+use crate::symbol::SymbolId;
+use crate::types::Symbol;
+use hdrhistogram::Histogram;
+use metrics::histogram;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use utoipa::ToSchema;
+
+/// 直方图可记录的最大耗时（纳秒），超出的记为最大值
+const MAX_TRACKABLE_NANOS: u64 = 60_000_000_000;
+/// 直方图有效数字位数
+const SIGNIFICANT_FIGURES: u8 = 3;
+
+/// 一类延迟的分位数（微秒）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct LatencyPercentiles {
+    /// 样本数
+    pub count: u64,
+    pub p50_us: f64,
+    pub p95_us: f64,
+    pub p99_us: f64,
+    pub p999_us: f64,
+    pub max_us: f64,
+}
+
+/// 下单到受理和下单到首笔成交的延迟分位数
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct LatencyStats {
+    /// 下单到受理（订单完成撮合并返回）
+    pub submit_to_ack: LatencyPercentiles,
+    /// 下单到首笔成交，只统计提交时即有成交的订单
+    pub submit_to_first_fill: LatencyPercentiles,
+}
+
+/// 撮合延迟报告
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct LatencyReport {
+    /// 全部交易对汇总
+    pub overall: LatencyStats,
+    /// 按交易对统计，未启用 `per_symbol` 时为空
+    pub symbols: BTreeMap<String, LatencyStats>,
+}
+
+struct LatencyHistograms {
+    submit_to_ack: Histogram<u64>,
+    submit_to_first_fill: Histogram<u64>,
+}
+
+impl LatencyHistograms {
+    fn new() -> Self {
+        Self {
+            submit_to_ack: new_histogram(),
+            submit_to_first_fill: new_histogram(),
+        }
+    }
+
+    fn stats(&self) -> LatencyStats {
+        LatencyStats {
+            submit_to_ack: percentiles(&self.submit_to_ack),
+            submit_to_first_fill: percentiles(&self.submit_to_first_fill),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum LatencyKind {
+    SubmitToAck,
+    SubmitToFirstFill,
+}
+
+impl LatencyKind {
+    fn metric_name(self) -> &'static str {
+        match self {
+            LatencyKind::SubmitToAck => "matching_engine_submit_to_ack_seconds",
+            LatencyKind::SubmitToFirstFill => "matching_engine_submit_to_first_fill_seconds",
+        }
+    }
+
+    fn histogram(self, histograms: &mut LatencyHistograms) -> &mut Histogram<u64> {
+        match self {
+            LatencyKind::SubmitToAck => &mut histograms.submit_to_ack,
+            LatencyKind::SubmitToFirstFill => &mut histograms.submit_to_first_fill,
+        }
+    }
+}
+
+struct LatencyState {
+    overall: LatencyHistograms,
+    symbols: HashMap<SymbolId, (Symbol, LatencyHistograms)>,
+}
+
+/// 撮合延迟统计器
+pub struct LatencyTracker {
+    per_symbol: bool,
+    state: Mutex<LatencyState>,
+}
+
+impl LatencyTracker {
+    pub fn new(config: &LatencyConfig) -> Self {
+        Self {
+            per_symbol: config.per_symbol,
+            state: Mutex::new(LatencyState {
+                overall: LatencyHistograms::new(),
+                symbols: HashMap::new(),
+            }),
+        }
+    }
+
+    /// 记录下单到受理的耗时
+    pub fn record_ack(&self, symbol: &Symbol, elapsed: Duration) {
+        self.record(LatencyKind::SubmitToAck, symbol, elapsed);
+    }
+
+    /// 记录下单到首笔成交的耗时
+    pub fn record_first_fill(&self, symbol: &Symbol, elapsed: Duration) {
+        self.record(LatencyKind::SubmitToFirstFill, symbol, elapsed);
+    }
+
+    /// 当前的延迟分位数
+    pub fn report(&self) -> LatencyReport {
+        let state = self.state.lock();
+        LatencyReport {
+            overall: state.overall.stats(),
+            symbols: state
+                .symbols
+                .values()
+                .map(|(symbol, histograms)| (symbol.to_string(), histograms.stats()))
+                .collect(),
+        }
+    }
+
+    fn record(&self, kind: LatencyKind, symbol: &Symbol, elapsed: Duration) {
+        let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+        {
+            let mut state = self.state.lock();
+            kind.histogram(&mut state.overall)
+                .saturating_record(nanos.max(1));
+            if self.per_symbol {
+                let (_, histograms) = state
+                    .symbols
+                    .entry(symbol.id())
+                    .or_insert_with(|| (*symbol, LatencyHistograms::new()));
+                kind.histogram(histograms).saturating_record(nanos.max(1));
+            }
+        }
+
+        if self.per_symbol {
+            histogram!(kind.metric_name(), "symbol" => symbol.to_string())
+                .record(elapsed.as_secs_f64());
+        } else {
+            histogram!(kind.metric_name()).record(elapsed.as_secs_f64());
+        }
+    }
+}
+
+pub(crate) fn new_histogram() -> Histogram<u64> {
+    Histogram::new_with_bounds(1, MAX_TRACKABLE_NANOS, SIGNIFICANT_FIGURES)
+        .expect("latency histogram bounds are valid")
+}
+
+pub(crate) fn percentiles(histogram: &Histogram<u64>) -> LatencyPercentiles {
+    if histogram.is_empty() {
+        return LatencyPercentiles::default();
+    }
+    let micros = |nanos: u64| nanos as f64 / 1_000.0;
+    LatencyPercentiles {
+        count: histogram.len(),
+        p50_us: micros(histogram.value_at_quantile(0.5)),
+        p95_us: micros(histogram.value_at_quantile(0.95)),
+        p99_us: micros(histogram.value_at_quantile(0.99)),
+        p999_us: micros(histogram.value_at_quantile(0.999)),
+        max_us: micros(histogram.max()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_overall_and_per_symbol() {
+        let tracker = LatencyTracker::new(&LatencyConfig {
+            enabled: true,
+            per_symbol: true,
+        });
+        let btc = Symbol::new("BTC", "USDT");
+        let eth = Symbol::new("ETH", "USDT");
+
+        for micros in 1..=100 {
+            tracker.record_ack(&btc, Duration::from_micros(micros));
+        }
+        tracker.record_ack(&eth, Duration::from_millis(5));
+        tracker.record_first_fill(&btc, Duration::from_micros(40));
+
+        let report = tracker.report();
+        let ack = &report.overall.submit_to_ack;
+        assert_eq!(ack.count, 101);
+        assert!((ack.p50_us - 51.0).abs() < 0.1);
+        assert!(ack.p99_us <= 100.1);
+        assert!((ack.max_us - 5_000.0).abs() < 5.0);
+        assert_eq!(report.overall.submit_to_first_fill.count, 1);
+
+        assert_eq!(report.symbols.len(), 2);
+        let eth_stats = &report.symbols[&eth.to_string()];
+        assert_eq!(eth_stats.submit_to_ack.count, 1);
+        assert_eq!(
+            eth_stats.submit_to_first_fill,
+            LatencyPercentiles::default()
+        );
+    }
+}
@@ -1,13 +1,97 @@
-// pub mod api;
-// pub mod config;
-// pub mod logging;
+//! 高性能撮合引擎
+//!
+//! 默认构建包含 REST/WebSocket 服务、Prometheus 监控和数据库驱动；关闭默认特性
+//! （`default-features = false`）后只保留撮合核心（订单簿、撮合引擎、类型定义等），
+//! 不引入 axum、sqlx 和 prometheus，常用类型可通过 [`prelude`] 一次导入。
+
+pub mod account;
+#[cfg(feature = "http")]
+pub mod admin;
+pub mod allocation;
+#[cfg(feature = "http")]
+pub mod api;
+#[cfg(feature = "http")]
+pub mod api_keys;
+pub mod archive;
+pub mod audit;
+#[cfg(feature = "http")]
+pub mod auth;
+pub mod backpressure;
+#[cfg(feature = "http")]
+pub mod binance;
+pub mod clock;
+pub mod cluster;
+pub mod config;
+pub mod drop_copy;
+pub mod engine_api;
+pub mod event_bus;
+pub mod events;
+pub mod export;
+pub mod fees;
+pub mod funding;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod id;
+#[cfg(feature = "kafka")]
+pub mod kafka_sink;
+pub mod latency;
+pub mod liquidation;
+pub mod loadgen;
+#[cfg(feature = "logging")]
+pub mod logging;
+pub mod margin;
 pub mod matching_engine;
-// pub mod monitoring;
+pub mod mock_engine;
+#[cfg(feature = "monitoring")]
+pub mod monitoring;
+#[cfg(feature = "nats")]
+pub mod nats_sink;
 pub mod orderbook;
+pub mod position;
+pub mod prelude;
+#[cfg(feature = "price-index")]
+pub mod price_index;
+#[cfg(feature = "redis")]
+pub mod redis_publisher;
+pub mod replay;
+pub mod replication;
+pub mod request_id;
+pub mod risk;
+pub mod sandbox;
+pub mod session;
+pub mod settlement;
+pub mod store;
+#[cfg(feature = "http")]
+pub mod surveillance;
+pub mod symbol;
+#[cfg(feature = "http")]
+pub mod telemetry;
+#[cfg(feature = "http")]
+pub mod tenant;
+#[cfg(feature = "http")]
+pub mod tls;
+pub mod trade_history;
+pub mod trigger;
 pub mod types;
-// pub mod websocket;
+pub mod volume;
+#[cfg(feature = "webhooks")]
+pub mod webhooks;
+#[cfg(feature = "http")]
+pub mod websocket;
+#[cfg(feature = "http")]
+pub mod ws_codec;
+#[cfg(feature = "http")]
+pub mod ws_replay;
 
 // 重新导出主要类型，方便使用
+pub use account::AccountManager;
+pub use clock::{Clock, MockClock, SystemClock};
+pub use cluster::EngineCluster;
+pub use engine_api::MatchingEngineApi;
+pub use id::{IdGenerator, OrderId, TradeId};
 pub use matching_engine::MatchingEngine;
+pub use mock_engine::MockMatchingEngine;
 pub use orderbook::{OrderBook, SafeOrderBook};
+pub use risk::{PreTradeCheck, PreTradeContext};
+pub use symbol::{SymbolId, SymbolRegistry};
 pub use types::*;
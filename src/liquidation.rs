@@ -0,0 +1,156 @@
+//! 强平引擎
+//!
+//! 维持保证金监控发现待强平账户后，撤销其全部挂单，按标记价格上下的价格带提交只减仓限价单平掉持仓，
+//! 价格带内未成交的部分撤销，留待下一次监控继续强平。平仓后按剩余权益结算保险基金：
+//! 权益为正时收取强平手续费，穿仓时由保险基金补足亏损。
+use crate::account::Posting;
+use crate::config::LiquidationConfig;
+use crate::types::*;
+use parking_lot::RwLock;
+use std::collections::VecDeque;
+
+/// 强平单和保险基金结算
+pub struct Liquidator {
+    config: LiquidationConfig,
+    /// 最近的强平事件，按发生顺序
+    history: RwLock<VecDeque<LiquidationEvent>>,
+}
+
+impl Liquidator {
+    pub fn new(config: LiquidationConfig) -> Self {
+        Self {
+            config,
+            history: RwLock::new(VecDeque::new()),
+        }
+    }
+
+    /// 保险基金账户的用户ID
+    pub fn insurance_fund_account(&self) -> &str {
+        &self.config.insurance_fund_account
+    }
+
+    /// 平掉 `position` 持仓的强平单方向和限价
+    ///
+    /// 卖出平多的限价为标记价格下方价格带边界（向上取整到 tick），买入平空为上方边界（向下取整），
+    /// 保证强平单不会以价格带外的价格成交
+    pub fn order_price(
+        &self,
+        position: f64,
+        mark_price: f64,
+        scale: &PriceScale,
+    ) -> (OrderSide, f64) {
+        let band = self.config.price_band_pct / 100.0;
+        if position > 0.0 {
+            let price = scale.round_to_tick(mark_price * (1.0 - band), true);
+            (OrderSide::Sell, price)
+        } else {
+            let price = scale.round_to_tick(mark_price * (1.0 + band), false);
+            (OrderSide::Buy, price)
+        }
+    }
+
+    /// 平仓后的保险基金结算，返回 (强平手续费, 保险基金赔付) 和对应的记账
+    ///
+    /// 手续费按平仓名义价值计算，不超过剩余权益；权益为负时保险基金补足到 0
+    pub fn settlement(
+        &self,
+        user_id: &str,
+        collateral_asset: &str,
+        equity: f64,
+        closed_notional: f64,
+    ) -> (f64, f64, Vec<Posting>) {
+        let posting = |user_id: &str, amount: f64, entry_type| Posting {
+            user_id: user_id.to_string(),
+            asset: collateral_asset.to_string(),
+            amount,
+            entry_type,
+        };
+        let fund = self.insurance_fund_account();
+
+        if equity < 0.0 {
+            let payout = -equity;
+            let postings = vec![
+                posting(user_id, payout, LedgerEntryType::InsuranceFund),
+                posting(fund, -payout, LedgerEntryType::InsuranceFund),
+            ];
+            return (0.0, payout, postings);
+        }
+
+        let fee = (closed_notional * self.config.fee_pct / 100.0).min(equity);
+        let postings = vec![
+            posting(user_id, -fee, LedgerEntryType::LiquidationFee),
+            posting(fund, fee, LedgerEntryType::LiquidationFee),
+        ];
+        (fee, 0.0, postings)
+    }
+
+    /// 记录强平事件，超出保留条数时丢弃最早的事件
+    pub fn record(&self, event: LiquidationEvent) {
+        let mut history = self.history.write();
+        history.push_back(event);
+        while history.len() > self.config.history_size {
+            history.pop_front();
+        }
+    }
+
+    /// 最近的强平事件，最新的在前
+    pub fn history(&self, limit: Option<usize>) -> Vec<LiquidationEvent> {
+        let history = self.history.read();
+        history
+            .iter()
+            .rev()
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn liquidator() -> Liquidator {
+        Liquidator::new(LiquidationConfig {
+            enabled: true,
+            price_band_pct: 5.0,
+            fee_pct: 1.0,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_order_price_respects_band_and_tick() {
+        let liquidator = liquidator();
+        let scale = PriceScale::from_tick_size(0.5).unwrap();
+
+        // 101 * 0.95 = 95.95，卖单向上取整到 96
+        assert_eq!(
+            liquidator.order_price(2.0, 101.0, &scale),
+            (OrderSide::Sell, 96.0)
+        );
+        // 101 * 1.05 = 106.05，买单向下取整到 106
+        assert_eq!(
+            liquidator.order_price(-2.0, 101.0, &scale),
+            (OrderSide::Buy, 106.0)
+        );
+    }
+
+    #[test]
+    fn test_settlement_charges_fee_or_pays_out() {
+        let liquidator = liquidator();
+
+        let (fee, payout, postings) = liquidator.settlement("alice", "USDT", 50.0, 1000.0);
+        assert_eq!((fee, payout), (10.0, 0.0));
+        assert_eq!(postings[0].amount, -10.0);
+        assert_eq!(postings[1].user_id, "insurance-fund");
+
+        // 手续费不超过剩余权益
+        let (fee, _, _) = liquidator.settlement("alice", "USDT", 4.0, 1000.0);
+        assert_eq!(fee, 4.0);
+
+        let (fee, payout, postings) = liquidator.settlement("alice", "USDT", -30.0, 1000.0);
+        assert_eq!((fee, payout), (0.0, 30.0));
+        assert_eq!(postings[0].amount, 30.0);
+        assert_eq!(postings[1].amount, -30.0);
+    }
+}
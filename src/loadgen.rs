@@ -0,0 +1,713 @@
+//! 合成订单流压测
+//!
+//! 按可配置的分布生成接近真实的订单流：交易对按 Zipf 分布选取，各交易对的中间价随机游走，
+//! 挂单按距中间价的 tick 数分布在盘口两侧，吃单以市价单成交，已挂出的订单按撤单率撤销。
+//! 订单流可以直接提交给进程内引擎，也可以经 REST 接口提交（`loadgen` feature），结束后报告吞吐量和延迟分位数。
+use crate::backpressure::is_engine_busy;
+use crate::id::OrderId;
+use crate::latency::{new_histogram, percentiles, LatencyPercentiles};
+use crate::matching_engine::MatchingEngine;
+use crate::types::*;
+use hdrhistogram::Histogram;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// 每个生成器最多跟踪的挂单数，超出时丢弃最早的挂单（不再撤销）
+const MAX_TRACKED_RESTING: usize = 10_000;
+
+/// 订单流参数
+#[derive(Debug, Clone)]
+pub struct LoadProfile {
+    /// 参与的交易对，越靠前被选中的概率越高
+    pub symbols: Vec<Symbol>,
+    /// Zipf 分布指数，0 为均匀分布
+    pub zipf_exponent: f64,
+    /// 下单用户数
+    pub users: usize,
+    /// 命令总数（下单和撤单）
+    pub commands: usize,
+    /// 并发提交的任务数
+    pub concurrency: usize,
+    /// 每秒目标命令数，为空时不限速
+    pub rate: Option<u64>,
+    /// 各交易对的初始中间价
+    pub start_price: f64,
+    /// 价格最小变动单位，需与引擎的 tick 配置兼容
+    pub tick_size: f64,
+    /// 每笔订单前中间价最多移动的 tick 数
+    pub walk_ticks: u32,
+    /// 挂单距中间价最多的 tick 数
+    pub depth_ticks: u32,
+    /// 挂单（限价单）占下单的比例，其余为市价吃单
+    pub maker_ratio: f64,
+    /// 每条命令为撤销已有挂单的概率
+    pub cancel_rate: f64,
+    /// 数量单位
+    pub lot_size: f64,
+    /// 单笔订单最多的数量单位
+    pub max_lots: u32,
+    /// 随机种子，相同种子生成相同的订单流
+    pub seed: u64,
+}
+
+impl Default for LoadProfile {
+    fn default() -> Self {
+        Self {
+            symbols: vec![
+                Symbol::new("BTC", "USDT"),
+                Symbol::new("ETH", "USDT"),
+                Symbol::new("SOL", "USDT"),
+            ],
+            zipf_exponent: 1.1,
+            users: 100,
+            commands: 100_000,
+            concurrency: 4,
+            rate: None,
+            start_price: 100.0,
+            tick_size: 0.01,
+            walk_ticks: 2,
+            depth_ticks: 20,
+            maker_ratio: 0.7,
+            cancel_rate: 0.3,
+            lot_size: 0.001,
+            max_lots: 1000,
+            seed: 42,
+        }
+    }
+}
+
+impl LoadProfile {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.symbols.is_empty() {
+            return Err("At least one symbol is required".to_string());
+        }
+        if self.users == 0 || self.concurrency == 0 {
+            return Err("Users and concurrency cannot be 0".to_string());
+        }
+        if self.rate == Some(0) {
+            return Err("Rate cannot be 0".to_string());
+        }
+        if !(self.zipf_exponent >= 0.0 && self.zipf_exponent.is_finite()) {
+            return Err("Zipf exponent must be a non-negative number".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.maker_ratio) || !(0.0..=1.0).contains(&self.cancel_rate) {
+            return Err("Maker ratio and cancel rate must be between 0 and 1".to_string());
+        }
+        if !(self.tick_size > 0.0 && self.lot_size > 0.0) || self.max_lots == 0 {
+            return Err("Tick size, lot size and max lots must be positive".to_string());
+        }
+        if self.depth_ticks == 0 || self.start_price / self.tick_size <= self.depth_ticks as f64 {
+            return Err("Start price must be more than depth_ticks ticks above 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// 生成的命令
+#[derive(Debug, Clone)]
+pub enum FlowCommand {
+    Submit(Order),
+    Cancel { order_id: OrderId, user_id: String },
+}
+
+/// SplitMix64 伪随机数，保证相同种子的订单流可复现
+#[derive(Debug, Clone)]
+struct FlowRng(u64);
+
+impl FlowRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// [0, 1) 上的均匀分布
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// [0, n) 上的均匀分布
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+}
+
+/// 订单流生成器
+///
+/// [`fork`](Self::fork) 出的生成器共享各交易对的中间价，多个并发任务生成的报价围绕同一价格游走
+pub struct OrderFlowGenerator {
+    profile: Arc<LoadProfile>,
+    rng: FlowRng,
+    /// 交易对选取的累积分布
+    zipf_cdf: Arc<[f64]>,
+    /// 各交易对的中间价（tick 数）
+    mids: Arc<[AtomicI64]>,
+    /// 可撤销的挂单
+    resting: VecDeque<(OrderId, String)>,
+}
+
+impl OrderFlowGenerator {
+    pub fn new(profile: LoadProfile) -> Result<Self, String> {
+        profile.validate()?;
+        let weights: Vec<f64> = (1..=profile.symbols.len())
+            .map(|rank| 1.0 / (rank as f64).powf(profile.zipf_exponent))
+            .collect();
+        let total: f64 = weights.iter().sum();
+        let zipf_cdf = weights
+            .iter()
+            .scan(0.0, |cumulative, weight| {
+                *cumulative += weight / total;
+                Some(*cumulative)
+            })
+            .collect();
+        let start = (profile.start_price / profile.tick_size).round() as i64;
+        let mids = profile
+            .symbols
+            .iter()
+            .map(|_| AtomicI64::new(start))
+            .collect();
+
+        Ok(Self {
+            rng: FlowRng(profile.seed),
+            profile: Arc::new(profile),
+            zipf_cdf,
+            mids,
+            resting: VecDeque::new(),
+        })
+    }
+
+    /// 派生共享中间价、使用独立随机序列的生成器
+    pub fn fork(&self, stream: u64) -> Self {
+        Self {
+            profile: Arc::clone(&self.profile),
+            rng: FlowRng(self.profile.seed ^ stream.wrapping_mul(0xA076_1D64_78BD_642F)),
+            zipf_cdf: Arc::clone(&self.zipf_cdf),
+            mids: Arc::clone(&self.mids),
+            resting: VecDeque::new(),
+        }
+    }
+
+    /// 登记仍在订单簿上的挂单，之后可能被撤销
+    pub fn record_resting(&mut self, order_id: OrderId, user_id: String) {
+        if self.resting.len() >= MAX_TRACKED_RESTING {
+            self.resting.pop_front();
+        }
+        self.resting.push_back((order_id, user_id));
+    }
+
+    /// 生成下一条命令
+    pub fn next_command(&mut self) -> FlowCommand {
+        if !self.resting.is_empty() && self.rng.chance(self.profile.cancel_rate) {
+            let index = self.rng.below(self.resting.len() as u64) as usize;
+            if let Some((order_id, user_id)) = self.resting.swap_remove_back(index) {
+                return FlowCommand::Cancel { order_id, user_id };
+            }
+        }
+
+        let profile = Arc::clone(&self.profile);
+        let index = self.pick_symbol();
+        let mid = self.walk(index);
+        let side = if self.rng.chance(0.5) {
+            OrderSide::Buy
+        } else {
+            OrderSide::Sell
+        };
+        let quantity = (1 + self.rng.below(profile.max_lots as u64)) as f64 * profile.lot_size;
+        let user_id = format!("loadgen_{}", self.rng.below(profile.users as u64));
+
+        let order = if self.rng.chance(profile.maker_ratio) {
+            let offset = 1 + self.rng.below(profile.depth_ticks as u64) as i64;
+            let ticks = match side {
+                OrderSide::Buy => mid - offset,
+                OrderSide::Sell => mid + offset,
+            };
+            Order::new(
+                profile.symbols[index],
+                side,
+                OrderType::Limit,
+                quantity,
+                Some(ticks as f64 * profile.tick_size),
+                user_id,
+            )
+        } else {
+            Order::new(
+                profile.symbols[index],
+                side,
+                OrderType::Market,
+                quantity,
+                None,
+                user_id,
+            )
+        };
+        FlowCommand::Submit(order)
+    }
+
+    fn pick_symbol(&mut self) -> usize {
+        let sample = self.rng.next_f64();
+        self.zipf_cdf
+            .partition_point(|&cumulative| cumulative <= sample)
+            .min(self.zipf_cdf.len() - 1)
+    }
+
+    /// 中间价随机游走，始终高于挂单深度以保证买价为正，返回游走后的中间价
+    fn walk(&mut self, index: usize) -> i64 {
+        let walk = self.profile.walk_ticks as i64;
+        let delta = self.rng.below(2 * walk as u64 + 1) as i64 - walk;
+        let floor = self.profile.depth_ticks as i64 + 1;
+        let step = |mid: i64| Some((mid + delta).max(floor));
+        let previous = self.mids[index]
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, step)
+            .unwrap_or_default();
+        (previous + delta).max(floor)
+    }
+}
+
+/// 下单结果
+#[derive(Debug, Clone)]
+pub struct SubmitOutcome {
+    /// 引擎分配的订单ID
+    pub order_id: OrderId,
+    /// 是否有成交
+    pub filled: bool,
+    /// 是否仍有剩余数量挂在订单簿上
+    pub resting: bool,
+    /// 本次撮合的成交笔数，经 REST 提交时无法得知
+    pub trades: Option<usize>,
+}
+
+/// 订单流的提交目标
+pub enum LoadTarget {
+    /// 进程内引擎
+    InProcess(Arc<MatchingEngine>),
+    /// 经 REST 接口提交
+    #[cfg(feature = "loadgen")]
+    Http(HttpTarget),
+}
+
+impl LoadTarget {
+    async fn submit(&self, order: Order) -> Result<SubmitOutcome, String> {
+        match self {
+            LoadTarget::InProcess(engine) => {
+                let order_id = order.id;
+                let is_limit = order.order_type == OrderType::Limit;
+                let quantity = order.quantity;
+                let trades = engine.submit_order(order).await?;
+                let filled_quantity: f64 = trades.iter().map(|trade| trade.quantity).sum();
+                Ok(SubmitOutcome {
+                    order_id,
+                    filled: !trades.is_empty(),
+                    resting: is_limit && filled_quantity < quantity,
+                    trades: Some(trades.len()),
+                })
+            }
+            #[cfg(feature = "loadgen")]
+            LoadTarget::Http(target) => target.submit(&order).await,
+        }
+    }
+
+    async fn cancel(&self, order_id: OrderId, user_id: String) -> Result<(), String> {
+        match self {
+            LoadTarget::InProcess(engine) => engine.cancel_order(order_id, user_id).await.map(drop),
+            #[cfg(feature = "loadgen")]
+            LoadTarget::Http(target) => target.cancel(order_id, &user_id).await,
+        }
+    }
+}
+
+/// 压测结果
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadReport {
+    /// 已执行的命令数
+    pub commands: u64,
+    /// 被受理的订单数
+    pub orders: u64,
+    /// 成功撤销的订单数
+    pub cancels: u64,
+    /// 有成交的订单数
+    pub filled_orders: u64,
+    /// 成交笔数，经 REST 提交且未统计成交流时为空
+    pub trades: Option<u64>,
+    /// 被拒绝的订单数（不含繁忙拒绝）
+    pub rejected: u64,
+    /// 下单队列已满被拒绝的订单数
+    pub busy: u64,
+    /// 撤单失败数，通常是挂单在撤销前已经成交
+    pub cancel_misses: u64,
+    pub elapsed_seconds: f64,
+    /// 每秒命令数
+    pub throughput_per_second: f64,
+    /// 单条命令从提交到返回的延迟
+    pub latency: LatencyPercentiles,
+}
+
+struct WorkerStats {
+    orders: u64,
+    cancels: u64,
+    filled_orders: u64,
+    trades: Option<u64>,
+    rejected: u64,
+    busy: u64,
+    cancel_misses: u64,
+    latency: Histogram<u64>,
+}
+
+impl WorkerStats {
+    fn new() -> Self {
+        Self {
+            orders: 0,
+            cancels: 0,
+            filled_orders: 0,
+            trades: Some(0),
+            rejected: 0,
+            busy: 0,
+            cancel_misses: 0,
+            latency: new_histogram(),
+        }
+    }
+
+    fn merge(&mut self, other: WorkerStats) {
+        self.orders += other.orders;
+        self.cancels += other.cancels;
+        self.filled_orders += other.filled_orders;
+        self.trades = self.trades.zip(other.trades).map(|(a, b)| a + b);
+        self.rejected += other.rejected;
+        self.busy += other.busy;
+        self.cancel_misses += other.cancel_misses;
+        let _ = self.latency.add(&other.latency);
+    }
+}
+
+/// 按参数生成订单流并提交给目标，全部命令完成后返回统计结果
+pub async fn run_load(target: Arc<LoadTarget>, profile: LoadProfile) -> Result<LoadReport, String> {
+    let generator = OrderFlowGenerator::new(profile.clone())?;
+    let workers = profile.concurrency;
+    let interval = profile
+        .rate
+        .map(|rate| Duration::from_secs_f64(workers as f64 / rate as f64));
+
+    let started = Instant::now();
+    let handles: Vec<_> = (0..workers)
+        .map(|worker| {
+            let commands =
+                profile.commands / workers + usize::from(worker < profile.commands % workers);
+            let generator = generator.fork(worker as u64);
+            let target = Arc::clone(&target);
+            tokio::spawn(run_worker(target, generator, commands, interval))
+        })
+        .collect();
+
+    let mut stats = WorkerStats::new();
+    for handle in handles {
+        stats.merge(
+            handle
+                .await
+                .map_err(|e| format!("Load worker failed: {}", e))?,
+        );
+    }
+    let elapsed = started.elapsed().as_secs_f64();
+
+    Ok(LoadReport {
+        commands: profile.commands as u64,
+        orders: stats.orders,
+        cancels: stats.cancels,
+        filled_orders: stats.filled_orders,
+        trades: stats.trades,
+        rejected: stats.rejected,
+        busy: stats.busy,
+        cancel_misses: stats.cancel_misses,
+        elapsed_seconds: elapsed,
+        throughput_per_second: profile.commands as f64 / elapsed.max(f64::EPSILON),
+        latency: percentiles(&stats.latency),
+    })
+}
+
+async fn run_worker(
+    target: Arc<LoadTarget>,
+    mut generator: OrderFlowGenerator,
+    commands: usize,
+    interval: Option<Duration>,
+) -> WorkerStats {
+    let mut stats = WorkerStats::new();
+    let mut pacing = interval.map(tokio::time::interval);
+
+    for _ in 0..commands {
+        if let Some(pacing) = pacing.as_mut() {
+            pacing.tick().await;
+        }
+        let started = Instant::now();
+        match generator.next_command() {
+            FlowCommand::Submit(order) => {
+                let user_id = order.user_id.clone();
+                match target.submit(order).await {
+                    Ok(outcome) => {
+                        stats.orders += 1;
+                        stats.filled_orders += u64::from(outcome.filled);
+                        stats.trades = stats.trades.zip(outcome.trades).map(|(a, b)| a + b as u64);
+                        if outcome.resting {
+                            generator.record_resting(outcome.order_id, user_id);
+                        }
+                    }
+                    Err(e) if is_engine_busy(&e) => stats.busy += 1,
+                    Err(_) => stats.rejected += 1,
+                }
+            }
+            FlowCommand::Cancel { order_id, user_id } => {
+                match target.cancel(order_id, user_id).await {
+                    Ok(()) => stats.cancels += 1,
+                    Err(_) => stats.cancel_misses += 1,
+                }
+            }
+        }
+        let nanos = u64::try_from(started.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        stats.latency.saturating_record(nanos.max(1));
+    }
+    stats
+}
+
+#[cfg(feature = "loadgen")]
+pub use http::{HttpTarget, TradeStreamCounter};
+
+#[cfg(feature = "loadgen")]
+mod http {
+    use super::SubmitOutcome;
+    use crate::backpressure::ENGINE_BUSY;
+    use crate::id::OrderId;
+    use crate::types::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+    use std::sync::Arc;
+    use std::thread::JoinHandle;
+    use std::time::Duration;
+    use tungstenite::stream::MaybeTlsStream;
+    use tungstenite::Message;
+
+    /// 成交流读取超时，用于定期检查停止标志
+    const READ_TIMEOUT: Duration = Duration::from_millis(200);
+
+    /// 经 REST 接口下单和撤单
+    pub struct HttpTarget {
+        client: reqwest::Client,
+        /// API 根地址，如 `http://127.0.0.1:8080/api/v1`
+        base_url: String,
+    }
+
+    impl HttpTarget {
+        pub fn new(base_url: &str) -> Result<Self, String> {
+            let client = reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+            Ok(Self {
+                client,
+                base_url: base_url.trim_end_matches('/').to_string(),
+            })
+        }
+
+        pub(super) async fn submit(&self, order: &Order) -> Result<SubmitOutcome, String> {
+            let body = json!({
+                "symbol": order.symbol,
+                "side": order.side,
+                "order_type": order.order_type,
+                "quantity": order.quantity,
+                "price": order.price,
+                "user_id": order.user_id,
+            });
+            let response = self
+                .client
+                .post(format!("{}/orders", self.base_url))
+                .json(&body)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to submit order: {}", e))?;
+
+            let status = response.status();
+            if status == reqwest::StatusCode::SERVICE_UNAVAILABLE {
+                return Err(format!("{}: HTTP {}", ENGINE_BUSY, status));
+            }
+            if !status.is_success() {
+                let text = response.text().await.unwrap_or_default();
+                return Err(format!("HTTP {}: {}", status, text));
+            }
+            let created: CreateOrderResponse = response
+                .json()
+                .await
+                .map_err(|e| format!("Invalid order response: {}", e))?;
+            Ok(SubmitOutcome {
+                order_id: created.order_id,
+                filled: created.status != OrderStatus::New,
+                resting: order.order_type == OrderType::Limit
+                    && matches!(
+                        created.status,
+                        OrderStatus::New | OrderStatus::PartiallyFilled
+                    ),
+                trades: None,
+            })
+        }
+
+        pub(super) async fn cancel(&self, order_id: OrderId, user_id: &str) -> Result<(), String> {
+            let response = self
+                .client
+                .delete(format!("{}/orders/{}", self.base_url, order_id))
+                .query(&[("user_id", user_id)])
+                .send()
+                .await
+                .map_err(|e| format!("Failed to cancel order: {}", e))?;
+            let status = response.status();
+            if status.is_success() {
+                Ok(())
+            } else {
+                Err(format!("HTTP {}", status))
+            }
+        }
+    }
+
+    /// 订阅 WebSocket 成交流并计数，统计经 REST 提交的订单产生的成交
+    pub struct TradeStreamCounter {
+        trades: Arc<AtomicU64>,
+        stop: Arc<AtomicBool>,
+        reader: Option<JoinHandle<()>>,
+    }
+
+    impl TradeStreamCounter {
+        /// 连接 `{ws_url}/trades`，如 `ws://127.0.0.1:8080/ws`
+        pub fn connect(ws_url: &str) -> Result<Self, String> {
+            let url = format!("{}/trades", ws_url.trim_end_matches('/'));
+            let (mut socket, _) = tungstenite::connect(url.as_str())
+                .map_err(|e| format!("Failed to connect to {}: {}", url, e))?;
+            if let MaybeTlsStream::Plain(stream) = socket.get_ref() {
+                stream
+                    .set_read_timeout(Some(READ_TIMEOUT))
+                    .map_err(|e| format!("Failed to set read timeout: {}", e))?;
+            }
+
+            let trades = Arc::new(AtomicU64::new(0));
+            let stop = Arc::new(AtomicBool::new(false));
+            let reader = std::thread::spawn({
+                let trades = Arc::clone(&trades);
+                let stop = Arc::clone(&stop);
+                move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        match socket.read() {
+                            Ok(Message::Text(text)) => {
+                                let is_trade = serde_json::from_str::<serde_json::Value>(&text)
+                                    .is_ok_and(|message| message["type"] == "trade");
+                                if is_trade {
+                                    trades.fetch_add(1, Ordering::Relaxed);
+                                }
+                            }
+                            Ok(_) => {}
+                            Err(tungstenite::Error::Io(e))
+                                if matches!(
+                                    e.kind(),
+                                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                                ) => {}
+                            Err(_) => break,
+                        }
+                    }
+                    let _ = socket.close(None);
+                }
+            });
+
+            Ok(Self {
+                trades,
+                stop,
+                reader: Some(reader),
+            })
+        }
+
+        /// 已收到的成交数
+        pub fn count(&self) -> u64 {
+            self.trades.load(Ordering::Relaxed)
+        }
+
+        /// 停止读取并返回收到的成交数
+        pub fn stop(mut self) -> u64 {
+            self.stop.store(true, Ordering::Relaxed);
+            if let Some(reader) = self.reader.take() {
+                let _ = reader.join();
+            }
+            self.count()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shape(command: &FlowCommand) -> String {
+        match command {
+            FlowCommand::Submit(order) => format!(
+                "{} {:?} {:?} {} {:?} {}",
+                order.symbol,
+                order.side,
+                order.order_type,
+                order.quantity,
+                order.price,
+                order.user_id
+            ),
+            FlowCommand::Cancel { user_id, .. } => format!("cancel {}", user_id),
+        }
+    }
+
+    #[test]
+    fn test_generator_is_reproducible_and_skewed() {
+        let profile = LoadProfile {
+            cancel_rate: 0.0,
+            ..LoadProfile::default()
+        };
+        let mut first = OrderFlowGenerator::new(profile.clone()).unwrap();
+        let mut second = OrderFlowGenerator::new(profile.clone()).unwrap();
+
+        let mut counts = vec![0usize; profile.symbols.len()];
+        for _ in 0..2000 {
+            let command = first.next_command();
+            assert_eq!(shape(&command), shape(&second.next_command()));
+            let FlowCommand::Submit(order) = command else {
+                panic!("cancels are disabled");
+            };
+            if let Some(price) = order.price {
+                assert!(price > 0.0);
+            }
+            let index = profile
+                .symbols
+                .iter()
+                .position(|s| *s == order.symbol)
+                .unwrap();
+            counts[index] += 1;
+        }
+        // Zipf 分布下排名靠前的交易对更活跃
+        assert!(counts[0] > counts[1] && counts[1] > counts[2]);
+    }
+
+    #[tokio::test]
+    async fn test_in_process_run_reports_flow() {
+        let engine = Arc::new(MatchingEngine::new());
+        let profile = LoadProfile {
+            commands: 2000,
+            ..LoadProfile::default()
+        };
+        let report = run_load(Arc::new(LoadTarget::InProcess(engine)), profile)
+            .await
+            .unwrap();
+
+        assert_eq!(report.commands, 2000);
+        assert_eq!(
+            report.orders + report.cancels + report.rejected + report.busy + report.cancel_misses,
+            2000
+        );
+        assert_eq!(report.rejected, 0);
+        assert!(report.cancels > 0);
+        assert!(report.trades.unwrap() > 0);
+        assert_eq!(report.latency.count, 2000);
+    }
+}
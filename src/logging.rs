@@ -1,16 +1,101 @@
 use std::path::Path;
-use tracing::{debug, error, info, warn};
+use tracing::info;
+use tracing_appender::non_blocking::WorkerGuard;
 use tracing_appender::{non_blocking, rolling};
-use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use tracing_subscriber::{
+    fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Layer, Registry,
+};
+
+/// 运行时日志级别控制句柄，可克隆后交给管理接口使用
+#[derive(Clone)]
+pub struct LogLevelHandle {
+    handle: reload::Handle<EnvFilter, Registry>,
+}
+
+impl LogLevelHandle {
+    /// 替换当前的过滤规则，支持 `info`、`matching_engine=debug,tower_http=warn` 等写法
+    pub fn set_level(&self, directives: &str) -> Result<(), String> {
+        let filter = EnvFilter::try_new(directives)
+            .map_err(|e| format!("Invalid log filter '{}': {}", directives, e))?;
+        self.handle
+            .reload(filter)
+            .map_err(|e| format!("Failed to reload log filter: {}", e))?;
+        info!("Log level changed to: {}", directives);
+        Ok(())
+    }
+
+    /// 当前的过滤规则
+    pub fn current_level(&self) -> Option<String> {
+        self.handle.with_current(|filter| filter.to_string()).ok()
+    }
+}
+
+/// 日志系统句柄
+///
+/// 持有文件写入器的 `WorkerGuard`，句柄被丢弃后后台写入线程退出、文件日志停止写入，
+/// 因此需要在整个进程生命周期内保留。
+pub struct LoggingHandle {
+    level: LogLevelHandle,
+    _guard: Option<WorkerGuard>,
+    /// OTLP 导出器，丢弃时刷新未发送的 span
+    #[cfg(feature = "otel")]
+    _otel: Option<crate::telemetry::OtelGuard>,
+}
+
+impl LoggingHandle {
+    /// 运行时日志级别控制句柄
+    pub fn level_handle(&self) -> LogLevelHandle {
+        self.level.clone()
+    }
+}
+
+/// 创建可在运行时替换的过滤层
+fn reloadable_filter(log_level: &str) -> (reload::Layer<EnvFilter, Registry>, LogLevelHandle) {
+    let env_filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(log_level));
+    let (filter, handle) = reload::Layer::new(env_filter);
+    (filter, LogLevelHandle { handle })
+}
+
+/// 类型擦除后的输出层
+pub(crate) type BoxedLayer<S> = Box<dyn Layer<S> + Send + Sync>;
+
+/// 创建按天滚动的 JSON 文件输出层
+fn file_layer<S>(
+    log_file_path: &str,
+) -> Result<(BoxedLayer<S>, WorkerGuard), Box<dyn std::error::Error>>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    // 确保日志目录存在
+    if let Some(parent) = Path::new(log_file_path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    // 创建滚动日志文件写入器
+    let file_appender = rolling::daily(log_file_path, "matching_engine.log");
+    let (non_blocking_appender, guard) = non_blocking(file_appender);
+
+    let layer = fmt::layer()
+        .with_writer(non_blocking_appender)
+        .with_target(true)
+        .with_thread_ids(true)
+        .with_thread_names(true)
+        .with_file(true)
+        .with_line_number(true)
+        .with_ansi(false)
+        .json()
+        .boxed();
+
+    Ok((layer, guard))
+}
 
 /// 初始化日志系统
 pub fn init_logging(
     log_level: &str,
     log_file: Option<&str>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    // 设置环境过滤器
-    let env_filter =
-        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(log_level));
+) -> Result<LoggingHandle, Box<dyn std::error::Error>> {
+    let (filter, level) = reloadable_filter(log_level);
 
     // 创建控制台输出层
     let console_layer = fmt::layer()
@@ -23,49 +108,32 @@ pub fn init_logging(
         .compact();
 
     // 创建文件输出层（如果指定了日志文件）
-    let file_layer = if let Some(log_file_path) = log_file {
-        // 确保日志目录存在
-        if let Some(parent) = Path::new(log_file_path).parent() {
-            std::fs::create_dir_all(parent)?;
+    let (file_layer, guard) = match log_file {
+        Some(log_file_path) => {
+            let (layer, guard) = file_layer(log_file_path)?;
+            (Some(layer), Some(guard))
         }
-
-        // 创建滚动日志文件写入器
-        let file_appender = rolling::daily(log_file_path, "matching_engine.log");
-        let (non_blocking_appender, _guard) = non_blocking(file_appender);
-
-        Some(
-            fmt::layer()
-                .with_writer(non_blocking_appender)
-                .with_target(true)
-                .with_thread_ids(true)
-                .with_thread_names(true)
-                .with_file(true)
-                .with_line_number(true)
-                .with_ansi(false)
-                .json()
-                .boxed(),
-        )
-    } else {
-        None
+        None => (None, None),
     };
 
     // 初始化订阅者
-    let registry = tracing_subscriber::registry()
-        .with(env_filter)
-        .with(console_layer);
-
-    if let Some(file_layer) = file_layer {
-        registry.with(file_layer).init();
-    } else {
-        registry.init();
-    }
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(console_layer)
+        .with(file_layer)
+        .try_init()?;
 
     info!("Logging system initialized with level: {}", log_level);
     if let Some(log_file) = log_file {
         info!("Log file: {}", log_file);
     }
 
-    Ok(())
+    Ok(LoggingHandle {
+        level,
+        _guard: guard,
+        #[cfg(feature = "otel")]
+        _otel: None,
+    })
 }
 
 // LoggingConfig 定义在 config.rs 中
@@ -73,9 +141,8 @@ pub fn init_logging(
 /// 高级日志初始化
 pub fn init_advanced_logging(
     config: crate::config::LoggingConfig,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let env_filter =
-        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&config.level));
+) -> Result<LoggingHandle, Box<dyn std::error::Error>> {
+    let (filter, level) = reloadable_filter(&config.level);
 
     let mut layers = Vec::new();
 
@@ -106,43 +173,34 @@ pub fn init_advanced_logging(
     }
 
     // 文件输出层
+    let mut guard = None;
     if let Some(log_file_path) = &config.file {
-        // 确保日志目录存在
-        if let Some(parent) = Path::new(log_file_path).parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-
-        // 创建滚动日志文件写入器
-        let file_appender = rolling::daily(log_file_path, "matching_engine.log");
-        let (non_blocking_appender, _guard) = non_blocking(file_appender);
-
-        let file_layer = fmt::layer()
-            .with_writer(non_blocking_appender)
-            .with_target(true)
-            .with_thread_ids(true)
-            .with_thread_names(true)
-            .with_file(true)
-            .with_line_number(true)
-            .with_ansi(false)
-            .json()
-            .boxed();
-
-        layers.push(file_layer);
+        let (layer, file_guard) = file_layer(log_file_path)?;
+        layers.push(layer);
+        guard = Some(file_guard);
     }
 
+    // OTLP 链路追踪层
+    #[cfg(feature = "otel")]
+    let otel = if config.otlp.enabled {
+        let (layer, otel_guard) = crate::telemetry::init_tracer(&config.otlp)?;
+        layers.push(layer);
+        Some(otel_guard)
+    } else {
+        None
+    };
+
     // 初始化订阅者
-    let registry = tracing_subscriber::registry().with(env_filter);
-
-    match layers.len() {
-        0 => registry.init(),
-        1 => registry.with(layers.into_iter().next().unwrap()).init(),
-        _ => {
-            let mut registry = registry;
-            for layer in layers {
-                registry = registry.with(layer);
-            }
-            registry.init();
-        }
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(layers)
+        .try_init()?;
+
+    #[cfg(not(feature = "otel"))]
+    if config.otlp.enabled {
+        tracing::warn!(
+            "OTLP tracing is enabled in config but the `otel` feature is not compiled in"
+        );
     }
 
     info!("Advanced logging system initialized");
@@ -152,8 +210,16 @@ pub fn init_advanced_logging(
     if let Some(log_file) = &config.file {
         info!("Log file: {}", log_file);
     }
+    if config.otlp.enabled {
+        info!("OTLP endpoint: {}", config.otlp.endpoint);
+    }
 
-    Ok(())
+    Ok(LoggingHandle {
+        level,
+        _guard: guard,
+        #[cfg(feature = "otel")]
+        _otel: otel,
+    })
 }
 
 /// 性能日志宏
@@ -251,6 +317,7 @@ macro_rules! stats_log {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::{LogRotationConfig, LoggingConfig, OtlpConfig};
 
     #[test]
     fn test_logging_config_default() {
@@ -268,6 +335,8 @@ mod tests {
             file: Some("/tmp/test.log".to_string()),
             console: false,
             json_format: true,
+            rotation: LogRotationConfig::default(),
+            otlp: OtlpConfig::default(),
         };
 
         assert_eq!(config.level, "debug");
@@ -275,4 +344,15 @@ mod tests {
         assert!(config.json_format);
         assert!(config.file.is_some());
     }
+
+    #[test]
+    fn test_log_level_reload() {
+        let (filter, level) = reloadable_filter("info");
+        let _subscriber = tracing_subscriber::registry().with(filter);
+
+        level.set_level("matching_engine=debug").unwrap();
+        assert_eq!(level.current_level().unwrap(), "matching_engine=debug");
+        assert!(level.set_level("matching_engine=[").is_err());
+        assert_eq!(level.current_level().unwrap(), "matching_engine=debug");
+    }
 }
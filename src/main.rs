@@ -1,12 +1,14 @@
-mod matching_engine;
-mod orderbook;
+mod server;
 mod simple_main;
-mod types;
 
 use anyhow::Result;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // 使用简化版本运行
-    simple_main::run_simple_server().await
+    // `--simple` 运行前端开发用的简化服务（固定端口 8888），默认按配置启动完整服务
+    if std::env::args().any(|arg| arg == "--simple") {
+        simple_main::run_simple_server().await
+    } else {
+        server::run_server().await
+    }
 }
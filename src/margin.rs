@@ -0,0 +1,316 @@
+//! 保证金检查与维持保证金监控
+//!
+//! 账户权益为保证金资产余额加上全部持仓的已实现和未实现盈亏。下单前按成交后最坏情况的持仓
+//! （当前持仓加上同方向全部挂单完全成交）计算初始保证金，增加保证金需求的订单超过权益时拒绝；
+//! 监控任务定期按标记价格计算各账户的维持保证金，不低于权益的账户被标记为待强平。
+use crate::config::MarginConfig;
+use crate::position::PositionEntry;
+use crate::types::*;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+/// 保证金比较的容差，避免浮点误差导致的误拒
+const MARGIN_EPSILON: f64 = 1e-9;
+
+/// 单个交易对上的持仓和挂单敞口
+#[derive(Default)]
+struct Exposure {
+    position: f64,
+    open_buys: f64,
+    open_sells: f64,
+    /// 没有参考价时用挂单价格估算名义价值
+    order_price: Option<f64>,
+}
+
+impl Exposure {
+    /// 挂单全部成交后可能达到的最大持仓绝对值
+    fn worst_case(&self) -> f64 {
+        (self.position + self.open_buys)
+            .abs()
+            .max((self.position - self.open_sells).abs())
+    }
+}
+
+/// 保证金计算和待强平账户登记
+pub struct MarginEngine {
+    config: MarginConfig,
+    /// 最近一次监控标记为待强平的账户
+    liquidations: RwLock<BTreeMap<String, MarginAccount>>,
+}
+
+impl MarginEngine {
+    pub fn new(config: MarginConfig) -> Self {
+        Self {
+            config,
+            liquidations: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// 作为保证金的资产
+    pub fn collateral_asset(&self) -> &str {
+        &self.config.collateral_asset
+    }
+
+    /// 维持保证金监控的间隔
+    pub fn check_interval(&self) -> Duration {
+        Duration::from_millis(self.config.check_interval_ms)
+    }
+
+    /// 计算账户的保证金状况，`price_of` 返回交易对的标记价格（没有时为最新成交价）
+    pub fn account(
+        &self,
+        user_id: &str,
+        collateral: f64,
+        positions: &[PositionEntry],
+        price_of: impl Fn(&Symbol) -> Option<f64>,
+        now: DateTime<Utc>,
+    ) -> MarginAccount {
+        let mut realized_pnl = 0.0;
+        let mut unrealized_pnl = 0.0;
+        let positions: Vec<PositionMargin> = positions
+            .iter()
+            .map(|position| {
+                let requirement = self.config.requirement_for(&position.symbol);
+                let mark_price = price_of(&position.symbol);
+                let notional = position.quantity.abs() * mark_price.unwrap_or(0.0);
+                realized_pnl += position.realized_pnl;
+                if let Some(price) = mark_price {
+                    unrealized_pnl += (price - position.entry_price) * position.quantity;
+                }
+                PositionMargin {
+                    symbol: position.symbol,
+                    quantity: position.quantity,
+                    mark_price,
+                    notional,
+                    initial_margin: notional * requirement.initial_margin_pct / 100.0,
+                    maintenance_margin: notional * requirement.maintenance_margin_pct / 100.0,
+                }
+            })
+            .collect();
+
+        let equity = collateral + realized_pnl + unrealized_pnl;
+        let maintenance_margin: f64 = positions.iter().map(|p| p.maintenance_margin).sum();
+        MarginAccount {
+            user_id: user_id.to_string(),
+            collateral,
+            realized_pnl,
+            unrealized_pnl,
+            equity,
+            initial_margin: positions.iter().map(|p| p.initial_margin).sum(),
+            maintenance_margin,
+            margin_ratio: (equity > 0.0).then(|| maintenance_margin / equity),
+            liquidation: maintenance_margin > MARGIN_EPSILON && maintenance_margin >= equity,
+            positions,
+            timestamp: now,
+        }
+    }
+
+    /// 检查账户权益能否覆盖订单成交后最坏情况持仓的初始保证金
+    ///
+    /// 不增加初始保证金需求的订单（如减仓单）总是允许，即使账户已低于要求
+    pub fn check_order(
+        &self,
+        order: &Order,
+        positions: &[PositionEntry],
+        open_orders: &[Order],
+        collateral: f64,
+        price_of: impl Fn(&Symbol) -> Option<f64>,
+    ) -> Result<(), String> {
+        let open_orders: Vec<&Order> = open_orders
+            .iter()
+            .filter(|open| open.id != order.id)
+            .collect();
+        let before = self.initial_requirement(positions, open_orders.iter().copied(), &price_of);
+        let after = self.initial_requirement(
+            positions,
+            open_orders.iter().copied().chain(std::iter::once(order)),
+            &price_of,
+        );
+        if after <= before + MARGIN_EPSILON {
+            return Ok(());
+        }
+
+        let equity = collateral
+            + positions
+                .iter()
+                .map(|position| {
+                    let unrealized = price_of(&position.symbol).map_or(0.0, |price| {
+                        (price - position.entry_price) * position.quantity
+                    });
+                    position.realized_pnl + unrealized
+                })
+                .sum::<f64>();
+        if after > equity + MARGIN_EPSILON {
+            return Err(format!(
+                "Insufficient margin: initial margin {} exceeds equity {}",
+                after, equity
+            ));
+        }
+        Ok(())
+    }
+
+    /// 持仓加上挂单全部成交后的初始保证金需求
+    fn initial_requirement<'a>(
+        &self,
+        positions: &[PositionEntry],
+        orders: impl Iterator<Item = &'a Order>,
+        price_of: &impl Fn(&Symbol) -> Option<f64>,
+    ) -> f64 {
+        let mut exposures: HashMap<Symbol, Exposure> = HashMap::new();
+        for position in positions {
+            exposures.entry(position.symbol).or_default().position = position.quantity;
+        }
+        for order in orders {
+            let exposure = exposures.entry(order.symbol).or_default();
+            match order.side {
+                OrderSide::Buy => exposure.open_buys += order.remaining_quantity,
+                OrderSide::Sell => exposure.open_sells += order.remaining_quantity,
+            }
+            if let Some(price) = order.price.or(order.stop_price) {
+                exposure.order_price = Some(
+                    exposure
+                        .order_price
+                        .map_or(price, |current| current.max(price)),
+                );
+            }
+        }
+
+        exposures
+            .iter()
+            .map(|(symbol, exposure)| {
+                let price = price_of(symbol).or(exposure.order_price).unwrap_or(0.0);
+                exposure.worst_case()
+                    * price
+                    * self.config.requirement_for(symbol).initial_margin_pct
+                    / 100.0
+            })
+            .sum()
+    }
+
+    /// 用一次监控的结果替换待强平账户，返回本次新标记的账户
+    pub fn update_liquidations(&self, accounts: Vec<MarginAccount>) -> Vec<MarginAccount> {
+        let mut liquidations = self.liquidations.write();
+        let flagged: BTreeMap<String, MarginAccount> = accounts
+            .into_iter()
+            .filter(|account| account.liquidation)
+            .map(|account| (account.user_id.clone(), account))
+            .collect();
+        let newly_flagged = flagged
+            .values()
+            .filter(|account| !liquidations.contains_key(&account.user_id))
+            .cloned()
+            .collect();
+        *liquidations = flagged;
+        newly_flagged
+    }
+
+    /// 最近一次监控标记为待强平的账户，按用户ID排序
+    pub fn liquidations(&self) -> Vec<MarginAccount> {
+        self.liquidations.read().values().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MarginRequirement;
+
+    fn btc() -> Symbol {
+        Symbol::new("BTC", "USDT")
+    }
+
+    fn margin() -> MarginEngine {
+        MarginEngine::new(MarginConfig {
+            enabled: true,
+            default: MarginRequirement {
+                initial_margin_pct: 10.0,
+                maintenance_margin_pct: 5.0,
+            },
+            ..Default::default()
+        })
+    }
+
+    fn position(quantity: f64, entry_price: f64) -> PositionEntry {
+        PositionEntry {
+            user_id: "alice".to_string(),
+            symbol: btc(),
+            quantity,
+            entry_price,
+            realized_pnl: 0.0,
+        }
+    }
+
+    fn limit(side: OrderSide, quantity: f64, price: f64) -> Order {
+        Order::new(
+            btc(),
+            side,
+            OrderType::Limit,
+            quantity,
+            Some(price),
+            "alice".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_check_order_uses_worst_case_exposure() {
+        let margin = margin();
+        let price = |_: &Symbol| Some(100.0);
+        let positions = vec![position(5.0, 100.0)];
+
+        // 持仓 5 需要 50，再买 5 需要 100
+        let buy = limit(OrderSide::Buy, 5.0, 100.0);
+        assert!(margin
+            .check_order(&buy, &positions, &[], 100.0, price)
+            .is_ok());
+        let err = margin
+            .check_order(&buy, &positions, &[], 99.0, price)
+            .unwrap_err();
+        assert!(err.contains("Insufficient margin"));
+
+        // 已有买入挂单时同方向新订单叠加计算
+        let open = vec![limit(OrderSide::Buy, 2.0, 100.0)];
+        let small = limit(OrderSide::Buy, 3.0, 100.0);
+        assert!(margin
+            .check_order(&small, &positions, &open, 99.0, price)
+            .is_err());
+
+        // 减仓单不增加需求，权益不足时也允许
+        let sell = limit(OrderSide::Sell, 5.0, 100.0);
+        assert!(margin
+            .check_order(&sell, &positions, &[], 10.0, price)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_account_flags_maintenance_breach() {
+        let margin = margin();
+        let positions = vec![position(10.0, 100.0)];
+
+        let healthy = margin.account("alice", 100.0, &positions, |_| Some(100.0), Utc::now());
+        assert_eq!(healthy.initial_margin, 100.0);
+        assert_eq!(healthy.maintenance_margin, 50.0);
+        assert_eq!(healthy.margin_ratio, Some(0.5));
+        assert!(!healthy.liquidation);
+
+        // 价格跌到 95：权益 50，维持保证金 47.5
+        let stressed = margin.account("alice", 100.0, &positions, |_| Some(95.0), Utc::now());
+        assert_eq!(stressed.unrealized_pnl, -50.0);
+        assert!(!stressed.liquidation);
+        // 价格跌到 94：权益 40，维持保证金 47
+        let breached = margin.account("alice", 100.0, &positions, |_| Some(94.0), Utc::now());
+        assert!(breached.liquidation);
+
+        assert_eq!(
+            margin
+                .update_liquidations(vec![healthy.clone(), breached.clone()])
+                .len(),
+            1
+        );
+        assert!(margin.update_liquidations(vec![breached]).is_empty());
+        assert_eq!(margin.liquidations().len(), 1);
+        margin.update_liquidations(vec![healthy]);
+        assert!(margin.liquidations().is_empty());
+    }
+}
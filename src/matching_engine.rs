@@ -1,46 +1,440 @@
-use crate::orderbook::SafeOrderBook;
+use crate::account::AccountManager;
+use crate::allocation;
+use crate::archive::OrderArchive;
+use crate::audit::{AuditEvent, AuditEventKind, AuditFill, AuditLog};
+use crate::backpressure::SubmissionQueues;
+use crate::clock::{Clock, SharedClock, SystemClock};
+use crate::config::{EngineConfig, PriceReference, TradePriceRule};
+use crate::drop_copy::DropCopySubscription;
+use crate::event_bus::{
+    EngineEvent, EventBus, EventJournal, EventListener, EventMetrics, EventSubscription,
+    SequencedEvent,
+};
+use crate::fees::{
+    FeeLedger, FeeRevenueReport, FeeSchedule, FeeSettlement, UserFeeTier, UserRebates,
+};
+use crate::funding::FundingEngine;
+use crate::id::{IdGenerator, OrderId, TradeId};
+use crate::latency::{LatencyReport, LatencyTracker};
+use crate::liquidation::Liquidator;
+use crate::margin::MarginEngine;
+use crate::orderbook::{OrderBook, OrderBookSnapshot, SafeOrderBook};
+use crate::position::{PositionTracker, QUANTITY_EPSILON};
+use crate::replication::ReplicationSnapshot;
+use crate::request_id;
+use crate::risk::{
+    MessageRateTracker, PreTradeCheck, PreTradeChecks, PreTradeContext, UserLimitCheck,
+    UserLimitStatus,
+};
+use crate::sandbox::{Sandbox, SandboxReset};
+use crate::session::{SessionCalendar, SessionInfo, SessionPhase};
+use crate::settlement::{EndOfDay, SettlementReport, SettlementSnapshot};
+use crate::store::{
+    aggregate_trades, taker_order_id, FillFilter, FillStore, OrderFilter, OrderStore, Page,
+    PageRequest, TradeFilter, TradeStore,
+};
+use crate::symbol::SymbolId;
+use crate::trade_history::TradeHistory;
+use crate::trigger::TriggerBook;
 use crate::types::*;
-use chrono::Utc;
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
-use std::time::Instant;
-use tokio::sync::broadcast;
-use tracing::info;
+use crate::volume::{VolumeInterval, VolumeSeries, VolumeStats};
+use chrono::{DateTime, NaiveDate, Utc};
+use metrics::{counter, gauge};
+use parking_lot::{Mutex, RwLock};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::future::Future;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, watch, Notify, OwnedMutexGuard};
+use tracing::{error, info, instrument, warn, Span};
 use uuid::Uuid;
 
+/// 到期队列键：(到期时间, 订单ID)
+type ExpiryKey = (DateTime<Utc>, OrderId);
+
+thread_local! {
+    /// 同步接口使用的单线程运行时，每个线程只创建一次
+    static BLOCKING_RUNTIME: tokio::runtime::Runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("failed to build the blocking engine runtime");
+}
+
+/// 在当前线程的单线程运行时上执行引擎操作；熔断冷却等后台任务只在后续的同步调用期间推进
+fn block_on<T>(future: impl Future<Output = Result<T, String>>) -> Result<T, String> {
+    if tokio::runtime::Handle::try_current().is_ok() {
+        return Err(
+            "Blocking engine calls cannot be made from within an async runtime".to_string(),
+        );
+    }
+    BLOCKING_RUNTIME.with(|runtime| runtime.block_on(future))
+}
+
+/// 复制快照中每个交易对携带的最近成交数，与市场数据的统计范围一致
+const REPLICATED_TRADES_PER_SYMBOL: usize = 1000;
+
+/// 交易时段调度任务两次检查的最长间隔
+const SESSION_RECHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 备用实例拒绝下单、撤单等操作时的错误信息
+const STANDBY_REJECT_REASON: &str = "Matching engine is a standby replica, order entry is disabled";
+
+/// 交易对累计计数，挂单和盘口数据从订单簿实时读取
+#[derive(Debug, Clone, Copy, Default)]
+struct SymbolCounters {
+    total_orders: u64,
+    total_trades: u64,
+    volume: f64,
+    quote_volume: f64,
+    /// 已申报的场外成交笔数，作为申报序号
+    otc_trades: u64,
+}
+
+/// 交易对熔断状态
+#[derive(Debug, Clone)]
+struct CircuitBreakerState {
+    /// 当前窗口的参考价
+    reference_price: f64,
+    /// 当前窗口开始时间
+    window_start: Instant,
+    /// 熔断触发次数，冷却任务据此判断期间是否再次熔断
+    trips: u64,
+}
+
+/// 撮合前计算出的一笔成交：吃单与 `order` 以 `price` 成交 `quantity`，成交后吃单剩余 `remaining_quantity`
+struct PlannedFill<'a> {
+    order: &'a Order,
+    quantity: f64,
+    price: f64,
+    remaining_quantity: f64,
+}
+
 /// 撮合引擎核心实现
-#[derive(Debug)]
+///
+/// 并发模型：引擎状态由若干 `parking_lot` 读写锁分别保护，锁只在同步代码段内短暂持有，
+/// 任何锁守卫都不会跨越 `.await`（`parking_lot` 的守卫不是 `Send`，跨越 `.await`
+/// 会导致 future 无法被 `tokio::spawn`，编译期即可发现）。因此锁竞争只会让工作线程
+/// 短暂自旋/停放，不会阻塞其它异步任务的调度。撮合读取对手盘快照后逐笔修改订单簿，
+/// 因此所有修改订单簿的操作（撮合、撤单、改单、只减仓缩减、导入和复制等）都先获取订单簿的
+/// 撮合锁（[`SafeOrderBook::lock_matching`]），同一交易对上串行执行，不同交易对之间互不等待。
+/// 撮合锁不可重入，持有撮合锁时不能再调用会获取同一把锁的方法。
 pub struct MatchingEngine {
+    /// 引擎配置
+    config: EngineConfig,
+    /// 交易对上市记录，启动时按 `supported_symbols` 登记
+    listings: RwLock<HashMap<SymbolId, SymbolListing>>,
     /// 每个交易对的订单簿
-    orderbooks: Arc<RwLock<HashMap<Symbol, SafeOrderBook>>>,
-    /// 所有订单的存储
-    orders: Arc<RwLock<HashMap<Uuid, Order>>>,
-    /// 交易历史
-    trades: Arc<RwLock<Vec<Trade>>>,
+    orderbooks: Arc<RwLock<HashMap<SymbolId, SafeOrderBook>>>,
+    /// 所有订单的存储，按用户建立索引
+    orders: Arc<RwLock<OrderStore>>,
+    /// 交易历史，按交易对建立索引
+    trades: Arc<RwLock<TradeStore>>,
+    /// 按用户索引的成交记录
+    fills: RwLock<FillStore>,
     /// 市场数据
-    market_data: Arc<RwLock<HashMap<Symbol, MarketData>>>,
+    market_data: Arc<RwLock<HashMap<SymbolId, MarketData>>>,
     /// 统计信息
     stats: Arc<RwLock<EngineStats>>,
+    /// 按交易对的累计计数
+    symbol_counters: RwLock<HashMap<SymbolId, SymbolCounters>>,
+    /// 引擎时钟，所有到期、熔断窗口和时间戳都以它为准
+    clock: SharedClock,
+    /// 成交ID生成器，注入时钟时按该时钟计时
+    ids: Arc<IdGenerator>,
     /// 启动时间
     start_time: Instant,
-    /// 交易广播通道
-    trade_sender: broadcast::Sender<Trade>,
-    /// 订单更新广播通道
-    order_sender: broadcast::Sender<Order>,
-    /// 市场数据广播通道
-    market_data_sender: broadcast::Sender<MarketData>,
+    /// 引擎对外全部事件的事件总线，订单簿推送盘口和后台任务推送交易状态时共用
+    event_bus: Arc<EventBus>,
+    /// 按订单的更新通道，只为有人等待的订单创建，订单进入终态后移除
+    order_watches: Mutex<HashMap<OrderId, watch::Sender<Order>>>,
+    /// 交易对交易状态，未登记的交易对视为正常交易
+    trading_states: Arc<RwLock<HashMap<SymbolId, TradingState>>>,
+    /// 每个交易对的条件单触发簿
+    triggers: Arc<RwLock<HashMap<SymbolId, TriggerBook>>>,
+    /// 交易对最新的指数价格和标记价格
+    mark_prices: RwLock<HashMap<SymbolId, MarkPrice>>,
+    /// 交易对熔断参考价
+    circuit_breakers: Arc<RwLock<HashMap<SymbolId, CircuitBreakerState>>>,
+    /// 账户余额
+    accounts: Arc<AccountManager>,
+    /// 用户净持仓
+    positions: Arc<PositionTracker>,
+    /// 下单前风控检查插件
+    pre_trade_checks: PreTradeChecks,
+    /// 终态订单归档，未启用时为空
+    archive: Option<OrderArchive>,
+    /// 成交历史文件，未启用时为空
+    trade_history: Option<TradeHistory>,
+    /// 该时间及之后的成交都在内存中，更早的成交需查询历史文件
+    trades_in_memory_since: RwLock<DateTime<Utc>>,
+    /// 按分钟累加的成交量统计
+    volume_stats: VolumeStats,
+    /// 订单生命周期审计日志，未启用时为空
+    audit: Option<AuditLog>,
+    /// 是否提供面向合规和风控的 drop copy 事件流
+    drop_copy: bool,
+    /// 用户消息/成交比统计，未启用时为空
+    message_rates: Option<MessageRateTracker>,
+    /// 按用户滚动成交额选择的手续费档位
+    fee_schedule: FeeSchedule,
+    /// 手续费收入和返佣的周期结算，未启用时为空
+    fee_ledger: Option<FeeLedger>,
+    /// 按交易对的有界下单队列，未启用时为空
+    submission_queues: Option<SubmissionQueues>,
+    /// 撮合延迟统计，未启用时为空
+    latency: Option<LatencyTracker>,
+    /// 沙盒模式的虚拟账户，未启用时为空
+    sandbox: Option<Sandbox>,
+    /// 保证金检查和待强平账户，未启用时为空
+    margin: Option<MarginEngine>,
+    /// 强平引擎和强平记录，未启用时为空
+    liquidator: Option<Liquidator>,
+    /// 永续合约资金费率，未启用时为空
+    funding: Option<FundingEngine>,
+    /// 日终结算报表，未启用时为空
+    end_of_day: Option<EndOfDay>,
+    /// 交易时段日历，未启用时为空
+    sessions: Option<SessionCalendar>,
+    /// 各交易对最近一次按时段切换到的阶段
+    session_phases: RwLock<HashMap<SymbolId, SessionPhase>>,
+    /// 终态订单首次被归档任务发现的时间
+    terminal_since: RwLock<HashMap<OrderId, Instant>>,
+    /// 外部 UUID 别名到订单ID的索引，订单归档后移除
+    external_ids: RwLock<HashMap<Uuid, OrderId>>,
+    /// GTD 订单到期队列，按 (到期时间, 订单ID) 排序
+    expiry_queue: Arc<RwLock<BTreeSet<ExpiryKey>>>,
+    /// 有更早的到期时间加入队列时唤醒到期调度任务
+    expiry_notify: Arc<Notify>,
+    /// 有新成交、市场数据待重新计算的交易对
+    stale_market_data: Mutex<HashSet<SymbolId>>,
+    /// 有交易对的市场数据过期时唤醒市场数据任务
+    market_data_notify: Notify,
+    /// 交易对最近一次广播的市场数据和广播时间，用于变化检测和推送限频
+    published_market_data: Mutex<HashMap<SymbolId, (MarketData, Instant)>>,
+    /// 停机开始后不再接受新订单
+    shutting_down: AtomicBool,
+    /// 进行中的下单和撤单数量
+    in_flight: AtomicUsize,
+    /// 进行中的操作全部完成时唤醒停机等待
+    drained: Notify,
+    /// 备用实例只应用主实例复制的事件，不受理下单、撤单和修改
+    standby: AtomicBool,
+    /// 生成复制快照期间暂停受理会改变订单簿的操作
+    paused: AtomicBool,
+    /// 暂停结束时唤醒等待中的操作
+    resumed: Notify,
+}
+
+/// 进行中操作计数守卫，离开作用域时计数减一
+struct InFlightGuard<'a> {
+    engine: &'a MatchingEngine,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        if self.engine.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.engine.drained.notify_waiters();
+        }
+    }
+}
+
+/// 复制快照暂停守卫，离开作用域时恢复受理并唤醒等待中的操作
+struct PauseGuard<'a> {
+    engine: &'a MatchingEngine,
+}
+
+impl Drop for PauseGuard<'_> {
+    fn drop(&mut self) {
+        self.engine.paused.store(false, Ordering::SeqCst);
+        self.engine.resumed.notify_waiters();
+    }
 }
 
 impl MatchingEngine {
     pub fn new() -> Self {
-        let (trade_sender, _) = broadcast::channel(10000);
-        let (order_sender, _) = broadcast::channel(10000);
-        let (market_data_sender, _) = broadcast::channel(1000);
+        Self::with_config(EngineConfig::default())
+    }
+
+    /// 使用指定配置创建撮合引擎，`id_shard` 与进程内已初始化的ID分片冲突时 panic
+    pub fn with_config(config: EngineConfig) -> Self {
+        Self::try_with_config(config).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// 使用指定配置和时钟创建撮合引擎，测试和回放中传入 `MockClock` 控制时间
+    ///
+    /// `id_shard` 与进程内已初始化的ID分片冲突时 panic，需要处理该错误时使用
+    /// [`MatchingEngine::try_with_clock`]
+    pub fn with_clock(config: EngineConfig, clock: SharedClock) -> Self {
+        Self::try_with_clock(config, clock).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// 使用指定配置创建撮合引擎，`id_shard` 与进程内已初始化的ID分片冲突时返回错误
+    ///
+    /// 引擎生成的成交ID使用进程级生成器，同一进程中的引擎实例共用同一分片，ID 互不重复
+    pub fn try_with_config(config: EngineConfig) -> Result<Self, String> {
+        IdGenerator::init_global(config.id_shard)?;
+        let ids = Arc::clone(IdGenerator::global());
+        Ok(Self::build(config, SystemClock::shared(), ids))
+    }
+
+    /// 使用指定配置和时钟创建撮合引擎，`id_shard` 与进程内已初始化的ID分片冲突时返回错误
+    ///
+    /// 引擎生成的成交ID和强平订单ID来自按该时钟计时的独立生成器，
+    /// 模拟时钟下同样的命令序列得到同样的ID
+    pub fn try_with_clock(config: EngineConfig, clock: SharedClock) -> Result<Self, String> {
+        IdGenerator::init_global(config.id_shard)?;
+        let ids = Arc::new(IdGenerator::with_clock(config.id_shard, clock.clone())?);
+        Ok(Self::build(config, clock, ids))
+    }
+
+    fn build(config: EngineConfig, clock: SharedClock, ids: Arc<IdGenerator>) -> Self {
+        // 配置了用户限额时注册内置的限额检查
+        let pre_trade_checks = PreTradeChecks::new();
+        if config.user_limits.is_enabled() {
+            pre_trade_checks.register(Arc::new(UserLimitCheck::new(config.user_limits.clone())));
+        }
+
+        let retention = &config.order_retention;
+        let archive = if retention.enabled {
+            match OrderArchive::open(&retention.archive_dir, retention.segment_max_orders) {
+                Ok(archive) => Some(archive),
+                Err(e) => {
+                    warn!("Order archive disabled: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let trade_history = if config.trade_history.enabled {
+            match TradeHistory::open(&config.trade_history.dir) {
+                Ok(history) => Some(history),
+                Err(e) => {
+                    warn!("Trade history disabled: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let trades_in_memory_since = RwLock::new(clock.now());
+        let volume_stats = VolumeStats::new(config.volume_stats.retention_hours);
+
+        let audit = if config.audit.enabled {
+            match AuditLog::open(&config.audit.dir) {
+                Ok(audit) => Some(audit),
+                Err(e) => {
+                    warn!("Order audit disabled: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let journal = if config.event_journal.enabled {
+            match EventJournal::open(&config.event_journal.dir) {
+                Ok(journal) => Some(Arc::new(journal)),
+                Err(e) => {
+                    warn!("Event journal disabled: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let event_bus = EventBus::starting_after(
+            journal
+                .as_ref()
+                .map_or(0, |journal| journal.last_sequence()),
+        )
+        .with_replay_capacity(config.event_bus.replay_capacity);
+        event_bus.register(Arc::new(EventMetrics));
+        if let Some(journal) = journal {
+            event_bus.register(journal);
+        }
+
+        let drop_copy = config.drop_copy.enabled;
+        let fee_schedule = FeeSchedule::new(config.fees.clone());
+        let fee_ledger = config
+            .fees
+            .settlement
+            .enabled
+            .then(|| FeeLedger::new(config.fees.settlement.clone(), clock.now()));
+        let message_rates = config
+            .message_ratios
+            .enabled
+            .then(|| MessageRateTracker::new(config.message_ratios.clone()));
+        let submission_queues = config
+            .submission_queue
+            .enabled
+            .then(|| SubmissionQueues::new(config.submission_queue.clone()));
+        let latency = config
+            .latency
+            .enabled
+            .then(|| LatencyTracker::new(&config.latency));
+        let sandbox = config
+            .sandbox
+            .enabled
+            .then(|| Sandbox::new(config.sandbox.clone()));
+        let margin = config
+            .margin
+            .enabled
+            .then(|| MarginEngine::new(config.margin.clone()));
+        let liquidator = config
+            .liquidation
+            .enabled
+            .then(|| Liquidator::new(config.liquidation.clone()));
+        let funding = config
+            .funding
+            .enabled
+            .then(|| FundingEngine::new(config.funding.clone()));
+        let end_of_day = if config.end_of_day.enabled {
+            match EndOfDay::open(config.end_of_day.clone()) {
+                Ok(end_of_day) => Some(end_of_day),
+                Err(e) => {
+                    warn!("End-of-day settlement disabled: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let sessions = config
+            .sessions
+            .enabled
+            .then(|| SessionCalendar::new(config.sessions.clone()));
+        let listed_at = clock.now();
+        let listings = config
+            .supported_symbols
+            .iter()
+            .filter_map(|symbol| Symbol::register(symbol).ok())
+            .map(|symbol| {
+                symbol.register_listed();
+                let listing = SymbolListing {
+                    symbol,
+                    status: ListingStatus::Listed,
+                    filters: SymbolFilters::default(),
+                    listed_at,
+                    delisted_at: None,
+                    final_stats: None,
+                };
+                (symbol.id(), listing)
+            })
+            .collect();
 
         Self {
+            config,
+            listings: RwLock::new(listings),
             orderbooks: Arc::new(RwLock::new(HashMap::new())),
-            orders: Arc::new(RwLock::new(HashMap::new())),
-            trades: Arc::new(RwLock::new(Vec::new())),
+            orders: Arc::new(RwLock::new(OrderStore::new())),
+            trades: Arc::new(RwLock::new(TradeStore::new())),
+            fills: RwLock::new(FillStore::new()),
+            symbol_counters: RwLock::new(HashMap::new()),
             market_data: Arc::new(RwLock::new(HashMap::new())),
             stats: Arc::new(RwLock::new(EngineStats {
                 total_orders: 0,
@@ -49,46 +443,318 @@ impl MatchingEngine {
                 active_orders: 0,
                 uptime_seconds: 0,
             })),
-            start_time: Instant::now(),
-            trade_sender,
-            order_sender,
-            market_data_sender,
+            start_time: clock.instant(),
+            clock,
+            ids,
+            event_bus: Arc::new(event_bus),
+            order_watches: Mutex::new(HashMap::new()),
+            trading_states: Arc::new(RwLock::new(HashMap::new())),
+            triggers: Arc::new(RwLock::new(HashMap::new())),
+            mark_prices: RwLock::new(HashMap::new()),
+            circuit_breakers: Arc::new(RwLock::new(HashMap::new())),
+            accounts: Arc::new(AccountManager::new()),
+            positions: Arc::new(PositionTracker::new()),
+            pre_trade_checks,
+            archive,
+            trade_history,
+            trades_in_memory_since,
+            volume_stats,
+            audit,
+            drop_copy,
+            message_rates,
+            fee_schedule,
+            fee_ledger,
+            submission_queues,
+            latency,
+            sandbox,
+            margin,
+            liquidator,
+            funding,
+            end_of_day,
+            sessions,
+            session_phases: RwLock::new(HashMap::new()),
+            terminal_since: RwLock::new(HashMap::new()),
+            external_ids: RwLock::new(HashMap::new()),
+            expiry_queue: Arc::new(RwLock::new(BTreeSet::new())),
+            expiry_notify: Arc::new(Notify::new()),
+            stale_market_data: Mutex::new(HashSet::new()),
+            market_data_notify: Notify::new(),
+            published_market_data: Mutex::new(HashMap::new()),
+            shutting_down: AtomicBool::new(false),
+            in_flight: AtomicUsize::new(0),
+            drained: Notify::new(),
+            standby: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            resumed: Notify::new(),
         }
     }
 
     /// 提交订单进行撮合
+    #[instrument(
+        name = "submit_order",
+        skip_all,
+        fields(order_id = order.id, symbol = %order.symbol, user_id = %order.user_id)
+    )]
     pub async fn submit_order(&self, mut order: Order) -> Result<Vec<Trade>, String> {
+        let submitted_at = Instant::now();
         let order_id = order.id;
-        let symbol = order.symbol.clone();
+        let symbol = order.symbol;
 
         info!("Submitting order {} for {}", order_id, symbol.to_string());
 
-        // 验证订单
-        self.validate_order(&order)?;
+        // 先登记再检查停机标志，保证停机等待不会漏掉已通过检查的订单
+        let _in_flight = self.enter_order_entry().await;
+        if self.is_shutting_down() {
+            let reason =
+                "Matching engine is shutting down, new orders are not accepted".to_string();
+            self.record_rejected(&order, &reason);
+            return Err(reason);
+        }
+        if self.is_standby() {
+            return Err(STANDBY_REJECT_REASON.to_string());
+        }
 
-        // 获取或创建订单簿
-        let orderbook = self.get_or_create_orderbook(&symbol);
+        // 同一交易对的订单排队逐个撮合，队列已满时直接拒绝
+        let _queue_slot = match &self.submission_queues {
+            Some(queues) => match queues.enter(&symbol).await {
+                Ok(slot) => Some(slot),
+                Err(reason) => {
+                    self.record_rejected(&order, &reason);
+                    return Err(reason);
+                }
+            },
+            None => None,
+        };
+
+        let orderbook = match self.prepare_order(&mut order) {
+            Ok(prepared) => prepared,
+            Err(reason) => {
+                self.record_rejected(&order, &reason);
+                return Err(reason);
+            }
+        };
+
+        // 依赖持仓、余额和挂单的检查在撮合锁内执行，检查通过到撮合之间同一交易对上不会有其它成交
+        let matching = orderbook.lock_matching().await;
+        if let Err(reason) = self.check_order_risk(&orderbook, &mut order) {
+            drop(matching);
+            self.record_rejected(&order, &reason);
+            return Err(reason);
+        }
+
+        // 登记外部 UUID 别名，同一别名不能对应多个订单
+        if let Some(external_id) = order.external_id {
+            let mut external_ids = self.external_ids.write();
+            if external_ids.contains_key(&external_id) {
+                let reason = format!("Duplicate external id {}", external_id);
+                self.record_rejected(&order, &reason);
+                return Err(reason);
+            }
+            external_ids.insert(external_id, order_id);
+        }
 
         // 存储订单
         {
-            let mut orders = self.orders.write().unwrap();
+            let mut orders = self.orders.write();
             orders.insert(order_id, order.clone());
         }
+        self.audit(
+            order_id,
+            AuditEventKind::Accepted {
+                request_id: request_id::current(),
+                channel: order.channel,
+            },
+        );
+        counter!(
+            "matching_engine_orders_by_channel_total",
+            "channel" => order.channel.as_str(),
+            "result" => "accepted"
+        )
+        .increment(1);
 
         // 更新统计信息
         {
-            let mut stats = self.stats.write().unwrap();
+            let mut stats = self.stats.write();
             stats.total_orders += 1;
             stats.active_orders += 1;
         }
+        self.symbol_counters
+            .write()
+            .entry(symbol.id())
+            .or_default()
+            .total_orders += 1;
+        if let Some(message_rates) = &self.message_rates {
+            message_rates.record_order(&order.user_id, self.now());
+        }
+
+        // 条件单进入触发簿，等待成交价触发
+        if order.order_type.is_trigger() {
+            drop(matching);
+            if let Err(reason) = self.add_trigger_order(order.clone()) {
+                self.rollback_submission(&order, &reason);
+                return Err(reason);
+            }
+            self.record_ack_latency(&symbol, submitted_at);
+            return Ok(Vec::new());
+        }
+
+        let trades = match self
+            .execute_order(&orderbook, order.clone(), matching, Some(submitted_at))
+            .await
+        {
+            Ok(trades) => trades,
+            Err(reason) => {
+                self.rollback_submission(&order, &reason);
+                return Err(reason);
+            }
+        };
+
+        // 新的成交价可能触发条件单
+        self.process_triggers(&symbol, &trades).await;
+
+        self.record_ack_latency(&symbol, submitted_at);
+        Ok(trades)
+    }
+
+    /// 下单前的状态检查和订单校验，返回订单簿
+    #[instrument(name = "validate", skip_all)]
+    fn prepare_order(&self, order: &mut Order) -> Result<SafeOrderBook, String> {
+        let symbol = order.symbol;
+
+        // 检查交易对状态
+        let trading_state = self.get_trading_state(&symbol);
+        if matches!(
+            trading_state,
+            TradingState::Halted | TradingState::CancelOnly
+        ) {
+            return Err(format!(
+                "Trading is {:?} for {}, new orders are not accepted",
+                trading_state, symbol
+            ));
+        }
+
+        // 验证订单
+        self.validate_order(order)?;
+
+        // 消息/成交比超限的用户不能提交新订单
+        if let Some(message_rates) = &self.message_rates {
+            message_rates.check(&order.user_id, self.now())?;
+        }
+
+        // 集合竞价只接受限价单，市价单没有可参与撮合价计算的价格
+        if trading_state == TradingState::AuctionOnly && order.order_type == OrderType::Market {
+            return Err(format!(
+                "Market orders are not accepted during the auction for {}",
+                symbol
+            ));
+        }
+
+        // 获取或创建订单簿
+        Ok(self.get_or_create_orderbook(&symbol))
+    }
+
+    /// 只减仓缩减、沙盒余额、保证金和注册的风控检查
+    ///
+    /// 调用方须持有订单簿的撮合锁，检查读取的持仓、余额和挂单在撮合前不会被同一交易对上的成交改变；
+    /// 其它交易对上的成交仍可能并发改变账户状态
+    #[instrument(name = "risk_check", skip_all)]
+    fn check_order_risk(&self, orderbook: &SafeOrderBook, order: &mut Order) -> Result<(), String> {
+        // 只减仓订单按当前持仓缩减数量，无可减持仓时拒绝
+        if order.reduce_only {
+            self.apply_reduce_only(order)?;
+        }
+
+        // 沙盒模式按虚拟余额检查可用资金
+        if let Some(sandbox) = &self.sandbox {
+            sandbox.ensure_funded(&self.accounts, &order.user_id);
+            let balances = self.accounts.get_balances(&order.user_id);
+            let open_orders = self.get_open_user_orders(&order.user_id);
+            sandbox.check_balance(order, &open_orders, &balances, orderbook.best_ask())?;
+        }
+
+        // 保证金模式检查成交后最坏情况持仓的初始保证金
+        if let Some(margin) = &self.margin {
+            let positions = self.positions.get_user_positions(&order.user_id);
+            let open_orders = self.get_open_user_orders(&order.user_id);
+            let collateral = self
+                .accounts
+                .get_balance(&order.user_id, margin.collateral_asset())
+                .available;
+            margin.check_order(order, &positions, &open_orders, collateral, |symbol| {
+                self.reference_price(symbol, PriceReference::Mark)
+            })?;
+        }
+
+        // 执行注册的风控检查
+        self.run_pre_trade_checks(orderbook, order)
+    }
+
+    /// 撮合订单并将剩余数量挂入订单簿，随后广播订单和市场数据
+    ///
+    /// 调用方传入已持有的订单簿撮合锁 `matching`，撮合和挂单完成后释放。
+    /// `submitted_at` 为用户下单的时间，有成交时据此统计下单到首笔成交的延迟；触发的条件单为空。
+    /// 交易状态在持有撮合锁后重新读取，等锁期间集合竞价结束的订单按连续交易撮合
+    async fn execute_order(
+        &self,
+        orderbook: &SafeOrderBook,
+        mut order: Order,
+        matching: OwnedMutexGuard<()>,
+        submitted_at: Option<Instant>,
+    ) -> Result<Vec<Trade>, String> {
+        let order_id = order.id;
+        let trading_state = self.get_trading_state(&order.symbol);
+        if matches!(
+            trading_state,
+            TradingState::Halted | TradingState::CancelOnly
+        ) {
+            return Err(format!(
+                "Trading is {:?} for {}, new orders are not accepted",
+                trading_state, order.symbol
+            ));
+        }
+        // 等锁期间交易对可能已下市
+        self.check_listing(&order.symbol, None, None)?;
 
-        // 尝试撮合
-        let trades = self.match_order(&orderbook, &mut order).await?;
+        // 尝试撮合（集合竞价阶段只挂单不撮合）
+        let trades = if trading_state == TradingState::AuctionOnly {
+            Vec::new()
+        } else {
+            self.match_order(orderbook, &mut order).await?
+        };
+        if let (Some(latency), Some(submitted_at)) = (&self.latency, submitted_at) {
+            if !trades.is_empty() {
+                latency.record_first_fill(&order.symbol, submitted_at.elapsed());
+            }
+        }
 
-        // 如果订单没有完全成交，添加到订单簿
-        if order.remaining_quantity > 0.0 {
+        if order.order_type == OrderType::Market {
+            // 市价单不挂入订单簿，未成交部分（含被滑点保护截断的部分）直接撤销
+            if self.has_unfilled_remainder(&order, &trades) {
+                order.status = OrderStatus::Cancelled;
+                self.audit(
+                    order_id,
+                    AuditEventKind::Cancelled {
+                        reason: "Unfilled market order remainder".to_string(),
+                    },
+                );
+                let mut stats = self.stats.write();
+                stats.active_orders = stats.active_orders.saturating_sub(1);
+                info!(
+                    "Market order {} filled {}, remainder cancelled",
+                    order_id, order.filled_quantity
+                );
+            } else {
+                order.status = OrderStatus::Filled;
+                info!("Order {} completely filled", order_id);
+            }
+        } else if order.remaining_quantity > 0.0 {
+            // 如果订单没有完全成交，添加到订单簿
             orderbook.add_order(order.clone())?;
             info!("Order {} partially filled, added to orderbook", order_id);
+
+            if let Some(expires_at) = order.expires_at {
+                self.schedule_expiry(order_id, expires_at);
+            }
         } else {
             order.status = OrderStatus::Filled;
             info!("Order {} completely filled", order_id);
@@ -96,42 +762,84 @@ impl MatchingEngine {
 
         // 更新订单状态
         {
-            let mut orders = self.orders.write().unwrap();
+            let mut orders = self.orders.write();
             orders.insert(order_id, order.clone());
         }
+        drop(matching);
+
+        self.publish_execution(order, &trades, trading_state).await;
+
+        Ok(trades)
+    }
+
+    /// 广播订单更新、聚合成交、受影响的只减仓订单、市场数据和竞价参考价
+    #[instrument(name = "broadcast", skip_all)]
+    async fn publish_execution(&self, order: Order, trades: &[Trade], trading_state: TradingState) {
+        let symbol = order.symbol;
 
         // 广播订单更新
-        let _ = self.order_sender.send(order);
+        self.publish_order(order);
+        self.publish_agg_trades(trades);
 
-        // 更新市场数据
-        self.update_market_data(&symbol).await;
+        // 成交改变了双方持仓，重新校验其挂单中的只减仓订单
+        self.reevaluate_reduce_only_for_trades(&symbol, trades)
+            .await;
 
-        // 广播市场数据
-        if let Some(market_data) = self.get_market_data(&symbol) {
-            let _ = self.market_data_sender.send(market_data);
+        // 市场数据由后台任务重新计算并广播
+        self.mark_market_data_stale(&symbol);
+
+        if trading_state == TradingState::AuctionOnly {
+            self.broadcast_auction_indicative(&symbol);
         }
+    }
 
-        Ok(trades)
+    /// 同步提交订单，供基准测试和没有 tokio 运行时的调用方使用
+    ///
+    /// 在当前线程复用的单线程运行时上执行 [`submit_order`](Self::submit_order)，不能在异步上下文中调用
+    pub fn submit_order_blocking(&self, order: Order) -> Result<Vec<Trade>, String> {
+        block_on(self.submit_order(order))
+    }
+
+    /// 同步撤销订单，约束同 [`submit_order_blocking`](Self::submit_order_blocking)
+    pub fn cancel_order_blocking(
+        &self,
+        order_id: OrderId,
+        user_id: String,
+    ) -> Result<Order, String> {
+        block_on(self.cancel_order(order_id, user_id))
     }
 
     /// 取消订单
-    pub async fn cancel_order(&self, order_id: Uuid, user_id: String) -> Result<Order, String> {
+    #[instrument(name = "cancel_order", skip(self), fields(symbol = tracing::field::Empty))]
+    pub async fn cancel_order(&self, order_id: OrderId, user_id: String) -> Result<Order, String> {
         info!("Cancelling order {} for user {}", order_id, user_id);
+        let _in_flight = self.enter_order_entry().await;
+        if self.is_standby() {
+            return Err(STANDBY_REJECT_REASON.to_string());
+        }
 
         // 获取订单
         let order = {
-            let orders = self.orders.read().unwrap();
+            let orders = self.orders.read();
             orders
                 .get(&order_id)
                 .cloned()
                 .ok_or_else(|| "Order not found".to_string())?
         };
+        Span::current().record("symbol", tracing::field::display(&order.symbol));
 
         // 验证用户权限
         if order.user_id != user_id {
             return Err("Unauthorized to cancel this order".to_string());
         }
 
+        if self.get_trading_state(&order.symbol) == TradingState::Halted {
+            return Err(format!(
+                "Trading is halted for {}, cancels are not accepted",
+                order.symbol
+            ));
+        }
+
         // 检查订单状态
         if order.status == OrderStatus::Filled {
             return Err("Cannot cancel filled order".to_string());
@@ -141,387 +849,7755 @@ impl MatchingEngine {
             return Err("Order already cancelled".to_string());
         }
 
-        // 从订单簿中移除
-        let orderbook = self
-            .get_orderbook(&order.symbol)
-            .ok_or_else(|| "Orderbook not found".to_string())?;
+        if order.status == OrderStatus::Expired {
+            return Err("Order already expired".to_string());
+        }
 
-        let mut cancelled_order = orderbook.remove_order(order_id)?;
+        // 从订单簿或触发簿中移除
+        let _matching = self.lock_matching(&order.symbol).await;
+        let mut cancelled_order = self.remove_open_order(&order)?;
         cancelled_order.status = OrderStatus::Cancelled;
 
         // 更新订单存储
         {
-            let mut orders = self.orders.write().unwrap();
+            let mut orders = self.orders.write();
             orders.insert(order_id, cancelled_order.clone());
         }
+        self.audit(
+            order_id,
+            AuditEventKind::Cancelled {
+                reason: "Cancelled by user".to_string(),
+            },
+        );
 
         // 更新统计信息
         {
-            let mut stats = self.stats.write().unwrap();
+            let mut stats = self.stats.write();
             stats.active_orders = stats.active_orders.saturating_sub(1);
         }
 
+        if let Some(message_rates) = &self.message_rates {
+            message_rates.record_cancel(&user_id, self.now());
+        }
+
         // 广播订单更新
-        let _ = self.order_sender.send(cancelled_order.clone());
+        self.publish_order(cancelled_order.clone());
+
+        if self.get_trading_state(&cancelled_order.symbol) == TradingState::AuctionOnly {
+            self.broadcast_auction_indicative(&cancelled_order.symbol);
+        }
 
         info!("Order {} cancelled successfully", order_id);
         Ok(cancelled_order)
     }
 
-    /// 获取订单信息
-    pub fn get_order(&self, order_id: Uuid) -> Option<Order> {
-        self.orders.read().unwrap().get(&order_id).cloned()
-    }
+    /// 缩减挂单的订单总量，保留时间优先级
+    ///
+    /// `new_quantity` 必须大于已成交数量且小于当前订单总量；加量或改价会失去队列位置，需撤单后重新下单
+    #[instrument(name = "amend_order", skip(self), fields(symbol = tracing::field::Empty))]
+    pub async fn amend_order(
+        &self,
+        order_id: OrderId,
+        user_id: String,
+        new_quantity: f64,
+    ) -> Result<Order, String> {
+        info!(
+            "Amending order {} for user {} to quantity {}",
+            order_id, user_id, new_quantity
+        );
+        let _in_flight = self.enter_order_entry().await;
+        if self.is_standby() {
+            return Err(STANDBY_REJECT_REASON.to_string());
+        }
 
-    /// 获取用户的所有订单
-    pub fn get_user_orders(&self, user_id: &str) -> Vec<Order> {
-        self.orders
-            .read()
-            .unwrap()
-            .values()
-            .filter(|order| order.user_id == user_id)
-            .cloned()
-            .collect()
-    }
+        let order = self
+            .get_order(order_id)
+            .ok_or_else(|| "Order not found".to_string())?;
+        Span::current().record("symbol", tracing::field::display(&order.symbol));
 
-    /// 获取订单簿深度
-    pub fn get_orderbook_depth(
-        &self,
-        symbol: &Symbol,
-        depth: Option<usize>,
-    ) -> Option<OrderBookDepth> {
-        self.get_orderbook(symbol)
-            .map(|orderbook| orderbook.get_depth(depth))
-    }
+        if order.user_id != user_id {
+            return Err("Unauthorized to amend this order".to_string());
+        }
 
-    /// 获取市场数据
-    pub fn get_market_data(&self, symbol: &Symbol) -> Option<MarketData> {
-        self.market_data.read().unwrap().get(symbol).cloned()
-    }
+        if self.get_trading_state(&order.symbol) == TradingState::Halted {
+            return Err(format!(
+                "Trading is halted for {}, amendments are not accepted",
+                order.symbol
+            ));
+        }
 
-    /// 获取所有市场数据
-    pub fn get_all_market_data(&self) -> HashMap<Symbol, MarketData> {
-        self.market_data.read().unwrap().clone()
-    }
+        if order.status.is_terminal() || order.order_type.is_trigger() {
+            return Err("Only resting limit orders can be amended".to_string());
+        }
 
-    /// 获取引擎统计信息
-    pub fn get_stats(&self) -> EngineStats {
-        let mut stats = self.stats.read().unwrap().clone();
-        stats.uptime_seconds = self.start_time.elapsed().as_secs();
-        stats
+        check_quantity(new_quantity).map_err(|reason| format!("New quantity {}", reason))?;
+        if new_quantity >= order.quantity || new_quantity <= order.filled_quantity {
+            return Err(
+                "New quantity must be below the order quantity and above the filled quantity"
+                    .to_string(),
+            );
+        }
+
+        let orderbook = self
+            .get_orderbook(&order.symbol)
+            .ok_or_else(|| "Orderbook not found".to_string())?;
+        let _matching = orderbook.lock_matching().await;
+        let amended = orderbook.reduce_order(order_id, order.quantity - new_quantity)?;
+        self.orders.write().insert(order_id, amended.clone());
+        self.audit(
+            order_id,
+            AuditEventKind::Amended {
+                old_remaining_quantity: order.remaining_quantity,
+                new_remaining_quantity: amended.remaining_quantity,
+                reason: "Amended by user".to_string(),
+            },
+        );
+        if let Some(message_rates) = &self.message_rates {
+            message_rates.record_amend(&user_id, self.now());
+        }
+        self.publish_order(amended.clone());
+
+        if self.get_trading_state(&amended.symbol) == TradingState::AuctionOnly {
+            self.broadcast_auction_indicative(&amended.symbol);
+        }
+
+        info!("Order {} amended successfully", order_id);
+        Ok(amended)
     }
 
-    /// 获取交易历史
-    pub fn get_trades(&self, symbol: Option<&Symbol>, limit: Option<usize>) -> Vec<Trade> {
-        let trades = self.trades.read().unwrap();
-        let mut filtered_trades: Vec<Trade> = trades
-            .iter()
-            .filter(|trade| {
-                if let Some(sym) = symbol {
-                    trade.symbol == *sym
-                } else {
-                    true
-                }
-            })
-            .cloned()
-            .collect();
+    /// 撤销（bust）一笔已执行的成交（管理接口）
+    ///
+    /// 从成交历史、用户成交和持仓中移除该成交并回退成交统计，双方订单的已成交数量相应减少，
+    /// 随后广播 [`TradeBust`] 和双方订单的更新。`restore_quantity` 为 true 时，被撤销的数量恢复为
+    /// 挂单方的剩余数量：仍在订单簿中的挂单保留时间优先级，因该成交完全成交的挂单按原价重新挂入订单簿，
+    /// 但会与对手盘交叉或已到期时不恢复。未恢复的数量视为撤销：仍在订单簿中的挂单缩减订单总量，
+    /// 已离开订单簿的订单计入剩余数量，完全成交的订单改为已撤销
+    #[instrument(name = "bust_trade", skip(self, reason), fields(symbol = tracing::field::Empty))]
+    pub async fn bust_trade(
+        &self,
+        trade_id: TradeId,
+        reason: String,
+        restore_quantity: bool,
+    ) -> Result<TradeBust, String> {
+        info!(
+            "Busting trade {} (restore quantity: {}): {}",
+            trade_id, restore_quantity, reason
+        );
+        let _in_flight = self.enter_order_entry().await;
+        if self.is_standby() {
+            return Err(STANDBY_REJECT_REASON.to_string());
+        }
+
+        let symbol = self
+            .trades
+            .read()
+            .get(trade_id)
+            .map(|trade| trade.symbol)
+            .ok_or_else(|| format!("Trade {} not found", trade_id))?;
+        Span::current().record("symbol", tracing::field::display(&symbol));
 
-        // 按时间倒序排列（最新的在前）
-        filtered_trades.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        // 持有撮合锁期间移除成交并回退双方订单，同一成交不会被并发撤销两次
+        let matching = self.lock_matching(&symbol).await;
+        let trade = self
+            .remove_trade(trade_id)
+            .ok_or_else(|| format!("Trade {} not found", trade_id))?;
 
-        if let Some(limit) = limit {
-            filtered_trades.truncate(limit);
+        let roles = self.reverse_trade(&trade);
+        // 场外成交没有对应的订单，无需回退
+        let order_ids = match trade.trade_type {
+            TradeType::Regular => vec![trade.buy_order_id, trade.sell_order_id],
+            TradeType::Otc => Vec::new(),
+        };
+
+        let mut restored_order_ids = Vec::new();
+        for (order_id, role) in order_ids.into_iter().zip(roles) {
+            match self.unwind_order_fill(&trade, order_id, role, restore_quantity, &reason) {
+                Ok(true) => restored_order_ids.push(order_id),
+                Ok(false) => {}
+                Err(e) => warn!(
+                    "Failed to unwind order {} of busted trade {}: {}",
+                    order_id, trade.id, e
+                ),
+            }
         }
+        drop(matching);
 
-        filtered_trades
-    }
+        let bust = TradeBust {
+            trade_id: trade.id,
+            trade,
+            reason,
+            restored_order_ids,
+            timestamp: self.clock.now(),
+        };
+        self.publish_trade_bust(bust.clone()).await;
 
-    /// 获取交易广播接收器
-    pub fn subscribe_trades(&self) -> broadcast::Receiver<Trade> {
-        self.trade_sender.subscribe()
+        info!("Trade {} busted successfully", trade_id);
+        Ok(bust)
     }
 
-    /// 获取订单更新广播接收器
-    pub fn subscribe_orders(&self) -> broadcast::Receiver<Order> {
-        self.order_sender.subscribe()
+    /// 是否启用场外成交申报
+    pub fn is_otc_reporting_enabled(&self) -> bool {
+        self.config.otc.enabled
     }
 
-    /// 获取市场数据广播接收器
-    pub fn subscribe_market_data(&self) -> broadcast::Receiver<MarketData> {
-        self.market_data_sender.subscribe()
-    }
+    /// 申报双方协商的场外成交
+    ///
+    /// 校验交易对状态、价格精度和价格带后分配成交ID和申报序号，更新双方持仓和统计，
+    /// 在单独的场外成交通道广播。默认不作为最新成交价，不触发条件单
+    pub async fn report_trade(&self, request: ReportTradeRequest) -> Result<TradeReport, String> {
+        if !self.config.otc.enabled {
+            return Err("OTC trade reporting is not enabled".to_string());
+        }
+        let _in_flight = self.enter_order_entry().await;
+        if self.is_standby() {
+            return Err(STANDBY_REJECT_REASON.to_string());
+        }
 
-    /// 验证订单
-    fn validate_order(&self, order: &Order) -> Result<(), String> {
-        if order.quantity <= 0.0 {
-            return Err("Order quantity must be positive".to_string());
+        let symbol = request.symbol;
+        self.check_listing(&symbol, Some(request.quantity), Some(request.price))?;
+        if self.get_trading_state(&symbol) == TradingState::Halted {
+            return Err(format!(
+                "Trading is halted for {}, trade reports are not accepted",
+                symbol
+            ));
+        }
+        check_quantity(request.quantity).map_err(|e| format!("Quantity {}", e))?;
+        check_price(request.price).map_err(|e| format!("Price {}", e))?;
+        self.price_scale(&symbol).to_key(request.price)?;
+        if request.buyer_id.is_empty() || request.seller_id.is_empty() {
+            return Err("Buyer and seller cannot be empty".to_string());
+        }
+        if request.buyer_id == request.seller_id {
+            return Err("Buyer and seller must be different users".to_string());
         }
 
-        if order.order_type == OrderType::Limit {
-            if let Some(price) = order.price {
-                if price <= 0.0 {
-                    return Err("Limit order price must be positive".to_string());
-                }
-            } else {
-                return Err("Limit order must have a price".to_string());
-            }
+        let reference_price = self
+            .reference_price(&symbol, PriceReference::Mark)
+            .ok_or_else(|| format!("No reference price for {}", symbol))?;
+        let deviation_pct = (request.price - reference_price).abs() / reference_price * 100.0;
+        if deviation_pct > self.config.otc.price_band_pct {
+            return Err(format!(
+                "Price {} is outside the {}% band around reference price {}",
+                request.price, self.config.otc.price_band_pct, reference_price
+            ));
         }
 
-        if order.user_id.is_empty() {
-            return Err("User ID cannot be empty".to_string());
+        let trade = Trade {
+            id: self.ids.next_id(),
+            symbol,
+            buy_order_id: 0,
+            sell_order_id: 0,
+            quantity: request.quantity,
+            price: request.price,
+            timestamp: self.now(),
+            buyer_id: request.buyer_id,
+            seller_id: request.seller_id,
+            trade_type: TradeType::Otc,
+            taker_side: None,
+            buy_tag: None,
+            sell_tag: None,
+        };
+        self.store_trade(&trade, None);
+        let sequence = self.next_otc_sequence(&symbol);
+        info!(
+            "Reported OTC trade {} #{} for {}: {} @ {}",
+            trade.id, sequence, symbol, trade.quantity, trade.price
+        );
+        self.publish_otc_trade(trade.clone());
+
+        // 持仓变化后重新校验双方的只减仓挂单
+        let trades = std::slice::from_ref(&trade);
+        self.reevaluate_reduce_only_for_trades(&symbol, trades)
+            .await;
+        if self.config.otc.update_last_price {
+            self.process_triggers(&symbol, trades).await;
         }
+        self.mark_market_data_stale(&symbol);
 
-        Ok(())
+        Ok(TradeReport { trade, sequence })
     }
 
-    /// 获取或创建订单簿
-    fn get_or_create_orderbook(&self, symbol: &Symbol) -> SafeOrderBook {
-        let mut orderbooks = self.orderbooks.write().unwrap();
-        if !orderbooks.contains_key(symbol) {
-            orderbooks.insert(symbol.clone(), SafeOrderBook::new(symbol.clone()));
+    /// 回退成交统计，移除双方的用户成交并回退持仓，返回买方和卖方在该成交中的角色
+    fn reverse_trade(&self, trade: &Trade) -> [Option<LiquidityRole>; 2] {
+        if self.counts_in_volume(trade) {
+            if let Some(counters) = self.symbol_counters.write().get_mut(&trade.symbol.id()) {
+                counters.total_trades = counters.total_trades.saturating_sub(1);
+                counters.volume -= trade.quantity;
+                counters.quote_volume -= trade.quantity * trade.price;
+            }
+            for user_id in [&trade.buyer_id, &trade.seller_id] {
+                self.fee_schedule
+                    .record(user_id, -trade.quantity * trade.price, trade.timestamp);
+            }
+            let mut stats = self.stats.write();
+            stats.total_trades = stats.total_trades.saturating_sub(1);
+            stats.total_volume -= trade.quantity * trade.price;
+        }
+
+        let fills = {
+            let mut fills = self.fills.write();
+            [
+                (trade.buy_order_id, trade.buyer_id.as_str()),
+                (trade.sell_order_id, trade.seller_id.as_str()),
+            ]
+            .map(|(order_id, user_id)| fills.remove(user_id, trade.id, order_id))
+        };
+        if let Some(fee_ledger) = &self.fee_ledger {
+            for (user_id, fill) in [&trade.buyer_id, &trade.seller_id].into_iter().zip(&fills) {
+                if let Some(fill) = fill {
+                    fee_ledger.reverse_fill(user_id, fill);
+                }
+            }
         }
-        orderbooks.get(symbol).unwrap().clone()
+        if self.counts_in_volume(trade) {
+            self.volume_stats.reverse(trade);
+        }
+        self.positions.reverse_trade(trade);
+        if let Some(sandbox) = &self.sandbox {
+            let fees = fills
+                .each_ref()
+                .map(|fill| fill.as_ref().map_or(0.0, |fill| fill.fee));
+            sandbox.reverse_trade(&self.accounts, trade, fees);
+        }
+        fills.map(|fill| fill.map(|fill| fill.role))
     }
 
-    /// 获取订单簿
-    fn get_orderbook(&self, symbol: &Symbol) -> Option<SafeOrderBook> {
-        self.orderbooks.read().unwrap().get(symbol).cloned()
+    /// 广播成交撤销并刷新市场数据
+    async fn publish_trade_bust(&self, bust: TradeBust) {
+        let symbol = bust.trade.symbol;
+        self.event_bus
+            .publish(EngineEvent::TradeBust(bust), self.now());
+
+        self.mark_market_data_stale(&symbol);
     }
 
-    /// 撮合订单
-    async fn match_order(
+    /// 撤销订单在被撤销成交中的成交数量，返回被撤销的数量是否恢复为可成交的剩余数量
+    ///
+    /// 调用方须持有订单簿的撮合锁
+    fn unwind_order_fill(
         &self,
-        orderbook: &SafeOrderBook,
+        trade: &Trade,
+        order_id: OrderId,
+        role: Option<LiquidityRole>,
+        restore: bool,
+        reason: &str,
+    ) -> Result<bool, String> {
+        let order = self
+            .get_order(order_id)
+            .ok_or_else(|| format!("Order {} not found", order_id))?;
+        let orderbook = self.get_orderbook(&order.symbol);
+        let resting = orderbook
+            .as_ref()
+            .filter(|orderbook| orderbook.contains_order(order_id));
+
+        let (unwound, restored) = if let Some(orderbook) = resting {
+            (
+                orderbook.unwind_fill(order_id, trade.quantity, restore)?,
+                restore,
+            )
+        } else if restore
+            && role == Some(LiquidityRole::Maker)
+            && order.status == OrderStatus::Filled
+            && order
+                .expires_at
+                .is_none_or(|expires_at| expires_at > self.clock.now())
+            && !self.would_cross(&order)
+        {
+            let mut reinstated = order.clone();
+            reinstated.filled_quantity = (order.filled_quantity - trade.quantity).max(0.0);
+            reinstated.remaining_quantity = trade.quantity;
+            reinstated.status = if reinstated.filled_quantity > 0.0 {
+                OrderStatus::PartiallyFilled
+            } else {
+                OrderStatus::New
+            };
+            self.get_or_create_orderbook(&order.symbol)
+                .add_order(reinstated.clone())?;
+            if let Some(expires_at) = reinstated.expires_at {
+                self.schedule_expiry(order_id, expires_at);
+            }
+            self.stats.write().active_orders += 1;
+            (reinstated, true)
+        } else {
+            // 已离开订单簿的订单不再成交，被撤销的数量计入剩余数量
+            let mut unwound = order.clone();
+            unwound.filled_quantity = (order.filled_quantity - trade.quantity).max(0.0);
+            unwound.remaining_quantity += trade.quantity;
+            if unwound.status == OrderStatus::Filled {
+                unwound.status = OrderStatus::Cancelled;
+            }
+            (unwound, false)
+        };
+
+        self.orders.write().insert(order_id, unwound.clone());
+        self.terminal_since.write().remove(&order_id);
+        self.audit(
+            order_id,
+            AuditEventKind::FillBusted {
+                trade_id: trade.id,
+                quantity: trade.quantity,
+                restored,
+                reason: reason.to_string(),
+            },
+        );
+        self.publish_order(unwound);
+        Ok(restored)
+    }
+
+    /// 限价单按原价挂入订单簿是否会与对手盘交叉
+    fn would_cross(&self, order: &Order) -> bool {
+        let (Some(price), Some(orderbook)) = (order.price, self.get_orderbook(&order.symbol))
+        else {
+            return true;
+        };
+        match order.side {
+            OrderSide::Buy => orderbook.best_ask().is_some_and(|ask| ask <= price),
+            OrderSide::Sell => orderbook.best_bid().is_some_and(|bid| bid >= price),
+        }
+    }
+
+    /// 撤销所有在 `now` 之前到期的 GTD 挂单，返回被撤销的订单
+    ///
+    /// 到期订单按交易对分组，每个交易对持有订单簿的撮合锁期间逐个撤销
+    pub async fn expire_orders(&self, now: DateTime<Utc>) -> Vec<Order> {
+        let due: Vec<OrderId> = {
+            let mut queue = self.expiry_queue.write();
+            let mut due = Vec::new();
+            while let Some(&(expires_at, order_id)) = queue.iter().next() {
+                if expires_at > now {
+                    break;
+                }
+                queue.remove(&(expires_at, order_id));
+                due.push(order_id);
+            }
+            due
+        };
+        // 备用实例的订单到期由主实例的到期事件驱动，提升为主实例时重新安排到期
+        if self.is_standby() {
+            return Vec::new();
+        }
+
+        let mut by_symbol: Vec<(Symbol, Vec<OrderId>)> = Vec::new();
+        for order in self.get_orders(&due) {
+            match by_symbol
+                .iter_mut()
+                .find(|(symbol, _)| *symbol == order.symbol)
+            {
+                Some((_, order_ids)) => order_ids.push(order.id),
+                None => by_symbol.push((order.symbol, vec![order.id])),
+            }
+        }
+
+        let mut expired = Vec::new();
+        for (symbol, order_ids) in by_symbol {
+            let _matching = self.lock_matching(&symbol).await;
+            for order_id in order_ids {
+                // 已成交或已撤销的订单在队列中惰性跳过
+                let order = match self.get_order(order_id) {
+                    Some(order) if !order.status.is_terminal() => order,
+                    _ => continue,
+                };
+
+                let mut expired_order = match self.remove_open_order(&order) {
+                    Ok(order) => order,
+                    Err(e) => {
+                        warn!("Failed to expire order {}: {}", order_id, e);
+                        continue;
+                    }
+                };
+                expired_order.status = OrderStatus::Expired;
+
+                {
+                    let mut orders = self.orders.write();
+                    orders.insert(order_id, expired_order.clone());
+                }
+                self.audit(order_id, AuditEventKind::Expired);
+
+                {
+                    let mut stats = self.stats.write();
+                    stats.active_orders = stats.active_orders.saturating_sub(1);
+                }
+
+                self.publish_order(expired_order.clone());
+                info!("Order {} expired", order_id);
+                expired.push(expired_order);
+            }
+        }
+
+        expired
+    }
+
+    /// 启动 GTD 订单到期调度任务
+    ///
+    /// 任务休眠到队列中最早的到期时间，新加入更早的到期时间时会被提前唤醒
+    pub fn start_expiry_scheduler(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let engine = Arc::clone(self);
+
+        tokio::spawn(async move {
+            loop {
+                let next_expiry = engine
+                    .expiry_queue
+                    .read()
+                    .iter()
+                    .next()
+                    .map(|&(expires_at, _)| expires_at);
+
+                match next_expiry {
+                    Some(expires_at) => {
+                        tokio::select! {
+                            _ = engine.clock.sleep_until(expires_at) => {}
+                            _ = engine.expiry_notify.notified() => continue,
+                        }
+                    }
+                    None => {
+                        engine.expiry_notify.notified().await;
+                        continue;
+                    }
+                }
+
+                engine.expire_orders(engine.clock.now()).await;
+            }
+        })
+    }
+
+    /// 将超过保留时间的终态订单写入归档并移出内存，返回归档数量
+    pub fn archive_terminal_orders(&self) -> usize {
+        let retention = Duration::from_secs(self.config.order_retention.retention_seconds);
+        self.archive_terminal_orders_after(retention)
+    }
+
+    fn archive_terminal_orders_after(&self, retention: Duration) -> usize {
+        let Some(archive) = &self.archive else {
+            return 0;
+        };
+
+        let now = self.clock.instant();
+        let due: Vec<Order> = {
+            let orders = self.orders.read();
+            let mut terminal_since = self.terminal_since.write();
+            orders
+                .values()
+                .filter(|order| order.status.is_terminal())
+                .filter(|order| {
+                    let since = *terminal_since.entry(order.id).or_insert(now);
+                    now.duration_since(since) >= retention
+                })
+                .cloned()
+                .collect()
+        };
+        if due.is_empty() {
+            return 0;
+        }
+
+        if let Err(e) = archive.append(&due) {
+            warn!("Failed to archive {} orders: {}", due.len(), e);
+            return 0;
+        }
+
+        {
+            let mut orders = self.orders.write();
+            let mut terminal_since = self.terminal_since.write();
+            let mut external_ids = self.external_ids.write();
+            for order in &due {
+                orders.remove(&order.id);
+                terminal_since.remove(&order.id);
+                if let Some(external_id) = order.external_id {
+                    external_ids.remove(&external_id);
+                }
+            }
+        }
+
+        info!("Archived {} terminal orders", due.len());
+        due.len()
+    }
+
+    /// 开始停机：此后提交的新订单一律拒绝，撤单仍然受理
+    pub fn begin_shutdown(&self) {
+        if !self.shutting_down.swap(true, Ordering::SeqCst) {
+            info!("Matching engine stopped accepting new orders");
+        }
+    }
+
+    /// 是否已开始停机
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    /// 等待进行中的下单和撤单完成，超时返回 false
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        let drained = async {
+            loop {
+                // 先注册等待再检查计数，避免错过计数归零时的唤醒
+                let notified = self.drained.notified();
+                if self.in_flight.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+                notified.await;
+            }
+        };
+        tokio::time::timeout(timeout, drained).await.is_ok()
+    }
+
+    /// 归档全部终态订单并将归档、成交历史和审计文件同步到磁盘
+    pub fn flush_persistence(&self) -> Result<(), String> {
+        self.archive_terminal_orders_after(Duration::ZERO);
+        if let Some(archive) = &self.archive {
+            archive.flush()?;
+        }
+        if let Some(history) = &self.trade_history {
+            history.flush()?;
+        }
+        if let Some(audit) = &self.audit {
+            audit.flush()?;
+        }
+        Ok(())
+    }
+
+    /// 当前未完成订单、统计和市场数据的快照
+    pub fn snapshot(&self) -> EngineSnapshot {
+        let open_orders: Vec<Order> = self.orders.read().open_orders().cloned().collect();
+        // 未触发的跟踪止损单取触发簿中的最新触发价
+        let mut open_orders: Vec<Order> = open_orders
+            .into_iter()
+            .map(|order| self.with_pending_trigger(order))
+            .collect();
+        open_orders.sort_by_key(|order| order.id);
+
+        EngineSnapshot {
+            timestamp: self.clock.now(),
+            stats: self.get_stats(),
+            open_orders,
+            market_data: self.market_data.read().values().cloned().collect(),
+        }
+    }
+
+    /// 将快照以 JSON 写入文件，先写临时文件再重命名，避免留下不完整的快照
+    pub fn write_snapshot(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create snapshot dir {}: {}", dir.display(), e))?;
+        }
+
+        let snapshot = self.snapshot();
+        let json = serde_json::to_vec_pretty(&snapshot)
+            .map_err(|e| format!("Failed to serialize snapshot: {}", e))?;
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, json)
+            .and_then(|_| std::fs::rename(&tmp_path, path))
+            .map_err(|e| format!("Failed to write snapshot {}: {}", path.display(), e))?;
+
+        info!(
+            "Wrote snapshot with {} open orders to {}",
+            snapshot.open_orders.len(),
+            path.display()
+        );
+        Ok(())
+    }
+
+    /// 优雅停机：停止接单，等待进行中的撮合完成，刷新持久化数据，按配置写出最终快照
+    pub async fn shutdown(&self) -> Result<(), String> {
+        self.begin_shutdown();
+
+        let timeout = Duration::from_secs(self.config.shutdown.drain_timeout_seconds);
+        if !self.drain(timeout).await {
+            warn!(
+                "{} operations still in flight after {:?}",
+                self.in_flight.load(Ordering::SeqCst),
+                timeout
+            );
+        }
+
+        self.flush_persistence()?;
+
+        if let Some(path) = &self.config.shutdown.snapshot_path {
+            self.write_snapshot(path)?;
+        }
+
+        info!("Matching engine shut down");
+        Ok(())
+    }
+
+    fn enter_in_flight(&self) -> InFlightGuard<'_> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard { engine: self }
+    }
+
+    /// 登记会改变订单簿的操作，生成复制快照期间等待恢复后再登记
+    async fn enter_order_entry(&self) -> InFlightGuard<'_> {
+        loop {
+            // 先注册等待再检查暂停标志，避免错过恢复时的唤醒
+            let resumed = self.resumed.notified();
+            let guard = self.enter_in_flight();
+            if !self.paused.load(Ordering::SeqCst) {
+                return guard;
+            }
+            drop(guard);
+            resumed.await;
+        }
+    }
+
+    /// 启动终态订单归档任务，未启用归档时返回 None
+    pub fn start_order_archiver(self: &Arc<Self>) -> Option<tokio::task::JoinHandle<()>> {
+        self.archive.as_ref()?;
+
+        let engine = Arc::clone(self);
+        let retention_seconds = self.config.order_retention.retention_seconds;
+        let interval = Duration::from_secs((retention_seconds / 2).clamp(1, 60));
+
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                engine.archive_terminal_orders();
+            }
+        }))
+    }
+
+    /// 启动维持保证金监控任务，未启用保证金模式时返回 None；启用强平时同时强平待强平账户
+    pub fn start_margin_monitor(self: &Arc<Self>) -> Option<tokio::task::JoinHandle<()>> {
+        let interval = self.margin.as_ref()?.check_interval();
+
+        let engine = Arc::clone(self);
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                engine.run_liquidations().await;
+            }
+        }))
+    }
+
+    /// 执行一次维持保证金监控，启用强平时依次强平待强平账户，返回本次的强平事件
+    ///
+    /// 备用实例只监控不强平，强平单由主实例提交后复制过来
+    pub async fn run_liquidations(&self) -> Vec<LiquidationEvent> {
+        let candidates = self.check_margins();
+        if self.liquidator.is_none() || self.is_standby() {
+            return Vec::new();
+        }
+
+        let mut events = Vec::new();
+        for account in candidates {
+            match self.liquidate_account(&account.user_id).await {
+                Ok(event) => events.push(event),
+                Err(e) => warn!("Failed to liquidate account {}: {}", account.user_id, e),
+            }
+        }
+        events
+    }
+
+    /// 强平低于维持保证金的账户
+    ///
+    /// 撤销全部挂单后，对每个持仓按标记价格价格带边界提交只减仓限价单，未成交部分随即撤销。
+    /// 平仓后权益为正时收取强平手续费转入保险基金；持仓全部平掉后权益仍为负时由保险基金补足，
+    /// 尚有持仓未平时穿仓亏损留待后续强平后结算
+    pub async fn liquidate_account(&self, user_id: &str) -> Result<LiquidationEvent, String> {
+        let liquidator = self
+            .liquidator
+            .as_ref()
+            .ok_or_else(|| "Liquidation is not enabled".to_string())?;
+        let margin = self
+            .margin
+            .as_ref()
+            .ok_or_else(|| "Margin mode is not enabled".to_string())?;
+        if self.is_standby() {
+            return Err(STANDBY_REJECT_REASON.to_string());
+        }
+
+        let account = self
+            .get_margin_account(user_id)
+            .ok_or_else(|| "Margin mode is not enabled".to_string())?;
+        if !account.liquidation {
+            return Err(format!("Account {} is above maintenance margin", user_id));
+        }
+        warn!(
+            "Liquidating account {}: equity {}, maintenance margin {}",
+            user_id, account.equity, account.maintenance_margin
+        );
+
+        // 撤销全部挂单，释放其占用的保证金
+        let mut cancelled_orders = Vec::new();
+        {
+            let _in_flight = self.enter_order_entry().await;
+            for order in self.get_open_user_orders(user_id) {
+                // 持有撮合锁后重新读取订单，等锁期间已成交或撤销的订单跳过
+                let _matching = self.lock_matching(&order.symbol).await;
+                let Some(order) = self
+                    .get_order(order.id)
+                    .filter(|order| !order.status.is_terminal())
+                else {
+                    continue;
+                };
+                match self.remove_open_order(&order) {
+                    Ok(removed) => {
+                        self.close_order(
+                            removed,
+                            OrderStatus::Cancelled,
+                            "Liquidation".to_string(),
+                        );
+                        cancelled_orders.push(order.id);
+                    }
+                    Err(e) => warn!("Failed to cancel order {} on liquidation: {}", order.id, e),
+                }
+            }
+        }
+
+        let mut orders = Vec::new();
+        let mut closed_notional = 0.0;
+        let open_positions = || {
+            self.positions
+                .get_user_positions(user_id)
+                .into_iter()
+                .filter(|position| position.quantity.abs() > QUANTITY_EPSILON)
+                .collect::<Vec<_>>()
+        };
+        for position in open_positions() {
+            let Some(mark_price) = self.reference_price(&position.symbol, PriceReference::Mark)
+            else {
+                warn!(
+                    "No mark price for {}, skipping liquidation of {}",
+                    position.symbol, user_id
+                );
+                continue;
+            };
+            let (side, price) = liquidator.order_price(
+                position.quantity,
+                mark_price,
+                &self.price_scale(&position.symbol),
+            );
+            let mut order = Order::new(
+                position.symbol,
+                side,
+                OrderType::Limit,
+                position.quantity.abs(),
+                Some(price),
+                user_id.to_string(),
+            )
+            .reduce_only();
+            order.id = self.ids.next_id();
+            order.timestamp = self.clock.now();
+            let order_id = order.id;
+            self.audit(
+                order_id,
+                AuditEventKind::Liquidation {
+                    equity: account.equity,
+                    maintenance_margin: account.maintenance_margin,
+                },
+            );
+
+            let result = self.submit_order(order).await;
+            if let Ok(trades) = &result {
+                closed_notional += trades
+                    .iter()
+                    .map(|trade| trade.price * trade.quantity)
+                    .sum::<f64>();
+            }
+
+            // 价格带内未成交的部分撤销，不在订单簿上挂单
+            let mut filled_quantity = 0.0;
+            {
+                let _in_flight = self.enter_order_entry().await;
+                let _matching = self.lock_matching(&position.symbol).await;
+                if let Some(order) = self.get_order(order_id) {
+                    filled_quantity = order.filled_quantity;
+                    if !order.status.is_terminal() {
+                        match self.remove_open_order(&order) {
+                            Ok(removed) => self.close_order(
+                                removed,
+                                OrderStatus::Cancelled,
+                                "Unfilled liquidation remainder".to_string(),
+                            ),
+                            Err(e) => {
+                                warn!("Failed to cancel liquidation order {}: {}", order_id, e)
+                            }
+                        }
+                    }
+                }
+            }
+
+            orders.push(LiquidationOrder {
+                order_id,
+                symbol: position.symbol,
+                side,
+                quantity: position.quantity.abs(),
+                price,
+                filled_quantity,
+                error: result.err(),
+            });
+        }
+
+        let equity = self
+            .get_margin_account(user_id)
+            .map_or(account.equity, |account| account.equity);
+        let flat = open_positions().is_empty();
+        let id = Uuid::new_v4();
+        let (liquidation_fee, insurance_fund_payout) = if equity < 0.0 && !flat {
+            (0.0, 0.0)
+        } else {
+            let (fee, payout, postings) =
+                liquidator.settlement(user_id, margin.collateral_asset(), equity, closed_notional);
+            self.accounts
+                .settle(&format!("liquidation-{}", id), &postings);
+            (fee, payout)
+        };
+
+        counter!("matching_engine_liquidations_total").increment(1);
+        if insurance_fund_payout > 0.0 {
+            counter!("matching_engine_insurance_fund_payouts_total").increment(1);
+        }
+        let event = LiquidationEvent {
+            id,
+            user_id: user_id.to_string(),
+            equity: account.equity,
+            maintenance_margin: account.maintenance_margin,
+            cancelled_orders,
+            orders,
+            liquidation_fee,
+            insurance_fund_payout,
+            remaining_equity: equity - liquidation_fee + insurance_fund_payout,
+            timestamp: self.now(),
+        };
+        info!(
+            "Liquidated account {}: fee {}, insurance fund payout {}",
+            user_id, liquidation_fee, insurance_fund_payout
+        );
+        liquidator.record(event.clone());
+        self.event_bus
+            .publish(EngineEvent::Liquidation(event.clone()), event.timestamp);
+        Ok(event)
+    }
+
+    /// 启动资金费结算任务，在每个结算时间结算资金费，未启用资金费率或结算间隔为 0 时返回 None
+    pub fn start_funding_scheduler(self: &Arc<Self>) -> Option<tokio::task::JoinHandle<()>> {
+        let mut settlement = self.funding.as_ref()?.next_settlement(self.now())?;
+
+        let engine = Arc::clone(self);
+        Some(tokio::spawn(async move {
+            loop {
+                engine.clock.sleep_until(settlement).await;
+                engine.settle_funding(settlement);
+                match engine
+                    .funding
+                    .as_ref()
+                    .and_then(|funding| funding.next_settlement(settlement))
+                {
+                    Some(next) => settlement = next,
+                    None => break,
+                }
+            }
+        }))
+    }
+
+    /// 启动手续费结算任务，在每个结算时间结算手续费收入和返佣，未启用手续费结算或结算间隔为 0 时返回 None
+    pub fn start_fee_settlement_scheduler(self: &Arc<Self>) -> Option<tokio::task::JoinHandle<()>> {
+        let mut settlement = self.fee_ledger.as_ref()?.next_settlement(self.now())?;
+
+        let engine = Arc::clone(self);
+        Some(tokio::spawn(async move {
+            loop {
+                engine.clock.sleep_until(settlement).await;
+                engine.settle_fees(settlement);
+                match engine
+                    .fee_ledger
+                    .as_ref()
+                    .and_then(|fee_ledger| fee_ledger.next_settlement(settlement))
+                {
+                    Some(next) => settlement = next,
+                    None => break,
+                }
+            }
+        }))
+    }
+
+    /// 结束当期手续费结算周期，把收入扣除返佣后的净额记入手续费收入账户
+    ///
+    /// 备用实例只开始新周期，不记账，手续费由主实例结算；未启用手续费结算时为空
+    pub fn settle_fees(&self, period_end: DateTime<Utc>) -> Option<FeeSettlement> {
+        let fee_ledger = self.fee_ledger.as_ref()?;
+        let (settlement, postings) = fee_ledger.settle(period_end);
+        if self.is_standby() {
+            return Some(settlement);
+        }
+        self.accounts.settle(
+            &format!("fee-settlement-{}", period_end.timestamp()),
+            &postings,
+        );
+        for revenue in &settlement.assets {
+            let pool = self
+                .accounts
+                .get_balance(fee_ledger.fee_account(), &revenue.asset);
+            if pool.available < 0.0 {
+                warn!(
+                    "Rebate pool {} is negative after fee settlement: {} {}",
+                    fee_ledger.fee_account(),
+                    pool.available,
+                    revenue.asset
+                );
+            }
+        }
+        info!(
+            "Settled fees for {} assets, {} rebated users",
+            settlement.assets.len(),
+            settlement.rebated_users
+        );
+        Some(settlement)
+    }
+
+    /// 手续费收入报表：当期累计、返佣池余额和最近的结算；未启用手续费结算时为空
+    pub fn get_fee_revenue(&self, limit: Option<usize>) -> Option<FeeRevenueReport> {
+        let fee_ledger = self.fee_ledger.as_ref()?;
+        Some(FeeRevenueReport {
+            fee_account: fee_ledger.fee_account().to_string(),
+            current_period: fee_ledger.current_period(self.now()),
+            rebate_pool: self.accounts.get_balances(fee_ledger.fee_account()),
+            settlements: fee_ledger.history(limit),
+        })
+    }
+
+    /// 用户的当期应计返佣和已结算返佣，未启用手续费结算时为空
+    pub fn get_user_rebates(&self, user_id: &str) -> Option<UserRebates> {
+        Some(self.fee_ledger.as_ref()?.user_rebates(user_id, self.now()))
+    }
+
+    /// 启动日终结算任务，在每天的截止时间生成并保存上一交易日的结算报表，未启用日终结算时返回 None
+    pub fn start_end_of_day_scheduler(self: &Arc<Self>) -> Option<tokio::task::JoinHandle<()>> {
+        let mut cutoff = self.end_of_day.as_ref()?.next_cutoff(self.now());
+
+        let engine = Arc::clone(self);
+        Some(tokio::spawn(async move {
+            loop {
+                engine.clock.sleep_until(cutoff).await;
+                // 备用实例不生成报表，由主实例结算
+                if !engine.is_standby() {
+                    if let Err(e) = engine.run_end_of_day(cutoff) {
+                        error!("End-of-day settlement failed: {}", e);
+                    }
+                }
+                if let Some(end_of_day) = &engine.end_of_day {
+                    cutoff = end_of_day.next_cutoff(cutoff);
+                }
+            }
+        }))
+    }
+
+    /// 生成截止时间 `cutoff` 之前一个交易日的结算报表并保存，同一交易日已有的报表被覆盖
+    ///
+    /// 成交和手续费取 [`cutoff` − 24 小时, `cutoff`) 内的成交；余额、持仓和未平仓量取当前状态
+    pub fn run_end_of_day(&self, cutoff: DateTime<Utc>) -> Result<SettlementReport, String> {
+        let end_of_day = self
+            .end_of_day
+            .as_ref()
+            .ok_or("End-of-day settlement is not enabled")?;
+
+        let filter = TradeFilter {
+            start_time: Some(cutoff - chrono::Duration::days(1)),
+            end_time: Some(cutoff),
+            ..TradeFilter::default()
+        };
+        let page = PageRequest {
+            cursor: None,
+            limit: usize::MAX,
+        };
+        let trades = self.query_trades(&filter, page).items;
+        let fills = {
+            let fills = self.fills.read();
+            trades
+                .iter()
+                .flat_map(|trade| {
+                    [
+                        (&trade.buyer_id, trade.buy_order_id),
+                        (&trade.seller_id, trade.sell_order_id),
+                    ]
+                    .map(|(user_id, order_id)| {
+                        let fill = fills.get(user_id, trade.id, order_id)?;
+                        Some((user_id.clone(), fill.clone()))
+                    })
+                })
+                .flatten()
+                .collect()
+        };
+        let balances = self.accounts.all_balances();
+        let positions: Vec<Position> = self
+            .positions
+            .users()
+            .iter()
+            .flat_map(|user_id| self.get_user_positions(user_id))
+            .collect();
+        let symbols = trades
+            .iter()
+            .map(|trade| trade.symbol)
+            .chain(positions.iter().map(|position| position.symbol))
+            .map(|symbol| {
+                let price = self.reference_price(&symbol, PriceReference::Mark);
+                (symbol, (price, self.positions.open_interest(&symbol)))
+            })
+            .collect();
+
+        let snapshot = SettlementSnapshot {
+            trades,
+            fills,
+            balances,
+            positions,
+            symbols,
+        };
+        let report = end_of_day.build_report(cutoff, snapshot, self.now());
+        end_of_day.save(&report)?;
+        info!(
+            "Settled trading day {}: {} trades, {} users",
+            report.trading_day, report.venue.total_trades, report.venue.users
+        );
+        Ok(report)
+    }
+
+    /// 已生成结算报表的交易日，最新的在前；未启用日终结算时为空
+    pub fn get_settlement_days(&self) -> Result<Option<Vec<NaiveDate>>, String> {
+        self.end_of_day
+            .as_ref()
+            .map(EndOfDay::trading_days)
+            .transpose()
+    }
+
+    /// 交易日的结算报表；未启用日终结算或报表不存在时为空
+    pub fn get_settlement_report(
+        &self,
+        trading_day: NaiveDate,
+    ) -> Result<Option<SettlementReport>, String> {
+        match &self.end_of_day {
+            Some(end_of_day) => end_of_day.load(trading_day),
+            None => Ok(None),
+        }
+    }
+
+    /// 交易日结算报表的 CSV 文件（`symbols.csv` 或 `users.csv`）；未启用日终结算或报表不存在时为空
+    pub fn get_settlement_csv(
+        &self,
+        trading_day: NaiveDate,
+        name: &str,
+    ) -> Result<Option<String>, String> {
+        match &self.end_of_day {
+            Some(end_of_day) => end_of_day.load_csv(trading_day, name),
+            None => Ok(None),
+        }
+    }
+
+    /// 启动交易时段调度任务，在各交易对的时段边界切换交易状态，未启用交易时段时返回 None
+    ///
+    /// 启动时先按当前阶段设置一次状态；两次边界之间最多间隔一分钟检查一次，运行中上市的交易对也会被纳入
+    pub fn start_session_scheduler(self: &Arc<Self>) -> Option<tokio::task::JoinHandle<()>> {
+        self.sessions.as_ref()?;
+
+        let engine = Arc::clone(self);
+        Some(tokio::spawn(async move {
+            loop {
+                engine.apply_trading_sessions().await;
+                let now = engine.now();
+                let recheck_at =
+                    now + chrono::Duration::seconds(SESSION_RECHECK_INTERVAL.as_secs() as i64);
+                let wake_at = engine
+                    .session_symbols()
+                    .iter()
+                    .filter_map(|symbol| engine.sessions.as_ref()?.next_transition(symbol, now))
+                    .filter(|&next| next > now)
+                    .min()
+                    .map_or(recheck_at, |next| next.min(recheck_at));
+                engine.clock.sleep_until(wake_at).await;
+            }
+        }))
+    }
+
+    /// 按当前时间切换阶段发生变化的交易对的交易状态，返回切换后的状态
+    ///
+    /// 集合竞价和保留订单的休市阶段进入集合竞价，开盘时撮合交叉部分并恢复连续交易，
+    /// 其余休市阶段只撤单。阶段不变时不改动状态，管理接口的暂停和恢复在下一个边界前保持有效；
+    /// 备用实例的交易状态由主实例复制，不自行切换
+    pub async fn apply_trading_sessions(&self) -> Vec<SymbolStatus> {
+        let Some(sessions) = &self.sessions else {
+            return Vec::new();
+        };
+        if self.is_standby() {
+            return Vec::new();
+        }
+
+        let now = self.now();
+        let mut statuses = Vec::new();
+        for symbol in self.session_symbols() {
+            let Some(phase) = sessions.phase(&symbol, now) else {
+                continue;
+            };
+            if self.session_phases.write().insert(symbol.id(), phase) == Some(phase) {
+                continue;
+            }
+            let queue_when_closed = sessions
+                .session_for(&symbol)
+                .is_some_and(|session| session.queue_when_closed);
+            info!("Trading session for {} entered {:?}", symbol, phase);
+
+            let status = match phase {
+                SessionPhase::PreOpen => {
+                    self.start_auction(&symbol, Some("Pre-open auction".to_string()))
+                }
+                SessionPhase::Closed if queue_when_closed => self.start_auction(
+                    &symbol,
+                    Some("Session closed, orders are queued for the open".to_string()),
+                ),
+                SessionPhase::Closed => self.set_trading_state(
+                    &symbol,
+                    TradingState::CancelOnly,
+                    Some("Session closed".to_string()),
+                ),
+                SessionPhase::Open
+                    if self.get_trading_state(&symbol) == TradingState::AuctionOnly =>
+                {
+                    match self.uncross_auction(&symbol, "Session open").await {
+                        Ok((_, status)) => status,
+                        // 撮合失败时交易对已转为暂停交易
+                        Err(e) => {
+                            warn!("Failed to open trading session for {}: {}", symbol, e);
+                            continue;
+                        }
+                    }
+                }
+                // 不在集合竞价中（如休市时只撤单）直接恢复连续交易
+                SessionPhase::Open => self.set_trading_state(
+                    &symbol,
+                    TradingState::Trading,
+                    Some("Session open".to_string()),
+                ),
+            };
+            statuses.push(status);
+        }
+        statuses
+    }
+
+    /// 受交易时段管理的交易对：单独设置了时段的交易对，有默认时段时再加上已上市和已有订单簿的交易对
+    fn session_symbols(&self) -> Vec<Symbol> {
+        let Some(sessions) = &self.sessions else {
+            return Vec::new();
+        };
+        let mut symbols: BTreeSet<SymbolId> = sessions
+            .configured_symbols()
+            .iter()
+            .map(Symbol::id)
+            .collect();
+        if self.config.sessions.default.is_some() {
+            symbols.extend(
+                self.listings
+                    .read()
+                    .values()
+                    .filter(|listing| listing.status == ListingStatus::Listed)
+                    .map(|listing| listing.symbol.id()),
+            );
+            symbols.extend(self.orderbooks.read().keys().copied());
+        }
+        symbols.into_iter().map(Symbol::from).collect()
+    }
+
+    /// 交易对的交易时段和当前阶段，未启用交易时段或交易对没有时段时为空
+    pub fn get_session(&self, symbol: &Symbol) -> Option<SessionInfo> {
+        self.sessions.as_ref()?.info(symbol, self.now())
+    }
+
+    /// 按本周期的平均溢价结算各永续交易对的资金费，返回本次结算的资金费率
+    ///
+    /// 备用实例只丢弃本周期的溢价样本，不记账，资金费由主实例结算
+    pub fn settle_funding(&self, funding_time: DateTime<Utc>) -> Vec<FundingRate> {
+        let (Some(funding), Some(margin)) = (&self.funding, &self.margin) else {
+            return Vec::new();
+        };
+
+        let mut settlements = Vec::new();
+        for symbol in funding.sampled_symbols() {
+            let Some((funding_rate, premium_index)) = funding.take_rate(&symbol) else {
+                continue;
+            };
+            if self.is_standby() {
+                continue;
+            }
+            let Some(mark_price) = self.get_mark_price(&symbol) else {
+                continue;
+            };
+
+            let positions = self.positions.get_symbol_positions(&symbol);
+            let postings = funding.payments(
+                funding_rate,
+                mark_price.mark_price,
+                &positions,
+                margin.collateral_asset(),
+            );
+            self.accounts.settle(
+                &format!("funding-{}-{}", symbol, funding_time.timestamp()),
+                &postings,
+            );
+            let total_payment = positions
+                .iter()
+                .filter(|position| position.quantity > 0.0)
+                .map(|position| position.quantity * mark_price.mark_price * funding_rate)
+                .sum();
+
+            gauge!("matching_engine_funding_rate", "symbol" => symbol.to_string())
+                .set(funding_rate);
+            info!(
+                "Settled funding for {}: rate {}, {} positions",
+                symbol,
+                funding_rate,
+                positions.len()
+            );
+            let settlement = FundingRate {
+                symbol,
+                funding_rate,
+                premium_index,
+                mark_price: mark_price.mark_price,
+                index_price: mark_price.index_price,
+                positions: positions.len(),
+                total_payment,
+                funding_time,
+            };
+            funding.record(settlement.clone());
+            settlements.push(settlement);
+        }
+        settlements
+    }
+
+    /// 交易对的资金费率记录，最新的在前；未启用资金费率时为空
+    pub fn get_funding_history(
+        &self,
+        symbol: &Symbol,
+        limit: Option<usize>,
+    ) -> Option<Vec<FundingRate>> {
+        self.funding
+            .as_ref()
+            .map(|funding| funding.history(symbol, limit))
+    }
+
+    /// 最近的强平事件，最新的在前；未启用强平时为空
+    pub fn get_liquidations(&self, limit: Option<usize>) -> Option<Vec<LiquidationEvent>> {
+        self.liquidator
+            .as_ref()
+            .map(|liquidator| liquidator.history(limit))
+    }
+
+    /// 按标记价格计算全部持仓账户的保证金率，标记维持保证金不低于权益的账户并返回待强平账户
+    pub fn check_margins(&self) -> Vec<MarginAccount> {
+        let Some(margin) = &self.margin else {
+            return Vec::new();
+        };
+        let accounts = self
+            .positions
+            .users()
+            .iter()
+            .filter_map(|user_id| self.get_margin_account(user_id))
+            .collect();
+        for account in margin.update_liquidations(accounts) {
+            counter!("matching_engine_margin_liquidation_flags_total").increment(1);
+            warn!(
+                "Account {} breached maintenance margin: equity {}, maintenance margin {}",
+                account.user_id, account.equity, account.maintenance_margin
+            );
+        }
+        margin.liquidations()
+    }
+
+    /// 获取账户的保证金状况，未启用保证金模式时为空
+    pub fn get_margin_account(&self, user_id: &str) -> Option<MarginAccount> {
+        let margin = self.margin.as_ref()?;
+        let collateral = self
+            .accounts
+            .get_balance(user_id, margin.collateral_asset())
+            .available;
+        Some(margin.account(
+            user_id,
+            collateral,
+            &self.positions.get_user_positions(user_id),
+            |symbol| self.reference_price(symbol, PriceReference::Mark),
+            self.now(),
+        ))
+    }
+
+    /// 最近一次监控标记为待强平的账户，未启用保证金模式时为空
+    pub fn get_liquidation_candidates(&self) -> Option<Vec<MarginAccount>> {
+        self.margin.as_ref().map(MarginEngine::liquidations)
+    }
+
+    /// 设置交易对交易状态并广播状态变更
+    pub fn set_trading_state(
+        &self,
+        symbol: &Symbol,
+        state: TradingState,
+        reason: Option<String>,
+    ) -> SymbolStatus {
+        apply_trading_state(
+            &self.trading_states,
+            &self.event_bus,
+            self.clock.as_ref(),
+            symbol,
+            state,
+            reason,
+        )
+    }
+
+    /// 获取交易对交易状态
+    pub fn get_trading_state(&self, symbol: &Symbol) -> TradingState {
+        self.trading_states
+            .read()
+            .get(&symbol.id())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// 上市交易对并广播上市事件，已下市的交易对可以重新上市
+    pub fn list_symbol(&self, request: ListSymbolRequest) -> Result<SymbolListing, String> {
+        if self.is_standby() {
+            return Err(STANDBY_REJECT_REASON.to_string());
+        }
+        let filters = request.filters;
+        if let Some(tick_size) = filters.tick_size {
+            PriceScale::from_tick_size(tick_size)?;
+        }
+        let limits = [
+            filters.min_quantity,
+            filters.max_quantity,
+            filters.min_notional,
+        ];
+        if limits
+            .iter()
+            .flatten()
+            .any(|limit| !(limit.is_finite() && *limit > 0.0))
+        {
+            return Err("Symbol filter limits must be positive".to_string());
+        }
+        if let (Some(min), Some(max)) = (filters.min_quantity, filters.max_quantity) {
+            if min > max {
+                return Err("Minimum quantity cannot exceed maximum quantity".to_string());
+            }
+        }
+
+        let symbol = request.symbol;
+        let listing = {
+            let mut listings = self.listings.write();
+            if listings
+                .get(&symbol.id())
+                .is_some_and(|listing| listing.status == ListingStatus::Listed)
+            {
+                return Err(format!("Symbol {} is already listed", symbol));
+            }
+            let listing = SymbolListing {
+                symbol,
+                status: ListingStatus::Listed,
+                filters,
+                listed_at: self.clock.now(),
+                delisted_at: None,
+                final_stats: None,
+            };
+            listings.insert(symbol.id(), listing.clone());
+            listing
+        };
+        symbol.register_listed();
+        info!("Listed symbol {}", symbol);
+        self.event_bus
+            .publish(EngineEvent::SymbolListing(listing.clone()), self.now());
+        Ok(listing)
+    }
+
+    /// 下市交易对：先拒绝新订单，再撤销全部挂单和未触发的条件单，记录最终统计并广播下市事件
+    ///
+    /// 上市状态在持有订单簿的撮合锁后检查和修改，等锁的订单在撮合前重新检查上市状态
+    pub async fn delist_symbol(&self, symbol: &Symbol) -> Result<SymbolListing, String> {
+        let _in_flight = self.enter_order_entry().await;
+        if self.is_standby() {
+            return Err(STANDBY_REJECT_REASON.to_string());
+        }
+        let matching = self.lock_matching(symbol).await;
+        let mut listing = {
+            let mut listings = self.listings.write();
+            let now = self.clock.now();
+            let listing = listings
+                .entry(symbol.id())
+                .or_insert_with(|| SymbolListing {
+                    symbol: *symbol,
+                    status: ListingStatus::Listed,
+                    filters: SymbolFilters::default(),
+                    listed_at: now,
+                    delisted_at: None,
+                    final_stats: None,
+                });
+            if listing.status == ListingStatus::Delisted {
+                return Err(format!("Symbol {} is already delisted", symbol));
+            }
+            listing.status = ListingStatus::Delisted;
+            listing.delisted_at = Some(now);
+            listing.clone()
+        };
+
+        let open_orders: Vec<Order> = self
+            .orders
+            .read()
+            .values()
+            .filter(|order| order.symbol == *symbol && !order.status.is_terminal())
+            .cloned()
+            .collect();
+        for order in &open_orders {
+            match self.remove_open_order(order) {
+                Ok(removed) => self.close_order(
+                    removed,
+                    OrderStatus::Cancelled,
+                    "Symbol delisted".to_string(),
+                ),
+                Err(e) => warn!("Failed to cancel order {} on delisting: {}", order.id, e),
+            }
+        }
+        drop(matching);
+        self.refresh_market_data(symbol);
+
+        listing.final_stats = self.get_symbol_stats(symbol);
+        if let Some(current) = self.listings.write().get_mut(&symbol.id()) {
+            if current.status == ListingStatus::Delisted {
+                current.final_stats = listing.final_stats.clone();
+            }
+        }
+        info!(
+            "Delisted symbol {}, cancelled {} open orders",
+            symbol,
+            open_orders.len()
+        );
+        self.event_bus
+            .publish(EngineEvent::SymbolListing(listing.clone()), self.now());
+        Ok(listing)
+    }
+
+    /// 全部交易对的上市记录，按交易对排序
+    pub fn get_symbol_listings(&self) -> Vec<SymbolListing> {
+        let mut listings: Vec<SymbolListing> = self.listings.read().values().cloned().collect();
+        listings.sort_by_key(|listing| listing.symbol.to_string());
+        listings
+    }
+
+    /// 进入集合竞价：订单只挂单不撮合，直到调用 `end_auction`
+    pub fn start_auction(&self, symbol: &Symbol, reason: Option<String>) -> SymbolStatus {
+        self.get_or_create_orderbook(symbol);
+        let status = self.set_trading_state(symbol, TradingState::AuctionOnly, reason);
+        self.broadcast_auction_indicative(symbol);
+        status
+    }
+
+    /// 获取集合竞价参考价和参考成交量
+    pub fn get_auction_indicative(&self, symbol: &Symbol) -> AuctionIndicative {
+        let uncross = self
+            .get_orderbook(symbol)
+            .and_then(|orderbook| orderbook.auction_uncross());
+
+        AuctionIndicative {
+            symbol: *symbol,
+            price: uncross.map(|(price, _, _)| price),
+            volume: uncross.map(|(_, volume, _)| volume).unwrap_or(0.0),
+            imbalance: uncross.map(|(_, _, imbalance)| imbalance).unwrap_or(0.0),
+            timestamp: self.clock.now(),
+        }
+    }
+
+    /// 结束集合竞价：以最大成交量价格一次性撮合交叉部分，然后恢复连续交易
+    pub async fn end_auction(&self, symbol: &Symbol) -> Result<Vec<Trade>, String> {
+        self.uncross_auction(symbol, "Auction ended")
+            .await
+            .map(|(trades, _)| trades)
+    }
+
+    /// 撮合集合竞价的交叉部分并以 `reason` 恢复连续交易，返回成交和新的交易状态
+    ///
+    /// 整个撮合和状态切换期间持有订单簿的撮合锁，排队的订单在恢复连续交易后才撮合。
+    /// 撮合中途失败时订单簿可能仍然交叉，交易对转为暂停交易等待人工处理，不会停留在集合竞价
+    async fn uncross_auction(
+        &self,
+        symbol: &Symbol,
+        reason: &str,
+    ) -> Result<(Vec<Trade>, SymbolStatus), String> {
+        let _in_flight = self.enter_order_entry().await;
+        if self.is_standby() {
+            return Err(STANDBY_REJECT_REASON.to_string());
+        }
+        let orderbook = self.get_or_create_orderbook(symbol);
+        let matching = orderbook.lock_matching().await;
+        if self.get_trading_state(symbol) != TradingState::AuctionOnly {
+            return Err(format!("{} is not in an auction", symbol));
+        }
+
+        let (trades, failure) = match orderbook.auction_uncross() {
+            Some((price, volume, _)) => {
+                let (trades, failure) = self.uncross(&orderbook, symbol, price, volume);
+                info!(
+                    "Auction for {} uncrossed at {} with volume {} ({} trades)",
+                    symbol,
+                    price,
+                    volume,
+                    trades.len()
+                );
+                (trades, failure)
+            }
+            None => (Vec::new(), None),
+        };
+
+        let status = match &failure {
+            None => self.set_trading_state(symbol, TradingState::Trading, Some(reason.to_string())),
+            Some(e) => {
+                warn!("Auction uncross for {} failed: {}", symbol, e);
+                self.set_trading_state(
+                    symbol,
+                    TradingState::Halted,
+                    Some(format!("Auction uncross failed: {}", e)),
+                )
+            }
+        };
+        drop(matching);
+
+        self.publish_agg_trades(&trades);
+        self.reevaluate_reduce_only_for_trades(symbol, &trades)
+            .await;
+        self.mark_market_data_stale(symbol);
+
+        match failure {
+            None => Ok((trades, status)),
+            Some(e) => Err(format!(
+                "Auction for {} stopped after {} trades and trading is halted: {}",
+                symbol,
+                trades.len(),
+                e
+            )),
+        }
+    }
+
+    /// 导出交易对订单簿快照，交易对没有订单簿时返回 None
+    pub fn export_orderbook(&self, symbol: &Symbol) -> Option<OrderBookSnapshot> {
+        self.get_orderbook(symbol)
+            .map(|orderbook| orderbook.export())
+    }
+
+    /// 将快照导入交易对的空订单簿，返回导入的订单数
+    ///
+    /// 导入的挂单登记到订单存储，GTD 订单加入到期调度。订单ID或外部别名与现有订单冲突时拒绝；
+    /// 除集合竞价期间外，快照中的买卖盘不能交叉
+    pub async fn import_orderbook(&self, snapshot: OrderBookSnapshot) -> Result<usize, String> {
+        let symbol = snapshot.symbol;
+        let orderbook = self.get_or_create_orderbook(&symbol);
+        let _matching = orderbook.lock_matching().await;
+
+        {
+            let orders = self.orders.read();
+            let external_ids = self.external_ids.read();
+            for entry in &snapshot.entries {
+                if orders.get(&entry.order.id).is_some() {
+                    return Err(format!("Order {} already exists", entry.order.id));
+                }
+                if let Some(external_id) = entry.order.external_id {
+                    if external_ids.contains_key(&external_id) {
+                        return Err(format!("Duplicate external id {}", external_id));
+                    }
+                }
+            }
+        }
+
+        if self.get_trading_state(&symbol) != TradingState::AuctionOnly {
+            let best = |side: OrderSide| {
+                snapshot
+                    .entries
+                    .iter()
+                    .filter(move |entry| entry.order.side == side)
+                    .filter_map(|entry| entry.order.price)
+            };
+            let best_bid = best(OrderSide::Buy).reduce(f64::max);
+            let best_ask = best(OrderSide::Sell).reduce(f64::min);
+            if let (Some(bid), Some(ask)) = (best_bid, best_ask) {
+                if bid >= ask {
+                    return Err(format!(
+                        "Snapshot for {} is crossed (bid {} >= ask {})",
+                        symbol, bid, ask
+                    ));
+                }
+            }
+        }
+
+        let imported_orders: Vec<Order> = snapshot
+            .entries
+            .iter()
+            .map(|entry| entry.order.clone())
+            .collect();
+        let imported = orderbook.import(snapshot)?;
+
+        for order in imported_orders {
+            if let Some(external_id) = order.external_id {
+                self.external_ids.write().insert(external_id, order.id);
+            }
+            if let Some(expires_at) = order.expires_at {
+                self.schedule_expiry(order.id, expires_at);
+            }
+            self.orders.write().insert(order.id, order);
+        }
+        self.stats.write().active_orders += imported as u64;
+
+        info!("Imported {} resting orders into {}", imported, symbol);
+        Ok(imported)
+    }
+
+    /// 是否为备用实例
+    pub fn is_standby(&self) -> bool {
+        self.standby.load(Ordering::SeqCst)
+    }
+
+    /// 切换为备用实例：此后只应用主实例复制的事件，下单、撤单、修改等操作一律拒绝
+    pub fn enter_standby(&self) {
+        if !self.standby.swap(true, Ordering::SeqCst) {
+            info!("Matching engine entered standby mode");
+        }
+    }
+
+    /// 将备用实例提升为主实例：恢复受理订单，并为未完成的 GTD 订单重新安排到期
+    pub fn promote(&self) -> Result<(), String> {
+        if !self.standby.swap(false, Ordering::SeqCst) {
+            return Err("Matching engine is not a standby replica".to_string());
+        }
+
+        let expiring: Vec<ExpiryKey> = self
+            .orders
+            .read()
+            .values()
+            .filter(|order| !order.status.is_terminal())
+            .filter_map(|order| Some((order.expires_at?, order.id)))
+            .collect();
+        for (expires_at, order_id) in expiring {
+            self.schedule_expiry(order_id, expires_at);
+        }
+
+        info!("Standby matching engine promoted, order entry enabled");
+        Ok(())
+    }
+
+    /// 模拟订单撮合而不修改任何状态（what-if），用于下单前估算成交、均价、滑点和剩余挂单
+    ///
+    /// 与下单一样校验订单、检查交易对状态并按持仓缩减只减仓订单，但不执行风控插件和沙盒余额检查
+    pub fn test_order(&self, mut order: Order) -> Result<OrderTestResult, String> {
+        if order.order_type.is_trigger() {
+            return Err("Trigger orders cannot be tested before they are triggered".to_string());
+        }
+        let symbol = order.symbol;
+        let trading_state = self.get_trading_state(&symbol);
+        if matches!(
+            trading_state,
+            TradingState::Halted | TradingState::CancelOnly
+        ) {
+            return Err(format!(
+                "Trading is {:?} for {}, new orders are not accepted",
+                trading_state, symbol
+            ));
+        }
+        self.validate_order(&order)?;
+        if order.reduce_only {
+            self.apply_reduce_only(&mut order)?;
+        }
+        if trading_state == TradingState::AuctionOnly && order.order_type == OrderType::Market {
+            return Err(format!(
+                "Market orders are not accepted during the auction for {}",
+                symbol
+            ));
+        }
+
+        let orderbook = self.get_orderbook(&symbol);
+        let reference_price = orderbook.as_ref().and_then(|orderbook| match order.side {
+            OrderSide::Buy => orderbook.best_ask(),
+            OrderSide::Sell => orderbook.best_bid(),
+        });
+        // 集合竞价阶段只挂单不撮合
+        let fills: Vec<SimulatedFill> = match &orderbook {
+            Some(orderbook) if trading_state != TradingState::AuctionOnly => orderbook
+                .simulate_match(&order)
+                .into_iter()
+                .map(|fill| SimulatedFill {
+                    price: self.trade_price_between(order.price, Some(fill.price)),
+                    quantity: fill.quantity,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let filled_quantity: f64 = fills.iter().map(|fill| fill.quantity).sum();
+        let filled_notional: f64 = fills.iter().map(|fill| fill.price * fill.quantity).sum();
+        let average_price = (filled_quantity > 0.0).then(|| filled_notional / filled_quantity);
+        let slippage_bps = average_price
+            .zip(reference_price)
+            .map(|(average, reference)| {
+                let deviation = match order.side {
+                    OrderSide::Buy => average - reference,
+                    OrderSide::Sell => reference - average,
+                };
+                deviation / reference * 10_000.0
+            });
+
+        let remaining_quote = order
+            .quote_quantity
+            .map(|quote_quantity| (quote_quantity - filled_notional).max(0.0));
+        let unfilled = if remaining_quote.is_some() {
+            0.0
+        } else {
+            (order.remaining_quantity - filled_quantity).max(0.0)
+        };
+        let (resting_quantity, cancelled_quantity) = if order.order_type == OrderType::Market {
+            (0.0, unfilled)
+        } else {
+            (unfilled, 0.0)
+        };
+
+        Ok(OrderTestResult {
+            symbol,
+            side: order.side,
+            fills,
+            filled_quantity,
+            filled_notional,
+            average_price,
+            reference_price,
+            slippage_bps,
+            estimated_fee: filled_notional * self.fee_schedule.rates(&order.user_id, self.now()).1,
+            resting_quantity,
+            cancelled_quantity,
+            remaining_quote,
+        })
+    }
+
+    /// 是否运行在沙盒模式
+    pub fn is_sandbox(&self) -> bool {
+        self.sandbox.is_some()
+    }
+
+    /// 沙盒模式下为首次使用的用户注入初始虚拟资金，未启用沙盒时不做任何事
+    pub fn fund_sandbox_account(&self, user_id: &str) {
+        if let Some(sandbox) = &self.sandbox {
+            sandbox.ensure_funded(&self.accounts, user_id);
+        }
+    }
+
+    /// 重置沙盒用户：撤销全部挂单和条件单，清空余额、流水和持仓后重新注入初始资金
+    pub async fn reset_sandbox_account(&self, user_id: &str) -> Result<SandboxReset, String> {
+        let sandbox = self
+            .sandbox
+            .as_ref()
+            .ok_or_else(|| "Sandbox mode is not enabled".to_string())?;
+
+        let mut cancelled_orders = 0;
+        for order in self.get_open_user_orders(user_id) {
+            match self.cancel_order(order.id, user_id.to_string()).await {
+                Ok(_) => cancelled_orders += 1,
+                Err(e) => warn!(
+                    "Failed to cancel order {} on sandbox reset: {}",
+                    order.id, e
+                ),
+            }
+        }
+
+        self.accounts.reset_user(user_id);
+        self.positions.clear_user(user_id);
+        sandbox.refund(&self.accounts, user_id);
+        info!(
+            "Reset sandbox account {}, cancelled {} orders",
+            user_id, cancelled_orders
+        );
+
+        Ok(SandboxReset {
+            user_id: user_id.to_string(),
+            cancelled_orders,
+            balances: self.accounts.get_balances(user_id),
+        })
+    }
+
+    /// 撮合延迟分位数，未启用延迟统计时为空
+    pub fn latency_report(&self) -> Option<LatencyReport> {
+        self.latency.as_ref().map(LatencyTracker::report)
+    }
+
+    /// 交易对下单队列中排队和正在撮合的订单数，未启用下单队列时为 0
+    pub fn submission_queue_depth(&self, symbol: &Symbol) -> usize {
+        self.submission_queues
+            .as_ref()
+            .map_or(0, |queues| queues.depth(symbol))
+    }
+
+    /// 下单队列已满时建议的重试间隔，未启用下单队列时为空
+    pub fn submission_retry_after(&self) -> Option<Duration> {
+        self.submission_queues
+            .as_ref()
+            .map(SubmissionQueues::retry_after)
+    }
+
+    /// 生成复制快照，返回快照和从快照之后第一条事件开始的事件总线订阅
+    ///
+    /// 期间暂停受理下单、撤单、修改、成交撤销和结束集合竞价，等进行中的操作完成后再导出，
+    /// 快照与订阅之间没有缺口。到期撤单不受暂停影响，快照中已反映的到期事件可能再次出现在订阅中，
+    /// 备用实例重复应用订单事件的结果不变。同一时间只能生成一个快照
+    pub async fn replication_snapshot(
+        &self,
+    ) -> Result<(ReplicationSnapshot, EventSubscription), String> {
+        if self.paused.swap(true, Ordering::SeqCst) {
+            return Err("Another replication snapshot is in progress".to_string());
+        }
+        let _paused = PauseGuard { engine: self };
+
+        let timeout = Duration::from_secs(self.config.shutdown.drain_timeout_seconds);
+        if !self.drain(timeout).await {
+            return Err(format!(
+                "{} operations still in flight after {:?}",
+                self.in_flight.load(Ordering::SeqCst),
+                timeout
+            ));
+        }
+
+        let subscription = self.event_bus.subscribe_from(None)?;
+        let engine_snapshot = self.snapshot();
+        let orderbooks: Vec<(SymbolId, SafeOrderBook)> = self
+            .orderbooks
+            .read()
+            .iter()
+            .map(|(&symbol_id, orderbook)| (symbol_id, orderbook.clone()))
+            .collect();
+        let mut recent_trades = Vec::new();
+        for (symbol_id, _) in &orderbooks {
+            let symbol = Symbol::from(*symbol_id);
+            recent_trades
+                .extend(self.get_trades(Some(&symbol), Some(REPLICATED_TRADES_PER_SYMBOL)));
+        }
+
+        let snapshot = ReplicationSnapshot {
+            sequence: subscription.next_sequence,
+            timestamp: engine_snapshot.timestamp,
+            stats: engine_snapshot.stats,
+            orderbooks: orderbooks
+                .iter()
+                .map(|(_, orderbook)| orderbook.export())
+                .collect(),
+            trigger_orders: engine_snapshot
+                .open_orders
+                .into_iter()
+                .filter(|order| order.order_type.is_trigger())
+                .collect(),
+            trading_states: self
+                .trading_states
+                .read()
+                .iter()
+                .map(|(&symbol_id, &state)| (Symbol::from(symbol_id), state))
+                .collect(),
+            listings: self.get_symbol_listings(),
+            positions: self.positions.export(),
+            recent_trades,
+            market_data: engine_snapshot.market_data,
+        };
+        Ok((snapshot, subscription))
+    }
+
+    /// 在备用实例上加载复制快照，替换全部订单簿、条件单、交易状态、上市记录、持仓、最近成交和统计
+    ///
+    /// 已结束的订单保留在订单存储中，未完成的订单以快照为准
+    pub async fn load_replication_snapshot(
+        &self,
+        snapshot: ReplicationSnapshot,
+    ) -> Result<(), String> {
+        if !self.is_standby() {
+            return Err("Replication snapshots can only be loaded by a standby".to_string());
+        }
+
+        // 移除未完成的订单和快照中包含的订单，随后由快照重新登记
+        {
+            let snapshot_ids: HashSet<OrderId> = snapshot
+                .orderbooks
+                .iter()
+                .flat_map(|orderbook| &orderbook.entries)
+                .map(|entry| entry.order.id)
+                .chain(snapshot.trigger_orders.iter().map(|order| order.id))
+                .collect();
+            let mut orders = self.orders.write();
+            let mut external_ids = self.external_ids.write();
+            let replaced: Vec<Order> = orders
+                .values()
+                .filter(|order| !order.status.is_terminal() || snapshot_ids.contains(&order.id))
+                .cloned()
+                .collect();
+            for order in replaced {
+                orders.remove(&order.id);
+                if let Some(external_id) = order.external_id {
+                    external_ids.remove(&external_id);
+                }
+            }
+        }
+        self.orderbooks.write().clear();
+        self.triggers.write().clear();
+        self.expiry_queue.write().clear();
+        *self.trading_states.write() = snapshot
+            .trading_states
+            .into_iter()
+            .map(|(symbol, state)| (symbol.id(), state))
+            .collect();
+        // 上市记录先于订单簿恢复，订单簿按上市的 tick 换算价格
+        self.listings.write().clear();
+        for listing in snapshot.listings {
+            self.apply_listing(listing);
+        }
+
+        for orderbook in snapshot.orderbooks {
+            self.import_orderbook(orderbook).await?;
+        }
+        for order in snapshot.trigger_orders {
+            self.triggers
+                .write()
+                .entry(order.symbol.id())
+                .or_insert_with(|| TriggerBook::new(order.symbol))
+                .add_order(order.clone(), None)?;
+            if let Some(external_id) = order.external_id {
+                self.external_ids.write().insert(external_id, order.id);
+            }
+            self.orders.write().insert(order.id, order);
+        }
+
+        self.positions.import(snapshot.positions);
+        {
+            let mut recent_trades = snapshot.recent_trades;
+            recent_trades.sort_by_key(|trade| trade.id);
+            let mut trades = self.trades.write();
+            *trades = TradeStore::new();
+            for trade in recent_trades {
+                trades.push(trade);
+            }
+        }
+        *self.market_data.write() = snapshot
+            .market_data
+            .into_iter()
+            .map(|market_data| (market_data.symbol.id(), market_data))
+            .collect();
+        *self.stats.write() = snapshot.stats;
+
+        info!(
+            "Loaded replication snapshot at sequence {}",
+            snapshot.sequence
+        );
+        Ok(())
+    }
+
+    /// 在备用实例上应用主实例事件总线上的一条事件
+    ///
+    /// 事件须按序号顺序应用。订单事件携带订单的完整状态，挂单按原位替换以保留时间优先级；
+    /// 部分成交的挂单没有单独的订单事件，由成交事件同步剩余数量。行情、盘口等派生事件由备用实例
+    /// 自己的状态产生，忽略主实例的副本
+    pub async fn apply_replicated_event(&self, event: SequencedEvent) -> Result<(), String> {
+        if !self.is_standby() {
+            return Err("Replicated events can only be applied by a standby".to_string());
+        }
+
+        match event.event {
+            EngineEvent::OrderUpdate(order) => self.apply_replicated_order(order).await,
+            EngineEvent::Trade(trade) | EngineEvent::OtcTrade(trade) => {
+                self.apply_replicated_trade(trade).await
+            }
+            EngineEvent::OrderRejected { order, reason } => {
+                self.record_rejected(&order, &reason);
+                Ok(())
+            }
+            EngineEvent::TradeBust(bust) => {
+                self.remove_trade(bust.trade_id);
+                self.reverse_trade(&bust.trade);
+                self.publish_trade_bust(bust).await;
+                Ok(())
+            }
+            EngineEvent::SymbolStatus(status) => {
+                self.set_trading_state(&status.symbol, status.state, status.reason);
+                Ok(())
+            }
+            EngineEvent::SymbolListing(listing) => {
+                self.apply_listing(listing);
+                Ok(())
+            }
+            EngineEvent::AggTrade(_)
+            | EngineEvent::MarketData(_)
+            | EngineEvent::BookTicker(_)
+            | EngineEvent::Auction(_)
+            | EngineEvent::MarkPrice(_)
+            | EngineEvent::Liquidation(_) => Ok(()),
+        }
+    }
+
+    /// 按主实例的上市记录更新本地记录，上市的交易对登记计价货币
+    fn apply_listing(&self, listing: SymbolListing) {
+        if listing.status == ListingStatus::Listed {
+            listing.symbol.register_listed();
+        }
+        self.listings.write().insert(listing.symbol.id(), listing);
+    }
+
+    /// 按复制的订单事件同步订单簿、触发簿、订单存储和统计
+    async fn apply_replicated_order(&self, order: Order) -> Result<(), String> {
+        let symbol = order.symbol;
+        let previous = self.orders.read().get(&order.id).cloned();
+        let open = !order.status.is_terminal();
+
+        // 未触发的条件单留在触发簿，其余订单（含刚被触发的条件单）移出触发簿
+        {
+            let mut triggers = self.triggers.write();
+            if open && order.order_type.is_trigger() {
+                triggers
+                    .entry(symbol.id())
+                    .or_insert_with(|| TriggerBook::new(symbol))
+                    .add_order(order.clone(), None)?;
+            } else if let Some(book) = triggers.get_mut(&symbol.id()) {
+                book.remove_order(order.id);
+            }
+        }
+
+        let resting = open
+            && !order.order_type.is_trigger()
+            && order.order_type != OrderType::Market
+            && order.remaining_quantity > 0.0;
+        let orderbook = if resting {
+            Some(self.get_or_create_orderbook(&symbol))
+        } else {
+            self.get_orderbook(&symbol)
+        };
+        if let Some(orderbook) = orderbook {
+            let _matching = orderbook.lock_matching().await;
+            match (orderbook.contains_order(order.id), resting) {
+                (true, true) => {
+                    orderbook.replace_order(order.clone())?;
+                }
+                (true, false) => {
+                    orderbook.remove_order(order.id)?;
+                }
+                (false, true) => orderbook.add_order(order.clone())?,
+                (false, false) => {}
+            }
+        }
+
+        let was_open = previous
+            .as_ref()
+            .is_some_and(|previous| !previous.status.is_terminal());
+        {
+            let mut stats = self.stats.write();
+            if previous.is_none() {
+                stats.total_orders += 1;
+            }
+            if open && !was_open {
+                stats.active_orders += 1;
+            } else if was_open && !open {
+                stats.active_orders = stats.active_orders.saturating_sub(1);
+            }
+        }
+        if previous.is_none() {
+            self.symbol_counters
+                .write()
+                .entry(symbol.id())
+                .or_default()
+                .total_orders += 1;
+            if let Some(external_id) = order.external_id {
+                self.external_ids.write().insert(external_id, order.id);
+            }
+        }
+
+        self.orders.write().insert(order.id, order.clone());
+        self.publish_order(order);
+        Ok(())
+    }
+
+    /// 按复制的成交同步仍在订单簿中的挂单，保存成交并刷新市场数据
+    async fn apply_replicated_trade(&self, trade: Trade) -> Result<(), String> {
+        if let Some(orderbook) = self.get_orderbook(&trade.symbol) {
+            let _matching = orderbook.lock_matching().await;
+            for order_id in [trade.buy_order_id, trade.sell_order_id] {
+                if !orderbook.contains_order(order_id) {
+                    continue;
+                }
+                let Some(order) = self.orders.read().get(&order_id).cloned() else {
+                    continue;
+                };
+
+                let remaining_quantity = (order.remaining_quantity - trade.quantity).max(0.0);
+                let updated = self.fill_resting_order(&orderbook, order_id, remaining_quantity)?;
+                self.orders.write().insert(order_id, updated);
+            }
+        }
+
+        let taker = (trade.trade_type == TradeType::Regular).then(|| taker_order_id(&trade));
+        self.store_trade(&trade, taker);
+        let symbol = trade.symbol;
+        match trade.trade_type {
+            TradeType::Regular => self.publish_trade(trade),
+            TradeType::Otc => {
+                // 备用实例同步申报序号，提升为主实例后继续编号
+                self.next_otc_sequence(&symbol);
+                self.publish_otc_trade(trade);
+            }
+        }
+
+        self.mark_market_data_stale(&symbol);
+        Ok(())
+    }
+
+    /// 获取订单信息
+    pub fn get_order(&self, order_id: OrderId) -> Option<Order> {
+        let order = self.orders.read().get(&order_id).cloned();
+        match order {
+            Some(order) => Some(self.with_pending_trigger(order)),
+            None => self.get_archived_order(order_id),
+        }
+    }
+
+    /// 批量获取订单，只加一次订单存储的读锁；结果按请求顺序排列，不存在的订单被跳过
+    pub fn get_orders(&self, order_ids: &[OrderId]) -> Vec<Order> {
+        let stored: Vec<Option<Order>> = {
+            let orders = self.orders.read();
+            order_ids
+                .iter()
+                .map(|order_id| orders.get(order_id).cloned())
+                .collect()
+        };
+        order_ids
+            .iter()
+            .zip(stored)
+            .filter_map(|(&order_id, order)| match order {
+                Some(order) => Some(self.with_pending_trigger(order)),
+                None => self.get_archived_order(order_id),
+            })
+            .collect()
+    }
+
+    /// 未触发的跟踪止损单以触发簿中的最新触发价为准
+    fn with_pending_trigger(&self, order: Order) -> Order {
+        if order.order_type.is_trigger() && !order.status.is_terminal() {
+            let triggers = self.triggers.read();
+            if let Some(pending) = triggers
+                .get(&order.symbol.id())
+                .and_then(|book| book.get_order(order.id))
+            {
+                return pending;
+            }
+        }
+        order
+    }
+
+    /// 是否启用了订单审计
+    pub fn is_audit_enabled(&self) -> bool {
+        self.audit.is_some()
+    }
+
+    /// 按发生顺序获取订单的审计事件，未启用审计时返回错误
+    pub fn get_order_audit(&self, order_id: OrderId) -> Result<Vec<AuditEvent>, String> {
+        self.audit
+            .as_ref()
+            .ok_or_else(|| "Order audit is not enabled".to_string())?
+            .get(order_id)
+    }
+
+    /// 获取用户的所有订单
+    pub fn get_user_orders(&self, user_id: &str) -> Vec<Order> {
+        self.orders.read().user_orders(user_id).cloned().collect()
+    }
+
+    /// 获取用户的未完成订单，可按交易对过滤，按订单ID从旧到新排列
+    ///
+    /// 只读取按用户维护的未完成订单索引，不遍历历史订单
+    pub fn get_open_orders(&self, user_id: &str, symbol: Option<&Symbol>) -> Vec<Order> {
+        let orders: Vec<Order> = self
+            .orders
+            .read()
+            .open_user_orders(user_id)
+            .filter(|order| symbol.is_none_or(|symbol| order.symbol == *symbol))
+            .cloned()
+            .collect();
+        orders
+            .into_iter()
+            .map(|order| self.with_pending_trigger(order))
+            .collect()
+    }
+
+    /// 按条件分页查询用户订单，按订单ID从新到旧排列
+    pub fn query_user_orders(
+        &self,
+        user_id: &str,
+        filter: &OrderFilter,
+        page: PageRequest,
+    ) -> Page<Order> {
+        self.orders.read().query_user(user_id, filter, page)
+    }
+
+    /// 按条件分页查询全部用户的订单（不含已归档的订单），按订单ID从新到旧排列
+    pub fn query_orders(&self, filter: &OrderFilter, page: PageRequest) -> Page<Order> {
+        self.orders.read().query(filter, page)
+    }
+
+    /// 获取订单簿深度
+    pub fn get_orderbook_depth(
+        &self,
+        symbol: &Symbol,
+        depth: Option<usize>,
+    ) -> Option<OrderBookDepth> {
+        self.get_orderbook(symbol)
+            .map(|orderbook| orderbook.get_depth(depth))
+    }
+
+    /// 获取交易对当前盘口
+    pub fn get_book_ticker(&self, symbol: &Symbol) -> Option<BookTicker> {
+        self.get_orderbook(symbol)
+            .map(|orderbook| orderbook.book_ticker())
+    }
+
+    /// 获取交易对最新的指数价格和标记价格
+    pub fn get_mark_price(&self, symbol: &Symbol) -> Option<MarkPrice> {
+        self.mark_prices.read().get(&symbol.id()).cloned()
+    }
+
+    /// 获取所有交易对最新的指数价格和标记价格
+    pub fn get_all_mark_prices(&self) -> Vec<MarkPrice> {
+        let mut mark_prices: Vec<MarkPrice> = self.mark_prices.read().values().cloned().collect();
+        mark_prices.sort_by_key(|mark_price| mark_price.symbol.to_string());
+        mark_prices
+    }
+
+    /// 更新交易对的标记价格并广播
+    ///
+    /// 条件单以标记价格为参考时，用新的标记价格检查触发；备用实例只记录不触发
+    pub async fn update_mark_price(&self, mark_price: MarkPrice) {
+        let symbol = mark_price.symbol;
+        let price = mark_price.mark_price;
+        self.mark_prices
+            .write()
+            .insert(symbol.id(), mark_price.clone());
+        if let Some(funding) = &self.funding {
+            funding.record_premium(&mark_price);
+        }
+        let timestamp = mark_price.timestamp;
+        self.event_bus
+            .publish(EngineEvent::MarkPrice(mark_price), timestamp);
+
+        if self.config.price_reference.triggers == PriceReference::Mark && !self.is_standby() {
+            let _guard = self.enter_order_entry().await;
+            self.run_triggers(&symbol, vec![price]).await;
+        }
+    }
+
+    /// 按配置的参考价格来源取交易对的参考价，没有标记价格时使用最新成交价
+    fn reference_price(&self, symbol: &Symbol, reference: PriceReference) -> Option<f64> {
+        let mark_price = match reference {
+            PriceReference::Mark => self.current_mark_price(symbol),
+            PriceReference::LastTrade => None,
+        };
+        mark_price.or_else(|| self.last_trade_price(symbol))
+    }
+
+    fn current_mark_price(&self, symbol: &Symbol) -> Option<f64> {
+        self.mark_prices
+            .read()
+            .get(&symbol.id())
+            .map(|mark_price| mark_price.mark_price)
+    }
+
+    /// 获取交易对的盘口分析指标，失衡度按前 `depth` 档计算
+    pub fn get_book_analytics(&self, symbol: &Symbol, depth: usize) -> Option<BookAnalytics> {
+        self.get_orderbook(symbol)
+            .map(|orderbook| orderbook.analytics(depth))
+    }
+
+    /// 按周期汇总交易对在 `[from, to)` 内的成交量，交易对不存在时为空
+    pub fn get_volume_series(
+        &self,
+        symbol: &Symbol,
+        interval: VolumeInterval,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Option<VolumeSeries> {
+        self.get_orderbook(symbol)?;
+        Some(VolumeSeries {
+            symbol: *symbol,
+            interval,
+            buckets: self.volume_stats.series(symbol, interval, from, to),
+            timestamp: self.clock.now(),
+        })
+    }
+
+    /// 检查全部订单簿的内部一致性，返回第一处违反的描述
+    ///
+    /// 集合竞价中的交易对允许订单簿交叉，只做结构检查
+    pub fn verify_invariants(&self) -> Result<(), String> {
+        let orderbooks: Vec<(SymbolId, SafeOrderBook)> = self
+            .orderbooks
+            .read()
+            .iter()
+            .map(|(id, orderbook)| (*id, orderbook.clone()))
+            .collect();
+
+        for (id, orderbook) in orderbooks {
+            let symbol = Symbol::from(id);
+            if self.get_trading_state(&symbol) == TradingState::AuctionOnly {
+                orderbook.verify_structure()?;
+            } else {
+                orderbook.verify_invariants()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 获取市场数据
+    ///
+    /// 市场数据任务尚未处理该交易对的新成交时就地重新计算，查询结果不会落后于成交
+    pub fn get_market_data(&self, symbol: &Symbol) -> Option<MarketData> {
+        if self.stale_market_data.lock().contains(&symbol.id()) {
+            return self.refresh_market_data(symbol);
+        }
+        self.market_data.read().get(&symbol.id()).cloned()
+    }
+
+    /// 获取所有市场数据
+    pub fn get_all_market_data(&self) -> HashMap<Symbol, MarketData> {
+        let stale: Vec<SymbolId> = self.stale_market_data.lock().iter().copied().collect();
+        for id in stale {
+            self.refresh_market_data(&Symbol::from(id));
+        }
+        self.market_data
+            .read()
+            .iter()
+            .map(|(id, data)| (Symbol::from(*id), data.clone()))
+            .collect()
+    }
+
+    /// 获取所有未暂停交易对的 24 小时行情，按交易对排序
+    ///
+    /// 价格指标来自市场数据，成交量取最近 24 小时的分钟成交量统计
+    pub fn get_tickers(&self) -> Vec<Ticker> {
+        let now = self.clock.now();
+        let mut tickers: Vec<Ticker> = self
+            .get_all_market_data()
+            .into_values()
+            .filter(|data| self.get_trading_state(&data.symbol) != TradingState::Halted)
+            .map(|data| {
+                let window =
+                    self.volume_stats
+                        .window(&data.symbol, now - chrono::Duration::hours(24), now);
+                let book = self.get_book_ticker(&data.symbol);
+                Ticker {
+                    symbol: data.symbol,
+                    last_price: data.last_price,
+                    price_change_percent: data.price_change_24h,
+                    high_price: data.high_24h,
+                    low_price: data.low_24h,
+                    volume: window.volume,
+                    quote_volume: window.quote_volume,
+                    trade_count: window.trade_count,
+                    bid_price: book.as_ref().and_then(|book| book.bid_price),
+                    bid_quantity: book.as_ref().map_or(0.0, |book| book.bid_quantity),
+                    ask_price: book.as_ref().and_then(|book| book.ask_price),
+                    ask_quantity: book.as_ref().map_or(0.0, |book| book.ask_quantity),
+                    timestamp: now,
+                }
+            })
+            .collect();
+        tickers.sort_by_key(|ticker| ticker.symbol.to_string());
+        tickers
+    }
+
+    /// 获取引擎统计信息
+    pub fn get_stats(&self) -> EngineStats {
+        let mut stats = self.stats.read().clone();
+        stats.uptime_seconds = self
+            .clock
+            .instant()
+            .duration_since(self.start_time)
+            .as_secs();
+        stats
+    }
+
+    /// 引擎时钟的当前时间
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    /// 引擎启动以来的单调时钟读数（纳秒），不受系统时间调整影响
+    pub fn monotonic_nanos(&self) -> u64 {
+        self.clock
+            .instant()
+            .duration_since(self.start_time)
+            .as_nanos() as u64
+    }
+
+    /// 获取交易对统计信息，交易对既没有订单簿也没有订单时返回 None
+    pub fn get_symbol_stats(&self, symbol: &Symbol) -> Option<SymbolStats> {
+        let counters = self.symbol_counters.read().get(&symbol.id()).copied();
+        let orderbook = self.get_orderbook(symbol);
+        if counters.is_none() && orderbook.is_none() {
+            return None;
+        }
+        let counters = counters.unwrap_or_default();
+
+        let mut stats = SymbolStats {
+            symbol: *symbol,
+            total_orders: counters.total_orders,
+            total_trades: counters.total_trades,
+            volume: counters.volume,
+            quote_volume: counters.quote_volume,
+            open_bid_quantity: 0.0,
+            open_ask_quantity: 0.0,
+            open_bid_orders: 0,
+            open_ask_orders: 0,
+            best_bid: None,
+            best_ask: None,
+            spread: None,
+            last_price: self.last_trade_price(symbol),
+            timestamp: self.clock.now(),
+        };
+        if let Some(orderbook) = orderbook {
+            let book = orderbook.get_stats();
+            stats.open_bid_quantity = book.total_bid_quantity;
+            stats.open_ask_quantity = book.total_ask_quantity;
+            stats.open_bid_orders = book.total_bid_orders;
+            stats.open_ask_orders = book.total_ask_orders;
+            stats.best_bid = orderbook.best_bid();
+            stats.best_ask = orderbook.best_ask();
+            stats.spread = orderbook.spread();
+        }
+        Some(stats)
+    }
+
+    /// 获取所有已知交易对的统计信息
+    pub fn get_all_symbol_stats(&self) -> Vec<SymbolStats> {
+        let mut symbols: BTreeSet<SymbolId> = self.orderbooks.read().keys().copied().collect();
+        symbols.extend(self.symbol_counters.read().keys().copied());
+        symbols
+            .into_iter()
+            .filter_map(|id| self.get_symbol_stats(&Symbol::from(id)))
+            .collect()
+    }
+
+    /// 获取交易历史，最新的在前
+    pub fn get_trades(&self, symbol: Option<&Symbol>, limit: Option<usize>) -> Vec<Trade> {
+        let filter = TradeFilter {
+            symbol: symbol.copied(),
+            ..TradeFilter::default()
+        };
+        let page = PageRequest {
+            cursor: None,
+            limit: limit.unwrap_or(usize::MAX),
+        };
+        self.trades.read().query(&filter, page).items
+    }
+
+    /// 按条件分页查询成交，按成交ID从新到旧排列
+    ///
+    /// 启用成交历史后，内存中的成交不足一页且查询范围早于内存保留的成交时，从历史文件中接着读取
+    pub fn query_trades(&self, filter: &TradeFilter, page: PageRequest) -> Page<Trade> {
+        let mut result = self.trades.read().query(filter, page);
+        let Some(history) = &self.trade_history else {
+            return result;
+        };
+        let in_memory_since = *self.trades_in_memory_since.read();
+        if result.next_cursor.is_some()
+            || filter
+                .start_time
+                .is_some_and(|start| start >= in_memory_since)
+        {
+            return result;
+        }
+
+        // 内存中的成交是历史文件中最新的一段，从本页最后一笔之前接着读取
+        let older = PageRequest {
+            cursor: result.items.last().map(|trade| trade.id).or(page.cursor),
+            limit: page.limit - result.items.len(),
+        };
+        match history.query(filter, older) {
+            Ok(older) => {
+                result.items.extend(older.items);
+                result.next_cursor = older.next_cursor;
+            }
+            Err(e) => warn!("Failed to query trade history: {}", e),
+        }
+        result
+    }
+
+    /// 将早于内存保留时间的成交移出内存，此后只能从历史文件中查询；未启用成交历史时返回 0
+    pub fn evict_trades(&self) -> usize {
+        if self.trade_history.is_none() {
+            return 0;
+        }
+        let retention = chrono::Duration::seconds(
+            self.config
+                .trade_history
+                .retention_seconds
+                .min(i64::MAX as u64) as i64,
+        );
+        let cutoff = self.now() - retention;
+        let evicted = self.trades.write().remove_before(cutoff);
+        {
+            let mut in_memory_since = self.trades_in_memory_since.write();
+            *in_memory_since = (*in_memory_since).max(cutoff);
+        }
+        if evicted > 0 {
+            info!(
+                "Evicted {} trades older than {} from memory",
+                evicted, cutoff
+            );
+        }
+        evicted
+    }
+
+    /// 标记交易对的市场数据需要重新计算，并唤醒市场数据任务
+    fn mark_market_data_stale(&self, symbol: &Symbol) {
+        self.stale_market_data.lock().insert(symbol.id());
+        self.market_data_notify.notify_one();
+    }
+
+    /// 重新计算待更新交易对的市场数据并广播有变化的部分，返回广播的交易对数量
+    ///
+    /// 距上次广播不足最小推送间隔的交易对保留到下次处理
+    pub fn publish_market_data(&self) -> usize {
+        self.publish_due_market_data().0
+    }
+
+    /// 同 [`MatchingEngine::publish_market_data`]，另外返回被推迟的交易对中最早可以广播的时间
+    fn publish_due_market_data(&self) -> (usize, Option<Instant>) {
+        let now = self.clock.instant();
+        let stale: Vec<SymbolId> = self.stale_market_data.lock().drain().collect();
+        let mut published = 0;
+        let mut next_due: Option<Instant> = None;
+        for id in stale {
+            let symbol = Symbol::from(id);
+            let min_interval = self.config.market_data.min_publish_interval_for(&symbol);
+            let due = self
+                .published_market_data
+                .lock()
+                .get(&id)
+                .map(|(_, published_at)| *published_at + min_interval);
+            if let Some(due) = due.filter(|due| *due > now) {
+                self.stale_market_data.lock().insert(id);
+                next_due = Some(next_due.map_or(due, |next| next.min(due)));
+                continue;
+            }
+
+            let Some(market_data) = self.refresh_market_data(&symbol) else {
+                continue;
+            };
+            let mut published_market_data = self.published_market_data.lock();
+            if published_market_data
+                .get(&id)
+                .is_some_and(|(last, _)| same_market_data(last, &market_data))
+            {
+                continue;
+            }
+            published_market_data.insert(id, (market_data.clone(), now));
+            self.event_bus
+                .publish(EngineEvent::MarketData(market_data), self.now());
+            published += 1;
+        }
+        (published, next_due)
+    }
+
+    /// 启动市场数据任务
+    ///
+    /// 下单和成交只标记交易对，24 小时统计由该任务在撮合路径之外重新计算，有变化时广播；
+    /// 一次唤醒处理期间积累的所有交易对，推送过于频繁的交易对在最小推送间隔到期后再处理
+    pub fn start_market_data_publisher(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let engine = Arc::clone(self);
+
+        tokio::spawn(async move {
+            let mut next_due: Option<Instant> = None;
+            loop {
+                match next_due {
+                    Some(due) => {
+                        let wait = due.saturating_duration_since(engine.clock.instant());
+                        tokio::select! {
+                            _ = tokio::time::sleep(wait) => {}
+                            _ = engine.market_data_notify.notified() => {}
+                        }
+                    }
+                    None => engine.market_data_notify.notified().await,
+                }
+                next_due = engine.publish_due_market_data().1;
+            }
+        })
+    }
+
+    /// 启动成交淘汰任务，未启用成交历史时返回 None
+    pub fn start_trade_evictor(self: &Arc<Self>) -> Option<tokio::task::JoinHandle<()>> {
+        self.trade_history.as_ref()?;
+
+        let engine = Arc::clone(self);
+        let retention_seconds = self.config.trade_history.retention_seconds;
+        let interval = Duration::from_secs((retention_seconds / 2).clamp(1, 60));
+
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                engine.evict_trades();
+            }
+        }))
+    }
+
+    /// 从内存和历史文件中删除一笔成交（成交被撤销时）
+    fn remove_trade(&self, trade_id: TradeId) -> Option<Trade> {
+        let trade = self.trades.write().remove(trade_id)?;
+        if let Some(history) = &self.trade_history {
+            if let Err(e) = history.remove(&trade) {
+                warn!(
+                    "Failed to remove busted trade {} from history: {}",
+                    trade_id, e
+                );
+            }
+        }
+        Some(trade)
+    }
+
+    /// 按条件分页查询聚合成交，按第一笔成交ID从新到旧排列
+    pub fn query_agg_trades(&self, filter: &TradeFilter, page: PageRequest) -> Page<AggTrade> {
+        self.trades.read().query_agg(filter, page)
+    }
+
+    /// 按条件分页查询用户自己的成交，按成交ID从新到旧排列
+    pub fn query_user_fills(
+        &self,
+        user_id: &str,
+        filter: &FillFilter,
+        page: PageRequest,
+    ) -> Page<Fill> {
+        self.fills.read().query_user(user_id, filter, page)
+    }
+
+    /// 获取订单及其逐笔成交明细
+    pub fn get_order_detail(&self, order_id: OrderId) -> Option<OrderDetail> {
+        let order = self.get_order(order_id)?;
+        let fills = self.fills.read().order_fills(&order.user_id, order_id);
+        Some(OrderDetail { order, fills })
+    }
+
+    /// 用户在某笔成交中某个订单的成交记录，包含流动性角色和手续费
+    pub fn get_user_fill(
+        &self,
+        user_id: &str,
+        trade_id: TradeId,
+        order_id: OrderId,
+    ) -> Option<Fill> {
+        self.fills.read().get(user_id, trade_id, order_id).cloned()
+    }
+
+    /// 订阅事件总线上的全部事件，引擎对外的各类事件按同一序号排列
+    pub fn subscribe_events(&self) -> broadcast::Receiver<SequencedEvent> {
+        self.event_bus.subscribe()
+    }
+
+    /// 从 `from_sequence` 开始订阅事件总线，先回放保留的事件再接收新事件
+    ///
+    /// 为空时只接收新事件；序号已移出回放缓冲区（`event_bus.replay_capacity`）或尚未产生时返回错误
+    pub fn subscribe_events_from(
+        &self,
+        from_sequence: Option<u64>,
+    ) -> Result<EventSubscription, String> {
+        self.event_bus.subscribe_from(from_sequence)
+    }
+
+    /// 在事件总线上注册进程内监听器，在发布事件的线程上按序号顺序同步调用
+    pub fn register_event_listener(&self, listener: Arc<dyn EventListener>) {
+        self.event_bus.register(listener);
+    }
+
+    /// 事件总线最近发布的事件序号
+    pub fn last_event_sequence(&self) -> u64 {
+        self.event_bus.last_sequence()
+    }
+
+    /// 获取交易广播接收器
+    pub fn subscribe_trades(&self) -> broadcast::Receiver<Trade> {
+        self.event_bus.subscribe_trades()
+    }
+
+    /// 获取成交撤销广播接收器
+    pub fn subscribe_trade_busts(&self) -> broadcast::Receiver<TradeBust> {
+        self.event_bus.subscribe_trade_busts()
+    }
+
+    /// 获取聚合成交广播接收器
+    pub fn subscribe_agg_trades(&self) -> broadcast::Receiver<AggTrade> {
+        self.event_bus.subscribe_agg_trades()
+    }
+
+    /// 获取订单更新广播接收器
+    pub fn subscribe_orders(&self) -> broadcast::Receiver<Order> {
+        self.event_bus.subscribe_orders()
+    }
+
+    /// 订阅单个订单的更新，接收器的初始值为订单当前状态；订单不存在时返回 None
+    ///
+    /// 订单进入终态后通道关闭，关闭前最后一次更新仍可收到
+    pub fn watch_order(&self, order_id: OrderId) -> Option<watch::Receiver<Order>> {
+        let order = self.get_order(order_id)?;
+        let mut watches = self.order_watches.lock();
+        // 顺带清理等待者已全部离开的通道
+        watches.retain(|_, watch| watch.receiver_count() > 0);
+        if order.status.is_terminal() {
+            return Some(watch::channel(order).1);
+        }
+        Some(
+            watches
+                .entry(order_id)
+                .or_insert_with(|| watch::channel(order).0)
+                .subscribe(),
+        )
+    }
+
+    /// 等待订单进入终态或成交数量超过 `filled_quantity`，最多等待 `timeout`
+    ///
+    /// 返回订单的最新状态（超时时为未变化的状态）；订单不存在时返回 None
+    pub async fn wait_for_order(
+        &self,
+        order_id: OrderId,
+        filled_quantity: f64,
+        timeout: Duration,
+    ) -> Option<Order> {
+        let progressed = |order: &Order| {
+            order.status.is_terminal() || order.filled_quantity > filled_quantity + QUANTITY_EPSILON
+        };
+        let mut updates = self.watch_order(order_id)?;
+        // 读取当前状态和登记通道之间的更新不会推送到通道，登记后再读一次
+        let order = self.get_order(order_id)?;
+        if progressed(&order) {
+            return Some(order);
+        }
+        let _ = tokio::time::timeout(timeout, updates.wait_for(progressed)).await;
+        self.get_order(order_id)
+    }
+
+    /// 订阅 drop copy 事件流：全体用户的订单事件和成交，沿用事件总线的全局序号
+    ///
+    /// `from_sequence` 为空时只接收新事件，否则先回放从该序号开始的缓冲事件；未启用 drop copy、
+    /// 序号已超出事件总线的回放缓冲区或尚未产生时返回错误
+    pub fn subscribe_drop_copy(
+        &self,
+        from_sequence: Option<u64>,
+    ) -> Result<DropCopySubscription, String> {
+        if !self.drop_copy {
+            return Err("Drop copy is not enabled".to_string());
+        }
+        self.event_bus
+            .subscribe_from(from_sequence)
+            .map(DropCopySubscription::new)
+    }
+
+    /// 获取市场数据广播接收器
+    pub fn subscribe_market_data(&self) -> broadcast::Receiver<MarketData> {
+        self.event_bus.subscribe_market_data()
+    }
+
+    /// 获取盘口变化广播接收器
+    pub fn subscribe_book_ticker(&self) -> broadcast::Receiver<BookTicker> {
+        self.event_bus.subscribe_book_tickers()
+    }
+
+    /// 获取交易对状态广播接收器
+    pub fn subscribe_symbol_status(&self) -> broadcast::Receiver<SymbolStatus> {
+        self.event_bus.subscribe_symbol_status()
+    }
+
+    /// 注册下单前风控检查，按注册顺序在订单被接受前执行
+    pub fn register_pre_trade_check(&self, check: Arc<dyn PreTradeCheck>) {
+        info!("Registered pre-trade check {}", check.name());
+        self.pre_trade_checks.register(check);
+    }
+
+    /// 获取用户当前的手续费档位和滚动成交额
+    pub fn get_user_fees(&self, user_id: &str) -> UserFeeTier {
+        self.fee_schedule.user_tier(user_id, self.now())
+    }
+
+    /// 获取用户挂单限额及当前占用
+    pub fn get_user_limits(&self, user_id: &str) -> UserLimitStatus {
+        let open_orders = self.get_open_user_orders(user_id);
+        let limits = self.config.user_limits.limits_for(user_id).clone();
+        let mut status = UserLimitStatus::new(user_id, limits, &open_orders);
+        if let Some(message_rates) = &self.message_rates {
+            status.message_counts = message_rates.counts(user_id, self.now());
+        }
+        status
+    }
+
+    /// 获取用户净持仓
+    pub fn positions(&self) -> &PositionTracker {
+        &self.positions
+    }
+
+    /// 获取用户在各交易对上的持仓，未实现盈亏按标记价格计算，没有标记价格时按最新成交价
+    pub fn get_user_positions(&self, user_id: &str) -> Vec<Position> {
+        self.positions
+            .get_user_positions(user_id)
+            .into_iter()
+            .map(|entry| {
+                let mark_price = self.reference_price(&entry.symbol, PriceReference::Mark);
+                Position {
+                    unrealized_pnl: mark_price
+                        .map(|price| (price - entry.entry_price) * entry.quantity),
+                    mark_price,
+                    user_id: entry.user_id,
+                    symbol: entry.symbol,
+                    quantity: entry.quantity,
+                    entry_price: entry.entry_price,
+                    realized_pnl: entry.realized_pnl,
+                }
+            })
+            .collect()
+    }
+
+    /// 获取交易对的未平仓量
+    pub fn get_open_interest(&self, symbol: &Symbol) -> OpenInterest {
+        OpenInterest {
+            symbol: *symbol,
+            open_interest: self.positions.open_interest(symbol),
+            timestamp: self.now(),
+        }
+    }
+
+    /// 获取集合竞价参考价广播接收器
+    pub fn subscribe_auction(&self) -> broadcast::Receiver<AuctionIndicative> {
+        self.event_bus.subscribe_auctions()
+    }
+
+    /// 获取场外成交广播接收器
+    pub fn subscribe_otc_trades(&self) -> broadcast::Receiver<Trade> {
+        self.event_bus.subscribe_otc_trades()
+    }
+
+    /// 获取标记价格广播接收器
+    pub fn subscribe_mark_prices(&self) -> broadcast::Receiver<MarkPrice> {
+        self.event_bus.subscribe_mark_prices()
+    }
+
+    /// 获取强平事件广播接收器
+    pub fn subscribe_liquidations(&self) -> broadcast::Receiver<LiquidationEvent> {
+        self.event_bus.subscribe_liquidations()
+    }
+
+    /// 订阅交易对上市和下市事件
+    pub fn subscribe_symbol_listings(&self) -> broadcast::Receiver<SymbolListing> {
+        self.event_bus.subscribe_listings()
+    }
+
+    /// 获取账户余额子系统
+    pub fn accounts(&self) -> &AccountManager {
+        &self.accounts
+    }
+
+    /// 检查交易对的上市状态和下单过滤规则
+    ///
+    /// 已下市的交易对总是拒绝；未登记的交易对只在启用 `restrict_symbols` 时拒绝
+    pub(crate) fn check_listing(
+        &self,
+        symbol: &Symbol,
+        quantity: Option<f64>,
+        price: Option<f64>,
+    ) -> Result<(), String> {
+        let listings = self.listings.read();
+        let Some(listing) = listings.get(&symbol.id()) else {
+            if self.config.restrict_symbols {
+                return Err(format!("Symbol {} is not supported", symbol));
+            }
+            return Ok(());
+        };
+        if listing.status == ListingStatus::Delisted {
+            return Err(format!("Symbol {} is delisted", symbol));
+        }
+
+        let filters = &listing.filters;
+        if let Some(quantity) = quantity {
+            if filters.min_quantity.is_some_and(|min| quantity < min) {
+                return Err(format!("Quantity is below the minimum for {}", symbol));
+            }
+            if filters.max_quantity.is_some_and(|max| quantity > max) {
+                return Err(format!("Quantity exceeds the maximum for {}", symbol));
+            }
+            if let (Some(min), Some(price)) = (filters.min_notional, price) {
+                if quantity * price < min {
+                    return Err(format!("Notional is below the minimum for {}", symbol));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 验证订单
+    fn validate_order(&self, order: &Order) -> Result<(), String> {
+        let quantity = order.quote_quantity.is_none().then_some(order.quantity);
+        self.check_listing(&order.symbol, quantity, order.price)?;
+        // 数值字段先检查有限性、范围和精度：NaN 和无穷大会破坏价格键和成交额累加
+        let numeric = |field: &str, result: Result<(), String>| {
+            result.map_err(|reason| format!("{} {}", field, reason))
+        };
+        if order.quote_quantity.is_none() {
+            numeric("Order quantity", check_quantity(order.quantity))?;
+        }
+        let scale = self.price_scale(&order.symbol);
+        if let Some(price) = order.price {
+            numeric("Order price", check_price(price))?;
+            scale.to_key(price)?;
+        }
+        if let Some(stop_price) = order.stop_price {
+            numeric("Stop price", check_price(stop_price))?;
+            scale.to_key(stop_price)?;
+        }
+        if let Some(quote_quantity) = order.quote_quantity {
+            numeric("Quote quantity", check_quantity(quote_quantity))?;
+        }
+        if let Some(min_fill_qty) = order.min_fill_qty {
+            numeric("Minimum fill quantity", check_quantity(min_fill_qty))?;
+        }
+        match order.trailing_offset {
+            Some(TrailingOffset::Amount(amount)) => {
+                numeric("Trailing offset", check_price(amount))?;
+            }
+            Some(TrailingOffset::Percent(percent)) if !percent.is_finite() => {
+                return Err("Trailing offset must be a finite number".to_string());
+            }
+            _ => {}
+        }
+        if order.max_slippage_bps.is_some_and(|bps| !bps.is_finite()) {
+            return Err("Max slippage must be a finite number".to_string());
+        }
+        if let Some(tag) = &order.tag {
+            numeric("Order tag", check_tag(tag))?;
+        }
+
+        if order.quote_quantity.is_some() {
+            if order.order_type != OrderType::Market {
+                return Err("Only market orders can use a quote quantity".to_string());
+            }
+            if order.quantity != 0.0 {
+                return Err("Quote quantity orders cannot also set a base quantity".to_string());
+            }
+            if order.reduce_only {
+                return Err("Quote quantity orders cannot be reduce-only".to_string());
+            }
+        }
+
+        if let Some(min_fill_qty) = order.min_fill_qty {
+            if order.order_type == OrderType::Market {
+                return Err("Market orders cannot set a minimum fill quantity".to_string());
+            }
+            if min_fill_qty > order.quantity {
+                return Err("Minimum fill quantity must not exceed the order quantity".to_string());
+            }
+        }
+
+        if let Some(max_slippage_bps) = order.max_slippage_bps {
+            if order.order_type != OrderType::Market {
+                return Err("Only market orders can set a max slippage".to_string());
+            }
+            if max_slippage_bps <= 0.0 {
+                return Err("Max slippage must be positive".to_string());
+            }
+        }
+
+        if order.order_type == OrderType::Limit && order.price.is_none() {
+            return Err("Limit order must have a price".to_string());
+        }
+
+        if order.user_id.is_empty() {
+            return Err("User ID cannot be empty".to_string());
+        }
+
+        match order.order_type {
+            OrderType::StopLoss | OrderType::TakeProfit => match order.stop_price {
+                Some(_) => {}
+                None => return Err("Stop order must have a stop price".to_string()),
+            },
+            OrderType::TrailingStop => match order.trailing_offset {
+                Some(TrailingOffset::Amount(_)) => {}
+                Some(TrailingOffset::Percent(percent)) if percent > 0.0 && percent < 100.0 => {}
+                Some(_) => return Err("Trailing offset is out of range".to_string()),
+                None => return Err("Trailing stop order must have a trailing offset".to_string()),
+            },
+            OrderType::Limit | OrderType::Market => {
+                if order.stop_price.is_some() || order.trailing_offset.is_some() {
+                    return Err(
+                        "Only stop orders can have a stop price or trailing offset".to_string()
+                    );
+                }
+            }
+        }
+
+        match (order.time_in_force, order.expires_at) {
+            (TimeInForce::GoodTillDate, Some(expires_at)) => {
+                if expires_at <= self.clock.now() {
+                    return Err("GTD order expiry must be in the future".to_string());
+                }
+            }
+            (TimeInForce::GoodTillDate, None) => {
+                return Err("GTD order must have an expiry time".to_string());
+            }
+            (TimeInForce::GoodTillCancel, Some(_)) => {
+                return Err("Only GTD orders can have an expiry time".to_string());
+            }
+            (TimeInForce::GoodTillCancel, None) => {}
+        }
+
+        Ok(())
+    }
+
+    /// 将挂单加入到期队列
+    fn schedule_expiry(&self, order_id: OrderId, expires_at: DateTime<Utc>) {
+        let is_earliest = {
+            let mut queue = self.expiry_queue.write();
+            queue.insert((expires_at, order_id));
+            queue.iter().next() == Some(&(expires_at, order_id))
+        };
+
+        // 新的最早到期时间需要唤醒调度任务重新计算休眠时长
+        if is_earliest {
+            self.expiry_notify.notify_one();
+        }
+    }
+
+    /// 按外部 UUID 别名查找订单ID
+    pub fn resolve_external_id(&self, external_id: Uuid) -> Option<OrderId> {
+        self.external_ids.read().get(&external_id).copied()
+    }
+
+    /// 按外部 UUID 别名获取订单信息
+    pub fn get_order_by_external_id(&self, external_id: Uuid) -> Option<Order> {
+        self.get_order(self.resolve_external_id(external_id)?)
+    }
+
+    /// 从归档中读取订单
+    fn get_archived_order(&self, order_id: OrderId) -> Option<Order> {
+        match self.archive.as_ref()?.get(order_id) {
+            Ok(order) => order,
+            Err(e) => {
+                warn!("Failed to read archived order {}: {}", order_id, e);
+                None
+            }
+        }
+    }
+
+    /// 将条件单加入触发簿
+    fn add_trigger_order(&self, order: Order) -> Result<(), String> {
+        let order_id = order.id;
+        let expires_at = order.expires_at;
+        let last_price = self.reference_price(&order.symbol, self.config.price_reference.triggers);
+
+        {
+            let mut triggers = self.triggers.write();
+            triggers
+                .entry(order.symbol.id())
+                .or_insert_with(|| TriggerBook::new(order.symbol))
+                .add_order(order.clone(), last_price)?;
+        }
+
+        if let Some(expires_at) = expires_at {
+            self.schedule_expiry(order_id, expires_at);
+        }
+
+        info!("Order {} added to trigger book", order_id);
+        self.publish_order(order);
+        Ok(())
+    }
+
+    /// 条件单以成交价为参考时，用新成交价驱动触发簿
+    async fn process_triggers(&self, symbol: &Symbol, trades: &[Trade]) {
+        // 以标记价格为参考时成交价不触发条件单
+        if self.config.price_reference.triggers == PriceReference::Mark {
+            return;
+        }
+        self.run_triggers(symbol, trades.iter().map(|trade| trade.price).collect())
+            .await;
+    }
+
+    /// 用参考价格驱动触发簿，触发的条件单转为市价单（有限价时转为限价单）参与撮合
+    ///
+    /// 以成交价为参考时，触发单成交产生的新价格会继续驱动触发簿，直到没有新的订单被触发
+    async fn run_triggers(&self, symbol: &Symbol, mut prices: Vec<f64>) {
+        while !prices.is_empty() {
+            let triggered: Vec<Order> = {
+                let mut triggers = self.triggers.write();
+                match triggers.get_mut(&symbol.id()) {
+                    Some(book) => prices
+                        .iter()
+                        .flat_map(|&price| book.on_trade(price))
+                        .collect(),
+                    None => return,
+                }
+            };
+            prices.clear();
+
+            for mut order in triggered {
+                // 触发过程中交易对被暂停（如熔断），放回触发簿等待恢复后的成交
+                let trading_state = self.get_trading_state(symbol);
+                if trading_state != TradingState::Trading {
+                    let restored = self
+                        .triggers
+                        .write()
+                        .get_mut(&symbol.id())
+                        .map(|book| book.add_order(order.clone(), None));
+                    if let Some(Err(e)) = restored {
+                        warn!("Rejecting triggered order {}: {}", order.id, e);
+                        self.close_order(order, OrderStatus::Rejected, e);
+                    }
+                    continue;
+                }
+
+                // 只减仓条件单在触发时按最新持仓重新校验，校验和撮合在同一次撮合锁内完成
+                let orderbook = self.get_or_create_orderbook(symbol);
+                let matching = orderbook.lock_matching().await;
+                if order.reduce_only {
+                    if let Err(e) = self.apply_reduce_only(&mut order) {
+                        drop(matching);
+                        warn!("Rejecting triggered order {}: {}", order.id, e);
+                        self.close_order(order, OrderStatus::Rejected, e);
+                        continue;
+                    }
+                }
+
+                order.order_type = if order.price.is_some() {
+                    OrderType::Limit
+                } else {
+                    OrderType::Market
+                };
+                info!(
+                    "Order {} triggered at stop price {:?}",
+                    order.id, order.stop_price
+                );
+                self.audit(
+                    order.id,
+                    AuditEventKind::Triggered {
+                        stop_price: order.stop_price,
+                    },
+                );
+
+                // 撮合失败时订单已不在触发簿，按拒绝关闭，避免残留为无簿可依的活跃订单
+                match self
+                    .execute_order(&orderbook, order.clone(), matching, None)
+                    .await
+                {
+                    Ok(trades) => {
+                        if self.config.price_reference.triggers == PriceReference::LastTrade {
+                            prices.extend(trades.iter().map(|trade| trade.price));
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Failed to execute triggered order {}: {}", order.id, e);
+                        self.close_order(order, OrderStatus::Rejected, e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// 收集订单簿和用户状态，执行风控检查
+    fn run_pre_trade_checks(&self, orderbook: &SafeOrderBook, order: &Order) -> Result<(), String> {
+        if self.pre_trade_checks.is_empty() {
+            return Ok(());
+        }
+
+        let book_stats = orderbook.get_stats();
+        let open_orders = self.get_open_user_orders(&order.user_id);
+        let balances = self.accounts.get_balances(&order.user_id);
+        let position = self.positions.get_position(&order.user_id, &order.symbol);
+
+        let result = self.pre_trade_checks.run(&PreTradeContext {
+            order,
+            book_stats: &book_stats,
+            open_orders: &open_orders,
+            balances: &balances,
+            position,
+        });
+        if let Err(reason) = &result {
+            warn!("Order {} rejected: {}", order.id, reason);
+        }
+        result
+    }
+
+    /// 用户未到终态的订单
+    fn get_open_user_orders(&self, user_id: &str) -> Vec<Order> {
+        self.get_open_orders(user_id, None)
+    }
+
+    /// 只减仓订单数量不得超过当前可减持仓，超出部分缩减，无可减持仓时拒绝
+    fn apply_reduce_only(&self, order: &mut Order) -> Result<(), String> {
+        let reducible =
+            self.positions
+                .reducible_quantity(&order.user_id, &order.symbol, order.side);
+        if reducible <= 0.0 {
+            return Err("Reduce-only order would increase position".to_string());
+        }
+
+        if order.remaining_quantity > reducible {
+            info!(
+                "Downsizing reduce-only order {} from {} to {}",
+                order.id, order.remaining_quantity, reducible
+            );
+            order.quantity -= order.remaining_quantity - reducible;
+            order.remaining_quantity = reducible;
+        }
+        Ok(())
+    }
+
+    /// 对成交双方重新校验只减仓挂单
+    async fn reevaluate_reduce_only_for_trades(&self, symbol: &Symbol, trades: &[Trade]) {
+        let mut users: Vec<&str> = trades
+            .iter()
+            .flat_map(|trade| [trade.buyer_id.as_str(), trade.seller_id.as_str()])
+            .collect();
+        users.sort_unstable();
+        users.dedup();
+
+        for user_id in users {
+            self.reevaluate_reduce_only(user_id, symbol).await;
+        }
+    }
+
+    /// 用户在交易对上挂单中的只减仓订单，从按用户维护的未完成订单索引读取
+    fn resting_reduce_only(&self, user_id: &str, symbol: &Symbol) -> Vec<Order> {
+        self.get_open_orders(user_id, Some(symbol))
+            .into_iter()
+            .filter(|order| order.reduce_only && !order.order_type.is_trigger())
+            .collect()
+    }
+
+    /// 按提交顺序重新分配可减持仓，超出部分缩减，无剩余额度的只减仓挂单被撤销
+    ///
+    /// 持有订单簿的撮合锁期间读取和修改挂单，调用方不能已持有该锁；
+    /// 用户在该交易对上没有只减仓挂单时不取锁直接返回
+    async fn reevaluate_reduce_only(&self, user_id: &str, symbol: &Symbol) {
+        if self.resting_reduce_only(user_id, symbol).is_empty() {
+            return;
+        }
+        let Some(orderbook) = self.get_orderbook(symbol) else {
+            return;
+        };
+        let _matching = orderbook.lock_matching().await;
+
+        // 等锁期间挂单可能已变化，持锁后重新读取
+        let mut resting = self.resting_reduce_only(user_id, symbol);
+        if resting.is_empty() {
+            return;
+        }
+        resting.sort_by_key(|order| order.timestamp);
+
+        for side in [OrderSide::Buy, OrderSide::Sell] {
+            let mut budget = self.positions.reducible_quantity(user_id, symbol, side);
+
+            for order in resting.iter().filter(|order| order.side == side) {
+                if order.remaining_quantity <= budget {
+                    budget -= order.remaining_quantity;
+                    continue;
+                }
+
+                if budget <= 0.0 {
+                    match orderbook.remove_order(order.id) {
+                        Ok(removed) => {
+                            info!(
+                                "Cancelling reduce-only order {}: no position left",
+                                order.id
+                            );
+                            self.close_order(
+                                removed,
+                                OrderStatus::Cancelled,
+                                "Reduce-only order has no position left".to_string(),
+                            );
+                        }
+                        Err(e) => warn!("Failed to cancel reduce-only order {}: {}", order.id, e),
+                    }
+                    continue;
+                }
+
+                match orderbook.reduce_order(order.id, order.remaining_quantity - budget) {
+                    Ok(reduced) => {
+                        info!(
+                            "Downsized reduce-only order {} to {}",
+                            order.id, reduced.remaining_quantity
+                        );
+                        self.orders.write().insert(reduced.id, reduced.clone());
+                        self.audit(
+                            reduced.id,
+                            AuditEventKind::Amended {
+                                old_remaining_quantity: order.remaining_quantity,
+                                new_remaining_quantity: reduced.remaining_quantity,
+                                reason: "Reduce-only order downsized to position".to_string(),
+                            },
+                        );
+                        self.publish_order(reduced);
+                    }
+                    Err(e) => warn!("Failed to downsize reduce-only order {}: {}", order.id, e),
+                }
+                budget = 0.0;
+            }
+        }
+    }
+
+    /// 将已移出订单簿或触发簿的订单置为撤销或拒绝，更新存储、审计和统计并广播
+    fn close_order(&self, mut order: Order, status: OrderStatus, reason: String) {
+        order.status = status;
+        self.orders.write().insert(order.id, order.clone());
+        match status {
+            OrderStatus::Rejected => self.audit(
+                order.id,
+                AuditEventKind::Rejected {
+                    reason,
+                    request_id: None,
+                    channel: order.channel,
+                },
+            ),
+            _ => self.audit(order.id, AuditEventKind::Cancelled { reason }),
+        }
+        {
+            let mut stats = self.stats.write();
+            stats.active_orders = stats.active_orders.saturating_sub(1);
+        }
+        self.publish_order(order);
+    }
+
+    /// 记录订单审计事件，未启用审计时忽略
+    fn audit(&self, order_id: OrderId, kind: AuditEventKind) {
+        if let Some(audit) = &self.audit {
+            if let Err(e) = audit.record(order_id, kind) {
+                warn!("Failed to audit order {}: {}", order_id, e);
+            }
+        }
+    }
+
+    /// 记录下单到受理的延迟，未启用延迟统计时忽略
+    fn record_ack_latency(&self, symbol: &Symbol, submitted_at: Instant) {
+        if let Some(latency) = &self.latency {
+            latency.record_ack(symbol, submitted_at.elapsed());
+        }
+    }
+
+    /// 撤销已登记的订单：移除外部ID别名和存储的订单，回退下单统计，并记录拒绝
+    ///
+    /// 用于订单登记后进入触发簿或撮合失败的情况
+    fn rollback_submission(&self, order: &Order, reason: &str) {
+        if let Some(external_id) = order.external_id {
+            self.external_ids.write().remove(&external_id);
+        }
+        self.orders.write().remove(&order.id);
+        {
+            let mut stats = self.stats.write();
+            stats.total_orders = stats.total_orders.saturating_sub(1);
+            stats.active_orders = stats.active_orders.saturating_sub(1);
+        }
+        if let Some(counters) = self.symbol_counters.write().get_mut(&order.symbol.id()) {
+            counters.total_orders = counters.total_orders.saturating_sub(1);
+        }
+        warn!("Order {} rolled back: {}", order.id, reason);
+        self.record_rejected(order, reason);
+    }
+
+    /// 记录未被接受的订单：写入审计日志并在事件总线上发布拒绝事件
+    fn record_rejected(&self, order: &Order, reason: &str) {
+        self.audit(
+            order.id,
+            AuditEventKind::Rejected {
+                reason: reason.to_string(),
+                request_id: request_id::current(),
+                channel: order.channel,
+            },
+        );
+        counter!(
+            "matching_engine_orders_by_channel_total",
+            "channel" => order.channel.as_str(),
+            "result" => "rejected"
+        )
+        .increment(1);
+        let mut order = order.clone();
+        order.status = OrderStatus::Rejected;
+        self.event_bus.publish(
+            EngineEvent::OrderRejected {
+                order,
+                reason: reason.to_string(),
+            },
+            self.now(),
+        );
+    }
+
+    /// 广播订单更新，先唤醒等待该订单的调用方
+    fn publish_order(&self, order: Order) {
+        {
+            let mut watches = self.order_watches.lock();
+            let watch = if order.status.is_terminal() {
+                watches.remove(&order.id)
+            } else {
+                watches.get(&order.id).cloned()
+            };
+            if let Some(watch) = watch {
+                watch.send_replace(order.clone());
+            }
+        }
+        self.event_bus
+            .publish(EngineEvent::OrderUpdate(order), self.now());
+    }
+
+    /// 广播成交
+    fn publish_trade(&self, trade: Trade) {
+        self.event_bus
+            .publish(EngineEvent::Trade(trade), self.now());
+    }
+
+    /// 以场外成交事件广播，不进入撮合成交的订阅方；drop copy 照常收到
+    fn publish_otc_trade(&self, trade: Trade) {
+        self.event_bus
+            .publish(EngineEvent::OtcTrade(trade), self.now());
+    }
+
+    /// 分配交易对的下一个场外成交申报序号
+    fn next_otc_sequence(&self, symbol: &Symbol) -> u64 {
+        let mut symbol_counters = self.symbol_counters.write();
+        let counters = symbol_counters.entry(symbol.id()).or_default();
+        counters.otc_trades += 1;
+        counters.otc_trades
+    }
+
+    /// 成交是否计入成交笔数和成交量统计，场外成交按配置决定
+    fn counts_in_volume(&self, trade: &Trade) -> bool {
+        trade.trade_type == TradeType::Regular || self.config.otc.include_in_volume
+    }
+
+    /// 成交是否可作为最新成交价，场外成交默认不参与
+    fn sets_last_price(&self, trade: &Trade) -> bool {
+        trade.trade_type == TradeType::Regular || self.config.otc.update_last_price
+    }
+
+    /// 记录一笔成交对订单的影响，剩余数量为 0 时记为完全成交
+    fn audit_fill(
+        &self,
+        trade: &Trade,
+        order_id: OrderId,
+        counterparty_order_id: OrderId,
+        remaining_quantity: f64,
+    ) {
+        if self.audit.is_none() {
+            return;
+        }
+
+        let fill = AuditFill {
+            trade_id: trade.id,
+            counterparty_order_id,
+            quantity: trade.quantity,
+            price: trade.price,
+            remaining_quantity: remaining_quantity.max(0.0),
+        };
+        let kind = if remaining_quantity <= 0.0 {
+            AuditEventKind::Filled(fill)
+        } else {
+            AuditEventKind::PartiallyFilled(fill)
+        };
+        self.audit(order_id, kind);
+    }
+
+    /// 获取交易对订单簿的撮合锁，订单簿不存在时返回 None
+    async fn lock_matching(&self, symbol: &Symbol) -> Option<OwnedMutexGuard<()>> {
+        match self.get_orderbook(symbol) {
+            Some(orderbook) => Some(orderbook.lock_matching().await),
+            None => None,
+        }
+    }
+
+    /// 从触发簿或订单簿中移除未完成的订单
+    fn remove_open_order(&self, order: &Order) -> Result<Order, String> {
+        if order.order_type.is_trigger() {
+            let mut triggers = self.triggers.write();
+            if let Some(removed) = triggers
+                .get_mut(&order.symbol.id())
+                .and_then(|book| book.remove_order(order.id))
+            {
+                return Ok(removed);
+            }
+        }
+
+        self.get_orderbook(&order.symbol)
+            .ok_or_else(|| "Orderbook not found".to_string())?
+            .remove_order(order.id)
+    }
+
+    /// 获取交易对最新成交价
+    fn last_trade_price(&self, symbol: &Symbol) -> Option<f64> {
+        self.trades
+            .read()
+            .last_matching(symbol, |trade| self.sets_last_price(trade))
+            .map(|trade| trade.price)
+    }
+
+    /// 获取或创建订单簿
+    fn get_or_create_orderbook(&self, symbol: &Symbol) -> SafeOrderBook {
+        let mut orderbooks = self.orderbooks.write();
+        orderbooks.entry(symbol.id()).or_insert_with(|| {
+            SafeOrderBook::with_event_bus(
+                OrderBook::with_clock(*symbol, self.clock.clone())
+                    .with_price_scale(self.price_scale(symbol)),
+                self.event_bus.clone(),
+            )
+        });
+        orderbooks.get(&symbol.id()).unwrap().clone()
+    }
+
+    /// 交易对的价格换算精度；tick 配置无效时退回默认精度（启动时已由配置校验拦截）
+    fn price_scale(&self, symbol: &Symbol) -> PriceScale {
+        let tick_size = self
+            .listings
+            .read()
+            .get(&symbol.id())
+            .and_then(|listing| listing.filters.tick_size)
+            .unwrap_or_else(|| self.config.tick_sizes.tick_size_for(symbol));
+        PriceScale::from_tick_size(tick_size).unwrap_or_else(|e| {
+            warn!("Invalid tick size for {}: {}", symbol, e);
+            PriceScale::default()
+        })
+    }
+
+    /// 获取订单簿
+    fn get_orderbook(&self, symbol: &Symbol) -> Option<SafeOrderBook> {
+        self.orderbooks.read().get(&symbol.id()).cloned()
+    }
+
+    /// 撮合订单
+    ///
+    /// 先按撮合规则计算全部可成交的挂单（见 [`plan_fills`](Self::plan_fills)），吃单设置了最小成交数量时
+    /// 以此校验，再逐笔修改订单簿并生成成交
+    #[instrument(name = "match", skip_all)]
+    async fn match_order(
+        &self,
+        orderbook: &SafeOrderBook,
         incoming_order: &mut Order,
     ) -> Result<Vec<Trade>, String> {
         let mut trades = Vec::new();
+
+        // 获取匹配的订单
+        let matching_orders = orderbook.get_matching_orders(incoming_order);
+        let fills = self.plan_fills(incoming_order, &matching_orders);
+
+        // 吃单设置了最小成交数量时，按规则实际可成交的数量不足则不撮合，直接挂单
+        let min_fill = incoming_order.min_fill_threshold();
+        if min_fill > 0.0 {
+            let executable: f64 = fills.iter().map(|fill| fill.quantity).sum();
+            if executable < min_fill {
+                info!(
+                    "Order {} not matched: executable {} below minimum fill {}",
+                    incoming_order.id, executable, min_fill
+                );
+                return Ok(trades);
+            }
+        }
+
+        for PlannedFill {
+            order: matching_order,
+            quantity: match_quantity,
+            price: match_price,
+            remaining_quantity,
+        } in fills
+        {
+            // 先修改订单簿再生成成交，已广播的成交一定已经落到订单簿上；
+            // 撮合锁保证快照中的挂单仍在订单簿中，修改失败时停止撮合，已完成的成交保留
+            let new_matching_quantity = matching_order.remaining_quantity - match_quantity;
+            let updated = match self.fill_resting_order(
+                orderbook,
+                matching_order.id,
+                new_matching_quantity,
+            ) {
+                Ok(updated) => updated,
+                Err(e) if trades.is_empty() => return Err(e),
+                Err(e) => {
+                    warn!(
+                        "Stopped matching order {} after {} trades at resting order {}: {}",
+                        incoming_order.id,
+                        trades.len(),
+                        matching_order.id,
+                        e
+                    );
+                    break;
+                }
+            };
+            self.orders.write().insert(updated.id, updated.clone());
+
+            // 创建交易
+            let trade = Trade::new_with_id(
+                self.ids.next_id(),
+                self.clock.now(),
+                incoming_order.symbol,
+                incoming_order,
+                matching_order,
+                match_quantity,
+                match_price,
+            )
+            .with_taker_side(incoming_order.side);
+
+            // 更新订单数量
+            incoming_order.filled_quantity += match_quantity;
+            incoming_order.remaining_quantity = remaining_quantity;
+
+            self.audit_fill(
+                &trade,
+                incoming_order.id,
+                matching_order.id,
+                remaining_quantity,
+            );
+            self.audit_fill(
+                &trade,
+                matching_order.id,
+                incoming_order.id,
+                new_matching_quantity,
+            );
+
+            // 存储交易、更新统计信息和持仓
+            self.store_trade(&trade, Some(incoming_order.id));
+
+            // 广播交易；挂单的订单更新在成交之后广播，备用实例按成交同步挂单的剩余数量
+            self.publish_trade(trade.clone());
+            self.publish_order(updated);
+            let trade_id = trade.id;
+            trades.push(trade);
+
+            info!(
+                "Trade executed: {} {} at {} for {}",
+                match_quantity,
+                incoming_order.symbol.to_string(),
+                match_price,
+                trade_id
+            );
+
+            // 触发熔断后停止继续撮合，剩余数量挂入订单簿
+            if self.check_circuit_breaker(&incoming_order.symbol, match_price) {
+                break;
+            }
+        }
+
+        // 按金额下单的订单数量即实际成交数量
+        if incoming_order.quote_quantity.is_some() {
+            incoming_order.quantity = incoming_order.filled_quantity;
+            incoming_order.remaining_quantity = 0.0;
+        }
+
+        Ok(trades)
+    }
+
+    /// 按撮合规则计算吃单依次与哪些挂单成交、成交数量和价格，不修改任何状态
+    ///
+    /// 依次应用滑点上限、按金额下单的剩余金额和各档位的分配算法（低于挂单自身最小成交数量的分配被跳过），
+    /// 并按当前熔断参考价模拟熔断：会触发熔断的成交之后不再撮合
+    fn plan_fills<'a>(
+        &self,
+        incoming_order: &Order,
+        matching_orders: &'a [OrderBookEntry],
+    ) -> Vec<PlannedFill<'a>> {
+        let mut fills = Vec::new();
         let mut remaining_quantity = incoming_order.remaining_quantity;
+        let algorithm = self.config.allocation.algorithm_for(&incoming_order.symbol);
+        let symbol = incoming_order.symbol;
+        let mut breaker = self.circuit_breakers.read().get(&symbol.id()).cloned();
+        let now = self.clock.instant();
+
+        // 市价单保护：滑点上限对应的最差成交价
+        let side = incoming_order.side;
+        let slippage_limit = incoming_order.max_slippage_bps.and_then(|bps| {
+            let best_price = matching_orders.first()?.order.price?;
+            Some(match side {
+                OrderSide::Buy => best_price * (1.0 + bps / 10_000.0),
+                OrderSide::Sell => best_price * (1.0 - bps / 10_000.0),
+            })
+        });
+        // 按金额下单时剩余的计价货币金额
+        let mut remaining_quote = incoming_order.quote_quantity;
+
+        // 按价格档位撮合，档位内按分配算法计算每个挂单的成交数量
+        'levels: for level in matching_orders.chunk_by(|a, b| a.order.price == b.order.price) {
+            let level_price = level[0].order.price;
+
+            // 超出滑点上限的价位不再撮合
+            if let (Some(limit), Some(price)) = (slippage_limit, level_price) {
+                let beyond_limit = match side {
+                    OrderSide::Buy => price > limit,
+                    OrderSide::Sell => price < limit,
+                };
+                if beyond_limit {
+                    info!(
+                        "Order {} stopped at {} by max slippage limit {}",
+                        incoming_order.id, price, limit
+                    );
+                    break;
+                }
+            }
+
+            // 按金额下单时，本档位可成交数量由剩余金额折算
+            if let (Some(quote), Some(price)) = (remaining_quote, level_price) {
+                remaining_quantity = allocation::floor_quantity(quote / price);
+            }
+
+            if remaining_quantity <= 0.0 {
+                break;
+            }
+
+            // 检查是否可以匹配
+            let level_orders: Vec<&Order> = level
+                .iter()
+                .map(|entry| &entry.order)
+                .filter(|order| incoming_order.can_match(order))
+                .collect();
+            let allocations = allocation::allocate(
+                algorithm,
+                &self.config.allocation,
+                &level_orders,
+                remaining_quantity,
+            );
+
+            for (order, quantity) in level_orders.into_iter().zip(allocations) {
+                if quantity <= 0.0 {
+                    continue;
+                }
+
+                let price = self.trade_price(incoming_order, order);
+                remaining_quantity -= quantity;
+                if let Some(quote) = remaining_quote.as_mut() {
+                    *quote -= quantity * price;
+                }
+                fills.push(PlannedFill {
+                    order,
+                    quantity,
+                    price,
+                    remaining_quantity,
+                });
+
+                if self.config.circuit_breaker.enabled
+                    && self.circuit_breaker_move(&symbol, price, &mut breaker, now)
+                        > self.config.circuit_breaker.max_price_move
+                {
+                    break 'levels;
+                }
+            }
+        }
+
+        fills
+    }
+
+    /// 集合竞价撮合：按价格优先、时间优先，以统一价格成交 `volume` 数量
+    ///
+    /// 调用方须持有订单簿的撮合锁。每笔成交先修改订单簿再广播，订单簿修改失败时停止撮合，
+    /// 返回已完成的成交和失败原因
+    fn uncross(
+        &self,
+        orderbook: &SafeOrderBook,
+        symbol: &Symbol,
+        price: f64,
+        volume: f64,
+    ) -> (Vec<Trade>, Option<String>) {
+        let bids = orderbook.get_crossing_orders(OrderSide::Buy, price);
+        let asks = orderbook.get_crossing_orders(OrderSide::Sell, price);
+
+        let mut trades = Vec::new();
+        let mut remaining_volume = volume;
+        let mut bid_iter = bids.into_iter().map(|entry| entry.order);
+        let mut ask_iter = asks.into_iter().map(|entry| entry.order);
+        let mut bid = bid_iter.next();
+        let mut ask = ask_iter.next();
+
+        while remaining_volume > 0.0 {
+            let (Some(buy_order), Some(sell_order)) = (bid.as_mut(), ask.as_mut()) else {
+                break;
+            };
+            for order_id in [buy_order.id, sell_order.id] {
+                if !orderbook.contains_order(order_id) {
+                    return (
+                        trades,
+                        Some(format!("Order {} is no longer in the orderbook", order_id)),
+                    );
+                }
+            }
+
+            let match_quantity = remaining_volume
+                .min(buy_order.remaining_quantity)
+                .min(sell_order.remaining_quantity);
+            remaining_volume -= match_quantity;
+            buy_order.remaining_quantity -= match_quantity;
+            sell_order.remaining_quantity -= match_quantity;
+
+            let mut updated_orders = Vec::with_capacity(2);
+            for order in [&*buy_order, &*sell_order] {
+                match self.fill_resting_order(orderbook, order.id, order.remaining_quantity) {
+                    Ok(updated) => updated_orders.push(updated),
+                    Err(e) => return (trades, Some(e)),
+                }
+            }
+
+            let trade = Trade::new_with_id(
+                self.ids.next_id(),
+                self.clock.now(),
+                *symbol,
+                buy_order,
+                sell_order,
+                match_quantity,
+                price,
+            );
+            self.audit_fill(
+                &trade,
+                buy_order.id,
+                sell_order.id,
+                buy_order.remaining_quantity,
+            );
+            self.audit_fill(
+                &trade,
+                sell_order.id,
+                buy_order.id,
+                sell_order.remaining_quantity,
+            );
+            {
+                let mut orders = self.orders.write();
+                for updated in &updated_orders {
+                    orders.insert(updated.id, updated.clone());
+                }
+            }
+            self.store_trade(&trade, None);
+
+            // 先广播成交再广播双方订单更新，备用实例按成交同步挂单的剩余数量
+            self.publish_trade(trade.clone());
+            for updated in updated_orders {
+                self.publish_order(updated);
+            }
+
+            let buy_filled = buy_order.remaining_quantity <= 0.0;
+            let sell_filled = sell_order.remaining_quantity <= 0.0;
+            trades.push(trade);
+
+            if buy_filled {
+                bid = bid_iter.next();
+            }
+            if sell_filled {
+                ask = ask_iter.next();
+            }
+        }
+
+        (trades, None)
+    }
+
+    /// 按成交后的剩余数量更新订单簿中的挂单，完全成交时移出订单簿，返回更新后的订单
+    fn fill_resting_order(
+        &self,
+        orderbook: &SafeOrderBook,
+        order_id: OrderId,
+        remaining_quantity: f64,
+    ) -> Result<Order, String> {
+        if remaining_quantity > 0.0 {
+            return orderbook.update_order(order_id, remaining_quantity);
+        }
+
+        let mut filled_order = orderbook.remove_order(order_id)?;
+        filled_order.status = OrderStatus::Filled;
+        filled_order.filled_quantity = filled_order.quantity;
+        filled_order.remaining_quantity = 0.0;
+
+        let mut stats = self.stats.write();
+        stats.active_orders = stats.active_orders.saturating_sub(1);
+        Ok(filled_order)
+    }
+
+    /// 合并一次撮合产生的成交并逐条广播
+    fn publish_agg_trades(&self, trades: &[Trade]) {
+        for agg_trade in aggregate_trades(trades) {
+            let timestamp = agg_trade.timestamp;
+            self.event_bus
+                .publish(EngineEvent::AggTrade(agg_trade), timestamp);
+        }
+    }
+
+    /// 推送集合竞价参考价
+    fn broadcast_auction_indicative(&self, symbol: &Symbol) {
+        self.event_bus.publish(
+            EngineEvent::Auction(self.get_auction_indicative(symbol)),
+            self.now(),
+        );
+    }
+
+    /// 市价单撮合后是否还有未成交部分
+    ///
+    /// 按金额下单时，剩余金额不足以按最后成交价买入最小数量单位即视为全部成交
+    fn has_unfilled_remainder(&self, order: &Order, trades: &[Trade]) -> bool {
+        match order.quote_quantity {
+            Some(quote_quantity) => {
+                let spent: f64 = trades
+                    .iter()
+                    .map(|trade| trade.quantity * trade.price)
+                    .sum();
+                match trades.last() {
+                    Some(last) => {
+                        allocation::floor_quantity((quote_quantity - spent) / last.price) > 0.0
+                    }
+                    None => true,
+                }
+            }
+            None => order.remaining_quantity > 0.0,
+        }
+    }
+
+    /// 保存成交，为双方记录用户成交并更新持仓
+    ///
+    /// `taker_order_id` 为吃单方订单，集合竞价成交没有吃单方，双方均为挂单方
+    fn store_trade(&self, trade: &Trade, taker_order_id: Option<OrderId>) {
+        self.trades.write().push(trade.clone());
+        if let Some(history) = &self.trade_history {
+            if let Err(e) = history.append(std::slice::from_ref(trade)) {
+                warn!("Failed to write trade {} to history: {}", trade.id, e);
+            }
+        }
+
+        if self.counts_in_volume(trade) {
+            {
+                let mut symbol_counters = self.symbol_counters.write();
+                let counters = symbol_counters.entry(trade.symbol.id()).or_default();
+                counters.total_trades += 1;
+                counters.volume += trade.quantity;
+                counters.quote_volume += trade.quantity * trade.price;
+            }
+            self.volume_stats.record(trade);
+            let mut stats = self.stats.write();
+            stats.total_trades += 1;
+            stats.total_volume += trade.quantity * trade.price;
+        }
+
+        let mut fees = [0.0; 2];
+        {
+            let mut fills = self.fills.write();
+            let sides = [
+                (
+                    OrderSide::Buy,
+                    trade.buy_order_id,
+                    &trade.buyer_id,
+                    &trade.buy_tag,
+                ),
+                (
+                    OrderSide::Sell,
+                    trade.sell_order_id,
+                    &trade.seller_id,
+                    &trade.sell_tag,
+                ),
+            ];
+            for (fee, (side, order_id, user_id, tag)) in fees.iter_mut().zip(sides) {
+                if let Some(message_rates) = &self.message_rates {
+                    message_rates.record_fill(user_id, trade.timestamp);
+                }
+                let (maker_rate, taker_rate) = self.fee_schedule.rates(user_id, trade.timestamp);
+                // 场外成交没有吃单方，也不收取撮合手续费
+                let (role, rate) = if taker_order_id == Some(order_id) {
+                    (LiquidityRole::Taker, taker_rate)
+                } else if trade.trade_type == TradeType::Otc {
+                    (LiquidityRole::Maker, 0.0)
+                } else {
+                    (LiquidityRole::Maker, maker_rate)
+                };
+                let fill = Fill {
+                    trade_id: trade.id,
+                    order_id,
+                    symbol: trade.symbol,
+                    side,
+                    price: trade.price,
+                    quantity: trade.quantity,
+                    role,
+                    fee: trade.price * trade.quantity * rate,
+                    fee_asset: trade.symbol.quote().to_string(),
+                    timestamp: trade.timestamp,
+                    tag: tag.clone(),
+                };
+                *fee = fill.fee;
+                if let Some(fee_ledger) = &self.fee_ledger {
+                    fee_ledger.record_fill(user_id, &fill);
+                }
+                fills.record(user_id, fill);
+            }
+        }
+        // 按成交前的档位计费后再累加成交额
+        if self.counts_in_volume(trade) {
+            for user_id in [&trade.buyer_id, &trade.seller_id] {
+                self.fee_schedule
+                    .record(user_id, trade.quantity * trade.price, trade.timestamp);
+            }
+        }
+
+        self.positions.apply_trade(trade);
+        if let Some(sandbox) = &self.sandbox {
+            sandbox.settle_trade(&self.accounts, trade, fees);
+        }
+    }
+
+    /// 按配置的成交价规则计算吃单与挂单的成交价
+    ///
+    /// 一方没有价格（市价单）时使用另一方的价格
+    fn trade_price(&self, taker: &Order, maker: &Order) -> f64 {
+        self.trade_price_between(taker.price, maker.price)
+    }
+
+    fn trade_price_between(&self, taker_price: Option<f64>, maker_price: Option<f64>) -> f64 {
+        match (taker_price, maker_price) {
+            (Some(taker_price), Some(maker_price)) => match self.config.trade_price_rule {
+                TradePriceRule::Maker => maker_price,
+                TradePriceRule::Taker => taker_price,
+                TradePriceRule::Midpoint => (taker_price + maker_price) / 2.0,
+            },
+            (Some(price), None) | (None, Some(price)) => price,
+            (None, None) => 0.0,
+        }
+    }
+
+    /// 检查成交价是否触发熔断，触发时暂停交易对并在冷却期后自动恢复
+    ///
+    /// 熔断以标记价格为参考且交易对已有标记价格时，比较成交价相对标记价格的偏离，否则比较窗口内的参考成交价
+    fn check_circuit_breaker(&self, symbol: &Symbol, price: f64) -> bool {
+        let config = &self.config.circuit_breaker;
+        if !config.enabled {
+            return false;
+        }
+
+        let now = self.clock.instant();
+        let price_move = {
+            let mut breakers = self.circuit_breakers.write();
+            let mut breaker = breakers.get(&symbol.id()).cloned();
+            let price_move = self.circuit_breaker_move(symbol, price, &mut breaker, now);
+            if let Some(breaker) = breaker {
+                breakers.insert(symbol.id(), breaker);
+            }
+            price_move
+        };
+
+        if price_move <= config.max_price_move {
+            return false;
+        }
+
+        let tripped_state = if config.cancel_only {
+            TradingState::CancelOnly
+        } else {
+            TradingState::Halted
+        };
+        let reason = format!(
+            "Circuit breaker: price {} moved {:.2}% from reference",
+            price, price_move
+        );
+        warn!("{} tripped for {}", reason, symbol);
+
+        counter!("matching_engine_circuit_breaker_trips_total", "symbol" => symbol.to_string())
+            .increment(1);
+        // 熔断后重置参考价，恢复交易时从触发价重新计算；先记录本次触发再暂停交易，
+        // 此前的冷却任务不会把本次熔断当作自己的
+        let trip = {
+            let mut breakers = self.circuit_breakers.write();
+            let trip = breakers
+                .get(&symbol.id())
+                .map_or(1, |breaker| breaker.trips + 1);
+            breakers.insert(
+                symbol.id(),
+                CircuitBreakerState {
+                    reference_price: price,
+                    window_start: now,
+                    trips: trip,
+                },
+            );
+            trip
+        };
+        self.set_trading_state(symbol, tripped_state, Some(reason));
+
+        // 冷却期结束后，若状态未被人工调整且期间没有再次熔断则自动恢复交易
+        let circuit_breakers = self.circuit_breakers.clone();
+        let trading_states = self.trading_states.clone();
+        let event_bus = self.event_bus.clone();
+        let clock = self.clock.clone();
+        let resume_at = clock.now()
+            + chrono::Duration::seconds(config.cooldown_seconds.min(i64::MAX as u64) as i64);
+        let symbol = *symbol;
+        tokio::spawn(async move {
+            clock.sleep_until(resume_at).await;
+            let breakers = circuit_breakers.read();
+            let same_trip = breakers
+                .get(&symbol.id())
+                .is_some_and(|breaker| breaker.trips == trip);
+            let still_tripped = trading_states.read().get(&symbol.id()) == Some(&tripped_state);
+            if same_trip && still_tripped {
+                apply_trading_state(
+                    &trading_states,
+                    &event_bus,
+                    clock.as_ref(),
+                    &symbol,
+                    TradingState::Trading,
+                    Some("Circuit breaker cooldown elapsed".to_string()),
+                );
+            }
+        });
+
+        true
+    }
+
+    /// 成交价相对熔断参考价的变动百分比
+    ///
+    /// 以标记价格为参考且已有标记价格时与标记价格比较；否则以 `breaker` 中的参考价比较，
+    /// 没有参考价或窗口已结束时以当前成交价开始新窗口
+    fn circuit_breaker_move(
+        &self,
+        symbol: &Symbol,
+        price: f64,
+        breaker: &mut Option<CircuitBreakerState>,
+        now: Instant,
+    ) -> f64 {
+        let mark_reference = match self.config.price_reference.circuit_breaker {
+            PriceReference::Mark => self.current_mark_price(symbol),
+            PriceReference::LastTrade => None,
+        };
+        if let Some(mark) = mark_reference {
+            return ((price - mark) / mark).abs() * 100.0;
+        }
+
+        let window = Duration::from_secs(self.config.circuit_breaker.window_seconds);
+        let breaker = breaker.get_or_insert(CircuitBreakerState {
+            reference_price: price,
+            window_start: now,
+            trips: 0,
+        });
+        // 窗口结束后以当前成交价作为新的参考价
+        if now.duration_since(breaker.window_start) >= window {
+            breaker.reference_price = price;
+            breaker.window_start = now;
+        }
+
+        ((price - breaker.reference_price) / breaker.reference_price).abs() * 100.0
+    }
+
+    /// 根据最近成交重新计算交易对的市场数据并保存，交易对没有订单簿时返回 None
+    fn refresh_market_data(&self, symbol: &Symbol) -> Option<MarketData> {
+        self.get_orderbook(symbol)?;
+
+        // 获取最近的交易来计算24小时数据
+        let mut recent_trades = self.get_trades(Some(symbol), Some(1000));
+
+        let mut volume_24h = 0.0;
+        let mut high_24h: f64 = 0.0;
+        let mut low_24h: f64 = f64::MAX;
+        let mut last_price = 0.0;
+
+        if !self.config.otc.include_in_volume {
+            recent_trades.retain(|trade| trade.trade_type == TradeType::Regular);
+        }
+        for trade in &recent_trades {
+            volume_24h += trade.quantity * trade.price;
+        }
+        // 价格指标只取可作为最新成交价的成交
+        recent_trades.retain(|trade| self.sets_last_price(trade));
+        for trade in &recent_trades {
+            high_24h = high_24h.max(trade.price);
+            low_24h = low_24h.min(trade.price);
+        }
+        // 成交按ID从新到旧排列
+        if let Some(latest) = recent_trades.first() {
+            last_price = latest.price;
+        }
+
+        if low_24h == f64::MAX {
+            low_24h = 0.0;
+        }
+
+        // 计算24小时价格变化
+        let price_change_24h = if recent_trades.len() > 1 {
+            let first_price = recent_trades.last().unwrap().price;
+            ((last_price - first_price) / first_price) * 100.0
+        } else {
+            0.0
+        };
+
+        let market_data = MarketData {
+            symbol: *symbol,
+            last_price,
+            volume_24h,
+            price_change_24h,
+            high_24h,
+            low_24h,
+            timestamp: self.clock.now(),
+        };
+
+        self.market_data
+            .write()
+            .insert(symbol.id(), market_data.clone());
+        Some(market_data)
+    }
+}
+
+/// 两次市场数据除时间戳外是否相同
+fn same_market_data(a: &MarketData, b: &MarketData) -> bool {
+    a.symbol == b.symbol
+        && a.last_price == b.last_price
+        && a.volume_24h == b.volume_24h
+        && a.price_change_24h == b.price_change_24h
+        && a.high_24h == b.high_24h
+        && a.low_24h == b.low_24h
+}
+
+/// 更新交易对交易状态并广播，供引擎方法和后台任务共用
+fn apply_trading_state(
+    trading_states: &RwLock<HashMap<SymbolId, TradingState>>,
+    event_bus: &EventBus,
+    clock: &dyn Clock,
+    symbol: &Symbol,
+    state: TradingState,
+    reason: Option<String>,
+) -> SymbolStatus {
+    {
+        let mut trading_states = trading_states.write();
+        trading_states.insert(symbol.id(), state);
+    }
+
+    info!(
+        "Trading state for {} changed to {:?} ({})",
+        symbol,
+        state,
+        reason.as_deref().unwrap_or("no reason")
+    );
+
+    let status = SymbolStatus {
+        symbol: *symbol,
+        state,
+        reason,
+        timestamp: clock.now(),
+    };
+    event_bus.publish(EngineEvent::SymbolStatus(status.clone()), status.timestamp);
+    status
+}
+
+impl Default for MatchingEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+
+    #[tokio::test]
+    async fn test_matching_engine_basic_matching() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+
+        // 提交卖单
+        let sell_order = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::Limit,
+            1.0,
+            Some(50000.0),
+            "seller".to_string(),
+        );
+
+        let trades = engine.submit_order(sell_order).await.unwrap();
+        assert_eq!(trades.len(), 0); // 没有匹配的买单
+
+        // 提交买单
+        let buy_order = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(50000.0),
+            "buyer".to_string(),
+        );
+
+        let trades = engine.submit_order(buy_order).await.unwrap();
+        assert_eq!(trades.len(), 1); // 应该有一个交易
+        assert_eq!(trades[0].quantity, 1.0);
+        assert_eq!(trades[0].price, 50000.0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn test_concurrent_submits_do_not_overfill_resting_orders() {
+        let engine = Arc::new(MatchingEngine::new());
+        let symbol = Symbol::new("BTC", "USDT");
+        let takers = 8;
+
+        for round in 0..200 {
+            let maker = Order::new(
+                symbol,
+                OrderSide::Sell,
+                OrderType::Limit,
+                1.0,
+                Some(100.0),
+                "maker".to_string(),
+            );
+            let maker_id = maker.id;
+            engine.submit_order(maker).await.unwrap();
+
+            // 同一交易对的多个市价买单同时撮合同一笔挂单
+            let barrier = Arc::new(tokio::sync::Barrier::new(takers));
+            let handles: Vec<_> = (0..takers)
+                .map(|i| {
+                    let engine = Arc::clone(&engine);
+                    let barrier = Arc::clone(&barrier);
+                    tokio::spawn(async move {
+                        let order = Order::new(
+                            symbol,
+                            OrderSide::Buy,
+                            OrderType::Market,
+                            0.25,
+                            None,
+                            format!("taker-{}", i),
+                        );
+                        barrier.wait().await;
+                        engine.submit_order(order).await
+                    })
+                })
+                .collect();
+
+            let mut filled = 0.0;
+            for handle in handles {
+                let trades = handle.await.unwrap().unwrap();
+                filled += trades.iter().map(|trade| trade.quantity).sum::<f64>();
+            }
+            assert_eq!(filled, 1.0, "round {} filled {}", round, filled);
+            let maker = engine.get_order(maker_id).unwrap();
+            assert_eq!(maker.status, OrderStatus::Filled);
+            assert_eq!(maker.filled_quantity, 1.0);
+        }
+        engine
+            .get_orderbook(&symbol)
+            .unwrap()
+            .verify_invariants()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sandbox_checks_and_settles_virtual_balances() {
+        let mut config = EngineConfig::default();
+        config.sandbox.enabled = true;
+        config.sandbox.initial_balances =
+            HashMap::from([("USDT".to_string(), 1_000.0), ("BTC".to_string(), 1.0)]);
+        let engine = MatchingEngine::with_config(config);
+        let symbol = Symbol::new("BTC", "USDT");
+        let limit = |side, quantity, price, user: &str| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                quantity,
+                Some(price),
+                user.to_string(),
+            )
+        };
+
+        // 买单所需金额超过虚拟余额
+        let err = engine
+            .submit_order(limit(OrderSide::Buy, 11.0, 100.0, "alice"))
+            .await
+            .unwrap_err();
+        assert!(err.contains("Insufficient sandbox USDT balance"));
+
+        engine
+            .submit_order(limit(OrderSide::Sell, 0.5, 100.0, "bob"))
+            .await
+            .unwrap();
+        let trades = engine
+            .submit_order(limit(OrderSide::Buy, 0.5, 100.0, "alice"))
+            .await
+            .unwrap();
+        assert_eq!(trades.len(), 1);
+
+        let accounts = engine.accounts();
+        let fees = engine.config.fees.clone();
+        assert_eq!(accounts.get_balance("alice", "BTC").available, 1.5);
+        assert!(
+            (accounts.get_balance("alice", "USDT").available - (950.0 - 50.0 * fees.taker_rate))
+                .abs()
+                < 1e-9
+        );
+        assert_eq!(accounts.get_balance("bob", "BTC").available, 0.5);
+        assert!(
+            (accounts.get_balance("bob", "USDT").available - (1_050.0 - 50.0 * fees.maker_rate))
+                .abs()
+                < 1e-9
+        );
+
+        // 撤销成交后冲回结算
+        engine
+            .bust_trade(trades[0].id, "Erroneous trade".to_string(), false)
+            .await
+            .unwrap();
+        assert!((accounts.get_balance("alice", "USDT").available - 1_000.0).abs() < 1e-9);
+        assert!((accounts.get_balance("bob", "BTC").available - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_blocking_submit_and_cancel() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+        let order = |side, user: &str| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                1.0,
+                Some(50000.0),
+                user.to_string(),
+            )
+        };
+
+        let resting = order(OrderSide::Sell, "seller");
+        let resting_id = resting.id;
+        assert!(engine.submit_order_blocking(resting).unwrap().is_empty());
+        let cancelled = engine
+            .cancel_order_blocking(resting_id, "seller".to_string())
+            .unwrap();
+        assert_eq!(cancelled.status, OrderStatus::Cancelled);
+
+        engine
+            .submit_order_blocking(order(OrderSide::Sell, "seller"))
+            .unwrap();
+        let trades = engine
+            .submit_order_blocking(order(OrderSide::Buy, "buyer"))
+            .unwrap();
+        assert_eq!(trades.len(), 1);
+
+        // 异步上下文中应使用异步接口
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let result = runtime
+            .block_on(async { engine.submit_order_blocking(order(OrderSide::Buy, "buyer")) });
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_matching_engine_partial_fill() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+
+        // 提交大卖单
+        let sell_order = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::Limit,
+            2.0,
+            Some(50000.0),
+            "seller".to_string(),
+        );
+
+        engine.submit_order(sell_order).await.unwrap();
+
+        // 提交小买单
+        let buy_order = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(50000.0),
+            "buyer".to_string(),
+        );
+
+        let trades = engine.submit_order(buy_order).await.unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].quantity, 1.0);
+
+        // 检查卖单是否部分成交
+        let orderbook_depth = engine.get_orderbook_depth(&symbol, None).unwrap();
+        assert_eq!(orderbook_depth.asks.len(), 1);
+        assert_eq!(orderbook_depth.asks[0].total_quantity, 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_trading_sessions_switch_state_at_boundaries() {
+        use crate::config::TradingSession;
+        use chrono::NaiveTime;
+
+        // 2024-01-05 是周五
+        let at = |time: &str| {
+            format!("2024-01-05T{}Z", time)
+                .parse::<DateTime<Utc>>()
+                .unwrap()
+        };
+        let clock = Arc::new(MockClock::new(at("08:00:00")));
+        let mut config = EngineConfig::default();
+        config.sessions.enabled = true;
+        config.sessions.symbols.insert(
+            "BTCUSDT".to_string(),
+            TradingSession {
+                open: NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+                close: NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+                pre_open_minutes: 15,
+                ..Default::default()
+            },
+        );
+        let engine = MatchingEngine::with_clock(config, clock.clone());
+        let symbol = Symbol::new("BTC", "USDT");
+        let limit = |side, user: &str| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                1.0,
+                Some(100.0),
+                user.to_string(),
+            )
+        };
+
+        // 开盘前休市：拒绝新订单
+        engine.apply_trading_sessions().await;
+        assert_eq!(engine.get_trading_state(&symbol), TradingState::CancelOnly);
+        assert!(engine
+            .submit_order(limit(OrderSide::Buy, "alice"))
+            .await
+            .is_err());
+
+        // 集合竞价只挂单不撮合
+        clock.set(at("09:15:00"));
+        engine.apply_trading_sessions().await;
+        assert_eq!(engine.get_trading_state(&symbol), TradingState::AuctionOnly);
+        engine
+            .submit_order(limit(OrderSide::Buy, "alice"))
+            .await
+            .unwrap();
+        assert!(engine
+            .submit_order(limit(OrderSide::Sell, "bob"))
+            .await
+            .unwrap()
+            .is_empty());
+        // 阶段不变时不重复切换
+        assert!(engine.apply_trading_sessions().await.is_empty());
+
+        // 开盘时撮合交叉部分
+        clock.set(at("09:30:00"));
+        let statuses = engine.apply_trading_sessions().await;
+        assert_eq!(statuses[0].reason.as_deref(), Some("Session open"));
+        assert_eq!(engine.get_trading_state(&symbol), TradingState::Trading);
+        assert_eq!(engine.get_trades(None, None).len(), 1);
+
+        clock.set(at("16:00:00"));
+        engine.apply_trading_sessions().await;
+        assert_eq!(engine.get_trading_state(&symbol), TradingState::CancelOnly);
+        let session = engine.get_session(&symbol).unwrap();
+        assert_eq!(session.phase, SessionPhase::Closed);
+        // 周末休市，下一次开盘在周一
+        assert_eq!(
+            session.next_open,
+            Some("2024-01-08T09:30:00Z".parse().unwrap())
+        );
+        assert!(engine.get_session(&Symbol::new("ETH", "USDT")).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_mock_clock_drives_expiry_and_timestamps() {
+        let start = "2024-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let clock = Arc::new(MockClock::new(start));
+        let engine = MatchingEngine::with_clock(EngineConfig::default(), clock.clone());
+        let symbol = Symbol::new("BTC", "USDT");
+
+        // 到期时间按引擎时钟校验，即使早于系统时间也被接受
+        let order = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::Limit,
+            2.0,
+            Some(100.0),
+            "maker".to_string(),
+        )
+        .with_expiry(start + chrono::Duration::seconds(60));
+        let order_id = order.id;
+        engine.submit_order(order).await.unwrap();
+
+        clock.advance(Duration::from_secs(30));
+        let taker = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Market,
+            1.0,
+            None,
+            "taker".to_string(),
+        );
+        let trades = engine.submit_order(taker).await.unwrap();
+        assert_eq!(trades[0].timestamp, start + chrono::Duration::seconds(30));
+        assert_eq!(crate::id::timestamp_of(trades[0].id), trades[0].timestamp);
+
+        assert!(engine.expire_orders(engine.now()).await.is_empty());
+        clock.advance(Duration::from_secs(30));
+        let expired = engine.expire_orders(engine.now()).await;
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, order_id);
+        assert_eq!(engine.get_stats().uptime_seconds, 60);
+    }
+
+    #[tokio::test]
+    async fn test_trade_ids_are_deterministic_under_mock_clock() {
+        let start = "2024-06-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let symbol = Symbol::new("BTC", "USDT");
+        let run = || async {
+            let clock = Arc::new(MockClock::new(start));
+            let engine = MatchingEngine::with_clock(EngineConfig::default(), clock.clone());
+            let mut maker = Order::new(
+                symbol,
+                OrderSide::Sell,
+                OrderType::Limit,
+                2.0,
+                Some(100.0),
+                "maker".to_string(),
+            );
+            maker.id = 1;
+            engine.submit_order(maker).await.unwrap();
+
+            let mut trade_ids = Vec::new();
+            for id in 2..4 {
+                clock.advance(Duration::from_millis(5));
+                let mut taker = Order::new(
+                    symbol,
+                    OrderSide::Buy,
+                    OrderType::Market,
+                    1.0,
+                    None,
+                    "taker".to_string(),
+                );
+                taker.id = id;
+                let trades = engine.submit_order(taker).await.unwrap();
+                trade_ids.extend(trades.iter().map(|trade| trade.id));
+            }
+            trade_ids
+        };
+
+        // 同样的命令和时钟读数得到同样的成交ID，与系统时间和其它引擎无关
+        let first = run().await;
+        assert_eq!(first.len(), 2);
+        assert_eq!(first, run().await);
+        assert_eq!(
+            crate::id::timestamp_of(first[0]),
+            start + chrono::Duration::milliseconds(5)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expiry_waits_for_matching_lock() {
+        let engine = Arc::new(MatchingEngine::new());
+        let symbol = Symbol::new("BTC", "USDT");
+        let expires_at = Utc::now() + chrono::Duration::seconds(60);
+        let order = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(50000.0),
+            "buyer".to_string(),
+        )
+        .with_expiry(expires_at);
+        let order_id = order.id;
+        engine.submit_order(order).await.unwrap();
+
+        let matching = engine.lock_matching(&symbol).await;
+        let expiry = tokio::spawn({
+            let engine = Arc::clone(&engine);
+            async move { engine.expire_orders(expires_at).await }
+        });
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        assert!(engine
+            .get_orderbook(&symbol)
+            .unwrap()
+            .contains_order(order_id));
+
+        drop(matching);
+        assert_eq!(expiry.await.unwrap().len(), 1);
+        assert!(!engine
+            .get_orderbook(&symbol)
+            .unwrap()
+            .contains_order(order_id));
+    }
+
+    #[tokio::test]
+    async fn test_gtd_order_expires() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+        let expires_at = Utc::now() + chrono::Duration::seconds(60);
+
+        let order = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(50000.0),
+            "buyer".to_string(),
+        )
+        .with_expiry(expires_at);
+        let order_id = order.id;
+        engine.submit_order(order).await.unwrap();
+
+        // 未到期前不会被撤销
+        assert!(engine.expire_orders(Utc::now()).await.is_empty());
+
+        let expired = engine.expire_orders(expires_at).await;
+        assert_eq!(expired.len(), 1);
+        assert_eq!(
+            engine.get_order(order_id).unwrap().status,
+            OrderStatus::Expired
+        );
+        assert!(engine
+            .get_orderbook_depth(&symbol, None)
+            .unwrap()
+            .bids
+            .is_empty());
+        assert_eq!(engine.get_stats().active_orders, 0);
+        assert!(engine
+            .cancel_order(order_id, "buyer".to_string())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_expiry_scheduler_cancels_resting_orders() {
+        let start = "2024-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let clock = Arc::new(MockClock::new(start));
+        let engine = Arc::new(MatchingEngine::with_clock(
+            EngineConfig::default(),
+            clock.clone(),
+        ));
+        let _scheduler = engine.start_expiry_scheduler();
+
+        let order = Order::new(
+            Symbol::new("BTC", "USDT"),
+            OrderSide::Sell,
+            OrderType::Limit,
+            1.0,
+            Some(50000.0),
+            "seller".to_string(),
+        )
+        .with_expiry(start + chrono::Duration::seconds(60));
+        let order_id = order.id;
+        engine.submit_order(order).await.unwrap();
+
+        // 到期前调度任务不会撤单
+        clock.advance(Duration::from_secs(59));
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(engine.get_order(order_id).unwrap().status, OrderStatus::New);
+
+        clock.advance(Duration::from_secs(1));
+        let order = engine
+            .wait_for_order(order_id, 0.0, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(order.status, OrderStatus::Expired);
+    }
+
+    #[tokio::test]
+    async fn test_gtd_order_validation() {
+        let engine = MatchingEngine::new();
+        let order = Order::new(
+            Symbol::new("BTC", "USDT"),
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(50000.0),
+            "buyer".to_string(),
+        );
+
+        let past = order
+            .clone()
+            .with_expiry(Utc::now() - chrono::Duration::seconds(1));
+        assert!(engine.submit_order(past).await.is_err());
+
+        let mut missing_expiry = order;
+        missing_expiry.time_in_force = TimeInForce::GoodTillDate;
+        assert!(engine.submit_order(missing_expiry).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_halt_and_resume_trading() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+        let mut status_receiver = engine.subscribe_symbol_status();
+
+        let order = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(50000.0),
+            "buyer".to_string(),
+        );
+        let order_id = order.id;
+        engine.submit_order(order).await.unwrap();
+
+        // 完全暂停：拒绝新订单和撤单
+        engine.set_trading_state(
+            &symbol,
+            TradingState::Halted,
+            Some("maintenance".to_string()),
+        );
+        assert_eq!(
+            status_receiver.try_recv().unwrap().state,
+            TradingState::Halted
+        );
+        let new_order = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::Limit,
+            1.0,
+            Some(50000.0),
+            "seller".to_string(),
+        );
+        assert!(engine.submit_order(new_order.clone()).await.is_err());
+        assert!(engine
+            .cancel_order(order_id, "buyer".to_string())
+            .await
+            .is_err());
+
+        // 只撤单：允许撤单
+        engine.set_trading_state(&symbol, TradingState::CancelOnly, None);
+        assert!(engine.submit_order(new_order.clone()).await.is_err());
+        assert!(engine
+            .cancel_order(order_id, "buyer".to_string())
+            .await
+            .is_ok());
+
+        // 恢复交易
+        engine.set_trading_state(&symbol, TradingState::Trading, None);
+        assert!(engine.submit_order(new_order).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_auction_only_accepts_orders_without_matching() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+        engine.set_trading_state(&symbol, TradingState::AuctionOnly, None);
+
+        for (side, user) in [(OrderSide::Sell, "seller"), (OrderSide::Buy, "buyer")] {
+            let order = Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                1.0,
+                Some(50000.0),
+                user.to_string(),
+            );
+            assert!(engine.submit_order(order).await.unwrap().is_empty());
+        }
+
+        assert_eq!(engine.get_stats().total_trades, 0);
+    }
+
+    #[tokio::test]
+    async fn test_order_queued_behind_uncross_matches_after_auction() {
+        let engine = Arc::new(MatchingEngine::new());
+        let symbol = Symbol::new("BTC", "USDT");
+        engine.start_auction(&symbol, None);
+        for (side, quantity, price, user) in [
+            (OrderSide::Buy, 1.0, 101.0, "bidder"),
+            (OrderSide::Sell, 2.0, 99.0, "seller"),
+        ] {
+            let order = Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                quantity,
+                Some(price),
+                user.to_string(),
+            );
+            engine.submit_order(order).await.unwrap();
+        }
+
+        // 撮合锁被占用时先排队结束竞价，再排队一笔竞价期间受理的买单
+        let matching = engine.lock_matching(&symbol).await;
+        let uncross = tokio::spawn({
+            let engine = Arc::clone(&engine);
+            async move { engine.end_auction(&symbol).await }
+        });
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        let buy = tokio::spawn({
+            let engine = Arc::clone(&engine);
+            async move {
+                let order = Order::new(
+                    symbol,
+                    OrderSide::Buy,
+                    OrderType::Limit,
+                    1.0,
+                    Some(101.0),
+                    "late".to_string(),
+                );
+                engine.submit_order(order).await
+            }
+        });
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        drop(matching);
+
+        assert_eq!(uncross.await.unwrap().unwrap().len(), 1);
+        assert_eq!(engine.get_trading_state(&symbol), TradingState::Trading);
+        // 竞价结束后按连续交易撮合，不会以交叉价格挂单
+        let trades = buy.await.unwrap().unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].buyer_id, "late");
+        let orderbook = engine.get_orderbook(&symbol).unwrap();
+        assert_eq!(orderbook.best_ask(), None);
+        assert_eq!(orderbook.best_bid(), None);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_halts_and_resumes() {
+        let mut config = EngineConfig::default();
+        config.circuit_breaker = crate::config::CircuitBreakerConfig {
+            enabled: true,
+            max_price_move: 5.0,
+            window_seconds: 300,
+            cooldown_seconds: 60,
+            cancel_only: false,
+        };
+        let start = "2024-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let clock = Arc::new(MockClock::new(start));
+        let engine = MatchingEngine::with_clock(config, clock.clone());
+        let symbol = Symbol::new("BTC", "USDT");
+
+        // 以 50000 建立参考价
+        for (side, price, user) in [
+            (OrderSide::Sell, 50000.0, "seller"),
+            (OrderSide::Buy, 50000.0, "buyer"),
+            // 上涨 10% 的成交触发熔断
+            (OrderSide::Sell, 55000.0, "seller"),
+            (OrderSide::Buy, 55000.0, "buyer"),
+        ] {
+            let order = Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                1.0,
+                Some(price),
+                user.to_string(),
+            );
+            engine.submit_order(order).await.unwrap();
+        }
+
+        assert_eq!(engine.get_trading_state(&symbol), TradingState::Halted);
+        assert_eq!(engine.get_stats().total_trades, 2);
+
+        // 冷却期结束后自动恢复
+        clock.advance(Duration::from_secs(59));
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(engine.get_trading_state(&symbol), TradingState::Halted);
+        clock.advance(Duration::from_secs(1));
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(engine.get_trading_state(&symbol), TradingState::Trading);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_cooldown_ignores_earlier_trip() {
+        let mut config = EngineConfig::default();
+        config.circuit_breaker = crate::config::CircuitBreakerConfig {
+            enabled: true,
+            max_price_move: 5.0,
+            window_seconds: 300,
+            cooldown_seconds: 60,
+            cancel_only: false,
+        };
+        let start = "2024-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let clock = Arc::new(MockClock::new(start));
+        let engine = MatchingEngine::with_clock(config, clock.clone());
+        let symbol = Symbol::new("BTC", "USDT");
+        let trade_at = |price: f64| {
+            [("seller", OrderSide::Sell), ("buyer", OrderSide::Buy)].map(|(user, side)| {
+                Order::new(
+                    symbol,
+                    side,
+                    OrderType::Limit,
+                    1.0,
+                    Some(price),
+                    user.to_string(),
+                )
+            })
+        };
+
+        for order in trade_at(50000.0).into_iter().chain(trade_at(55000.0)) {
+            engine.submit_order(order).await.unwrap();
+        }
+        assert_eq!(engine.get_trading_state(&symbol), TradingState::Halted);
+
+        // 人工恢复后在第一次冷却期内再次熔断
+        clock.advance(Duration::from_secs(10));
+        engine.set_trading_state(&symbol, TradingState::Trading, None);
+        clock.advance(Duration::from_secs(10));
+        for order in trade_at(60500.0) {
+            engine.submit_order(order).await.unwrap();
+        }
+        assert_eq!(engine.get_trading_state(&symbol), TradingState::Halted);
+
+        // 第一次冷却期结束不恢复，第二次冷却期结束才恢复
+        clock.advance(Duration::from_secs(40));
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(engine.get_trading_state(&symbol), TradingState::Halted);
+        clock.advance(Duration::from_secs(20));
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(engine.get_trading_state(&symbol), TradingState::Trading);
+    }
+
+    #[tokio::test]
+    async fn test_call_auction_uncross() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+        let mut auction_receiver = engine.subscribe_auction();
+
+        engine.start_auction(&symbol, Some("opening auction".to_string()));
+
+        for (side, quantity, price, user) in [
+            (OrderSide::Buy, 2.0, 102.0, "buyer1"),
+            (OrderSide::Buy, 1.0, 100.0, "buyer2"),
+            (OrderSide::Sell, 1.0, 99.0, "seller1"),
+            (OrderSide::Sell, 1.0, 101.0, "seller2"),
+            (OrderSide::Sell, 3.0, 103.0, "seller3"),
+        ] {
+            let order = Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                quantity,
+                Some(price),
+                user.to_string(),
+            );
+            assert!(engine.submit_order(order).await.unwrap().is_empty());
+        }
+
+        // 集合竞价期间不接受市价单
+        let market_order = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Market,
+            1.0,
+            None,
+            "buyer3".to_string(),
+        );
+        assert!(engine.submit_order(market_order).await.is_err());
+
+        let indicative = engine.get_auction_indicative(&symbol);
+        assert_eq!(indicative.price, Some(101.0));
+        assert_eq!(indicative.volume, 2.0);
+
+        let mut latest = None;
+        while let Ok(update) = auction_receiver.try_recv() {
+            latest = Some(update);
+        }
+        assert_eq!(latest.unwrap().price, Some(101.0));
+
+        // 结束竞价，以统一价格一次性撮合
+        let trades = engine.end_auction(&symbol).await.unwrap();
+        assert_eq!(trades.len(), 2);
+        assert!(trades.iter().all(|trade| trade.price == 101.0));
+        assert!(trades.iter().all(|trade| trade.taker_side.is_none()));
+        assert_eq!(trades.iter().map(|trade| trade.quantity).sum::<f64>(), 2.0);
+        assert_eq!(engine.get_trading_state(&symbol), TradingState::Trading);
+
+        // 未成交的挂单保留在订单簿中
+        let depth = engine.get_orderbook_depth(&symbol, None).unwrap();
+        assert_eq!(depth.bids[0].price, 100.0);
+        assert_eq!(depth.asks[0].price, 103.0);
+        assert!(engine.end_auction(&symbol).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_trailing_stop_triggers_on_retracement() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+
+        async fn trade_at(engine: &MatchingEngine, symbol: &Symbol, price: f64) {
+            let sell = Order::new(
+                *symbol,
+                OrderSide::Sell,
+                OrderType::Limit,
+                1.0,
+                Some(price),
+                "maker".to_string(),
+            );
+            let buy = Order::new(
+                *symbol,
+                OrderSide::Buy,
+                OrderType::Limit,
+                1.0,
+                Some(price),
+                "taker".to_string(),
+            );
+            engine.submit_order(sell).await.unwrap();
+            engine.submit_order(buy).await.unwrap();
+        }
+
+        trade_at(&engine, &symbol, 100.0).await;
+
+        // 回撤 5 的跟踪止损卖单，触发后以限价 90 卖出
+        let trailing = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::TrailingStop,
+            1.0,
+            Some(90.0),
+            "user1".to_string(),
+        )
+        .with_trailing_offset(TrailingOffset::Amount(5.0));
+        let trailing_id = trailing.id;
+        assert!(engine.submit_order(trailing).await.unwrap().is_empty());
+        assert_eq!(
+            engine.get_order(trailing_id).unwrap().stop_price,
+            Some(95.0)
+        );
+
+        trade_at(&engine, &symbol, 110.0).await;
+        assert_eq!(
+            engine.get_order(trailing_id).unwrap().stop_price,
+            Some(105.0)
+        );
+
+        // 挂一笔买单承接触发后的卖单，再以 104 成交触发跟踪止损
+        let bid = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            "bidder".to_string(),
+        );
+        engine.submit_order(bid).await.unwrap();
+        trade_at(&engine, &symbol, 104.0).await;
+
+        let triggered = engine.get_order(trailing_id).unwrap();
+        assert_eq!(triggered.order_type, OrderType::Limit);
+        assert_eq!(triggered.status, OrderStatus::Filled);
+        assert_eq!(
+            engine.get_user_orders("bidder")[0].status,
+            OrderStatus::Filled
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stop_triggers_on_mark_price() {
+        let mut config = EngineConfig::default();
+        config.price_reference.triggers = PriceReference::Mark;
+        let engine = MatchingEngine::with_config(config);
+        let symbol = Symbol::new("BTC", "USDT");
+        let limit = |side, price, user: &str| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                1.0,
+                Some(price),
+                user.to_string(),
+            )
+        };
+
+        engine
+            .submit_order(limit(OrderSide::Buy, 80.0, "bidder"))
+            .await
+            .unwrap();
+        let stop = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::StopLoss,
+            1.0,
+            None,
+            "user1".to_string(),
+        )
+        .with_stop_price(95.0);
+        let stop_id = stop.id;
+        engine.submit_order(stop).await.unwrap();
+
+        // 成交价穿过止损价，但按标记价格判断时不触发
+        engine
+            .submit_order(limit(OrderSide::Sell, 90.0, "maker"))
+            .await
+            .unwrap();
+        engine
+            .submit_order(limit(OrderSide::Buy, 90.0, "taker"))
+            .await
+            .unwrap();
+        assert_eq!(
+            engine.get_order(stop_id).unwrap().order_type,
+            OrderType::StopLoss
+        );
+
+        engine
+            .update_mark_price(MarkPrice {
+                symbol,
+                index_price: 94.0,
+                mark_price: 94.0,
+                components: Vec::new(),
+                timestamp: Utc::now(),
+            })
+            .await;
+        assert_eq!(engine.get_mark_price(&symbol).unwrap().mark_price, 94.0);
+        assert_eq!(
+            engine.get_order(stop_id).unwrap().status,
+            OrderStatus::Filled
+        );
+    }
+
+    #[tokio::test]
+    async fn test_margin_checks_and_liquidation_flags() {
+        let mut config = EngineConfig::default();
+        config.margin.enabled = true;
+        let engine = MatchingEngine::with_config(config);
+        let symbol = Symbol::new("BTC", "USDT");
+        let limit = |side, quantity, user: &str| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                quantity,
+                Some(100.0),
+                user.to_string(),
+            )
+        };
+        engine
+            .accounts()
+            .deposit("alice", "USDT", 100.0, "dep-alice")
+            .unwrap();
+        engine
+            .accounts()
+            .deposit("bob", "USDT", 1_000.0, "dep-bob")
+            .unwrap();
+
+        // 没有保证金的用户不能开仓
+        let err = engine
+            .submit_order(limit(OrderSide::Sell, 1.0, "carol"))
+            .await
+            .unwrap_err();
+        assert!(err.contains("Insufficient margin"));
+
+        engine
+            .submit_order(limit(OrderSide::Sell, 20.0, "bob"))
+            .await
+            .unwrap();
+        engine
+            .submit_order(limit(OrderSide::Buy, 10.0, "alice"))
+            .await
+            .unwrap();
+        assert!(engine
+            .submit_order(limit(OrderSide::Buy, 1.0, "alice"))
+            .await
+            .is_err());
+        let account = engine.get_margin_account("alice").unwrap();
+        assert_eq!(account.initial_margin, 100.0);
+        assert!(engine.check_margins().is_empty());
+
+        engine
+            .update_mark_price(MarkPrice {
+                symbol,
+                index_price: 94.0,
+                mark_price: 94.0,
+                components: Vec::new(),
+                timestamp: Utc::now(),
+            })
+            .await;
+        let flagged = engine.check_margins();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].user_id, "alice");
+        assert_eq!(flagged[0].unrealized_pnl, -60.0);
+        assert_eq!(engine.get_liquidation_candidates().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_funding_exchanged_between_longs_and_shorts() {
+        let mut config = EngineConfig::default();
+        config.margin.enabled = true;
+        config.funding.enabled = true;
+        config.funding.interest_rate_pct = 0.0;
+        let engine = MatchingEngine::with_config(config);
+        let symbol = Symbol::new("BTC", "USDT");
+        for user in ["alice", "bob"] {
+            engine
+                .accounts()
+                .deposit(user, "USDT", 1_000.0, &format!("dep-{}", user))
+                .unwrap();
+        }
+        let limit = |side, user: &str| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                2.0,
+                Some(100.0),
+                user.to_string(),
+            )
+        };
+        engine
+            .submit_order(limit(OrderSide::Sell, "bob"))
+            .await
+            .unwrap();
+        engine
+            .submit_order(limit(OrderSide::Buy, "alice"))
+            .await
+            .unwrap();
+
+        // 没有溢价样本时不结算
+        assert!(engine.settle_funding(Utc::now()).is_empty());
+
+        // 标记价格高于指数价格 0.2%，多头向空头支付 2 × 200 × 0.2% = 0.8
+        engine
+            .update_mark_price(MarkPrice {
+                symbol,
+                index_price: 199.6,
+                mark_price: 200.0,
+                components: Vec::new(),
+                timestamp: Utc::now(),
+            })
+            .await;
+        let settlements = engine.settle_funding(Utc::now());
+        assert_eq!(settlements.len(), 1);
+        assert_eq!(settlements[0].positions, 2);
+        assert!((settlements[0].funding_rate - 0.4 / 199.6).abs() < 1e-12);
+        let payment = 2.0 * 200.0 * settlements[0].funding_rate;
+        assert!((settlements[0].total_payment - payment).abs() < 1e-9);
+        let balance = |user: &str| engine.accounts().get_balance(user, "USDT").available;
+        assert!((balance("alice") - (1_000.0 - payment)).abs() < 1e-9);
+        assert!((balance("bob") - (1_000.0 + payment)).abs() < 1e-9);
+        assert_eq!(
+            engine.accounts().get_ledger("alice", Some(1))[0].entry_type,
+            LedgerEntryType::Funding
+        );
+
+        assert_eq!(
+            engine.get_funding_history(&symbol, None).unwrap(),
+            settlements
+        );
+        assert!(engine.settle_funding(Utc::now()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_liquidation_closes_positions_and_settles_insurance_fund() {
+        let mut config = EngineConfig::default();
+        config.margin.enabled = true;
+        config.liquidation.enabled = true;
+        let engine = MatchingEngine::with_config(config);
+        let symbol = Symbol::new("BTC", "USDT");
+        let limit = |side, quantity, price, user: &str| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                quantity,
+                Some(price),
+                user.to_string(),
+            )
+        };
+        let mark = |price: f64| MarkPrice {
+            symbol,
+            index_price: price,
+            mark_price: price,
+            components: Vec::new(),
+            timestamp: Utc::now(),
+        };
+        for (user, amount) in [("alice", 100.0), ("bob", 1_000.0), ("carol", 1_000.0)] {
+            engine
+                .accounts()
+                .deposit(user, "USDT", amount, &format!("dep-{}", user))
+                .unwrap();
+        }
+        let mut receiver = engine.subscribe_liquidations();
+
+        // alice 以 100 买入 10，另挂一笔减仓卖单
+        engine
+            .submit_order(limit(OrderSide::Sell, 10.0, 100.0, "bob"))
+            .await
+            .unwrap();
+        engine
+            .submit_order(limit(OrderSide::Buy, 10.0, 100.0, "alice"))
+            .await
+            .unwrap();
+        let resting = limit(OrderSide::Sell, 1.0, 120.0, "alice");
+        let resting_id = resting.id;
+        engine.submit_order(resting).await.unwrap();
+        assert!(engine.liquidate_account("alice").await.is_err());
+
+        // 标记价格 94：权益 40 低于维持保证金 47，价格带下沿 89.3 内只有 6 的买盘
+        engine
+            .submit_order(limit(OrderSide::Buy, 6.0, 92.0, "carol"))
+            .await
+            .unwrap();
+        engine.update_mark_price(mark(94.0)).await;
+        let events = engine.run_liquidations().await;
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.cancelled_orders, vec![resting_id]);
+        assert_eq!(event.orders[0].side, OrderSide::Sell);
+        assert!((event.orders[0].price - 89.3).abs() < 1e-9);
+        assert_eq!(event.orders[0].filled_quantity, 6.0);
+        assert_eq!(event.insurance_fund_payout, 0.0);
+        // 手续费为平仓名义价值 552 的 0.5%
+        assert!((event.liquidation_fee - 2.76).abs() < 1e-9);
+        assert_eq!(receiver.try_recv().unwrap(), *event);
+        assert_eq!(
+            engine.get_order(resting_id).unwrap().status,
+            OrderStatus::Cancelled
+        );
+        // 价格带外的剩余部分被撤销，不留在订单簿上
+        let liquidation_order = engine.get_order(event.orders[0].order_id).unwrap();
+        assert_eq!(liquidation_order.status, OrderStatus::Cancelled);
+        assert_eq!(engine.positions().get_position("alice", &symbol), 4.0);
+
+        // 标记价格 80：剩余持仓平掉后穿仓，由保险基金补足
+        engine
+            .submit_order(limit(OrderSide::Buy, 4.0, 80.0, "carol"))
+            .await
+            .unwrap();
+        engine.update_mark_price(mark(80.0)).await;
+        let events = engine.run_liquidations().await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].liquidation_fee, 0.0);
+        assert!((events[0].insurance_fund_payout - 30.76).abs() < 1e-9);
+        assert!(events[0].remaining_equity.abs() < 1e-9);
+        assert_eq!(engine.positions().get_position("alice", &symbol), 0.0);
+        let fund = engine.accounts().get_balance("insurance-fund", "USDT");
+        assert!((fund.available + 28.0).abs() < 1e-9);
+        assert_eq!(engine.get_liquidations(None).unwrap().len(), 2);
+        assert!(engine.run_liquidations().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_symbol_listing_and_delisting() {
+        let engine = MatchingEngine::with_config(EngineConfig {
+            supported_symbols: vec!["BTCUSDT".to_string()],
+            restrict_symbols: true,
+            ..Default::default()
+        });
+        let sol = Symbol::new("SOL", "USDT");
+        let limit = |side, quantity, price, user: &str| {
+            Order::new(
+                sol,
+                side,
+                OrderType::Limit,
+                quantity,
+                Some(price),
+                user.to_string(),
+            )
+        };
+        let err = engine
+            .submit_order(limit(OrderSide::Buy, 1.0, 10.0, "alice"))
+            .await
+            .unwrap_err();
+        assert!(err.contains("not supported"));
+
+        let mut listings = engine.subscribe_symbol_listings();
+        let request = ListSymbolRequest {
+            symbol: sol,
+            filters: SymbolFilters {
+                tick_size: Some(0.5),
+                min_quantity: Some(1.0),
+                ..Default::default()
+            },
+        };
+        engine.list_symbol(request.clone()).unwrap();
+        assert_eq!(listings.try_recv().unwrap().status, ListingStatus::Listed);
+        assert!(engine.list_symbol(request.clone()).is_err());
+        assert_eq!(engine.get_symbol_listings().len(), 2);
+
+        // 上市时的过滤规则：tick 和最小数量
+        assert!(engine
+            .submit_order(limit(OrderSide::Buy, 1.0, 10.2, "alice"))
+            .await
+            .is_err());
+        assert!(engine
+            .submit_order(limit(OrderSide::Buy, 0.5, 10.0, "alice"))
+            .await
+            .unwrap_err()
+            .contains("below the minimum"));
+        engine
+            .submit_order(limit(OrderSide::Buy, 2.0, 10.0, "alice"))
+            .await
+            .unwrap();
+        engine
+            .submit_order(limit(OrderSide::Sell, 1.0, 10.0, "bob"))
+            .await
+            .unwrap();
+
+        let listing = engine.delist_symbol(&sol).await.unwrap();
+        assert_eq!(listing.status, ListingStatus::Delisted);
+        let stats = listing.final_stats.unwrap();
+        assert_eq!(stats.total_trades, 1);
+        assert_eq!(stats.open_bid_orders, 0);
+        assert_eq!(
+            engine.get_user_orders("alice")[0].status,
+            OrderStatus::Cancelled
+        );
+        assert_eq!(listings.try_recv().unwrap().status, ListingStatus::Delisted);
+        assert!(engine
+            .submit_order(limit(OrderSide::Buy, 1.0, 10.0, "alice"))
+            .await
+            .unwrap_err()
+            .contains("delisted"));
+        assert!(engine.delist_symbol(&sol).await.is_err());
+
+        // 下市后可以重新上市
+        engine.list_symbol(request).unwrap();
+        engine
+            .submit_order(limit(OrderSide::Buy, 1.0, 10.0, "alice"))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_order_waiting_for_lock_is_rejected_after_delisting() {
+        let engine = Arc::new(MatchingEngine::new());
+        let symbol = Symbol::new("BTC", "USDT");
+        let order = |side, user: &str| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                1.0,
+                Some(100.0),
+                user.to_string(),
+            )
+        };
+        engine
+            .submit_order(order(OrderSide::Buy, "alice"))
+            .await
+            .unwrap();
+
+        // 下市先排到撮合锁，随后到达的卖单在撮合前看到已下市
+        let matching = engine.lock_matching(&symbol).await;
+        let delist = tokio::spawn({
+            let engine = Arc::clone(&engine);
+            async move { engine.delist_symbol(&symbol).await }
+        });
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        let sell = tokio::spawn({
+            let engine = Arc::clone(&engine);
+            let sell = order(OrderSide::Sell, "bob");
+            async move { engine.submit_order(sell).await }
+        });
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        drop(matching);
+
+        assert_eq!(
+            delist.await.unwrap().unwrap().status,
+            ListingStatus::Delisted
+        );
+        assert!(sell.await.unwrap().unwrap_err().contains("delisted"));
+        assert_eq!(engine.get_stats().total_trades, 0);
+        assert_eq!(engine.get_stats().active_orders, 0);
+    }
+
+    #[tokio::test]
+    async fn test_otc_trade_report() {
+        let mut config = EngineConfig::default();
+        config.otc.enabled = true;
+        let engine = MatchingEngine::with_config(config);
+        let symbol = Symbol::new("BTC", "USDT");
+        let report = |price| ReportTradeRequest {
+            symbol,
+            buyer_id: "alice".to_string(),
+            seller_id: "bob".to_string(),
+            quantity: 2.0,
+            price,
+        };
+
+        // 没有参考价格时拒绝
+        let err = engine.report_trade(report(100.0)).await.unwrap_err();
+        assert!(err.contains("No reference price"));
+
+        for (side, user) in [(OrderSide::Sell, "maker"), (OrderSide::Buy, "taker")] {
+            let order = Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                1.0,
+                Some(100.0),
+                user.to_string(),
+            );
+            engine.submit_order(order).await.unwrap();
+        }
+        let stop = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::StopLoss,
+            1.0,
+            None,
+            "carol".to_string(),
+        )
+        .with_stop_price(97.0);
+        let stop_id = stop.id;
+        engine.submit_order(stop).await.unwrap();
+        let mut trades = engine.subscribe_trades();
+        let mut otc_trades = engine.subscribe_otc_trades();
+
+        let err = engine.report_trade(report(111.0)).await.unwrap_err();
+        assert!(err.contains("outside the 10% band"));
+
+        // 场外成交不作为最新成交价，不触发止损单
+        let first = engine.report_trade(report(95.0)).await.unwrap();
+        assert_eq!(first.sequence, 1);
+        assert_eq!(first.trade.trade_type, TradeType::Otc);
+        assert_eq!(engine.get_order(stop_id).unwrap().status, OrderStatus::New);
+        assert_eq!(engine.last_trade_price(&symbol), Some(100.0));
+        assert_eq!(otc_trades.try_recv().unwrap().id, first.trade.id);
+        assert!(trades.try_recv().is_err());
+
+        // 默认计入成交统计，并更新双方持仓
+        let stats = engine.get_symbol_stats(&symbol).unwrap();
+        assert_eq!(stats.total_trades, 2);
+        assert_eq!(engine.positions().get_position("alice", &symbol), 2.0);
+        assert_eq!(engine.positions().get_position("bob", &symbol), -2.0);
+        assert_eq!(engine.report_trade(report(99.0)).await.unwrap().sequence, 2);
+
+        // 撤销场外成交回退持仓和统计
+        engine
+            .bust_trade(first.trade.id, "Reported in error".to_string(), false)
+            .await
+            .unwrap();
+        assert_eq!(engine.positions().get_position("alice", &symbol), 2.0);
+        assert_eq!(engine.get_symbol_stats(&symbol).unwrap().total_trades, 2);
+    }
+
+    #[tokio::test]
+    async fn test_stop_order_validation_and_cancel() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+
+        let missing_stop = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::StopLoss,
+            1.0,
+            None,
+            "user1".to_string(),
+        );
+        assert!(engine.submit_order(missing_stop).await.is_err());
+
+        let stop = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::StopLoss,
+            1.0,
+            None,
+            "user1".to_string(),
+        )
+        .with_stop_price(49000.0);
+        let stop_id = stop.id;
+        engine.submit_order(stop).await.unwrap();
+
+        let cancelled = engine
+            .cancel_order(stop_id, "user1".to_string())
+            .await
+            .unwrap();
+        assert_eq!(cancelled.status, OrderStatus::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_amend_order_keeps_priority() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+        let limit = |side, quantity, user: &str| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                quantity,
+                Some(100.0),
+                user.to_string(),
+            )
+        };
+
+        let first = limit(OrderSide::Sell, 3.0, "maker1");
+        let second = limit(OrderSide::Sell, 1.0, "maker2");
+        engine.submit_order(first.clone()).await.unwrap();
+        engine.submit_order(second.clone()).await.unwrap();
+        engine
+            .submit_order(limit(OrderSide::Buy, 1.0, "taker"))
+            .await
+            .unwrap();
+
+        // 已成交 1，总量只能改到 (1, 3) 之间
+        for quantity in [3.0, 1.0, 0.5] {
+            assert!(engine
+                .amend_order(first.id, "maker1".to_string(), quantity)
+                .await
+                .is_err());
+        }
+        assert_eq!(
+            engine
+                .amend_order(first.id, "maker2".to_string(), 2.0)
+                .await
+                .unwrap_err(),
+            "Unauthorized to amend this order"
+        );
+
+        let amended = engine
+            .amend_order(first.id, "maker1".to_string(), 1.5)
+            .await
+            .unwrap();
+        assert_eq!(amended.quantity, 1.5);
+        assert_eq!(amended.remaining_quantity, 0.5);
+        assert_eq!(amended.status, OrderStatus::PartiallyFilled);
+
+        // 改单后仍排在同价位第二笔挂单之前
+        let trades = engine
+            .submit_order(limit(OrderSide::Buy, 0.5, "taker"))
+            .await
+            .unwrap();
+        assert_eq!(trades[0].sell_order_id, first.id);
+        assert_eq!(
+            engine.get_order(first.id).unwrap().status,
+            OrderStatus::Filled
+        );
+        assert_eq!(engine.verify_invariants(), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_bust_trade_reverses_trade() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+        let limit = |side, quantity, user: &str| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                quantity,
+                Some(100.0),
+                user.to_string(),
+            )
+        };
+        let mut busts = engine.subscribe_trade_busts();
 
-        // 获取匹配的订单
-        let matching_orders = orderbook.get_matching_orders(incoming_order);
+        let maker = limit(OrderSide::Sell, 2.0, "maker");
+        let taker = limit(OrderSide::Buy, 1.0, "taker");
+        engine.submit_order(maker.clone()).await.unwrap();
+        let trades = engine.submit_order(taker.clone()).await.unwrap();
+        let trade_id = trades[0].id;
 
-        for matching_entry in matching_orders {
-            if remaining_quantity <= 0.0 {
-                break;
+        let bust = engine
+            .bust_trade(trade_id, "Erroneous price".to_string(), true)
+            .await
+            .unwrap();
+        assert_eq!(bust.trade_id, trade_id);
+        assert_eq!(bust.restored_order_ids, vec![maker.id]);
+        assert_eq!(busts.try_recv().unwrap().trade_id, trade_id);
+
+        // 成交、用户成交、持仓和统计均已回退
+        assert!(engine.get_trades(Some(&symbol), None).is_empty());
+        let stats = engine.get_symbol_stats(&symbol).unwrap();
+        assert_eq!((stats.total_trades, stats.volume), (0, 0.0));
+        assert_eq!(engine.get_stats().total_trades, 0);
+        assert!(engine.get_user_fill("taker", trade_id, taker.id).is_none());
+        assert_eq!(engine.positions().get_position("taker", &symbol), 0.0);
+
+        // 挂单恢复全部剩余数量，已完全成交的吃单改为已撤销
+        let depth = engine.get_orderbook_depth(&symbol, None).unwrap();
+        assert_eq!(depth.asks[0].total_quantity, 2.0);
+        let maker_order = engine.get_order(maker.id).unwrap();
+        assert_eq!(maker_order.status, OrderStatus::New);
+        assert_eq!(maker_order.filled_quantity, 0.0);
+        let taker_order = engine.get_order(taker.id).unwrap();
+        assert_eq!(taker_order.status, OrderStatus::Cancelled);
+        assert_eq!(taker_order.remaining_quantity, 1.0);
+
+        assert!(engine
+            .bust_trade(trade_id, "Again".to_string(), true)
+            .await
+            .is_err());
+        assert_eq!(engine.verify_invariants(), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_bust_trade_waits_for_matching_lock() {
+        let engine = Arc::new(MatchingEngine::new());
+        let symbol = Symbol::new("BTC", "USDT");
+        let limit = |side, quantity, user: &str| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                quantity,
+                Some(100.0),
+                user.to_string(),
+            )
+        };
+        let maker = limit(OrderSide::Sell, 2.0, "maker");
+        engine.submit_order(maker.clone()).await.unwrap();
+        let trades = engine
+            .submit_order(limit(OrderSide::Buy, 1.0, "taker"))
+            .await
+            .unwrap();
+
+        let matching = engine.lock_matching(&symbol).await;
+        let bust = tokio::spawn({
+            let engine = Arc::clone(&engine);
+            let trade_id = trades[0].id;
+            async move {
+                engine
+                    .bust_trade(trade_id, "Erroneous price".to_string(), true)
+                    .await
+            }
+        });
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        // 撮合锁释放前成交和挂单都保持不变
+        assert_eq!(engine.get_trades(Some(&symbol), None).len(), 1);
+        assert_eq!(engine.get_order(maker.id).unwrap().remaining_quantity, 1.0);
+
+        drop(matching);
+        assert_eq!(bust.await.unwrap().unwrap().restored_order_ids, [maker.id]);
+        assert_eq!(engine.get_order(maker.id).unwrap().remaining_quantity, 2.0);
+        assert_eq!(engine.verify_invariants(), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_bust_trade_reinstates_filled_maker() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+        let limit = |side, quantity, price, user: &str| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                quantity,
+                Some(price),
+                user.to_string(),
+            )
+        };
+
+        let filled_maker = limit(OrderSide::Sell, 1.0, 100.0, "maker1");
+        let partial_maker = limit(OrderSide::Sell, 2.0, 101.0, "maker2");
+        engine.submit_order(filled_maker.clone()).await.unwrap();
+        engine.submit_order(partial_maker.clone()).await.unwrap();
+        let trades = engine
+            .submit_order(limit(OrderSide::Buy, 2.0, 101.0, "taker"))
+            .await
+            .unwrap();
+        assert_eq!(trades.len(), 2);
+
+        // 完全成交的挂单按原价重新挂入订单簿
+        let active_orders = engine.get_stats().active_orders;
+        let bust = engine
+            .bust_trade(trades[0].id, "Fat finger".to_string(), true)
+            .await
+            .unwrap();
+        assert!(bust.restored_order_ids.contains(&filled_maker.id));
+        let reinstated = engine.get_order(filled_maker.id).unwrap();
+        assert_eq!(reinstated.status, OrderStatus::New);
+        assert_eq!(reinstated.remaining_quantity, 1.0);
+        assert_eq!(engine.get_stats().active_orders, active_orders + 1);
+
+        // 不恢复时挂单的订单总量缩减，剩余数量不变
+        let bust = engine
+            .bust_trade(trades[1].id, "Fat finger".to_string(), false)
+            .await
+            .unwrap();
+        assert!(bust.restored_order_ids.is_empty());
+        let depth = engine.get_orderbook_depth(&symbol, None).unwrap();
+        let partial = depth
+            .asks
+            .iter()
+            .find(|level| level.price == 101.0)
+            .unwrap();
+        assert_eq!(partial.total_quantity, 1.0);
+        assert_eq!(engine.verify_invariants(), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_reduce_only_orders() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+        let limit = |side, quantity, price, user: &str| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                quantity,
+                Some(price),
+                user.to_string(),
+            )
+        };
+
+        // 没有持仓时只减仓订单被拒绝
+        let rejected = limit(OrderSide::Sell, 1.0, 100.0, "alice").reduce_only();
+        assert!(engine.submit_order(rejected).await.is_err());
+
+        // alice 买入 2，建立多头
+        engine
+            .submit_order(limit(OrderSide::Sell, 2.0, 100.0, "bob"))
+            .await
+            .unwrap();
+        engine
+            .submit_order(limit(OrderSide::Buy, 2.0, 100.0, "alice"))
+            .await
+            .unwrap();
+        assert_eq!(engine.positions().get_position("alice", &symbol), 2.0);
+
+        // 超出持仓的只减仓卖单被缩减到 2
+        let reduce = limit(OrderSide::Sell, 5.0, 110.0, "alice").reduce_only();
+        let reduce_id = reduce.id;
+        engine.submit_order(reduce).await.unwrap();
+        assert_eq!(engine.get_order(reduce_id).unwrap().remaining_quantity, 2.0);
+
+        // alice 通过普通卖单平掉 1.5，只减仓挂单随之缩减到 0.5
+        engine
+            .submit_order(limit(OrderSide::Buy, 1.5, 105.0, "carol"))
+            .await
+            .unwrap();
+        engine
+            .submit_order(limit(OrderSide::Sell, 1.5, 105.0, "alice"))
+            .await
+            .unwrap();
+        let reduced = engine.get_order(reduce_id).unwrap();
+        assert_eq!(reduced.remaining_quantity, 0.5);
+        assert_eq!(reduced.quantity, 0.5);
+
+        // 持仓全部平掉后只减仓挂单被撤销
+        engine
+            .submit_order(limit(OrderSide::Buy, 0.5, 105.0, "carol"))
+            .await
+            .unwrap();
+        engine
+            .submit_order(limit(OrderSide::Sell, 0.5, 105.0, "alice"))
+            .await
+            .unwrap();
+        assert_eq!(
+            engine.get_order(reduce_id).unwrap().status,
+            OrderStatus::Cancelled
+        );
+    }
+
+    async fn cross(
+        engine: &MatchingEngine,
+        resting_side: OrderSide,
+        resting: f64,
+        incoming: f64,
+    ) -> f64 {
+        let symbol = Symbol::new("BTC", "USDT");
+        let opposite = match resting_side {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        };
+        let maker = Order::new(
+            symbol,
+            resting_side,
+            OrderType::Limit,
+            1.0,
+            Some(resting),
+            "maker".to_string(),
+        );
+        let taker = Order::new(
+            symbol,
+            opposite,
+            OrderType::Limit,
+            1.0,
+            Some(incoming),
+            "taker".to_string(),
+        );
+        engine.submit_order(maker).await.unwrap();
+        engine.submit_order(taker).await.unwrap()[0].price
+    }
+
+    #[tokio::test]
+    async fn test_crossed_orders_trade_at_maker_price() {
+        let engine = MatchingEngine::new();
+
+        // 激进买单吃卖单，激进卖单吃买单，都以挂单价成交
+        assert_eq!(cross(&engine, OrderSide::Sell, 100.0, 105.0).await, 100.0);
+        assert_eq!(cross(&engine, OrderSide::Buy, 100.0, 95.0).await, 100.0);
+    }
+
+    #[tokio::test]
+    async fn test_trade_records_taker_side() {
+        let engine = MatchingEngine::new();
+        cross(&engine, OrderSide::Sell, 100.0, 105.0).await;
+        cross(&engine, OrderSide::Buy, 100.0, 95.0).await;
+
+        let trades = engine.get_trades(None, None);
+        let sides: Vec<Option<OrderSide>> = trades.iter().map(|trade| trade.taker_side).collect();
+        assert_eq!(sides, [Some(OrderSide::Sell), Some(OrderSide::Buy)]);
+        let json = serde_json::to_value(&trades[0]).unwrap();
+        assert_eq!(json["taker_side"], "sell");
+    }
+
+    #[tokio::test]
+    async fn test_configurable_trade_price_rule() {
+        let mut config = EngineConfig {
+            trade_price_rule: TradePriceRule::Taker,
+            ..Default::default()
+        };
+        let engine = MatchingEngine::with_config(config.clone());
+        assert_eq!(cross(&engine, OrderSide::Sell, 100.0, 105.0).await, 105.0);
+        assert_eq!(cross(&engine, OrderSide::Buy, 100.0, 95.0).await, 95.0);
+
+        config.trade_price_rule = TradePriceRule::Midpoint;
+        let engine = MatchingEngine::with_config(config);
+        assert_eq!(cross(&engine, OrderSide::Sell, 100.0, 105.0).await, 102.5);
+        assert_eq!(cross(&engine, OrderSide::Buy, 100.0, 95.0).await, 97.5);
+    }
+
+    #[tokio::test]
+    async fn test_pro_rata_allocation_per_symbol() {
+        let mut config = EngineConfig::default();
+        config.allocation.symbols.insert(
+            "BTCUSDT".to_string(),
+            crate::config::AllocationAlgorithm::ProRata,
+        );
+        let engine = MatchingEngine::with_config(config);
+        let symbol = Symbol::new("BTC", "USDT");
+
+        let mut maker_ids = Vec::new();
+        for (quantity, user) in [(1.0, "maker1"), (3.0, "maker2")] {
+            let order = Order::new(
+                symbol,
+                OrderSide::Sell,
+                OrderType::Limit,
+                quantity,
+                Some(100.0),
+                user.to_string(),
+            );
+            maker_ids.push(order.id);
+            engine.submit_order(order).await.unwrap();
+        }
+
+        let taker = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            2.0,
+            Some(100.0),
+            "taker".to_string(),
+        );
+        let trades = engine.submit_order(taker).await.unwrap();
+
+        // 按 1:3 比例分配，而非先填满第一笔挂单
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].quantity, 0.5);
+        assert_eq!(trades[1].quantity, 1.5);
+        let depth = engine.get_orderbook_depth(&symbol, None).unwrap();
+        assert_eq!(depth.asks[0].total_quantity, 2.0);
+    }
+
+    #[tokio::test]
+    async fn test_market_order_max_slippage() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+
+        for price in [100.0, 100.5, 102.0] {
+            let ask = Order::new(
+                symbol,
+                OrderSide::Sell,
+                OrderType::Limit,
+                1.0,
+                Some(price),
+                "maker".to_string(),
+            );
+            engine.submit_order(ask).await.unwrap();
+        }
+
+        // 最大滑点 100bp，只能吃到 101 以内的卖单，剩余撤销
+        let market = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Market,
+            3.0,
+            None,
+            "taker".to_string(),
+        )
+        .with_max_slippage_bps(100.0);
+        let market_id = market.id;
+        let trades = engine.submit_order(market).await.unwrap();
+
+        assert_eq!(trades.len(), 2);
+        let order = engine.get_order(market_id).unwrap();
+        assert_eq!(order.status, OrderStatus::Cancelled);
+        assert_eq!(order.filled_quantity, 2.0);
+        assert_eq!(
+            engine
+                .get_orderbook_depth(&symbol, None)
+                .unwrap()
+                .bids
+                .len(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_market_order_quote_quantity() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+
+        for price in [100.0, 200.0] {
+            let ask = Order::new(
+                symbol,
+                OrderSide::Sell,
+                OrderType::Limit,
+                1.0,
+                Some(price),
+                "maker".to_string(),
+            );
+            engine.submit_order(ask).await.unwrap();
+        }
+
+        // 花费 200 USDT：100 买入 1，剩余 100 以 200 买入 0.5
+        let market = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Market,
+            0.0,
+            None,
+            "taker".to_string(),
+        )
+        .with_quote_quantity(200.0);
+        let market_id = market.id;
+        let trades = engine.submit_order(market).await.unwrap();
+
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[1].quantity, 0.5);
+        let order = engine.get_order(market_id).unwrap();
+        assert_eq!(order.status, OrderStatus::Filled);
+        assert_eq!(order.quantity, 1.5);
+
+        let invalid = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            0.0,
+            Some(100.0),
+            "taker".to_string(),
+        )
+        .with_quote_quantity(100.0);
+        assert!(engine.submit_order(invalid).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_all_or_none_resting_order_keeps_queue_position() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+        let ask = |quantity, user: &str| {
+            Order::new(
+                symbol,
+                OrderSide::Sell,
+                OrderType::Limit,
+                quantity,
+                Some(100.0),
+                user.to_string(),
+            )
+        };
+        let bid = |quantity| {
+            Order::new(
+                symbol,
+                OrderSide::Buy,
+                OrderType::Limit,
+                quantity,
+                Some(100.0),
+                "taker".to_string(),
+            )
+        };
+
+        let block = ask(5.0, "block").all_or_none();
+        let block_id = block.id;
+        engine.submit_order(block).await.unwrap();
+        engine.submit_order(ask(2.0, "small")).await.unwrap();
+
+        // 3 不足以完全成交 AON 挂单，跳过它与后面的挂单成交
+        let trades = engine.submit_order(bid(3.0)).await.unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].seller_id, "small");
+        assert_eq!(engine.get_order(block_id).unwrap().filled_quantity, 0.0);
+
+        // 吃单剩余的 1 挂在买盘，新的 5 可以完全成交 AON 挂单
+        let trades = engine.submit_order(bid(5.0)).await.unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].seller_id, "block");
+        assert_eq!(trades[0].quantity, 5.0);
+    }
+
+    #[tokio::test]
+    async fn test_min_fill_counts_only_executable_quantity() {
+        let mut config = EngineConfig::default();
+        config.circuit_breaker = crate::config::CircuitBreakerConfig {
+            enabled: true,
+            max_price_move: 10.0,
+            window_seconds: 300,
+            cooldown_seconds: 60,
+            cancel_only: false,
+        };
+        let engine = MatchingEngine::with_config(config);
+        let symbol = Symbol::new("BTC", "USDT");
+        let ask = |quantity, price, user: &str| {
+            Order::new(
+                symbol,
+                OrderSide::Sell,
+                OrderType::Limit,
+                quantity,
+                Some(price),
+                user.to_string(),
+            )
+        };
+        let bid = |quantity, price| {
+            Order::new(
+                symbol,
+                OrderSide::Buy,
+                OrderType::Limit,
+                quantity,
+                Some(price),
+                "taker".to_string(),
+            )
+        };
+
+        // AON 挂单不会分到 3 以内的数量，实际可成交的只有 1
+        engine
+            .submit_order(ask(5.0, 100.0, "block").all_or_none())
+            .await
+            .unwrap();
+        engine.submit_order(ask(1.0, 100.0, "small")).await.unwrap();
+        let taker = bid(3.0, 100.0).with_min_fill_qty(3.0);
+        let taker_id = taker.id;
+        assert!(engine.submit_order(taker).await.unwrap().is_empty());
+        assert_eq!(engine.get_order(taker_id).unwrap().filled_quantity, 0.0);
+        engine
+            .cancel_order(taker_id, "taker".to_string())
+            .await
+            .unwrap();
+
+        // 会触发熔断的成交之后不再撮合：100 档可成交 6，115 触发熔断，之后的 116 不可成交
+        engine.submit_order(ask(1.0, 115.0, "spike")).await.unwrap();
+        engine.submit_order(ask(5.0, 116.0, "after")).await.unwrap();
+        let sweep = bid(8.0, 116.0).with_min_fill_qty(8.0);
+        let sweep_id = sweep.id;
+        assert!(engine.submit_order(sweep).await.unwrap().is_empty());
+        assert_eq!(engine.get_trading_state(&symbol), TradingState::Trading);
+        assert_eq!(engine.get_stats().total_trades, 0);
+        engine
+            .cancel_order(sweep_id, "taker".to_string())
+            .await
+            .unwrap();
+
+        // 可成交数量达到最小成交数量时按同样的规则撮合，在触发熔断的成交处停止
+        let trades = engine
+            .submit_order(bid(7.0, 116.0).with_min_fill_qty(7.0))
+            .await
+            .unwrap();
+        let filled: f64 = trades.iter().map(|trade| trade.quantity).sum();
+        assert_eq!(filled, 7.0);
+        assert_eq!(trades.last().unwrap().price, 115.0);
+        assert_eq!(engine.get_trading_state(&symbol), TradingState::Halted);
+    }
+
+    #[derive(Debug)]
+    struct MaxOpenOrders(usize);
+
+    impl PreTradeCheck for MaxOpenOrders {
+        fn name(&self) -> &str {
+            "max_open_orders"
+        }
+
+        fn check(&self, context: &PreTradeContext<'_>) -> Result<(), String> {
+            if context.open_orders.len() >= self.0 {
+                return Err(format!("user already has {} open orders", self.0));
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_numbers_rejected() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+        let order = |quantity, price| {
+            Order::new(
+                symbol,
+                OrderSide::Buy,
+                OrderType::Limit,
+                quantity,
+                Some(price),
+                "user1".to_string(),
+            )
+        };
+
+        for (quantity, price) in [
+            (f64::NAN, 100.0),
+            (f64::INFINITY, 100.0),
+            (1.0, f64::NAN),
+            (1.0, f64::INFINITY),
+        ] {
+            let err = engine
+                .submit_order(order(quantity, price))
+                .await
+                .unwrap_err();
+            assert!(err.contains("finite"), "{}", err);
+        }
+        for (quantity, price, reason) in [
+            (-1.0, 100.0, "Order quantity must be positive"),
+            (1.0, 0.0, "Order price must be positive"),
+            (1.0, 1e13, "Order price must not exceed 1000000000000"),
+            (
+                1.0,
+                100.0000001,
+                "Price 100.0000001 is not a multiple of tick size 0.000001",
+            ),
+            (
+                1.0,
+                5e9,
+                "Price 5000000000 cannot be represented exactly with tick size 0.000001",
+            ),
+            (
+                1e-9,
+                100.0,
+                "Order quantity must have at most 8 decimal places",
+            ),
+        ] {
+            let err = engine
+                .submit_order(order(quantity, price))
+                .await
+                .unwrap_err();
+            assert_eq!(err, reason);
+        }
+        let stop = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::StopLoss,
+            1.0,
+            None,
+            "user1".to_string(),
+        )
+        .with_stop_price(f64::INFINITY);
+        assert!(engine.submit_order(stop).await.is_err());
+
+        assert_eq!(engine.get_stats().total_orders, 0);
+        assert_eq!(engine.verify_invariants(), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_per_symbol_tick_size() {
+        let mut config = EngineConfig::default();
+        config
+            .tick_sizes
+            .overrides
+            .insert("BTCUSDT".to_string(), 0.05);
+        config
+            .tick_sizes
+            .overrides
+            .insert("SHIBUSDT".to_string(), 1e-9);
+        let engine = MatchingEngine::with_config(config);
+        let btc = Symbol::new("BTC", "USDT");
+        let shib = Symbol::new("SHIB", "USDT");
+        let order = |symbol, side, price| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                1.0,
+                Some(price),
+                "user1".to_string(),
+            )
+        };
+
+        let err = engine
+            .submit_order(order(btc, OrderSide::Buy, 100.01))
+            .await
+            .unwrap_err();
+        assert_eq!(err, "Price 100.01 is not a multiple of tick size 0.05");
+        engine
+            .submit_order(order(btc, OrderSide::Buy, 100.05))
+            .await
+            .unwrap();
+        // 0.05 的倍数经浮点运算后仍能落到同一价位
+        engine
+            .submit_order(order(btc, OrderSide::Buy, 0.1 + 0.2 + 99.75))
+            .await
+            .unwrap();
+
+        // 低于默认精度的价格在细 tick 交易对上保持独立价位
+        engine
+            .submit_order(order(shib, OrderSide::Sell, 0.000012345))
+            .await
+            .unwrap();
+        engine
+            .submit_order(order(shib, OrderSide::Sell, 0.000012346))
+            .await
+            .unwrap();
+
+        let btc_depth = engine.get_orderbook_depth(&btc, None).unwrap();
+        assert_eq!(btc_depth.bids.len(), 1);
+        assert_eq!(btc_depth.bids[0].price, 100.05);
+        assert_eq!(btc_depth.bids[0].order_count, 2);
+        let shib_depth = engine.get_orderbook_depth(&shib, None).unwrap();
+        assert_eq!(shib_depth.asks.len(), 2);
+        assert_eq!(shib_depth.asks[0].price, 0.000012345);
+        assert_eq!(engine.verify_invariants(), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_pre_trade_check_rejects_order() {
+        let engine = MatchingEngine::new();
+        engine.register_pre_trade_check(Arc::new(MaxOpenOrders(1)));
+        let symbol = Symbol::new("BTC", "USDT");
+        let order = || {
+            Order::new(
+                symbol,
+                OrderSide::Buy,
+                OrderType::Limit,
+                1.0,
+                Some(100.0),
+                "user1".to_string(),
+            )
+        };
+
+        engine.submit_order(order()).await.unwrap();
+        let err = engine.submit_order(order()).await.unwrap_err();
+        assert!(err.contains("max_open_orders"));
+        assert_eq!(engine.get_stats().total_orders, 1);
+    }
+
+    #[tokio::test]
+    async fn test_user_limits() {
+        let mut config = EngineConfig::default();
+        config.user_limits.default.max_open_orders = Some(2);
+        config.user_limits.default.max_open_notional_per_symbol = Some(250.0);
+        config.user_limits.overrides.insert(
+            "market_maker".to_string(),
+            crate::config::UserLimits::default(),
+        );
+        let engine = MatchingEngine::with_config(config);
+        let symbol = Symbol::new("BTC", "USDT");
+        let bid = |price, user: &str| {
+            Order::new(
+                symbol,
+                OrderSide::Buy,
+                OrderType::Limit,
+                1.0,
+                Some(price),
+                user.to_string(),
+            )
+        };
+
+        engine.submit_order(bid(100.0, "user1")).await.unwrap();
+        let err = engine.submit_order(bid(200.0, "user1")).await.unwrap_err();
+        assert!(err.contains("open notional limit"));
+        engine.submit_order(bid(100.0, "user1")).await.unwrap();
+        let err = engine.submit_order(bid(10.0, "user1")).await.unwrap_err();
+        assert!(err.contains("open order limit"));
+
+        let status = engine.get_user_limits("user1");
+        assert_eq!(status.open_orders, 2);
+        assert_eq!(status.open_notional, 200.0);
+
+        // 覆盖配置不限制做市商
+        for _ in 0..3 {
+            engine
+                .submit_order(bid(1000.0, "market_maker"))
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_channel_limits_apply_to_orders_from_channel() {
+        let mut config = EngineConfig::default();
+        config.user_limits.channels.insert(
+            OrderChannel::Fix,
+            crate::config::UserLimits {
+                max_open_orders: Some(1),
+                ..Default::default()
+            },
+        );
+        let engine = MatchingEngine::with_config(config);
+        let symbol = Symbol::new("BTC", "USDT");
+        let bid = |channel| {
+            Order::new(
+                symbol,
+                OrderSide::Buy,
+                OrderType::Limit,
+                1.0,
+                Some(100.0),
+                "desk".to_string(),
+            )
+            .with_channel(channel)
+        };
+
+        engine.submit_order(bid(OrderChannel::Rest)).await.unwrap();
+        engine.submit_order(bid(OrderChannel::Fix)).await.unwrap();
+        let err = engine
+            .submit_order(bid(OrderChannel::Fix))
+            .await
+            .unwrap_err();
+        assert!(err.contains("fix channel open order limit"));
+        engine.submit_order(bid(OrderChannel::Rest)).await.unwrap();
+
+        let accepted = engine.get_open_user_orders("desk");
+        assert_eq!(accepted.len(), 3);
+        assert_eq!(
+            accepted
+                .iter()
+                .filter(|order| order.channel == OrderChannel::Fix)
+                .count(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_trade_history_serves_evicted_trades() {
+        let dir = std::env::temp_dir().join(format!("trade-history-{}", uuid::Uuid::new_v4()));
+        let start = "2024-01-01T23:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let clock = Arc::new(MockClock::new(start));
+        let mut config = EngineConfig::default();
+        config.trade_history = crate::config::TradeHistoryConfig {
+            enabled: true,
+            retention_seconds: 3600,
+            dir: dir.to_string_lossy().into_owned(),
+        };
+        let engine = MatchingEngine::with_clock(config, clock.clone());
+        let symbol = Symbol::new("BTC", "USDT");
+
+        // 每隔 30 分钟成交一笔，跨越 UTC 日期
+        let mut trade_ids = Vec::new();
+        for _ in 0..4 {
+            for (side, user) in [(OrderSide::Sell, "bob"), (OrderSide::Buy, "alice")] {
+                let order = Order::new(
+                    symbol,
+                    side,
+                    OrderType::Limit,
+                    1.0,
+                    Some(100.0),
+                    user.to_string(),
+                );
+                if let Some(trade) = engine.submit_order(order).await.unwrap().first() {
+                    trade_ids.push(trade.id);
+                }
+            }
+            clock.advance(Duration::from_secs(1800));
+        }
+        assert_eq!(engine.evict_trades(), 2);
+
+        let query = |filter: &TradeFilter, cursor, limit| {
+            let page = engine.query_trades(filter, PageRequest { cursor, limit });
+            let ids: Vec<TradeId> = page.items.iter().map(|trade| trade.id).collect();
+            (ids, page.next_cursor)
+        };
+        let all = TradeFilter::default();
+        let (ids, next_cursor) = query(&all, None, 3);
+        assert_eq!(ids, [trade_ids[3], trade_ids[2], trade_ids[1]]);
+        assert_eq!(query(&all, next_cursor, 3), (vec![trade_ids[0]], None));
+
+        let early = TradeFilter {
+            start_time: Some(start),
+            end_time: Some(start + chrono::Duration::minutes(45)),
+            ..Default::default()
+        };
+        assert_eq!(query(&early, None, 10).0, [trade_ids[1], trade_ids[0]]);
+
+        engine
+            .bust_trade(trade_ids[2], "test".to_string(), false)
+            .await
+            .unwrap();
+        assert_eq!(query(&all, None, 10).0.len(), 3);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancel_ratio_throttles_new_orders() {
+        let start = "2024-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let clock = Arc::new(MockClock::new(start));
+        let mut config = EngineConfig::default();
+        config.message_ratios = crate::config::MessageRatioConfig {
+            enabled: true,
+            window_seconds: 60,
+            min_messages: 4,
+            max_cancels_per_fill: 2.0,
+            exempt_users: vec!["market_maker".to_string()],
+            ..Default::default()
+        };
+        let engine = MatchingEngine::with_clock(config, clock.clone());
+        let bid = |user: &str| {
+            Order::new(
+                Symbol::new("BTC", "USDT"),
+                OrderSide::Buy,
+                OrderType::Limit,
+                1.0,
+                Some(100.0),
+                user.to_string(),
+            )
+        };
+        let place_and_cancel = |user: &'static str| {
+            let engine = &engine;
+            async move {
+                let order = bid(user);
+                let order_id = order.id;
+                engine.submit_order(order).await?;
+                engine.cancel_order(order_id, user.to_string()).await?;
+                Ok::<(), String>(())
             }
+        };
+
+        for _ in 0..3 {
+            place_and_cancel("stuffer").await.unwrap();
+            place_and_cancel("market_maker").await.unwrap();
+        }
+        // 3 次撤单、没有成交，超过每笔成交 2 次撤单的上限
+        let err = engine.submit_order(bid("stuffer")).await.unwrap_err();
+        assert!(err.contains("Message rate limit exceeded"));
+        place_and_cancel("market_maker").await.unwrap();
+
+        let counts = engine.get_user_limits("stuffer").message_counts;
+        assert_eq!((counts.orders, counts.cancels, counts.fills), (3, 3, 0));
+
+        // 窗口滚动后恢复
+        clock.advance(Duration::from_secs(61));
+        engine.submit_order(bid("stuffer")).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_terminal_orders_archived() {
+        let dir = std::env::temp_dir().join(format!("engine-archive-{}", Uuid::new_v4()));
+        let mut config = EngineConfig::default();
+        config.order_retention = crate::config::OrderRetentionConfig {
+            enabled: true,
+            retention_seconds: 0,
+            archive_dir: dir.to_string_lossy().to_string(),
+            segment_max_orders: 100,
+        };
+        let engine = MatchingEngine::with_config(config);
+        let symbol = Symbol::new("BTC", "USDT");
+
+        let resting = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            "user1".to_string(),
+        );
+        let cancelled = resting.clone();
+        let resting = Order {
+            id: crate::id::next_order_id(),
+            ..resting
+        };
+        engine.submit_order(cancelled.clone()).await.unwrap();
+        engine.submit_order(resting.clone()).await.unwrap();
+        engine
+            .cancel_order(cancelled.id, "user1".to_string())
+            .await
+            .unwrap();
+
+        // 只有终态订单被归档，归档后仍可查询
+        assert_eq!(engine.archive_terminal_orders(), 1);
+        assert_eq!(engine.get_user_orders("user1").len(), 1);
+        assert_eq!(
+            engine.get_order(cancelled.id).unwrap().status,
+            OrderStatus::Cancelled
+        );
+        assert_eq!(
+            engine.get_order(resting.id).unwrap().status,
+            OrderStatus::New
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_order_lifecycle_audit_trail() {
+        let dir = std::env::temp_dir().join(format!("engine-audit-{}", Uuid::new_v4()));
+        let mut config = EngineConfig::default();
+        config.audit = crate::config::AuditConfig {
+            enabled: true,
+            dir: dir.to_string_lossy().to_string(),
+        };
+        let engine = MatchingEngine::with_config(config);
+        let symbol = Symbol::new("BTC", "USDT");
+
+        let sell = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::Limit,
+            2.0,
+            Some(100.0),
+            "user2".to_string(),
+        );
+        let buy = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            "user1".to_string(),
+        );
+        let invalid = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            0.0,
+            Some(100.0),
+            "user1".to_string(),
+        );
+        engine.submit_order(sell.clone()).await.unwrap();
+        let trades = engine.submit_order(buy.clone()).await.unwrap();
+        engine
+            .cancel_order(sell.id, "user2".to_string())
+            .await
+            .unwrap();
+        let submitted =
+            crate::request_id::scope("req-1".to_string(), engine.submit_order(invalid.clone()));
+        assert!(submitted.await.is_err());
+
+        let fill = AuditFill {
+            trade_id: trades[0].id,
+            counterparty_order_id: buy.id,
+            quantity: 1.0,
+            price: 100.0,
+            remaining_quantity: 1.0,
+        };
+        let sell_events = engine.get_order_audit(sell.id).unwrap();
+        let kinds: Vec<AuditEventKind> = sell_events.iter().map(|e| e.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                AuditEventKind::Accepted {
+                    request_id: None,
+                    channel: OrderChannel::Internal,
+                },
+                AuditEventKind::PartiallyFilled(fill),
+                AuditEventKind::Cancelled {
+                    reason: "Cancelled by user".to_string()
+                },
+            ]
+        );
+        assert!(sell_events
+            .windows(2)
+            .all(|pair| pair[0].sequence < pair[1].sequence));
+
+        let buy_events = engine.get_order_audit(buy.id).unwrap();
+        assert!(matches!(
+            buy_events.last().unwrap().kind,
+            AuditEventKind::Filled(AuditFill { counterparty_order_id, .. })
+                if counterparty_order_id == sell.id
+        ));
+
+        let rejected = engine.get_order_audit(invalid.id).unwrap();
+        assert_eq!(rejected.len(), 1);
+        assert!(matches!(
+            &rejected[0].kind,
+            AuditEventKind::Rejected { request_id: Some(id), .. } if id == "req-1"
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_drop_copy_records_all_users_in_sequence() {
+        assert!(MatchingEngine::new().subscribe_drop_copy(None).is_err());
+
+        let mut config = EngineConfig::default();
+        config.drop_copy.enabled = true;
+        let engine = MatchingEngine::with_config(config);
+        let symbol = Symbol::new("BTC", "USDT");
+
+        let sell = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            "user2".to_string(),
+        );
+        let invalid = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            0.0,
+            Some(100.0),
+            "user1".to_string(),
+        );
+        engine.submit_order(sell.clone()).await.unwrap();
+        let mut live = engine.subscribe_drop_copy(None).unwrap();
+        let live_from = live.next_sequence;
+        engine
+            .submit_order(Order::new(
+                symbol,
+                OrderSide::Buy,
+                OrderType::Limit,
+                1.0,
+                Some(100.0),
+                "user1".to_string(),
+            ))
+            .await
+            .unwrap();
+        assert!(engine.submit_order(invalid.clone()).await.is_err());
 
-            let matching_order = &matching_entry.order;
+        // 沿用事件总线的序号，盘口等其它事件占用的序号被跳过
+        let events = engine.subscribe_drop_copy(Some(1)).unwrap().replay;
+        assert!(events
+            .windows(2)
+            .all(|pair| pair[0].sequence < pair[1].sequence));
+        assert!(events.iter().all(|event| event.event.is_drop_copy()));
+        assert!(matches!(&events[0].event, EngineEvent::OrderUpdate(order) if order.id == sell.id));
+        assert_eq!(
+            events
+                .iter()
+                .filter(|event| matches!(event.event, EngineEvent::Trade(_)))
+                .count(),
+            1
+        );
+        assert!(matches!(
+            &events.last().unwrap().event,
+            EngineEvent::OrderRejected { order, .. }
+                if order.id == invalid.id && order.status == OrderStatus::Rejected
+        ));
 
-            // 检查是否可以匹配
-            if !incoming_order.can_match(matching_order) {
-                continue;
-            }
+        // 实时订阅从订阅时的下一个序号开始，与回放中的同一事件序号一致
+        let first_live = events
+            .iter()
+            .find(|event| event.sequence >= live_from)
+            .unwrap();
+        assert_eq!(live.try_recv().unwrap().sequence, first_live.sequence);
+    }
 
-            // 计算匹配数量
-            let match_quantity = remaining_quantity.min(matching_order.remaining_quantity);
+    #[tokio::test]
+    async fn test_shutdown_rejects_new_orders_and_writes_snapshot() {
+        let dir = std::env::temp_dir().join(format!("engine-shutdown-{}", Uuid::new_v4()));
+        let snapshot_path = dir.join("snapshot.json");
+        let mut config = EngineConfig::default();
+        config.shutdown.snapshot_path = Some(snapshot_path.to_string_lossy().to_string());
+        let engine = MatchingEngine::with_config(config);
+        let symbol = Symbol::new("BTC", "USDT");
 
-            // 计算匹配价格
-            let match_price = incoming_order.match_price(matching_order);
+        let resting = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            "user1".to_string(),
+        );
+        engine.submit_order(resting.clone()).await.unwrap();
 
-            // 创建交易
-            let trade = Trade::new(
-                incoming_order.symbol.clone(),
-                incoming_order,
-                matching_order,
-                match_quantity,
-                match_price,
-            );
+        engine.shutdown().await.unwrap();
+        assert!(engine.is_shutting_down());
+        assert!(engine.drain(Duration::ZERO).await);
 
-            // 更新订单数量
-            remaining_quantity -= match_quantity;
-            incoming_order.filled_quantity += match_quantity;
-            incoming_order.remaining_quantity = remaining_quantity;
+        // 停机后拒绝新订单，撤单仍然受理
+        let late = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            "user2".to_string(),
+        );
+        assert!(engine.submit_order(late).await.is_err());
 
-            // 更新匹配订单
-            let new_matching_quantity = matching_order.remaining_quantity - match_quantity;
-            orderbook.update_order(matching_order.id, new_matching_quantity)?;
+        let snapshot: EngineSnapshot =
+            serde_json::from_slice(&std::fs::read(&snapshot_path).unwrap()).unwrap();
+        assert_eq!(snapshot.open_orders.len(), 1);
+        assert_eq!(snapshot.open_orders[0].id, resting.id);
 
-            // 如果匹配订单完全成交，从订单簿中移除
-            if new_matching_quantity <= 0.0 {
-                let mut filled_order = orderbook.remove_order(matching_order.id)?;
-                filled_order.status = OrderStatus::Filled;
-                filled_order.filled_quantity = filled_order.quantity;
-                filled_order.remaining_quantity = 0.0;
+        engine
+            .cancel_order(resting.id, "user1".to_string())
+            .await
+            .unwrap();
 
-                // 更新订单存储
-                {
-                    let mut orders = self.orders.write().unwrap();
-                    orders.insert(filled_order.id, filled_order.clone());
-                }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 
-                // 广播订单更新
-                let _ = self.order_sender.send(filled_order);
+    #[tokio::test]
+    async fn test_order_ids_increase_and_external_alias() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+        let external_id = Uuid::new_v4();
 
-                // 更新统计信息
-                {
-                    let mut stats = self.stats.write().unwrap();
-                    stats.active_orders = stats.active_orders.saturating_sub(1);
-                }
-            }
+        let first = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            "seller".to_string(),
+        )
+        .with_external_id(external_id);
+        let second = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            "buyer".to_string(),
+        );
+        assert!(first.id < second.id);
 
-            // 存储交易
-            {
-                let mut trades_store = self.trades.write().unwrap();
-                trades_store.push(trade.clone());
-            }
+        engine.submit_order(first.clone()).await.unwrap();
+        let trades = engine.submit_order(second.clone()).await.unwrap();
+        assert_eq!(trades[0].sell_order_id, first.id);
+        assert!(trades[0].id > second.id);
 
-            // 更新统计信息
-            {
-                let mut stats = self.stats.write().unwrap();
-                stats.total_trades += 1;
-                stats.total_volume += trade.quantity * trade.price;
-            }
+        assert_eq!(engine.resolve_external_id(external_id), Some(first.id));
+        assert_eq!(
+            engine.get_order_by_external_id(external_id).unwrap().status,
+            OrderStatus::Filled
+        );
 
-            // 广播交易
-            let _ = self.trade_sender.send(trade.clone());
-            let trade_id = trade.id;
-            trades.push(trade);
+        // 同一别名不能重复使用
+        let duplicate = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            "seller".to_string(),
+        )
+        .with_external_id(external_id);
+        assert!(engine.submit_order(duplicate).await.is_err());
+    }
 
-            info!(
-                "Trade executed: {} {} at {} for {}",
-                match_quantity,
-                incoming_order.symbol.to_string(),
-                match_price,
-                trade_id
-            );
-        }
+    #[tokio::test]
+    async fn test_event_bus_orders_events_and_resumes_from_journal() {
+        let dir = std::env::temp_dir().join(format!("event-bus-{}", uuid::Uuid::new_v4()));
+        let mut config = EngineConfig::default();
+        config.event_journal = crate::config::EventJournalConfig {
+            enabled: true,
+            dir: dir.to_string_lossy().into_owned(),
+        };
+        let symbol = Symbol::new("BTC", "USDT");
+        let order = |side, user: &str| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                1.0,
+                Some(100.0),
+                user.to_string(),
+            )
+        };
 
-        Ok(trades)
-    }
+        let last_sequence = {
+            let engine = MatchingEngine::with_config(config.clone());
+            let mut events = engine.subscribe_events();
+            engine
+                .submit_order(order(OrderSide::Sell, "maker"))
+                .await
+                .unwrap();
+            engine
+                .submit_order(order(OrderSide::Buy, "taker"))
+                .await
+                .unwrap();
+            engine.publish_market_data();
 
-    /// 更新市场数据
-    async fn update_market_data(&self, symbol: &Symbol) {
-        let orderbook = match self.get_orderbook(symbol) {
-            Some(ob) => ob,
-            None => return,
+            let mut received = Vec::new();
+            while let Ok(event) = events.try_recv() {
+                received.push((event.sequence, event.event.kind()));
+            }
+            assert!(received.windows(2).all(|pair| pair[1].0 == pair[0].0 + 1));
+            // 盘口和聚合成交与订单、成交共用同一序号
+            assert!(received.iter().any(|(_, kind)| *kind == "book_ticker"));
+            assert!(received.iter().any(|(_, kind)| *kind == "agg_trade"));
+            let kinds: Vec<&str> = received
+                .iter()
+                .map(|(_, kind)| *kind)
+                .filter(|kind| !matches!(*kind, "book_ticker" | "agg_trade"))
+                .collect();
+            assert_eq!(
+                kinds,
+                [
+                    "order_update",
+                    "trade",
+                    "order_update",
+                    "order_update",
+                    "market_data"
+                ]
+            );
+            engine.last_event_sequence()
         };
 
-        let _best_bid = orderbook.best_bid();
-        let _best_ask = orderbook.best_ask();
-        let _spread = orderbook.spread();
+        // 重启后序号接着事件日志继续
+        let engine = MatchingEngine::with_config(config);
+        assert_eq!(engine.last_event_sequence(), last_sequence);
+        let mut events = engine.subscribe_events();
+        engine
+            .submit_order(order(OrderSide::Sell, "maker"))
+            .await
+            .unwrap();
+        assert_eq!(events.try_recv().unwrap().sequence, last_sequence + 1);
 
-        // 获取最近的交易来计算24小时数据
-        let recent_trades = self.get_trades(Some(symbol), Some(1000));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 
-        let mut volume_24h = 0.0;
-        let mut high_24h: f64 = 0.0;
-        let mut low_24h: f64 = f64::MAX;
-        let mut last_price = 0.0;
+    #[tokio::test]
+    async fn test_market_data_published_outside_submit_path() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+        let mut receiver = engine.subscribe_market_data();
 
-        for trade in &recent_trades {
-            volume_24h += trade.quantity * trade.price;
-            high_24h = high_24h.max(trade.price);
-            low_24h = low_24h.min(trade.price);
-            last_price = trade.price;
+        for (side, user) in [(OrderSide::Sell, "maker"), (OrderSide::Buy, "taker")] {
+            engine
+                .submit_order(Order::new(
+                    symbol,
+                    side,
+                    OrderType::Limit,
+                    2.0,
+                    Some(100.0),
+                    user.to_string(),
+                ))
+                .await
+                .unwrap();
         }
+        assert!(receiver.try_recv().is_err());
 
-        if low_24h == f64::MAX {
-            low_24h = 0.0;
-        }
+        // 查询不等待后台任务
+        let market_data = engine.get_market_data(&symbol).unwrap();
+        assert_eq!(market_data.last_price, 100.0);
+        assert_eq!(market_data.volume_24h, 200.0);
 
-        // 计算24小时价格变化
-        let price_change_24h = if recent_trades.len() > 1 {
-            let first_price = recent_trades.last().unwrap().price;
-            ((last_price - first_price) / first_price) * 100.0
-        } else {
-            0.0
-        };
+        // 两次下单合并为一次广播
+        assert_eq!(engine.publish_market_data(), 1);
+        assert_eq!(receiver.try_recv().unwrap().last_price, 100.0);
+        assert_eq!(engine.publish_market_data(), 0);
+    }
 
-        let market_data = MarketData {
-            symbol: symbol.clone(),
-            last_price,
-            volume_24h,
-            price_change_24h,
-            high_24h,
-            low_24h,
-            timestamp: Utc::now(),
+    #[tokio::test]
+    async fn test_market_data_coalesced_by_change_and_interval() {
+        let clock = Arc::new(MockClock::new(Utc::now()));
+        let mut config = EngineConfig::default();
+        config.market_data.min_publish_interval_ms = 1000;
+        let engine = MatchingEngine::with_clock(config, clock.clone());
+        let symbol = Symbol::new("BTC", "USDT");
+        let mut receiver = engine.subscribe_market_data();
+        let order = |side, price, user: &str| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                1.0,
+                Some(price),
+                user.to_string(),
+            )
         };
 
-        {
-            let mut market_data_store = self.market_data.write().unwrap();
-            market_data_store.insert(symbol.clone(), market_data);
-        }
-    }
-}
+        engine
+            .submit_order(order(OrderSide::Sell, 100.0, "maker"))
+            .await
+            .unwrap();
+        engine
+            .submit_order(order(OrderSide::Buy, 100.0, "taker"))
+            .await
+            .unwrap();
+        assert_eq!(engine.publish_market_data(), 1);
+        assert_eq!(receiver.try_recv().unwrap().last_price, 100.0);
 
-impl Default for MatchingEngine {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+        // 没有成交的挂单不改变市场数据，不重复推送
+        clock.advance(Duration::from_secs(2));
+        engine
+            .submit_order(order(OrderSide::Buy, 90.0, "taker"))
+            .await
+            .unwrap();
+        assert_eq!(engine.publish_market_data(), 0);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        engine
+            .submit_order(order(OrderSide::Sell, 90.0, "maker"))
+            .await
+            .unwrap();
+        assert_eq!(engine.publish_market_data(), 1);
+        assert_eq!(receiver.try_recv().unwrap().last_price, 90.0);
+
+        // 间隔内的新成交推迟到间隔结束后推送，查询不受影响
+        engine
+            .submit_order(order(OrderSide::Sell, 110.0, "maker"))
+            .await
+            .unwrap();
+        engine
+            .submit_order(order(OrderSide::Buy, 110.0, "taker"))
+            .await
+            .unwrap();
+        assert_eq!(engine.publish_market_data(), 0);
+        assert_eq!(engine.get_market_data(&symbol).unwrap().last_price, 110.0);
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(engine.publish_market_data(), 1);
+        assert_eq!(receiver.try_recv().unwrap().last_price, 110.0);
+        assert!(receiver.try_recv().is_err());
+    }
 
     #[tokio::test]
-    async fn test_matching_engine_basic_matching() {
+    async fn test_order_tag_carried_to_fills_and_filterable() {
         let engine = MatchingEngine::new();
         let symbol = Symbol::new("BTC", "USDT");
 
-        // 提交卖单
-        let sell_order = Order::new(
-            symbol.clone(),
+        let maker = Order::new(
+            symbol,
             OrderSide::Sell,
             OrderType::Limit,
             1.0,
-            Some(50000.0),
-            "seller".to_string(),
+            Some(100.0),
+            "maker".to_string(),
+        )
+        .with_tag("mm-v2");
+        let untagged = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::Limit,
+            1.0,
+            Some(101.0),
+            "maker".to_string(),
         );
+        let taker = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            "taker".to_string(),
+        )
+        .with_tag("twap-7");
+        engine.submit_order(maker.clone()).await.unwrap();
+        engine.submit_order(untagged).await.unwrap();
+        let trades = engine.submit_order(taker).await.unwrap();
+        assert_eq!(trades[0].buy_tag.as_deref(), Some("twap-7"));
+        assert_eq!(trades[0].sell_tag.as_deref(), Some("mm-v2"));
 
-        let trades = engine.submit_order(sell_order).await.unwrap();
-        assert_eq!(trades.len(), 0); // 没有匹配的买单
+        let tagged = OrderFilter {
+            tag: Some("mm-v2".to_string()),
+            ..OrderFilter::default()
+        };
+        let orders = engine
+            .query_user_orders("maker", &tagged, PageRequest::default())
+            .items;
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].id, maker.id);
 
-        // 提交买单
-        let buy_order = Order::new(
-            symbol.clone(),
+        let fills = engine
+            .query_user_fills(
+                "taker",
+                &FillFilter {
+                    tag: Some("twap-7".to_string()),
+                    ..FillFilter::default()
+                },
+                PageRequest::default(),
+            )
+            .items;
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].tag.as_deref(), Some("twap-7"));
+
+        let too_long = Order::new(
+            symbol,
             OrderSide::Buy,
             OrderType::Limit,
             1.0,
-            Some(50000.0),
-            "buyer".to_string(),
-        );
-
-        let trades = engine.submit_order(buy_order).await.unwrap();
-        assert_eq!(trades.len(), 1); // 应该有一个交易
-        assert_eq!(trades[0].quantity, 1.0);
-        assert_eq!(trades[0].price, 50000.0);
+            Some(99.0),
+            "taker".to_string(),
+        )
+        .with_tag("x".repeat(MAX_TAG_LEN + 1));
+        assert!(engine.submit_order(too_long).await.is_err());
     }
 
     #[tokio::test]
-    async fn test_matching_engine_partial_fill() {
-        let engine = MatchingEngine::new();
+    async fn test_user_fills_record_role_and_fee() {
+        let mut config = EngineConfig::default();
+        config.fees = crate::config::FeeConfig {
+            maker_rate: -0.0001,
+            taker_rate: 0.001,
+            ..Default::default()
+        };
+        let engine = MatchingEngine::with_config(config);
         let symbol = Symbol::new("BTC", "USDT");
 
-        // 提交大卖单
-        let sell_order = Order::new(
-            symbol.clone(),
+        let maker = Order::new(
+            symbol,
             OrderSide::Sell,
             OrderType::Limit,
             2.0,
-            Some(50000.0),
-            "seller".to_string(),
+            Some(100.0),
+            "maker".to_string(),
+        );
+        let taker = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            2.0,
+            Some(100.0),
+            "taker".to_string(),
         );
+        engine.submit_order(maker.clone()).await.unwrap();
+        engine.submit_order(taker.clone()).await.unwrap();
 
-        engine.submit_order(sell_order).await.unwrap();
+        let taker_fills = engine
+            .query_user_fills("taker", &FillFilter::default(), PageRequest::default())
+            .items;
+        assert_eq!(taker_fills.len(), 1);
+        assert_eq!(taker_fills[0].order_id, taker.id);
+        assert_eq!(taker_fills[0].side, OrderSide::Buy);
+        assert_eq!(taker_fills[0].role, LiquidityRole::Taker);
+        assert!((taker_fills[0].fee - 0.2).abs() < 1e-9);
+        assert_eq!(taker_fills[0].fee_asset, "USDT");
 
-        // 提交小买单
-        let buy_order = Order::new(
-            symbol.clone(),
+        let maker_fills = engine
+            .query_user_fills("maker", &FillFilter::default(), PageRequest::default())
+            .items;
+        assert_eq!(maker_fills[0].role, LiquidityRole::Maker);
+        assert!((maker_fills[0].fee + 0.02).abs() < 1e-9);
+
+        let takers_only = FillFilter {
+            role: Some(LiquidityRole::Taker),
+            ..FillFilter::default()
+        };
+        assert!(engine
+            .query_user_fills("maker", &takers_only, PageRequest::default())
+            .items
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fee_tier_follows_rolling_volume() {
+        let mut config = EngineConfig::default();
+        config.fees = crate::config::FeeConfig {
+            maker_rate: 0.0,
+            taker_rate: 0.001,
+            tiers: vec![crate::config::FeeTier {
+                min_volume: 300.0,
+                maker_rate: 0.0,
+                taker_rate: 0.0005,
+            }],
+            ..Default::default()
+        };
+        let engine = MatchingEngine::with_config(config);
+        let symbol = Symbol::new("BTC", "USDT");
+        let order = |side, user: &str| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                2.0,
+                Some(100.0),
+                user.to_string(),
+            )
+        };
+        let last_fee = |engine: &MatchingEngine| {
+            engine
+                .query_user_fills("taker", &FillFilter::default(), PageRequest::default())
+                .items[0]
+                .fee
+        };
+
+        // 第一笔按基础费率计费，计费后成交额达到第一档
+        engine
+            .submit_order(order(OrderSide::Sell, "maker"))
+            .await
+            .unwrap();
+        engine
+            .submit_order(order(OrderSide::Buy, "taker"))
+            .await
+            .unwrap();
+        assert!((last_fee(&engine) - 0.2).abs() < 1e-9);
+        let tier = engine.get_user_fees("taker");
+        assert_eq!((tier.tier, tier.volume), (0, 200.0));
+        assert_eq!(tier.next_tier_volume, Some(300.0));
+
+        engine
+            .submit_order(order(OrderSide::Sell, "maker"))
+            .await
+            .unwrap();
+        engine
+            .submit_order(order(OrderSide::Buy, "taker"))
+            .await
+            .unwrap();
+        assert!((last_fee(&engine) - 0.2).abs() < 1e-9);
+        let tier = engine.get_user_fees("taker");
+        assert_eq!((tier.tier, tier.taker_rate), (1, 0.0005));
+        assert_eq!(tier.next_tier_volume, None);
+
+        engine
+            .submit_order(order(OrderSide::Sell, "maker"))
+            .await
+            .unwrap();
+        let trades = engine
+            .submit_order(order(OrderSide::Buy, "taker"))
+            .await
+            .unwrap();
+        assert!((last_fee(&engine) - 0.1).abs() < 1e-9);
+
+        // 撤销成交扣回成交额
+        engine
+            .bust_trade(trades[0].id, "test".to_string(), false)
+            .await
+            .unwrap();
+        assert_eq!(engine.get_user_fees("taker").volume, 400.0);
+    }
+
+    #[tokio::test]
+    async fn test_fee_settlement_pays_rebates_from_fee_account() {
+        let mut config = EngineConfig::default();
+        config.fees = crate::config::FeeConfig {
+            maker_rate: -0.0001,
+            taker_rate: 0.001,
+            ..Default::default()
+        };
+        config.fees.settlement.enabled = true;
+        let engine = MatchingEngine::with_config(config);
+        let symbol = Symbol::new("BTC", "USDT");
+        for (side, user) in [(OrderSide::Sell, "maker"), (OrderSide::Buy, "taker")] {
+            engine
+                .submit_order(Order::new(
+                    symbol,
+                    side,
+                    OrderType::Limit,
+                    2.0,
+                    Some(100.0),
+                    user.to_string(),
+                ))
+                .await
+                .unwrap();
+        }
+        let rebates = engine.get_user_rebates("maker").unwrap();
+        assert!((rebates.accrued["USDT"] - 0.02).abs() < 1e-9);
+
+        let settlement = engine.settle_fees(Utc::now()).unwrap();
+        assert_eq!(settlement.rebated_users, 1);
+        let pool = engine.accounts.get_balance("fee-revenue", "USDT").available;
+        assert!((pool - 0.18).abs() < 1e-9);
+
+        let report = engine.get_fee_revenue(None).unwrap();
+        assert!(report.current_period.assets.is_empty());
+        assert_eq!(report.settlements.len(), 1);
+        let rebates = engine.get_user_rebates("maker").unwrap();
+        assert!(rebates.accrued.is_empty());
+        assert!((rebates.settled["USDT"] - 0.02).abs() < 1e-9);
+        assert!(MatchingEngine::new().get_user_rebates("maker").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_symbol_stats() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("ETH", "USDT");
+        assert!(engine.get_symbol_stats(&symbol).is_none());
+
+        for (side, quantity, price) in [
+            (OrderSide::Sell, 2.0, 101.0),
+            (OrderSide::Buy, 1.0, 99.0),
+            (OrderSide::Buy, 0.5, 101.0),
+        ] {
+            let order = Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                quantity,
+                Some(price),
+                "user1".to_string(),
+            );
+            engine.submit_order(order).await.unwrap();
+        }
+
+        let stats = engine.get_symbol_stats(&symbol).unwrap();
+        assert_eq!(stats.total_orders, 3);
+        assert_eq!(stats.total_trades, 1);
+        assert!((stats.volume - 0.5).abs() < 1e-9);
+        assert!((stats.quote_volume - 50.5).abs() < 1e-9);
+        assert!((stats.open_bid_quantity - 1.0).abs() < 1e-9);
+        assert!((stats.open_ask_quantity - 1.5).abs() < 1e-9);
+        assert_eq!(stats.best_bid, Some(99.0));
+        assert_eq!(stats.best_ask, Some(101.0));
+        assert_eq!(stats.spread, Some(2.0));
+        assert_eq!(stats.last_price, Some(101.0));
+        assert_eq!(engine.get_all_symbol_stats().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sweep_publishes_agg_trades() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+        let mut receiver = engine.subscribe_agg_trades();
+
+        for price in [100.0, 100.0, 101.0] {
+            let maker = Order::new(
+                symbol,
+                OrderSide::Sell,
+                OrderType::Limit,
+                1.0,
+                Some(price),
+                "maker".to_string(),
+            );
+            engine.submit_order(maker).await.unwrap();
+        }
+        assert!(receiver.try_recv().is_err());
+
+        let taker = Order::new(
+            symbol,
             OrderSide::Buy,
             OrderType::Limit,
-            1.0,
-            Some(50000.0),
-            "buyer".to_string(),
+            3.0,
+            Some(101.0),
+            "taker".to_string(),
         );
+        let taker_id = taker.id;
+        let trades = engine.submit_order(taker).await.unwrap();
+        assert_eq!(trades.len(), 3);
 
-        let trades = engine.submit_order(buy_order).await.unwrap();
+        // 三笔成交按价格合并为两条
+        let first = receiver.try_recv().unwrap();
+        assert_eq!(first.price, 100.0);
+        assert_eq!(first.quantity, 2.0);
+        assert_eq!(first.first_trade_id, trades[0].id);
+        assert_eq!(first.last_trade_id, trades[1].id);
+        assert_eq!(first.taker_order_id, taker_id);
+        let second = receiver.try_recv().unwrap();
+        assert_eq!(second.price, 101.0);
+        assert_eq!(second.first_trade_id, trades[2].id);
+        assert!(receiver.try_recv().is_err());
+
+        let filter = TradeFilter {
+            symbol: Some(symbol),
+            ..TradeFilter::default()
+        };
+        let page = engine.query_agg_trades(&filter, PageRequest::default());
+        assert_eq!(page.items, [second, first]);
+    }
+
+    #[tokio::test]
+    async fn test_orderbook_snapshot_roundtrip() {
+        let source = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+        for (side, price, user) in [
+            (OrderSide::Buy, 99.0, "alice"),
+            (OrderSide::Buy, 99.0, "bob"),
+            (OrderSide::Sell, 101.0, "carol"),
+        ] {
+            let order = Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                1.0,
+                Some(price),
+                user.to_string(),
+            );
+            source.submit_order(order).await.unwrap();
+        }
+
+        let snapshot = source.export_orderbook(&symbol).unwrap();
+        assert_eq!(snapshot.entries.len(), 3);
+        let json = serde_json::to_string(&snapshot).unwrap();
+
+        let target = MatchingEngine::new();
+        let imported = target
+            .import_orderbook(serde_json::from_str(&json).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(imported, 3);
+        assert_eq!(target.get_stats().active_orders, 3);
+        assert_eq!(target.get_user_orders("bob").len(), 1);
+
+        // 只能导入空订单簿
+        assert!(target.import_orderbook(snapshot.clone()).await.is_err());
+
+        // 导入后保留时间优先级：alice 先于 bob 成交
+        let taker = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::Limit,
+            1.0,
+            Some(99.0),
+            "dave".to_string(),
+        );
+        let trades = target.submit_order(taker).await.unwrap();
         assert_eq!(trades.len(), 1);
-        assert_eq!(trades[0].quantity, 1.0);
+        assert_eq!(trades[0].buyer_id, "alice");
 
-        // 检查卖单是否部分成交
-        let orderbook_depth = engine.get_orderbook_depth(&symbol, None).unwrap();
-        assert_eq!(orderbook_depth.asks.len(), 1);
-        assert_eq!(orderbook_depth.asks[0].total_quantity, 1.0);
+        // 连续交易状态下拒绝交叉的快照
+        let mut crossed = snapshot;
+        crossed.entries[0].order.price = Some(102.0);
+        assert!(MatchingEngine::new()
+            .import_orderbook(crossed)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_import_orderbook_waits_for_matching_lock() {
+        let symbol = Symbol::new("BTC", "USDT");
+        let source = MatchingEngine::new();
+        source
+            .submit_order(Order::new(
+                symbol,
+                OrderSide::Buy,
+                OrderType::Limit,
+                1.0,
+                Some(99.0),
+                "alice".to_string(),
+            ))
+            .await
+            .unwrap();
+        let snapshot = source.export_orderbook(&symbol).unwrap();
+
+        let target = Arc::new(MatchingEngine::new());
+        let matching = target
+            .get_or_create_orderbook(&symbol)
+            .lock_matching()
+            .await;
+        let import = tokio::spawn({
+            let target = Arc::clone(&target);
+            async move { target.import_orderbook(snapshot).await }
+        });
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(target.get_stats().active_orders, 0);
+
+        drop(matching);
+        assert_eq!(import.await.unwrap().unwrap(), 1);
+        assert_eq!(target.get_stats().active_orders, 1);
+    }
+
+    /// 随机操作序列：价格和数量取整数，避免浮点误差影响守恒检查
+    #[derive(Debug, Clone)]
+    enum Op {
+        Submit {
+            side: OrderSide,
+            price: u32,
+            quantity: u32,
+            user: u8,
+        },
+        Cancel {
+            index: usize,
+        },
+        /// 撤单后以新数量重新下单
+        Amend {
+            index: usize,
+            quantity: u32,
+        },
+    }
+
+    fn op_strategy() -> impl proptest::strategy::Strategy<Value = Op> {
+        use proptest::prelude::*;
+
+        let side = prop_oneof![Just(OrderSide::Buy), Just(OrderSide::Sell)];
+        prop_oneof![
+            4 => (side, 95u32..=105, 1u32..=10, 0u8..3).prop_map(|(side, price, quantity, user)| {
+                Op::Submit {
+                    side,
+                    price,
+                    quantity,
+                    user,
+                }
+            }),
+            1 => any::<usize>().prop_map(|index| Op::Cancel { index }),
+            1 => (any::<usize>(), 1u32..=10)
+                .prop_map(|(index, quantity)| Op::Amend { index, quantity }),
+        ]
+    }
+
+    /// 订单数量去向：成交、撤销或仍在订单簿中
+    #[derive(Default)]
+    struct QuantityModel {
+        /// 已接受订单的 (订单ID, 用户, 方向, 价格)
+        orders: Vec<(OrderId, String, OrderSide, f64)>,
+        quantities: HashMap<OrderId, f64>,
+        filled: HashMap<OrderId, f64>,
+        cancelled: HashMap<OrderId, f64>,
+    }
+
+    impl QuantityModel {
+        async fn submit(
+            &mut self,
+            engine: &MatchingEngine,
+            side: OrderSide,
+            price: f64,
+            quantity: f64,
+            user: String,
+        ) {
+            let symbol = Symbol::new("BTC", "USDT");
+            let order = Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                quantity,
+                Some(price),
+                user.clone(),
+            );
+            let order_id = order.id;
+            let Ok(trades) = engine.submit_order(order).await else {
+                return;
+            };
+
+            self.orders.push((order_id, user, side, price));
+            self.quantities.insert(order_id, quantity);
+            for trade in trades {
+                *self.filled.entry(trade.buy_order_id).or_default() += trade.quantity;
+                *self.filled.entry(trade.sell_order_id).or_default() += trade.quantity;
+            }
+        }
+
+        async fn cancel(
+            &mut self,
+            engine: &MatchingEngine,
+            index: usize,
+        ) -> Option<(String, OrderSide, f64)> {
+            if self.orders.is_empty() {
+                return None;
+            }
+            let (order_id, user, side, price) = self.orders[index % self.orders.len()].clone();
+            let cancelled = engine.cancel_order(order_id, user.clone()).await.ok()?;
+            *self.cancelled.entry(order_id).or_default() += cancelled.remaining_quantity;
+            Some((user, side, price))
+        }
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn prop_random_order_flow_preserves_invariants(
+            ops in proptest::collection::vec(op_strategy(), 1..60)
+        ) {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            let engine = MatchingEngine::new();
+            let mut model = QuantityModel::default();
+
+            runtime.block_on(async {
+                for op in &ops {
+                    match op {
+                        Op::Submit {
+                            side,
+                            price,
+                            quantity,
+                            user,
+                        } => {
+                            model
+                                .submit(
+                                    &engine,
+                                    *side,
+                                    *price as f64,
+                                    *quantity as f64,
+                                    format!("user{}", user),
+                                )
+                                .await
+                        }
+                        Op::Cancel { index } => {
+                            model.cancel(&engine, *index).await;
+                        }
+                        Op::Amend { index, quantity } => {
+                            if let Some((user, side, price)) = model.cancel(&engine, *index).await {
+                                model
+                                    .submit(&engine, side, price, *quantity as f64, user)
+                                    .await;
+                            }
+                        }
+                    }
+                    proptest::prop_assert_eq!(engine.verify_invariants(), Ok(()), "after {:?}", op);
+                }
+                Ok(())
+            })?;
+
+            // 每个订单的数量 = 成交 + 撤销 + 仍挂在订单簿中的剩余数量
+            let resting: HashMap<OrderId, f64> = engine
+                .export_orderbook(&Symbol::new("BTC", "USDT"))
+                .map(|snapshot| {
+                    snapshot
+                        .entries
+                        .into_iter()
+                        .map(|entry| (entry.order.id, entry.order.remaining_quantity))
+                        .collect()
+                })
+                .unwrap_or_default();
+            for (order_id, quantity) in &model.quantities {
+                let accounted = model.filled.get(order_id).copied().unwrap_or(0.0)
+                    + model.cancelled.get(order_id).copied().unwrap_or(0.0)
+                    + resting.get(order_id).copied().unwrap_or(0.0);
+                proptest::prop_assert!(
+                    (accounted - quantity).abs() < 1e-9,
+                    "order {} quantity {} accounted {}",
+                    order_id,
+                    quantity,
+                    accounted
+                );
+            }
+        }
     }
 }
@@ -0,0 +1,374 @@
+use crate::engine_api::MatchingEngineApi;
+use crate::id::OrderId;
+use crate::types::*;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use tokio::sync::broadcast;
+
+/// 对 [`MockMatchingEngine`] 的一次调用
+#[derive(Debug, Clone)]
+pub enum MockCall {
+    Submit(Order),
+    Cancel {
+        order_id: OrderId,
+        user_id: String,
+    },
+    Amend {
+        order_id: OrderId,
+        user_id: String,
+        new_quantity: f64,
+    },
+}
+
+/// 内存中的可编排撮合引擎，用于测试嵌入本库的服务
+///
+/// 不做撮合：下单、撤单、改单优先返回通过 `expect_*` 预设的结果，未预设时按最简单的规则处理
+/// （下单直接接受为挂单，撤单和改单作用于已记录的订单）。所有调用按顺序记录，可用 [`calls`](Self::calls) 断言；
+/// 查询返回通过 `insert_order`、`set_depth`、`set_book_ticker`、`push_trade` 设置的数据
+#[derive(Debug)]
+pub struct MockMatchingEngine {
+    state: Mutex<MockState>,
+    trade_sender: broadcast::Sender<Trade>,
+    order_sender: broadcast::Sender<Order>,
+    book_ticker_sender: broadcast::Sender<BookTicker>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    calls: Vec<MockCall>,
+    submit_results: VecDeque<Result<Vec<Trade>, String>>,
+    cancel_results: VecDeque<Result<Order, String>>,
+    amend_results: VecDeque<Result<Order, String>>,
+    orders: HashMap<OrderId, Order>,
+    trades: Vec<Trade>,
+    depths: HashMap<Symbol, OrderBookDepth>,
+    book_tickers: HashMap<Symbol, BookTicker>,
+}
+
+impl MockMatchingEngine {
+    pub fn new() -> Self {
+        let (trade_sender, _) = broadcast::channel(1000);
+        let (order_sender, _) = broadcast::channel(1000);
+        let (book_ticker_sender, _) = broadcast::channel(1000);
+        Self {
+            state: Mutex::new(MockState::default()),
+            trade_sender,
+            order_sender,
+            book_ticker_sender,
+        }
+    }
+
+    /// 预设下一次下单的结果；`Ok` 中的成交按数量计入订单的成交进度
+    pub fn expect_submit(&self, result: Result<Vec<Trade>, String>) {
+        self.state.lock().submit_results.push_back(result);
+    }
+
+    /// 预设下一次撤单的结果
+    pub fn expect_cancel(&self, result: Result<Order, String>) {
+        self.state.lock().cancel_results.push_back(result);
+    }
+
+    /// 预设下一次改单的结果
+    pub fn expect_amend(&self, result: Result<Order, String>) {
+        self.state.lock().amend_results.push_back(result);
+    }
+
+    /// 按调用顺序返回所有下单、撤单、改单调用
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.state.lock().calls.clone()
+    }
+
+    /// 记录订单，供查询、撤单和改单使用
+    pub fn insert_order(&self, order: Order) {
+        self.state.lock().orders.insert(order.id, order);
+    }
+
+    /// 设置交易对的订单簿深度
+    pub fn set_depth(&self, depth: OrderBookDepth) {
+        self.state.lock().depths.insert(depth.symbol, depth);
+    }
+
+    /// 设置交易对的盘口并广播
+    pub fn set_book_ticker(&self, ticker: BookTicker) {
+        self.state
+            .lock()
+            .book_tickers
+            .insert(ticker.symbol, ticker.clone());
+        let _ = self.book_ticker_sender.send(ticker);
+    }
+
+    /// 记录成交并广播
+    pub fn push_trade(&self, trade: Trade) {
+        self.state.lock().trades.push(trade.clone());
+        let _ = self.trade_sender.send(trade);
+    }
+
+    /// 记录订单更新并广播
+    pub fn push_order_update(&self, order: Order) {
+        self.insert_order(order.clone());
+        let _ = self.order_sender.send(order);
+    }
+
+    /// 预设结果为 `Ok` 时按其更新订单记录并广播
+    fn apply_result(&self, result: &Result<Order, String>) {
+        if let Ok(order) = result {
+            self.push_order_update(order.clone());
+        }
+    }
+}
+
+impl Default for MockMatchingEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MatchingEngineApi for MockMatchingEngine {
+    async fn submit_order(&self, mut order: Order) -> Result<Vec<Trade>, String> {
+        let scripted = {
+            let mut state = self.state.lock();
+            state.calls.push(MockCall::Submit(order.clone()));
+            state.submit_results.pop_front()
+        };
+        let trades = scripted.unwrap_or_else(|| Ok(Vec::new()))?;
+
+        let filled: f64 = trades.iter().map(|trade| trade.quantity).sum();
+        order.filled_quantity = filled.min(order.quantity);
+        order.remaining_quantity = order.quantity - order.filled_quantity;
+        order.status = if order.remaining_quantity <= 0.0 {
+            OrderStatus::Filled
+        } else if order.filled_quantity > 0.0 {
+            OrderStatus::PartiallyFilled
+        } else {
+            OrderStatus::New
+        };
+        self.push_order_update(order);
+        for trade in &trades {
+            self.push_trade(trade.clone());
+        }
+        Ok(trades)
+    }
+
+    async fn cancel_order(&self, order_id: OrderId, user_id: String) -> Result<Order, String> {
+        let (scripted, order) = {
+            let mut state = self.state.lock();
+            state.calls.push(MockCall::Cancel {
+                order_id,
+                user_id: user_id.clone(),
+            });
+            (
+                state.cancel_results.pop_front(),
+                state.orders.get(&order_id).cloned(),
+            )
+        };
+        if let Some(result) = scripted {
+            self.apply_result(&result);
+            return result;
+        }
+
+        let mut order = order.ok_or_else(|| "Order not found".to_string())?;
+        if order.user_id != user_id {
+            return Err("Unauthorized to cancel this order".to_string());
+        }
+        if order.status.is_terminal() {
+            return Err(format!("Order already {:?}", order.status));
+        }
+        order.status = OrderStatus::Cancelled;
+        self.push_order_update(order.clone());
+        Ok(order)
+    }
+
+    async fn amend_order(
+        &self,
+        order_id: OrderId,
+        user_id: String,
+        new_quantity: f64,
+    ) -> Result<Order, String> {
+        let (scripted, order) = {
+            let mut state = self.state.lock();
+            state.calls.push(MockCall::Amend {
+                order_id,
+                user_id: user_id.clone(),
+                new_quantity,
+            });
+            (
+                state.amend_results.pop_front(),
+                state.orders.get(&order_id).cloned(),
+            )
+        };
+        if let Some(result) = scripted {
+            self.apply_result(&result);
+            return result;
+        }
+
+        let mut order = order.ok_or_else(|| "Order not found".to_string())?;
+        if order.user_id != user_id {
+            return Err("Unauthorized to amend this order".to_string());
+        }
+        if order.status.is_terminal() {
+            return Err("Only resting limit orders can be amended".to_string());
+        }
+        if new_quantity >= order.quantity || new_quantity <= order.filled_quantity {
+            return Err(
+                "New quantity must be below the order quantity and above the filled quantity"
+                    .to_string(),
+            );
+        }
+        order.quantity = new_quantity;
+        order.remaining_quantity = new_quantity - order.filled_quantity;
+        self.push_order_update(order.clone());
+        Ok(order)
+    }
+
+    fn get_order(&self, order_id: OrderId) -> Option<Order> {
+        self.state.lock().orders.get(&order_id).cloned()
+    }
+
+    fn get_user_orders(&self, user_id: &str) -> Vec<Order> {
+        let mut orders: Vec<Order> = self
+            .state
+            .lock()
+            .orders
+            .values()
+            .filter(|order| order.user_id == user_id)
+            .cloned()
+            .collect();
+        orders.sort_by_key(|order| order.id);
+        orders
+    }
+
+    fn get_orderbook_depth(&self, symbol: &Symbol, depth: Option<usize>) -> Option<OrderBookDepth> {
+        let mut book = self.state.lock().depths.get(symbol).cloned()?;
+        if let Some(depth) = depth {
+            book.bids.truncate(depth);
+            book.asks.truncate(depth);
+        }
+        Some(book)
+    }
+
+    fn get_book_ticker(&self, symbol: &Symbol) -> Option<BookTicker> {
+        self.state.lock().book_tickers.get(symbol).cloned()
+    }
+
+    fn get_trades(&self, symbol: Option<&Symbol>, limit: Option<usize>) -> Vec<Trade> {
+        self.state
+            .lock()
+            .trades
+            .iter()
+            .rev()
+            .filter(|trade| symbol.is_none_or(|symbol| trade.symbol == *symbol))
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    fn subscribe_trades(&self) -> broadcast::Receiver<Trade> {
+        self.trade_sender.subscribe()
+    }
+
+    fn subscribe_orders(&self) -> broadcast::Receiver<Order> {
+        self.order_sender.subscribe()
+    }
+
+    fn subscribe_book_ticker(&self) -> broadcast::Receiver<BookTicker> {
+        self.book_ticker_sender.subscribe()
+    }
+}
+
+/// 构造一笔成交，供预设下单结果使用；对手方订单为同交易对的虚拟挂单
+pub fn mock_trade(order: &Order, quantity: f64, price: f64) -> Trade {
+    let mut counterparty = order.clone();
+    counterparty.id = crate::id::next_order_id();
+    counterparty.side = match order.side {
+        OrderSide::Buy => OrderSide::Sell,
+        OrderSide::Sell => OrderSide::Buy,
+    };
+    counterparty.user_id = "mock_counterparty".to_string();
+    let (buy_order, sell_order) = match order.side {
+        OrderSide::Buy => (order, &counterparty),
+        OrderSide::Sell => (&counterparty, order),
+    };
+    Trade::new(order.symbol, buy_order, sell_order, quantity, price)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matching_engine::MatchingEngine;
+
+    /// 示例路由逻辑：未全部成交时撤销剩余部分，与具体引擎实现无关
+    async fn route<E: MatchingEngineApi>(engine: &E, order: Order) -> Result<f64, String> {
+        let trades = engine.submit_order(order.clone()).await?;
+        let filled: f64 = trades.iter().map(|trade| trade.quantity).sum();
+        if filled < order.quantity {
+            engine.cancel_order(order.id, order.user_id.clone()).await?;
+        }
+        Ok(filled)
+    }
+
+    fn limit_order(quantity: f64) -> Order {
+        Order::new(
+            Symbol::new("BTC", "USDT"),
+            OrderSide::Buy,
+            OrderType::Limit,
+            quantity,
+            Some(100.0),
+            "user1".to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_mock_engine_scripted_results() {
+        let engine = MockMatchingEngine::new();
+        let mut orders = engine.subscribe_orders();
+
+        let order = limit_order(2.0);
+        engine.expect_submit(Ok(vec![mock_trade(&order, 0.5, 100.0)]));
+        assert_eq!(route(&engine, order.clone()).await, Ok(0.5));
+        let calls = engine.calls();
+        assert_eq!(calls.len(), 2);
+        assert!(matches!(&calls[0], MockCall::Submit(submitted) if submitted.id == order.id));
+        assert!(
+            matches!(&calls[1], MockCall::Cancel { order_id, user_id } if *order_id == order.id && user_id == "user1")
+        );
+        assert_eq!(
+            orders.recv().await.unwrap().status,
+            OrderStatus::PartiallyFilled
+        );
+        assert_eq!(orders.recv().await.unwrap().status, OrderStatus::Cancelled);
+        assert_eq!(engine.get_trades(None, None).len(), 1);
+
+        engine.expect_submit(Err("Insufficient balance".to_string()));
+        let rejected = limit_order(1.0);
+        assert_eq!(
+            route(&engine, rejected.clone()).await,
+            Err("Insufficient balance".to_string())
+        );
+        assert!(engine.get_order(rejected.id).is_none());
+
+        let resting = limit_order(3.0);
+        engine.submit_order(resting.clone()).await.unwrap();
+        let amended = engine
+            .amend_order(resting.id, "user1".to_string(), 1.0)
+            .await
+            .unwrap();
+        assert_eq!(amended.remaining_quantity, 1.0);
+        assert!(engine
+            .amend_order(resting.id, "user2".to_string(), 0.5)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_real_engine_implements_api() {
+        let engine = MatchingEngine::new();
+        let order = limit_order(2.0);
+        assert_eq!(route(&engine, order.clone()).await, Ok(0.0));
+        assert_eq!(
+            MatchingEngineApi::get_order(&engine, order.id)
+                .unwrap()
+                .status,
+            OrderStatus::Cancelled
+        );
+    }
+}
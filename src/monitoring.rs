@@ -1,24 +1,26 @@
 use crate::config::MonitoringConfig;
+use crate::matching_engine::MatchingEngine;
 use crate::types::*;
-use axum::{extract::State, http::StatusCode, response::Json, routing::get, Router};
-use metrics::{counter, gauge, histogram, Counter, Gauge, Histogram};
-use metrics_exporter_prometheus::PrometheusBuilder;
+use axum::{extract::State, response::Json, routing::get, Router};
+use metrics::{
+    counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram, Counter,
+    Gauge, Histogram, Unit,
+};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
 use serde_json::json;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
-use tracing::{error, info, warn};
+use tracing::{error, info};
 
 /// 监控状态
 #[derive(Clone)]
 pub struct MonitoringState {
     pub config: MonitoringConfig,
-    pub metrics: Arc<MatchingEngineMetrics>,
+    pub manager: Arc<MonitoringManager>,
 }
 
 /// 撮合引擎指标
-#[derive(Debug)]
 pub struct MatchingEngineMetrics {
     // 订单相关指标
     pub orders_total: Counter,
@@ -38,14 +40,9 @@ pub struct MatchingEngineMetrics {
     pub orderbook_update_duration: Histogram,
 
     // 系统指标
-    pub memory_usage: Gauge,
-    pub cpu_usage: Gauge,
     pub uptime_seconds: Gauge,
 
     // 业务指标
-    pub spread_avg: Gauge,
-    pub spread_max: Gauge,
-    pub spread_min: Gauge,
     pub orderbook_depth: Gauge,
 
     // 错误指标
@@ -56,205 +53,260 @@ pub struct MatchingEngineMetrics {
 }
 
 impl MatchingEngineMetrics {
+    /// 注册指标描述并获取指标句柄，需在安装指标记录器之后调用
     pub fn new() -> Self {
-        Self {
-            orders_total: register_counter!(
-                "matching_engine_orders_total",
-                "Total number of orders"
-            ),
-            orders_filled: register_counter!(
-                "matching_engine_orders_filled_total",
-                "Total number of filled orders"
-            ),
-            orders_cancelled: register_counter!(
-                "matching_engine_orders_cancelled_total",
-                "Total number of cancelled orders"
-            ),
-            orders_rejected: register_counter!(
-                "matching_engine_orders_rejected_total",
-                "Total number of rejected orders"
-            ),
-            active_orders: register_gauge!(
-                "matching_engine_active_orders",
-                "Number of active orders"
-            ),
-
-            trades_total: register_counter!(
-                "matching_engine_trades_total",
-                "Total number of trades"
-            ),
-            trade_volume_total: register_counter!(
-                "matching_engine_trade_volume_total",
-                "Total trade volume"
-            ),
-            trade_volume_24h: register_gauge!(
-                "matching_engine_trade_volume_24h",
-                "24-hour trade volume"
-            ),
+        describe_counter!("matching_engine_orders_total", "Total number of orders");
+        describe_counter!(
+            "matching_engine_orders_filled_total",
+            "Total number of filled orders"
+        );
+        describe_counter!(
+            "matching_engine_orders_cancelled_total",
+            "Total number of cancelled orders"
+        );
+        describe_counter!(
+            "matching_engine_orders_rejected_total",
+            "Total number of rejected orders"
+        );
+        describe_counter!(
+            "matching_engine_orders_by_channel_total",
+            "Orders submitted per entry channel, labelled by result"
+        );
+        describe_counter!(
+            "matching_engine_bus_events_total",
+            "Events published on the engine event bus, labelled by kind"
+        );
+        describe_gauge!("matching_engine_active_orders", "Number of active orders");
+        describe_counter!("matching_engine_trades_total", "Total number of trades");
+        describe_counter!("matching_engine_trade_volume_total", "Total trade volume");
+        describe_gauge!("matching_engine_trade_volume_24h", "24-hour trade volume");
+        describe_histogram!(
+            "matching_engine_order_processing_duration_seconds",
+            Unit::Seconds,
+            "Order processing duration"
+        );
+        describe_histogram!(
+            "matching_engine_trade_execution_duration_seconds",
+            Unit::Seconds,
+            "Trade execution duration"
+        );
+        describe_histogram!(
+            "matching_engine_orderbook_update_duration_seconds",
+            Unit::Seconds,
+            "Orderbook update duration"
+        );
+        describe_gauge!(
+            "matching_engine_uptime_seconds",
+            Unit::Seconds,
+            "Engine uptime in seconds"
+        );
+        describe_gauge!("matching_engine_orderbook_depth", "Orderbook depth");
+        describe_counter!("matching_engine_errors_total", "Total number of errors");
+        describe_gauge!(
+            "matching_engine_websocket_connections",
+            "Number of WebSocket connections"
+        );
+        describe_gauge!(
+            "matching_engine_symbol_orders",
+            "Orders accepted per symbol"
+        );
+        describe_gauge!(
+            "matching_engine_symbol_trades",
+            "Trades executed per symbol"
+        );
+        describe_gauge!(
+            "matching_engine_symbol_volume",
+            "Traded base quantity per symbol"
+        );
+        describe_gauge!(
+            "matching_engine_symbol_quote_volume",
+            "Traded quote amount per symbol"
+        );
+        describe_gauge!(
+            "matching_engine_symbol_open_quantity",
+            "Resting quantity per symbol and side"
+        );
+        describe_gauge!(
+            "matching_engine_symbol_open_orders",
+            "Resting orders per symbol and side"
+        );
+        describe_gauge!(
+            "matching_engine_symbol_best_price",
+            "Best bid/ask price per symbol"
+        );
+        describe_gauge!("matching_engine_symbol_spread", "Bid/ask spread per symbol");
+        describe_gauge!(
+            "matching_engine_symbol_last_price",
+            "Last trade price per symbol"
+        );
+        describe_histogram!(
+            "matching_engine_submit_to_ack_seconds",
+            Unit::Seconds,
+            "Latency from order submission to acknowledgement"
+        );
+        describe_histogram!(
+            "matching_engine_submit_to_first_fill_seconds",
+            Unit::Seconds,
+            "Latency from order submission to its first fill"
+        );
+        describe_counter!(
+            "matching_engine_api_requests_total",
+            "Total number of API requests"
+        );
+        describe_histogram!(
+            "matching_engine_api_request_duration_seconds",
+            Unit::Seconds,
+            "API request duration"
+        );
 
-            order_processing_duration: register_histogram!(
-                "matching_engine_order_processing_duration_seconds",
-                "Order processing duration"
+        Self {
+            orders_total: counter!("matching_engine_orders_total"),
+            orders_filled: counter!("matching_engine_orders_filled_total"),
+            orders_cancelled: counter!("matching_engine_orders_cancelled_total"),
+            orders_rejected: counter!("matching_engine_orders_rejected_total"),
+            active_orders: gauge!("matching_engine_active_orders"),
+
+            trades_total: counter!("matching_engine_trades_total"),
+            trade_volume_total: counter!("matching_engine_trade_volume_total"),
+            trade_volume_24h: gauge!("matching_engine_trade_volume_24h"),
+
+            order_processing_duration: histogram!(
+                "matching_engine_order_processing_duration_seconds"
             ),
-            trade_execution_duration: register_histogram!(
-                "matching_engine_trade_execution_duration_seconds",
-                "Trade execution duration"
+            trade_execution_duration: histogram!(
+                "matching_engine_trade_execution_duration_seconds"
             ),
-            orderbook_update_duration: register_histogram!(
-                "matching_engine_orderbook_update_duration_seconds",
-                "Orderbook update duration"
+            orderbook_update_duration: histogram!(
+                "matching_engine_orderbook_update_duration_seconds"
             ),
 
-            memory_usage: register_gauge!(
-                "matching_engine_memory_usage_bytes",
-                "Memory usage in bytes"
-            ),
-            cpu_usage: register_gauge!("matching_engine_cpu_usage_percent", "CPU usage percentage"),
-            uptime_seconds: register_gauge!(
-                "matching_engine_uptime_seconds",
-                "Engine uptime in seconds"
-            ),
+            uptime_seconds: gauge!("matching_engine_uptime_seconds"),
 
-            spread_avg: register_gauge!("matching_engine_spread_avg", "Average spread"),
-            spread_max: register_gauge!("matching_engine_spread_max", "Maximum spread"),
-            spread_min: register_gauge!("matching_engine_spread_min", "Minimum spread"),
-            orderbook_depth: register_gauge!("matching_engine_orderbook_depth", "Orderbook depth"),
+            orderbook_depth: gauge!("matching_engine_orderbook_depth"),
 
-            errors_total: register_counter!(
-                "matching_engine_errors_total",
-                "Total number of errors"
-            ),
-            websocket_connections: register_gauge!(
-                "matching_engine_websocket_connections",
-                "Number of WebSocket connections"
-            ),
-            api_requests_total: register_counter!(
-                "matching_engine_api_requests_total",
-                "Total number of API requests"
-            ),
-            api_request_duration: register_histogram!(
-                "matching_engine_api_request_duration_seconds",
-                "API request duration"
-            ),
+            errors_total: counter!("matching_engine_errors_total"),
+            websocket_connections: gauge!("matching_engine_websocket_connections"),
+            api_requests_total: counter!("matching_engine_api_requests_total"),
+            api_request_duration: histogram!("matching_engine_api_request_duration_seconds"),
         }
     }
 }
 
+impl Default for MatchingEngineMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// 监控管理器
 pub struct MonitoringManager {
     pub config: MonitoringConfig,
     pub metrics: Arc<MatchingEngineMetrics>,
     pub start_time: Instant,
-    pub stats_cache: Arc<RwLock<HashMap<String, f64>>>,
+    /// Prometheus 指标渲染句柄
+    handle: PrometheusHandle,
 }
 
 impl MonitoringManager {
+    /// 安装全局 Prometheus 指标记录器
+    ///
+    /// `serve_on_api` 为 true 时不单独监听端口，由调用方把 [`MonitoringManager::metrics_router`]
+    /// 挂载到主 HTTP 服务上；否则在 `metrics_port` 上启动独立的导出器（需在 tokio 运行时内调用）。
     pub fn new(config: MonitoringConfig) -> Result<Self, Box<dyn std::error::Error>> {
-        // 初始化 Prometheus 指标导出器
-        let builder = PrometheusBuilder::new();
-        let (recorder, exporter) = builder
-            .with_http_listener(([0, 0, 0, 0], config.metrics_port))
-            .build()?;
-
-        // 设置全局指标记录器
-        metrics::set_global_recorder(recorder)?;
-
-        // 启动指标导出器
-        tokio::spawn(async move {
-            if let Err(e) = exporter.await {
-                error!("Prometheus exporter error: {}", e);
-            }
-        });
-
-        info!(
-            "Monitoring system initialized on port {}",
-            config.metrics_port
-        );
+        let handle = if config.serve_on_api {
+            let handle = PrometheusBuilder::new().install_recorder()?;
+            info!(
+                "Monitoring system initialized, metrics served on the API server at {}",
+                config.metrics_path
+            );
+            handle
+        } else {
+            let (recorder, exporter) = PrometheusBuilder::new()
+                .with_http_listener(([0, 0, 0, 0], config.metrics_port))
+                .build()?;
+            let handle = recorder.handle();
+
+            // 设置全局指标记录器
+            metrics::set_global_recorder(recorder)?;
+
+            // 启动指标导出器
+            tokio::spawn(async move {
+                if let Err(e) = exporter.await {
+                    error!("Prometheus exporter error: {:?}", e);
+                }
+            });
+
+            info!(
+                "Monitoring system initialized on port {}",
+                config.metrics_port
+            );
+            handle
+        };
 
         Ok(Self {
             config,
             metrics: Arc::new(MatchingEngineMetrics::new()),
             start_time: Instant::now(),
-            stats_cache: Arc::new(RwLock::new(HashMap::new())),
+            handle,
         })
     }
 
     /// 记录订单提交
-    pub fn record_order_submitted(&self, order: &Order) {
-        // // counter!(self.metrics.orders_total, 1.0);
-        // // gauge!(self.metrics.active_orders, 1.0);
-
-        // 按交易对记录
-        let labels = [("symbol", order.symbol.to_string())];
-        // counter!(self.metrics.orders_total, 1.0, "symbol" => order.symbol.to_string());
+    pub fn record_order_submitted(&self, _order: &Order) {
+        self.metrics.orders_total.increment(1);
+        self.metrics.active_orders.increment(1.0);
     }
 
     /// 记录订单成交
-    pub fn record_order_filled(&self, order: &Order) {
-        // counter!(self.metrics.orders_filled, 1.0);
-        // gauge!(self.metrics.active_orders, -1.0);
-
-        // counter!(self.metrics.orders_filled, 1.0, "symbol" => order.symbol.to_string());
+    pub fn record_order_filled(&self, _order: &Order) {
+        self.metrics.orders_filled.increment(1);
+        self.metrics.active_orders.decrement(1.0);
     }
 
     /// 记录订单取消
-    pub fn record_order_cancelled(&self, order: &Order) {
-        // counter!(self.metrics.orders_cancelled, 1.0);
-        // gauge!(self.metrics.active_orders, -1.0);
-
-        // counter!(self.metrics.orders_cancelled, 1.0, "symbol" => order.symbol.to_string());
+    pub fn record_order_cancelled(&self, _order: &Order) {
+        self.metrics.orders_cancelled.increment(1);
+        self.metrics.active_orders.decrement(1.0);
     }
 
     /// 记录订单拒绝
-    pub fn record_order_rejected(&self, order: &Order, reason: &str) {
-        // counter!(self.metrics.orders_rejected, 1.0);
-
-        // counter!(self.metrics.orders_rejected, 1.0, "symbol" => order.symbol.to_string(), "reason" => reason.to_string());
+    pub fn record_order_rejected(&self, _order: &Order, _reason: &str) {
+        self.metrics.orders_rejected.increment(1);
     }
 
     /// 记录交易执行
     pub fn record_trade_executed(&self, trade: &Trade) {
-        // counter!(self.metrics.trades_total, 1.0);
-        counter!(
-            self.metrics.trade_volume_total,
-            trade.quantity * trade.price
-        );
-
-        // counter!(self.metrics.trades_total, 1.0, "symbol" => trade.symbol.to_string());
-        counter!(
-            self.metrics.trade_volume_total,
-            trade.quantity * trade.price,
-            "symbol" => trade.symbol.to_string()
-        );
+        self.metrics.trades_total.increment(1);
+        // 计数器只接受整数，成交额按计价货币取整累计
+        self.metrics
+            .trade_volume_total
+            .increment((trade.quantity * trade.price).round() as u64);
     }
 
     /// 记录订单处理时间
     pub fn record_order_processing_time(&self, duration: Duration) {
-        histogram!(
-            self.metrics.order_processing_duration,
-            duration.as_secs_f64()
-        );
+        self.metrics.order_processing_duration.record(duration);
     }
 
     /// 记录交易执行时间
     pub fn record_trade_execution_time(&self, duration: Duration) {
-        histogram!(
-            self.metrics.trade_execution_duration,
-            duration.as_secs_f64()
-        );
+        self.metrics.trade_execution_duration.record(duration);
     }
 
     /// 记录订单簿更新时间
     pub fn record_orderbook_update_time(&self, duration: Duration) {
-        histogram!(
-            self.metrics.orderbook_update_duration,
-            duration.as_secs_f64()
-        );
+        self.metrics.orderbook_update_duration.record(duration);
     }
 
     /// 记录错误
     pub fn record_error(&self, error_type: &str, context: &str) {
-        // counter!(self.metrics.errors_total, 1.0, "error_type" => error_type.to_string(), "context" => context.to_string());
+        self.metrics.errors_total.increment(1);
+        counter!(
+            "matching_engine_errors_by_type_total",
+            "error_type" => error_type.to_string(),
+            "context" => context.to_string()
+        )
+        .increment(1);
     }
 
     /// 记录API请求
@@ -265,131 +317,168 @@ impl MonitoringManager {
         status_code: u16,
         duration: Duration,
     ) {
-        // counter!(self.metrics.api_requests_total, 1.0, "method" => method.to_string(), "path" => path.to_string(), "status" => status_code.to_string());
+        self.metrics.api_requests_total.increment(1);
         histogram!(
-            self.metrics.api_request_duration,
-            duration.as_secs_f64(),
+            "matching_engine_api_request_duration_seconds",
             "method" => method.to_string(),
             "path" => path.to_string(),
             "status" => status_code.to_string()
-        );
+        )
+        .record(duration);
     }
 
     /// 更新WebSocket连接数
     pub fn update_websocket_connections(&self, count: i64) {
-        // gauge!(self.metrics.websocket_connections, count as f64);
+        self.metrics.websocket_connections.set(count as f64);
     }
 
     /// 更新系统指标
-    pub async fn update_system_metrics(&self) {
-        // 更新运行时间
-        let uptime = self.start_time.elapsed().as_secs() as f64;
-        // gauge!(self.metrics.uptime_seconds, uptime);
-
-        // 更新内存使用情况
-        if let Ok(memory_usage) = get_memory_usage() {
-            // gauge!(self.metrics.memory_usage, memory_usage);
-        }
-
-        // 更新CPU使用情况
-        if let Ok(cpu_usage) = get_cpu_usage().await {
-            // gauge!(self.metrics.cpu_usage, cpu_usage);
-        }
+    pub fn update_system_metrics(&self) {
+        self.metrics
+            .uptime_seconds
+            .set(self.start_time.elapsed().as_secs_f64());
     }
 
     /// 更新业务指标
-    pub async fn update_business_metrics(
+    pub fn update_business_metrics(
         &self,
         stats: &EngineStats,
         market_data: &HashMap<Symbol, MarketData>,
     ) {
         // 更新24小时交易量
         let total_volume_24h: f64 = market_data.values().map(|data| data.volume_24h).sum();
-        // gauge!(self.metrics.trade_volume_24h, total_volume_24h);
-
-        // 更新价差指标
-        let spreads: Vec<f64> = market_data
-            .values()
-            .filter_map(|data| {
-                // 这里需要从订单簿获取价差，简化处理
-                Some(0.0) // 实际实现中应该计算真实价差
-            })
-            .collect();
+        self.metrics.trade_volume_24h.set(total_volume_24h);
 
-        if !spreads.is_empty() {
-            let avg_spread = spreads.iter().sum::<f64>() / spreads.len() as f64;
-            let max_spread = spreads.iter().fold(0.0f64, |a, &b| a.max(b));
-            let min_spread = spreads.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+        // 更新订单簿深度
+        self.metrics.orderbook_depth.set(stats.active_orders as f64);
+    }
 
-            // gauge!(self.metrics.spread_avg, avg_spread);
-            // gauge!(self.metrics.spread_max, max_spread);
-            // gauge!(self.metrics.spread_min, min_spread);
+    /// 定期把引擎的交易对统计写入按 `symbol` 标签区分的指标，未启用业务指标时返回 None
+    pub fn start_symbol_metrics(
+        &self,
+        engine: Arc<MatchingEngine>,
+    ) -> Option<tokio::task::JoinHandle<()>> {
+        if !self.config.enable_business_metrics {
+            return None;
         }
+        let interval = Duration::from_secs(self.config.symbol_metrics_interval_seconds.max(1));
+
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                for stats in engine.get_all_symbol_stats() {
+                    record_symbol_stats(&stats);
+                }
+            }
+        }))
+    }
 
-        // 更新订单簿深度
-        // gauge!(self.metrics.orderbook_depth, stats.active_orders as f64);
+    /// 获取 Prometheus 文本格式的指标数据
+    pub fn render(&self) -> String {
+        self.handle.render()
     }
 
-    /// 获取指标数据
-    pub async fn get_metrics(&self) -> String {
-        // 这里应该返回 Prometheus 格式的指标数据
-        // 由于我们使用了 metrics-exporter-prometheus，它会自动处理
-        "".to_string()
+    /// 只包含 `metrics_path` 的路由，用于挂载到主 HTTP 服务
+    pub fn metrics_router(self: &Arc<Self>) -> Router {
+        Router::new()
+            .route(&self.config.metrics_path, get(get_metrics))
+            .with_state(MonitoringState {
+                config: self.config.clone(),
+                manager: self.clone(),
+            })
     }
 }
 
-/// 获取内存使用情况
-fn get_memory_usage() -> Result<f64, Box<dyn std::error::Error>> {
-    // 简化实现，实际应该使用系统API
-    Ok(0.0)
-}
+/// 写入单个交易对的统计指标，盘口价格缺失时对应指标置为 NaN
+pub fn record_symbol_stats(stats: &SymbolStats) {
+    let symbol = stats.symbol.to_string();
+    let set = |name: &'static str, value: f64| gauge!(name, "symbol" => symbol.clone()).set(value);
+    let set_side = |name: &'static str, side: &'static str, value: f64| {
+        gauge!(name, "symbol" => symbol.clone(), "side" => side).set(value)
+    };
 
-/// 获取CPU使用情况
-async fn get_cpu_usage() -> Result<f64, Box<dyn std::error::Error>> {
-    // 简化实现，实际应该使用系统API
-    Ok(0.0)
+    set("matching_engine_symbol_orders", stats.total_orders as f64);
+    set("matching_engine_symbol_trades", stats.total_trades as f64);
+    set("matching_engine_symbol_volume", stats.volume);
+    set("matching_engine_symbol_quote_volume", stats.quote_volume);
+    set_side(
+        "matching_engine_symbol_open_quantity",
+        "buy",
+        stats.open_bid_quantity,
+    );
+    set_side(
+        "matching_engine_symbol_open_quantity",
+        "sell",
+        stats.open_ask_quantity,
+    );
+    set_side(
+        "matching_engine_symbol_open_orders",
+        "buy",
+        stats.open_bid_orders as f64,
+    );
+    set_side(
+        "matching_engine_symbol_open_orders",
+        "sell",
+        stats.open_ask_orders as f64,
+    );
+    set_side(
+        "matching_engine_symbol_best_price",
+        "buy",
+        stats.best_bid.unwrap_or(f64::NAN),
+    );
+    set_side(
+        "matching_engine_symbol_best_price",
+        "sell",
+        stats.best_ask.unwrap_or(f64::NAN),
+    );
+    set(
+        "matching_engine_symbol_spread",
+        stats.spread.unwrap_or(f64::NAN),
+    );
+    set(
+        "matching_engine_symbol_last_price",
+        stats.last_price.unwrap_or(f64::NAN),
+    );
 }
 
 /// 创建监控路由
-pub fn create_monitoring_router(config: MonitoringConfig) -> Router {
+pub fn create_monitoring_router(manager: Arc<MonitoringManager>) -> Router {
     let state = MonitoringState {
-        config: config.clone(),
-        metrics: Arc::new(MatchingEngineMetrics::new()),
+        config: manager.config.clone(),
+        manager,
     };
 
     Router::new()
-        .route("/health", get(health_check))
-        .route("/metrics", get(get_metrics))
+        .route(&state.config.health_path, get(health_check))
+        .route(&state.config.metrics_path, get(get_metrics))
         .route("/stats", get(get_stats))
         .with_state(state)
 }
 
 /// 健康检查
-async fn health_check() -> Result<Json<serde_json::Value>, StatusCode> {
-    Ok(Json(json!({
+async fn health_check() -> Json<serde_json::Value> {
+    Json(json!({
         "status": "healthy",
         "timestamp": chrono::Utc::now(),
         "version": env!("CARGO_PKG_VERSION")
-    })))
+    }))
 }
 
 /// 获取指标
-async fn get_metrics(State(state): State<MonitoringState>) -> Result<String, StatusCode> {
-    // 这里应该返回 Prometheus 格式的指标
-    // 由于我们使用了 metrics-exporter-prometheus，它会自动处理
-    Ok("".to_string())
+async fn get_metrics(State(state): State<MonitoringState>) -> String {
+    state.manager.render()
 }
 
 /// 获取统计信息
-async fn get_stats(
-    State(state): State<MonitoringState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    Ok(Json(json!({
+async fn get_stats(State(state): State<MonitoringState>) -> Json<serde_json::Value> {
+    Json(json!({
         "metrics_enabled": state.config.enabled,
         "metrics_port": state.config.metrics_port,
+        "serve_on_api": state.config.serve_on_api,
         "performance_metrics": state.config.enable_performance_metrics,
         "business_metrics": state.config.enable_business_metrics
-    })))
+    }))
 }
 
 /// 性能计时器
@@ -443,10 +532,16 @@ mod tests {
 
     #[test]
     fn test_metrics_creation() {
-        let metrics = MatchingEngineMetrics::new();
-        // 测试指标是否正确创建
-        assert!(metrics.orders_total.name().contains("orders_total"));
-        assert!(metrics.trades_total.name().contains("trades_total"));
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+
+        let metrics = metrics::with_local_recorder(&recorder, MatchingEngineMetrics::new);
+        metrics.orders_total.increment(3);
+        metrics.trades_total.increment(1);
+
+        let rendered = handle.render();
+        assert!(rendered.contains("matching_engine_orders_total 3"));
+        assert!(rendered.contains("matching_engine_trades_total 1"));
     }
 
     #[test]
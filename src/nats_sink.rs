@@ -0,0 +1,76 @@
+//! NATS 事件投递（`nats` 特性）
+use crate::config::{EventTopicsConfig, NatsSinkConfig};
+use crate::events::{EngineEvent, EventSink, SinkFuture, EVENT_SCHEMA_VERSION};
+use async_nats::jetstream;
+use async_nats::HeaderMap;
+
+/// 发布到 NATS 的事件目标
+///
+/// 主题为 `<事件主题>.<交易对>`，如 `engine.trades.BTCUSDT`，消息体为 JSON。
+/// 启用 JetStream 时等待服务端确认，并以 `Nats-Msg-Id` 头（事件ID）让服务端对重试去重
+pub struct NatsEventSink {
+    client: async_nats::Client,
+    jetstream: Option<jetstream::Context>,
+    topics: EventTopicsConfig,
+}
+
+impl NatsEventSink {
+    pub async fn connect(
+        config: &NatsSinkConfig,
+        topics: EventTopicsConfig,
+    ) -> Result<Self, String> {
+        let client = async_nats::connect(config.url.as_str())
+            .await
+            .map_err(|e| format!("Failed to connect to NATS at {}: {}", config.url, e))?;
+        let jetstream = config.jetstream.then(|| jetstream::new(client.clone()));
+        Ok(Self {
+            client,
+            jetstream,
+            topics,
+        })
+    }
+}
+
+impl EventSink for NatsEventSink {
+    fn name(&self) -> &str {
+        "nats"
+    }
+
+    fn publish<'a>(&'a self, event: &'a EngineEvent) -> SinkFuture<'a> {
+        Box::pin(async move {
+            let payload = event.to_json()?;
+            let subject = format!("{}.{}", self.topics.topic(event.kind()), event.symbol);
+            let mut headers = HeaderMap::new();
+            headers.insert("Nats-Msg-Id", event.event_id.to_string());
+            headers.insert("Schema-Version", EVENT_SCHEMA_VERSION.to_string());
+            headers.insert("Event-Type", event.kind().as_str());
+
+            match &self.jetstream {
+                Some(jetstream) => {
+                    jetstream
+                        .publish_with_headers(subject, headers, payload.into())
+                        .await
+                        .map_err(|e| format!("NATS publish failed: {}", e))?
+                        .await
+                        .map_err(|e| format!("NATS JetStream ack failed: {}", e))?;
+                }
+                None => {
+                    self.client
+                        .publish_with_headers(subject, headers, payload.into())
+                        .await
+                        .map_err(|e| format!("NATS publish failed: {}", e))?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn flush(&self) -> SinkFuture<'_> {
+        Box::pin(async move {
+            self.client
+                .flush()
+                .await
+                .map_err(|e| format!("Failed to flush NATS connection: {}", e))
+        })
+    }
+}
@@ -1,9 +1,16 @@
+use crate::allocation;
+use crate::clock::{SharedClock, SystemClock};
+use crate::event_bus::{EngineEvent, EventBus};
+use crate::id::OrderId;
 use crate::types::*;
-use chrono::Utc;
-use std::collections::{BTreeMap, HashMap};
-use std::sync::{Arc, RwLock};
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::{Mutex, OwnedMutexGuard};
 use tracing::debug;
-use uuid::Uuid;
+use utoipa::ToSchema;
 
 /// 订单簿实现
 /// 使用 BTreeMap 来维护价格优先，时间优先的排序
@@ -15,78 +22,173 @@ pub struct OrderBook {
     // 卖盘：价格从低到高排序
     asks: BTreeMap<i64, Vec<OrderBookEntry>>,
     // 订单ID到价格的映射，用于快速查找和删除
-    order_price_map: HashMap<Uuid, (OrderSide, i64)>,
+    order_price_map: HashMap<OrderId, (OrderSide, i64)>,
     // 时间优先级计数器
     priority_counter: u64,
+    // 当前盘口：(价格键, 该档位挂单总量)，买卖各一
+    best_bid_level: Option<(i64, f64)>,
+    best_ask_level: Option<(i64, f64)>,
+    // 盘口自上次取出后是否发生变化
+    top_changed: bool,
+    // 行情和快照时间戳使用的时钟
+    clock: SharedClock,
+    // 价格与价格键的换算
+    scale: PriceScale,
 }
 
 impl OrderBook {
     pub fn new(symbol: Symbol) -> Self {
+        Self::with_clock(symbol, SystemClock::shared())
+    }
+
+    /// 创建使用指定时钟的订单簿
+    pub fn with_clock(symbol: Symbol, clock: SharedClock) -> Self {
         Self {
             symbol,
             bids: BTreeMap::new(),
             asks: BTreeMap::new(),
             order_price_map: HashMap::new(),
             priority_counter: 0,
+            best_bid_level: None,
+            best_ask_level: None,
+            top_changed: false,
+            clock,
+            scale: PriceScale::default(),
         }
     }
 
+    /// 设置价格换算精度，只能在订单簿为空时调用
+    pub fn with_price_scale(mut self, scale: PriceScale) -> Self {
+        debug_assert!(self.order_price_map.is_empty());
+        self.scale = scale;
+        self
+    }
+
+    /// 价格换算精度
+    pub fn price_scale(&self) -> PriceScale {
+        self.scale
+    }
+
     /// 添加订单到订单簿
     pub fn add_order(&mut self, order: Order) -> Result<(), String> {
         if order.symbol != self.symbol {
             return Err(format!(
                 "Order symbol {} does not match orderbook symbol {}",
-                order.symbol.to_string(),
-                self.symbol.to_string()
+                order.symbol, self.symbol
             ));
         }
 
         if order.remaining_quantity <= 0.0 {
             return Err("Order quantity must be positive".to_string());
         }
+        if let Some(price) = order.price {
+            self.scale.to_key(price)?;
+        }
 
         // 设置时间优先级
         let priority = self.priority_counter;
         self.priority_counter += 1;
 
-        let entry = OrderBookEntry::new(order.clone(), priority);
+        let order_id = order.id;
+        self.insert_entry(OrderBookEntry::new(order, priority));
+        self.refresh_top_of_book();
 
+        debug!(
+            "Added order {} to orderbook for {}",
+            order_id,
+            self.symbol.to_string()
+        );
+        Ok(())
+    }
+
+    /// 将条目追加到对应价格档位的队尾
+    fn insert_entry(&mut self, entry: OrderBookEntry) {
         // 将价格转换为整数以避免浮点数精度问题
-        let price_key = self.price_to_key(order.price.unwrap_or(0.0));
+        let price_key = self.price_to_key(entry.order.price.unwrap_or(0.0));
+        let order_id = entry.order.id;
 
         // 根据订单方向添加到相应的订单簿
-        match order.side {
+        match entry.order.side {
             OrderSide::Buy => {
                 // 买盘：使用负数价格键来实现降序排序
                 let price_key = -price_key;
-                self.bids
-                    .entry(price_key)
-                    .or_insert_with(Vec::new)
-                    .push(entry);
+                self.bids.entry(price_key).or_default().push(entry);
                 self.order_price_map
-                    .insert(order.id, (OrderSide::Buy, price_key));
+                    .insert(order_id, (OrderSide::Buy, price_key));
             }
             OrderSide::Sell => {
                 // 卖盘：使用正数价格键来实现升序排序
-                self.asks
-                    .entry(price_key)
-                    .or_insert_with(Vec::new)
-                    .push(entry);
+                self.asks.entry(price_key).or_default().push(entry);
                 self.order_price_map
-                    .insert(order.id, (OrderSide::Sell, price_key));
+                    .insert(order_id, (OrderSide::Sell, price_key));
             }
         }
+    }
+
+    /// 导出全部挂单及其时间优先级
+    pub fn export(&self) -> OrderBookSnapshot {
+        OrderBookSnapshot {
+            version: ORDERBOOK_SNAPSHOT_VERSION,
+            symbol: self.symbol,
+            exported_at: self.clock.now(),
+            next_priority: self.priority_counter,
+            entries: self
+                .bids
+                .values()
+                .chain(self.asks.values())
+                .flatten()
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// 从快照恢复挂单，保留原有的时间优先级，返回导入的订单数
+    ///
+    /// 只能导入到空订单簿；快照中任一条目不合法时整体拒绝，订单簿保持不变
+    pub fn import(&mut self, snapshot: OrderBookSnapshot) -> Result<usize, String> {
+        if snapshot.version != ORDERBOOK_SNAPSHOT_VERSION {
+            return Err(format!(
+                "Unsupported orderbook snapshot version {}, expected {}",
+                snapshot.version, ORDERBOOK_SNAPSHOT_VERSION
+            ));
+        }
+        if snapshot.symbol != self.symbol {
+            return Err(format!(
+                "Snapshot symbol {} does not match orderbook symbol {}",
+                snapshot.symbol, self.symbol
+            ));
+        }
+        if !self.order_price_map.is_empty() {
+            return Err(format!(
+                "Orderbook for {} is not empty, cannot import snapshot",
+                self.symbol
+            ));
+        }
+        validate_snapshot_entries(&snapshot, self.scale)?;
+
+        let mut entries = snapshot.entries;
+        entries.sort_by_key(|entry| entry.priority);
+        let next_priority = entries
+            .last()
+            .map_or(0, |entry| entry.priority + 1)
+            .max(snapshot.next_priority);
+        let imported = entries.len();
+
+        for entry in entries {
+            self.insert_entry(entry);
+        }
+        self.priority_counter = next_priority;
+        self.refresh_top_of_book();
 
         debug!(
-            "Added order {} to orderbook for {}",
-            order.id,
-            self.symbol.to_string()
+            "Imported {} orders into orderbook for {}",
+            imported, self.symbol
         );
-        Ok(())
+        Ok(imported)
     }
 
     /// 从订单簿中移除订单
-    pub fn remove_order(&mut self, order_id: Uuid) -> Result<Order, String> {
+    pub fn remove_order(&mut self, order_id: OrderId) -> Result<Order, String> {
         let (side, price_key) = self
             .order_price_map
             .remove(&order_id)
@@ -113,6 +215,7 @@ impl OrderBook {
         if entries.is_empty() {
             orderbook.remove(&price_key);
         }
+        self.refresh_top_of_book();
 
         debug!(
             "Removed order {} from orderbook for {}",
@@ -123,7 +226,7 @@ impl OrderBook {
     }
 
     /// 更新订单
-    pub fn update_order(&mut self, order_id: Uuid, new_quantity: f64) -> Result<Order, String> {
+    pub fn update_order(&mut self, order_id: OrderId, new_quantity: f64) -> Result<Order, String> {
         let (side, price_key) = self
             .order_price_map
             .get(&order_id)
@@ -154,13 +257,139 @@ impl OrderBook {
         } else if entry.order.filled_quantity > 0.0 {
             entry.order.status = OrderStatus::PartiallyFilled;
         }
+        let updated = entry.order.clone();
+        self.refresh_top_of_book();
 
         debug!(
             "Updated order {} quantity from {} to {}",
             order_id, old_quantity, new_quantity
         );
 
-        Ok(entry.order.clone())
+        Ok(updated)
+    }
+
+    /// 撤销挂单的一部分成交，保留时间优先级
+    ///
+    /// 已成交数量减少 `quantity`；`restore` 为 true 时加回剩余数量，否则从订单总量中扣除
+    pub fn unwind_fill(
+        &mut self,
+        order_id: OrderId,
+        quantity: f64,
+        restore: bool,
+    ) -> Result<Order, String> {
+        let (side, price_key) = self
+            .order_price_map
+            .get(&order_id)
+            .ok_or_else(|| "Order not found".to_string())?;
+
+        let orderbook = match side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        };
+
+        let entry = orderbook
+            .get_mut(price_key)
+            .and_then(|entries| entries.iter_mut().find(|entry| entry.order.id == order_id))
+            .ok_or_else(|| "Order not found in price level".to_string())?;
+
+        if quantity <= 0.0 {
+            return Err("Unwound quantity must be positive".to_string());
+        }
+
+        let order = &mut entry.order;
+        order.filled_quantity = (order.filled_quantity - quantity).max(0.0);
+        if restore {
+            order.remaining_quantity += quantity;
+        } else {
+            order.quantity = order.filled_quantity + order.remaining_quantity;
+        }
+        order.status = if order.filled_quantity > 0.0 {
+            OrderStatus::PartiallyFilled
+        } else {
+            OrderStatus::New
+        };
+        let unwound = order.clone();
+        self.refresh_top_of_book();
+
+        debug!(
+            "Unwound {} filled quantity of order {}, remaining {}",
+            quantity, order_id, unwound.remaining_quantity
+        );
+        Ok(unwound)
+    }
+
+    /// 以新的订单状态替换挂单，保留时间优先级
+    ///
+    /// 订单的方向和价格必须与挂单一致，用于备用实例按主实例推送的订单更新同步挂单
+    pub fn replace_order(&mut self, order: Order) -> Result<Order, String> {
+        let (side, price_key) = self
+            .order_price_map
+            .get(&order.id)
+            .ok_or_else(|| "Order not found".to_string())?;
+
+        if order.remaining_quantity <= 0.0 {
+            return Err("Order quantity must be positive".to_string());
+        }
+
+        let orderbook = match side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        };
+        let entry = orderbook
+            .get_mut(price_key)
+            .and_then(|entries| entries.iter_mut().find(|entry| entry.order.id == order.id))
+            .ok_or_else(|| "Order not found in price level".to_string())?;
+
+        if entry.order.side != order.side || entry.order.price != order.price {
+            return Err(format!(
+                "Order {} side or price does not match the resting order",
+                order.id
+            ));
+        }
+        let previous = std::mem::replace(&mut entry.order, order);
+        self.refresh_top_of_book();
+        Ok(previous)
+    }
+
+    /// 订单是否挂在订单簿中
+    pub fn contains_order(&self, order_id: OrderId) -> bool {
+        self.order_price_map.contains_key(&order_id)
+    }
+
+    /// 缩减挂单数量（订单总量和剩余量同时减少），保留时间优先级
+    pub fn reduce_order(&mut self, order_id: OrderId, reduce_by: f64) -> Result<Order, String> {
+        let (side, price_key) = self
+            .order_price_map
+            .get(&order_id)
+            .ok_or_else(|| "Order not found".to_string())?;
+
+        let orderbook = match side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        };
+
+        let entry = orderbook
+            .get_mut(price_key)
+            .and_then(|entries| entries.iter_mut().find(|entry| entry.order.id == order_id))
+            .ok_or_else(|| "Order not found in price level".to_string())?;
+
+        if reduce_by <= 0.0 || reduce_by >= entry.order.remaining_quantity {
+            return Err(
+                "Reduction must be positive and less than the remaining quantity".to_string(),
+            );
+        }
+
+        entry.order.quantity -= reduce_by;
+        entry.order.remaining_quantity -= reduce_by;
+        let reduced = entry.order.clone();
+        self.refresh_top_of_book();
+
+        debug!(
+            "Reduced order {} by {} to remaining {}",
+            order_id, reduce_by, reduced.remaining_quantity
+        );
+
+        Ok(reduced)
     }
 
     /// 获取最佳买价
@@ -181,6 +410,92 @@ impl OrderBook {
         }
     }
 
+    /// 获取当前盘口
+    pub fn book_ticker(&self) -> BookTicker {
+        let (bid_price, bid_quantity) = match self.best_bid_level {
+            Some((key, quantity)) => (Some(self.key_to_price(-key)), quantity),
+            None => (None, 0.0),
+        };
+        let (ask_price, ask_quantity) = match self.best_ask_level {
+            Some((key, quantity)) => (Some(self.key_to_price(key)), quantity),
+            None => (None, 0.0),
+        };
+
+        BookTicker {
+            symbol: self.symbol,
+            bid_price,
+            bid_quantity,
+            ask_price,
+            ask_quantity,
+            timestamp: self.clock.now(),
+        }
+    }
+
+    /// 计算中间价、微观价格、价差基点和前 `depth` 档的买卖失衡度
+    pub fn analytics(&self, depth: usize) -> BookAnalytics {
+        let ticker = self.book_ticker();
+        let (mid_price, microprice, spread, spread_bps) = match (ticker.bid_price, ticker.ask_price)
+        {
+            (Some(bid), Some(ask)) => {
+                let mid = (bid + ask) / 2.0;
+                let microprice = (bid * ticker.ask_quantity + ask * ticker.bid_quantity)
+                    / (ticker.bid_quantity + ticker.ask_quantity);
+                let spread = ask - bid;
+                let spread_bps = (mid > 0.0).then(|| spread / mid * 10_000.0);
+                (Some(mid), Some(microprice), Some(spread), spread_bps)
+            }
+            _ => (None, None, None, None),
+        };
+
+        let depth_quantity = |levels: &BTreeMap<i64, Vec<OrderBookEntry>>| {
+            levels
+                .values()
+                .take(depth)
+                .flatten()
+                .fold(0.0, |sum, entry| sum + entry.order.remaining_quantity)
+        };
+        let bid_depth_quantity = depth_quantity(&self.bids);
+        let ask_depth_quantity = depth_quantity(&self.asks);
+        let total = bid_depth_quantity + ask_depth_quantity;
+        let imbalance = (total > 0.0).then(|| (bid_depth_quantity - ask_depth_quantity) / total);
+
+        BookAnalytics {
+            symbol: self.symbol,
+            best_bid: ticker.bid_price,
+            best_ask: ticker.ask_price,
+            mid_price,
+            microprice,
+            spread,
+            spread_bps,
+            depth,
+            bid_depth_quantity,
+            ask_depth_quantity,
+            imbalance,
+            timestamp: ticker.timestamp,
+        }
+    }
+
+    /// 盘口自上次调用后发生过变化时返回最新盘口，否则返回 None
+    pub fn take_book_ticker(&mut self) -> Option<BookTicker> {
+        if !self.top_changed {
+            return None;
+        }
+        self.top_changed = false;
+        Some(self.book_ticker())
+    }
+
+    /// 订单簿变动后重新计算买一、卖一档位，价格或数量变化时标记盘口已变化
+    fn refresh_top_of_book(&mut self) {
+        let best_bid_level = top_level(&self.bids);
+        let best_ask_level = top_level(&self.asks);
+
+        if best_bid_level != self.best_bid_level || best_ask_level != self.best_ask_level {
+            self.best_bid_level = best_bid_level;
+            self.best_ask_level = best_ask_level;
+            self.top_changed = true;
+        }
+    }
+
     /// 获取订单簿深度
     pub fn get_depth(&self, max_depth: Option<usize>) -> OrderBookDepth {
         let depth = max_depth.unwrap_or(10);
@@ -209,10 +524,10 @@ impl OrderBook {
         }
 
         OrderBookDepth {
-            symbol: self.symbol.clone(),
+            symbol: self.symbol,
             bids,
             asks,
-            timestamp: Utc::now(),
+            timestamp: self.clock.now(),
         }
     }
 
@@ -274,25 +589,173 @@ impl OrderBook {
         matching_orders
     }
 
+    /// 计算集合竞价撮合价（最大成交量原则）
+    ///
+    /// 以所有挂单价位作为候选价，选出可成交量最大的价格；成交量相同时取未成交余量最小的，
+    /// 仍相同时取较低价格。返回 (撮合价, 成交量, 余量)，买卖盘不交叉时返回 None
+    pub fn auction_uncross(&self) -> Option<(f64, f64, f64)> {
+        let candidates: BTreeSet<i64> = self
+            .bids
+            .keys()
+            .map(|&key| -key)
+            .chain(self.asks.keys().copied())
+            .collect();
+
+        let mut best: Option<(i64, f64, f64)> = None;
+        for price_key in candidates {
+            let demand: f64 = self
+                .bids
+                .range(..=-price_key)
+                .flat_map(|(_, entries)| entries.iter())
+                .map(|e| e.order.remaining_quantity)
+                .sum();
+            let supply: f64 = self
+                .asks
+                .range(..=price_key)
+                .flat_map(|(_, entries)| entries.iter())
+                .map(|e| e.order.remaining_quantity)
+                .sum();
+
+            let volume = demand.min(supply);
+            if volume <= 0.0 {
+                continue;
+            }
+
+            let imbalance = demand - supply;
+            let is_better = match best {
+                None => true,
+                Some((_, best_volume, best_imbalance)) => {
+                    volume > best_volume
+                        || (volume == best_volume && imbalance.abs() < best_imbalance.abs())
+                }
+            };
+            if is_better {
+                best = Some((price_key, volume, imbalance));
+            }
+        }
+
+        best.map(|(price_key, volume, imbalance)| (self.key_to_price(price_key), volume, imbalance))
+    }
+
+    /// 不修改订单簿，模拟订单按价格优先吃单，返回每个价格档位的预计成交（挂单价格和数量）
+    ///
+    /// 与撮合时一样跳过不可成交的挂单，遵守最小成交数量、按金额下单和市价单滑点上限；
+    /// 档位内的分配算法不影响吃单方的成交，不模拟熔断
+    pub fn simulate_match(&self, order: &Order) -> Vec<SimulatedFill> {
+        let levels: Vec<(f64, &Vec<OrderBookEntry>)> = match order.side {
+            OrderSide::Buy => {
+                let max_key = order
+                    .price
+                    .map_or(i64::MAX, |price| self.price_to_key(price));
+                self.asks
+                    .range(..=max_key)
+                    .map(|(&key, entries)| (self.key_to_price(key), entries))
+                    .collect()
+            }
+            OrderSide::Sell => {
+                let min_key = order
+                    .price
+                    .map_or(i64::MIN, |price| self.price_to_key(price));
+                self.bids
+                    .range(..=min_key.saturating_neg())
+                    .map(|(&key, entries)| (self.key_to_price(-key), entries))
+                    .collect()
+            }
+        };
+        let available = |entries: &Vec<OrderBookEntry>| -> f64 {
+            entries
+                .iter()
+                .filter(|entry| order.can_match(&entry.order))
+                .map(|entry| entry.order.remaining_quantity)
+                .sum()
+        };
+
+        // 对手盘可成交总量不足最小成交数量时不撮合
+        let min_fill = order.min_fill_threshold();
+        if min_fill > 0.0
+            && levels
+                .iter()
+                .map(|(_, entries)| available(entries))
+                .sum::<f64>()
+                < min_fill
+        {
+            return Vec::new();
+        }
+
+        let slippage_limit = order.max_slippage_bps.and_then(|bps| {
+            let (best_price, _) = levels.first()?;
+            Some(match order.side {
+                OrderSide::Buy => best_price * (1.0 + bps / 10_000.0),
+                OrderSide::Sell => best_price * (1.0 - bps / 10_000.0),
+            })
+        });
+
+        let mut fills = Vec::new();
+        let mut remaining_quantity = order.remaining_quantity;
+        let mut remaining_quote = order.quote_quantity;
+        for (price, entries) in levels {
+            let beyond_limit = slippage_limit.is_some_and(|limit| match order.side {
+                OrderSide::Buy => price > limit,
+                OrderSide::Sell => price < limit,
+            });
+            if beyond_limit {
+                break;
+            }
+            if let Some(quote) = remaining_quote {
+                remaining_quantity = allocation::floor_quantity(quote / price);
+            }
+            if remaining_quantity <= 0.0 {
+                break;
+            }
+
+            let quantity = available(entries).min(remaining_quantity);
+            if quantity <= 0.0 {
+                continue;
+            }
+            remaining_quantity -= quantity;
+            if let Some(quote) = remaining_quote.as_mut() {
+                *quote -= quantity * price;
+            }
+            fills.push(SimulatedFill { price, quantity });
+        }
+        fills
+    }
+
+    /// 获取能以指定价格成交的某一方向挂单（价格优先，时间优先）
+    pub fn get_crossing_orders(&self, side: OrderSide, price: f64) -> Vec<OrderBookEntry> {
+        let price_key = self.price_to_key(price);
+        let levels: Vec<&Vec<OrderBookEntry>> = match side {
+            OrderSide::Buy => self.bids.range(..=-price_key).map(|(_, v)| v).collect(),
+            OrderSide::Sell => self.asks.range(..=price_key).map(|(_, v)| v).collect(),
+        };
+
+        let mut crossing_orders = Vec::new();
+        for entries in levels {
+            let mut sorted_entries = entries.clone();
+            sorted_entries.sort_by_key(|e| e.priority);
+            crossing_orders.extend(sorted_entries);
+        }
+        crossing_orders
+    }
+
     /// 获取订单簿统计信息
     pub fn get_stats(&self) -> OrderBookStats {
+        // 用 fold 从 0.0 开始累加：空迭代器的 f64 sum 结果为 -0.0
         let total_bid_orders: usize = self.bids.values().map(|v| v.len()).sum();
         let total_ask_orders: usize = self.asks.values().map(|v| v.len()).sum();
         let total_bid_quantity: f64 = self
             .bids
             .values()
             .flat_map(|v| v.iter())
-            .map(|e| e.order.remaining_quantity)
-            .sum();
+            .fold(0.0, |total, e| total + e.order.remaining_quantity);
         let total_ask_quantity: f64 = self
             .asks
             .values()
             .flat_map(|v| v.iter())
-            .map(|e| e.order.remaining_quantity)
-            .sum();
+            .fold(0.0, |total, e| total + e.order.remaining_quantity);
 
         OrderBookStats {
-            symbol: self.symbol.clone(),
+            symbol: self.symbol,
             bid_levels: self.bids.len(),
             ask_levels: self.asks.len(),
             total_bid_orders,
@@ -302,15 +765,189 @@ impl OrderBook {
         }
     }
 
+    /// 检查订单簿内部一致性，返回第一处违反的描述
+    ///
+    /// 除结构检查外要求买一价低于卖一价；集合竞价期间订单簿允许交叉，应改用 [`Self::verify_structure`]
+    pub fn verify_invariants(&self) -> Result<(), String> {
+        self.verify_structure()?;
+
+        if let (Some(bid), Some(ask)) = (self.best_bid(), self.best_ask()) {
+            if bid >= ask {
+                return Err(format!(
+                    "Orderbook for {} is crossed: best bid {} >= best ask {}",
+                    self.symbol, bid, ask
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// 检查订单簿结构：档位非空且条目与档位的方向、价格一致，同档位按时间优先级排列，
+    /// 订单索引与档位中的条目一一对应，缓存的盘口与档位数量一致
+    pub fn verify_structure(&self) -> Result<(), String> {
+        let mut entry_count = 0;
+
+        for (side, levels) in [(OrderSide::Buy, &self.bids), (OrderSide::Sell, &self.asks)] {
+            for (&price_key, entries) in levels {
+                if entries.is_empty() {
+                    return Err(format!("Empty {:?} level at key {}", side, price_key));
+                }
+
+                let mut last_priority = None;
+                for entry in entries {
+                    let order = &entry.order;
+                    let expected_key = match side {
+                        OrderSide::Buy => -self.price_to_key(order.price.unwrap_or(0.0)),
+                        OrderSide::Sell => self.price_to_key(order.price.unwrap_or(0.0)),
+                    };
+                    if order.symbol != self.symbol {
+                        return Err(format!(
+                            "Order {} symbol {} does not match orderbook symbol {}",
+                            order.id, order.symbol, self.symbol
+                        ));
+                    }
+                    if order.side != side || expected_key != price_key {
+                        return Err(format!(
+                            "Order {} is on the wrong level: {:?} key {}",
+                            order.id, side, price_key
+                        ));
+                    }
+                    if !order.remaining_quantity.is_finite()
+                        || !order.price.is_some_and(f64::is_finite)
+                    {
+                        return Err(format!(
+                            "Order {} has non-finite price {:?} or remaining {}",
+                            order.id, order.price, order.remaining_quantity
+                        ));
+                    }
+                    if order.remaining_quantity <= 0.0 || order.status.is_terminal() {
+                        return Err(format!(
+                            "Order {} is resting with remaining {} and status {:?}",
+                            order.id, order.remaining_quantity, order.status
+                        ));
+                    }
+                    if last_priority.is_some_and(|last| last >= entry.priority)
+                        || entry.priority >= self.priority_counter
+                    {
+                        return Err(format!(
+                            "Order {} has out-of-order priority {}",
+                            order.id, entry.priority
+                        ));
+                    }
+                    last_priority = Some(entry.priority);
+
+                    if self.order_price_map.get(&order.id) != Some(&(side, price_key)) {
+                        return Err(format!(
+                            "Order {} is missing from the order index",
+                            order.id
+                        ));
+                    }
+                    entry_count += 1;
+                }
+            }
+        }
+
+        if entry_count != self.order_price_map.len() {
+            return Err(format!(
+                "Order index has {} orders but levels hold {}",
+                self.order_price_map.len(),
+                entry_count
+            ));
+        }
+
+        if top_level(&self.bids) != self.best_bid_level
+            || top_level(&self.asks) != self.best_ask_level
+        {
+            return Err(format!(
+                "Cached top of book for {} is stale: bid {:?} ask {:?}",
+                self.symbol, self.best_bid_level, self.best_ask_level
+            ));
+        }
+
+        Ok(())
+    }
+
     /// 将价格转换为整数键（避免浮点数精度问题）
     fn price_to_key(&self, price: f64) -> i64 {
-        (price * 1_000_000.0) as i64 // 保留6位小数精度
+        self.scale.to_key_lossy(price)
     }
 
     /// 将整数键转换回价格
     fn key_to_price(&self, key: i64) -> f64 {
-        key as f64 / 1_000_000.0
+        self.scale.to_price(key)
+    }
+}
+
+/// 第一个档位的价格键和挂单总量
+fn top_level(levels: &BTreeMap<i64, Vec<OrderBookEntry>>) -> Option<(i64, f64)> {
+    levels.iter().next().map(|(&key, entries)| {
+        let quantity = entries
+            .iter()
+            .fold(0.0, |sum, entry| sum + entry.order.remaining_quantity);
+        (key, quantity)
+    })
+}
+
+/// 检查快照条目：订单属于快照交易对、是未完成的限价挂单，订单ID和时间优先级不重复
+fn validate_snapshot_entries(
+    snapshot: &OrderBookSnapshot,
+    scale: PriceScale,
+) -> Result<(), String> {
+    let mut order_ids = HashSet::new();
+    let mut priorities = HashSet::new();
+
+    for entry in &snapshot.entries {
+        let order = &entry.order;
+        if order.symbol != snapshot.symbol {
+            return Err(format!(
+                "Order {} symbol {} does not match snapshot symbol {}",
+                order.id, order.symbol, snapshot.symbol
+            ));
+        }
+        if order.order_type.is_trigger() || order.order_type == OrderType::Market {
+            return Err(format!("Order {} is not a resting limit order", order.id));
+        }
+        if order.status.is_terminal() {
+            return Err(format!("Order {} is already {:?}", order.id, order.status));
+        }
+        match order.price {
+            Some(price) if price > 0.0 => {
+                scale
+                    .to_key(price)
+                    .map_err(|e| format!("Order {}: {}", order.id, e))?;
+            }
+            _ => return Err(format!("Order {} must have a positive price", order.id)),
+        }
+        if order.remaining_quantity <= 0.0 {
+            return Err(format!(
+                "Order {} must have a positive remaining quantity",
+                order.id
+            ));
+        }
+        if !order_ids.insert(order.id) {
+            return Err(format!("Duplicate order {} in snapshot", order.id));
+        }
+        if !priorities.insert(entry.priority) {
+            return Err(format!("Duplicate priority {} in snapshot", entry.priority));
+        }
     }
+
+    Ok(())
+}
+
+/// 订单簿快照格式版本，格式不兼容地变化时递增
+pub const ORDERBOOK_SNAPSHOT_VERSION: u32 = 1;
+
+/// 订单簿快照：全部挂单及其时间优先级，用于在实例间迁移订单簿或为测试环境准备数据
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OrderBookSnapshot {
+    pub version: u32,
+    pub symbol: Symbol,
+    pub exported_at: DateTime<Utc>,
+    /// 导出时的下一个时间优先级
+    pub next_priority: u64,
+    /// 全部挂单，买盘按价格从高到低、卖盘从低到高，同价位按时间优先级
+    pub entries: Vec<OrderBookEntry>,
 }
 
 /// 订单簿统计信息
@@ -329,55 +966,142 @@ pub struct OrderBookStats {
 #[derive(Debug, Clone)]
 pub struct SafeOrderBook {
     inner: Arc<RwLock<OrderBook>>,
+    /// 盘口变化发布到的事件总线，未设置时不推送
+    event_bus: Option<Arc<EventBus>>,
+    /// 撮合锁，同一订单簿的撮合、撤单和改单按此串行执行
+    matching: Arc<Mutex<()>>,
 }
 
 impl SafeOrderBook {
     pub fn new(symbol: Symbol) -> Self {
         Self {
             inner: Arc::new(RwLock::new(OrderBook::new(symbol))),
+            event_bus: None,
+            matching: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// 包装订单簿，每次变动导致盘口变化时向 `event_bus` 发布最新盘口
+    pub fn with_event_bus(orderbook: OrderBook, event_bus: Arc<EventBus>) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(orderbook)),
+            event_bus: Some(event_bus),
+            matching: Arc::new(Mutex::new(())),
         }
     }
 
+    /// 获取撮合锁
+    ///
+    /// 撮合先读取对手盘挂单的快照，再逐笔修改订单簿；持锁期间其它撮合、撤单和改单等待，
+    /// 快照中的挂单不会被并发修改。守卫可以跨越 `.await` 持有
+    pub async fn lock_matching(&self) -> OwnedMutexGuard<()> {
+        Arc::clone(&self.matching).lock_owned().await
+    }
+
     pub fn add_order(&self, order: Order) -> Result<(), String> {
-        self.inner.write().unwrap().add_order(order)
+        self.mutate(|book| book.add_order(order))
+    }
+
+    pub fn remove_order(&self, order_id: OrderId) -> Result<Order, String> {
+        self.mutate(|book| book.remove_order(order_id))
+    }
+
+    pub fn update_order(&self, order_id: OrderId, new_quantity: f64) -> Result<Order, String> {
+        self.mutate(|book| book.update_order(order_id, new_quantity))
+    }
+
+    pub fn reduce_order(&self, order_id: OrderId, reduce_by: f64) -> Result<Order, String> {
+        self.mutate(|book| book.reduce_order(order_id, reduce_by))
+    }
+
+    pub fn unwind_fill(
+        &self,
+        order_id: OrderId,
+        quantity: f64,
+        restore: bool,
+    ) -> Result<Order, String> {
+        self.mutate(|book| book.unwind_fill(order_id, quantity, restore))
+    }
+
+    pub fn replace_order(&self, order: Order) -> Result<Order, String> {
+        self.mutate(|book| book.replace_order(order))
     }
 
-    pub fn remove_order(&self, order_id: Uuid) -> Result<Order, String> {
-        self.inner.write().unwrap().remove_order(order_id)
+    pub fn contains_order(&self, order_id: OrderId) -> bool {
+        self.inner.read().contains_order(order_id)
     }
 
-    pub fn update_order(&self, order_id: Uuid, new_quantity: f64) -> Result<Order, String> {
-        self.inner
-            .write()
-            .unwrap()
-            .update_order(order_id, new_quantity)
+    pub fn book_ticker(&self) -> BookTicker {
+        self.inner.read().book_ticker()
+    }
+
+    pub fn analytics(&self, depth: usize) -> BookAnalytics {
+        self.inner.read().analytics(depth)
+    }
+
+    pub fn export(&self) -> OrderBookSnapshot {
+        self.inner.read().export()
+    }
+
+    pub fn import(&self, snapshot: OrderBookSnapshot) -> Result<usize, String> {
+        self.mutate(|book| book.import(snapshot))
+    }
+
+    /// 在写锁内修改订单簿，盘口变化时在释放锁之前广播，保证推送顺序与变动顺序一致
+    fn mutate<R>(&self, f: impl FnOnce(&mut OrderBook) -> R) -> R {
+        let mut book = self.inner.write();
+        let result = f(&mut book);
+        if let Some(ticker) = book.take_book_ticker() {
+            if let Some(event_bus) = &self.event_bus {
+                let timestamp = ticker.timestamp;
+                event_bus.publish(EngineEvent::BookTicker(ticker), timestamp);
+            }
+        }
+        result
     }
 
     pub fn best_bid(&self) -> Option<f64> {
-        self.inner.read().unwrap().best_bid()
+        self.inner.read().best_bid()
     }
 
     pub fn best_ask(&self) -> Option<f64> {
-        self.inner.read().unwrap().best_ask()
+        self.inner.read().best_ask()
     }
 
     pub fn spread(&self) -> Option<f64> {
-        self.inner.read().unwrap().spread()
+        self.inner.read().spread()
     }
 
     pub fn get_depth(&self, max_depth: Option<usize>) -> OrderBookDepth {
-        self.inner.read().unwrap().get_depth(max_depth)
+        self.inner.read().get_depth(max_depth)
     }
 
     pub fn get_matching_orders(&self, incoming_order: &Order) -> Vec<OrderBookEntry> {
-        self.inner
-            .read()
-            .unwrap()
-            .get_matching_orders(incoming_order)
+        self.inner.read().get_matching_orders(incoming_order)
+    }
+
+    pub fn auction_uncross(&self) -> Option<(f64, f64, f64)> {
+        self.inner.read().auction_uncross()
+    }
+
+    pub fn simulate_match(&self, order: &Order) -> Vec<SimulatedFill> {
+        self.inner.read().simulate_match(order)
+    }
+
+    pub fn get_crossing_orders(&self, side: OrderSide, price: f64) -> Vec<OrderBookEntry> {
+        self.inner.read().get_crossing_orders(side, price)
     }
 
     pub fn get_stats(&self) -> OrderBookStats {
-        self.inner.read().unwrap().get_stats()
+        self.inner.read().get_stats()
+    }
+
+    pub fn verify_invariants(&self) -> Result<(), String> {
+        self.inner.read().verify_invariants()
+    }
+
+    pub fn verify_structure(&self) -> Result<(), String> {
+        self.inner.read().verify_structure()
     }
 }
 
@@ -385,14 +1109,74 @@ impl SafeOrderBook {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_simulate_match_is_read_only() {
+        let symbol = Symbol::new("BTC", "USDT");
+        let mut orderbook = OrderBook::new(symbol);
+        for (quantity, price) in [(1.0, 100.0), (2.0, 100.0), (1.0, 110.0), (5.0, 200.0)] {
+            let order = Order::new(
+                symbol,
+                OrderSide::Sell,
+                OrderType::Limit,
+                quantity,
+                Some(price),
+                "maker".to_string(),
+            );
+            orderbook.add_order(order).unwrap();
+        }
+        let stats_before = orderbook.get_stats();
+
+        let market = |quantity| {
+            Order::new(
+                symbol,
+                OrderSide::Buy,
+                OrderType::Market,
+                quantity,
+                None,
+                "taker".to_string(),
+            )
+        };
+        let fills = orderbook.simulate_match(&market(3.5));
+        assert_eq!(
+            fills,
+            vec![
+                SimulatedFill {
+                    price: 100.0,
+                    quantity: 3.0
+                },
+                SimulatedFill {
+                    price: 110.0,
+                    quantity: 0.5
+                },
+            ]
+        );
+
+        // 滑点上限 20% 时不吃 200 的档位
+        let fills = orderbook.simulate_match(&market(10.0).with_max_slippage_bps(2_000.0));
+        assert_eq!(fills.iter().map(|fill| fill.quantity).sum::<f64>(), 4.0);
+
+        // 按金额下单：310 USDT 在 100 买入 3 个，剩余 10 USDT 在 110 买入约 0.0909 个
+        let fills = orderbook.simulate_match(&market(1.0).with_quote_quantity(310.0));
+        assert_eq!(fills.len(), 2);
+        assert_eq!(fills[0].quantity, 3.0);
+        assert!((fills[1].quantity - 0.09090909).abs() < 1e-6);
+
+        let stats_after = orderbook.get_stats();
+        assert_eq!(stats_before.total_ask_orders, stats_after.total_ask_orders);
+        assert_eq!(
+            stats_before.total_ask_quantity,
+            stats_after.total_ask_quantity
+        );
+    }
+
     #[test]
     fn test_orderbook_basic_operations() {
         let symbol = Symbol::new("BTC", "USDT");
-        let mut orderbook = OrderBook::new(symbol.clone());
+        let mut orderbook = OrderBook::new(symbol);
 
         // 添加买单
         let buy_order = Order::new(
-            symbol.clone(),
+            symbol,
             OrderSide::Buy,
             OrderType::Limit,
             1.0,
@@ -406,7 +1190,7 @@ mod tests {
 
         // 添加卖单
         let sell_order = Order::new(
-            symbol.clone(),
+            symbol,
             OrderSide::Sell,
             OrderType::Limit,
             1.0,
@@ -418,8 +1202,19 @@ mod tests {
         assert_eq!(orderbook.best_ask(), Some(51000.0));
         assert_eq!(orderbook.spread(), Some(1000.0));
 
+        // 未穿价的买单不应匹配到卖单
+        assert!(orderbook.get_matching_orders(&buy_order).is_empty());
+
         // 测试匹配
-        let matching_orders = orderbook.get_matching_orders(&buy_order);
+        let crossing_buy = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(51000.0),
+            "user3".to_string(),
+        );
+        let matching_orders = orderbook.get_matching_orders(&crossing_buy);
         assert_eq!(matching_orders.len(), 1);
         assert_eq!(matching_orders[0].order.id, sell_order.id);
     }
@@ -427,11 +1222,11 @@ mod tests {
     #[test]
     fn test_price_priority() {
         let symbol = Symbol::new("BTC", "USDT");
-        let mut orderbook = OrderBook::new(symbol.clone());
+        let mut orderbook = OrderBook::new(symbol);
 
         // 添加多个不同价格的买单
         let order1 = Order::new(
-            symbol.clone(),
+            symbol,
             OrderSide::Buy,
             OrderType::Limit,
             1.0,
@@ -439,7 +1234,7 @@ mod tests {
             "user1".to_string(),
         );
         let order2 = Order::new(
-            symbol.clone(),
+            symbol,
             OrderSide::Buy,
             OrderType::Limit,
             1.0,
@@ -447,7 +1242,7 @@ mod tests {
             "user2".to_string(),
         );
         let order3 = Order::new(
-            symbol.clone(),
+            symbol,
             OrderSide::Buy,
             OrderType::Limit,
             1.0,
@@ -462,4 +1257,161 @@ mod tests {
         // 最佳买价应该是51000（最高价格）
         assert_eq!(orderbook.best_bid(), Some(51000.0));
     }
+
+    #[test]
+    fn test_auction_uncross_maximises_volume() {
+        let symbol = Symbol::new("BTC", "USDT");
+        let mut orderbook = OrderBook::new(symbol);
+
+        // 集合竞价阶段买卖盘可以交叉
+        for (side, quantity, price) in [
+            (OrderSide::Buy, 2.0, 102.0),
+            (OrderSide::Buy, 1.0, 100.0),
+            (OrderSide::Sell, 1.0, 99.0),
+            (OrderSide::Sell, 1.0, 101.0),
+            (OrderSide::Sell, 3.0, 103.0),
+        ] {
+            let order = Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                quantity,
+                Some(price),
+                "user".to_string(),
+            );
+            orderbook.add_order(order).unwrap();
+        }
+
+        // 101 处买方 2、卖方 2，成交量最大且无余量
+        assert_eq!(orderbook.auction_uncross(), Some((101.0, 2.0, 0.0)));
+        assert_eq!(
+            orderbook.get_crossing_orders(OrderSide::Buy, 101.0).len(),
+            1
+        );
+        assert_eq!(
+            orderbook.get_crossing_orders(OrderSide::Sell, 101.0).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_book_ticker_tracks_top_of_book_changes() {
+        let symbol = Symbol::new("BTC", "USDT");
+        let mut orderbook = OrderBook::new(symbol);
+        let order = |side, quantity, price| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                quantity,
+                Some(price),
+                "user".to_string(),
+            )
+        };
+
+        let best = order(OrderSide::Buy, 1.0, 100.0);
+        let best_id = best.id;
+        orderbook.add_order(best).unwrap();
+        let ticker = orderbook.take_book_ticker().unwrap();
+        assert_eq!(ticker.bid_price, Some(100.0));
+        assert_eq!(ticker.bid_quantity, 1.0);
+        assert_eq!(ticker.ask_price, None);
+        assert!(orderbook.take_book_ticker().is_none());
+
+        // 买一之外的档位变化不影响盘口
+        orderbook
+            .add_order(order(OrderSide::Buy, 2.0, 99.0))
+            .unwrap();
+        assert!(orderbook.take_book_ticker().is_none());
+
+        // 同价位追加挂单改变买一数量
+        orderbook
+            .add_order(order(OrderSide::Buy, 0.5, 100.0))
+            .unwrap();
+        assert_eq!(orderbook.take_book_ticker().unwrap().bid_quantity, 1.5);
+
+        orderbook
+            .add_order(order(OrderSide::Sell, 3.0, 101.0))
+            .unwrap();
+        let ticker = orderbook.take_book_ticker().unwrap();
+        assert_eq!(ticker.ask_price, Some(101.0));
+        assert_eq!(ticker.ask_quantity, 3.0);
+
+        orderbook.update_order(best_id, 0.25).unwrap();
+        assert_eq!(orderbook.take_book_ticker().unwrap().bid_quantity, 0.75);
+
+        orderbook.remove_order(best_id).unwrap();
+        assert_eq!(orderbook.take_book_ticker().unwrap().bid_quantity, 0.5);
+    }
+
+    #[test]
+    fn test_book_analytics() {
+        let symbol = Symbol::new("BTC", "USDT");
+        let mut orderbook = OrderBook::new(symbol);
+        let analytics = orderbook.analytics(5);
+        assert_eq!(analytics.mid_price, None);
+        assert_eq!(analytics.imbalance, None);
+
+        for (side, quantity, price) in [
+            (OrderSide::Buy, 3.0, 99.0),
+            (OrderSide::Buy, 2.0, 98.0),
+            (OrderSide::Sell, 1.0, 101.0),
+            (OrderSide::Sell, 4.0, 102.0),
+        ] {
+            let order = Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                quantity,
+                Some(price),
+                "user".to_string(),
+            );
+            orderbook.add_order(order).unwrap();
+        }
+
+        let analytics = orderbook.analytics(1);
+        assert_eq!(analytics.mid_price, Some(100.0));
+        // 买一量更大，微观价格偏向卖一: (99 * 1 + 101 * 3) / 4
+        assert_eq!(analytics.microprice, Some(100.5));
+        assert_eq!(analytics.spread, Some(2.0));
+        assert_eq!(analytics.spread_bps, Some(200.0));
+        assert_eq!(analytics.imbalance, Some(0.5));
+
+        let analytics = orderbook.analytics(5);
+        assert_eq!(analytics.bid_depth_quantity, 5.0);
+        assert_eq!(analytics.ask_depth_quantity, 5.0);
+        assert_eq!(analytics.imbalance, Some(0.0));
+    }
+
+    #[test]
+    fn test_verify_invariants() {
+        let symbol = Symbol::new("BTC", "USDT");
+        let mut orderbook = OrderBook::new(symbol);
+        let order = |side, price| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                1.0,
+                Some(price),
+                "user".to_string(),
+            )
+        };
+
+        orderbook.add_order(order(OrderSide::Buy, 99.0)).unwrap();
+        orderbook.add_order(order(OrderSide::Sell, 101.0)).unwrap();
+        assert_eq!(orderbook.verify_invariants(), Ok(()));
+
+        // 订单簿本身不撮合，直接挂入交叉的买单只破坏不交叉约束
+        orderbook.add_order(order(OrderSide::Buy, 102.0)).unwrap();
+        assert_eq!(orderbook.verify_structure(), Ok(()));
+        assert!(orderbook
+            .verify_invariants()
+            .unwrap_err()
+            .contains("crossed"));
+
+        // 索引与档位不一致
+        orderbook.order_price_map.insert(0, (OrderSide::Sell, 1));
+        assert!(orderbook.verify_structure().is_err());
+    }
 }
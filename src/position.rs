@@ -0,0 +1,368 @@
+use crate::types::*;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 视为零持仓的数量容差，避免浮点误差残留的极小持仓
+pub const QUANTITY_EPSILON: f64 = 1e-9;
+
+/// 用户净持仓跟踪
+///
+/// 按 (用户ID, 交易对) 记录净持仓数量：买入成交增加、卖出成交减少，正数为多头、负数为空头。
+/// 开仓和加仓按成交价加权更新开仓均价，减仓和平仓按均价计算已实现盈亏；同时按交易对维护未平仓量
+/// （全部多头持仓之和，等于全部空头持仓之和）。用于 reduce-only 订单校验和持仓查询，为后续衍生品模式预留。
+#[derive(Debug, Default)]
+pub struct PositionTracker {
+    state: RwLock<TrackerState>,
+}
+
+#[derive(Debug, Default)]
+struct TrackerState {
+    positions: HashMap<(String, Symbol), PositionState>,
+    open_interest: HashMap<Symbol, f64>,
+}
+
+/// 单个用户在单个交易对上的持仓状态
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct PositionState {
+    quantity: f64,
+    /// 开仓均价；平仓后保留最后的均价，以便撤销平仓成交时恢复
+    entry_price: f64,
+    realized_pnl: f64,
+}
+
+impl PositionState {
+    /// 按成交更新持仓，`delta` 为带符号的成交数量（买入为正）
+    fn apply(&mut self, delta: f64, price: f64) {
+        let previous = self.quantity;
+        let quantity = previous + delta;
+        if previous.abs() <= QUANTITY_EPSILON || previous.signum() == delta.signum() {
+            // 开仓或加仓
+            self.entry_price =
+                (self.entry_price * previous.abs() + price * delta.abs()) / quantity.abs();
+        } else {
+            let closed = delta.abs().min(previous.abs());
+            self.realized_pnl += (price - self.entry_price) * closed * previous.signum();
+            if delta.abs() > previous.abs() + QUANTITY_EPSILON {
+                // 反手：剩余部分按成交价开仓
+                self.entry_price = price;
+            }
+        }
+        self.quantity = normalize(quantity);
+    }
+
+    /// 撤销 [`apply`](Self::apply) 对持仓的影响
+    ///
+    /// 开仓、加仓和不反手的减仓可以精确恢复；反手成交的原开仓均价已被覆盖，撤销后以成交价作为均价
+    fn revert(&mut self, delta: f64, price: f64) {
+        let quantity = self.quantity;
+        let previous = normalize(quantity - delta);
+        if previous.abs() <= QUANTITY_EPSILON {
+            // 撤销开仓
+        } else if previous.signum() == delta.signum() {
+            // 撤销加仓
+            self.entry_price =
+                (self.entry_price * quantity.abs() - price * delta.abs()) / previous.abs();
+        } else {
+            let closed = delta.abs().min(previous.abs());
+            self.realized_pnl -= (price - self.entry_price) * closed * previous.signum();
+        }
+        self.quantity = previous;
+    }
+}
+
+fn normalize(quantity: f64) -> f64 {
+    if quantity.abs() <= QUANTITY_EPSILON {
+        0.0
+    } else {
+        quantity
+    }
+}
+
+/// 单个用户在单个交易对上的持仓，用于查询、导出和恢复持仓
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionEntry {
+    pub user_id: String,
+    pub symbol: Symbol,
+    pub quantity: f64,
+    /// 开仓均价
+    #[serde(default)]
+    pub entry_price: f64,
+    /// 已实现盈亏（计价货币）
+    #[serde(default)]
+    pub realized_pnl: f64,
+}
+
+impl TrackerState {
+    /// 按带符号的成交数量更新用户持仓和交易对未平仓量
+    fn update(&mut self, user_id: &str, symbol: Symbol, update: impl FnOnce(&mut PositionState)) {
+        let position = self
+            .positions
+            .entry((user_id.to_string(), symbol))
+            .or_default();
+        let before = position.quantity.max(0.0);
+        update(position);
+        let after = position.quantity.max(0.0);
+        let open_interest = self.open_interest.entry(symbol).or_default();
+        *open_interest = normalize(*open_interest + after - before);
+    }
+
+    fn rebuild_open_interest(&mut self) {
+        self.open_interest.clear();
+        for ((_, symbol), position) in &self.positions {
+            *self.open_interest.entry(*symbol).or_default() += position.quantity.max(0.0);
+        }
+    }
+}
+
+impl PositionTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 按成交更新买卖双方的持仓
+    pub fn apply_trade(&self, trade: &Trade) {
+        let mut state = self.state.write();
+        state.update(&trade.buyer_id, trade.symbol, |position| {
+            position.apply(trade.quantity, trade.price)
+        });
+        state.update(&trade.seller_id, trade.symbol, |position| {
+            position.apply(-trade.quantity, trade.price)
+        });
+    }
+
+    /// 回退一笔被撤销成交对双方持仓的影响
+    pub fn reverse_trade(&self, trade: &Trade) {
+        let mut state = self.state.write();
+        state.update(&trade.buyer_id, trade.symbol, |position| {
+            position.revert(trade.quantity, trade.price)
+        });
+        state.update(&trade.seller_id, trade.symbol, |position| {
+            position.revert(-trade.quantity, trade.price)
+        });
+    }
+
+    /// 获取用户在交易对上的净持仓
+    pub fn get_position(&self, user_id: &str, symbol: &Symbol) -> f64 {
+        self.state
+            .read()
+            .positions
+            .get(&(user_id.to_string(), *symbol))
+            .map_or(0.0, |position| position.quantity)
+    }
+
+    /// 获取用户在各交易对上的持仓，包括已平仓但有已实现盈亏的交易对，按交易对排序
+    pub fn get_user_positions(&self, user_id: &str) -> Vec<PositionEntry> {
+        let mut positions: Vec<PositionEntry> = self
+            .state
+            .read()
+            .positions
+            .iter()
+            .filter(|((user, _), _)| user == user_id)
+            .map(|((user_id, symbol), position)| entry(user_id, *symbol, position))
+            .collect();
+        positions.sort_by_key(|position| position.symbol.to_string());
+        positions
+    }
+
+    /// 交易对上全部非零持仓，按用户ID排序
+    pub fn get_symbol_positions(&self, symbol: &Symbol) -> Vec<PositionEntry> {
+        let mut positions: Vec<PositionEntry> = self
+            .state
+            .read()
+            .positions
+            .iter()
+            .filter(|((_, position_symbol), position)| {
+                position_symbol == symbol && position.quantity.abs() > QUANTITY_EPSILON
+            })
+            .map(|((user_id, symbol), position)| entry(user_id, *symbol, position))
+            .collect();
+        positions.sort_by(|a, b| a.user_id.cmp(&b.user_id));
+        positions
+    }
+
+    /// 有持仓记录的全部用户，按用户ID排序
+    pub fn users(&self) -> Vec<String> {
+        let mut users: Vec<String> = self
+            .state
+            .read()
+            .positions
+            .keys()
+            .map(|(user_id, _)| user_id.clone())
+            .collect();
+        users.sort_unstable();
+        users.dedup();
+        users
+    }
+
+    /// 交易对的未平仓量
+    pub fn open_interest(&self, symbol: &Symbol) -> f64 {
+        self.state
+            .read()
+            .open_interest
+            .get(symbol)
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// 清空用户在所有交易对上的持仓
+    pub fn clear_user(&self, user_id: &str) {
+        let mut state = self.state.write();
+        state.positions.retain(|(user, _), _| user != user_id);
+        state.rebuild_open_interest();
+    }
+
+    /// 导出全部非零持仓和有已实现盈亏的已平仓持仓
+    pub fn export(&self) -> Vec<PositionEntry> {
+        self.state
+            .read()
+            .positions
+            .iter()
+            .filter(|(_, position)| position.quantity != 0.0 || position.realized_pnl != 0.0)
+            .map(|((user_id, symbol), position)| entry(user_id, *symbol, position))
+            .collect()
+    }
+
+    /// 以导出的持仓替换当前全部持仓
+    pub fn import(&self, entries: Vec<PositionEntry>) {
+        let mut state = self.state.write();
+        state.positions = entries
+            .into_iter()
+            .map(|entry| {
+                let position = PositionState {
+                    quantity: entry.quantity,
+                    entry_price: entry.entry_price,
+                    realized_pnl: entry.realized_pnl,
+                };
+                ((entry.user_id, entry.symbol), position)
+            })
+            .collect();
+        state.rebuild_open_interest();
+    }
+
+    /// reduce-only 订单在该方向上最多可成交的数量
+    ///
+    /// 卖单只能平多头，买单只能平空头
+    pub fn reducible_quantity(&self, user_id: &str, symbol: &Symbol, side: OrderSide) -> f64 {
+        let position = self.get_position(user_id, symbol);
+        match side {
+            OrderSide::Sell => position.max(0.0),
+            OrderSide::Buy => (-position).max(0.0),
+        }
+    }
+}
+
+fn entry(user_id: &str, symbol: Symbol, position: &PositionState) -> PositionEntry {
+    PositionEntry {
+        user_id: user_id.to_string(),
+        symbol,
+        quantity: position.quantity,
+        entry_price: position.entry_price,
+        realized_pnl: position.realized_pnl,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positions_follow_trades() {
+        let symbol = Symbol::new("BTC", "USDT");
+        let buy = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            2.0,
+            Some(100.0),
+            "alice".to_string(),
+        );
+        let sell = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::Limit,
+            2.0,
+            Some(100.0),
+            "bob".to_string(),
+        );
+
+        let positions = PositionTracker::new();
+        positions.apply_trade(&Trade::new(symbol, &buy, &sell, 2.0, 100.0));
+
+        assert_eq!(positions.get_position("alice", &symbol), 2.0);
+        assert_eq!(positions.get_position("bob", &symbol), -2.0);
+        assert_eq!(
+            positions.reducible_quantity("alice", &symbol, OrderSide::Sell),
+            2.0
+        );
+        assert_eq!(
+            positions.reducible_quantity("alice", &symbol, OrderSide::Buy),
+            0.0
+        );
+        assert_eq!(
+            positions.reducible_quantity("bob", &symbol, OrderSide::Buy),
+            2.0
+        );
+    }
+
+    #[test]
+    fn test_entry_price_realized_pnl_and_open_interest() {
+        let symbol = Symbol::new("BTC", "USDT");
+        let trade = |buyer: &str, seller: &str, quantity: f64, price: f64| {
+            let order = |side, user: &str| {
+                Order::new(
+                    symbol,
+                    side,
+                    OrderType::Limit,
+                    quantity,
+                    Some(price),
+                    user.to_string(),
+                )
+            };
+            Trade::new(
+                symbol,
+                &order(OrderSide::Buy, buyer),
+                &order(OrderSide::Sell, seller),
+                quantity,
+                price,
+            )
+        };
+        let positions = PositionTracker::new();
+
+        positions.apply_trade(&trade("alice", "bob", 1.0, 100.0));
+        let add = trade("alice", "carol", 1.0, 110.0);
+        positions.apply_trade(&add);
+        let alice = &positions.get_user_positions("alice")[0];
+        assert_eq!(alice.quantity, 2.0);
+        assert!((alice.entry_price - 105.0).abs() < 1e-9);
+        assert_eq!(positions.open_interest(&symbol), 2.0);
+
+        // 平掉 1.5，按均价 105 计算已实现盈亏
+        let close = trade("bob", "alice", 1.5, 120.0);
+        positions.apply_trade(&close);
+        let alice = &positions.get_user_positions("alice")[0];
+        assert_eq!(alice.quantity, 0.5);
+        assert!((alice.realized_pnl - 22.5).abs() < 1e-9);
+        let bob = &positions.get_user_positions("bob")[0];
+        assert_eq!(bob.quantity, 0.5);
+        assert!((bob.realized_pnl + 20.0).abs() < 1e-9);
+        assert!((positions.open_interest(&symbol) - 1.0).abs() < 1e-9);
+
+        // 撤销成交后恢复持仓、均价、盈亏和未平仓量
+        positions.reverse_trade(&close);
+        positions.reverse_trade(&add);
+        let alice = &positions.get_user_positions("alice")[0];
+        assert_eq!(alice.quantity, 1.0);
+        assert!((alice.entry_price - 100.0).abs() < 1e-9);
+        assert!(alice.realized_pnl.abs() < 1e-9);
+        assert_eq!(positions.open_interest(&symbol), 1.0);
+
+        // 反手后剩余部分按成交价开仓
+        positions.apply_trade(&trade("dave", "alice", 3.0, 90.0));
+        let alice = &positions.get_user_positions("alice")[0];
+        assert_eq!(alice.quantity, -2.0);
+        assert_eq!(alice.entry_price, 90.0);
+        assert!((alice.realized_pnl + 10.0).abs() < 1e-9);
+        assert_eq!(positions.open_interest(&symbol), 3.0);
+    }
+}
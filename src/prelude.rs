@@ -0,0 +1,33 @@
+//! 嵌入使用撮合引擎时常用的类型
+//!
+//! ```
+//! use matching_engine::prelude::*;
+//!
+//! let engine = MatchingEngine::with_config(EngineConfig::default());
+//! let order = Order::new(
+//!     Symbol::new("BTC", "USDT"),
+//!     OrderSide::Buy,
+//!     OrderType::Limit,
+//!     1.0,
+//!     Some(50000.0),
+//!     "user1".to_string(),
+//! );
+//! # tokio_test::block_on(async {
+//! let trades = engine.submit_order(order).await.unwrap();
+//! assert!(trades.is_empty());
+//! # });
+//! ```
+
+pub use crate::clock::{Clock, MockClock, SharedClock, SystemClock};
+pub use crate::cluster::EngineCluster;
+pub use crate::config::EngineConfig;
+pub use crate::engine_api::MatchingEngineApi;
+pub use crate::id::{OrderId, TradeId};
+pub use crate::matching_engine::MatchingEngine;
+pub use crate::mock_engine::MockMatchingEngine;
+pub use crate::orderbook::{OrderBook, SafeOrderBook};
+pub use crate::risk::{PreTradeCheck, PreTradeContext};
+pub use crate::types::{
+    BookTicker, Order, OrderBookDepth, OrderSide, OrderStatus, OrderType, PriceScale, Symbol,
+    TimeInForce, Trade, TradingState,
+};
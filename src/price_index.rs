@@ -0,0 +1,593 @@
+//! 指数价格与标记价格（`price-index` 特性）
+//!
+//! 从外部价格源（HTTP 轮询或 WebSocket 推送）采集各交易对的报价，剔除过期报价和偏离成分中位数过大的报价后
+//! 按权重计算指数价格；标记价格为指数价格加上订单簿中间价相对指数的基差，基差按指数移动平均平滑并限制在
+//! `max_basis_pct` 以内。结果按 `publish_interval_ms` 写入引擎，供 REST/WebSocket 查询，
+//! 条件单和熔断可配置为按标记价格判断。
+//!
+//! 价格源通过 [`PriceFeed`] 接入，除配置中的 HTTP/WebSocket 源外也可以注册自定义实现。
+use crate::config::{PriceFeedConfig, PriceFeedKind, PriceIndexConfig};
+use crate::matching_engine::MatchingEngine;
+use crate::symbol::SymbolId;
+use crate::types::*;
+use chrono::{DateTime, Utc};
+use metrics::gauge;
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::Message;
+
+/// HTTP 价格源的请求超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// WebSocket 读取超时，用于定期检查停止标志
+const READ_TIMEOUT: Duration = Duration::from_millis(200);
+/// WebSocket 断线重连的初始和最大退避时间
+const MIN_RECONNECT_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(30);
+
+/// 价格源任务的 future
+pub type FeedFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// 外部价格源
+pub trait PriceFeed: Send + Sync {
+    /// 价格源名称，同一交易对内唯一
+    fn name(&self) -> &str;
+
+    /// 报价所属的交易对
+    fn symbol(&self) -> Symbol;
+
+    /// 持续采集报价并通过 [`PriceIndex::record`] 写入；指数停止时任务被中止，
+    /// 阻塞式实现应检查 [`PriceIndex::is_stopped`]
+    fn run(self: Arc<Self>, index: Arc<PriceIndex>) -> FeedFuture;
+}
+
+/// 成分报价
+struct Source {
+    weight: f64,
+    quote: Option<(f64, DateTime<Utc>)>,
+}
+
+/// 单个交易对的成分和基差状态
+struct SymbolIndex {
+    symbol: Symbol,
+    sources: BTreeMap<String, Source>,
+    /// 平滑后的基差（中间价减指数价格），尚无中间价时为空
+    basis: Option<f64>,
+}
+
+/// 指数价格和标记价格计算
+pub struct PriceIndex {
+    config: PriceIndexConfig,
+    symbols: Mutex<HashMap<SymbolId, SymbolIndex>>,
+    feeds: Vec<Arc<dyn PriceFeed>>,
+    stopped: AtomicBool,
+}
+
+impl PriceIndex {
+    pub fn new(config: PriceIndexConfig) -> Self {
+        Self {
+            config,
+            symbols: Mutex::new(HashMap::new()),
+            feeds: Vec::new(),
+            stopped: AtomicBool::new(false),
+        }
+    }
+
+    /// 按配置创建指数并注册其中的 HTTP/WebSocket 价格源
+    pub fn from_config(config: PriceIndexConfig) -> Result<Self, String> {
+        let feeds = config.feeds.clone();
+        let mut index = Self::new(config);
+        for feed in feeds {
+            let weight = feed.weight;
+            let feed: Arc<dyn PriceFeed> = match feed.kind {
+                PriceFeedKind::Http => Arc::new(HttpPriceFeed::new(feed)?),
+                PriceFeedKind::Websocket => Arc::new(WebSocketPriceFeed::new(feed)?),
+            };
+            index.add_feed(feed, weight);
+        }
+        Ok(index)
+    }
+
+    /// 注册价格源，`weight` 为计算指数时的权重
+    pub fn add_feed(&mut self, feed: Arc<dyn PriceFeed>, weight: f64) {
+        let symbol = feed.symbol();
+        self.symbols
+            .get_mut()
+            .entry(symbol.id())
+            .or_insert_with(|| SymbolIndex {
+                symbol,
+                sources: BTreeMap::new(),
+                basis: None,
+            })
+            .sources
+            .insert(
+                feed.name().to_string(),
+                Source {
+                    weight,
+                    quote: None,
+                },
+            );
+        self.feeds.push(feed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    /// 记录价格源的最新报价
+    pub fn record(&self, symbol: &Symbol, source: &str, price: f64) -> Result<(), String> {
+        self.record_at(symbol, source, price, Utc::now())
+    }
+
+    /// 记录价格源在指定时间收到的报价
+    pub fn record_at(
+        &self,
+        symbol: &Symbol,
+        source: &str,
+        price: f64,
+        timestamp: DateTime<Utc>,
+    ) -> Result<(), String> {
+        if !(price.is_finite() && price > 0.0) {
+            return Err(format!("Invalid price {} from {}", price, source));
+        }
+        let mut symbols = self.symbols.lock();
+        let entry = symbols
+            .get_mut(&symbol.id())
+            .and_then(|index| index.sources.get_mut(source))
+            .ok_or_else(|| format!("Unknown price source {} for {}", source, symbol))?;
+        entry.quote = Some((price, timestamp));
+        Ok(())
+    }
+
+    /// 在 `now` 时刻的指数价格和参与计算的成分，有效成分不足 `min_sources` 时为空
+    pub fn index_price(
+        &self,
+        symbol: &Symbol,
+        now: DateTime<Utc>,
+    ) -> Option<(f64, Vec<IndexComponent>)> {
+        let symbols = self.symbols.lock();
+        self.compute_index(symbols.get(&symbol.id())?, now)
+    }
+
+    /// 计算标记价格，`mid` 为订单簿中间价，为空时沿用上次的基差
+    pub fn mark_price(
+        &self,
+        symbol: &Symbol,
+        mid: Option<f64>,
+        now: DateTime<Utc>,
+    ) -> Option<MarkPrice> {
+        let mut symbols = self.symbols.lock();
+        let state = symbols.get_mut(&symbol.id())?;
+        let (index_price, components) = self.compute_index(state, now)?;
+
+        if let Some(mid) = mid {
+            let raw = mid - index_price;
+            let alpha = self.config.basis_ema_alpha;
+            state.basis = Some(match state.basis {
+                Some(basis) => basis + alpha * (raw - basis),
+                None => raw,
+            });
+        }
+        let limit = index_price * self.config.max_basis_pct / 100.0;
+        let basis = state.basis.unwrap_or(0.0).clamp(-limit, limit);
+
+        Some(MarkPrice {
+            symbol: state.symbol,
+            index_price,
+            mark_price: index_price + basis,
+            components,
+            timestamp: now,
+        })
+    }
+
+    fn compute_index(
+        &self,
+        state: &SymbolIndex,
+        now: DateTime<Utc>,
+    ) -> Option<(f64, Vec<IndexComponent>)> {
+        let stale_after = chrono::Duration::milliseconds(self.config.stale_after_ms as i64);
+        let mut fresh: Vec<(&str, f64, f64, DateTime<Utc>)> = state
+            .sources
+            .iter()
+            .filter_map(|(name, source)| {
+                let (price, timestamp) = source.quote?;
+                (now - timestamp <= stale_after).then_some((
+                    name.as_str(),
+                    price,
+                    source.weight,
+                    timestamp,
+                ))
+            })
+            .collect();
+
+        if self.config.max_deviation_pct > 0.0 && !fresh.is_empty() {
+            let mut prices: Vec<f64> = fresh.iter().map(|(_, price, _, _)| *price).collect();
+            prices.sort_by(f64::total_cmp);
+            let middle = prices.len() / 2;
+            let median = if prices.len().is_multiple_of(2) {
+                (prices[middle - 1] + prices[middle]) / 2.0
+            } else {
+                prices[middle]
+            };
+            fresh.retain(|(_, price, _, _)| {
+                (price - median).abs() / median * 100.0 <= self.config.max_deviation_pct
+            });
+        }
+        if fresh.len() < self.config.min_sources.max(1) {
+            return None;
+        }
+
+        let total_weight: f64 = fresh.iter().map(|(_, _, weight, _)| weight).sum();
+        let index_price = fresh
+            .iter()
+            .map(|(_, price, weight, _)| price * weight)
+            .sum::<f64>()
+            / total_weight;
+        let components = fresh
+            .into_iter()
+            .map(|(source, price, weight, timestamp)| IndexComponent {
+                source: source.to_string(),
+                price,
+                weight: weight / total_weight,
+                timestamp,
+            })
+            .collect();
+        Some((index_price, components))
+    }
+
+    /// 启动全部价格源并定时向引擎发布标记价格，直到 `shutdown` 完成
+    pub fn start(
+        self: &Arc<Self>,
+        engine: &Arc<MatchingEngine>,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(Arc::clone(self).run(Arc::clone(engine), shutdown))
+    }
+
+    async fn run(self: Arc<Self>, engine: Arc<MatchingEngine>, shutdown: impl Future<Output = ()>) {
+        let mut feeds = JoinSet::new();
+        for feed in &self.feeds {
+            feeds.spawn(Arc::clone(feed).run(Arc::clone(&self)));
+        }
+        info!("Price index started with {} feeds", self.feeds.len());
+
+        tokio::pin!(shutdown);
+        let mut publish =
+            tokio::time::interval(Duration::from_millis(self.config.publish_interval_ms));
+        loop {
+            tokio::select! {
+                biased;
+                _ = &mut shutdown => break,
+                _ = publish.tick() => self.publish(&engine).await,
+            }
+        }
+
+        self.stopped.store(true, Ordering::Relaxed);
+        feeds.shutdown().await;
+        info!("Price index stopped");
+    }
+
+    async fn publish(&self, engine: &MatchingEngine) {
+        let symbols: Vec<Symbol> = self
+            .symbols
+            .lock()
+            .values()
+            .map(|state| state.symbol)
+            .collect();
+        let now = Utc::now();
+        for symbol in symbols {
+            let mid = engine
+                .get_book_ticker(&symbol)
+                .and_then(|ticker| Some((ticker.bid_price? + ticker.ask_price?) / 2.0));
+            let Some(mark_price) = self.mark_price(&symbol, mid, now) else {
+                continue;
+            };
+            gauge!("matching_engine_index_price", "symbol" => symbol.to_string())
+                .set(mark_price.index_price);
+            gauge!("matching_engine_mark_price", "symbol" => symbol.to_string())
+                .set(mark_price.mark_price);
+            engine.update_mark_price(mark_price).await;
+        }
+    }
+}
+
+/// 从 JSON 报文中按 JSON Pointer 取出价格，字段可以是数字或数字字符串
+pub fn extract_price(body: &serde_json::Value, pointer: &str) -> Result<f64, String> {
+    let value = body
+        .pointer(pointer)
+        .ok_or_else(|| format!("Price field {} not found", pointer))?;
+    let price = match value {
+        serde_json::Value::Number(number) => number.as_f64(),
+        serde_json::Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| format!("Price field {} is not a number: {}", pointer, value))?;
+    if !(price.is_finite() && price > 0.0) {
+        return Err(format!("Invalid price {} at {}", price, pointer));
+    }
+    Ok(price)
+}
+
+fn parse_symbol(config: &PriceFeedConfig) -> Result<Symbol, String> {
+    Symbol::register(&config.symbol)
+        .map_err(|_| format!("Invalid price feed symbol {}", config.symbol))
+}
+
+/// 定时 GET 轮询的价格源
+pub struct HttpPriceFeed {
+    config: PriceFeedConfig,
+    symbol: Symbol,
+    client: reqwest::Client,
+}
+
+impl HttpPriceFeed {
+    pub fn new(config: PriceFeedConfig) -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+        Ok(Self {
+            symbol: parse_symbol(&config)?,
+            config,
+            client,
+        })
+    }
+
+    async fn fetch(&self) -> Result<f64, String> {
+        let body: serde_json::Value = self
+            .client
+            .get(&self.config.url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("Request failed: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Invalid response: {}", e))?;
+        extract_price(&body, &self.config.price_pointer)
+    }
+}
+
+impl PriceFeed for HttpPriceFeed {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn symbol(&self) -> Symbol {
+        self.symbol
+    }
+
+    fn run(self: Arc<Self>, index: Arc<PriceIndex>) -> FeedFuture {
+        Box::pin(async move {
+            let mut poll =
+                tokio::time::interval(Duration::from_millis(self.config.poll_interval_ms));
+            poll.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                poll.tick().await;
+                let result = self
+                    .fetch()
+                    .await
+                    .and_then(|price| index.record(&self.symbol, &self.config.name, price));
+                if let Err(e) = result {
+                    warn!("Price feed {} failed: {}", self.config.name, e);
+                }
+            }
+        })
+    }
+}
+
+/// WebSocket 推送的价格源，断线后按指数退避重连
+pub struct WebSocketPriceFeed {
+    config: PriceFeedConfig,
+    symbol: Symbol,
+}
+
+impl WebSocketPriceFeed {
+    pub fn new(config: PriceFeedConfig) -> Result<Self, String> {
+        Ok(Self {
+            symbol: parse_symbol(&config)?,
+            config,
+        })
+    }
+
+    fn read_loop(&self, index: &PriceIndex) {
+        let mut backoff = MIN_RECONNECT_BACKOFF;
+        while !index.is_stopped() {
+            match self.read_stream(index) {
+                Ok(()) => return,
+                Err(e) => warn!(
+                    "Price feed {} disconnected: {}, reconnecting in {:?}",
+                    self.config.name, e, backoff
+                ),
+            }
+            std::thread::sleep(backoff);
+            backoff = (backoff * 2).min(MAX_RECONNECT_BACKOFF);
+        }
+    }
+
+    /// 读取推送直到指数停止（返回 `Ok`）或连接出错
+    fn read_stream(&self, index: &PriceIndex) -> Result<(), String> {
+        let (mut socket, _) = tungstenite::connect(self.config.url.as_str())
+            .map_err(|e| format!("Failed to connect: {}", e))?;
+        if let MaybeTlsStream::Plain(stream) = socket.get_ref() {
+            stream
+                .set_read_timeout(Some(READ_TIMEOUT))
+                .map_err(|e| format!("Failed to set read timeout: {}", e))?;
+        }
+        if let Some(message) = &self.config.subscribe_message {
+            socket
+                .send(Message::Text(message.clone()))
+                .map_err(|e| format!("Failed to subscribe: {}", e))?;
+        }
+        info!("Price feed {} connected", self.config.name);
+
+        while !index.is_stopped() {
+            match socket.read() {
+                Ok(Message::Text(text)) => {
+                    // 心跳、订阅确认等消息没有价格字段，直接忽略
+                    let price = serde_json::from_str(&text)
+                        .map_err(|e| e.to_string())
+                        .and_then(|body| extract_price(&body, &self.config.price_pointer));
+                    if let Ok(price) = price {
+                        index.record(&self.symbol, &self.config.name, price)?;
+                    }
+                }
+                Ok(Message::Close(_)) => return Err("Connection closed".to_string()),
+                Ok(_) => {}
+                Err(tungstenite::Error::Io(e))
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        let _ = socket.close(None);
+        Ok(())
+    }
+}
+
+impl PriceFeed for WebSocketPriceFeed {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn symbol(&self) -> Symbol {
+        self.symbol
+    }
+
+    fn run(self: Arc<Self>, index: Arc<PriceIndex>) -> FeedFuture {
+        Box::pin(async move {
+            let _ = tokio::task::spawn_blocking(move || self.read_loop(&index)).await;
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// 只登记名称的价格源，报价由测试直接写入
+    struct ManualFeed {
+        name: String,
+        symbol: Symbol,
+    }
+
+    impl PriceFeed for ManualFeed {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn symbol(&self) -> Symbol {
+            self.symbol
+        }
+
+        fn run(self: Arc<Self>, _index: Arc<PriceIndex>) -> FeedFuture {
+            Box::pin(async {})
+        }
+    }
+
+    fn btc() -> Symbol {
+        Symbol::new("BTC", "USDT")
+    }
+
+    fn index(config: PriceIndexConfig, feeds: &[(&str, f64)]) -> PriceIndex {
+        let mut index = PriceIndex::new(config);
+        for (name, weight) in feeds {
+            let feed = ManualFeed {
+                name: name.to_string(),
+                symbol: btc(),
+            };
+            index.add_feed(Arc::new(feed), *weight);
+        }
+        index
+    }
+
+    #[test]
+    fn test_weighted_index_skips_stale_quotes() {
+        let config = PriceIndexConfig {
+            enabled: true,
+            min_sources: 2,
+            ..Default::default()
+        };
+        let index = index(config, &[("a", 3.0), ("b", 1.0), ("c", 1.0)]);
+        let now = Utc::now();
+        index.record_at(&btc(), "a", 100.0, now).unwrap();
+        index.record_at(&btc(), "b", 104.0, now).unwrap();
+        index
+            .record_at(&btc(), "c", 500.0, now - chrono::Duration::seconds(60))
+            .unwrap();
+        assert!(index.record_at(&btc(), "d", 100.0, now).is_err());
+        assert!(index.record_at(&btc(), "a", f64::NAN, now).is_err());
+
+        let (price, components) = index.index_price(&btc(), now).unwrap();
+        assert!((price - 101.0).abs() < 1e-9);
+        assert_eq!(components.len(), 2);
+        assert!((components[0].weight - 0.75).abs() < 1e-9);
+
+        // 过期后有效成分不足
+        let later = now + chrono::Duration::seconds(11);
+        assert!(index.index_price(&btc(), later).is_none());
+    }
+
+    #[test]
+    fn test_outliers_are_removed() {
+        let config = PriceIndexConfig {
+            enabled: true,
+            max_deviation_pct: 1.0,
+            ..Default::default()
+        };
+        let index = index(config, &[("a", 1.0), ("b", 1.0), ("c", 1.0)]);
+        let now = Utc::now();
+        index.record_at(&btc(), "a", 100.0, now).unwrap();
+        index.record_at(&btc(), "b", 100.5, now).unwrap();
+        index.record_at(&btc(), "c", 120.0, now).unwrap();
+
+        let (price, components) = index.index_price(&btc(), now).unwrap();
+        assert!((price - 100.25).abs() < 1e-9);
+        assert!(components.iter().all(|component| component.source != "c"));
+    }
+
+    #[test]
+    fn test_mark_price_basis_is_smoothed_and_clamped() {
+        let config = PriceIndexConfig {
+            enabled: true,
+            basis_ema_alpha: 0.5,
+            max_basis_pct: 1.0,
+            ..Default::default()
+        };
+        let index = index(config, &[("a", 1.0)]);
+        let now = Utc::now();
+        index.record_at(&btc(), "a", 100.0, now).unwrap();
+
+        let mark = index.mark_price(&btc(), None, now).unwrap();
+        assert_eq!(mark.mark_price, 100.0);
+        let mark = index.mark_price(&btc(), Some(100.4), now).unwrap();
+        assert!((mark.mark_price - 100.4).abs() < 1e-9);
+        let mark = index.mark_price(&btc(), Some(100.8), now).unwrap();
+        assert!((mark.mark_price - 100.6).abs() < 1e-9);
+        // 基差超过指数的 1% 时被截断
+        let mark = index.mark_price(&btc(), Some(110.0), now).unwrap();
+        assert!((mark.mark_price - 101.0).abs() < 1e-9);
+        assert_eq!(mark.index_price, 100.0);
+    }
+
+    #[test]
+    fn test_extract_price() {
+        let body = json!({"data": [{"last": "42000.5"}], "price": 41999, "bad": "n/a"});
+        assert_eq!(extract_price(&body, "/data/0/last").unwrap(), 42000.5);
+        assert_eq!(extract_price(&body, "/price").unwrap(), 41999.0);
+        assert_eq!(extract_price(&json!(7.5), "").unwrap(), 7.5);
+        assert!(extract_price(&body, "/bad").is_err());
+        assert!(extract_price(&body, "/missing").is_err());
+        assert!(extract_price(&json!(-1.0), "").is_err());
+    }
+}
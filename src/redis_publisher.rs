@@ -0,0 +1,251 @@
+//! Redis 行情推送和快照缓存（`redis` 特性）
+//!
+//! 成交和盘口变化发布到 Redis 频道，最新深度、市场数据和盘口写入带过期时间的键，
+//! 前端等读多的服务可以直接订阅或读取 Redis，不必访问引擎进程。
+use crate::config::RedisConfig;
+use crate::matching_engine::MatchingEngine;
+use crate::types::*;
+use metrics::counter;
+use redis::aio::{ConnectionManager, ConnectionManagerConfig};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{info, warn};
+
+/// Redis 频道和键的命名
+///
+/// 频道：`<prefix>:trades:<SYMBOL>`、`<prefix>:bbo:<SYMBOL>`；
+/// 键：`<prefix>:depth:<SYMBOL>`、`<prefix>:market:<SYMBOL>`、`<prefix>:bbo:<SYMBOL>`。
+/// 频道和键的命名空间互不影响，盘口的频道和键同名
+#[derive(Debug, Clone)]
+pub struct RedisKeys {
+    prefix: String,
+}
+
+impl RedisKeys {
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.trim_end_matches(':').to_string(),
+        }
+    }
+
+    pub fn trades_channel(&self, symbol: &Symbol) -> String {
+        format!("{}:trades:{}", self.prefix, symbol)
+    }
+
+    pub fn bbo_channel(&self, symbol: &Symbol) -> String {
+        format!("{}:bbo:{}", self.prefix, symbol)
+    }
+
+    pub fn bbo_key(&self, symbol: &Symbol) -> String {
+        self.bbo_channel(symbol)
+    }
+
+    pub fn depth_key(&self, symbol: &Symbol) -> String {
+        format!("{}:depth:{}", self.prefix, symbol)
+    }
+
+    pub fn market_key(&self, symbol: &Symbol) -> String {
+        format!("{}:market:{}", self.prefix, symbol)
+    }
+}
+
+/// 把引擎的行情推送到 Redis
+///
+/// 成交和盘口变化逐条发布；深度和市场数据按 `snapshot_interval_ms` 合并写入变化过的交易对，
+/// 并每隔半个 TTL 全量刷新一次，使未变化的快照不会过期。Redis 暂时不可用时记录错误并继续，
+/// 连接由 `ConnectionManager` 自动重建
+pub struct RedisPublisher {
+    connection: ConnectionManager,
+    config: RedisConfig,
+    keys: RedisKeys,
+}
+
+impl RedisPublisher {
+    pub async fn connect(config: &RedisConfig) -> Result<Self, String> {
+        let client = redis::Client::open(config.url.as_str())
+            .map_err(|e| format!("Invalid Redis url {}: {}", config.url, e))?;
+        let manager_config = ConnectionManagerConfig::new()
+            .set_connection_timeout(Duration::from_secs(config.connection_timeout))
+            .set_response_timeout(Duration::from_secs(config.command_timeout));
+        let connection = ConnectionManager::new_with_config(client, manager_config)
+            .await
+            .map_err(|e| format!("Failed to connect to Redis at {}: {}", config.url, e))?;
+
+        Ok(Self {
+            connection,
+            keys: RedisKeys::new(&config.key_prefix),
+            config: config.clone(),
+        })
+    }
+
+    /// 订阅引擎并在后台推送，`shutdown` 完成后写出最后一次快照并退出
+    pub fn start(
+        self,
+        engine: &Arc<MatchingEngine>,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> tokio::task::JoinHandle<()> {
+        let trades = engine.subscribe_trades();
+        let orders = engine.subscribe_orders();
+        let tickers = engine.subscribe_book_ticker();
+        let engine = Arc::clone(engine);
+        tokio::spawn(self.run(engine, trades, orders, tickers, shutdown))
+    }
+
+    async fn run(
+        mut self,
+        engine: Arc<MatchingEngine>,
+        mut trades: broadcast::Receiver<Trade>,
+        mut orders: broadcast::Receiver<Order>,
+        mut tickers: broadcast::Receiver<BookTicker>,
+        shutdown: impl Future<Output = ()>,
+    ) {
+        info!("Redis publisher started with prefix {}", self.keys.prefix);
+        tokio::pin!(shutdown);
+        let mut interval =
+            tokio::time::interval(Duration::from_millis(self.config.snapshot_interval_ms));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let refresh_every = Duration::from_secs(self.config.snapshot_ttl_seconds) / 2;
+        let mut last_refresh = Instant::now();
+        let mut known = HashSet::new();
+        let mut dirty = HashSet::new();
+
+        loop {
+            tokio::select! {
+                biased;
+                _ = &mut shutdown => break,
+                result = trades.recv() => match result {
+                    Ok(trade) => {
+                        dirty.insert(trade.symbol);
+                        self.publish_trade(&trade).await;
+                    }
+                    Err(RecvError::Lagged(skipped)) => lagged("trades", skipped),
+                    Err(RecvError::Closed) => break,
+                },
+                result = tickers.recv() => match result {
+                    Ok(ticker) => self.publish_ticker(&ticker).await,
+                    Err(RecvError::Lagged(skipped)) => lagged("book ticker", skipped),
+                    Err(RecvError::Closed) => break,
+                },
+                result = orders.recv() => match result {
+                    Ok(order) => {
+                        dirty.insert(order.symbol);
+                    }
+                    // 订单更新只用于标记深度变化，丢失时全量刷新
+                    Err(RecvError::Lagged(_)) => dirty.extend(known.iter().copied()),
+                    Err(RecvError::Closed) => break,
+                },
+                _ = interval.tick() => {
+                    known.extend(dirty.iter().copied());
+                    if last_refresh.elapsed() >= refresh_every {
+                        dirty.extend(known.iter().copied());
+                        last_refresh = Instant::now();
+                    }
+                    for symbol in dirty.drain() {
+                        self.write_snapshots(&engine, &symbol).await;
+                    }
+                }
+            }
+        }
+
+        for symbol in dirty.drain() {
+            self.write_snapshots(&engine, &symbol).await;
+        }
+        info!("Redis publisher stopped");
+    }
+
+    async fn publish_ticker(&mut self, ticker: &BookTicker) {
+        let Some(payload) = to_json(ticker) else {
+            return;
+        };
+        let result = redis::pipe()
+            .publish(self.keys.bbo_channel(&ticker.symbol), &payload)
+            .ignore()
+            .set_ex(
+                self.keys.bbo_key(&ticker.symbol),
+                &payload,
+                self.config.snapshot_ttl_seconds,
+            )
+            .ignore()
+            .query_async::<()>(&mut self.connection)
+            .await;
+        record_error(result);
+    }
+
+    /// 以公开形式发布成交，不含双方用户ID和订单ID
+    async fn publish_trade(&mut self, trade: &Trade) {
+        let Some(payload) = to_json(&PublicTrade::from(trade)) else {
+            return;
+        };
+        let result = redis::cmd("PUBLISH")
+            .arg(self.keys.trades_channel(&trade.symbol))
+            .arg(payload)
+            .query_async::<()>(&mut self.connection)
+            .await;
+        record_error(result);
+    }
+
+    /// 写入交易对的深度和市场数据快照
+    async fn write_snapshots(&mut self, engine: &MatchingEngine, symbol: &Symbol) {
+        let ttl = self.config.snapshot_ttl_seconds;
+        let depth = engine
+            .get_orderbook_depth(symbol, Some(self.config.depth_levels))
+            .and_then(|depth| to_json(&depth));
+        let market = engine
+            .get_market_data(symbol)
+            .and_then(|market| to_json(&market));
+        if depth.is_none() && market.is_none() {
+            return;
+        }
+
+        let mut pipe = redis::pipe();
+        if let Some(depth) = depth {
+            pipe.set_ex(self.keys.depth_key(symbol), depth, ttl)
+                .ignore();
+        }
+        if let Some(market) = market {
+            pipe.set_ex(self.keys.market_key(symbol), market, ttl)
+                .ignore();
+        }
+        let result = pipe.query_async::<()>(&mut self.connection).await;
+        record_error(result);
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Option<String> {
+    serde_json::to_string(value)
+        .inspect_err(|e| warn!("Failed to serialize Redis payload: {}", e))
+        .ok()
+}
+
+fn record_error(result: redis::RedisResult<()>) {
+    if let Err(e) = result {
+        warn!("Redis command failed: {}", e);
+        counter!("matching_engine_redis_errors_total").increment(1);
+    }
+}
+
+fn lagged(stream: &str, skipped: u64) {
+    warn!(
+        "Redis publisher lagged, {} {} updates skipped",
+        skipped, stream
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_names() {
+        let keys = RedisKeys::new("exchange:");
+        let symbol = Symbol::new("BTC", "USDT");
+        assert_eq!(keys.trades_channel(&symbol), "exchange:trades:BTCUSDT");
+        assert_eq!(keys.bbo_channel(&symbol), "exchange:bbo:BTCUSDT");
+        assert_eq!(keys.depth_key(&symbol), "exchange:depth:BTCUSDT");
+        assert_eq!(keys.market_key(&symbol), "exchange:market:BTCUSDT");
+    }
+}
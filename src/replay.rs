@@ -0,0 +1,414 @@
+use crate::clock::MockClock;
+use crate::config::EngineConfig;
+use crate::event_bus::{EngineEvent, EventListener, SequencedEvent};
+use crate::id::OrderId;
+use crate::matching_engine::MatchingEngine;
+use crate::store::taker_order_id;
+use crate::symbol::interning;
+use crate::types::*;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// CSV 命令日志的表头
+pub const CSV_HEADER: &str =
+    "timestamp,command,order_id,user_id,symbol,side,order_type,quantity,price";
+
+/// 录制的下单或撤单命令
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ReplayCommand {
+    Submit {
+        timestamp: DateTime<Utc>,
+        order_id: OrderId,
+        user_id: String,
+        symbol: Symbol,
+        side: OrderSide,
+        order_type: OrderType,
+        quantity: f64,
+        #[serde(default)]
+        price: Option<f64>,
+        #[serde(default)]
+        stop_price: Option<f64>,
+        /// GTD 订单到期时间，仅 JSON Lines 格式支持
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expires_at: Option<DateTime<Utc>>,
+    },
+    Cancel {
+        timestamp: DateTime<Utc>,
+        order_id: OrderId,
+        user_id: String,
+    },
+}
+
+impl ReplayCommand {
+    pub fn order_id(&self) -> OrderId {
+        match self {
+            ReplayCommand::Submit { order_id, .. } | ReplayCommand::Cancel { order_id, .. } => {
+                *order_id
+            }
+        }
+    }
+
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        match self {
+            ReplayCommand::Submit { timestamp, .. } | ReplayCommand::Cancel { timestamp, .. } => {
+                *timestamp
+            }
+        }
+    }
+}
+
+/// 回放产生的成交
+///
+/// 不含引擎生成的成交ID：`sequence` 为回放中的成交序号，`timestamp` 取回放时钟，
+/// 即触发成交的命令时间，同一命令日志每次回放得到完全相同的结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayTrade {
+    pub sequence: u64,
+    /// 触发成交的命令在日志中的序号（从 1 开始）
+    pub command: usize,
+    pub symbol: Symbol,
+    pub taker_order_id: OrderId,
+    pub buy_order_id: OrderId,
+    pub sell_order_id: OrderId,
+    pub price: f64,
+    pub quantity: f64,
+    pub buyer_id: String,
+    pub seller_id: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 回放输出的事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ReplayEvent {
+    Trade(ReplayTrade),
+    /// 命令被引擎拒绝
+    Rejected {
+        command: usize,
+        order_id: OrderId,
+        reason: String,
+    },
+}
+
+/// 按扩展名读取命令日志：`.csv` 按 [`CSV_HEADER`] 的列解析，其余按 JSON Lines 解析
+pub fn load_commands(path: impl AsRef<Path>) -> Result<Vec<ReplayCommand>, String> {
+    let path = path.as_ref();
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    if path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"))
+    {
+        parse_csv(&content)
+    } else {
+        parse_jsonl(&content)
+    }
+}
+
+/// 解析 JSON Lines 命令日志，忽略空行
+pub fn parse_jsonl(content: &str) -> Result<Vec<ReplayCommand>, String> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            interning(|| serde_json::from_str(line))
+                .map_err(|e| format!("Line {}: {}", index + 1, e))
+        })
+        .collect()
+}
+
+/// 解析 CSV 命令日志，第一行必须为 [`CSV_HEADER`]
+///
+/// 交易对写作 `BTC/USDT` 或 `BTC-USDT`；撤单行只需填写前四列
+pub fn parse_csv(content: &str) -> Result<Vec<ReplayCommand>, String> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    match lines.next() {
+        Some((_, header)) if header.trim() == CSV_HEADER => {}
+        _ => return Err(format!("CSV header must be `{}`", CSV_HEADER)),
+    }
+
+    lines
+        .map(|(index, line)| parse_csv_row(line).map_err(|e| format!("Line {}: {}", index + 1, e)))
+        .collect()
+}
+
+fn parse_csv_row(line: &str) -> Result<ReplayCommand, String> {
+    let columns: Vec<&str> = line.split(',').map(str::trim).collect();
+    let column = |index: usize, name: &str| {
+        columns
+            .get(index)
+            .copied()
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| format!("missing {}", name))
+    };
+    let number = |index: usize, name: &str| {
+        column(index, name)?
+            .parse::<f64>()
+            .map_err(|e| format!("invalid {}: {}", name, e))
+    };
+
+    let timestamp = column(0, "timestamp")?
+        .parse::<DateTime<Utc>>()
+        .map_err(|e| format!("invalid timestamp: {}", e))?;
+    let order_id = column(2, "order_id")?
+        .parse::<OrderId>()
+        .map_err(|e| format!("invalid order_id: {}", e))?;
+    let user_id = column(3, "user_id")?.to_string();
+
+    match column(1, "command")? {
+        "submit" => Ok(ReplayCommand::Submit {
+            timestamp,
+            order_id,
+            user_id,
+            symbol: parse_symbol(column(4, "symbol")?)?,
+            side: parse_enum(column(5, "side")?)?,
+            order_type: parse_enum(column(6, "order_type")?)?,
+            quantity: number(7, "quantity")?,
+            price: column(8, "price")
+                .ok()
+                .map(|_| number(8, "price"))
+                .transpose()?,
+            stop_price: None,
+            expires_at: None,
+        }),
+        "cancel" => Ok(ReplayCommand::Cancel {
+            timestamp,
+            order_id,
+            user_id,
+        }),
+        other => Err(format!("unknown command {}", other)),
+    }
+}
+
+fn parse_symbol(value: &str) -> Result<Symbol, String> {
+    match value.split_once(['/', '-']) {
+        Some((base, quote)) if !base.is_empty() && !quote.is_empty() => {
+            Ok(Symbol::new(base, quote))
+        }
+        _ => Err(format!("invalid symbol {}, expected BASE/QUOTE", value)),
+    }
+}
+
+/// 按 serde 的小写命名解析枚举值
+fn parse_enum<T: serde::de::DeserializeOwned>(value: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_lowercase()))
+        .map_err(|e| format!("invalid value {}: {}", value, e))
+}
+
+/// 在事件总线上同步收集成交，不经过容量有限的广播，一条命令产生再多成交也不会丢失
+#[derive(Default)]
+struct TradeCollector {
+    trades: Mutex<Vec<Trade>>,
+}
+
+impl TradeCollector {
+    /// 取出收集到的成交
+    fn take(&self) -> Vec<Trade> {
+        std::mem::take(&mut *self.trades.lock())
+    }
+}
+
+impl EventListener for TradeCollector {
+    fn name(&self) -> &str {
+        "replay"
+    }
+
+    fn on_event(&self, event: &SequencedEvent) {
+        if let EngineEvent::Trade(trade) = &event.event {
+            self.trades.lock().push(trade.clone());
+        }
+    }
+}
+
+/// 将命令按日志顺序依次送入一个新的撮合引擎，返回成交和被拒绝的命令
+///
+/// 订单使用日志中记录的订单ID和时间戳；引擎时钟在每条命令前推进到命令时间，
+/// 并撤销此时已到期的 GTD 订单，结果只取决于命令日志本身
+pub async fn replay(commands: &[ReplayCommand]) -> Vec<ReplayEvent> {
+    let start = commands
+        .first()
+        .map(ReplayCommand::timestamp)
+        .unwrap_or_default();
+    let clock = Arc::new(MockClock::new(start));
+    let engine = MatchingEngine::with_clock(EngineConfig::default(), clock.clone());
+    let trades = Arc::new(TradeCollector::default());
+    engine.register_event_listener(trades.clone());
+    let mut events = Vec::new();
+    let mut sequence = 0;
+
+    for (index, command) in commands.iter().enumerate() {
+        let number = index + 1;
+        clock.set(command.timestamp());
+        engine.expire_orders(engine.now()).await;
+
+        let result = match command {
+            ReplayCommand::Submit {
+                timestamp,
+                order_id,
+                user_id,
+                symbol,
+                side,
+                order_type,
+                quantity,
+                price,
+                stop_price,
+                expires_at,
+            } => {
+                let mut order = Order::new(
+                    *symbol,
+                    *side,
+                    *order_type,
+                    *quantity,
+                    *price,
+                    user_id.clone(),
+                );
+                order.id = *order_id;
+                order.timestamp = *timestamp;
+                order.stop_price = *stop_price;
+                if let Some(expires_at) = expires_at {
+                    order = order.with_expiry(*expires_at);
+                }
+                engine.submit_order(order).await.map(|_| ())
+            }
+            ReplayCommand::Cancel {
+                order_id, user_id, ..
+            } => engine
+                .cancel_order(*order_id, user_id.clone())
+                .await
+                .map(|_| ()),
+        };
+
+        if let Err(reason) = result {
+            events.push(ReplayEvent::Rejected {
+                command: number,
+                order_id: command.order_id(),
+                reason,
+            });
+        }
+
+        // 条件单被触发产生的成交同样来自本条命令
+        for trade in trades.take() {
+            sequence += 1;
+            events.push(ReplayEvent::Trade(ReplayTrade {
+                sequence,
+                command: number,
+                symbol: trade.symbol,
+                taker_order_id: taker_order_id(&trade),
+                buy_order_id: trade.buy_order_id,
+                sell_order_id: trade.sell_order_id,
+                price: trade.price,
+                quantity: trade.quantity,
+                buyer_id: trade.buyer_id,
+                seller_id: trade.seller_id,
+                timestamp: trade.timestamp,
+            }));
+        }
+    }
+
+    events
+}
+
+/// 比较两组回放事件，返回第一处差异的描述
+pub fn first_difference(expected: &[ReplayEvent], actual: &[ReplayEvent]) -> Option<String> {
+    for (index, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+        if expected != actual {
+            return Some(format!(
+                "Event {} differs: expected {:?}, got {:?}",
+                index + 1,
+                expected,
+                actual
+            ));
+        }
+    }
+
+    (expected.len() != actual.len())
+        .then(|| format!("Expected {} events, got {}", expected.len(), actual.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_replay_is_deterministic() {
+        let csv = format!(
+            "{}\n\
+             2024-01-01T00:00:00Z,submit,1,maker,BTC/USDT,sell,limit,1.0,100\n\
+             2024-01-01T00:00:01Z,submit,2,maker,BTC/USDT,sell,limit,1.0,101\n\
+             2024-01-01T00:00:02Z,submit,3,taker,BTC/USDT,buy,market,1.5,\n\
+             2024-01-01T00:00:03Z,cancel,2,maker\n\
+             2024-01-01T00:00:04Z,cancel,2,maker\n",
+            CSV_HEADER
+        );
+        let commands = parse_csv(&csv).unwrap();
+        assert_eq!(commands.len(), 5);
+
+        // JSON Lines 与 CSV 表示同一日志
+        let jsonl: String = commands
+            .iter()
+            .map(|command| serde_json::to_string(command).unwrap() + "\n")
+            .collect();
+        assert_eq!(parse_jsonl(&jsonl).unwrap(), commands);
+
+        let events = replay(&commands).await;
+        assert_eq!(events.len(), 3);
+        match &events[1] {
+            ReplayEvent::Trade(trade) => {
+                assert_eq!(trade.sequence, 2);
+                assert_eq!(trade.command, 3);
+                assert_eq!(trade.taker_order_id, 3);
+                assert_eq!(trade.sell_order_id, 2);
+                assert_eq!(trade.price, 101.0);
+                assert_eq!(trade.quantity, 0.5);
+            }
+            other => panic!("expected trade, got {:?}", other),
+        }
+        // 订单 2 剩余部分已在第 4 条命令撤销
+        assert!(matches!(
+            &events[2],
+            ReplayEvent::Rejected {
+                command: 5,
+                order_id: 2,
+                ..
+            }
+        ));
+
+        assert_eq!(first_difference(&events, &replay(&commands).await), None);
+        assert!(first_difference(&events, &events[..2]).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_replay_keeps_every_trade_of_a_large_sweep() {
+        // 一条命令吃掉的挂单数超过成交广播的容量（10000，按 2 的幂取整为 16384）
+        let makers = 17_000;
+        let mut csv = format!("{}\n", CSV_HEADER);
+        for id in 1..=makers {
+            csv.push_str(&format!(
+                "2024-01-01T00:00:00Z,submit,{},maker,BTC/USDT,sell,limit,1.0,100\n",
+                id
+            ));
+        }
+        csv.push_str(&format!(
+            "2024-01-01T00:00:01Z,submit,{},taker,BTC/USDT,buy,market,{},\n",
+            makers + 1,
+            makers
+        ));
+
+        let events = replay(&parse_csv(&csv).unwrap()).await;
+        assert_eq!(events.len(), makers as usize);
+        assert!(events.iter().all(|event| matches!(
+            event,
+            ReplayEvent::Trade(trade) if trade.command == makers as usize + 1
+        )));
+    }
+}
@@ -0,0 +1,693 @@
+//! 主备复制
+//!
+//! 主实例在独立的 TCP 端口上把事件总线的事件流按序号推送给备用实例，每行一条 JSON 消息；
+//! 备用实例把订单、成交、交易状态和上市事件应用到自己的订单簿，状态与主实例保持一致。备用实例连接时带上
+//! 下一个需要的序号，该序号仍在主实例事件总线的回放缓冲区内时只补发缺失的事件，否则主实例先发送
+//! 复制快照，再推送快照之后的事件。
+//! 主实例故障时通过管理接口提升备用实例，提升后停止复制并开始受理订单。
+use crate::config::{ReplicationConfig, ReplicationRole};
+use crate::event_bus::{EventSubscription, SequencedEvent};
+use crate::matching_engine::MatchingEngine;
+use crate::orderbook::OrderBookSnapshot;
+use crate::position::PositionEntry;
+use crate::symbol::interning;
+use crate::types::*;
+use chrono::{DateTime, Utc};
+use metrics::{counter, gauge};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Notify;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// 主实例等待备用实例握手消息的时间
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 备用实例超过该数量的心跳间隔没有收到消息时断开重连
+const IDLE_HEARTBEATS: u32 = 3;
+
+/// 复制快照：主实例在某个序号处的完整撮合状态
+///
+/// 加载快照后依次应用从 `sequence` 开始的事件即可追上主实例
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicationSnapshot {
+    /// 快照之后第一条事件的序号
+    pub sequence: u64,
+    pub timestamp: DateTime<Utc>,
+    pub stats: EngineStats,
+    /// 各交易对的全部挂单及其时间优先级
+    pub orderbooks: Vec<OrderBookSnapshot>,
+    /// 未触发的条件单
+    pub trigger_orders: Vec<Order>,
+    /// 已登记交易状态的交易对
+    pub trading_states: Vec<(Symbol, TradingState)>,
+    /// 交易对上市记录，含运行中上市和下市的交易对
+    pub listings: Vec<SymbolListing>,
+    pub positions: Vec<PositionEntry>,
+    /// 各交易对的最近成交，用于计算市场数据和条件单的最新价
+    pub recent_trades: Vec<Trade>,
+    pub market_data: Vec<MarketData>,
+}
+
+/// 备用实例连接后发送的握手消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicationHello {
+    /// 共享令牌
+    pub token: String,
+    /// 上次复制的主实例标识，主实例重启后标识变化，需要重新发送快照
+    pub epoch: Option<Uuid>,
+    /// 下一个需要的事件序号，为空时请求快照
+    pub next_sequence: Option<u64>,
+}
+
+/// 主实例推送给备用实例的消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum ReplicationMessage {
+    /// 复制快照，`epoch` 为主实例标识
+    Snapshot {
+        epoch: Uuid,
+        snapshot: Box<ReplicationSnapshot>,
+    },
+    /// 事件总线上的事件，序号连续
+    Event(Box<SequencedEvent>),
+    /// 心跳，携带主实例下一条事件的序号
+    Heartbeat {
+        next_sequence: u64,
+        timestamp: DateTime<Utc>,
+    },
+}
+
+/// 复制状态
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReplicationStatus {
+    pub role: ReplicationRole,
+    /// 主实例的标识，备用实例为最近一次复制的主实例
+    pub epoch: Option<Uuid>,
+    /// 主实例实际监听的复制地址
+    pub listen_addr: Option<SocketAddr>,
+    /// 主实例当前连接的备用实例数
+    pub connected_standbys: usize,
+    /// 备用实例是否已连接到主实例
+    pub connected: bool,
+    /// 主实例为下一条事件将使用的序号，备用实例为下一条待应用的事件序号
+    pub next_sequence: Option<u64>,
+    /// 备用实例最近一次从主实例得知的下一条事件序号
+    pub primary_sequence: Option<u64>,
+    /// 备用实例落后主实例的事件数
+    pub lag_events: Option<u64>,
+    /// 备用实例最近应用的事件在主实例上的发生时间
+    pub last_event_at: Option<DateTime<Utc>>,
+    /// 备用实例加载的快照数
+    pub snapshots_loaded: u64,
+}
+
+/// 主备复制：主实例接受备用实例连接并推送事件，备用实例连接主实例并应用事件
+pub struct Replication {
+    engine: Arc<MatchingEngine>,
+    config: ReplicationConfig,
+    status: Mutex<ReplicationStatus>,
+    /// 备用实例被提升时唤醒复制任务
+    promoted: Notify,
+}
+
+impl Replication {
+    /// 创建复制，备用角色立即将引擎切换为备用实例
+    pub fn new(engine: Arc<MatchingEngine>, config: ReplicationConfig) -> Self {
+        let mut status = ReplicationStatus {
+            role: config.role,
+            ..ReplicationStatus::default()
+        };
+        match config.role {
+            ReplicationRole::Primary => status.epoch = Some(Uuid::new_v4()),
+            ReplicationRole::Standby => engine.enter_standby(),
+            ReplicationRole::Disabled => {}
+        }
+        Self {
+            engine,
+            config,
+            status: Mutex::new(status),
+            promoted: Notify::new(),
+        }
+    }
+
+    /// 启动复制任务：主实例监听复制端口，备用实例连接主实例；`shutdown` 完成后任务退出
+    pub async fn start(
+        self: &Arc<Self>,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> Result<tokio::task::JoinHandle<()>, String> {
+        match self.config.role {
+            ReplicationRole::Primary => {
+                let listener = TcpListener::bind(&self.config.listen_addr)
+                    .await
+                    .map_err(|e| {
+                        format!(
+                            "Failed to bind replication listener {}: {}",
+                            self.config.listen_addr, e
+                        )
+                    })?;
+                let local_addr = listener
+                    .local_addr()
+                    .map_err(|e| format!("Failed to read replication listener address: {}", e))?;
+                self.status.lock().listen_addr = Some(local_addr);
+                info!("Replication primary listening on {}", local_addr);
+                Ok(tokio::spawn(
+                    Arc::clone(self).accept_standbys(listener, shutdown),
+                ))
+            }
+            ReplicationRole::Standby => {
+                info!(
+                    "Replication standby following primary at {}",
+                    self.config.primary_addr
+                );
+                Ok(tokio::spawn(Arc::clone(self).follow_primary(shutdown)))
+            }
+            ReplicationRole::Disabled => Err("Replication is disabled".to_string()),
+        }
+    }
+
+    /// 当前复制状态
+    pub fn status(&self) -> ReplicationStatus {
+        let mut status = self.status.lock().clone();
+        if status.role == ReplicationRole::Primary {
+            status.next_sequence = Some(self.engine.last_event_sequence() + 1);
+        }
+        status
+    }
+
+    /// 提升备用实例为主实例：停止复制并开始受理订单
+    ///
+    /// 提升后的实例不再向其它备用实例推送事件，需要时以主实例角色重启
+    pub fn promote(&self) -> Result<ReplicationStatus, String> {
+        self.engine.promote()?;
+        {
+            let mut status = self.status.lock();
+            status.role = ReplicationRole::Primary;
+            status.connected = false;
+        }
+        self.promoted.notify_waiters();
+        gauge!("matching_engine_replication_lag_events").set(0.0);
+        gauge!("matching_engine_replication_lag_seconds").set(0.0);
+        info!("Standby promoted to primary");
+        Ok(self.status())
+    }
+
+    async fn accept_standbys(
+        self: Arc<Self>,
+        listener: TcpListener,
+        shutdown: impl Future<Output = ()>,
+    ) {
+        tokio::pin!(shutdown);
+        loop {
+            let (stream, peer) = tokio::select! {
+                _ = &mut shutdown => break,
+                accepted = listener.accept() => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        warn!("Failed to accept replication connection: {}", e);
+                        continue;
+                    }
+                },
+            };
+
+            let replication = Arc::clone(&self);
+            tokio::spawn(async move {
+                info!("Standby {} connected", peer);
+                replication.update_standbys(1);
+                if let Err(e) = replication.serve_standby(stream).await {
+                    warn!("Replication to standby {} stopped: {}", peer, e);
+                }
+                replication.update_standbys(-1);
+            });
+        }
+        info!("Replication listener stopped");
+    }
+
+    fn update_standbys(&self, delta: isize) {
+        let mut status = self.status.lock();
+        status.connected_standbys = status.connected_standbys.saturating_add_signed(delta);
+        gauge!("matching_engine_replication_standbys").set(status.connected_standbys as f64);
+    }
+
+    /// 校验握手，补发缺失的事件或发送快照，随后持续推送新事件和心跳
+    async fn serve_standby(&self, stream: TcpStream) -> Result<(), String> {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        let line = tokio::time::timeout(HANDSHAKE_TIMEOUT, lines.next_line())
+            .await
+            .map_err(|_| "Handshake timed out".to_string())?
+            .map_err(|e| format!("Failed to read handshake: {}", e))?
+            .ok_or_else(|| "Connection closed before handshake".to_string())?;
+        let hello: ReplicationHello =
+            serde_json::from_str(&line).map_err(|e| format!("Invalid handshake: {}", e))?;
+        if hello.token != self.config.token {
+            return Err("Invalid replication token".to_string());
+        }
+
+        let epoch = self.status.lock().epoch;
+        let resumed = match hello.next_sequence {
+            Some(next_sequence) if hello.epoch == epoch => {
+                match self.engine.subscribe_events_from(Some(next_sequence)) {
+                    Ok(subscription) => Some(subscription),
+                    Err(e) => {
+                        info!("Sending replication snapshot: {}", e);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        let EventSubscription { replay, live, .. } = match resumed {
+            Some(subscription) => subscription,
+            None => {
+                let (snapshot, subscription) = self.engine.replication_snapshot().await?;
+                counter!("matching_engine_replication_snapshots_total").increment(1);
+                info!(
+                    "Sending replication snapshot at sequence {}",
+                    snapshot.sequence
+                );
+                let message = ReplicationMessage::Snapshot {
+                    epoch: epoch.unwrap_or_default(),
+                    snapshot: Box::new(snapshot),
+                };
+                write_message(&mut writer, &message).await?;
+                subscription
+            }
+        };
+
+        for event in replay {
+            write_message(&mut writer, &ReplicationMessage::Event(Box::new(event))).await?;
+        }
+
+        let mut live = live;
+        let mut heartbeat =
+            tokio::time::interval(Duration::from_millis(self.config.heartbeat_interval_ms));
+        loop {
+            let message = tokio::select! {
+                event = live.recv() => match event {
+                    Ok(event) => ReplicationMessage::Event(Box::new(event)),
+                    Err(RecvError::Lagged(skipped)) => {
+                        return Err(format!("Standby fell behind by {} events", skipped));
+                    }
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+                _ = heartbeat.tick() => ReplicationMessage::Heartbeat {
+                    next_sequence: self.engine.last_event_sequence() + 1,
+                    timestamp: self.engine.now(),
+                },
+                // 备用实例不再发送消息，读到连接关闭即停止推送
+                line = lines.next_line() => match line {
+                    Ok(Some(_)) => continue,
+                    Ok(None) => return Ok(()),
+                    Err(e) => return Err(format!("Connection error: {}", e)),
+                },
+            };
+            write_message(&mut writer, &message).await?;
+        }
+    }
+
+    async fn follow_primary(self: Arc<Self>, shutdown: impl Future<Output = ()>) {
+        tokio::pin!(shutdown);
+        let backoff = Duration::from_millis(self.config.reconnect_backoff_ms);
+
+        while self.engine.is_standby() {
+            let result = tokio::select! {
+                _ = &mut shutdown => break,
+                _ = self.promoted.notified() => break,
+                result = self.replicate() => result,
+            };
+            self.status.lock().connected = false;
+            if let Err(e) = result {
+                warn!(
+                    "Replication from primary {} interrupted: {}",
+                    self.config.primary_addr, e
+                );
+            }
+
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = self.promoted.notified() => break,
+                _ = tokio::time::sleep(backoff) => {}
+            }
+        }
+        info!("Replication from primary stopped");
+    }
+
+    /// 连接主实例并应用事件，连接中断或应用失败时返回错误
+    async fn replicate(&self) -> Result<(), String> {
+        let stream = TcpStream::connect(&self.config.primary_addr)
+            .await
+            .map_err(|e| format!("Failed to connect: {}", e))?;
+        let (reader, mut writer) = stream.into_split();
+
+        let hello = {
+            let status = self.status.lock();
+            ReplicationHello {
+                token: self.config.token.clone(),
+                epoch: status.epoch,
+                next_sequence: status.next_sequence,
+            }
+        };
+        write_message(&mut writer, &hello).await?;
+        self.status.lock().connected = true;
+        info!(
+            "Connected to replication primary {}",
+            self.config.primary_addr
+        );
+
+        let idle_timeout =
+            Duration::from_millis(self.config.heartbeat_interval_ms) * IDLE_HEARTBEATS;
+        let mut lines = BufReader::new(reader).lines();
+        loop {
+            let line = tokio::time::timeout(idle_timeout, lines.next_line())
+                .await
+                .map_err(|_| format!("No message from primary within {:?}", idle_timeout))?
+                .map_err(|e| format!("Connection error: {}", e))?
+                .ok_or_else(|| "Primary closed the connection".to_string())?;
+            let message: ReplicationMessage = interning(|| serde_json::from_str(&line))
+                .map_err(|e| format!("Invalid replication message: {}", e))?;
+            self.apply(message).await?;
+        }
+    }
+
+    /// 应用主实例的一条消息，事件序号不连续时返回错误以便重连补齐
+    async fn apply(&self, message: ReplicationMessage) -> Result<(), String> {
+        match message {
+            ReplicationMessage::Snapshot { epoch, snapshot } => {
+                let sequence = snapshot.sequence;
+                self.engine.load_replication_snapshot(*snapshot).await?;
+                let mut status = self.status.lock();
+                status.epoch = Some(epoch);
+                status.next_sequence = Some(sequence);
+                status.snapshots_loaded += 1;
+                info!("Loaded replication snapshot at sequence {}", sequence);
+            }
+            ReplicationMessage::Event(event) => {
+                let next_sequence = self.status.lock().next_sequence;
+                match next_sequence {
+                    // 重连后补发的事件可能与已应用的事件重叠
+                    Some(next) if event.sequence < next => return Ok(()),
+                    Some(next) if event.sequence == next => {}
+                    _ => {
+                        return Err(format!(
+                            "Expected event {:?}, received {}",
+                            next_sequence, event.sequence
+                        ))
+                    }
+                }
+
+                let sequence = event.sequence;
+                let timestamp = event.timestamp;
+                self.engine.apply_replicated_event(*event).await?;
+
+                let mut status = self.status.lock();
+                status.next_sequence = Some(sequence + 1);
+                status.last_event_at = Some(timestamp);
+                let lag = (self.engine.now() - timestamp).num_milliseconds().max(0);
+                gauge!("matching_engine_replication_lag_seconds").set(lag as f64 / 1000.0);
+                record_lag(&mut status);
+            }
+            ReplicationMessage::Heartbeat { next_sequence, .. } => {
+                let mut status = self.status.lock();
+                status.primary_sequence = Some(next_sequence);
+                record_lag(&mut status);
+                // 已追上主实例时事件延迟归零
+                if status.lag_events == Some(0) {
+                    gauge!("matching_engine_replication_lag_seconds").set(0.0);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 按主实例最近的序号计算落后的事件数并更新指标
+fn record_lag(status: &mut ReplicationStatus) {
+    if let (Some(primary), Some(next)) = (status.primary_sequence, status.next_sequence) {
+        let lag = primary.saturating_sub(next);
+        status.lag_events = Some(lag);
+        gauge!("matching_engine_replication_lag_events").set(lag as f64);
+    }
+}
+
+/// 以一行 JSON 写出一条消息
+async fn write_message<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message: &impl Serialize,
+) -> Result<(), String> {
+    let mut line = serde_json::to_vec(message)
+        .map_err(|e| format!("Failed to serialize replication message: {}", e))?;
+    line.push(b'\n');
+    writer
+        .write_all(&line)
+        .await
+        .map_err(|e| format!("Failed to send replication message: {}", e))
+}
+
+#[cfg(feature = "http")]
+pub use router::create_replication_router;
+
+#[cfg(feature = "http")]
+mod router {
+    use super::{Replication, ReplicationStatus};
+    use crate::api::ApiError;
+    use axum::{
+        extract::State,
+        http::StatusCode,
+        routing::{get, post},
+        Json, Router,
+    };
+    use std::sync::Arc;
+    use tracing::warn;
+
+    /// 创建复制状态和提升接口的路由，与 API 路由共用前缀
+    pub fn create_replication_router(replication: Arc<Replication>) -> Router {
+        Router::new()
+            .route("/admin/replication/status", get(replication_status))
+            .route("/admin/replication/promote", post(promote))
+            .with_state(replication)
+    }
+
+    async fn replication_status(
+        State(replication): State<Arc<Replication>>,
+    ) -> Json<ReplicationStatus> {
+        Json(replication.status())
+    }
+
+    async fn promote(
+        State(replication): State<Arc<Replication>>,
+    ) -> Result<Json<ReplicationStatus>, ApiError> {
+        match replication.promote() {
+            Ok(status) => Ok(Json(status)),
+            Err(e) => {
+                warn!("Failed to promote standby: {}", e);
+                Err(ApiError::new(StatusCode::CONFLICT, "promotion_failed", e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EngineConfig;
+
+    fn symbol() -> Symbol {
+        Symbol::new("BTC", "USDT")
+    }
+
+    fn limit(side: OrderSide, quantity: f64, price: f64, user_id: &str) -> Order {
+        Order::new(
+            symbol(),
+            side,
+            OrderType::Limit,
+            quantity,
+            Some(price),
+            user_id.to_string(),
+        )
+    }
+
+    fn book_json(engine: &MatchingEngine) -> serde_json::Value {
+        let snapshot = engine.export_orderbook(&symbol()).unwrap();
+        serde_json::to_value((snapshot.next_priority, snapshot.entries)).unwrap()
+    }
+
+    async fn caught_up(primary: &MatchingEngine, standby: &Replication) {
+        // 主实例的后台任务可能继续发布行情，追上读取时的序号即可
+        let target = primary.last_event_sequence() + 1;
+        for _ in 0..200 {
+            if standby
+                .status()
+                .next_sequence
+                .is_some_and(|next| next >= target)
+            {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("Standby did not catch up to {}", target);
+    }
+
+    async fn start_primary(engine: &Arc<MatchingEngine>) -> Arc<Replication> {
+        let replication = Arc::new(Replication::new(
+            Arc::clone(engine),
+            ReplicationConfig {
+                role: ReplicationRole::Primary,
+                listen_addr: "127.0.0.1:0".to_string(),
+                token: "secret".to_string(),
+                heartbeat_interval_ms: 50,
+                ..ReplicationConfig::default()
+            },
+        ));
+        replication.start(std::future::pending()).await.unwrap();
+        replication
+    }
+
+    async fn start_standby(
+        engine: &Arc<MatchingEngine>,
+        primary: &Replication,
+    ) -> Arc<Replication> {
+        let replication = Arc::new(Replication::new(
+            Arc::clone(engine),
+            ReplicationConfig {
+                role: ReplicationRole::Standby,
+                primary_addr: primary.status().listen_addr.unwrap().to_string(),
+                token: "secret".to_string(),
+                heartbeat_interval_ms: 50,
+                reconnect_backoff_ms: 50,
+                ..ReplicationConfig::default()
+            },
+        ));
+        replication.start(std::future::pending()).await.unwrap();
+        replication
+    }
+
+    #[tokio::test]
+    async fn test_standby_replicates_and_takes_over() {
+        let primary = Arc::new(MatchingEngine::new());
+
+        // 备用实例连接前的挂单通过快照传输
+        primary
+            .submit_order(limit(OrderSide::Buy, 2.0, 99.0, "alice"))
+            .await
+            .unwrap();
+        primary
+            .submit_order(limit(OrderSide::Sell, 3.0, 101.0, "bob"))
+            .await
+            .unwrap();
+
+        let primary_replication = start_primary(&primary).await;
+        let standby = Arc::new(MatchingEngine::new());
+        let standby_replication = start_standby(&standby, &primary_replication).await;
+        caught_up(&primary, &standby_replication).await;
+        assert_eq!(standby_replication.status().snapshots_loaded, 1);
+
+        // 之后的挂单、部分成交、修改和撤单通过事件流复制
+        let resting = limit(OrderSide::Buy, 1.0, 99.0, "carol");
+        let resting_id = resting.id;
+        primary.submit_order(resting).await.unwrap();
+        let trades = primary
+            .submit_order(limit(OrderSide::Buy, 1.0, 101.0, "dave"))
+            .await
+            .unwrap();
+        assert_eq!(trades.len(), 1);
+        let amended = limit(OrderSide::Sell, 5.0, 102.0, "erin");
+        let amended_id = amended.id;
+        primary.submit_order(amended).await.unwrap();
+        primary
+            .amend_order(amended_id, "erin".to_string(), 4.0)
+            .await
+            .unwrap();
+        primary
+            .cancel_order(resting_id, "carol".to_string())
+            .await
+            .unwrap();
+        caught_up(&primary, &standby_replication).await;
+
+        assert_eq!(book_json(&standby), book_json(&primary));
+        assert_eq!(
+            standby.get_order(amended_id).unwrap().quantity,
+            primary.get_order(amended_id).unwrap().quantity
+        );
+        assert_eq!(
+            standby.get_order(resting_id).unwrap().status,
+            OrderStatus::Cancelled
+        );
+        assert_eq!(standby.positions().get_position("dave", &symbol()), 1.0);
+        assert_eq!(standby_replication.status().lag_events, Some(0));
+
+        // 备用实例只能在提升后受理订单
+        let taker = limit(OrderSide::Sell, 2.0, 99.0, "frank");
+        assert!(standby.submit_order(taker.clone()).await.is_err());
+        standby_replication.promote().unwrap();
+        let trades = standby.submit_order(taker).await.unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].buyer_id, "alice");
+        assert!(standby_replication.promote().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_standby_replicates_listings() {
+        let config = EngineConfig {
+            supported_symbols: vec!["BTCUSDT".to_string()],
+            restrict_symbols: true,
+            ..EngineConfig::default()
+        };
+        let eth = Symbol::new("ETH", "USDT");
+        let sol = Symbol::new("SOL", "USDT");
+        let list = |symbol: Symbol, tick_size: f64| ListSymbolRequest {
+            symbol,
+            filters: SymbolFilters {
+                tick_size: Some(tick_size),
+                ..Default::default()
+            },
+        };
+
+        // 备用实例连接前上市的交易对通过快照传输
+        let primary = Arc::new(MatchingEngine::with_config(config.clone()));
+        primary.list_symbol(list(eth, 0.5)).unwrap();
+        let primary_replication = start_primary(&primary).await;
+        let standby = Arc::new(MatchingEngine::with_config(config));
+        let standby_replication = start_standby(&standby, &primary_replication).await;
+        caught_up(&primary, &standby_replication).await;
+        assert!(standby.check_listing(&eth, None, None).is_ok());
+        assert!(standby.check_listing(&sol, None, None).is_err());
+
+        // 之后的上市和下市通过事件流复制
+        primary.list_symbol(list(sol, 0.25)).unwrap();
+        primary.delist_symbol(&eth).await.unwrap();
+        caught_up(&primary, &standby_replication).await;
+
+        assert!(standby.check_listing(&sol, None, None).is_ok());
+        assert_eq!(
+            standby.check_listing(&eth, None, None).unwrap_err(),
+            "Symbol ETHUSDT is delisted"
+        );
+        let listings = standby.get_symbol_listings();
+        let sol_listing = listings.iter().find(|listing| listing.symbol == sol);
+        assert_eq!(sol_listing.unwrap().filters.tick_size, Some(0.25));
+
+        // 提升后按复制的上市记录受理订单
+        standby_replication.promote().unwrap();
+        let order = |symbol: Symbol| {
+            Order::new(
+                symbol,
+                OrderSide::Buy,
+                OrderType::Limit,
+                1.0,
+                Some(100.25),
+                "alice".to_string(),
+            )
+        };
+        assert!(standby.submit_order(order(sol)).await.is_ok());
+        assert!(standby.submit_order(order(eth)).await.is_err());
+    }
+}
@@ -0,0 +1,110 @@
+//! 请求ID
+//!
+//! 每个 HTTP 请求都有一个请求ID：沿用请求头 `X-Request-Id` 中的值，没有或不合法时生成 UUID。
+//! 请求ID 通过响应头返回，并写入请求的 tracing span、JSON 错误响应体和下单的订单审计记录，
+//! 用户反馈问题时提供该ID，运维即可找到对应的日志和审计事件。
+use std::future::Future;
+
+/// 请求ID的请求头和响应头
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// 当前请求的ID，不在 HTTP 请求中处理（如后台任务）时为 None
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// 在请求ID的作用域内执行
+pub async fn scope<F: Future>(request_id: String, f: F) -> F::Output {
+    REQUEST_ID.scope(request_id, f).await
+}
+
+#[cfg(feature = "http")]
+pub use layer::{propagate_request_id, RequestId};
+
+#[cfg(feature = "http")]
+mod layer {
+    use super::{scope, REQUEST_ID_HEADER};
+    use axum::{extract::Request, http::HeaderValue, middleware::Next, response::Response};
+    use uuid::Uuid;
+
+    /// 沿用客户端请求ID的最大长度
+    const MAX_REQUEST_ID_LEN: usize = 128;
+
+    /// 请求扩展中的请求ID
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct RequestId(pub String);
+
+    /// 请求ID中间件：确定请求ID并放入请求扩展，在其作用域内处理请求，响应时回写 `X-Request-Id`
+    pub async fn propagate_request_id(mut request: Request, next: Next) -> Response {
+        let request_id = request
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| is_valid(id))
+            .map_or_else(|| Uuid::new_v4().to_string(), str::to_string);
+        request
+            .extensions_mut()
+            .insert(RequestId(request_id.clone()));
+
+        let mut response = scope(request_id.clone(), next.run(request)).await;
+        if let Ok(value) = HeaderValue::from_str(&request_id) {
+            response.headers_mut().insert(REQUEST_ID_HEADER, value);
+        }
+        response
+    }
+
+    /// 客户端提供的请求ID只接受可见 ASCII 字符，且不超过 128 个字符
+    fn is_valid(id: &str) -> bool {
+        !id.is_empty()
+            && id.len() <= MAX_REQUEST_ID_LEN
+            && id.bytes().all(|byte| byte.is_ascii_graphic())
+    }
+}
+
+#[cfg(all(test, feature = "http"))]
+mod tests {
+    use super::*;
+    use crate::api::ApiError;
+    use axum::{
+        body::Body, http::Request, middleware, response::IntoResponse, routing::get, Router,
+    };
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_request_id_is_propagated_to_errors() {
+        let app = Router::new()
+            .route(
+                "/missing",
+                get(|| async { ApiError::not_found("Order not found").into_response() }),
+            )
+            .layer(middleware::from_fn(propagate_request_id));
+        let send = |request_id: Option<&str>| {
+            let mut request = Request::builder().uri("/missing");
+            if let Some(request_id) = request_id {
+                request = request.header(REQUEST_ID_HEADER, request_id);
+            }
+            app.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        let response = send(Some("client-req-1")).await.unwrap();
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "client-req-1");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["request_id"], "client-req-1");
+
+        // 不合法的请求ID被替换为新生成的 UUID
+        let response = send(Some("has space")).await.unwrap();
+        let generated = response.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+        assert!(generated.parse::<uuid::Uuid>().is_ok());
+        let response = send(None).await.unwrap();
+        assert!(response.headers().contains_key(REQUEST_ID_HEADER));
+
+        assert_eq!(current(), None);
+    }
+}
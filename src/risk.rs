@@ -0,0 +1,343 @@
+use crate::config::{MessageRatioConfig, UserLimits, UserLimitsConfig};
+use crate::orderbook::OrderBookStats;
+use crate::types::*;
+use chrono::{DateTime, Utc};
+use metrics::counter;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+/// 下单前风控检查的输入
+#[derive(Debug)]
+pub struct PreTradeContext<'a> {
+    /// 待接受的订单
+    pub order: &'a Order,
+    /// 订单所在交易对的订单簿统计
+    pub book_stats: &'a OrderBookStats,
+    /// 用户当前未完成的订单
+    pub open_orders: &'a [Order],
+    /// 用户各资产余额
+    pub balances: &'a [Balance],
+    /// 用户在该交易对上的净持仓
+    pub position: f64,
+}
+
+/// 下单前风控检查插件
+///
+/// 在订单被引擎接受前同步调用，返回 `Err` 时订单被拒绝。
+/// 集成方可以通过 `MatchingEngine::register_pre_trade_check` 注册自定义规则（持仓上限、授信检查等）
+pub trait PreTradeCheck: Send + Sync {
+    /// 检查名称，用于日志和拒单原因
+    fn name(&self) -> &str;
+
+    /// 执行检查
+    fn check(&self, context: &PreTradeContext<'_>) -> Result<(), String>;
+}
+
+/// 已注册的风控检查，按注册顺序执行
+#[derive(Default)]
+pub struct PreTradeChecks {
+    checks: RwLock<Vec<Arc<dyn PreTradeCheck>>>,
+}
+
+impl PreTradeChecks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册风控检查
+    pub fn register(&self, check: Arc<dyn PreTradeCheck>) {
+        self.checks.write().push(check);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.checks.read().is_empty()
+    }
+
+    /// 依次执行所有检查，返回第一个失败的检查的拒绝原因
+    pub fn run(&self, context: &PreTradeContext<'_>) -> Result<(), String> {
+        for check in self.checks.read().iter() {
+            check
+                .check(context)
+                .map_err(|reason| format!("Rejected by risk check {}: {}", check.name(), reason))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for PreTradeChecks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let checks = self.checks.read();
+        f.debug_list()
+            .entries(checks.iter().map(|check| check.name()))
+            .finish()
+    }
+}
+
+/// 用户限额及当前占用
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserLimitStatus {
+    pub user_id: String,
+    pub limits: UserLimits,
+    pub open_orders: usize,
+    pub open_notional: f64,
+    /// 交易对 -> 挂单名义价值
+    pub open_notional_by_symbol: HashMap<String, f64>,
+    /// 消息/成交比统计窗口内的计数，未启用时为 0
+    #[serde(default)]
+    pub message_counts: MessageCounts,
+}
+
+impl UserLimitStatus {
+    /// 根据用户未完成订单计算限额占用
+    pub fn new(user_id: &str, limits: UserLimits, open_orders: &[Order]) -> Self {
+        let mut open_notional_by_symbol: HashMap<String, f64> = HashMap::new();
+        for order in open_orders {
+            *open_notional_by_symbol
+                .entry(order.symbol.to_string())
+                .or_default() += open_notional(order);
+        }
+
+        Self {
+            user_id: user_id.to_string(),
+            limits,
+            open_orders: open_orders.len(),
+            open_notional: open_notional_by_symbol.values().sum(),
+            open_notional_by_symbol,
+            message_counts: MessageCounts::default(),
+        }
+    }
+}
+
+/// 订单未成交部分的名义价值，市价单不挂单，不计入
+fn open_notional(order: &Order) -> f64 {
+    order
+        .price
+        .or(order.stop_price)
+        .map(|price| price * order.remaining_quantity)
+        .unwrap_or(0.0)
+}
+
+/// 用户挂单限额检查：未完成订单数、单交易对和全局挂单名义价值
+#[derive(Debug)]
+pub struct UserLimitCheck {
+    config: UserLimitsConfig,
+}
+
+impl UserLimitCheck {
+    pub fn new(config: UserLimitsConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl PreTradeCheck for UserLimitCheck {
+    fn name(&self) -> &str {
+        "user_limits"
+    }
+
+    fn check(&self, context: &PreTradeContext<'_>) -> Result<(), String> {
+        let order = context.order;
+        check_limits(
+            order,
+            self.config.limits_for(&order.user_id),
+            context.open_orders,
+        )?;
+
+        if let Some(limits) = self.config.channels.get(&order.channel) {
+            let channel_orders: Vec<Order> = context
+                .open_orders
+                .iter()
+                .filter(|open| open.channel == order.channel)
+                .cloned()
+                .collect();
+            check_limits(order, limits, &channel_orders)
+                .map_err(|reason| format!("{} channel {}", order.channel.as_str(), reason))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// 检查订单接受后用户的挂单是否仍在限额内
+fn check_limits(order: &Order, limits: &UserLimits, open_orders: &[Order]) -> Result<(), String> {
+    let status = UserLimitStatus::new(&order.user_id, limits.clone(), open_orders);
+
+    if let Some(max_open_orders) = limits.max_open_orders {
+        if status.open_orders >= max_open_orders {
+            return Err(format!(
+                "open order limit exceeded ({} of {})",
+                status.open_orders, max_open_orders
+            ));
+        }
+    }
+
+    let notional = open_notional(order);
+    if let Some(max_per_symbol) = limits.max_open_notional_per_symbol {
+        let symbol_notional = status
+            .open_notional_by_symbol
+            .get(&order.symbol.to_string())
+            .copied()
+            .unwrap_or(0.0);
+        if symbol_notional + notional > max_per_symbol {
+            return Err(format!(
+                "open notional limit for {} exceeded ({} + {} > {})",
+                order.symbol, symbol_notional, notional, max_per_symbol
+            ));
+        }
+    }
+
+    if let Some(max_open_notional) = limits.max_open_notional {
+        if status.open_notional + notional > max_open_notional {
+            return Err(format!(
+                "total open notional limit exceeded ({} + {} > {})",
+                status.open_notional, notional, max_open_notional
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// 用户在滚动窗口内的消息和成交计数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct MessageCounts {
+    pub orders: u64,
+    pub amends: u64,
+    pub cancels: u64,
+    /// 用户作为任一方参与的成交笔数
+    pub fills: u64,
+}
+
+impl MessageCounts {
+    /// 下单、改单和撤单合计
+    pub fn messages(&self) -> u64 {
+        self.orders + self.amends + self.cancels
+    }
+
+    fn add(&mut self, other: &MessageCounts) {
+        self.orders += other.orders;
+        self.amends += other.amends;
+        self.cancels += other.cancels;
+        self.fills += other.fills;
+    }
+}
+
+/// 按用户统计的消息/成交比，超过配置上限时拒绝新订单
+///
+/// 计数按秒分桶，窗口外的桶在下一次记录或检查时丢弃
+#[derive(Debug)]
+pub struct MessageRateTracker {
+    config: MessageRatioConfig,
+    exempt_users: HashSet<String>,
+    /// 用户ID -> 按秒排列的 (Unix 秒, 计数)
+    users: Mutex<HashMap<String, VecDeque<(i64, MessageCounts)>>>,
+}
+
+impl MessageRateTracker {
+    pub fn new(config: MessageRatioConfig) -> Self {
+        Self {
+            exempt_users: config.exempt_users.iter().cloned().collect(),
+            config,
+            users: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn record_order(&self, user_id: &str, now: DateTime<Utc>) {
+        self.record(user_id, now, |counts| counts.orders += 1);
+    }
+
+    pub fn record_amend(&self, user_id: &str, now: DateTime<Utc>) {
+        self.record(user_id, now, |counts| counts.amends += 1);
+    }
+
+    pub fn record_cancel(&self, user_id: &str, now: DateTime<Utc>) {
+        self.record(user_id, now, |counts| counts.cancels += 1);
+    }
+
+    pub fn record_fill(&self, user_id: &str, now: DateTime<Utc>) {
+        self.record(user_id, now, |counts| counts.fills += 1);
+    }
+
+    /// 用户在窗口内的合计计数
+    pub fn counts(&self, user_id: &str, now: DateTime<Utc>) -> MessageCounts {
+        let mut users = self.users.lock();
+        let Some(buckets) = users.get_mut(user_id) else {
+            return MessageCounts::default();
+        };
+        self.evict(buckets, now);
+        let mut total = MessageCounts::default();
+        for (_, counts) in buckets.iter() {
+            total.add(counts);
+        }
+        if buckets.is_empty() {
+            users.remove(user_id);
+        }
+        total
+    }
+
+    /// 检查用户能否提交新订单，超限时返回拒绝原因
+    pub fn check(&self, user_id: &str, now: DateTime<Utc>) -> Result<(), String> {
+        if self.exempt_users.contains(user_id) {
+            return Ok(());
+        }
+        let counts = self.counts(user_id, now);
+        if counts.messages() < self.config.min_messages {
+            return Ok(());
+        }
+
+        let fills = counts.fills.max(1) as f64;
+        let limits = [
+            (
+                "messages_per_fill",
+                "messages",
+                counts.messages(),
+                self.config.max_messages_per_fill,
+            ),
+            (
+                "cancels_per_fill",
+                "cancels",
+                counts.cancels,
+                self.config.max_cancels_per_fill,
+            ),
+        ];
+        for (reason, label, count, max_ratio) in limits {
+            let ratio = count as f64 / fills;
+            if ratio > max_ratio {
+                counter!("matching_engine_throttled_orders_total", "reason" => reason).increment(1);
+                return Err(format!(
+                    "Message rate limit exceeded: {} {} for {} fills in the last {}s ({:.1} per fill, max {})",
+                    count, label, counts.fills, self.config.window_seconds, ratio, max_ratio
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn record(&self, user_id: &str, now: DateTime<Utc>, update: impl FnOnce(&mut MessageCounts)) {
+        let second = now.timestamp();
+        let mut users = self.users.lock();
+        let buckets = users.entry(user_id.to_string()).or_default();
+        self.evict(buckets, now);
+        match buckets.back_mut() {
+            Some((bucket, counts)) if *bucket == second => update(counts),
+            _ => {
+                let mut counts = MessageCounts::default();
+                update(&mut counts);
+                buckets.push_back((second, counts));
+            }
+        }
+    }
+
+    fn evict(&self, buckets: &mut VecDeque<(i64, MessageCounts)>, now: DateTime<Utc>) {
+        let window_start = now.timestamp() - self.config.window_seconds as i64;
+        while buckets
+            .front()
+            .is_some_and(|(second, _)| *second <= window_start)
+        {
+            buckets.pop_front();
+        }
+    }
+}
@@ -0,0 +1,262 @@
+//! 沙盒（模拟交易）模式
+//!
+//! 每个用户首次使用时自动注入配置的虚拟资金；下单前按虚拟余额减去挂单占用检查可用资金，
+//! 成交后按成交价和手续费结算到买卖双方余额，成交被撤销时冲回。
+use crate::account::{AccountManager, Posting};
+use crate::config::SandboxConfig;
+use crate::types::*;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+/// 余额比较的容差，避免浮点误差导致的误拒
+const BALANCE_EPSILON: f64 = 1e-9;
+
+/// 沙盒账户重置结果
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SandboxReset {
+    pub user_id: String,
+    /// 重置时撤销的挂单数量
+    pub cancelled_orders: usize,
+    /// 重置后的余额
+    pub balances: Vec<Balance>,
+}
+
+/// 沙盒账户管理：自动注资、下单前余额检查和成交结算
+pub struct Sandbox {
+    config: SandboxConfig,
+    /// 已注入初始资金的用户
+    funded: RwLock<HashSet<String>>,
+}
+
+impl Sandbox {
+    pub fn new(config: SandboxConfig) -> Self {
+        Self {
+            config,
+            funded: RwLock::new(HashSet::new()),
+        }
+    }
+
+    /// 用户首次使用时注入初始虚拟资金
+    pub fn ensure_funded(&self, accounts: &AccountManager, user_id: &str) {
+        if self.funded.read().contains(user_id) {
+            return;
+        }
+        let mut funded = self.funded.write();
+        if !funded.insert(user_id.to_string()) {
+            return;
+        }
+        for (asset, amount) in &self.config.initial_balances {
+            let idempotency_key = format!("sandbox-funding-{}", asset.to_uppercase());
+            if let Err(e) = accounts.deposit(user_id, asset, *amount, &idempotency_key) {
+                warn!("Failed to fund sandbox account {}: {}", user_id, e);
+            }
+        }
+        info!("Funded sandbox account {}", user_id);
+    }
+
+    /// 重置用户后重新注入初始资金
+    pub fn refund(&self, accounts: &AccountManager, user_id: &str) {
+        self.funded.write().remove(user_id);
+        self.ensure_funded(accounts, user_id);
+    }
+
+    /// 检查虚拟余额扣除已有挂单占用后是否足够支付新订单
+    ///
+    /// 买单占用计价货币，卖单占用基础货币；没有价格的市价买单按 `reference_price`（卖一价）估算
+    pub fn check_balance(
+        &self,
+        order: &Order,
+        open_orders: &[Order],
+        balances: &[Balance],
+        reference_price: Option<f64>,
+    ) -> Result<(), String> {
+        let asset = match order.side {
+            OrderSide::Buy => order.symbol.quote(),
+            OrderSide::Sell => order.symbol.base(),
+        };
+        let reserved: f64 = open_orders
+            .iter()
+            .filter(|open| {
+                open.id != order.id
+                    && open.side == order.side
+                    && match order.side {
+                        OrderSide::Buy => open.symbol.quote() == asset,
+                        OrderSide::Sell => open.symbol.base() == asset,
+                    }
+            })
+            .map(|open| required_amount(open, None))
+            .sum();
+        let required = reserved + required_amount(order, reference_price);
+        let available = balances
+            .iter()
+            .find(|balance| balance.asset == asset)
+            .map(|balance| balance.available)
+            .unwrap_or(0.0);
+
+        if required > available + BALANCE_EPSILON {
+            return Err(format!(
+                "Insufficient sandbox {} balance: available {}, required {}",
+                asset, available, required
+            ));
+        }
+        Ok(())
+    }
+
+    /// 把成交结算到买卖双方的虚拟余额，`fees` 为买方和卖方的手续费（计价货币）
+    pub fn settle_trade(&self, accounts: &AccountManager, trade: &Trade, fees: [f64; 2]) {
+        let postings = trade_postings(trade, fees, 1.0);
+        accounts.settle(&format!("trade-{}", trade.id), &postings);
+    }
+
+    /// 冲回被撤销成交的结算
+    pub fn reverse_trade(&self, accounts: &AccountManager, trade: &Trade, fees: [f64; 2]) {
+        let postings = trade_postings(trade, fees, -1.0);
+        accounts.settle(&format!("bust-{}", trade.id), &postings);
+    }
+}
+
+/// 订单剩余部分需要占用的资产数量
+fn required_amount(order: &Order, reference_price: Option<f64>) -> f64 {
+    match order.side {
+        OrderSide::Sell => order.remaining_quantity,
+        OrderSide::Buy => match order.quote_quantity {
+            Some(quote_quantity) => quote_quantity,
+            None => {
+                order
+                    .price
+                    .or(order.stop_price)
+                    .or(reference_price)
+                    .unwrap_or(0.0)
+                    * order.remaining_quantity
+            }
+        },
+    }
+}
+
+/// 成交的记账分录，`direction` 为 -1 时生成冲回分录
+fn trade_postings(trade: &Trade, fees: [f64; 2], direction: f64) -> Vec<Posting> {
+    let base = trade.symbol.base().to_string();
+    let quote = trade.symbol.quote().to_string();
+    let notional = trade.price * trade.quantity;
+    let posting = |user_id: &str, asset: &str, amount: f64, entry_type| Posting {
+        user_id: user_id.to_string(),
+        asset: asset.to_string(),
+        amount: amount * direction,
+        entry_type,
+    };
+    let [buyer_fee, seller_fee] = fees;
+
+    vec![
+        posting(
+            &trade.buyer_id,
+            &base,
+            trade.quantity,
+            LedgerEntryType::Trade,
+        ),
+        posting(&trade.buyer_id, &quote, -notional, LedgerEntryType::Trade),
+        posting(&trade.buyer_id, &quote, -buyer_fee, LedgerEntryType::Fee),
+        posting(
+            &trade.seller_id,
+            &base,
+            -trade.quantity,
+            LedgerEntryType::Trade,
+        ),
+        posting(&trade.seller_id, &quote, notional, LedgerEntryType::Trade),
+        posting(&trade.seller_id, &quote, -seller_fee, LedgerEntryType::Fee),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn sandbox() -> Sandbox {
+        Sandbox::new(SandboxConfig {
+            enabled: true,
+            initial_balances: HashMap::from([
+                ("USDT".to_string(), 1_000.0),
+                ("BTC".to_string(), 1.0),
+            ]),
+        })
+    }
+
+    fn limit(side: OrderSide, quantity: f64, price: f64, user_id: &str) -> Order {
+        Order::new(
+            Symbol::new("BTC", "USDT"),
+            side,
+            OrderType::Limit,
+            quantity,
+            Some(price),
+            user_id.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_funding_is_applied_once() {
+        let sandbox = sandbox();
+        let accounts = AccountManager::new();
+        sandbox.ensure_funded(&accounts, "alice");
+        sandbox.ensure_funded(&accounts, "alice");
+        assert_eq!(accounts.get_balance("alice", "USDT").available, 1_000.0);
+        assert_eq!(accounts.get_balance("alice", "BTC").available, 1.0);
+
+        accounts.reset_user("alice");
+        sandbox.refund(&accounts, "alice");
+        assert_eq!(accounts.get_balance("alice", "USDT").available, 1_000.0);
+    }
+
+    #[test]
+    fn test_check_balance_includes_open_orders() {
+        let sandbox = sandbox();
+        let accounts = AccountManager::new();
+        sandbox.ensure_funded(&accounts, "alice");
+        let balances = accounts.get_balances("alice");
+
+        let open = vec![limit(OrderSide::Buy, 5.0, 100.0, "alice")];
+        let order = limit(OrderSide::Buy, 5.0, 100.0, "alice");
+        assert!(sandbox
+            .check_balance(&order, &open, &balances, None)
+            .is_ok());
+        let order = limit(OrderSide::Buy, 6.0, 100.0, "alice");
+        let err = sandbox
+            .check_balance(&order, &open, &balances, None)
+            .unwrap_err();
+        assert!(err.contains("Insufficient sandbox USDT balance"));
+
+        // 卖单只占用基础货币，不受买单占用影响
+        let order = limit(OrderSide::Sell, 1.0, 100.0, "alice");
+        assert!(sandbox
+            .check_balance(&order, &open, &balances, None)
+            .is_ok());
+        let order = limit(OrderSide::Sell, 1.5, 100.0, "alice");
+        assert!(sandbox
+            .check_balance(&order, &open, &balances, None)
+            .is_err());
+    }
+
+    #[test]
+    fn test_settle_and_reverse_trade() {
+        let sandbox = sandbox();
+        let accounts = AccountManager::new();
+        sandbox.ensure_funded(&accounts, "alice");
+        sandbox.ensure_funded(&accounts, "bob");
+
+        let buy = limit(OrderSide::Buy, 0.5, 100.0, "alice");
+        let sell = limit(OrderSide::Sell, 0.5, 100.0, "bob");
+        let trade = Trade::new(Symbol::new("BTC", "USDT"), &buy, &sell, 0.5, 100.0);
+        sandbox.settle_trade(&accounts, &trade, [0.05, -0.01]);
+
+        assert_eq!(accounts.get_balance("alice", "BTC").available, 1.5);
+        assert!((accounts.get_balance("alice", "USDT").available - 949.95).abs() < 1e-9);
+        assert_eq!(accounts.get_balance("bob", "BTC").available, 0.5);
+        assert!((accounts.get_balance("bob", "USDT").available - 1_050.01).abs() < 1e-9);
+
+        sandbox.reverse_trade(&accounts, &trade, [0.05, -0.01]);
+        assert!((accounts.get_balance("alice", "USDT").available - 1_000.0).abs() < 1e-9);
+        assert!((accounts.get_balance("bob", "BTC").available - 1.0).abs() < 1e-9);
+    }
+}
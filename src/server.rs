@@ -0,0 +1,623 @@
+use anyhow::{anyhow, Context, Result};
+use axum::Router;
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use matching_engine::admin::protect_admin_router;
+use matching_engine::api::{
+    apply_server_layers, apply_server_layers_with, create_admin_router, create_router,
+};
+use matching_engine::auth::Authenticator;
+use matching_engine::binance::create_binance_router;
+use matching_engine::config::{AppConfig, EventsConfig, ReplicationRole};
+use matching_engine::events::EventPublisher;
+use matching_engine::logging::init_advanced_logging;
+use matching_engine::monitoring::{create_monitoring_router, MonitoringManager};
+use matching_engine::replication::{create_replication_router, Replication};
+use matching_engine::tenant::{create_tenant_router, TenantRegistry};
+use matching_engine::tls::{load_rustls_config, start_cert_reloader};
+use matching_engine::websocket::{create_websocket_router, WebSocketBroadcaster};
+use matching_engine::MatchingEngine;
+
+/// 按 `config/` 下的配置启动完整服务
+///
+/// API 路由挂载在 `server.api_prefix` 下，WebSocket 路由挂载在 `server.ws_prefix` 下，
+/// 启用监控时另挂载 `/monitoring`，启用 Binance 兼容接口时另挂载 `/api/v3`。
+pub async fn run_server() -> Result<()> {
+    let config = AppConfig::load().context("Failed to load configuration")?;
+    config
+        .validate()
+        .map_err(|e| anyhow!("Invalid configuration: {}", e))?;
+
+    // 初始化日志，句柄需保留到进程退出
+    let logging = init_advanced_logging(config.logging.clone())
+        .map_err(|e| anyhow!("Failed to initialize logging: {}", e))?;
+
+    info!("Starting Matching Engine v{}", env!("CARGO_PKG_VERSION"));
+
+    if config.tenancy.enabled {
+        return run_tenants(&config).await;
+    }
+
+    // 创建撮合引擎
+    let engine = Arc::new(
+        MatchingEngine::try_with_config(config.engine.clone())
+            .map_err(|e| anyhow!("Failed to create matching engine: {}", e))?,
+    );
+    engine.start_expiry_scheduler();
+    engine.start_market_data_publisher();
+    engine.start_order_archiver();
+    engine.start_trade_evictor();
+    engine.start_margin_monitor();
+    engine.start_funding_scheduler();
+    engine.start_fee_settlement_scheduler();
+    engine.start_end_of_day_scheduler();
+    engine.start_session_scheduler();
+    info!("Matching engine initialized");
+
+    #[cfg(feature = "redis")]
+    let redis = match &config.redis {
+        Some(redis) => {
+            let publisher = matching_engine::redis_publisher::RedisPublisher::connect(redis)
+                .await
+                .map_err(|e| anyhow!(e))?;
+            info!("Publishing market data to Redis at {}", redis.url);
+            Some(publisher.start(&engine, shutdown_signal()))
+        }
+        None => None,
+    };
+    #[cfg(not(feature = "redis"))]
+    if config.redis.is_some() {
+        tracing::warn!("Redis is configured but the server was built without the redis feature");
+    }
+
+    let events = if config.events.enabled {
+        let publisher = build_event_publisher(&config.events).await?;
+        Some(publisher.start(&engine, shutdown_signal()))
+    } else {
+        None
+    };
+
+    // WebSocket 连接由广播器登记，心跳任务负责探活
+    let broadcaster = WebSocketBroadcaster::new();
+    broadcaster.start_heartbeat(config.server.websocket.clone());
+
+    let monitoring = if config.monitoring.enabled {
+        let manager = MonitoringManager::new(config.monitoring.clone())
+            .map_err(|e| anyhow!("Failed to initialize monitoring: {}", e))?;
+        manager.start_symbol_metrics(Arc::clone(&engine));
+        Some(Arc::new(manager))
+    } else {
+        None
+    };
+
+    // 创建路由
+    let mut api = create_router(Arc::clone(&engine));
+    let mut admin = create_admin_router(Arc::clone(&engine), Some(logging.level_handle()));
+    let ws = create_websocket_router(
+        Arc::clone(&engine),
+        broadcaster.clone(),
+        config.server.websocket.clone(),
+    );
+
+    // 成交监控的告警查询接口属于管理接口
+    let surveillance = if config.surveillance.enabled {
+        use matching_engine::surveillance::{create_surveillance_router, Surveillance};
+        let surveillance = Arc::new(Surveillance::new(config.surveillance.clone()));
+        admin = admin.merge(create_surveillance_router(Arc::clone(&surveillance)));
+        info!("Trade surveillance enabled");
+        Some(surveillance.start(&engine, shutdown_signal()))
+    } else {
+        None
+    };
+
+    // 主备复制：备用实例在接收 HTTP 请求前已切换为备用，状态和提升接口属于管理接口
+    let replication = if config.replication.role != ReplicationRole::Disabled {
+        let replication = Arc::new(Replication::new(
+            Arc::clone(&engine),
+            config.replication.clone(),
+        ));
+        admin = admin.merge(create_replication_router(Arc::clone(&replication)));
+        Some(
+            replication
+                .start(shutdown_signal())
+                .await
+                .map_err(|e| anyhow!(e))?,
+        )
+    } else {
+        None
+    };
+
+    // Webhook 管理接口与 API 路由共用前缀
+    #[cfg(feature = "webhooks")]
+    let webhooks = if config.webhooks.enabled {
+        use matching_engine::webhooks::{
+            create_webhook_router, WebhookDispatcher, WebhookRegistry,
+        };
+        let registry = Arc::new(WebhookRegistry::new(config.webhooks.max_webhooks_per_user));
+        api = api.merge(create_webhook_router(Arc::clone(&registry)));
+        let dispatcher =
+            WebhookDispatcher::new(registry, config.webhooks.clone()).map_err(|e| anyhow!(e))?;
+        info!("Webhook notifications enabled");
+        Some(dispatcher.start(&engine, shutdown_signal()))
+    } else {
+        None
+    };
+    #[cfg(not(feature = "webhooks"))]
+    if config.webhooks.enabled {
+        tracing::warn!(
+            "Webhooks are enabled in configuration but the server was built without the webhooks feature"
+        );
+    }
+
+    #[cfg(feature = "price-index")]
+    let price_index = if config.price_index.enabled {
+        use matching_engine::price_index::PriceIndex;
+        let price_index =
+            Arc::new(PriceIndex::from_config(config.price_index.clone()).map_err(|e| anyhow!(e))?);
+        Some(price_index.start(&engine, shutdown_signal()))
+    } else {
+        None
+    };
+    #[cfg(not(feature = "price-index"))]
+    if config.price_index.enabled {
+        tracing::warn!(
+            "Price index is enabled in configuration but the server was built without the price-index feature"
+        );
+    }
+
+    #[cfg(feature = "parquet")]
+    let export = if config.export.enabled {
+        use matching_engine::export::ExportArchiver;
+        let archiver = ExportArchiver::new(config.export.clone()).map_err(|e| anyhow!(e))?;
+        Some(archiver.start(&engine, shutdown_signal()))
+    } else {
+        None
+    };
+    #[cfg(not(feature = "parquet"))]
+    if config.export.enabled {
+        tracing::warn!(
+            "Parquet archiving is enabled in configuration but the server was built without the parquet feature"
+        );
+    }
+
+    // REST API 和 gRPC 共用一个校验器，API key 的新增、删除和请求计数在两个入口之间一致
+    let authenticator = if config.server.auth.enabled {
+        Some(Arc::new(Authenticator::new(&config.server.auth).map_err(
+            |e| anyhow!("Invalid server configuration: {}", e),
+        )?))
+    } else {
+        None
+    };
+
+    #[cfg(feature = "grpc")]
+    let grpc = if config.server.grpc.enabled {
+        let addr = config
+            .grpc_addr()
+            .parse()
+            .with_context(|| format!("Invalid gRPC address {}", config.grpc_addr()))?;
+        Some(tokio::spawn(matching_engine::grpc::serve(
+            Arc::clone(&engine),
+            addr,
+            authenticator.clone(),
+            shutdown_signal(),
+        )))
+    } else {
+        None
+    };
+    #[cfg(not(feature = "grpc"))]
+    if config.server.grpc.enabled {
+        tracing::warn!(
+            "gRPC is enabled in configuration but the server was built without the grpc feature"
+        );
+    }
+
+    // 管理接口校验管理令牌并审计，未配置单独端口时与 API 路由共用前缀
+    let admin = protect_admin_router(admin, &config.server.admin, &config.server.admin.audit_dir)
+        .map_err(|e| anyhow!("Failed to initialize admin API: {}", e))?;
+    let admin_server = if config.server.admin.port == 0 {
+        api = api.merge(admin);
+        None
+    } else {
+        let admin = mount(Router::new(), &config.server.api_prefix, admin);
+        Some(serve_admin(admin, &config).await?)
+    };
+
+    let mut app = mount(Router::new(), &config.server.api_prefix, api);
+    app = mount(app, &config.server.ws_prefix, ws);
+    if config.server.binance.enabled {
+        app = app.merge(create_binance_router(Arc::clone(&engine)));
+        info!("Binance compatible API enabled under /api/v3");
+    }
+    if let Some(monitoring) = &monitoring {
+        app = mount_monitoring(app, monitoring, &config);
+    }
+    let app = apply_server_layers_with(app, &config.server, authenticator)
+        .map_err(|e| anyhow!("Invalid server configuration: {}", e))?;
+
+    // 收到 SIGINT/SIGTERM 后停止接单、关闭 WebSocket 并等待进行中的请求完成
+    let draining_engine = Arc::clone(&engine);
+    let on_shutdown = async move {
+        shutdown_signal().await;
+        info!("Shutdown signal received, draining");
+        draining_engine.begin_shutdown();
+        let closed = broadcaster.close_all().await;
+        info!("Closed {} WebSocket connections", closed);
+    };
+    serve(app, &config, on_shutdown).await?;
+    if let Some(admin_server) = admin_server {
+        wait_admin_server(admin_server).await;
+    }
+
+    #[cfg(feature = "grpc")]
+    if let Some(grpc) = grpc {
+        match grpc.await {
+            Ok(Ok(())) => info!("gRPC server stopped"),
+            Ok(Err(e)) => error!("gRPC server failed: {}", e),
+            Err(e) => error!("gRPC server task failed: {}", e),
+        }
+    }
+
+    if let Some(events) = events {
+        if let Err(e) = events.await {
+            error!("Event publisher task failed: {}", e);
+        }
+    }
+    #[cfg(feature = "redis")]
+    if let Some(redis) = redis {
+        if let Err(e) = redis.await {
+            error!("Redis publisher task failed: {}", e);
+        }
+    }
+    if let Some(surveillance) = surveillance {
+        if let Err(e) = surveillance.await {
+            error!("Trade surveillance task failed: {}", e);
+        }
+    }
+    if let Some(replication) = replication {
+        if let Err(e) = replication.await {
+            error!("Replication task failed: {}", e);
+        }
+    }
+    #[cfg(feature = "webhooks")]
+    if let Some(webhooks) = webhooks {
+        if let Err(e) = webhooks.await {
+            error!("Webhook dispatcher task failed: {}", e);
+        }
+    }
+    #[cfg(feature = "price-index")]
+    if let Some(price_index) = price_index {
+        if let Err(e) = price_index.await {
+            error!("Price index task failed: {}", e);
+        }
+    }
+    #[cfg(feature = "parquet")]
+    if let Some(export) = export {
+        if let Err(e) = export.await {
+            error!("Parquet archiver task failed: {}", e);
+        }
+    }
+
+    // 等待引擎内进行中的撮合完成，刷新归档和审计，按配置写出快照
+    engine
+        .shutdown()
+        .await
+        .map_err(|e| anyhow!("Failed to shut down matching engine: {}", e))?;
+
+    info!("Server stopped");
+    Ok(())
+}
+
+/// 多租户模式：每个租户使用独立的撮合引擎和路由，请求按 API key 分发到所属租户
+///
+/// 监控路由不区分租户；运行时日志级别是进程级的，不对租户开放。
+/// 管理接口同样按 API key 分发，审计日志按租户写入 `audit_dir/tenant-<id>`
+async fn run_tenants(config: &AppConfig) -> Result<()> {
+    let registry = TenantRegistry::new(&config.tenancy, &config.engine)
+        .map_err(|e| anyhow!("Failed to create tenant engines: {}", e))?;
+    for tenant in registry.tenants() {
+        tenant.engine.start_expiry_scheduler();
+        tenant.engine.start_market_data_publisher();
+        tenant.engine.start_order_archiver();
+        tenant.engine.start_trade_evictor();
+        tenant.engine.start_margin_monitor();
+        tenant.engine.start_funding_scheduler();
+        tenant.engine.start_fee_settlement_scheduler();
+        tenant.engine.start_end_of_day_scheduler();
+        tenant.engine.start_session_scheduler();
+    }
+    info!(
+        "Matching engines initialized for {} tenants",
+        registry.tenants().len()
+    );
+
+    let broadcaster = WebSocketBroadcaster::new();
+    broadcaster.start_heartbeat(config.server.websocket.clone());
+
+    let mut admin_routers = HashMap::new();
+    for tenant in registry.tenants() {
+        let audit_dir =
+            Path::new(&config.server.admin.audit_dir).join(format!("tenant-{}", tenant.id));
+        let admin = create_admin_router(Arc::clone(&tenant.engine), None);
+        let admin = protect_admin_router(admin, &config.server.admin, audit_dir).map_err(|e| {
+            anyhow!(
+                "Failed to initialize admin API of tenant {}: {}",
+                tenant.id,
+                e
+            )
+        })?;
+        admin_routers.insert(tenant.id.clone(), admin);
+    }
+    let shared_admin = config.server.admin.port == 0;
+    let admin_server = if shared_admin {
+        None
+    } else {
+        let admin = create_tenant_router(&registry, &config.tenancy.api_key_header, |tenant| {
+            mount(
+                Router::new(),
+                &config.server.api_prefix,
+                admin_routers[&tenant.id].clone(),
+            )
+        });
+        Some(serve_admin(admin, config).await?)
+    };
+
+    let mut app = create_tenant_router(&registry, &config.tenancy.api_key_header, |tenant| {
+        let mut api = create_router(Arc::clone(&tenant.engine));
+        if shared_admin {
+            api = api.merge(admin_routers[&tenant.id].clone());
+        }
+        let ws = create_websocket_router(
+            Arc::clone(&tenant.engine),
+            broadcaster.clone(),
+            config.server.websocket.clone(),
+        );
+        let mut app = mount(Router::new(), &config.server.api_prefix, api);
+        app = mount(app, &config.server.ws_prefix, ws);
+        if config.server.binance.enabled {
+            app = app.merge(create_binance_router(Arc::clone(&tenant.engine)));
+        }
+        app
+    });
+    if config.monitoring.enabled {
+        let manager = MonitoringManager::new(config.monitoring.clone())
+            .map_err(|e| anyhow!("Failed to initialize monitoring: {}", e))?;
+        app = mount_monitoring(app, &Arc::new(manager), config);
+    }
+    let app = apply_server_layers(app, &config.server)
+        .map_err(|e| anyhow!("Invalid server configuration: {}", e))?;
+
+    let engines: Vec<Arc<MatchingEngine>> = registry
+        .tenants()
+        .iter()
+        .map(|tenant| Arc::clone(&tenant.engine))
+        .collect();
+    let on_shutdown = async move {
+        shutdown_signal().await;
+        info!("Shutdown signal received, draining");
+        for engine in &engines {
+            engine.begin_shutdown();
+        }
+        let closed = broadcaster.close_all().await;
+        info!("Closed {} WebSocket connections", closed);
+    };
+    serve(app, config, on_shutdown).await?;
+    if let Some(admin_server) = admin_server {
+        wait_admin_server(admin_server).await;
+    }
+
+    for tenant in registry.tenants() {
+        tenant.engine.shutdown().await.map_err(|e| {
+            anyhow!(
+                "Failed to shut down matching engine of tenant {}: {}",
+                tenant.id,
+                e
+            )
+        })?;
+    }
+
+    info!("Server stopped");
+    Ok(())
+}
+
+/// 挂载监控路由，配置了 `serve_on_api` 时指标接口同时挂载到主服务
+fn mount_monitoring(
+    mut app: Router,
+    monitoring: &Arc<MonitoringManager>,
+    config: &AppConfig,
+) -> Router {
+    app = app.nest(
+        "/monitoring",
+        create_monitoring_router(Arc::clone(monitoring)),
+    );
+    if config.monitoring.serve_on_api {
+        app = app.merge(monitoring.metrics_router());
+    }
+    app
+}
+
+/// 绑定监听地址并提供服务，直到 `on_shutdown` 完成且进行中的请求处理完毕
+async fn serve(
+    app: Router,
+    config: &AppConfig,
+    on_shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    let listener = TcpListener::bind(config.server_addr())
+        .await
+        .with_context(|| format!("Failed to bind {}", config.server_addr()))?;
+    info!("Server listening on {}", config.server_addr());
+    info!("API endpoint: {}", config.api_base_url());
+    info!("WebSocket endpoint: {}", config.ws_base_url());
+
+    if config.server.tls.enabled {
+        serve_tls(listener, app, config, on_shutdown).await
+    } else {
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(on_shutdown)
+        .await?;
+        Ok(())
+    }
+}
+
+/// 在 `server.admin.port` 上单独提供管理接口，收到 SIGINT/SIGTERM 后停止
+///
+/// 管理端口不启用 TLS，应只在内网开放
+async fn serve_admin(admin: Router, config: &AppConfig) -> Result<JoinHandle<Result<()>>> {
+    let admin = apply_server_layers(admin, &config.server)
+        .map_err(|e| anyhow!("Invalid server configuration: {}", e))?;
+    let listener = TcpListener::bind(config.admin_addr())
+        .await
+        .with_context(|| format!("Failed to bind {}", config.admin_addr()))?;
+    info!("Admin API listening on {}", config.admin_addr());
+    Ok(tokio::spawn(async move {
+        axum::serve(
+            listener,
+            admin.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
+        Ok(())
+    }))
+}
+
+async fn wait_admin_server(admin_server: JoinHandle<Result<()>>) {
+    match admin_server.await {
+        Ok(Ok(())) => info!("Admin server stopped"),
+        Ok(Err(e)) => error!("Admin server failed: {}", e),
+        Err(e) => error!("Admin server task failed: {}", e),
+    }
+}
+
+/// 按配置创建事件发布器，未以对应特性编译的投递目标会被忽略并告警
+async fn build_event_publisher(config: &EventsConfig) -> Result<EventPublisher> {
+    #[allow(unused_mut)]
+    let mut publisher = EventPublisher::new(config.clone());
+
+    if let Some(kafka) = &config.kafka {
+        #[cfg(feature = "kafka")]
+        {
+            let sink =
+                matching_engine::kafka_sink::KafkaEventSink::new(kafka, config.topics.clone())
+                    .map_err(|e| anyhow!(e))?;
+            publisher = publisher.with_sink(Arc::new(sink));
+            info!("Publishing events to Kafka at {}", kafka.brokers);
+        }
+        #[cfg(not(feature = "kafka"))]
+        tracing::warn!(
+            "Kafka sink for {} is configured but the server was built without the kafka feature",
+            kafka.brokers
+        );
+    }
+
+    if let Some(nats) = &config.nats {
+        #[cfg(feature = "nats")]
+        {
+            let sink =
+                matching_engine::nats_sink::NatsEventSink::connect(nats, config.topics.clone())
+                    .await
+                    .map_err(|e| anyhow!(e))?;
+            publisher = publisher.with_sink(Arc::new(sink));
+            info!("Publishing events to NATS at {}", nats.url);
+        }
+        #[cfg(not(feature = "nats"))]
+        tracing::warn!(
+            "NATS sink for {} is configured but the server was built without the nats feature",
+            nats.url
+        );
+    }
+
+    if publisher.is_empty() {
+        return Err(anyhow!(
+            "Event publishing is enabled but no sink is available"
+        ));
+    }
+    Ok(publisher)
+}
+
+/// 以 HTTPS/WSS 提供服务，证书文件变化时按配置自动重载
+async fn serve_tls(
+    listener: TcpListener,
+    app: Router,
+    config: &AppConfig,
+    on_shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    let tls = &config.server.tls;
+    let rustls = load_rustls_config(tls).map_err(|e| anyhow!("Failed to load TLS: {}", e))?;
+    start_cert_reloader(rustls.clone(), tls.clone());
+    info!(
+        "TLS enabled with certificate {}{}",
+        tls.cert_path,
+        if tls.client_ca_path.is_some() {
+            ", client certificates required"
+        } else {
+            ""
+        }
+    );
+
+    let handle = axum_server::Handle::new();
+    let drain_timeout = Duration::from_secs(config.engine.shutdown.drain_timeout_seconds);
+    tokio::spawn({
+        let handle = handle.clone();
+        async move {
+            on_shutdown.await;
+            handle.graceful_shutdown(Some(drain_timeout));
+        }
+    });
+
+    axum_server::from_tcp_rustls(listener.into_std()?, rustls)
+        .handle(handle)
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .await?;
+    Ok(())
+}
+
+/// 把路由挂载到配置的路径前缀下，前缀为空时直接合并到根路由
+fn mount(app: Router, prefix: &str, router: Router) -> Router {
+    let prefix = prefix.trim_matches('/');
+    if prefix.is_empty() {
+        app.merge(router)
+    } else {
+        app.nest(&format!("/{}", prefix), router)
+    }
+}
+
+/// 等待 SIGINT 或 SIGTERM
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for SIGINT: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
@@ -0,0 +1,203 @@
+//! 交易时段
+//!
+//! 按交易对的时段日历计算当前所处阶段（开盘前集合竞价、连续交易、休市）和下一次开盘、收盘时间。
+//! 时段为本地时间，每个交易日的集合竞价、开盘和收盘都在同一天内；周末休市的交易对周六、周日没有时段。
+use crate::config::{SessionConfig, TradingSession};
+use crate::types::*;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// 向后查找时段的最大天数，覆盖周末和跨时区的日期偏移
+const LOOKAHEAD_DAYS: i64 = 8;
+
+/// 交易时段阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionPhase {
+    /// 开盘前集合竞价
+    PreOpen,
+    /// 连续交易
+    Open,
+    /// 休市
+    Closed,
+}
+
+/// 交易对的交易时段和当前阶段
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionInfo {
+    pub symbol: Symbol,
+    pub session: TradingSession,
+    pub phase: SessionPhase,
+    /// 下一次集合竞价开始时间，没有集合竞价时为空
+    pub next_pre_open: Option<DateTime<Utc>>,
+    pub next_open: Option<DateTime<Utc>>,
+    pub next_close: Option<DateTime<Utc>>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 单个交易日的时段边界（UTC）
+struct SessionDay {
+    pre_open: DateTime<Utc>,
+    open: DateTime<Utc>,
+    close: DateTime<Utc>,
+}
+
+/// 按配置计算各交易对的交易时段
+pub struct SessionCalendar {
+    config: SessionConfig,
+}
+
+impl SessionCalendar {
+    pub fn new(config: SessionConfig) -> Self {
+        Self { config }
+    }
+
+    /// 单独设置了时段的交易对
+    pub fn configured_symbols(&self) -> Vec<Symbol> {
+        self.config
+            .symbols
+            .keys()
+            .filter_map(|symbol| Symbol::register(symbol).ok())
+            .collect()
+    }
+
+    /// 交易对适用的时段，没有默认时段且未单独设置时为空
+    pub fn session_for(&self, symbol: &Symbol) -> Option<&TradingSession> {
+        self.config.session_for(symbol)
+    }
+
+    /// 交易对在 `now` 所处的阶段，没有时段的交易对为空
+    pub fn phase(&self, symbol: &Symbol, now: DateTime<Utc>) -> Option<SessionPhase> {
+        let session = self.session_for(symbol)?;
+        let phase = days_around(session, now)
+            .find(|day| day.pre_open <= now && now < day.close)
+            .map_or(SessionPhase::Closed, |day| {
+                if now < day.open {
+                    SessionPhase::PreOpen
+                } else {
+                    SessionPhase::Open
+                }
+            });
+        Some(phase)
+    }
+
+    /// 交易对 `now` 之后（不含）最近的阶段切换时间
+    pub fn next_transition(&self, symbol: &Symbol, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let session = self.session_for(symbol)?;
+        days_around(session, now)
+            .flat_map(|day| [day.pre_open, day.open, day.close])
+            .find(|boundary| *boundary > now)
+    }
+
+    /// 交易对的时段、当前阶段和下一次集合竞价、开盘、收盘时间
+    pub fn info(&self, symbol: &Symbol, now: DateTime<Utc>) -> Option<SessionInfo> {
+        let session = self.session_for(symbol)?;
+        let upcoming = |boundary: fn(&SessionDay) -> DateTime<Utc>| {
+            days_around(session, now)
+                .map(|day| boundary(&day))
+                .find(|time| *time > now)
+        };
+        Some(SessionInfo {
+            symbol: *symbol,
+            session: session.clone(),
+            phase: self.phase(symbol, now)?,
+            next_pre_open: (session.pre_open_minutes > 0)
+                .then(|| upcoming(|day| day.pre_open))
+                .flatten(),
+            next_open: upcoming(|day| day.open),
+            next_close: upcoming(|day| day.close),
+            timestamp: now,
+        })
+    }
+}
+
+/// 从 `now` 的前一个本地日期开始按时间顺序列出交易日的时段边界
+fn days_around(
+    session: &TradingSession,
+    now: DateTime<Utc>,
+) -> impl Iterator<Item = SessionDay> + '_ {
+    let offset = Duration::minutes(session.utc_offset_minutes as i64);
+    let today = (now + offset).date_naive();
+    (-1..=LOOKAHEAD_DAYS)
+        .filter_map(move |days| today.checked_add_signed(Duration::days(days)))
+        .filter(|date| {
+            !(session.weekend_closed && matches!(date.weekday(), Weekday::Sat | Weekday::Sun))
+        })
+        .map(move |date| session_day(session, date, offset))
+}
+
+fn session_day(session: &TradingSession, date: NaiveDate, offset: Duration) -> SessionDay {
+    let at = |time| date.and_time(time).and_utc() - offset;
+    let open = at(session.open);
+    SessionDay {
+        pre_open: open - Duration::minutes(session.pre_open_minutes as i64),
+        open,
+        close: at(session.close),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveTime, TimeZone};
+    use std::collections::HashMap;
+
+    fn calendar() -> SessionCalendar {
+        let session = TradingSession {
+            open: NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+            close: NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+            pre_open_minutes: 30,
+            weekend_closed: true,
+            utc_offset_minutes: 60,
+            queue_when_closed: false,
+        };
+        SessionCalendar::new(SessionConfig {
+            enabled: true,
+            default: None,
+            symbols: HashMap::from([("BTCUSDT".to_string(), session)]),
+        })
+    }
+
+    #[test]
+    fn test_phase_follows_local_schedule() {
+        let calendar = calendar();
+        let btc = Symbol::new("BTC", "USDT");
+        // 2024-01-05 是周五，本地时间 = UTC + 1 小时
+        let utc = |day, hour, minute| Utc.with_ymd_and_hms(2024, 1, day, hour, minute, 0).unwrap();
+
+        assert_eq!(
+            calendar.phase(&btc, utc(5, 7, 59)),
+            Some(SessionPhase::Closed)
+        );
+        assert_eq!(
+            calendar.phase(&btc, utc(5, 8, 0)),
+            Some(SessionPhase::PreOpen)
+        );
+        assert_eq!(
+            calendar.phase(&btc, utc(5, 8, 30)),
+            Some(SessionPhase::Open)
+        );
+        assert_eq!(
+            calendar.phase(&btc, utc(5, 15, 0)),
+            Some(SessionPhase::Closed)
+        );
+        assert_eq!(
+            calendar.phase(&btc, utc(6, 10, 0)),
+            Some(SessionPhase::Closed)
+        );
+        assert!(calendar
+            .phase(&Symbol::new("ETH", "USDT"), utc(5, 10, 0))
+            .is_none());
+
+        assert_eq!(
+            calendar.next_transition(&btc, utc(5, 8, 0)),
+            Some(utc(5, 8, 30))
+        );
+        // 周五收盘后下一次是周一的集合竞价
+        let info = calendar.info(&btc, utc(5, 15, 0)).unwrap();
+        assert_eq!(info.next_pre_open, Some(utc(8, 8, 0)));
+        assert_eq!(info.next_open, Some(utc(8, 8, 30)));
+        assert_eq!(info.next_close, Some(utc(8, 15, 0)));
+    }
+}
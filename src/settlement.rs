@@ -0,0 +1,483 @@
+//! 日终结算
+//!
+//! 每天在配置的截止时间（本地时间）生成上一交易日的结算报表。成交笔数、成交额和手续费按成交时间统计
+//! 截止前 24 小时内的成交，与报表实际生成的时间无关；余额、持仓和未平仓量为截止后生成报表时的状态。
+//! 报表分场所级和用户级两部分，以 `report.json`、`symbols.csv` 和 `users.csv` 写入报表目录下
+//! 以交易日（`YYYY-MM-DD`）命名的子目录。
+use crate::config::EndOfDayConfig;
+use crate::symbol::interning;
+use crate::types::*;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use tracing::info;
+use utoipa::ToSchema;
+
+/// 完整报表文件名
+const REPORT_FILE: &str = "report.json";
+/// 按交易对的场所级 CSV 文件名
+pub const SYMBOLS_CSV: &str = "symbols.csv";
+/// 按用户和资产的 CSV 文件名
+pub const USERS_CSV: &str = "users.csv";
+
+/// 一种资产的手续费和返佣
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AssetFees {
+    pub asset: String,
+    /// 收取的手续费
+    pub fees: f64,
+    /// 支付的 maker 返佣
+    pub rebates: f64,
+}
+
+/// 交易对的日终结算
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SymbolSettlement {
+    pub symbol: Symbol,
+    pub trades: u64,
+    /// 基础货币成交量
+    pub volume: f64,
+    /// 计价货币成交额
+    pub quote_volume: f64,
+    /// 结算价：有标记价格时为标记价格，否则为最新成交价
+    pub settlement_price: Option<f64>,
+    pub open_interest: f64,
+}
+
+/// 场所级结算汇总
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VenueSettlement {
+    pub symbols: Vec<SymbolSettlement>,
+    pub total_trades: u64,
+    pub fees: Vec<AssetFees>,
+    /// 当日有成交、余额或持仓的用户数
+    pub users: usize,
+}
+
+/// 用户的日终结算
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserSettlement {
+    pub user_id: String,
+    /// 当日参与的成交笔数
+    pub trades: u64,
+    /// 计价货币 -> 当日成交额
+    pub volumes: BTreeMap<String, f64>,
+    pub fees: Vec<AssetFees>,
+    pub balances: Vec<Balance>,
+    pub positions: Vec<Position>,
+}
+
+/// 一个交易日的结算报表
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SettlementReport {
+    #[schema(value_type = String)]
+    pub trading_day: NaiveDate,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub venue: VenueSettlement,
+    pub users: Vec<UserSettlement>,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// 生成报表时冻结的引擎状态
+#[derive(Debug, Default)]
+pub struct SettlementSnapshot {
+    /// 交易日内的成交
+    pub trades: Vec<Trade>,
+    /// 交易日内成交的用户成交记录：(用户ID, 成交记录)
+    pub fills: Vec<(String, Fill)>,
+    pub balances: Vec<Balance>,
+    pub positions: Vec<Position>,
+    /// 交易对 -> (结算价, 未平仓量)
+    pub symbols: HashMap<Symbol, (Option<f64>, f64)>,
+}
+
+/// 日终结算调度和报表存储
+pub struct EndOfDay {
+    config: EndOfDayConfig,
+    dir: PathBuf,
+}
+
+impl EndOfDay {
+    /// 创建报表目录
+    pub fn open(config: EndOfDayConfig) -> Result<Self, String> {
+        let dir = PathBuf::from(&config.report_dir);
+        fs::create_dir_all(&dir).map_err(|e| {
+            format!(
+                "Failed to create settlement report dir {}: {}",
+                dir.display(),
+                e
+            )
+        })?;
+        Ok(Self { config, dir })
+    }
+
+    /// `now` 之后（不含）的下一个结算截止时间
+    pub fn next_cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let offset = Duration::minutes(self.config.utc_offset_minutes as i64);
+        let today = (now + offset).date_naive();
+        let cutoff = today.and_time(self.config.cutoff).and_utc() - offset;
+        if cutoff > now {
+            cutoff
+        } else {
+            cutoff + Duration::days(1)
+        }
+    }
+
+    /// 截止时间所结算的交易日：截止前最后一刻的本地日期
+    pub fn trading_day(&self, cutoff: DateTime<Utc>) -> NaiveDate {
+        let offset = Duration::minutes(self.config.utc_offset_minutes as i64);
+        (cutoff + offset - Duration::nanoseconds(1)).date_naive()
+    }
+
+    /// 按快照生成交易日的结算报表
+    pub fn build_report(
+        &self,
+        cutoff: DateTime<Utc>,
+        snapshot: SettlementSnapshot,
+        generated_at: DateTime<Utc>,
+    ) -> SettlementReport {
+        let mut symbols: BTreeMap<String, SymbolSettlement> = BTreeMap::new();
+        let mut users: BTreeMap<String, UserSettlement> = BTreeMap::new();
+
+        for trade in &snapshot.trades {
+            let symbol = symbols
+                .entry(trade.symbol.to_string())
+                .or_insert_with(|| symbol_settlement(trade.symbol, &snapshot.symbols));
+            let notional = trade.quantity * trade.price;
+            symbol.trades += 1;
+            symbol.volume += trade.quantity;
+            symbol.quote_volume += notional;
+            for user_id in [&trade.buyer_id, &trade.seller_id] {
+                let user = user_settlement(&mut users, user_id);
+                user.trades += 1;
+                *user
+                    .volumes
+                    .entry(trade.symbol.quote().to_string())
+                    .or_default() += notional;
+            }
+        }
+        let mut venue_fees = Vec::new();
+        for (user_id, fill) in &snapshot.fills {
+            add_fee(&mut user_settlement(&mut users, user_id).fees, fill);
+            add_fee(&mut venue_fees, fill);
+        }
+        for balance in snapshot.balances {
+            user_settlement(&mut users, &balance.user_id)
+                .balances
+                .push(balance);
+        }
+        for position in snapshot.positions {
+            symbols
+                .entry(position.symbol.to_string())
+                .or_insert_with(|| symbol_settlement(position.symbol, &snapshot.symbols));
+            user_settlement(&mut users, &position.user_id)
+                .positions
+                .push(position);
+        }
+
+        let symbols: Vec<SymbolSettlement> = symbols.into_values().collect();
+        let users: Vec<UserSettlement> = users.into_values().collect();
+        SettlementReport {
+            trading_day: self.trading_day(cutoff),
+            period_start: cutoff - Duration::days(1),
+            period_end: cutoff,
+            venue: VenueSettlement {
+                total_trades: snapshot.trades.len() as u64,
+                symbols,
+                fees: venue_fees,
+                users: users.len(),
+            },
+            users,
+            generated_at,
+        }
+    }
+
+    /// 写入报表，同一交易日已有的报表被覆盖
+    pub fn save(&self, report: &SettlementReport) -> Result<(), String> {
+        let dir = self.day_dir(report.trading_day);
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        let json = serde_json::to_vec_pretty(report)
+            .map_err(|e| format!("Failed to serialize settlement report: {}", e))?;
+        for (name, content) in [
+            (REPORT_FILE, json),
+            (SYMBOLS_CSV, symbols_csv(report).into_bytes()),
+            (USERS_CSV, users_csv(report).into_bytes()),
+        ] {
+            let path = dir.join(name);
+            fs::write(&path, content)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+        info!(
+            "Saved settlement report for {} to {}",
+            report.trading_day,
+            dir.display()
+        );
+        Ok(())
+    }
+
+    /// 读取交易日的报表，不存在时为空
+    pub fn load(&self, trading_day: NaiveDate) -> Result<Option<SettlementReport>, String> {
+        let Some(content) = self.read(trading_day, REPORT_FILE)? else {
+            return Ok(None);
+        };
+        interning(|| serde_json::from_str(&content))
+            .map(Some)
+            .map_err(|e| format!("Invalid settlement report for {}: {}", trading_day, e))
+    }
+
+    /// 读取交易日报表的 CSV 文件（`symbols.csv` 或 `users.csv`），不存在时为空
+    pub fn load_csv(&self, trading_day: NaiveDate, name: &str) -> Result<Option<String>, String> {
+        self.read(trading_day, name)
+    }
+
+    /// 已生成报表的交易日，最新的在前
+    pub fn trading_days(&self) -> Result<Vec<NaiveDate>, String> {
+        let entries = fs::read_dir(&self.dir)
+            .map_err(|e| format!("Failed to read {}: {}", self.dir.display(), e))?;
+        let days: BTreeSet<NaiveDate> = entries
+            .flatten()
+            .filter(|entry| entry.path().join(REPORT_FILE).is_file())
+            .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+            .collect();
+        Ok(days.into_iter().rev().collect())
+    }
+
+    fn read(&self, trading_day: NaiveDate, name: &str) -> Result<Option<String>, String> {
+        let path = self.day_dir(trading_day).join(name);
+        match fs::read_to_string(&path) {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    fn day_dir(&self, trading_day: NaiveDate) -> PathBuf {
+        self.dir.join(trading_day.to_string())
+    }
+}
+
+fn user_settlement<'a>(
+    users: &'a mut BTreeMap<String, UserSettlement>,
+    user_id: &str,
+) -> &'a mut UserSettlement {
+    users
+        .entry(user_id.to_string())
+        .or_insert_with(|| UserSettlement {
+            user_id: user_id.to_string(),
+            trades: 0,
+            volumes: BTreeMap::new(),
+            fees: Vec::new(),
+            balances: Vec::new(),
+            positions: Vec::new(),
+        })
+}
+
+fn symbol_settlement(
+    symbol: Symbol,
+    prices: &HashMap<Symbol, (Option<f64>, f64)>,
+) -> SymbolSettlement {
+    let (settlement_price, open_interest) = prices.get(&symbol).copied().unwrap_or_default();
+    SymbolSettlement {
+        symbol,
+        trades: 0,
+        volume: 0.0,
+        quote_volume: 0.0,
+        settlement_price,
+        open_interest,
+    }
+}
+
+/// 把成交记录的手续费计入对应资产，负数手续费计为返佣
+fn add_fee(fees: &mut Vec<AssetFees>, fill: &Fill) {
+    if fill.fee == 0.0 {
+        return;
+    }
+    let index = match fees.iter().position(|fees| fees.asset == fill.fee_asset) {
+        Some(index) => index,
+        None => {
+            fees.push(AssetFees {
+                asset: fill.fee_asset.clone(),
+                ..Default::default()
+            });
+            fees.len() - 1
+        }
+    };
+    if fill.fee > 0.0 {
+        fees[index].fees += fill.fee;
+    } else {
+        fees[index].rebates -= fill.fee;
+    }
+}
+
+fn symbols_csv(report: &SettlementReport) -> String {
+    let mut csv = String::from(
+        "trading_day,symbol,trades,volume,quote_volume,settlement_price,open_interest\n",
+    );
+    for symbol in &report.venue.symbols {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{}",
+            report.trading_day,
+            symbol.symbol,
+            symbol.trades,
+            symbol.volume,
+            symbol.quote_volume,
+            symbol
+                .settlement_price
+                .map(|price| price.to_string())
+                .unwrap_or_default(),
+            symbol.open_interest
+        );
+    }
+    csv
+}
+
+/// 每个用户每种资产一行：成交额、手续费、返佣和余额
+fn users_csv(report: &SettlementReport) -> String {
+    let mut csv = String::from("trading_day,user_id,asset,trades,volume,fees,rebates,balance\n");
+    for user in &report.users {
+        let assets: BTreeSet<&str> = user
+            .volumes
+            .keys()
+            .map(String::as_str)
+            .chain(user.fees.iter().map(|fees| fees.asset.as_str()))
+            .chain(user.balances.iter().map(|balance| balance.asset.as_str()))
+            .collect();
+        for asset in assets {
+            let fees = user.fees.iter().find(|fees| fees.asset == asset);
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{}",
+                report.trading_day,
+                csv_field(&user.user_id),
+                asset,
+                user.trades,
+                user.volumes.get(asset).copied().unwrap_or(0.0),
+                fees.map_or(0.0, |fees| fees.fees),
+                fees.map_or(0.0, |fees| fees.rebates),
+                user.balances
+                    .iter()
+                    .find(|balance| balance.asset == asset)
+                    .map(|balance| balance.available.to_string())
+                    .unwrap_or_default()
+            );
+        }
+    }
+    csv
+}
+
+/// 含逗号、引号或换行的字段加引号
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveTime, TimeZone};
+
+    #[test]
+    fn test_report_is_saved_per_trading_day() {
+        let dir = std::env::temp_dir().join(format!("settlement-{}", uuid::Uuid::new_v4()));
+        let end_of_day = EndOfDay::open(EndOfDayConfig {
+            enabled: true,
+            cutoff: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            utc_offset_minutes: -300,
+            report_dir: dir.to_string_lossy().into_owned(),
+        })
+        .unwrap();
+
+        // 本地时间 17:00 = UTC 22:00
+        let now = Utc.with_ymd_and_hms(2024, 1, 5, 23, 0, 0).unwrap();
+        let cutoff = end_of_day.next_cutoff(now);
+        assert_eq!(cutoff, Utc.with_ymd_and_hms(2024, 1, 6, 22, 0, 0).unwrap());
+        let trading_day = end_of_day.trading_day(cutoff);
+        assert_eq!(trading_day, NaiveDate::from_ymd_opt(2024, 1, 6).unwrap());
+
+        let symbol = Symbol::new("BTC", "USDT");
+        let order = |side, user: &str| {
+            Order::new(
+                symbol,
+                side,
+                OrderType::Limit,
+                2.0,
+                Some(100.0),
+                user.to_string(),
+            )
+        };
+        let mut trade = Trade::new(
+            symbol,
+            &order(OrderSide::Buy, "bob"),
+            &order(OrderSide::Sell, "alice"),
+            2.0,
+            100.0,
+        );
+        trade.timestamp = cutoff - Duration::hours(1);
+        let fill = |fee: f64| Fill {
+            trade_id: trade.id,
+            order_id: 1,
+            symbol,
+            side: OrderSide::Buy,
+            price: 100.0,
+            quantity: 2.0,
+            role: LiquidityRole::Taker,
+            fee,
+            fee_asset: "USDT".to_string(),
+            timestamp: trade.timestamp,
+            tag: None,
+        };
+        let snapshot = SettlementSnapshot {
+            fills: vec![
+                ("bob".to_string(), fill(0.2)),
+                ("alice".to_string(), fill(-0.02)),
+            ],
+            trades: vec![trade],
+            balances: vec![Balance {
+                user_id: "carol".to_string(),
+                asset: "USDT".to_string(),
+                available: 50.0,
+            }],
+            positions: Vec::new(),
+            symbols: HashMap::from([(symbol, (Some(101.0), 0.0))]),
+        };
+        let report = end_of_day.build_report(cutoff, snapshot, cutoff);
+        assert_eq!(report.venue.total_trades, 1);
+        assert_eq!(report.venue.symbols[0].quote_volume, 200.0);
+        assert_eq!(report.venue.symbols[0].settlement_price, Some(101.0));
+        assert_eq!(report.venue.users, 3);
+        assert_eq!(
+            report.venue.fees,
+            vec![AssetFees {
+                asset: "USDT".to_string(),
+                fees: 0.2,
+                rebates: 0.02,
+            }]
+        );
+
+        end_of_day.save(&report).unwrap();
+        assert_eq!(end_of_day.trading_days().unwrap(), vec![trading_day]);
+        let loaded = end_of_day.load(trading_day).unwrap().unwrap();
+        assert_eq!(loaded.users.len(), 3);
+        let users = end_of_day
+            .load_csv(trading_day, USERS_CSV)
+            .unwrap()
+            .unwrap();
+        assert!(users.contains("2024-01-06,bob,USDT,1,200,0.2,0,"));
+        assert!(users.contains("2024-01-06,carol,USDT,0,0,0,0,50"));
+        assert!(end_of_day
+            .load(trading_day.pred_opt().unwrap())
+            .unwrap()
+            .is_none());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
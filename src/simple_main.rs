@@ -1,10 +1,11 @@
 use anyhow::Result;
 use axum::{
     extract::{
-        ws::{Message, WebSocket, WebSocketUpgrade},
+        rejection::JsonRejection,
+        ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade},
         Path, State,
     },
-    http::StatusCode,
+    middleware,
     response::Json,
     routing::{get, post},
     Router,
@@ -13,26 +14,38 @@ use chrono::Utc;
 use futures_util::{sink::SinkExt, stream::StreamExt};
 use serde_json::json;
 use std::sync::Arc;
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, watch};
+use tower_http::trace::TraceLayer;
 use tracing::{error, info};
 
-use crate::matching_engine::MatchingEngine;
+use matching_engine::api::{parse_symbol, ApiError};
+use matching_engine::config::MonitoringConfig;
+use matching_engine::monitoring::MonitoringManager;
+use matching_engine::request_id::propagate_request_id;
+use matching_engine::telemetry::make_request_span;
+use matching_engine::MatchingEngine;
+
+use crate::server::shutdown_signal;
 
 /// 简化的 API 状态
 #[derive(Clone)]
 pub struct SimpleApiState {
     pub engine: Arc<MatchingEngine>,
     pub trade_sender: broadcast::Sender<String>,
+    /// 停机信号，变为 true 时 WebSocket 连接发送关闭帧
+    pub shutdown: watch::Receiver<bool>,
 }
 
 /// 创建简化的路由
 pub fn create_simple_router(
     engine: Arc<MatchingEngine>,
     trade_sender: broadcast::Sender<String>,
+    shutdown: watch::Receiver<bool>,
 ) -> Router {
     let state = SimpleApiState {
         engine,
         trade_sender,
+        shutdown,
     };
 
     Router::new()
@@ -44,29 +57,29 @@ pub fn create_simple_router(
         .route("/orderbook/:symbol", get(get_orderbook))
         .route("/trades/:symbol", get(get_trades))
         .route("/market_data/:symbol", get(get_market_data))
+        .layer(TraceLayer::new_for_http().make_span_with(make_request_span))
+        .layer(middleware::from_fn(propagate_request_id))
         .with_state(state)
 }
 
 /// 健康检查
-async fn health_check(
-    State(state): State<SimpleApiState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+async fn health_check(State(state): State<SimpleApiState>) -> Json<serde_json::Value> {
     let stats = state.engine.get_stats();
 
-    Ok(Json(json!({
+    Json(json!({
         "status": "healthy",
         "uptime_seconds": stats.uptime_seconds,
         "total_orders": stats.total_orders,
         "total_trades": stats.total_trades,
         "active_orders": stats.active_orders
-    })))
+    }))
 }
 
 /// 获取引擎统计信息
 async fn get_engine_stats(
     State(state): State<SimpleApiState>,
-) -> Result<Json<crate::types::EngineStats>, StatusCode> {
-    Ok(Json(state.engine.get_stats()))
+) -> Json<matching_engine::EngineStats> {
+    Json(state.engine.get_stats())
 }
 
 /// WebSocket处理器
@@ -80,6 +93,7 @@ async fn websocket_handler(
 /// WebSocket连接处理
 async fn websocket_connection(socket: WebSocket, state: SimpleApiState) {
     let mut rx = state.trade_sender.subscribe();
+    let mut shutdown = state.shutdown.clone();
 
     let (mut sender, mut receiver) = socket.split();
 
@@ -94,12 +108,27 @@ async fn websocket_connection(socket: WebSocket, state: SimpleApiState) {
         ))
         .await;
 
-    // 监听广播消息
+    // 监听广播消息，停机时发送关闭帧
     tokio::spawn(async move {
-        while let Ok(msg) = rx.recv().await {
-            if let Err(e) = sender.send(Message::Text(msg)).await {
-                error!("WebSocket发送失败: {}", e);
-                break;
+        loop {
+            tokio::select! {
+                msg = rx.recv() => {
+                    let Ok(msg) = msg else { break };
+                    if let Err(e) = sender.send(Message::Text(msg)).await {
+                        error!("WebSocket发送失败: {}", e);
+                        break;
+                    }
+                }
+                // wait_for 返回的 Ref 不是 Send，在分支内丢弃
+                _ = async { drop(shutdown.wait_for(|&stopping| stopping).await) } => {
+                    let _ = sender
+                        .send(Message::Close(Some(CloseFrame {
+                            code: close_code::AWAY,
+                            reason: "Server shutting down".into(),
+                        })))
+                        .await;
+                    break;
+                }
             }
         }
     });
@@ -127,13 +156,16 @@ async fn websocket_connection(socket: WebSocket, state: SimpleApiState) {
 /// 提交订单处理器
 async fn submit_order_handler(
     State(state): State<SimpleApiState>,
-    Json(_order_data): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+    order_data: Result<Json<serde_json::Value>, JsonRejection>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let Json(_order_data) =
+        order_data.map_err(|e| ApiError::new(e.status(), "invalid_request_body", e.body_text()))?;
+
     // 创建测试订单
-    let order = crate::types::Order::new(
-        crate::types::Symbol::new("BTC", "USDT"),
-        crate::types::OrderSide::Buy,
-        crate::types::OrderType::Limit,
+    let order = matching_engine::Order::new(
+        matching_engine::Symbol::new("BTC", "USDT"),
+        matching_engine::OrderSide::Buy,
+        matching_engine::OrderType::Limit,
         1.0,
         Some(45000.0),
         "test_user".to_string(),
@@ -156,10 +188,7 @@ async fn submit_order_handler(
         }
         Err(e) => {
             error!("订单提交失败: {}", e);
-            Ok(Json(json!({
-                "success": false,
-                "error": e
-            })))
+            Err(ApiError::bad_request("order_rejected", e))
         }
     }
 }
@@ -168,9 +197,10 @@ async fn submit_order_handler(
 async fn get_orderbook(
     Path(symbol): Path<String>,
     State(_state): State<SimpleApiState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let symbol = parse_symbol(&symbol)?;
     // 生成模拟订单簿数据
-    let mock_orderbook = generate_mock_orderbook(&symbol);
+    let mock_orderbook = generate_mock_orderbook(&symbol.to_string());
     Ok(Json(mock_orderbook))
 }
 
@@ -178,9 +208,10 @@ async fn get_orderbook(
 async fn get_trades(
     Path(symbol): Path<String>,
     State(_state): State<SimpleApiState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let symbol = parse_symbol(&symbol)?;
     // 生成模拟交易数据
-    let mock_trades = generate_mock_trades(&symbol);
+    let mock_trades = generate_mock_trades(&symbol.to_string());
     Ok(Json(mock_trades))
 }
 
@@ -188,18 +219,19 @@ async fn get_trades(
 async fn get_user_orders(
     Path(user_id): Path<String>,
     State(_state): State<SimpleApiState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Json<serde_json::Value> {
     let mock_orders = generate_mock_user_orders(&user_id);
-    Ok(Json(mock_orders))
+    Json(mock_orders)
 }
 
 /// 获取市场数据
 async fn get_market_data(
     Path(symbol): Path<String>,
     State(_state): State<SimpleApiState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let symbol = parse_symbol(&symbol)?;
     // 生成模拟市场数据
-    let mock_market_data = generate_mock_market_data(&symbol);
+    let mock_market_data = generate_mock_market_data(&symbol.to_string());
     Ok(Json(mock_market_data))
 }
 
@@ -279,8 +311,9 @@ fn generate_mock_market_data(symbol: &str) -> serde_json::Value {
 
 /// 简化的主函数
 pub async fn run_simple_server() -> Result<()> {
-    // 初始化简单的日志
-    tracing_subscriber::fmt::init();
+    // 初始化日志，句柄需保留到进程退出
+    let _logging = matching_engine::logging::init_logging("info", None)
+        .map_err(|e| anyhow::anyhow!("Failed to initialize logging: {}", e))?;
 
     info!(
         "Starting Simple Matching Engine v{}",
@@ -289,23 +322,55 @@ pub async fn run_simple_server() -> Result<()> {
 
     // 创建撮合引擎
     let engine = Arc::new(MatchingEngine::new());
+    engine.start_expiry_scheduler();
+    engine.start_market_data_publisher();
+    engine.start_order_archiver();
     info!("Matching engine initialized");
 
     // 创建广播通道
     let (trade_sender, _) = broadcast::channel(1000);
     info!("WebSocket broadcast channel created");
 
+    // 单端口部署：Prometheus 指标直接挂载在主 HTTP 服务的 /metrics 上
+    let monitoring = MonitoringManager::new(MonitoringConfig {
+        serve_on_api: true,
+        ..MonitoringConfig::default()
+    })
+    .map_err(|e| anyhow::anyhow!("Failed to initialize monitoring: {}", e))?;
+    monitoring.start_symbol_metrics(Arc::clone(&engine));
+    let monitoring = Arc::new(monitoring);
+
+    // 停机信号
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+
     // 创建路由
-    let app = create_simple_router(engine, trade_sender);
+    let app = create_simple_router(Arc::clone(&engine), trade_sender, shutdown_rx)
+        .merge(monitoring.metrics_router());
 
     // 启动服务器
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8888").await?;
     info!("Server listening on 0.0.0.0:8888");
     info!("WebSocket endpoint: ws://localhost:8888/ws");
-
-    // 启动服务器
-    axum::serve(listener, app).await?;
-
+    info!("Metrics endpoint: http://localhost:8888/metrics");
+
+    // 启动服务器，收到 SIGINT/SIGTERM 后停止接单、关闭 WebSocket 并等待进行中的请求完成
+    let draining_engine = Arc::clone(&engine);
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            info!("Shutdown signal received, draining");
+            draining_engine.begin_shutdown();
+            let _ = shutdown_tx.send(true);
+        })
+        .await?;
+
+    // 等待引擎内进行中的撮合完成，刷新归档和审计，按配置写出快照
+    engine
+        .shutdown()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to shut down matching engine: {}", e))?;
+
+    info!("Server stopped");
     Ok(())
 }
 
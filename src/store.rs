@@ -0,0 +1,749 @@
+use crate::id::{self, OrderId, TradeId};
+use crate::symbol::SymbolId;
+use crate::types::*;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use utoipa::ToSchema;
+
+/// 未指定 `limit` 时每页返回的条数
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+/// 每页最多返回的条数
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// 游标分页结果，按ID从新到旧排列
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// 下一页的游标，作为 `cursor` 参数传入；没有更多数据时为空
+    pub next_cursor: Option<u64>,
+}
+
+/// 分页请求：返回ID小于 `cursor` 的最多 `limit` 条记录
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageRequest {
+    pub cursor: Option<u64>,
+    pub limit: usize,
+}
+
+impl Default for PageRequest {
+    fn default() -> Self {
+        Self {
+            cursor: None,
+            limit: DEFAULT_PAGE_SIZE,
+        }
+    }
+}
+
+/// 订单查询条件，时间范围为 [start_time, end_time)
+#[derive(Debug, Clone, Default)]
+pub struct OrderFilter {
+    pub status: Option<OrderStatus>,
+    pub symbol: Option<Symbol>,
+    pub side: Option<OrderSide>,
+    /// 订单的自定义标签
+    pub tag: Option<String>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+impl OrderFilter {
+    fn matches(&self, order: &Order) -> bool {
+        self.status.is_none_or(|status| order.status == status)
+            && self.symbol.is_none_or(|symbol| order.symbol == symbol)
+            && self.side.is_none_or(|side| order.side == side)
+            && (self.tag.is_none() || order.tag == self.tag)
+            && in_range(order.timestamp, self.start_time, self.end_time)
+    }
+}
+
+/// 成交查询条件，时间范围为 [start_time, end_time)
+#[derive(Debug, Clone, Default)]
+pub struct TradeFilter {
+    pub symbol: Option<Symbol>,
+    /// 主动方方向，见 [`taker_side`]
+    pub side: Option<OrderSide>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+impl TradeFilter {
+    pub(crate) fn matches(&self, trade: &Trade) -> bool {
+        self.symbol.is_none_or(|symbol| trade.symbol == symbol)
+            && self.side.is_none_or(|side| taker_side(trade) == side)
+            && in_range(trade.timestamp, self.start_time, self.end_time)
+    }
+}
+
+/// 成交的主动方方向
+///
+/// 优先使用撮合时记录的主动方；没有记录时（集合竞价成交、旧版本写入的成交）按订单ID推断：
+/// 订单ID随时间单调递增，后到达、吃掉对手挂单的一方ID较大
+pub fn taker_side(trade: &Trade) -> OrderSide {
+    trade
+        .taker_side
+        .unwrap_or(if trade.buy_order_id > trade.sell_order_id {
+            OrderSide::Buy
+        } else {
+            OrderSide::Sell
+        })
+}
+
+/// 成交的主动方订单ID，判定规则同 [`taker_side`]
+pub fn taker_order_id(trade: &Trade) -> OrderId {
+    match trade.taker_side {
+        Some(OrderSide::Buy) => trade.buy_order_id,
+        Some(OrderSide::Sell) => trade.sell_order_id,
+        None => trade.buy_order_id.max(trade.sell_order_id),
+    }
+}
+
+/// 将按ID顺序排列的成交合并为聚合成交
+pub fn aggregate_trades<'a>(trades: impl IntoIterator<Item = &'a Trade>) -> Vec<AggTrade> {
+    let mut aggregated: Vec<AggTrade> = Vec::new();
+    for trade in trades {
+        if let Some(last) = aggregated.last_mut() {
+            if merge_trade(last, trade) {
+                continue;
+            }
+        }
+        aggregated.push(agg_trade(trade));
+    }
+    aggregated
+}
+
+fn agg_trade(trade: &Trade) -> AggTrade {
+    AggTrade {
+        symbol: trade.symbol,
+        price: trade.price,
+        quantity: trade.quantity,
+        first_trade_id: trade.id,
+        last_trade_id: trade.id,
+        taker_order_id: taker_order_id(trade),
+        taker_side: taker_side(trade),
+        timestamp: trade.timestamp,
+    }
+}
+
+/// 成交与聚合成交同一吃单、同一价格时并入，成交可以位于聚合成交的任一端
+fn merge_trade(agg: &mut AggTrade, trade: &Trade) -> bool {
+    if agg.symbol != trade.symbol
+        || agg.taker_order_id != taker_order_id(trade)
+        || agg.price != trade.price
+    {
+        return false;
+    }
+
+    agg.quantity += trade.quantity;
+    if trade.id < agg.first_trade_id {
+        agg.first_trade_id = trade.id;
+        agg.timestamp = trade.timestamp;
+    }
+    agg.last_trade_id = agg.last_trade_id.max(trade.id);
+    true
+}
+
+fn in_range(
+    timestamp: DateTime<Utc>,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> bool {
+    start.is_none_or(|start| timestamp >= start) && end.is_none_or(|end| timestamp < end)
+}
+
+/// 订单存储，按用户维护全部订单和未完成订单的ID索引
+#[derive(Debug, Default)]
+pub struct OrderStore {
+    orders: HashMap<OrderId, Order>,
+    by_user: HashMap<String, BTreeSet<OrderId>>,
+    /// 未进入终态的订单，订单每次写入时按状态维护
+    open_by_user: HashMap<String, BTreeSet<OrderId>>,
+}
+
+impl OrderStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 插入或更新订单，返回旧值
+    pub fn insert(&mut self, order_id: OrderId, order: Order) -> Option<Order> {
+        self.by_user
+            .entry(order.user_id.clone())
+            .or_default()
+            .insert(order_id);
+        if order.status.is_terminal() {
+            remove_indexed(&mut self.open_by_user, &order.user_id, order_id);
+        } else {
+            self.open_by_user
+                .entry(order.user_id.clone())
+                .or_default()
+                .insert(order_id);
+        }
+        self.orders.insert(order_id, order)
+    }
+
+    pub fn get(&self, order_id: &OrderId) -> Option<&Order> {
+        self.orders.get(order_id)
+    }
+
+    pub fn remove(&mut self, order_id: &OrderId) -> Option<Order> {
+        let order = self.orders.remove(order_id)?;
+        remove_indexed(&mut self.by_user, &order.user_id, *order_id);
+        remove_indexed(&mut self.open_by_user, &order.user_id, *order_id);
+        Some(order)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Order> {
+        self.orders.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    /// 用户的全部订单，按ID从旧到新
+    pub fn user_orders<'a>(&'a self, user_id: &str) -> impl Iterator<Item = &'a Order> + 'a {
+        self.by_user
+            .get(user_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|order_id| self.orders.get(order_id))
+    }
+
+    /// 用户的未完成订单，按ID从旧到新，只读取未完成订单索引
+    pub fn open_user_orders<'a>(&'a self, user_id: &str) -> impl Iterator<Item = &'a Order> + 'a {
+        self.open_by_user
+            .get(user_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|order_id| self.orders.get(order_id))
+    }
+
+    /// 全部用户的未完成订单
+    pub fn open_orders(&self) -> impl Iterator<Item = &Order> {
+        self.open_by_user
+            .values()
+            .flatten()
+            .filter_map(|order_id| self.orders.get(order_id))
+    }
+
+    /// 按条件分页查询用户订单
+    pub fn query_user(
+        &self,
+        user_id: &str,
+        filter: &OrderFilter,
+        page: PageRequest,
+    ) -> Page<Order> {
+        let Some(ids) = self.by_user.get(user_id) else {
+            return empty_page();
+        };
+        let Some(range) = id_range(filter.start_time, page.cursor) else {
+            return empty_page();
+        };
+        collect_page(
+            ids.range(range).rev().copied(),
+            page.limit,
+            |order_id| self.orders.get(&order_id),
+            |order| filter.matches(order),
+        )
+    }
+
+    /// 按条件分页查询全部用户的订单
+    pub fn query(&self, filter: &OrderFilter, page: PageRequest) -> Page<Order> {
+        let Some(range) = id_range(filter.start_time, page.cursor) else {
+            return empty_page();
+        };
+        let mut ids: Vec<OrderId> = self
+            .orders
+            .iter()
+            .filter(|(order_id, order)| range.contains(order_id) && filter.matches(order))
+            .map(|(order_id, _)| *order_id)
+            .collect();
+        ids.sort_unstable_by(|a, b| b.cmp(a));
+        collect_page(
+            ids.into_iter(),
+            page.limit,
+            |order_id| self.orders.get(&order_id),
+            |_| true,
+        )
+    }
+}
+
+/// 成交存储，按交易对维护成交ID索引
+#[derive(Debug, Default)]
+pub struct TradeStore {
+    trades: BTreeMap<TradeId, Trade>,
+    by_symbol: HashMap<SymbolId, BTreeSet<TradeId>>,
+}
+
+impl TradeStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, trade: Trade) {
+        self.by_symbol
+            .entry(trade.symbol.id())
+            .or_default()
+            .insert(trade.id);
+        self.trades.insert(trade.id, trade);
+    }
+
+    pub fn get(&self, trade_id: TradeId) -> Option<&Trade> {
+        self.trades.get(&trade_id)
+    }
+
+    /// 移除一笔成交（成交被撤销时）
+    pub fn remove(&mut self, trade_id: TradeId) -> Option<Trade> {
+        let trade = self.trades.remove(&trade_id)?;
+        if let Some(ids) = self.by_symbol.get_mut(&trade.symbol.id()) {
+            ids.remove(&trade_id);
+        }
+        Some(trade)
+    }
+
+    pub fn len(&self) -> usize {
+        self.trades.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trades.is_empty()
+    }
+
+    /// 移除时间早于 `cutoff` 的成交，返回移除的数量
+    pub fn remove_before(&mut self, cutoff: DateTime<Utc>) -> usize {
+        let expired: Vec<TradeId> = self
+            .trades
+            .values()
+            .filter(|trade| trade.timestamp < cutoff)
+            .map(|trade| trade.id)
+            .collect();
+        for trade_id in &expired {
+            self.remove(*trade_id);
+        }
+        expired.len()
+    }
+
+    /// 交易对最近一笔满足条件的成交
+    pub fn last_matching(
+        &self,
+        symbol: &Symbol,
+        predicate: impl Fn(&Trade) -> bool,
+    ) -> Option<&Trade> {
+        self.by_symbol
+            .get(&symbol.id())?
+            .iter()
+            .rev()
+            .filter_map(|trade_id| self.trades.get(trade_id))
+            .find(|trade| predicate(trade))
+    }
+
+    /// 按条件分页查询成交，指定交易对时只遍历该交易对的索引
+    pub fn query(&self, filter: &TradeFilter, page: PageRequest) -> Page<Trade> {
+        let Some(range) = id_range(filter.start_time, page.cursor) else {
+            return empty_page();
+        };
+        collect_page(
+            self.ids_desc(filter.symbol, range),
+            page.limit,
+            |trade_id| self.trades.get(&trade_id),
+            |trade| filter.matches(trade),
+        )
+    }
+
+    /// 按条件分页查询聚合成交，按第一笔成交ID从新到旧
+    ///
+    /// 游标为聚合成交的 `first_trade_id`，一条聚合成交不会被拆到两页
+    pub fn query_agg(&self, filter: &TradeFilter, page: PageRequest) -> Page<AggTrade> {
+        let Some(range) = id_range(filter.start_time, page.cursor) else {
+            return empty_page();
+        };
+
+        let mut items: Vec<AggTrade> = Vec::new();
+        let mut next_cursor = None;
+        let trades = self
+            .ids_desc(filter.symbol, range)
+            .filter_map(|trade_id| self.trades.get(&trade_id))
+            .filter(|trade| filter.matches(trade));
+        for trade in trades {
+            if let Some(last) = items.last_mut() {
+                if merge_trade(last, trade) {
+                    continue;
+                }
+            }
+            if items.len() == page.limit {
+                next_cursor = items.last().map(|last| last.first_trade_id);
+                break;
+            }
+            items.push(agg_trade(trade));
+        }
+
+        Page { items, next_cursor }
+    }
+
+    /// 区间内的成交ID，从新到旧；指定交易对时只遍历该交易对的索引
+    fn ids_desc(
+        &self,
+        symbol: Option<Symbol>,
+        range: std::ops::Range<u64>,
+    ) -> Box<dyn Iterator<Item = TradeId> + '_> {
+        match symbol {
+            Some(symbol) => match self.by_symbol.get(&symbol.id()) {
+                Some(ids) => Box::new(ids.range(range).rev().copied()),
+                None => Box::new(std::iter::empty()),
+            },
+            None => Box::new(
+                self.trades
+                    .range(range)
+                    .rev()
+                    .map(|(trade_id, _)| *trade_id),
+            ),
+        }
+    }
+}
+
+/// 用户成交查询条件，时间范围为 [start_time, end_time)
+#[derive(Debug, Clone, Default)]
+pub struct FillFilter {
+    pub symbol: Option<Symbol>,
+    /// 用户自己的买卖方向
+    pub side: Option<OrderSide>,
+    pub role: Option<LiquidityRole>,
+    /// 成交所属订单的自定义标签
+    pub tag: Option<String>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+impl FillFilter {
+    fn matches(&self, fill: &Fill) -> bool {
+        self.symbol.is_none_or(|symbol| fill.symbol == symbol)
+            && self.side.is_none_or(|side| fill.side == side)
+            && self.role.is_none_or(|role| fill.role == role)
+            && (self.tag.is_none() || fill.tag == self.tag)
+            && in_range(fill.timestamp, self.start_time, self.end_time)
+    }
+}
+
+/// 按用户索引的成交记录，撮合时为成交双方各记录一条
+///
+/// 自成交时同一成交ID下有两条记录，键中带订单ID加以区分
+#[derive(Debug, Default)]
+pub struct FillStore {
+    by_user: HashMap<String, BTreeMap<(TradeId, OrderId), Fill>>,
+    /// 订单ID -> 该订单参与的成交ID
+    by_order: HashMap<OrderId, BTreeSet<TradeId>>,
+}
+
+impl FillStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, user_id: &str, fill: Fill) {
+        self.by_order
+            .entry(fill.order_id)
+            .or_default()
+            .insert(fill.trade_id);
+        self.by_user
+            .entry(user_id.to_string())
+            .or_default()
+            .insert((fill.trade_id, fill.order_id), fill);
+    }
+
+    /// 移除用户的一条成交记录（成交被撤销时）
+    pub fn remove(&mut self, user_id: &str, trade_id: TradeId, order_id: OrderId) -> Option<Fill> {
+        if let Some(trade_ids) = self.by_order.get_mut(&order_id) {
+            trade_ids.remove(&trade_id);
+            if trade_ids.is_empty() {
+                self.by_order.remove(&order_id);
+            }
+        }
+        self.by_user.get_mut(user_id)?.remove(&(trade_id, order_id))
+    }
+
+    /// 用户某个订单的全部成交记录，按成交ID从旧到新
+    pub fn order_fills(&self, user_id: &str, order_id: OrderId) -> Vec<Fill> {
+        let (Some(trade_ids), Some(fills)) =
+            (self.by_order.get(&order_id), self.by_user.get(user_id))
+        else {
+            return Vec::new();
+        };
+        trade_ids
+            .iter()
+            .filter_map(|trade_id| fills.get(&(*trade_id, order_id)))
+            .cloned()
+            .collect()
+    }
+
+    /// 用户在某笔成交中某个订单的成交记录
+    pub fn get(&self, user_id: &str, trade_id: TradeId, order_id: OrderId) -> Option<&Fill> {
+        self.by_user.get(user_id)?.get(&(trade_id, order_id))
+    }
+
+    /// 按条件分页查询用户成交，按成交ID从新到旧
+    ///
+    /// 游标为成交ID，同一成交的记录不会被拆到两页
+    pub fn query_user(&self, user_id: &str, filter: &FillFilter, page: PageRequest) -> Page<Fill> {
+        let Some(fills) = self.by_user.get(user_id) else {
+            return empty_page();
+        };
+        let Some(range) = id_range(filter.start_time, page.cursor) else {
+            return empty_page();
+        };
+
+        let mut items: Vec<Fill> = Vec::new();
+        let mut next_cursor = None;
+        let fills = fills
+            .range((range.start, 0)..(range.end, 0))
+            .rev()
+            .map(|(_, fill)| fill)
+            .filter(|fill| filter.matches(fill));
+        for fill in fills {
+            if items.len() >= page.limit {
+                let last_trade_id = items.last().map(|last| last.trade_id);
+                if last_trade_id != Some(fill.trade_id) {
+                    next_cursor = last_trade_id;
+                    break;
+                }
+            }
+            items.push(fill.clone());
+        }
+
+        Page { items, next_cursor }
+    }
+}
+
+/// 从按用户的ID索引中移除订单，用户没有剩余订单时移除整个条目
+fn remove_indexed(
+    index: &mut HashMap<String, BTreeSet<OrderId>>,
+    user_id: &str,
+    order_id: OrderId,
+) {
+    if let Some(ids) = index.get_mut(user_id) {
+        ids.remove(&order_id);
+        if ids.is_empty() {
+            index.remove(user_id);
+        }
+    }
+}
+
+fn empty_page<T>() -> Page<T> {
+    Page {
+        items: Vec::new(),
+        next_cursor: None,
+    }
+}
+
+/// 由起始时间和游标确定需要遍历的ID区间
+///
+/// ID 与记录时间戳几乎同时生成，早于 `start_time` 的ID可以直接跳过；
+/// 下限留出 1 秒余量，覆盖两者分别取时钟产生的误差
+fn id_range(
+    start_time: Option<DateTime<Utc>>,
+    cursor: Option<u64>,
+) -> Option<std::ops::Range<u64>> {
+    let lower = start_time
+        .map(|start| {
+            let start = start
+                .checked_sub_signed(chrono::Duration::seconds(1))
+                .unwrap_or(start);
+            id::min_id_at(start)
+        })
+        .unwrap_or(0);
+    let upper = cursor.unwrap_or(u64::MAX);
+    (lower < upper).then_some(lower..upper)
+}
+
+/// 从按ID倒序的迭代器中取出一页匹配的记录
+fn collect_page<'a, T: Clone + 'a>(
+    ids: impl Iterator<Item = u64>,
+    limit: usize,
+    lookup: impl Fn(u64) -> Option<&'a T>,
+    matches: impl Fn(&T) -> bool,
+) -> Page<T> {
+    let mut items = Vec::new();
+    let mut last_id = None;
+    let mut next_cursor = None;
+
+    for item_id in ids {
+        let Some(item) = lookup(item_id) else {
+            continue;
+        };
+        if !matches(item) {
+            continue;
+        }
+        if items.len() == limit {
+            next_cursor = last_id;
+            break;
+        }
+        items.push(item.clone());
+        last_id = Some(item_id);
+    }
+
+    Page { items, next_cursor }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(user_id: &str, side: OrderSide) -> Order {
+        Order::new(
+            Symbol::new("BTC", "USDT"),
+            side,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            user_id.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_order_store_pages_user_orders_newest_first() {
+        let mut store = OrderStore::new();
+        let orders: Vec<Order> = (0..5)
+            .map(|i| {
+                let side = if i % 2 == 0 {
+                    OrderSide::Buy
+                } else {
+                    OrderSide::Sell
+                };
+                order("alice", side)
+            })
+            .collect();
+        for order in &orders {
+            store.insert(order.id, order.clone());
+        }
+        let other = order("bob", OrderSide::Buy);
+        store.insert(other.id, other.clone());
+
+        let first = store.query_user(
+            "alice",
+            &OrderFilter::default(),
+            PageRequest {
+                cursor: None,
+                limit: 2,
+            },
+        );
+        let ids: Vec<OrderId> = first.items.iter().map(|order| order.id).collect();
+        assert_eq!(ids, [orders[4].id, orders[3].id]);
+        assert_eq!(first.next_cursor, Some(orders[3].id));
+
+        let rest = store.query_user(
+            "alice",
+            &OrderFilter::default(),
+            PageRequest {
+                cursor: first.next_cursor,
+                limit: 10,
+            },
+        );
+        assert_eq!(rest.items.len(), 3);
+        assert_eq!(rest.next_cursor, None);
+
+        let buys = OrderFilter {
+            side: Some(OrderSide::Buy),
+            ..OrderFilter::default()
+        };
+        let page = store.query_user("alice", &buys, PageRequest::default());
+        assert_eq!(page.items.len(), 3);
+
+        // 删除后索引同步更新
+        store.remove(&other.id);
+        assert_eq!(store.user_orders("bob").count(), 0);
+        assert_eq!(store.len(), 5);
+    }
+
+    #[test]
+    fn test_open_order_index_follows_status() {
+        let mut store = OrderStore::new();
+        let mut resting = order("alice", OrderSide::Buy);
+        let mut cancelled = order("alice", OrderSide::Sell);
+        store.insert(resting.id, resting.clone());
+        store.insert(cancelled.id, cancelled.clone());
+        let open_ids = |store: &OrderStore| -> Vec<OrderId> {
+            store
+                .open_user_orders("alice")
+                .map(|order| order.id)
+                .collect()
+        };
+        assert_eq!(open_ids(&store), [resting.id, cancelled.id]);
+
+        resting.status = OrderStatus::PartiallyFilled;
+        store.insert(resting.id, resting.clone());
+        cancelled.status = OrderStatus::Cancelled;
+        store.insert(cancelled.id, cancelled.clone());
+        assert_eq!(open_ids(&store), [resting.id]);
+        assert_eq!(store.user_orders("alice").count(), 2);
+
+        store.remove(&resting.id);
+        assert!(open_ids(&store).is_empty());
+        assert_eq!(store.open_orders().count(), 0);
+    }
+
+    #[test]
+    fn test_agg_trades_merge_same_taker_and_price() {
+        let symbol = Symbol::new("BTC", "USDT");
+        let sell = |price| {
+            Order::new(
+                symbol,
+                OrderSide::Sell,
+                OrderType::Limit,
+                1.0,
+                Some(price),
+                "maker".to_string(),
+            )
+        };
+        let makers = [sell(100.0), sell(100.0), sell(101.0)];
+        let taker = order("taker", OrderSide::Buy);
+        let late_taker = order("taker", OrderSide::Buy);
+
+        let mut store = TradeStore::new();
+        let trades = [
+            Trade::new(symbol, &taker, &makers[0], 1.0, 100.0),
+            Trade::new(symbol, &taker, &makers[1], 0.5, 100.0),
+            Trade::new(symbol, &taker, &makers[2], 1.0, 101.0),
+            Trade::new(symbol, &late_taker, &makers[2], 0.25, 101.0),
+        ];
+        for trade in &trades {
+            store.push(trade.clone());
+        }
+
+        let aggregated = aggregate_trades(&trades);
+        assert_eq!(aggregated.len(), 3);
+        assert_eq!(aggregated[0].quantity, 1.5);
+        assert_eq!(aggregated[0].first_trade_id, trades[0].id);
+        assert_eq!(aggregated[0].last_trade_id, trades[1].id);
+        assert_eq!(aggregated[0].taker_order_id, taker.id);
+        assert_eq!(aggregated[0].taker_side, OrderSide::Buy);
+
+        // 倒序分页得到相同的聚合结果，聚合成交不会被拆到两页
+        let filter = TradeFilter {
+            symbol: Some(symbol),
+            ..TradeFilter::default()
+        };
+        let first = store.query_agg(
+            &filter,
+            PageRequest {
+                cursor: None,
+                limit: 2,
+            },
+        );
+        assert_eq!(first.items, [aggregated[2].clone(), aggregated[1].clone()]);
+        assert_eq!(first.next_cursor, Some(trades[2].id));
+
+        let rest = store.query_agg(
+            &filter,
+            PageRequest {
+                cursor: first.next_cursor,
+                limit: 2,
+            },
+        );
+        assert_eq!(rest.items, [aggregated[0].clone()]);
+        assert_eq!(rest.next_cursor, None);
+    }
+}
@@ -0,0 +1,686 @@
+//! 成交监控：自成交、对倒和幌骗识别
+//!
+//! 后台任务消费引擎的成交、订单更新和最优报价推送，识别以下可疑模式并生成告警：
+//! - 买卖双方为同一用户，或同属一组配置的关联账户（自成交 / 关联账户对倒）
+//! - 同一对用户在时间窗口内反复互换买卖方向成交（乒乓交易）
+//! - 用户在最优价附近大量挂单后又撤单（分层挂单 / 幌骗）
+//!
+//! 告警只保存在内存中，通过 `GET /admin/surveillance/alerts` 查询，
+//! 并计入 `matching_engine_surveillance_alerts_total{kind}` 指标。
+use crate::api::{FieldError, ValidQuery, Validate};
+use crate::config::SurveillanceConfig;
+use crate::id::{OrderId, TradeId};
+use crate::matching_engine::MatchingEngine;
+use crate::types::*;
+use axum::{extract::State, routing::get, Json, Router};
+use chrono::{DateTime, Duration, Utc};
+use metrics::counter;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{info, warn};
+use utoipa::{IntoParams, ToSchema};
+
+/// 查询告警的默认条数
+const DEFAULT_ALERT_LIMIT: usize = 100;
+/// 查询告警的最大条数
+const MAX_ALERT_LIMIT: usize = 1000;
+/// 清理过期窗口数据的间隔
+const PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// 告警类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    /// 买卖双方为同一用户
+    SelfTrade,
+    /// 买卖双方同属一组关联账户
+    CorrelatedTrade,
+    /// 同一对用户反复互换买卖方向成交
+    PingPong,
+    /// 最优价附近的挂单大部分被撤销
+    Layering,
+}
+
+impl AlertKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SelfTrade => "self_trade",
+            Self::CorrelatedTrade => "correlated_trade",
+            Self::PingPong => "ping_pong",
+            Self::Layering => "layering",
+        }
+    }
+}
+
+/// 监控告警
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SurveillanceAlert {
+    /// 从 1 开始递增的告警ID
+    pub id: u64,
+    pub kind: AlertKind,
+    pub symbol: Symbol,
+    /// 涉及的用户
+    pub user_ids: Vec<String>,
+    /// 触发告警的成交，分层挂单告警为空
+    pub trade_ids: Vec<TradeId>,
+    /// 触发告警的撤单，成交类告警为空
+    pub order_ids: Vec<OrderId>,
+    pub description: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 告警查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AlertQuery {
+    /// 按告警类型过滤
+    pub kind: Option<AlertKind>,
+    /// 按交易对过滤，如 BTCUSDT
+    pub symbol: Option<String>,
+    /// 按涉及的用户过滤
+    pub user_id: Option<String>,
+    /// 返回条数，默认 100
+    pub limit: Option<usize>,
+}
+
+impl Validate for AlertQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if let Some(symbol) = &self.symbol {
+            if symbol.parse::<Symbol>().is_err() {
+                errors.push(FieldError::new("symbol", "unrecognized symbol"));
+            }
+        }
+        if let Some(limit) = self.limit {
+            if !(1..=MAX_ALERT_LIMIT).contains(&limit) {
+                errors.push(FieldError::new(
+                    "limit",
+                    &format!("must be between 1 and {}", MAX_ALERT_LIMIT),
+                ));
+            }
+        }
+        errors
+    }
+}
+
+/// 同一对用户之间的一笔成交
+struct PairTrade {
+    trade_id: TradeId,
+    timestamp: DateTime<Utc>,
+    /// 用户ID较小的一方是否为买方
+    first_is_buyer: bool,
+}
+
+/// 用户在最优价附近的挂单或撤单
+enum Activity {
+    Placed,
+    Cancelled(OrderId),
+}
+
+/// 用户在一个交易对上按时间排列的近盘口挂撤单
+type ActivityWindow = VecDeque<(DateTime<Utc>, Activity)>;
+
+/// 成交监控
+pub struct Surveillance {
+    config: SurveillanceConfig,
+    /// 用户ID -> 所属关联账户组的序号
+    account_groups: HashMap<String, usize>,
+    state: Mutex<SurveillanceState>,
+}
+
+#[derive(Default)]
+struct SurveillanceState {
+    next_alert_id: u64,
+    alerts: VecDeque<SurveillanceAlert>,
+    /// (交易对, 较小用户ID, 较大用户ID) -> 窗口内的成交
+    pairs: HashMap<(Symbol, String, String), VecDeque<PairTrade>>,
+    /// 各交易对最近的最优报价
+    touches: HashMap<Symbol, BookTicker>,
+    /// 挂在最优价附近、尚未进入终态的订单
+    near_touch_orders: HashSet<OrderId>,
+    /// (用户, 交易对) -> 窗口内的近盘口挂单和撤单
+    activity: HashMap<(String, Symbol), ActivityWindow>,
+}
+
+impl Surveillance {
+    pub fn new(config: SurveillanceConfig) -> Self {
+        let account_groups = config
+            .correlated_accounts
+            .iter()
+            .enumerate()
+            .flat_map(|(group, users)| users.iter().map(move |user| (user.clone(), group)))
+            .collect();
+        Self {
+            config,
+            account_groups,
+            state: Mutex::new(SurveillanceState {
+                next_alert_id: 1,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// 订阅引擎的成交、订单更新和最优报价并在后台检测，`shutdown` 完成后停止
+    pub fn start(
+        self: &Arc<Self>,
+        engine: &Arc<MatchingEngine>,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> tokio::task::JoinHandle<()> {
+        let trades = engine.subscribe_trades();
+        let orders = engine.subscribe_orders();
+        let tickers = engine.subscribe_book_ticker();
+        tokio::spawn(Arc::clone(self).run(Arc::clone(engine), trades, orders, tickers, shutdown))
+    }
+
+    async fn run(
+        self: Arc<Self>,
+        engine: Arc<MatchingEngine>,
+        mut trades: broadcast::Receiver<Trade>,
+        mut orders: broadcast::Receiver<Order>,
+        mut tickers: broadcast::Receiver<BookTicker>,
+        shutdown: impl Future<Output = ()>,
+    ) {
+        info!("Trade surveillance started");
+        tokio::pin!(shutdown);
+        let mut prune = tokio::time::interval(PRUNE_INTERVAL);
+
+        loop {
+            tokio::select! {
+                biased;
+                _ = &mut shutdown => break,
+                result = tickers.recv() => match result {
+                    Ok(ticker) => self.on_book_ticker(ticker),
+                    Err(RecvError::Lagged(skipped)) => lagged("book_ticker", skipped),
+                    Err(RecvError::Closed) => break,
+                },
+                result = trades.recv() => match result {
+                    Ok(trade) => self.on_trade(&trade),
+                    Err(RecvError::Lagged(skipped)) => lagged("trades", skipped),
+                    Err(RecvError::Closed) => break,
+                },
+                result = orders.recv() => match result {
+                    Ok(order) => self.on_order(&order, engine.now()),
+                    Err(RecvError::Lagged(skipped)) => lagged("orders", skipped),
+                    Err(RecvError::Closed) => break,
+                },
+                _ = prune.tick() => self.prune(engine.now()),
+            }
+        }
+
+        info!("Trade surveillance stopped");
+    }
+
+    /// 记录交易对最新的最优报价，用于判断挂单是否靠近盘口
+    pub fn on_book_ticker(&self, ticker: BookTicker) {
+        self.state.lock().touches.insert(ticker.symbol, ticker);
+    }
+
+    /// 检查成交双方是否为同一用户、关联账户或在反复对倒
+    pub fn on_trade(&self, trade: &Trade) {
+        let mut state = self.state.lock();
+        if trade.buyer_id == trade.seller_id {
+            self.raise(
+                &mut state,
+                AlertKind::SelfTrade,
+                trade.symbol,
+                vec![trade.buyer_id.clone()],
+                vec![trade.id],
+                Vec::new(),
+                format!(
+                    "User {} traded {} with itself at {}",
+                    trade.buyer_id, trade.quantity, trade.price
+                ),
+                trade.timestamp,
+            );
+            return;
+        }
+
+        if self.are_correlated(&trade.buyer_id, &trade.seller_id) {
+            self.raise(
+                &mut state,
+                AlertKind::CorrelatedTrade,
+                trade.symbol,
+                vec![trade.buyer_id.clone(), trade.seller_id.clone()],
+                vec![trade.id],
+                Vec::new(),
+                format!(
+                    "Correlated accounts {} and {} traded {} at {}",
+                    trade.buyer_id, trade.seller_id, trade.quantity, trade.price
+                ),
+                trade.timestamp,
+            );
+            return;
+        }
+
+        let first_is_buyer = trade.buyer_id < trade.seller_id;
+        let (first, second) = if first_is_buyer {
+            (&trade.buyer_id, &trade.seller_id)
+        } else {
+            (&trade.seller_id, &trade.buyer_id)
+        };
+        let key = (trade.symbol, first.clone(), second.clone());
+        let window_start = trade.timestamp - self.ping_pong_window();
+        let history = state.pairs.entry(key).or_default();
+        while history
+            .front()
+            .is_some_and(|previous| previous.timestamp < window_start)
+        {
+            history.pop_front();
+        }
+        history.push_back(PairTrade {
+            trade_id: trade.id,
+            timestamp: trade.timestamp,
+            first_is_buyer,
+        });
+
+        // 相邻两笔成交方向相反记为一次换手
+        let reversals = history
+            .iter()
+            .zip(history.iter().skip(1))
+            .filter(|(a, b)| a.first_is_buyer != b.first_is_buyer)
+            .count();
+        if reversals < self.config.ping_pong_min_reversals {
+            return;
+        }
+
+        let trade_ids = history
+            .drain(..)
+            .map(|previous| previous.trade_id)
+            .collect();
+        self.raise(
+            &mut state,
+            AlertKind::PingPong,
+            trade.symbol,
+            vec![first.clone(), second.clone()],
+            trade_ids,
+            Vec::new(),
+            format!(
+                "Users {} and {} reversed trade direction {} times within {} ms",
+                first, second, reversals, self.config.ping_pong_window_ms
+            ),
+            trade.timestamp,
+        );
+    }
+
+    /// 跟踪最优价附近的限价挂单，撤单比例超过阈值时告警
+    pub fn on_order(&self, order: &Order, now: DateTime<Utc>) {
+        let mut state = self.state.lock();
+        let key = (order.user_id.clone(), order.symbol);
+        match order.status {
+            // 新挂单或改单后重新挂出
+            OrderStatus::New if order.filled_quantity == 0.0 => {
+                if state.near_touch_orders.contains(&order.id) || !self.is_near_touch(&state, order)
+                {
+                    return;
+                }
+                state.near_touch_orders.insert(order.id);
+                state
+                    .activity
+                    .entry(key)
+                    .or_default()
+                    .push_back((now, Activity::Placed));
+            }
+            OrderStatus::Cancelled => {
+                if !state.near_touch_orders.remove(&order.id) {
+                    return;
+                }
+                let window_start = now - self.layering_window();
+                let activity = state.activity.entry(key).or_default();
+                while activity
+                    .front()
+                    .is_some_and(|(timestamp, _)| *timestamp < window_start)
+                {
+                    activity.pop_front();
+                }
+                activity.push_back((now, Activity::Cancelled(order.id)));
+
+                let placed = activity
+                    .iter()
+                    .filter(|(_, activity)| matches!(activity, Activity::Placed))
+                    .count();
+                let cancelled: Vec<OrderId> = activity
+                    .iter()
+                    .filter_map(|(_, activity)| match activity {
+                        Activity::Cancelled(order_id) => Some(*order_id),
+                        Activity::Placed => None,
+                    })
+                    .collect();
+                if placed < self.config.layering_min_orders
+                    || (cancelled.len() as f64) < placed as f64 * self.config.layering_cancel_ratio
+                {
+                    return;
+                }
+
+                activity.clear();
+                let description = format!(
+                    "User {} cancelled {} of {} orders placed within {} bps of the touch in {} ms",
+                    order.user_id,
+                    cancelled.len(),
+                    placed,
+                    self.config.layering_near_touch_bps,
+                    self.config.layering_window_ms
+                );
+                self.raise(
+                    &mut state,
+                    AlertKind::Layering,
+                    order.symbol,
+                    vec![order.user_id.clone()],
+                    Vec::new(),
+                    cancelled,
+                    description,
+                    now,
+                );
+            }
+            status if status.is_terminal() => {
+                state.near_touch_orders.remove(&order.id);
+            }
+            _ => {}
+        }
+    }
+
+    /// 清理窗口外的成交和挂单记录
+    pub fn prune(&self, now: DateTime<Utc>) {
+        let pair_start = now - self.ping_pong_window();
+        let activity_start = now - self.layering_window();
+        let mut state = self.state.lock();
+        state.pairs.retain(|_, history| {
+            history.retain(|trade| trade.timestamp >= pair_start);
+            !history.is_empty()
+        });
+        state.activity.retain(|_, activity| {
+            activity.retain(|(timestamp, _)| *timestamp >= activity_start);
+            !activity.is_empty()
+        });
+    }
+
+    /// 按条件查询告警，从新到旧
+    pub fn alerts(&self, query: &AlertQuery) -> Vec<SurveillanceAlert> {
+        let symbol = query.symbol.as_deref().and_then(|s| s.parse().ok());
+        self.state
+            .lock()
+            .alerts
+            .iter()
+            .rev()
+            .filter(|alert| query.kind.is_none_or(|kind| alert.kind == kind))
+            .filter(|alert| symbol.is_none_or(|symbol| alert.symbol == symbol))
+            .filter(|alert| {
+                query
+                    .user_id
+                    .as_ref()
+                    .is_none_or(|user_id| alert.user_ids.contains(user_id))
+            })
+            .take(query.limit.unwrap_or(DEFAULT_ALERT_LIMIT))
+            .cloned()
+            .collect()
+    }
+
+    fn are_correlated(&self, a: &str, b: &str) -> bool {
+        match (self.account_groups.get(a), self.account_groups.get(b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    /// 限价单价格不劣于同方向最优价减去 `layering_near_touch_bps`，
+    /// 同方向没有报价时订单本身就是最优价
+    fn is_near_touch(&self, state: &SurveillanceState, order: &Order) -> bool {
+        let Some(price) = order.price else {
+            return false;
+        };
+        let ticker = state.touches.get(&order.symbol);
+        let band = self.config.layering_near_touch_bps / 10_000.0;
+        match order.side {
+            OrderSide::Buy => ticker
+                .and_then(|ticker| ticker.bid_price)
+                .is_none_or(|bid| price >= bid * (1.0 - band)),
+            OrderSide::Sell => ticker
+                .and_then(|ticker| ticker.ask_price)
+                .is_none_or(|ask| price <= ask * (1.0 + band)),
+        }
+    }
+
+    fn ping_pong_window(&self) -> Duration {
+        Duration::milliseconds(self.config.ping_pong_window_ms as i64)
+    }
+
+    fn layering_window(&self) -> Duration {
+        Duration::milliseconds(self.config.layering_window_ms as i64)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn raise(
+        &self,
+        state: &mut SurveillanceState,
+        kind: AlertKind,
+        symbol: Symbol,
+        user_ids: Vec<String>,
+        trade_ids: Vec<TradeId>,
+        order_ids: Vec<OrderId>,
+        description: String,
+        timestamp: DateTime<Utc>,
+    ) {
+        warn!("Surveillance alert {}: {}", kind.as_str(), description);
+        counter!("matching_engine_surveillance_alerts_total", "kind" => kind.as_str()).increment(1);
+
+        let alert = SurveillanceAlert {
+            id: state.next_alert_id,
+            kind,
+            symbol,
+            user_ids,
+            trade_ids,
+            order_ids,
+            description,
+            timestamp,
+        };
+        state.next_alert_id += 1;
+        if state.alerts.len() >= self.config.max_alerts {
+            state.alerts.pop_front();
+        }
+        state.alerts.push_back(alert);
+    }
+}
+
+fn lagged(stream: &'static str, skipped: u64) {
+    warn!(
+        "Trade surveillance lagged, {} {} updates skipped",
+        skipped, stream
+    );
+    counter!("matching_engine_surveillance_skipped_total", "stream" => stream).increment(skipped);
+}
+
+/// 创建告警查询路由，与 API 路由共用前缀
+pub fn create_surveillance_router(surveillance: Arc<Surveillance>) -> Router {
+    Router::new()
+        .route("/admin/surveillance/alerts", get(list_alerts))
+        .with_state(surveillance)
+}
+
+async fn list_alerts(
+    State(surveillance): State<Arc<Surveillance>>,
+    ValidQuery(query): ValidQuery<AlertQuery>,
+) -> Json<Vec<SurveillanceAlert>> {
+    Json(surveillance.alerts(&query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbol() -> Symbol {
+        Symbol::new("BTC", "USDT")
+    }
+
+    fn trade(id: TradeId, buyer: &str, seller: &str, timestamp: DateTime<Utc>) -> Trade {
+        Trade {
+            id,
+            symbol: symbol(),
+            buy_order_id: id * 2,
+            sell_order_id: id * 2 + 1,
+            quantity: 1.0,
+            price: 100.0,
+            timestamp,
+            buyer_id: buyer.to_string(),
+            seller_id: seller.to_string(),
+            trade_type: TradeType::Regular,
+            taker_side: None,
+            buy_tag: None,
+            sell_tag: None,
+        }
+    }
+
+    fn query(kind: Option<AlertKind>) -> AlertQuery {
+        AlertQuery {
+            kind,
+            symbol: None,
+            user_id: None,
+            limit: None,
+        }
+    }
+
+    #[test]
+    fn test_self_and_correlated_trades() {
+        let surveillance = Surveillance::new(SurveillanceConfig {
+            correlated_accounts: vec![vec!["alice".to_string(), "alice-sub".to_string()]],
+            ..Default::default()
+        });
+        let now = Utc::now();
+        surveillance.on_trade(&trade(1, "alice", "alice", now));
+        surveillance.on_trade(&trade(2, "alice-sub", "alice", now));
+        surveillance.on_trade(&trade(3, "alice", "bob", now));
+
+        let alerts = surveillance.alerts(&query(None));
+        assert_eq!(alerts.len(), 2);
+        assert_eq!(alerts[0].kind, AlertKind::CorrelatedTrade);
+        assert_eq!(alerts[0].trade_ids, vec![2]);
+        assert_eq!(alerts[1].kind, AlertKind::SelfTrade);
+        assert_eq!(alerts[1].id, 1);
+
+        let mut by_user = query(None);
+        by_user.user_id = Some("alice-sub".to_string());
+        assert_eq!(surveillance.alerts(&by_user).len(), 1);
+    }
+
+    #[test]
+    fn test_ping_pong_within_window() {
+        let surveillance = Surveillance::new(SurveillanceConfig {
+            ping_pong_window_ms: 1000,
+            ping_pong_min_reversals: 3,
+            ..Default::default()
+        });
+        let start = Utc::now();
+        let at = |ms: i64| start + Duration::milliseconds(ms);
+
+        // 窗口外的换手不计入
+        surveillance.on_trade(&trade(1, "a", "b", at(0)));
+        surveillance.on_trade(&trade(2, "b", "a", at(100)));
+        surveillance.on_trade(&trade(3, "a", "b", at(2000)));
+        surveillance.on_trade(&trade(4, "b", "a", at(2100)));
+        assert!(surveillance.alerts(&query(None)).is_empty());
+
+        // 同方向成交不算换手
+        surveillance.on_trade(&trade(5, "b", "a", at(2200)));
+        surveillance.on_trade(&trade(6, "a", "b", at(2300)));
+        surveillance.on_trade(&trade(7, "b", "a", at(2400)));
+
+        let alerts = surveillance.alerts(&query(Some(AlertKind::PingPong)));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].user_ids, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(alerts[0].trade_ids, vec![3, 4, 5, 6, 7]);
+
+        // 告警后重新计数
+        surveillance.on_trade(&trade(8, "a", "b", at(2500)));
+        assert_eq!(surveillance.alerts(&query(None)).len(), 1);
+    }
+
+    #[test]
+    fn test_layering_near_touch() {
+        let surveillance = Surveillance::new(SurveillanceConfig {
+            layering_min_orders: 4,
+            layering_cancel_ratio: 0.75,
+            layering_near_touch_bps: 10.0,
+            ..Default::default()
+        });
+        surveillance.on_book_ticker(BookTicker {
+            symbol: symbol(),
+            bid_price: Some(100.0),
+            bid_quantity: 1.0,
+            ask_price: Some(100.5),
+            ask_quantity: 1.0,
+            timestamp: Utc::now(),
+        });
+        let now = Utc::now();
+        let order = |price: f64| {
+            Order::new(
+                symbol(),
+                OrderSide::Buy,
+                OrderType::Limit,
+                1.0,
+                Some(price),
+                "spoofer".to_string(),
+            )
+        };
+        let cancel = |mut order: Order| {
+            order.status = OrderStatus::Cancelled;
+            surveillance.on_order(&order, now);
+        };
+
+        // 远离盘口的挂单和撤单不计入
+        for _ in 0..4 {
+            let far = order(95.0);
+            surveillance.on_order(&far, now);
+            cancel(far);
+        }
+        assert!(surveillance.alerts(&query(None)).is_empty());
+
+        let near: Vec<Order> = [99.95, 100.0, 100.1, 99.92].map(order).into();
+        for order in &near {
+            surveillance.on_order(order, now);
+        }
+        cancel(near[0].clone());
+        cancel(near[1].clone());
+        assert!(surveillance.alerts(&query(None)).is_empty());
+        cancel(near[2].clone());
+
+        let alerts = surveillance.alerts(&query(Some(AlertKind::Layering)));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].user_ids, vec!["spoofer".to_string()]);
+        assert_eq!(
+            alerts[0].order_ids,
+            vec![near[0].id, near[1].id, near[2].id]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_engine_self_trade_alert() {
+        let engine = Arc::new(MatchingEngine::new());
+        let surveillance = Arc::new(Surveillance::new(SurveillanceConfig::default()));
+        let handle = surveillance.start(&engine, std::future::pending());
+
+        for side in [OrderSide::Sell, OrderSide::Buy] {
+            let order = Order::new(
+                symbol(),
+                side,
+                OrderType::Limit,
+                1.0,
+                Some(100.0),
+                "wash".to_string(),
+            );
+            engine.submit_order(order).await.unwrap();
+        }
+
+        for _ in 0..50 {
+            if !surveillance.alerts(&query(None)).is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let alerts = surveillance.alerts(&query(Some(AlertKind::SelfTrade)));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].user_ids, vec!["wash".to_string()]);
+        handle.abort();
+    }
+}
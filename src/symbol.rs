@@ -0,0 +1,176 @@
+use parking_lot::RwLock;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// 驻留后的交易对编号
+///
+/// 撮合热路径上用它代替两个堆分配的字符串做复制、比较和哈希。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SymbolId(u32);
+
+impl SymbolId {
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+}
+
+/// 交易对注册表
+///
+/// 把 (基础货币, 计价货币) 驻留为 `SymbolId`。交易对数量有限且不会注销，
+/// 字符串在首次注册时泄漏为 `&'static str`，解析时无需分配。
+/// 因此只有配置、上市和引擎自己的持久化数据会注册交易对，API 等外部输入只查找已注册的交易对。
+#[derive(Debug, Default)]
+pub struct SymbolRegistry {
+    inner: RwLock<RegistryInner>,
+}
+
+#[derive(Debug, Default)]
+struct RegistryInner {
+    ids: HashMap<(&'static str, &'static str), SymbolId>,
+    names: Vec<(&'static str, &'static str)>,
+    /// 上市交易对的计价货币
+    listed_quotes: Vec<&'static str>,
+}
+
+impl SymbolRegistry {
+    /// 进程级全局注册表
+    pub fn global() -> &'static SymbolRegistry {
+        static REGISTRY: OnceLock<SymbolRegistry> = OnceLock::new();
+        REGISTRY.get_or_init(SymbolRegistry::default)
+    }
+
+    /// 驻留交易对，已注册时直接返回原编号
+    ///
+    /// 传入的货币代码需已转换为大写。
+    pub fn intern(&self, base: &str, quote: &str) -> SymbolId {
+        if let Some(id) = self.lookup(base, quote) {
+            return id;
+        }
+
+        let mut inner = self.inner.write();
+        // 获取写锁期间可能已被其它线程注册
+        if let Some(&id) = inner.ids.get(&(base, quote)) {
+            return id;
+        }
+        let id = SymbolId(u32::try_from(inner.names.len()).expect("symbol registry exhausted"));
+        let base: &'static str = Box::leak(base.to_string().into_boxed_str());
+        let quote: &'static str = Box::leak(quote.to_string().into_boxed_str());
+        inner.names.push((base, quote));
+        inner.ids.insert((base, quote), id);
+        id
+    }
+
+    /// 查找已注册的交易对，不做注册
+    pub fn lookup(&self, base: &str, quote: &str) -> Option<SymbolId> {
+        self.inner.read().ids.get(&(base, quote)).copied()
+    }
+
+    /// 解析编号对应的 (基础货币, 计价货币)
+    pub fn resolve(&self, id: SymbolId) -> (&'static str, &'static str) {
+        self.inner.read().names[id.0 as usize]
+    }
+
+    /// 把交易对的计价货币登记为已上市的计价货币，解析连写格式的交易对时参与匹配
+    pub fn register_listed(&self, id: SymbolId) {
+        let mut inner = self.inner.write();
+        let quote = inner.names[id.0 as usize].1;
+        if !inner.listed_quotes.contains(&quote) {
+            inner.listed_quotes.push(quote);
+        }
+    }
+
+    /// 已上市交易对使用的计价货币
+    pub fn listed_quotes(&self) -> Vec<&'static str> {
+        self.inner.read().listed_quotes.clone()
+    }
+
+    /// 已注册的交易对数量
+    pub fn len(&self) -> usize {
+        self.inner.read().names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+thread_local! {
+    /// 当前线程上的反序列化是否可以注册新的交易对，见 [`interning`]
+    static INTERNING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// 在 `f` 内反序列化遇到未注册的交易对时直接注册
+///
+/// 反序列化默认只查找已注册的交易对。读取引擎自己写入的归档、审计、成交历史、事件日志、
+/// 结算报告和主实例的复制流时，其中的交易对可能是上次运行时上市的，需要在此范围内解析。
+pub fn interning<R>(f: impl FnOnce() -> R) -> R {
+    struct Restore(bool);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            INTERNING.with(|flag| flag.set(self.0));
+        }
+    }
+
+    let _restore = Restore(INTERNING.with(|flag| flag.replace(true)));
+    f()
+}
+
+/// 当前线程是否处在 [`interning`] 范围内
+pub(crate) fn is_interning() -> bool {
+    INTERNING.with(Cell::get)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Symbol;
+
+    #[test]
+    fn test_intern_returns_stable_ids() {
+        let registry = SymbolRegistry::default();
+
+        let btc = registry.intern("BTC", "USDT");
+        let eth = registry.intern("ETH", "USDT");
+
+        assert_ne!(btc, eth);
+        assert_eq!(registry.intern("BTC", "USDT"), btc);
+        assert_eq!(registry.lookup("ETH", "USDT"), Some(eth));
+        assert_eq!(registry.lookup("DOGE", "USDT"), None);
+        assert_eq!(registry.resolve(btc), ("BTC", "USDT"));
+        assert_eq!(registry.len(), 2);
+
+        assert!(registry.listed_quotes().is_empty());
+        registry.register_listed(btc);
+        registry.register_listed(eth);
+        assert_eq!(registry.listed_quotes(), vec!["USDT"]);
+    }
+
+    #[test]
+    fn test_symbol_uses_string_form_at_boundary() {
+        let symbol = Symbol::new("btc", "usdt");
+        assert_eq!(symbol, Symbol::new("BTC", "USDT"));
+        assert_eq!(symbol.to_string(), "BTCUSDT");
+
+        let json = serde_json::to_string(&symbol).unwrap();
+        assert_eq!(json, r#"{"base":"BTC","quote":"USDT"}"#);
+
+        let parsed: Symbol = serde_json::from_str(r#"{"base":"btc","quote":"USDT"}"#).unwrap();
+        assert_eq!(parsed.id(), symbol.id());
+    }
+
+    #[test]
+    fn test_external_input_does_not_register_symbols() {
+        let json = r#"{"base":"NOPE","quote":"ZZZ"}"#;
+
+        assert!(serde_json::from_str::<Symbol>(json).is_err());
+        assert!("NOPE-ZZZ".parse::<Symbol>().is_err());
+        assert!(SymbolRegistry::global().lookup("NOPE", "ZZZ").is_none());
+
+        // 持久化数据在 interning 范围内解析时注册
+        let symbol: Symbol = interning(|| serde_json::from_str(json)).unwrap();
+        assert_eq!(symbol, Symbol::new("NOPE", "ZZZ"));
+        assert!(!is_interning());
+        assert_eq!("NOPE-ZZZ".parse::<Symbol>().unwrap(), symbol);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::request_id::RequestId;
+use axum::http::Request;
+use tracing::Span;
+
+#[cfg(feature = "otel")]
+pub use otel::{init_tracer, OtelGuard};
+
+/// 为 HTTP 请求创建根 span，供 `TraceLayer::make_span_with` 使用
+///
+/// 经过请求ID中间件的请求在 span 中带有 `request_id`，请求内的日志都可以按该ID查找。
+/// 启用 `otel` 特性时，请求头中的 W3C `traceparent` 作为该 span 的父上下文，
+/// 引擎内部的订单 span 随之挂在上游链路下。
+pub fn make_request_span<B>(request: &Request<B>) -> Span {
+    let span = tracing::info_span!(
+        "http_request",
+        method = %request.method(),
+        path = %request.uri().path(),
+        request_id = request
+            .extensions()
+            .get::<RequestId>()
+            .map(|id| id.0.as_str()),
+    );
+
+    #[cfg(feature = "otel")]
+    otel::set_remote_parent(&span, request.headers());
+
+    span
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use crate::config::OtlpConfig;
+    use crate::logging::BoxedLayer;
+    use axum::http::HeaderMap;
+    use opentelemetry::propagation::Extractor;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+    use opentelemetry_sdk::Resource;
+    use tracing::Span;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::Layer;
+
+    /// 链路追踪导出句柄，丢弃时刷新并关闭导出器
+    pub struct OtelGuard {
+        provider: SdkTracerProvider,
+    }
+
+    impl Drop for OtelGuard {
+        fn drop(&mut self) {
+            if let Err(e) = self.provider.shutdown() {
+                eprintln!("Failed to shut down OTLP tracer provider: {}", e);
+            }
+        }
+    }
+
+    /// 创建 OTLP 导出器，返回可加入订阅者的 `tracing` 层
+    pub fn init_tracer<S>(
+        config: &OtlpConfig,
+    ) -> Result<(BoxedLayer<S>, OtelGuard), Box<dyn std::error::Error>>
+    where
+        S: tracing::Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+    {
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(&config.endpoint)
+            .build()?;
+
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+                config.sample_ratio,
+            ))))
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(config.service_name.clone())
+                    .build(),
+            )
+            .build();
+
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        opentelemetry::global::set_tracer_provider(provider.clone());
+
+        let tracer = provider.tracer("matching_engine");
+        let layer = tracing_opentelemetry::layer().with_tracer(tracer).boxed();
+
+        Ok((layer, OtelGuard { provider }))
+    }
+
+    /// 把请求头中的链路上下文设为 span 的父上下文
+    pub(super) fn set_remote_parent(span: &Span, headers: &HeaderMap) {
+        let context = opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(headers))
+        });
+        let _ = span.set_parent(context);
+    }
+
+    struct HeaderExtractor<'a>(&'a HeaderMap);
+
+    impl Extractor for HeaderExtractor<'_> {
+        fn get(&self, key: &str) -> Option<&str> {
+            self.0.get(key).and_then(|value| value.to_str().ok())
+        }
+
+        fn keys(&self) -> Vec<&str> {
+            self.0.keys().map(|key| key.as_str()).collect()
+        }
+    }
+}
@@ -0,0 +1,347 @@
+//! 多租户
+//!
+//! 每个租户拥有独立的撮合引擎（订单簿、订单、成交、账户和统计）和一套完整的 API/WebSocket 路由。
+//! 请求按 API key 分发到所属租户的路由，查询和推送只能看到该租户引擎中的数据；
+//! 缺少 API key 或 API key 未知时返回 401。
+//!
+//! 以下状态是进程级的，由全部租户共享：
+//! - 交易对注册表（[`crate::symbol::SymbolRegistry`]）：某个租户上市的交易对及其计价货币对所有租户都可以解析，
+//!   但上市记录、交易状态和订单簿属于各自的引擎，其它租户是否接受该交易对的订单仍由自己的
+//!   `symbols`（`restrict_symbols`）配置决定；
+//! - 订单和成交ID生成器（[`crate::id::IdGenerator`]）：全部租户使用同一分片，ID 在租户之间也不重复。
+use crate::api::ApiError;
+use crate::config::{EngineConfig, TenancyConfig};
+use crate::matching_engine::MatchingEngine;
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Router,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+/// WebSocket 客户端无法设置请求头时，通过该查询参数传入 API key
+const API_KEY_QUERY_PARAM: &str = "api_key";
+
+/// 租户及其撮合引擎
+pub struct Tenant {
+    pub id: String,
+    pub engine: Arc<MatchingEngine>,
+}
+
+/// 按配置创建的全部租户和 API key 索引
+pub struct TenantRegistry {
+    tenants: Vec<Tenant>,
+    /// API key -> 租户在 `tenants` 中的下标
+    api_keys: HashMap<String, usize>,
+}
+
+impl TenantRegistry {
+    /// 为每个租户按 `engine` 加上租户覆盖项创建撮合引擎
+    pub fn new(config: &TenancyConfig, engine: &EngineConfig) -> Result<Self, String> {
+        let mut api_keys = HashMap::new();
+        let tenants = config
+            .tenants
+            .iter()
+            .enumerate()
+            .map(|(index, tenant)| {
+                for api_key in &tenant.api_keys {
+                    if let Some(owner) = api_keys.insert(api_key.clone(), index) {
+                        return Err(format!(
+                            "API key of tenant {} is already assigned to tenant {}",
+                            tenant.id, config.tenants[owner].id
+                        ));
+                    }
+                }
+                let engine = MatchingEngine::try_with_config(tenant.engine_config(engine))?;
+                Ok(Tenant {
+                    id: tenant.id.clone(),
+                    engine: Arc::new(engine),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Self { tenants, api_keys })
+    }
+
+    /// 全部租户，按配置顺序
+    pub fn tenants(&self) -> &[Tenant] {
+        &self.tenants
+    }
+
+    /// API key 所属的租户
+    pub fn resolve(&self, api_key: &str) -> Option<&Tenant> {
+        self.api_keys
+            .get(api_key)
+            .map(|&index| &self.tenants[index])
+    }
+}
+
+/// 租户路由分发状态：API key -> 所属租户的路由
+#[derive(Clone)]
+struct TenantRouting {
+    api_key_header: String,
+    routers: Arc<HashMap<String, Router>>,
+}
+
+/// 创建按 API key 分发请求的路由
+///
+/// `build` 为每个租户创建完整路由（含 API 和 WebSocket 前缀），请求原样交给所属租户的路由处理。
+/// API key 取自 `api_key_header` 请求头，没有时取 `api_key` 查询参数
+pub fn create_tenant_router(
+    registry: &TenantRegistry,
+    api_key_header: &str,
+    build: impl Fn(&Tenant) -> Router,
+) -> Router {
+    let tenant_routers: Vec<Router> = registry.tenants.iter().map(build).collect();
+    let routers = registry
+        .api_keys
+        .iter()
+        .map(|(api_key, &index)| (api_key.clone(), tenant_routers[index].clone()))
+        .collect();
+
+    Router::new().fallback(dispatch).with_state(TenantRouting {
+        api_key_header: api_key_header.to_string(),
+        routers: Arc::new(routers),
+    })
+}
+
+/// 把请求交给 API key 所属租户的路由
+async fn dispatch(State(routing): State<TenantRouting>, request: Request) -> Response {
+    let router = api_key(&request, &routing.api_key_header)
+        .and_then(|api_key| routing.routers.get(&api_key))
+        .cloned();
+    match router {
+        Some(router) => router
+            .oneshot(request)
+            .await
+            .unwrap_or_else(|never| match never {}),
+        None => ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            "unauthorized",
+            "Missing or unknown API key",
+        )
+        .into_response(),
+    }
+}
+
+/// 从请求头或查询参数中取出 API key
+fn api_key(request: &Request, header: &str) -> Option<String> {
+    if let Some(value) = request.headers().get(header) {
+        return value.to_str().ok().map(str::to_string);
+    }
+    form_urlencoded::parse(request.uri().query()?.as_bytes())
+        .find(|(name, _)| name == API_KEY_QUERY_PARAM)
+        .map(|(_, value)| value.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::create_router;
+    use crate::config::TenantConfig;
+    use crate::types::*;
+    use axum::body::Body;
+    use axum::http::header;
+
+    fn tenant(id: &str, api_key: &str, symbols: &[&str]) -> TenantConfig {
+        TenantConfig {
+            id: id.to_string(),
+            api_keys: vec![api_key.to_string()],
+            symbols: symbols.iter().map(|symbol| symbol.to_string()).collect(),
+            fees: None,
+            user_limits: None,
+        }
+    }
+
+    fn registry() -> TenantRegistry {
+        let config = TenancyConfig {
+            enabled: true,
+            tenants: vec![
+                tenant("venue-a", "key-a", &["BTCUSDT"]),
+                tenant("venue-b", "key-b", &[]),
+            ],
+            ..Default::default()
+        };
+        TenantRegistry::new(&config, &EngineConfig::default()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_tenants_are_isolated() {
+        let registry = registry();
+        let venue_a = &registry.resolve("key-a").unwrap().engine;
+        let venue_b = &registry.resolve("key-b").unwrap().engine;
+        assert!(registry.resolve("key-c").is_none());
+
+        let btc = Symbol::new("BTC", "USDT");
+        let order = |symbol, user: &str| {
+            Order::new(
+                symbol,
+                OrderSide::Buy,
+                OrderType::Limit,
+                1.0,
+                Some(100.0),
+                user.to_string(),
+            )
+        };
+        venue_a.submit_order(order(btc, "alice")).await.unwrap();
+        assert_eq!(venue_a.get_user_orders("alice").len(), 1);
+        assert!(venue_b.get_user_orders("alice").is_empty());
+        assert!(venue_b.get_market_data(&btc).is_none());
+
+        // venue-a 只开放了 BTCUSDT
+        let err = venue_a
+            .submit_order(order(Symbol::new("ETH", "USDT"), "alice"))
+            .await
+            .unwrap_err();
+        assert!(err.contains("not supported"));
+    }
+
+    #[test]
+    fn test_duplicate_api_key_across_tenants_is_rejected() {
+        let config = TenancyConfig {
+            enabled: true,
+            tenants: vec![
+                tenant("venue-a", "key-a", &[]),
+                tenant("venue-b", "key-a", &[]),
+            ],
+            ..Default::default()
+        };
+        let err = TenantRegistry::new(&config, &EngineConfig::default())
+            .err()
+            .unwrap();
+        assert_eq!(
+            err,
+            "API key of tenant venue-b is already assigned to tenant venue-a"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tenants_share_symbol_registry_and_id_generator() {
+        let config = TenancyConfig {
+            enabled: true,
+            tenants: vec![
+                tenant("venue-a", "key-a", &[]),
+                tenant("venue-b", "key-b", &["BTCUSDT"]),
+            ],
+            ..Default::default()
+        };
+        let registry = TenantRegistry::new(&config, &EngineConfig::default()).unwrap();
+        let venue_a = &registry.resolve("key-a").unwrap().engine;
+        let venue_b = &registry.resolve("key-b").unwrap().engine;
+
+        // venue-a 上市的交易对在 venue-b 也能解析，但上市记录只属于 venue-a，
+        // venue-b 仍按自己的交易对白名单拒绝
+        let listed = Symbol::new("TNTA", "TNTQ");
+        venue_a
+            .list_symbol(ListSymbolRequest {
+                symbol: listed,
+                filters: SymbolFilters::default(),
+            })
+            .unwrap();
+        assert_eq!("TNTATNTQ".parse::<Symbol>().unwrap(), listed);
+        assert!(venue_b
+            .get_symbol_listings()
+            .iter()
+            .all(|listing| listing.symbol != listed));
+        let err = venue_b
+            .submit_order(Order::new(
+                listed,
+                OrderSide::Buy,
+                OrderType::Limit,
+                1.0,
+                Some(100.0),
+                "alice".to_string(),
+            ))
+            .await
+            .unwrap_err();
+        assert!(err.contains("not supported"));
+
+        // 两个租户的成交ID来自同一生成器，分片相同且互不重复
+        let btc = Symbol::new("BTC", "USDT");
+        let mut trade_ids = Vec::new();
+        for engine in [venue_a, venue_b] {
+            for (side, user) in [(OrderSide::Sell, "maker"), (OrderSide::Buy, "taker")] {
+                let trades = engine
+                    .submit_order(Order::new(
+                        btc,
+                        side,
+                        OrderType::Limit,
+                        1.0,
+                        Some(100.0),
+                        user.to_string(),
+                    ))
+                    .await
+                    .unwrap();
+                trade_ids.extend(trades.iter().map(|trade| trade.id));
+            }
+        }
+        assert_eq!(trade_ids.len(), 2);
+        assert_ne!(trade_ids[0], trade_ids[1]);
+        assert_eq!(
+            crate::id::shard_of(trade_ids[0]),
+            crate::id::shard_of(trade_ids[1])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_router_dispatches_by_api_key() {
+        let registry = registry();
+        registry
+            .resolve("key-a")
+            .unwrap()
+            .engine
+            .submit_order(Order::new(
+                Symbol::new("BTC", "USDT"),
+                OrderSide::Sell,
+                OrderType::Limit,
+                2.0,
+                Some(100.0),
+                "bob".to_string(),
+            ))
+            .await
+            .unwrap();
+        let router = crate::api::apply_server_layers(
+            create_tenant_router(&registry, "x-api-key", |tenant| {
+                create_router(Arc::clone(&tenant.engine))
+            }),
+            &crate::config::ServerConfig::default(),
+        )
+        .unwrap();
+
+        let open_orders = |request: axum::http::request::Builder| async {
+            let response = router
+                .clone()
+                .oneshot(request.body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&body).unwrap_or_default();
+            (status, body["items"].as_array().map(Vec::len))
+        };
+
+        let request = |uri: &str| Request::get(uri).header(header::ACCEPT, "application/json");
+        assert_eq!(
+            open_orders(request("/orders/user/bob").header("x-api-key", "key-a")).await,
+            (StatusCode::OK, Some(1))
+        );
+        assert_eq!(
+            open_orders(request("/orders/user/bob?api_key=key-b")).await,
+            (StatusCode::OK, Some(0))
+        );
+        assert_eq!(
+            open_orders(request("/orders/user/bob")).await.0,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            open_orders(request("/orders/user/bob").header("x-api-key", "key-c"))
+                .await
+                .0,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use crate::config::TlsConfig;
+use axum_server::tls_rustls::RustlsConfig;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tracing::{info, warn};
+
+/// 读取证书和私钥，构建 rustls 服务端配置
+///
+/// 设置了 `client_ca_path` 时要求客户端出示由该 CA 签发的证书（双向 TLS）
+pub fn build_server_config(config: &TlsConfig) -> Result<Arc<ServerConfig>, String> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("Invalid TLS protocol versions: {}", e))?;
+
+    let builder = match &config.client_ca_path {
+        Some(ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in read_certs(ca_path)? {
+                roots
+                    .add(cert)
+                    .map_err(|e| format!("Invalid client CA certificate in {}: {}", ca_path, e))?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                .build()
+                .map_err(|e| format!("Invalid client CA {}: {}", ca_path, e))?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut server_config = builder
+        .with_single_cert(read_certs(&config.cert_path)?, read_key(&config.key_path)?)
+        .map_err(|e| format!("Invalid TLS certificate or key: {}", e))?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(Arc::new(server_config))
+}
+
+/// 加载供 axum-server 监听使用的 TLS 配置
+pub fn load_rustls_config(config: &TlsConfig) -> Result<RustlsConfig, String> {
+    Ok(RustlsConfig::from_config(build_server_config(config)?))
+}
+
+/// 启动证书重载任务，`reload_interval_seconds` 为 0 时返回 None
+///
+/// 证书、私钥或客户端 CA 文件的修改时间变化后重新加载，之后的新连接使用新证书；
+/// 加载失败（如文件只写了一半）时保留当前证书并在下个周期重试。
+pub fn start_cert_reloader(
+    rustls: RustlsConfig,
+    config: TlsConfig,
+) -> Option<tokio::task::JoinHandle<()>> {
+    if config.reload_interval_seconds == 0 {
+        return None;
+    }
+    let interval = Duration::from_secs(config.reload_interval_seconds);
+
+    Some(tokio::spawn(async move {
+        let mut last_modified = modified_times(&config);
+        let mut ticker = tokio::time::interval(interval);
+        // 第一次 tick 立即完成
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            let modified = modified_times(&config);
+            if modified == last_modified {
+                continue;
+            }
+
+            match build_server_config(&config) {
+                Ok(server_config) => {
+                    rustls.reload_from_config(server_config);
+                    last_modified = modified;
+                    info!("Reloaded TLS certificate from {}", config.cert_path);
+                }
+                Err(e) => warn!(
+                    "Failed to reload TLS certificate, keeping current one: {}",
+                    e
+                ),
+            }
+        }
+    }))
+}
+
+fn modified_times(config: &TlsConfig) -> Vec<Option<SystemTime>> {
+    [Some(&config.cert_path), Some(&config.key_path)]
+        .into_iter()
+        .chain(std::iter::once(config.client_ca_path.as_ref()))
+        .flatten()
+        .map(|path| fs::metadata(path).and_then(|meta| meta.modified()).ok())
+        .collect()
+}
+
+fn read_certs(path: &str) -> Result<Vec<CertificateDer<'static>>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to parse certificates in {}: {}", path, e))?;
+    if certs.is_empty() {
+        return Err(format!("No certificates found in {}", path));
+    }
+    Ok(certs)
+}
+
+fn read_key(path: &str) -> Result<PrivateKeyDer<'static>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))
+        .map_err(|e| format!("Failed to parse private key in {}: {}", path, e))?
+        .ok_or_else(|| format!("No private key found in {}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_server_config_from_pem_files() {
+        let dir = std::env::temp_dir().join(format!("tls-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert_path = dir.join("server.crt");
+        let key_path = dir.join("server.key");
+        fs::write(&cert_path, cert.cert.pem()).unwrap();
+        fs::write(&key_path, cert.key_pair.serialize_pem()).unwrap();
+
+        let mut config = TlsConfig {
+            enabled: true,
+            cert_path: cert_path.to_string_lossy().to_string(),
+            key_path: key_path.to_string_lossy().to_string(),
+            client_ca_path: None,
+            reload_interval_seconds: 0,
+        };
+        let server_config = build_server_config(&config).unwrap();
+        assert!(server_config.alpn_protocols.contains(&b"http/1.1".to_vec()));
+
+        // 自签证书同时作为客户端 CA，启用双向 TLS
+        config.client_ca_path = Some(config.cert_path.clone());
+        assert!(build_server_config(&config).is_ok());
+
+        config.key_path = dir.join("missing.key").to_string_lossy().to_string();
+        assert!(build_server_config(&config).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,328 @@
+use crate::store::{Page, PageRequest, TradeFilter};
+use crate::symbol::interning;
+use crate::types::*;
+use chrono::NaiveDate;
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// 成交历史
+///
+/// 成交按交易对和 UTC 日期以 JSON Lines 追加写入 `<交易对>/<YYYY-MM-DD>.jsonl`。内存中只维护
+/// 交易对 -> 有成交的日期 的有序索引，按时间范围查询时只读取覆盖该范围的日期文件。
+#[derive(Debug)]
+pub struct TradeHistory {
+    dir: PathBuf,
+    state: Mutex<HistoryState>,
+}
+
+#[derive(Debug, Default)]
+struct HistoryState {
+    days: BTreeMap<String, BTreeSet<NaiveDate>>,
+    /// 交易对 -> 正在写入的日期文件
+    writers: HashMap<String, (NaiveDate, BufWriter<File>)>,
+}
+
+impl TradeHistory {
+    /// 打开历史目录，已有的日期文件会被重新索引
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, String> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).map_err(|e| {
+            format!(
+                "Failed to create trade history dir {}: {}",
+                dir.display(),
+                e
+            )
+        })?;
+
+        let mut state = HistoryState::default();
+        let symbols = fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to read trade history dir {}: {}", dir.display(), e))?;
+        for symbol in symbols.flatten() {
+            let Ok(files) = fs::read_dir(symbol.path()) else {
+                continue;
+            };
+            let days: BTreeSet<NaiveDate> = files
+                .flatten()
+                .filter_map(|file| parse_day_name(&file.file_name().to_string_lossy()))
+                .collect();
+            if !days.is_empty() {
+                let symbol = symbol.file_name().to_string_lossy().into_owned();
+                state.days.insert(symbol, days);
+            }
+        }
+
+        info!(
+            "Opened trade history at {} with {} symbols",
+            dir.display(),
+            state.days.len()
+        );
+
+        Ok(Self {
+            dir,
+            state: Mutex::new(state),
+        })
+    }
+
+    /// 追加成交
+    pub fn append(&self, trades: &[Trade]) -> Result<(), String> {
+        let mut state = self.state.lock();
+
+        for trade in trades {
+            let symbol = trade.symbol.to_string();
+            let writer = state.writer(&self.dir, &symbol, trade.timestamp.date_naive())?;
+            let mut line = serde_json::to_vec(trade)
+                .map_err(|e| format!("Failed to serialize trade {}: {}", trade.id, e))?;
+            line.push(b'\n');
+            writer
+                .write_all(&line)
+                .and_then(|_| writer.flush())
+                .map_err(|e| format!("Failed to write trade history for {}: {}", symbol, e))?;
+        }
+        Ok(())
+    }
+
+    /// 按条件分页查询成交，按成交ID从新到旧
+    ///
+    /// 从最近的日期开始向前读取，凑满一页后停止
+    pub fn query(&self, filter: &TradeFilter, page: PageRequest) -> Result<Page<Trade>, String> {
+        let first = filter
+            .start_time
+            .map_or(NaiveDate::MIN, |start| start.date_naive());
+        let last = filter
+            .end_time
+            .map_or(NaiveDate::MAX, |end| end.date_naive());
+        if first > last {
+            return Ok(Page {
+                items: Vec::new(),
+                next_cursor: None,
+            });
+        }
+
+        // 日期 -> 当天有成交的交易对
+        let mut days: BTreeMap<NaiveDate, Vec<String>> = BTreeMap::new();
+        {
+            let state = self.state.lock();
+            let symbols = state.days.iter().filter(|(symbol, _)| {
+                filter
+                    .symbol
+                    .is_none_or(|wanted| wanted.to_string() == **symbol)
+            });
+            for (symbol, symbol_days) in symbols {
+                for day in symbol_days.range(first..=last) {
+                    days.entry(*day).or_default().push(symbol.clone());
+                }
+            }
+        }
+
+        let mut items = Vec::new();
+        for (day, symbols) in days.iter().rev() {
+            let mut trades = Vec::new();
+            for symbol in symbols {
+                for trade in read_day(&day_path(&self.dir, symbol, *day))? {
+                    if page.cursor.is_none_or(|cursor| trade.id < cursor) && filter.matches(&trade)
+                    {
+                        trades.push(trade);
+                    }
+                }
+            }
+            trades.sort_unstable_by_key(|trade| std::cmp::Reverse(trade.id));
+            items.extend(trades);
+            if items.len() > page.limit {
+                break;
+            }
+        }
+
+        let next_cursor = (items.len() > page.limit).then(|| {
+            items.truncate(page.limit);
+            items.last().map(|trade| trade.id).or(page.cursor)
+        });
+        Ok(Page {
+            items,
+            next_cursor: next_cursor.flatten(),
+        })
+    }
+
+    /// 删除一笔成交（成交被撤销时），重写所在的日期文件；不存在时返回 false
+    pub fn remove(&self, trade: &Trade) -> Result<bool, String> {
+        let mut state = self.state.lock();
+        let symbol = trade.symbol.to_string();
+        let day = trade.timestamp.date_naive();
+        // 关闭正在写入的同一文件，下次追加时重新打开
+        if state
+            .writers
+            .get(&symbol)
+            .is_some_and(|(current, _)| *current == day)
+        {
+            state.writers.remove(&symbol);
+        }
+
+        let path = day_path(&self.dir, &symbol, day);
+        let trades = read_day(&path)?;
+        let kept: Vec<&Trade> = trades.iter().filter(|kept| kept.id != trade.id).collect();
+        if kept.len() == trades.len() {
+            return Ok(false);
+        }
+
+        let mut content = Vec::new();
+        for kept in kept {
+            serde_json::to_writer(&mut content, kept)
+                .map_err(|e| format!("Failed to serialize trade {}: {}", kept.id, e))?;
+            content.push(b'\n');
+        }
+        let partial = path.with_extension("jsonl.partial");
+        fs::write(&partial, content)
+            .and_then(|_| fs::rename(&partial, &path))
+            .map_err(|e| format!("Failed to rewrite {}: {}", path.display(), e))?;
+        Ok(true)
+    }
+
+    /// 将正在写入的文件同步到磁盘
+    pub fn flush(&self) -> Result<(), String> {
+        let mut state = self.state.lock();
+        for (symbol, (_, writer)) in state.writers.iter_mut() {
+            writer
+                .flush()
+                .and_then(|_| writer.get_ref().sync_all())
+                .map_err(|e| format!("Failed to sync trade history for {}: {}", symbol, e))?;
+        }
+        Ok(())
+    }
+}
+
+impl HistoryState {
+    /// 交易对在该日期的文件写入器，日期变化时切换到新文件
+    fn writer(
+        &mut self,
+        dir: &Path,
+        symbol: &str,
+        day: NaiveDate,
+    ) -> Result<&mut BufWriter<File>, String> {
+        let current = self
+            .writers
+            .get(symbol)
+            .is_some_and(|(writing, _)| *writing == day);
+        if !current {
+            let path = day_path(dir, symbol, day);
+            let file = fs::create_dir_all(dir.join(symbol))
+                .and_then(|_| OpenOptions::new().create(true).append(true).open(&path))
+                .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+            self.writers
+                .insert(symbol.to_string(), (day, BufWriter::new(file)));
+            self.days.entry(symbol.to_string()).or_default().insert(day);
+        }
+        Ok(&mut self.writers.get_mut(symbol).unwrap().1)
+    }
+}
+
+fn day_path(dir: &Path, symbol: &str, day: NaiveDate) -> PathBuf {
+    dir.join(symbol).join(format!("{}.jsonl", day))
+}
+
+fn parse_day_name(name: &str) -> Option<NaiveDate> {
+    name.strip_suffix(".jsonl")?.parse().ok()
+}
+
+/// 读取日期文件中的全部成交，文件不存在时为空；无法解析的行（如写了一半的最后一行）被跳过
+fn read_day(path: &Path) -> Result<Vec<Trade>, String> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to open {}: {}", path.display(), e)),
+    };
+    let mut trades = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if let Ok(trade) = interning(|| serde_json::from_str(&line)) {
+            trades.push(trade);
+        }
+    }
+    Ok(trades)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_query_reads_days_in_range_and_survives_reopen() {
+        let dir = std::env::temp_dir().join(format!("trade-history-{}", uuid::Uuid::new_v4()));
+        let btc = Symbol::new("BTC", "USDT");
+        let eth = Symbol::new("ETH", "USDT");
+        // 每天两笔 BTC 成交和一笔 ETH 成交，共三天
+        let trades: Vec<Trade> = (0..9)
+            .map(|i| {
+                let symbol = if i % 3 == 2 { eth } else { btc };
+                let buy = Order::new(
+                    symbol,
+                    OrderSide::Buy,
+                    OrderType::Limit,
+                    1.0,
+                    Some(100.0),
+                    "alice".to_string(),
+                );
+                let sell = Order::new(
+                    symbol,
+                    OrderSide::Sell,
+                    OrderType::Limit,
+                    1.0,
+                    Some(100.0),
+                    "bob".to_string(),
+                );
+                let mut trade = Trade::new(symbol, &buy, &sell, 1.0, 100.0 + i as f64);
+                trade.timestamp = Utc.with_ymd_and_hms(2024, 1, 5 + i / 3, 12, i, 0).unwrap();
+                trade
+            })
+            .collect();
+
+        {
+            let history = TradeHistory::open(&dir).unwrap();
+            history.append(&trades).unwrap();
+        }
+        let history = TradeHistory::open(&dir).unwrap();
+
+        let filter = TradeFilter {
+            symbol: Some(btc),
+            start_time: Some(Utc.with_ymd_and_hms(2024, 1, 6, 0, 0, 0).unwrap()),
+            end_time: Some(Utc.with_ymd_and_hms(2024, 1, 7, 12, 7, 0).unwrap()),
+            ..Default::default()
+        };
+        let page = history
+            .query(
+                &filter,
+                PageRequest {
+                    cursor: None,
+                    limit: 2,
+                },
+            )
+            .unwrap();
+        let ids: Vec<u64> = page.items.iter().map(|trade| trade.id).collect();
+        assert_eq!(ids, [trades[6].id, trades[4].id]);
+        let page = history
+            .query(
+                &filter,
+                PageRequest {
+                    cursor: page.next_cursor,
+                    limit: 2,
+                },
+            )
+            .unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].id, trades[3].id);
+        assert!(page.next_cursor.is_none());
+
+        assert!(history.remove(&trades[4]).unwrap());
+        assert!(!history.remove(&trades[4]).unwrap());
+        let all = history
+            .query(&TradeFilter::default(), PageRequest::default())
+            .unwrap();
+        assert_eq!(all.items.len(), 8);
+        assert_eq!(all.items[0].id, trades[8].id);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,195 @@
+use crate::id::OrderId;
+use crate::types::*;
+use std::collections::HashMap;
+use tracing::debug;
+
+/// 条件单触发簿
+///
+/// 每个交易对一个，保存尚未触发的止损、止盈和跟踪止损单。
+/// 每笔成交后由引擎调用 `on_trade`，返回已触发、需要转为普通订单参与撮合的订单。
+#[derive(Debug)]
+pub struct TriggerBook {
+    symbol: Symbol,
+    orders: HashMap<OrderId, PendingTrigger>,
+}
+
+#[derive(Debug, Clone)]
+struct PendingTrigger {
+    order: Order,
+    /// 跟踪止损单观察到的最优价（卖单取最高价，买单取最低价）
+    extreme_price: Option<f64>,
+}
+
+impl TriggerBook {
+    pub fn new(symbol: Symbol) -> Self {
+        Self {
+            symbol,
+            orders: HashMap::new(),
+        }
+    }
+
+    /// 加入条件单，`last_price` 为当前最新成交价，用于初始化跟踪止损
+    pub fn add_order(&mut self, mut order: Order, last_price: Option<f64>) -> Result<(), String> {
+        if order.symbol != self.symbol {
+            return Err(format!(
+                "Order symbol {} does not match trigger book symbol {}",
+                order.symbol, self.symbol
+            ));
+        }
+
+        let mut extreme_price = None;
+        if let (Some(offset), Some(last_price)) = (order.trailing_offset, last_price) {
+            extreme_price = Some(last_price);
+            order.stop_price = Some(trailing_stop_price(order.side, last_price, offset));
+        }
+
+        self.orders.insert(
+            order.id,
+            PendingTrigger {
+                order,
+                extreme_price,
+            },
+        );
+        Ok(())
+    }
+
+    /// 移除条件单
+    pub fn remove_order(&mut self, order_id: OrderId) -> Option<Order> {
+        self.orders.remove(&order_id).map(|pending| pending.order)
+    }
+
+    /// 获取条件单（跟踪止损单的触发价为当前值）
+    pub fn get_order(&self, order_id: OrderId) -> Option<Order> {
+        self.orders
+            .get(&order_id)
+            .map(|pending| pending.order.clone())
+    }
+
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    /// 处理一笔成交价：更新跟踪止损的触发价，并取出所有已触发的订单（按提交时间排序）
+    pub fn on_trade(&mut self, price: f64) -> Vec<Order> {
+        let mut triggered_ids = Vec::new();
+
+        for (order_id, pending) in self.orders.iter_mut() {
+            if let Some(offset) = pending.order.trailing_offset {
+                let extreme_price = match (pending.order.side, pending.extreme_price) {
+                    (OrderSide::Sell, Some(extreme)) => extreme.max(price),
+                    (OrderSide::Buy, Some(extreme)) => extreme.min(price),
+                    (_, None) => price,
+                };
+                pending.extreme_price = Some(extreme_price);
+                pending.order.stop_price = Some(trailing_stop_price(
+                    pending.order.side,
+                    extreme_price,
+                    offset,
+                ));
+            }
+
+            if is_triggered(&pending.order, price) {
+                triggered_ids.push(*order_id);
+            }
+        }
+
+        let mut triggered: Vec<Order> = triggered_ids
+            .into_iter()
+            .filter_map(|order_id| self.remove_order(order_id))
+            .collect();
+        triggered.sort_by_key(|order| order.timestamp);
+
+        for order in &triggered {
+            debug!(
+                "Order {} triggered at {} (stop {:?})",
+                order.id, price, order.stop_price
+            );
+        }
+        triggered
+    }
+}
+
+/// 跟踪止损触发价：卖单在最高价下方，买单在最低价上方
+fn trailing_stop_price(side: OrderSide, extreme_price: f64, offset: TrailingOffset) -> f64 {
+    let distance = offset.distance(extreme_price);
+    match side {
+        OrderSide::Sell => extreme_price - distance,
+        OrderSide::Buy => extreme_price + distance,
+    }
+}
+
+/// 判断条件单在成交价 `price` 下是否触发
+///
+/// 止损单和跟踪止损单在价格向不利方向突破触发价时触发（卖单价格跌破、买单价格涨破），
+/// 止盈单方向相反
+fn is_triggered(order: &Order, price: f64) -> bool {
+    let stop_price = match order.stop_price {
+        Some(stop_price) => stop_price,
+        None => return false,
+    };
+
+    match (order.order_type, order.side) {
+        (OrderType::StopLoss | OrderType::TrailingStop, OrderSide::Sell) => price <= stop_price,
+        (OrderType::StopLoss | OrderType::TrailingStop, OrderSide::Buy) => price >= stop_price,
+        (OrderType::TakeProfit, OrderSide::Sell) => price >= stop_price,
+        (OrderType::TakeProfit, OrderSide::Buy) => price <= stop_price,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sell_order(order_type: OrderType) -> Order {
+        Order::new(
+            Symbol::new("BTC", "USDT"),
+            OrderSide::Sell,
+            order_type,
+            1.0,
+            None,
+            "user1".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_stop_loss_and_take_profit_trigger() {
+        let mut book = TriggerBook::new(Symbol::new("BTC", "USDT"));
+        let stop = sell_order(OrderType::StopLoss).with_stop_price(49000.0);
+        let take_profit = sell_order(OrderType::TakeProfit).with_stop_price(52000.0);
+        book.add_order(stop.clone(), None).unwrap();
+        book.add_order(take_profit.clone(), None).unwrap();
+
+        assert!(book.on_trade(50000.0).is_empty());
+
+        let triggered = book.on_trade(48500.0);
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].id, stop.id);
+
+        let triggered = book.on_trade(52000.0);
+        assert_eq!(triggered[0].id, take_profit.id);
+        assert!(book.is_empty());
+    }
+
+    #[test]
+    fn test_trailing_stop_follows_price() {
+        let mut book = TriggerBook::new(Symbol::new("BTC", "USDT"));
+        let trailing =
+            sell_order(OrderType::TrailingStop).with_trailing_offset(TrailingOffset::Percent(10.0));
+        book.add_order(trailing.clone(), Some(100.0)).unwrap();
+        assert_eq!(book.get_order(trailing.id).unwrap().stop_price, Some(90.0));
+
+        // 价格上涨带动触发价上移，回落时触发价不变
+        assert!(book.on_trade(120.0).is_empty());
+        assert!(book.on_trade(110.0).is_empty());
+        assert_eq!(book.get_order(trailing.id).unwrap().stop_price, Some(108.0));
+
+        let triggered = book.on_trade(107.0);
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].stop_price, Some(108.0));
+    }
+}
@@ -1,9 +1,12 @@
+use crate::id::{next_order_id, next_trade_id, OrderId, TradeId};
+use crate::symbol::{SymbolId, SymbolRegistry};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 /// 订单类型
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum OrderType {
     /// 限价单
@@ -14,10 +17,42 @@ pub enum OrderType {
     StopLoss,
     /// 止盈单
     TakeProfit,
+    /// 跟踪止损单
+    TrailingStop,
+}
+
+impl OrderType {
+    /// 是否为条件单（先进入触发簿，触发后才参与撮合）
+    pub fn is_trigger(&self) -> bool {
+        matches!(
+            self,
+            OrderType::StopLoss | OrderType::TakeProfit | OrderType::TrailingStop
+        )
+    }
+}
+
+/// 跟踪止损的回撤距离
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TrailingOffset {
+    /// 固定价差
+    Amount(f64),
+    /// 相对最优价的百分比
+    Percent(f64),
+}
+
+impl TrailingOffset {
+    /// 根据参考价计算回撤距离
+    pub fn distance(&self, reference_price: f64) -> f64 {
+        match self {
+            TrailingOffset::Amount(amount) => *amount,
+            TrailingOffset::Percent(percent) => reference_price * percent / 100.0,
+        }
+    }
 }
 
 /// 订单方向
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum OrderSide {
     /// 买入
@@ -27,7 +62,7 @@ pub enum OrderSide {
 }
 
 /// 订单状态
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum OrderStatus {
     /// 新订单
@@ -40,32 +75,440 @@ pub enum OrderStatus {
     Cancelled,
     /// 已拒绝
     Rejected,
+    /// 已过期（GTD 订单到期自动撤销）
+    Expired,
+}
+
+impl OrderStatus {
+    /// 是否为终态（不会再发生成交或状态变化）
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Filled
+                | OrderStatus::Cancelled
+                | OrderStatus::Rejected
+                | OrderStatus::Expired
+        )
+    }
+}
+
+/// 订单有效期
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeInForce {
+    /// 撤销前有效
+    #[default]
+    GoodTillCancel,
+    /// 指定时间前有效，到期由引擎自动撤销
+    GoodTillDate,
+}
+
+/// 交易对交易状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TradingState {
+    /// 正常连续交易
+    #[default]
+    Trading,
+    /// 暂停交易，不接受新订单和撤单
+    Halted,
+    /// 只允许撤单
+    CancelOnly,
+    /// 只接受订单，不进行连续撮合
+    AuctionOnly,
 }
 
 /// 交易对
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+///
+/// 内部只保存驻留后的 `SymbolId`，复制、比较和哈希都不涉及字符串；
+/// 序列化时仍以 `{"base": ..., "quote": ...}` 的字符串形式出现。
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Symbol {
-    pub base: String,  // 基础货币，如 BTC
-    pub quote: String, // 计价货币，如 USDT
+    id: SymbolId,
 }
 
 impl Symbol {
     pub fn new(base: &str, quote: &str) -> Self {
         Self {
-            base: base.to_uppercase(),
-            quote: quote.to_uppercase(),
+            id: SymbolRegistry::global().intern(&base.to_uppercase(), &quote.to_uppercase()),
+        }
+    }
+
+    /// 校验币种代码后创建交易对，币种须为 1 到 [`MAX_ASSET_LEN`] 个 ASCII 字母或数字
+    ///
+    /// 未注册的交易对会被注册，只用于配置、上市等可信来源；外部输入使用 [`lookup`](Self::lookup)
+    pub fn try_new(base: &str, quote: &str) -> Result<Self, String> {
+        check_asset_codes(base, quote)?;
+        Ok(Self::new(base, quote))
+    }
+
+    /// 校验币种代码后查找已注册的交易对，不注册新的交易对
+    pub fn lookup(base: &str, quote: &str) -> Result<Self, String> {
+        check_asset_codes(base, quote)?;
+        SymbolRegistry::global()
+            .lookup(&base.to_uppercase(), &quote.to_uppercase())
+            .map(Self::from)
+            .ok_or_else(|| {
+                format!(
+                    "Unknown symbol {}{}",
+                    base.to_uppercase(),
+                    quote.to_uppercase()
+                )
+            })
+    }
+
+    /// 解析并注册交易对，格式同 [`FromStr`](std::str::FromStr)，只用于配置、上市等可信来源
+    pub fn register(symbol_str: &str) -> Result<Self, String> {
+        let (base, quote) = split_symbol(symbol_str)?;
+        Self::try_new(base, quote)
+    }
+
+    /// 驻留编号
+    pub fn id(&self) -> SymbolId {
+        self.id
+    }
+
+    /// 基础货币，如 BTC
+    pub fn base(&self) -> &'static str {
+        SymbolRegistry::global().resolve(self.id).0
+    }
+
+    /// 计价货币，如 USDT
+    pub fn quote(&self) -> &'static str {
+        SymbolRegistry::global().resolve(self.id).1
+    }
+
+    /// 登记为已上市交易对，此后连写格式也能按它的计价货币拆分
+    pub fn register_listed(&self) {
+        SymbolRegistry::global().register_listed(self.id);
+    }
+}
+
+/// 数量精度（小数位数）
+pub const QUANTITY_DECIMALS: i32 = 8;
+
+/// 价格上限
+pub const MAX_PRICE: f64 = 1e12;
+
+/// 单笔订单数量上限
+pub const MAX_QUANTITY: f64 = 1e12;
+
+/// 检查价格：有限、为正且不超过 [`MAX_PRICE`]，错误信息不含字段名，由调用方补上
+///
+/// 价格是否落在 tick 上由交易对的 [`PriceScale`] 检查
+pub fn check_price(price: f64) -> Result<(), String> {
+    check_range(price, MAX_PRICE)
+}
+
+/// 检查数量：有限、为正、不超过 [`MAX_QUANTITY`] 且小数位不超过 [`QUANTITY_DECIMALS`]
+pub fn check_quantity(quantity: f64) -> Result<(), String> {
+    check_range(quantity, MAX_QUANTITY)?;
+    if !is_integral(quantity * 10f64.powi(QUANTITY_DECIMALS)) {
+        return Err(format!(
+            "must have at most {} decimal places",
+            QUANTITY_DECIMALS
+        ));
+    }
+    Ok(())
+}
+
+/// 订单标签最大长度
+pub const MAX_TAG_LEN: usize = 64;
+
+/// 检查订单标签：1 到 [`MAX_TAG_LEN`] 个字符，不含控制字符
+pub fn check_tag(tag: &str) -> Result<(), String> {
+    if tag.is_empty() || tag.chars().count() > MAX_TAG_LEN {
+        return Err(format!("must be 1 to {} characters", MAX_TAG_LEN));
+    }
+    if tag.chars().any(char::is_control) {
+        return Err("must not contain control characters".to_string());
+    }
+    Ok(())
+}
+
+fn check_range(value: f64, max: f64) -> Result<(), String> {
+    if !value.is_finite() {
+        return Err("must be a finite number".to_string());
+    }
+    if value <= 0.0 {
+        return Err("must be positive".to_string());
+    }
+    if value > max {
+        return Err(format!("must not exceed {}", max));
+    }
+    Ok(())
+}
+
+/// 按精度放大后的十进制数是否为整数
+///
+/// 容差按几个 ulp 计算，覆盖十进制小数在 f64 中的表示误差和放大、加减时的舍入误差
+fn is_integral(scaled: f64) -> bool {
+    (scaled - scaled.round()).abs() <= scaled.abs() * f64::EPSILON * 4.0
+}
+
+/// 价格键的最大绝对值：超过后相邻价格键之间的误差无法可靠区分
+const MAX_PRICE_KEY: f64 = (1u64 << 50) as f64;
+
+/// tick 最多支持的小数位数
+pub const MAX_PRICE_DECIMALS: u32 = 12;
+
+/// 价格与订单簿整数价格键之间的换算，由交易对的最小价格变动单位（tick）决定
+///
+/// 价格键以 10^-decimals 为单位，decimals 取能精确表示 tick 的最小小数位数，
+/// 因此 0.05 这类非 10 的幂的 tick 同样适用；键换回价格时除以 10 的幂，结果是最接近该十进制数的 f64
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriceScale {
+    decimals: u32,
+    /// tick 对应的价格键单位数
+    tick_units: i64,
+}
+
+impl PriceScale {
+    pub fn from_tick_size(tick_size: f64) -> Result<Self, String> {
+        if !tick_size.is_finite() || tick_size <= 0.0 {
+            return Err(format!("Tick size {} must be positive", tick_size));
+        }
+
+        (0..=MAX_PRICE_DECIMALS)
+            .find_map(|decimals| {
+                let scaled = tick_size * 10f64.powi(decimals as i32);
+                ((1.0..=MAX_PRICE_KEY).contains(&scaled) && is_integral(scaled)).then(|| Self {
+                    decimals,
+                    tick_units: scaled.round() as i64,
+                })
+            })
+            .ok_or_else(|| {
+                format!(
+                    "Tick size {} needs more than {} decimal places",
+                    tick_size, MAX_PRICE_DECIMALS
+                )
+            })
+    }
+
+    pub fn tick_size(&self) -> f64 {
+        self.to_price(self.tick_units)
+    }
+
+    fn factor(&self) -> f64 {
+        10f64.powi(self.decimals as i32)
+    }
+
+    /// 将价格换算为价格键；价格不是 tick 的整数倍，或放大后超出可精确区分的范围时返回错误
+    pub fn to_key(&self, price: f64) -> Result<i64, String> {
+        let scaled = price * self.factor();
+        if !scaled.is_finite() || scaled.abs() > MAX_PRICE_KEY {
+            return Err(format!(
+                "Price {} cannot be represented exactly with tick size {}",
+                price,
+                self.tick_size()
+            ));
+        }
+
+        let key = scaled.round() as i64;
+        if !is_integral(scaled) || key % self.tick_units != 0 {
+            return Err(format!(
+                "Price {} is not a multiple of tick size {}",
+                price,
+                self.tick_size()
+            ));
+        }
+        Ok(key)
+    }
+
+    /// 把价格按 tick 取整，`round_up` 为 true 时向上取整，否则向下
+    pub fn round_to_tick(&self, price: f64, round_up: bool) -> f64 {
+        // 先消除浮点误差，避免恰好落在 tick 上的价格被取整到相邻 tick
+        let ticks = (price * self.factor() / self.tick_units as f64 * 1e9).round() / 1e9;
+        let ticks = if round_up {
+            ticks.ceil()
+        } else {
+            ticks.floor()
+        };
+        self.to_price(ticks as i64 * self.tick_units)
+    }
+
+    /// 就近换算为价格键，用于查询边界等不要求价格落在 tick 上的场景，超出范围时饱和
+    pub fn to_key_lossy(&self, price: f64) -> i64 {
+        (price * self.factor()).round() as i64
+    }
+
+    pub fn to_price(&self, key: i64) -> f64 {
+        key as f64 / self.factor()
+    }
+}
+
+impl Default for PriceScale {
+    /// 6 位小数精度，tick 为 0.000001
+    fn default() -> Self {
+        Self {
+            decimals: 6,
+            tick_units: 1,
+        }
+    }
+}
+
+/// 币种代码最大长度
+pub const MAX_ASSET_LEN: usize = 16;
+
+/// 连写格式交易对（如 BTCUSDT）默认可识别的计价货币，已上市交易对的计价货币也参与匹配
+const QUOTE_ASSETS: [&str; 7] = ["USDT", "USDC", "BUSD", "USD", "BTC", "ETH", "BNB"];
+
+/// 币种须为 1 到 [`MAX_ASSET_LEN`] 个 ASCII 字母或数字
+fn check_asset_codes(base: &str, quote: &str) -> Result<(), String> {
+    for asset in [base, quote] {
+        if asset.is_empty()
+            || asset.len() > MAX_ASSET_LEN
+            || !asset.bytes().all(|byte| byte.is_ascii_alphanumeric())
+        {
+            return Err(format!("Invalid asset code {:?}", asset));
+        }
+    }
+    Ok(())
+}
+
+impl std::str::FromStr for Symbol {
+    type Err = String;
+
+    /// 支持格式: BTCUSDT, BTC-USDT, BTC/USDT，只查找已注册的交易对
+    fn from_str(symbol_str: &str) -> Result<Self, Self::Err> {
+        let (base, quote) = split_symbol(symbol_str)?;
+        Symbol::lookup(base, quote)
+    }
+}
+
+/// 把交易对字符串拆分为 (基础货币, 计价货币)
+fn split_symbol(symbol_str: &str) -> Result<(&str, &str), String> {
+    Ok(match symbol_str.split_once(['-', '/']) {
+        Some(parts) => parts,
+        None => symbol_str.split_at(split_compact(symbol_str)?),
+    })
+}
+
+/// 连写格式按已知计价货币的最长后缀拆分，返回计价货币的起始位置
+fn split_compact(symbol_str: &str) -> Result<usize, String> {
+    let mut quotes = SymbolRegistry::global().listed_quotes();
+    quotes.extend(QUOTE_ASSETS);
+    quotes
+        .iter()
+        .filter_map(|quote| {
+            let split = symbol_str.len().checked_sub(quote.len())?;
+            let suffix = symbol_str.get(split..)?;
+            // 基础货币不能为空
+            (split > 0 && suffix.eq_ignore_ascii_case(quote)).then_some(split)
+        })
+        .min()
+        .ok_or_else(|| {
+            quotes.sort_unstable();
+            quotes.dedup();
+            format!(
+                "Unknown symbol {:?}: use BASE-QUOTE (e.g. BTC-USDT) or a known quote asset ({})",
+                symbol_str,
+                quotes.join(", ")
+            )
+        })
+}
+
+impl From<SymbolId> for Symbol {
+    fn from(id: SymbolId) -> Self {
+        Self { id }
+    }
+}
+
+impl std::fmt::Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (base, quote) = SymbolRegistry::global().resolve(self.id);
+        write!(f, "{}{}", base, quote)
+    }
+}
+
+impl std::fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (base, quote) = SymbolRegistry::global().resolve(self.id);
+        f.debug_struct("Symbol")
+            .field("base", &base)
+            .field("quote", &quote)
+            .finish()
+    }
+}
+
+/// 交易对的字符串形式，只在 API 边界使用
+#[derive(Serialize, Deserialize, ToSchema)]
+struct SymbolRepr<'a> {
+    /// 基础货币，如 BTC
+    base: std::borrow::Cow<'a, str>,
+    /// 计价货币，如 USDT
+    quote: std::borrow::Cow<'a, str>,
+}
+
+impl Serialize for Symbol {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (base, quote) = SymbolRegistry::global().resolve(self.id);
+        SymbolRepr {
+            base: base.into(),
+            quote: quote.into(),
         }
+        .serialize(serializer)
     }
+}
 
-    pub fn to_string(&self) -> String {
-        format!("{}{}", self.base, self.quote)
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = SymbolRepr::deserialize(deserializer)?;
+        // 外部输入只查找已注册的交易对，见 [`crate::symbol::interning`]
+        if crate::symbol::is_interning() {
+            Symbol::try_new(&repr.base, &repr.quote).map_err(serde::de::Error::custom)
+        } else {
+            Symbol::lookup(&repr.base, &repr.quote).map_err(serde::de::Error::custom)
+        }
+    }
+}
+
+impl utoipa::PartialSchema for Symbol {
+    fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+        SymbolRepr::schema()
+    }
+}
+
+impl ToSchema for Symbol {
+    fn name() -> std::borrow::Cow<'static, str> {
+        "Symbol".into()
+    }
+}
+
+/// 订单的提交渠道
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderChannel {
+    /// 引擎内部生成（如强平单）或直接调用库接口提交
+    #[default]
+    Internal,
+    Rest,
+    #[serde(rename = "websocket")]
+    WebSocket,
+    Fix,
+    Grpc,
+    /// 管理接口
+    Admin,
+}
+
+impl OrderChannel {
+    /// 指标标签和日志中使用的名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderChannel::Internal => "internal",
+            OrderChannel::Rest => "rest",
+            OrderChannel::WebSocket => "websocket",
+            OrderChannel::Fix => "fix",
+            OrderChannel::Grpc => "grpc",
+            OrderChannel::Admin => "admin",
+        }
     }
 }
 
 /// 订单
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Order {
-    pub id: Uuid,
+    pub id: OrderId,
     pub symbol: Symbol,
     pub side: OrderSide,
     pub order_type: OrderType,
@@ -76,6 +519,38 @@ pub struct Order {
     pub remaining_quantity: f64,
     pub timestamp: DateTime<Utc>,
     pub user_id: String,
+    #[serde(default)]
+    pub time_in_force: TimeInForce,
+    /// GTD 订单的到期时间
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// 条件单触发价，跟踪止损单由引擎随成交价更新
+    #[serde(default)]
+    pub stop_price: Option<f64>,
+    /// 跟踪止损单的回撤距离
+    #[serde(default)]
+    pub trailing_offset: Option<TrailingOffset>,
+    /// 只减仓：成交不得增加用户在该交易对上的净持仓
+    #[serde(default)]
+    pub reduce_only: bool,
+    /// 市价单最大滑点（基点），相对撮合开始时的对手方最优价
+    #[serde(default)]
+    pub max_slippage_bps: Option<f64>,
+    /// 市价单按计价货币金额下单（如买入价值 1000 USDT），此时 `quantity` 为 0
+    #[serde(default)]
+    pub quote_quantity: Option<f64>,
+    /// 最小成交数量：单次成交低于该数量时不与之撮合（等于订单数量即全部成交或不成交）
+    #[serde(default)]
+    pub min_fill_qty: Option<f64>,
+    /// 客户端提供的外部 UUID 别名，可用于查询订单
+    #[serde(default)]
+    pub external_id: Option<Uuid>,
+    /// 客户端自定义标签（如策略ID），随成交和订单更新返回，可用于过滤订单和成交
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// 提交渠道，由接入层设置
+    #[serde(default)]
+    pub channel: OrderChannel,
 }
 
 impl Order {
@@ -87,7 +562,7 @@ impl Order {
         price: Option<f64>,
         user_id: String,
     ) -> Self {
-        let id = Uuid::new_v4();
+        let id = next_order_id();
         let timestamp = Utc::now();
 
         Self {
@@ -102,9 +577,96 @@ impl Order {
             remaining_quantity: quantity,
             timestamp,
             user_id,
+            time_in_force: TimeInForce::GoodTillCancel,
+            expires_at: None,
+            stop_price: None,
+            trailing_offset: None,
+            reduce_only: false,
+            max_slippage_bps: None,
+            quote_quantity: None,
+            min_fill_qty: None,
+            external_id: None,
+            tag: None,
+            channel: OrderChannel::default(),
         }
     }
 
+    /// 设置为 GTD 订单，到期后由引擎自动撤销
+    pub fn with_expiry(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.time_in_force = TimeInForce::GoodTillDate;
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// 设置止损/止盈单的触发价
+    pub fn with_stop_price(mut self, stop_price: f64) -> Self {
+        self.stop_price = Some(stop_price);
+        self
+    }
+
+    /// 设置市价单最大滑点（基点）
+    pub fn with_max_slippage_bps(mut self, max_slippage_bps: f64) -> Self {
+        self.max_slippage_bps = Some(max_slippage_bps);
+        self
+    }
+
+    /// 按计价货币金额下市价单，数量由撮合时的成交价决定
+    pub fn with_quote_quantity(mut self, quote_quantity: f64) -> Self {
+        self.quantity = 0.0;
+        self.remaining_quantity = 0.0;
+        self.quote_quantity = Some(quote_quantity);
+        self
+    }
+
+    /// 设置最小成交数量
+    pub fn with_min_fill_qty(mut self, min_fill_qty: f64) -> Self {
+        self.min_fill_qty = Some(min_fill_qty);
+        self
+    }
+
+    /// 全部成交或不成交（AON），即最小成交数量等于订单数量
+    pub fn all_or_none(self) -> Self {
+        let quantity = self.quantity;
+        self.with_min_fill_qty(quantity)
+    }
+
+    /// 单次成交的最小数量，剩余数量不足最小成交数量时以剩余数量为准
+    pub fn min_fill_threshold(&self) -> f64 {
+        self.min_fill_qty
+            .map(|min_fill_qty| min_fill_qty.min(self.remaining_quantity))
+            .unwrap_or(0.0)
+    }
+
+    /// 标记为只减仓订单
+    pub fn reduce_only(mut self) -> Self {
+        self.reduce_only = true;
+        self
+    }
+
+    /// 设置跟踪止损单的回撤距离
+    pub fn with_trailing_offset(mut self, trailing_offset: TrailingOffset) -> Self {
+        self.trailing_offset = Some(trailing_offset);
+        self
+    }
+
+    /// 设置外部 UUID 别名
+    pub fn with_external_id(mut self, external_id: Uuid) -> Self {
+        self.external_id = Some(external_id);
+        self
+    }
+
+    /// 设置客户端自定义标签
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    /// 设置提交渠道
+    pub fn with_channel(mut self, channel: OrderChannel) -> Self {
+        self.channel = channel;
+        self
+    }
+
     /// 检查订单是否可以与另一个订单匹配
     pub fn can_match(&self, other: &Order) -> bool {
         // 必须是不同的方向
@@ -140,43 +702,42 @@ impl Order {
             _ => false,
         }
     }
+}
 
-    /// 计算匹配价格（价格优先原则）
-    pub fn match_price(&self, other: &Order) -> f64 {
-        match (self.side, other.side) {
-            (OrderSide::Buy, OrderSide::Sell) => {
-                // 买单与卖单匹配，使用先进入市场的价格
-                if self.timestamp <= other.timestamp {
-                    other.price.unwrap_or(0.0) // 卖单价格
-                } else {
-                    self.price.unwrap_or(0.0) // 买单价格
-                }
-            }
-            (OrderSide::Sell, OrderSide::Buy) => {
-                // 卖单与买单匹配，使用先进入市场的价格
-                if self.timestamp <= other.timestamp {
-                    self.price.unwrap_or(0.0) // 卖单价格
-                } else {
-                    other.price.unwrap_or(0.0) // 买单价格
-                }
-            }
-            _ => 0.0,
-        }
-    }
+/// 成交类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeType {
+    /// 订单簿撮合产生的成交
+    #[default]
+    Regular,
+    /// 双方协商后申报的场外成交，没有对应的订单，订单ID为 0
+    Otc,
 }
 
 /// 交易
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Trade {
-    pub id: Uuid,
+    pub id: TradeId,
     pub symbol: Symbol,
-    pub buy_order_id: Uuid,
-    pub sell_order_id: Uuid,
+    pub buy_order_id: OrderId,
+    pub sell_order_id: OrderId,
     pub quantity: f64,
     pub price: f64,
     pub timestamp: DateTime<Utc>,
     pub buyer_id: String,
     pub seller_id: String,
+    #[serde(default)]
+    pub trade_type: TradeType,
+    /// 主动吃单方的方向，集合竞价和场外成交没有主动方时为空
+    #[serde(default)]
+    pub taker_side: Option<OrderSide>,
+    /// 买方订单的自定义标签
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buy_tag: Option<String>,
+    /// 卖方订单的自定义标签
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sell_tag: Option<String>,
 }
 
 impl Trade {
@@ -187,42 +748,135 @@ impl Trade {
         quantity: f64,
         price: f64,
     ) -> Self {
-        let id = Uuid::new_v4();
-        let timestamp = Utc::now();
+        Self::new_with_id(
+            next_trade_id(),
+            Utc::now(),
+            symbol,
+            buy_order,
+            sell_order,
+            quantity,
+            price,
+        )
+    }
 
-        let (buy_order_id, sell_order_id, buyer_id, seller_id) =
-            match (buy_order.side, sell_order.side) {
-                (OrderSide::Buy, OrderSide::Sell) => (
-                    buy_order.id,
-                    sell_order.id,
-                    buy_order.user_id.clone(),
-                    sell_order.user_id.clone(),
-                ),
-                (OrderSide::Sell, OrderSide::Buy) => (
-                    sell_order.id,
-                    buy_order.id,
-                    sell_order.user_id.clone(),
-                    buy_order.user_id.clone(),
-                ),
-                _ => panic!("Invalid order sides for trade"),
-            };
+    /// 使用给定的成交ID和时间创建成交，引擎按自身时钟分配二者
+    pub fn new_with_id(
+        id: TradeId,
+        timestamp: DateTime<Utc>,
+        symbol: Symbol,
+        buy_order: &Order,
+        sell_order: &Order,
+        quantity: f64,
+        price: f64,
+    ) -> Self {
+        let (buy_order, sell_order) = match (buy_order.side, sell_order.side) {
+            (OrderSide::Buy, OrderSide::Sell) => (buy_order, sell_order),
+            (OrderSide::Sell, OrderSide::Buy) => (sell_order, buy_order),
+            _ => panic!("Invalid order sides for trade"),
+        };
 
         Self {
             id,
             symbol,
-            buy_order_id,
-            sell_order_id,
+            buy_order_id: buy_order.id,
+            sell_order_id: sell_order.id,
             quantity,
             price,
             timestamp,
-            buyer_id,
-            seller_id,
+            buyer_id: buy_order.user_id.clone(),
+            seller_id: sell_order.user_id.clone(),
+            trade_type: TradeType::Regular,
+            taker_side: None,
+            buy_tag: buy_order.tag.clone(),
+            sell_tag: sell_order.tag.clone(),
+        }
+    }
+
+    /// 记录主动吃单方的方向，连续撮合时为新到达订单的方向
+    pub fn with_taker_side(mut self, side: OrderSide) -> Self {
+        self.taker_side = Some(side);
+        self
+    }
+}
+
+/// 成交撤销（bust）：管理员撤销一笔已执行的错误成交后发出的补偿事件
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TradeBust {
+    /// 被撤销的成交ID
+    pub trade_id: TradeId,
+    /// 被撤销的原成交
+    pub trade: Trade,
+    pub reason: String,
+    /// 被撤销的数量恢复为剩余数量、可以继续成交的订单
+    pub restored_order_ids: Vec<OrderId>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 公开成交：去掉双方用户ID和订单ID，用于公共行情频道和成交查询接口
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PublicTrade {
+    pub id: TradeId,
+    pub symbol: Symbol,
+    pub quantity: f64,
+    pub price: f64,
+    pub timestamp: DateTime<Utc>,
+    pub trade_type: TradeType,
+    /// 主动吃单方的方向，集合竞价和场外成交没有主动方时为空
+    pub taker_side: Option<OrderSide>,
+}
+
+impl From<&Trade> for PublicTrade {
+    fn from(trade: &Trade) -> Self {
+        Self {
+            id: trade.id,
+            symbol: trade.symbol,
+            quantity: trade.quantity,
+            price: trade.price,
+            timestamp: trade.timestamp,
+            trade_type: trade.trade_type,
+            taker_side: trade.taker_side,
         }
     }
 }
 
+/// 公开的成交撤销，不含恢复挂单的订单ID
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PublicTradeBust {
+    pub trade_id: TradeId,
+    pub trade: PublicTrade,
+    pub reason: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl From<&TradeBust> for PublicTradeBust {
+    fn from(bust: &TradeBust) -> Self {
+        Self {
+            trade_id: bust.trade_id,
+            trade: PublicTrade::from(&bust.trade),
+            reason: bust.reason.clone(),
+            timestamp: bust.timestamp,
+        }
+    }
+}
+
+/// 聚合成交：同一吃单订单在同一价格上连续成交的多笔成交合并为一条
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AggTrade {
+    pub symbol: Symbol,
+    pub price: f64,
+    /// 合并后的总成交量
+    pub quantity: f64,
+    /// 第一笔成交ID，同时作为分页游标
+    pub first_trade_id: TradeId,
+    pub last_trade_id: TradeId,
+    pub taker_order_id: OrderId,
+    pub taker_side: OrderSide,
+    /// 第一笔成交的时间
+    pub timestamp: DateTime<Utc>,
+}
+
 /// 订单簿条目
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct OrderBookEntry {
     pub order: Order,
     pub priority: u64, // 时间优先级，越小越优先
@@ -235,15 +889,88 @@ impl OrderBookEntry {
 }
 
 /// 价格级别
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct PriceLevel {
     pub price: f64,
     pub total_quantity: f64,
     pub order_count: usize,
 }
 
+/// 交易对状态变更
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SymbolStatus {
+    pub symbol: Symbol,
+    pub state: TradingState,
+    pub reason: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 交易对上市状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ListingStatus {
+    Listed,
+    /// 已下市，挂单已全部撤销，不再接受新订单
+    Delisted,
+}
+
+/// 交易对下单过滤规则，未设置的项不限制
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct SymbolFilters {
+    /// 最小价格变动单位，未设置时使用 `engine.tick_sizes` 中的配置
+    pub tick_size: Option<f64>,
+    /// 单笔最小数量
+    pub min_quantity: Option<f64>,
+    /// 单笔最大数量
+    pub max_quantity: Option<f64>,
+    /// 限价单最小名义价值（价格 × 数量）
+    pub min_notional: Option<f64>,
+}
+
+/// 交易对上市记录
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SymbolListing {
+    pub symbol: Symbol,
+    pub status: ListingStatus,
+    pub filters: SymbolFilters,
+    pub listed_at: DateTime<Utc>,
+    pub delisted_at: Option<DateTime<Utc>>,
+    /// 下市时的最终统计
+    pub final_stats: Option<SymbolStats>,
+}
+
+/// 上市交易对请求
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ListSymbolRequest {
+    /// 上市的交易对，未注册时注册
+    #[serde(deserialize_with = "deserialize_listed_symbol")]
+    pub symbol: Symbol,
+    #[serde(default)]
+    pub filters: SymbolFilters,
+}
+
+fn deserialize_listed_symbol<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Symbol, D::Error> {
+    crate::symbol::interning(|| Symbol::deserialize(deserializer))
+}
+
+/// 集合竞价参考价和参考成交量
+///
+/// 买卖盘不交叉时 `price` 为空、`volume` 为 0
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuctionIndicative {
+    pub symbol: Symbol,
+    pub price: Option<f64>,
+    pub volume: f64,
+    /// 参考价下未能成交的数量（买方多为正，卖方多为负）
+    pub imbalance: f64,
+    pub timestamp: DateTime<Utc>,
+}
+
 /// 订单簿深度
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct OrderBookDepth {
     pub symbol: Symbol,
     pub bids: Vec<PriceLevel>, // 买盘，价格从高到低
@@ -251,8 +978,103 @@ pub struct OrderBookDepth {
     pub timestamp: DateTime<Utc>,
 }
 
+/// 最优买卖价（盘口），一侧无挂单时价格为空、数量为 0
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct BookTicker {
+    pub symbol: Symbol,
+    pub bid_price: Option<f64>,
+    /// 最优买价档位的挂单总量
+    pub bid_quantity: f64,
+    pub ask_price: Option<f64>,
+    /// 最优卖价档位的挂单总量
+    pub ask_quantity: f64,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 由实时订单簿计算的盘口分析指标
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BookAnalytics {
+    pub symbol: Symbol,
+    pub best_bid: Option<f64>,
+    pub best_ask: Option<f64>,
+    /// 买一卖一的中间价
+    pub mid_price: Option<f64>,
+    /// 以买一卖一挂单量加权的微观价格，挂单量大的一侧对价格的拉力更小
+    pub microprice: Option<f64>,
+    pub spread: Option<f64>,
+    /// 价差相对中间价的基点数
+    pub spread_bps: Option<f64>,
+    /// 参与计算失衡度的每侧档位数
+    pub depth: usize,
+    /// 前 `depth` 档买盘挂单总量
+    pub bid_depth_quantity: f64,
+    /// 前 `depth` 档卖盘挂单总量
+    pub ask_depth_quantity: f64,
+    /// 买卖失衡度 (买量 - 卖量) / (买量 + 卖量)，取值 [-1, 1]，两侧均无挂单时为空
+    pub imbalance: Option<f64>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 模拟撮合中在一个价格档位的预计成交
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SimulatedFill {
+    pub price: f64,
+    pub quantity: f64,
+}
+
+/// 模拟下单（what-if）的结果，不修改订单簿和任何状态
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OrderTestResult {
+    pub symbol: Symbol,
+    pub side: OrderSide,
+    /// 按价格档位汇总的预计成交，价格已按成交价规则计算
+    pub fills: Vec<SimulatedFill>,
+    pub filled_quantity: f64,
+    /// 成交额（计价货币）
+    pub filled_notional: f64,
+    /// 成交均价，没有成交时为空
+    pub average_price: Option<f64>,
+    /// 对手方最优价，作为滑点的参考价
+    pub reference_price: Option<f64>,
+    /// 成交均价相对对手方最优价的不利偏离基点数
+    pub slippage_bps: Option<f64>,
+    /// 按吃单费率估算的手续费（计价货币）
+    pub estimated_fee: f64,
+    /// 将挂入订单簿的剩余数量
+    pub resting_quantity: f64,
+    /// 不会挂单而被撤销的剩余数量（市价单）
+    pub cancelled_quantity: f64,
+    /// 按金额下单时未用完的金额
+    pub remaining_quote: Option<f64>,
+}
+
+/// 指数价格的成分报价
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct IndexComponent {
+    /// 价格源名称
+    pub source: String,
+    pub price: f64,
+    /// 归一化后的权重，参与计算的成分权重之和为 1
+    pub weight: f64,
+    /// 报价的接收时间
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 交易对的指数价格和标记价格
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct MarkPrice {
+    pub symbol: Symbol,
+    /// 外部价格源的加权指数价格
+    pub index_price: f64,
+    /// 指数价格加上平滑后的基差
+    pub mark_price: f64,
+    /// 参与计算的成分报价
+    pub components: Vec<IndexComponent>,
+    pub timestamp: DateTime<Utc>,
+}
+
 /// 市场数据
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct MarketData {
     pub symbol: Symbol,
     pub last_price: f64,
@@ -263,8 +1085,293 @@ pub struct MarketData {
     pub timestamp: DateTime<Utc>,
 }
 
+/// 交易对的 24 小时行情和最优买卖价
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Ticker {
+    pub symbol: Symbol,
+    pub last_price: f64,
+    /// 24 小时价格变化百分比
+    pub price_change_percent: f64,
+    pub high_price: f64,
+    pub low_price: f64,
+    /// 最近 24 小时的基础资产成交量
+    pub volume: f64,
+    /// 最近 24 小时的计价资产成交额
+    pub quote_volume: f64,
+    pub trade_count: u64,
+    pub bid_price: Option<f64>,
+    pub bid_quantity: f64,
+    pub ask_price: Option<f64>,
+    pub ask_quantity: f64,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 用户在交易对上的持仓
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Position {
+    pub user_id: String,
+    pub symbol: Symbol,
+    /// 带符号的净持仓，正数为多头、负数为空头
+    pub quantity: f64,
+    /// 开仓均价
+    pub entry_price: f64,
+    /// 已实现盈亏（计价货币）
+    pub realized_pnl: f64,
+    /// 计算未实现盈亏的参考价：有标记价格时为标记价格，否则为最新成交价
+    pub mark_price: Option<f64>,
+    /// 按参考价计算的未实现盈亏，没有参考价时为空
+    pub unrealized_pnl: Option<f64>,
+}
+
+/// 交易对的未平仓量
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct OpenInterest {
+    pub symbol: Symbol,
+    /// 全部多头持仓之和（等于全部空头持仓之和）
+    pub open_interest: f64,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 单个持仓的保证金占用
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PositionMargin {
+    pub symbol: Symbol,
+    /// 带符号的净持仓
+    pub quantity: f64,
+    /// 计算名义价值的标记价格，没有标记价格时为最新成交价
+    pub mark_price: Option<f64>,
+    /// 持仓名义价值（数量绝对值 × 标记价格）
+    pub notional: f64,
+    pub initial_margin: f64,
+    pub maintenance_margin: f64,
+}
+
+/// 账户保证金状况
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct MarginAccount {
+    pub user_id: String,
+    /// 保证金资产的余额
+    pub collateral: f64,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    /// 账户权益：保证金余额加上已实现和未实现盈亏
+    pub equity: f64,
+    /// 当前持仓的初始保证金合计
+    pub initial_margin: f64,
+    /// 当前持仓的维持保证金合计
+    pub maintenance_margin: f64,
+    /// 保证金率：维持保证金 / 账户权益，达到 1 时待强平；权益不为正时为空
+    pub margin_ratio: Option<f64>,
+    /// 是否已被标记为待强平
+    pub liquidation: bool,
+    pub positions: Vec<PositionMargin>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 强平引擎代用户提交的平仓单
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct LiquidationOrder {
+    #[schema(value_type = u64)]
+    pub order_id: OrderId,
+    pub symbol: Symbol,
+    pub side: OrderSide,
+    pub quantity: f64,
+    /// 价格带边界的限价，超出价格带的部分不成交并被撤销
+    pub price: f64,
+    pub filled_quantity: f64,
+    /// 提交失败（如交易对暂停）时的原因
+    pub error: Option<String>,
+}
+
+/// 账户强平事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct LiquidationEvent {
+    pub id: Uuid,
+    pub user_id: String,
+    /// 触发强平时的账户权益
+    pub equity: f64,
+    /// 触发强平时的维持保证金
+    pub maintenance_margin: f64,
+    /// 被撤销的挂单
+    #[schema(value_type = Vec<u64>)]
+    pub cancelled_orders: Vec<OrderId>,
+    pub orders: Vec<LiquidationOrder>,
+    /// 保险基金收到的强平手续费
+    pub liquidation_fee: f64,
+    /// 保险基金弥补的穿仓亏损
+    pub insurance_fund_payout: f64,
+    /// 强平后的账户权益
+    pub remaining_equity: f64,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 公开的强平事件：只含强平单的交易对、方向、数量和价格，不含用户、账户和订单信息，
+/// 用于公共强平频道
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PublicLiquidation {
+    pub orders: Vec<PublicLiquidationOrder>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 公开强平事件中的一笔强平单
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PublicLiquidationOrder {
+    pub symbol: Symbol,
+    pub side: OrderSide,
+    pub quantity: f64,
+    pub price: f64,
+}
+
+impl From<&LiquidationEvent> for PublicLiquidation {
+    fn from(event: &LiquidationEvent) -> Self {
+        Self {
+            orders: event
+                .orders
+                .iter()
+                .map(|order| PublicLiquidationOrder {
+                    symbol: order.symbol,
+                    side: order.side,
+                    quantity: order.quantity,
+                    price: order.price,
+                })
+                .collect(),
+            timestamp: event.timestamp,
+        }
+    }
+}
+
+/// 一次资金费结算
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FundingRate {
+    pub symbol: Symbol,
+    /// 资金费率（小数），为正时多头向空头支付
+    pub funding_rate: f64,
+    /// 结算周期内的平均溢价（小数）
+    pub premium_index: f64,
+    /// 结算时的标记价格
+    pub mark_price: f64,
+    /// 结算时的指数价格
+    pub index_price: f64,
+    /// 参与结算的持仓数量
+    pub positions: usize,
+    /// 多头支付（或收取）的资金费总额
+    pub total_payment: f64,
+    pub funding_time: DateTime<Utc>,
+}
+
+/// 用户资产余额
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Balance {
+    pub user_id: String,
+    pub asset: String,
+    pub available: f64,
+}
+
+impl Balance {
+    pub fn empty(user_id: &str, asset: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            asset: asset.to_string(),
+            available: 0.0,
+        }
+    }
+}
+
+/// 流动性角色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LiquidityRole {
+    /// 挂单方，提供流动性
+    Maker,
+    /// 吃单方，消耗流动性
+    Taker,
+}
+
+/// 用户视角的单笔成交，不包含对手方信息
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Fill {
+    pub trade_id: TradeId,
+    pub order_id: OrderId,
+    pub symbol: Symbol,
+    pub side: OrderSide,
+    pub price: f64,
+    pub quantity: f64,
+    pub role: LiquidityRole,
+    /// 手续费，负数表示返佣
+    pub fee: f64,
+    /// 手续费币种，即交易对的计价货币
+    pub fee_asset: String,
+    pub timestamp: DateTime<Utc>,
+    /// 订单的自定义标签
+    #[serde(default)]
+    pub tag: Option<String>,
+}
+
+/// 订单及其逐笔成交明细
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OrderDetail {
+    #[serde(flatten)]
+    pub order: Order,
+    /// 订单的逐笔成交，按成交ID从旧到新，包含流动性角色和手续费
+    pub fills: Vec<Fill>,
+}
+
+/// 余额流水类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerEntryType {
+    /// 充值
+    Deposit,
+    /// 提现
+    Withdrawal,
+    /// 划转转入
+    TransferIn,
+    /// 划转转出
+    TransferOut,
+    /// 成交结算（沙盒模式）
+    Trade,
+    /// 成交手续费（沙盒模式），负数金额为扣费
+    Fee,
+    /// 强平手续费，从被强平用户转入保险基金
+    LiquidationFee,
+    /// 保险基金弥补强平穿仓亏损
+    InsuranceFund,
+    /// 永续合约资金费，负数金额为支付
+    Funding,
+    /// 手续费结算记入收入账户的净额，负数为返佣池支付的超额返佣
+    FeeRevenue,
+}
+
+/// 余额流水
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LedgerEntry {
+    pub id: Uuid,
+    pub user_id: String,
+    pub asset: String,
+    /// 变动数量，正数为增加，负数为减少
+    pub amount: f64,
+    /// 变动后的可用余额
+    pub balance_after: f64,
+    pub entry_type: LedgerEntryType,
+    pub idempotency_key: String,
+    /// 划转对手方
+    pub counterparty: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 余额变动推送
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceUpdate {
+    pub user_id: String,
+    pub asset: String,
+    pub delta: f64,
+    pub balance: f64,
+    pub reason: LedgerEntryType,
+    pub timestamp: DateTime<Utc>,
+}
+
 /// API 请求和响应类型
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateOrderRequest {
     pub symbol: Symbol,
     pub side: OrderSide,
@@ -272,22 +1379,84 @@ pub struct CreateOrderRequest {
     pub quantity: f64,
     pub price: Option<f64>,
     pub user_id: String,
+    #[serde(default)]
+    pub time_in_force: TimeInForce,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub stop_price: Option<f64>,
+    #[serde(default)]
+    pub trailing_offset: Option<TrailingOffset>,
+    #[serde(default)]
+    pub reduce_only: bool,
+    #[serde(default)]
+    pub max_slippage_bps: Option<f64>,
+    #[serde(default)]
+    pub quote_quantity: Option<f64>,
+    #[serde(default)]
+    pub min_fill_qty: Option<f64>,
+    #[serde(default)]
+    pub external_id: Option<Uuid>,
+    /// 自定义标签（如策略ID），最长 64 个字符
+    #[serde(default)]
+    pub tag: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl CreateOrderRequest {
+    /// 转换为待提交的订单，字段合法性由引擎下单时校验
+    pub fn into_order(self) -> Order {
+        let mut order = Order::new(
+            self.symbol,
+            self.side,
+            self.order_type,
+            self.quantity,
+            self.price,
+            self.user_id,
+        );
+        order.time_in_force = self.time_in_force;
+        order.expires_at = self.expires_at;
+        order.stop_price = self.stop_price;
+        order.trailing_offset = self.trailing_offset;
+        order.reduce_only = self.reduce_only;
+        order.max_slippage_bps = self.max_slippage_bps;
+        order.quote_quantity = self.quote_quantity;
+        order.min_fill_qty = self.min_fill_qty;
+        order.external_id = self.external_id;
+        order.tag = self.tag;
+        order
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateOrderResponse {
-    pub order_id: Uuid,
+    pub order_id: OrderId,
     pub status: OrderStatus,
     pub message: String,
 }
 
+/// 批量查询订单请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct OrderQueryRequest {
+    /// 数字订单ID或下单时提供的外部 UUID
+    pub order_ids: Vec<String>,
+}
+
+/// 批量查询订单结果
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct OrderQueryResponse {
+    /// 查到的订单，按请求顺序排列
+    pub orders: Vec<Order>,
+    /// 不存在的订单ID，保持请求中的写法
+    pub not_found: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CancelOrderRequest {
-    pub order_id: Uuid,
+    pub order_id: OrderId,
     pub user_id: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CancelOrderResponse {
     pub success: bool,
     pub message: String,
@@ -299,24 +1468,164 @@ pub struct GetOrderBookRequest {
     pub depth: Option<usize>,
 }
 
+/// 充值 / 提现请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BalanceChangeRequest {
+    pub asset: String,
+    pub amount: f64,
+    pub idempotency_key: String,
+}
+
+/// 内部划转请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TransferRequest {
+    pub to_user_id: String,
+    pub asset: String,
+    pub amount: f64,
+    pub idempotency_key: String,
+}
+
+/// 沙盒账户重置请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SandboxResetRequest {
+    pub user_id: String,
+}
+
+/// 暂停交易请求
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct HaltSymbolRequest {
+    /// 为 true 时进入只撤单状态，否则完全暂停
+    #[serde(default)]
+    pub cancel_only: bool,
+    pub reason: Option<String>,
+}
+
+/// 撤销成交请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BustTradeRequest {
+    /// 撤销原因，记录在审计日志和撤销事件中
+    pub reason: String,
+    /// 为 true 时被撤销的数量恢复为挂单方的剩余数量，可以继续成交
+    #[serde(default)]
+    pub restore_quantity: bool,
+}
+
+/// 场外成交申报请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReportTradeRequest {
+    pub symbol: Symbol,
+    pub buyer_id: String,
+    pub seller_id: String,
+    pub quantity: f64,
+    pub price: f64,
+}
+
+/// 场外成交申报结果
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TradeReport {
+    pub trade: Trade,
+    /// 交易对内场外成交的申报序号，从 1 开始连续递增
+    pub sequence: u64,
+}
+
+/// 修改日志级别请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SetLogLevelRequest {
+    /// `EnvFilter` 过滤规则，如 `info` 或 `matching_engine=debug,tower_http=warn`
+    pub level: String,
+}
+
+/// 创建用户数据流 listen key 请求
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateListenKeyRequest {
+    pub user_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateListenKeyResponse {
+    pub listen_key: String,
+}
+
 /// WebSocket 消息类型
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum WebSocketMessage {
     #[serde(rename = "trade")]
     Trade(Trade),
+    #[serde(rename = "otc_trade")]
+    OtcTrade(Trade),
+    #[serde(rename = "aggTrade")]
+    AggTrade(AggTrade),
+    /// 成交被撤销，按原成交的交易对和双方推送
+    #[serde(rename = "trade_bust")]
+    TradeBust(TradeBust),
     #[serde(rename = "orderbook")]
     OrderBook(OrderBookDepth),
+    #[serde(rename = "bookTicker")]
+    BookTicker(BookTicker),
     #[serde(rename = "market_data")]
     MarketData(MarketData),
     #[serde(rename = "order_update")]
     OrderUpdate(Order),
+    #[serde(rename = "balance_update")]
+    BalanceUpdate(BalanceUpdate),
+    #[serde(rename = "symbol_status")]
+    SymbolStatus(SymbolStatus),
+    #[serde(rename = "auction_indicative")]
+    AuctionIndicative(AuctionIndicative),
+    #[serde(rename = "mark_price")]
+    MarkPrice(MarkPrice),
+    #[serde(rename = "liquidation")]
+    Liquidation(LiquidationEvent),
+    /// 交易对上市或下市，推送给所有连接
+    #[serde(rename = "symbol_listing")]
+    SymbolListing(SymbolListing),
     #[serde(rename = "error")]
     Error { message: String },
+    /// 客户端消费落后、丢失了部分消息，随后会推送最新快照
+    #[serde(rename = "resync")]
+    Resync { channel: String, skipped: u64 },
+    /// 对客户端 `{"op": "ping"}` 的应答，原样带回请求中的 id
+    #[serde(rename = "pong")]
+    Pong { id: Option<u64> },
 }
 
-/// 撮合引擎统计信息
+/// 停机时写出的引擎快照
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineSnapshot {
+    pub timestamp: DateTime<Utc>,
+    pub stats: EngineStats,
+    /// 未完成的订单（含订单簿挂单和未触发的条件单）
+    pub open_orders: Vec<Order>,
+    pub market_data: Vec<MarketData>,
+}
+
+/// 单个交易对的统计信息
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SymbolStats {
+    pub symbol: Symbol,
+    /// 累计受理的订单数
+    pub total_orders: u64,
+    pub total_trades: u64,
+    /// 累计成交量（基础货币）
+    pub volume: f64,
+    /// 累计成交额（计价货币）
+    pub quote_volume: f64,
+    /// 买盘挂单剩余数量合计
+    pub open_bid_quantity: f64,
+    /// 卖盘挂单剩余数量合计
+    pub open_ask_quantity: f64,
+    pub open_bid_orders: usize,
+    pub open_ask_orders: usize,
+    pub best_bid: Option<f64>,
+    pub best_ask: Option<f64>,
+    pub spread: Option<f64>,
+    pub last_price: Option<f64>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 撮合引擎统计信息
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct EngineStats {
     pub total_orders: u64,
     pub total_trades: u64,
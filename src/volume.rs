@@ -0,0 +1,292 @@
+//! 成交量统计
+//!
+//! 成交发生时按交易对累加到 1 分钟桶中，查询时把时间范围内的分钟桶合并为请求的周期，
+//! 不需要扫描成交记录。分钟桶只保留最近 `retention_hours` 小时。
+use crate::symbol::SymbolId;
+use crate::types::*;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use utoipa::ToSchema;
+
+/// 成交量统计周期
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum VolumeInterval {
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[default]
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "4h")]
+    FourHours,
+    #[serde(rename = "1d")]
+    OneDay,
+}
+
+impl VolumeInterval {
+    pub fn duration(self) -> Duration {
+        Duration::minutes(match self {
+            Self::OneMinute => 1,
+            Self::FiveMinutes => 5,
+            Self::FifteenMinutes => 15,
+            Self::OneHour => 60,
+            Self::FourHours => 4 * 60,
+            Self::OneDay => 24 * 60,
+        })
+    }
+}
+
+/// 一个周期内的成交统计
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct VolumeBucket {
+    /// 周期开始时间（含）
+    pub open_time: DateTime<Utc>,
+    /// 周期结束时间（不含）
+    pub close_time: DateTime<Utc>,
+    /// 基础资产成交量
+    pub volume: f64,
+    /// 计价资产成交额
+    pub quote_volume: f64,
+    pub trade_count: u64,
+    /// 成交量加权平均价
+    pub vwap: f64,
+    /// 吃单方为买方的成交量
+    pub taker_buy_volume: f64,
+    /// 吃单方为卖方的成交量；集合竞价成交没有吃单方，不计入买卖任一侧
+    pub taker_sell_volume: f64,
+}
+
+/// 交易对按周期汇总的成交统计
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VolumeSeries {
+    pub symbol: Symbol,
+    pub interval: VolumeInterval,
+    /// 有成交的周期，按开始时间从早到晚
+    pub buckets: Vec<VolumeBucket>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 1 分钟桶的累计值
+#[derive(Debug, Clone, Copy, Default)]
+struct MinuteBucket {
+    volume: f64,
+    quote_volume: f64,
+    trade_count: u64,
+    taker_buy_volume: f64,
+    taker_sell_volume: f64,
+}
+
+impl MinuteBucket {
+    /// 累加一笔成交，`sign` 为 -1 时撤销
+    fn apply(&mut self, trade: &Trade, sign: f64) {
+        self.volume += sign * trade.quantity;
+        self.quote_volume += sign * trade.quantity * trade.price;
+        match trade.taker_side {
+            Some(OrderSide::Buy) => self.taker_buy_volume += sign * trade.quantity,
+            Some(OrderSide::Sell) => self.taker_sell_volume += sign * trade.quantity,
+            None => {}
+        }
+    }
+
+    fn summary(&self, open_time: DateTime<Utc>, close_time: DateTime<Utc>) -> VolumeBucket {
+        VolumeBucket {
+            open_time,
+            close_time,
+            volume: self.volume,
+            quote_volume: self.quote_volume,
+            trade_count: self.trade_count,
+            vwap: if self.volume > 0.0 {
+                self.quote_volume / self.volume
+            } else {
+                0.0
+            },
+            taker_buy_volume: self.taker_buy_volume,
+            taker_sell_volume: self.taker_sell_volume,
+        }
+    }
+
+    fn merge(&mut self, other: &MinuteBucket) {
+        self.volume += other.volume;
+        self.quote_volume += other.quote_volume;
+        self.trade_count += other.trade_count;
+        self.taker_buy_volume += other.taker_buy_volume;
+        self.taker_sell_volume += other.taker_sell_volume;
+    }
+}
+
+/// 各交易对的分钟成交统计
+#[derive(Debug)]
+pub struct VolumeStats {
+    retention: Duration,
+    buckets: RwLock<HashMap<SymbolId, BTreeMap<DateTime<Utc>, MinuteBucket>>>,
+}
+
+impl VolumeStats {
+    pub fn new(retention_hours: u64) -> Self {
+        Self {
+            retention: Duration::hours(retention_hours as i64),
+            buckets: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 累加一笔成交，同时丢弃该交易对超出保留时长的分钟桶
+    pub fn record(&self, trade: &Trade) {
+        let mut buckets = self.buckets.write();
+        let minutes = buckets.entry(trade.symbol.id()).or_default();
+        let bucket = minutes.entry(minute_of(trade.timestamp)).or_default();
+        bucket.apply(trade, 1.0);
+        bucket.trade_count += 1;
+
+        let cutoff = trade.timestamp - self.retention;
+        while minutes
+            .first_key_value()
+            .is_some_and(|(minute, _)| *minute < cutoff)
+        {
+            minutes.pop_first();
+        }
+    }
+
+    /// 撤销一笔成交（成交被撤销时），分钟桶中没有剩余成交时删除该桶
+    pub fn reverse(&self, trade: &Trade) {
+        let mut buckets = self.buckets.write();
+        let Some(minutes) = buckets.get_mut(&trade.symbol.id()) else {
+            return;
+        };
+        let minute = minute_of(trade.timestamp);
+        let Some(bucket) = minutes.get_mut(&minute) else {
+            return;
+        };
+        bucket.apply(trade, -1.0);
+        bucket.trade_count = bucket.trade_count.saturating_sub(1);
+        if bucket.trade_count == 0 {
+            minutes.remove(&minute);
+        }
+    }
+
+    /// 汇总分钟在 `[from, to)` 内的成交，周期按 UTC 对齐；没有成交的周期不返回
+    pub fn series(
+        &self,
+        symbol: &Symbol,
+        interval: VolumeInterval,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<VolumeBucket> {
+        let buckets = self.buckets.read();
+        let Some(minutes) = buckets.get(&symbol.id()) else {
+            return Vec::new();
+        };
+        if from >= to {
+            return Vec::new();
+        }
+
+        let step = interval.duration();
+        let mut merged: BTreeMap<DateTime<Utc>, MinuteBucket> = BTreeMap::new();
+        for (minute, bucket) in minutes.range(from..to) {
+            let open_time = minute.duration_trunc(step).unwrap_or(*minute);
+            merged.entry(open_time).or_default().merge(bucket);
+        }
+
+        merged
+            .into_iter()
+            .map(|(open_time, bucket)| bucket.summary(open_time, open_time + step))
+            .collect()
+    }
+
+    /// 把分钟在 `[from, to)` 内的成交汇总为一个区间，用于滚动窗口统计
+    pub fn window(&self, symbol: &Symbol, from: DateTime<Utc>, to: DateTime<Utc>) -> VolumeBucket {
+        let mut total = MinuteBucket::default();
+        if from < to {
+            if let Some(minutes) = self.buckets.read().get(&symbol.id()) {
+                for bucket in minutes.range(from..to).map(|(_, bucket)| bucket) {
+                    total.merge(bucket);
+                }
+            }
+        }
+        total.summary(from, to)
+    }
+}
+
+fn minute_of(time: DateTime<Utc>) -> DateTime<Utc> {
+    time.duration_trunc(Duration::minutes(1)).unwrap_or(time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn trade(
+        symbol: Symbol,
+        quantity: f64,
+        price: f64,
+        timestamp: DateTime<Utc>,
+        taker_side: Option<OrderSide>,
+    ) -> Trade {
+        let buy = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            quantity,
+            Some(price),
+            "alice".to_string(),
+        );
+        let sell = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::Limit,
+            quantity,
+            Some(price),
+            "bob".to_string(),
+        );
+        let mut trade = Trade::new(symbol, &buy, &sell, quantity, price);
+        trade.timestamp = timestamp;
+        trade.taker_side = taker_side;
+        trade
+    }
+
+    #[test]
+    fn test_series_merges_minutes_into_interval() {
+        let stats = VolumeStats::new(24);
+        let btc = Symbol::new("BTC", "USDT");
+        let at = |hour, minute| Utc.with_ymd_and_hms(2024, 1, 5, hour, minute, 30).unwrap();
+
+        let first = trade(btc, 1.0, 100.0, at(10, 5), Some(OrderSide::Buy));
+        stats.record(&first);
+        stats.record(&trade(btc, 3.0, 200.0, at(10, 59), Some(OrderSide::Sell)));
+        stats.record(&trade(btc, 2.0, 150.0, at(11, 0), None));
+
+        let series = stats.series(&btc, VolumeInterval::OneHour, at(0, 0), at(23, 0));
+        assert_eq!(series.len(), 2);
+        assert_eq!(
+            series[0].open_time,
+            Utc.with_ymd_and_hms(2024, 1, 5, 10, 0, 0).unwrap()
+        );
+        assert_eq!(series[0].trade_count, 2);
+        assert_eq!(series[0].volume, 4.0);
+        assert_eq!(series[0].vwap, 175.0);
+        assert_eq!(series[0].taker_buy_volume, 1.0);
+        assert_eq!(series[0].taker_sell_volume, 3.0);
+        // 集合竞价成交只计入总量
+        assert_eq!(series[1].volume, 2.0);
+        assert_eq!(
+            series[1].taker_buy_volume + series[1].taker_sell_volume,
+            0.0
+        );
+
+        stats.reverse(&first);
+        let series = stats.series(&btc, VolumeInterval::OneMinute, at(10, 0), at(10, 59));
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].volume, 3.0);
+
+        // 超出保留时长的分钟桶在下一笔成交时被丢弃
+        let next_day = Utc.with_ymd_and_hms(2024, 1, 6, 12, 0, 0).unwrap();
+        stats.record(&trade(btc, 1.0, 100.0, next_day, Some(OrderSide::Buy)));
+        let series = stats.series(&btc, VolumeInterval::OneDay, at(0, 0), next_day);
+        assert!(series.is_empty());
+    }
+}
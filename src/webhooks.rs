@@ -0,0 +1,730 @@
+//! Webhook 回调（`webhooks` 特性）
+//!
+//! 用户注册回调地址和签名密钥后，引擎把该用户的成交和订单终态 POST 到回调地址，
+//! 适合无法保持 WebSocket 长连接的用户。事件先进入独立的有界投递队列，再由后台任务并发投递，
+//! 失败时按指数退避重试，不会阻塞撮合。
+//!
+//! 每个请求带 `X-Webhook-Id`（事件ID，重试时不变，可用于去重）、`X-Webhook-Timestamp`（Unix 秒）
+//! 和 `X-Webhook-Signature` 头，签名为 `sha256=` 加上以密钥对 `<timestamp>.<body>` 计算的
+//! HMAC-SHA256 十六进制值。
+use crate::api::{ApiError, FieldError, ValidJson, Validate};
+use crate::config::WebhookConfig;
+use crate::matching_engine::MatchingEngine;
+use crate::types::*;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use metrics::counter;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// 事件ID请求头
+pub const EVENT_ID_HEADER: &str = "X-Webhook-Id";
+/// 签名时间戳请求头
+pub const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+/// 签名请求头
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+/// 签名密钥的最小长度
+const MIN_SECRET_LEN: usize = 16;
+
+/// Webhook 事件类型
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventKind {
+    /// 用户订单的成交
+    Fill,
+    /// 订单进入终态（完全成交、撤销、拒绝、到期）
+    Order,
+}
+
+/// 已注册的回调
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub user_id: String,
+    pub url: String,
+    /// 签名密钥，注册后不再返回
+    #[serde(skip)]
+    pub secret: String,
+    /// 订阅的事件类型
+    pub events: Vec<WebhookEventKind>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    fn wants(&self, kind: WebhookEventKind) -> bool {
+        self.events.contains(&kind)
+    }
+}
+
+/// 注册回调请求
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RegisterWebhookRequest {
+    /// 回调地址，须为 http 或 https
+    pub url: String,
+    /// HMAC 签名密钥，至少 16 个字符
+    pub secret: String,
+    /// 订阅的事件类型，为空时订阅全部
+    #[serde(default)]
+    pub events: Vec<WebhookEventKind>,
+}
+
+impl Validate for RegisterWebhookRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        match reqwest::Url::parse(&self.url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(_) => errors.push(FieldError::new("url", "must use http or https")),
+            Err(e) => errors.push(FieldError::new("url", &e.to_string())),
+        }
+        if self.secret.len() < MIN_SECRET_LEN {
+            errors.push(FieldError::new(
+                "secret",
+                &format!("must be at least {} characters", MIN_SECRET_LEN),
+            ));
+        }
+        errors
+    }
+}
+
+/// 按用户登记的回调
+#[derive(Debug)]
+pub struct WebhookRegistry {
+    by_user: RwLock<HashMap<String, Vec<Arc<Webhook>>>>,
+    max_per_user: usize,
+}
+
+impl WebhookRegistry {
+    pub fn new(max_per_user: usize) -> Self {
+        Self {
+            by_user: RwLock::new(HashMap::new()),
+            max_per_user,
+        }
+    }
+
+    /// 为用户注册回调，超过每用户上限时返回错误
+    pub fn register(
+        &self,
+        user_id: &str,
+        request: RegisterWebhookRequest,
+    ) -> Result<Webhook, String> {
+        let mut by_user = self.by_user.write();
+        let webhooks = by_user.entry(user_id.to_string()).or_default();
+        if webhooks.len() >= self.max_per_user {
+            return Err(format!(
+                "User {} already has {} webhooks",
+                user_id, self.max_per_user
+            ));
+        }
+
+        let mut events = request.events;
+        if events.is_empty() {
+            events = vec![WebhookEventKind::Fill, WebhookEventKind::Order];
+        }
+        events.sort_unstable();
+        events.dedup();
+        let webhook = Webhook {
+            id: Uuid::new_v4(),
+            user_id: user_id.to_string(),
+            url: request.url,
+            secret: request.secret,
+            events,
+            created_at: Utc::now(),
+        };
+        webhooks.push(Arc::new(webhook.clone()));
+        Ok(webhook)
+    }
+
+    pub fn list(&self, user_id: &str) -> Vec<Webhook> {
+        self.by_user
+            .read()
+            .get(user_id)
+            .map(|webhooks| webhooks.iter().map(|webhook| (**webhook).clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// 删除用户的回调，回调不存在时返回 false
+    pub fn remove(&self, user_id: &str, webhook_id: Uuid) -> bool {
+        let mut by_user = self.by_user.write();
+        let Some(webhooks) = by_user.get_mut(user_id) else {
+            return false;
+        };
+        let before = webhooks.len();
+        webhooks.retain(|webhook| webhook.id != webhook_id);
+        let removed = webhooks.len() < before;
+        if webhooks.is_empty() {
+            by_user.remove(user_id);
+        }
+        removed
+    }
+
+    /// 订阅了该类事件的用户回调
+    fn targets(&self, user_id: &str, kind: WebhookEventKind) -> Vec<Arc<Webhook>> {
+        self.by_user
+            .read()
+            .get(user_id)
+            .map(|webhooks| {
+                webhooks
+                    .iter()
+                    .filter(|webhook| webhook.wants(kind))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// 创建回调管理路由，挂载在 API 前缀下
+pub fn create_webhook_router(registry: Arc<WebhookRegistry>) -> Router {
+    Router::new()
+        .route(
+            "/webhooks/:user_id",
+            get(list_webhooks).post(register_webhook),
+        )
+        .route("/webhooks/:user_id/:webhook_id", delete(remove_webhook))
+        .with_state(registry)
+}
+
+async fn register_webhook(
+    State(registry): State<Arc<WebhookRegistry>>,
+    Path(user_id): Path<String>,
+    ValidJson(request): ValidJson<RegisterWebhookRequest>,
+) -> Result<Json<Webhook>, ApiError> {
+    registry
+        .register(&user_id, request)
+        .map(|webhook| {
+            info!("Registered webhook {} for user {}", webhook.id, user_id);
+            Json(webhook)
+        })
+        .map_err(|e| ApiError::bad_request("webhook_limit_exceeded", e))
+}
+
+async fn list_webhooks(
+    State(registry): State<Arc<WebhookRegistry>>,
+    Path(user_id): Path<String>,
+) -> Json<Vec<Webhook>> {
+    Json(registry.list(&user_id))
+}
+
+async fn remove_webhook(
+    State(registry): State<Arc<WebhookRegistry>>,
+    Path((user_id, webhook_id)): Path<(String, Uuid)>,
+) -> Result<StatusCode, ApiError> {
+    if registry.remove(&user_id, webhook_id) {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::not_found("Webhook not found"))
+    }
+}
+
+/// 回调事件内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum WebhookPayload {
+    /// 用户订单的一笔成交
+    Fill(Fill),
+    /// 进入终态的订单
+    Order(Order),
+}
+
+/// POST 到回调地址的事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEvent {
+    /// 事件ID，重试时不变
+    pub event_id: Uuid,
+    pub webhook_id: Uuid,
+    pub user_id: String,
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub payload: WebhookPayload,
+}
+
+fn signature_mac(secret: &str, timestamp: i64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// 计算 `sha256=<hex>` 格式的签名
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let digest = signature_mac(secret, timestamp, body)
+        .finalize()
+        .into_bytes();
+    format!("sha256={}", hex::encode(digest))
+}
+
+/// 校验回调请求的签名，供接收方使用
+pub fn verify_signature(secret: &str, timestamp: i64, body: &[u8], signature: &str) -> bool {
+    let Some(digest) = signature
+        .strip_prefix("sha256=")
+        .and_then(|digest| hex::decode(digest).ok())
+    else {
+        return false;
+    };
+    signature_mac(secret, timestamp, body)
+        .verify_slice(&digest)
+        .is_ok()
+}
+
+/// 第 `retry` 次重试前的等待时间，从 `retry_backoff_ms` 开始翻倍，不超过 `max_retry_backoff_ms`
+fn retry_backoff(config: &WebhookConfig, retry: u32) -> Duration {
+    let factor = 1u64
+        .checked_shl(retry.saturating_sub(1))
+        .unwrap_or(u64::MAX);
+    Duration::from_millis(
+        config
+            .retry_backoff_ms
+            .saturating_mul(factor)
+            .min(config.max_retry_backoff_ms),
+    )
+}
+
+/// 队列中待投递的事件
+struct Delivery {
+    webhook: Arc<Webhook>,
+    event_id: Uuid,
+    body: Vec<u8>,
+}
+
+/// 订阅引擎的成交和订单更新，为注册了回调的用户生成事件并投递
+pub struct WebhookDispatcher {
+    registry: Arc<WebhookRegistry>,
+    config: WebhookConfig,
+    client: reqwest::Client,
+}
+
+impl WebhookDispatcher {
+    pub fn new(registry: Arc<WebhookRegistry>, config: WebhookConfig) -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.request_timeout_ms))
+            .build()
+            .map_err(|e| format!("Failed to build webhook HTTP client: {}", e))?;
+        Ok(Self {
+            registry,
+            config,
+            client,
+        })
+    }
+
+    /// 订阅引擎并在后台投递，`shutdown` 完成后停止，未完成的投递被丢弃
+    pub fn start(
+        self,
+        engine: &Arc<MatchingEngine>,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> tokio::task::JoinHandle<()> {
+        let trades = engine.subscribe_trades();
+        let orders = engine.subscribe_orders();
+        let engine = Arc::clone(engine);
+        tokio::spawn(self.run(engine, trades, orders, shutdown))
+    }
+
+    async fn run(
+        self,
+        engine: Arc<MatchingEngine>,
+        mut trades: broadcast::Receiver<Trade>,
+        mut orders: broadcast::Receiver<Order>,
+        shutdown: impl Future<Output = ()>,
+    ) {
+        info!("Webhook dispatcher started");
+        tokio::pin!(shutdown);
+        let (queue, receiver) = mpsc::channel(self.config.queue_size);
+        let mut deliveries = tokio::spawn(deliver_queued(
+            receiver,
+            self.client.clone(),
+            self.config.clone(),
+        ));
+
+        loop {
+            tokio::select! {
+                biased;
+                _ = &mut shutdown => break,
+                result = trades.recv() => match result {
+                    Ok(trade) => self.dispatch_fills(&engine, &trade, &queue),
+                    Err(RecvError::Lagged(skipped)) => lagged("trades", skipped),
+                    Err(RecvError::Closed) => break,
+                },
+                result = orders.recv() => match result {
+                    Ok(order) if order.status.is_terminal() => {
+                        let targets = self.registry.targets(&order.user_id, WebhookEventKind::Order);
+                        for webhook in targets {
+                            enqueue(&queue, webhook, WebhookPayload::Order(order.clone()));
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(skipped)) => lagged("orders", skipped),
+                    Err(RecvError::Closed) => break,
+                },
+                _ = &mut deliveries => {
+                    warn!("Webhook delivery task exited unexpectedly");
+                    break;
+                }
+            }
+        }
+
+        deliveries.abort();
+        info!("Webhook dispatcher stopped");
+    }
+
+    /// 为成交双方中注册了回调的用户生成成交事件
+    fn dispatch_fills(
+        &self,
+        engine: &MatchingEngine,
+        trade: &Trade,
+        queue: &mpsc::Sender<Delivery>,
+    ) {
+        let sides = [
+            (trade.buy_order_id, &trade.buyer_id),
+            (trade.sell_order_id, &trade.seller_id),
+        ];
+        for (order_id, user_id) in sides {
+            let targets = self.registry.targets(user_id, WebhookEventKind::Fill);
+            if targets.is_empty() {
+                continue;
+            }
+            let Some(fill) = engine.get_user_fill(user_id, trade.id, order_id) else {
+                warn!(
+                    "Fill for trade {} order {} not found, webhook skipped",
+                    trade.id, order_id
+                );
+                continue;
+            };
+            for webhook in targets {
+                enqueue(queue, webhook, WebhookPayload::Fill(fill.clone()));
+            }
+        }
+    }
+}
+
+/// 序列化事件并放入投递队列，队列已满时丢弃
+fn enqueue(queue: &mpsc::Sender<Delivery>, webhook: Arc<Webhook>, payload: WebhookPayload) {
+    let event = WebhookEvent {
+        event_id: Uuid::new_v4(),
+        webhook_id: webhook.id,
+        user_id: webhook.user_id.clone(),
+        timestamp: Utc::now(),
+        payload,
+    };
+    let body = match serde_json::to_vec(&event) {
+        Ok(body) => body,
+        Err(e) => {
+            warn!("Failed to serialize webhook event: {}", e);
+            return;
+        }
+    };
+    let delivery = Delivery {
+        webhook,
+        event_id: event.event_id,
+        body,
+    };
+    if queue.try_send(delivery).is_err() {
+        warn!(
+            "Webhook delivery queue is full, event {} dropped",
+            event.event_id
+        );
+        counter!("matching_engine_webhook_deliveries_total", "result" => "dropped").increment(1);
+    }
+}
+
+/// 从队列取出事件并发投递，同时进行的投递数不超过 `max_concurrent_deliveries`
+async fn deliver_queued(
+    mut receiver: mpsc::Receiver<Delivery>,
+    client: reqwest::Client,
+    config: WebhookConfig,
+) {
+    let permits = Arc::new(Semaphore::new(config.max_concurrent_deliveries));
+    let config = Arc::new(config);
+    let mut in_flight = JoinSet::new();
+    while let Some(delivery) = receiver.recv().await {
+        let Ok(permit) = Arc::clone(&permits).acquire_owned().await else {
+            break;
+        };
+        while in_flight.try_join_next().is_some() {}
+
+        let client = client.clone();
+        let config = Arc::clone(&config);
+        in_flight.spawn(async move {
+            deliver(&client, &config, &delivery).await;
+            drop(permit);
+        });
+    }
+}
+
+/// 投递单个事件，网络错误、超时、5xx、408 和 429 按指数退避重试，其余 4xx 不重试
+async fn deliver(client: &reqwest::Client, config: &WebhookConfig, delivery: &Delivery) {
+    let mut retry = 0;
+    loop {
+        let timestamp = Utc::now().timestamp();
+        let result = client
+            .post(&delivery.webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_ID_HEADER, delivery.event_id.to_string())
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(
+                SIGNATURE_HEADER,
+                sign(&delivery.webhook.secret, timestamp, &delivery.body),
+            )
+            .body(delivery.body.clone())
+            .send()
+            .await;
+
+        let (error, retryable) = match result {
+            Ok(response) if response.status().is_success() => {
+                counter!("matching_engine_webhook_deliveries_total", "result" => "delivered")
+                    .increment(1);
+                return;
+            }
+            Ok(response) => {
+                let status = response.status();
+                let retryable = status.is_server_error()
+                    || status == reqwest::StatusCode::REQUEST_TIMEOUT
+                    || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+                (format!("HTTP {}", status), retryable)
+            }
+            Err(e) => (e.to_string(), true),
+        };
+
+        if !retryable || retry >= config.max_retries {
+            warn!(
+                "Dropping webhook event {} for {} after {} retries: {}",
+                delivery.event_id, delivery.webhook.url, retry, error
+            );
+            counter!("matching_engine_webhook_deliveries_total", "result" => "failed").increment(1);
+            return;
+        }
+
+        retry += 1;
+        warn!(
+            "Webhook event {} to {} failed (retry {}): {}",
+            delivery.event_id, delivery.webhook.url, retry, error
+        );
+        counter!("matching_engine_webhook_retries_total").increment(1);
+        tokio::time::sleep(retry_backoff(config, retry)).await;
+    }
+}
+
+fn lagged(stream: &'static str, skipped: u64) {
+    warn!(
+        "Webhook dispatcher lagged, {} {} updates skipped",
+        skipped, stream
+    );
+    counter!("matching_engine_webhook_skipped_total", "stream" => stream).increment(skipped);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Bytes, http::HeaderMap, routing::post};
+    use parking_lot::Mutex;
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+
+    const SECRET: &str = "0123456789abcdef";
+
+    fn request(url: &str, events: Vec<WebhookEventKind>) -> RegisterWebhookRequest {
+        RegisterWebhookRequest {
+            url: url.to_string(),
+            secret: SECRET.to_string(),
+            events,
+        }
+    }
+
+    #[test]
+    fn test_signature_and_backoff() {
+        let body = br#"{"type":"fill"}"#;
+        let signature = sign(SECRET, 1_700_000_000, body);
+        assert!(signature.starts_with("sha256="));
+        assert!(verify_signature(SECRET, 1_700_000_000, body, &signature));
+        assert!(!verify_signature(SECRET, 1_700_000_001, body, &signature));
+        assert!(!verify_signature(
+            "another-secret-value",
+            1_700_000_000,
+            body,
+            &signature
+        ));
+        assert!(!verify_signature(SECRET, 1_700_000_000, b"{}", &signature));
+
+        let config = WebhookConfig {
+            retry_backoff_ms: 100,
+            max_retry_backoff_ms: 1000,
+            ..Default::default()
+        };
+        let backoffs: Vec<u64> = (1..=6)
+            .map(|retry| retry_backoff(&config, retry).as_millis() as u64)
+            .collect();
+        assert_eq!(backoffs, vec![100, 200, 400, 800, 1000, 1000]);
+        assert_eq!(retry_backoff(&config, 200).as_millis(), 1000);
+    }
+
+    #[test]
+    fn test_registry() {
+        let registry = WebhookRegistry::new(2);
+        let all = registry
+            .register("user1", request("https://example.com/hook", Vec::new()))
+            .unwrap();
+        assert_eq!(
+            all.events,
+            vec![WebhookEventKind::Fill, WebhookEventKind::Order]
+        );
+        let fills = registry
+            .register(
+                "user1",
+                request("https://example.com/fills", vec![WebhookEventKind::Fill]),
+            )
+            .unwrap();
+        assert!(registry
+            .register("user1", request("https://example.com/more", Vec::new()))
+            .is_err());
+
+        assert_eq!(registry.targets("user1", WebhookEventKind::Fill).len(), 2);
+        assert_eq!(registry.targets("user1", WebhookEventKind::Order).len(), 1);
+        assert!(registry.targets("user2", WebhookEventKind::Fill).is_empty());
+
+        // 密钥不随回调返回
+        let json = serde_json::to_value(&registry.list("user1")[0]).unwrap();
+        assert!(json.get("secret").is_none());
+
+        assert!(!registry.remove("user2", fills.id));
+        assert!(registry.remove("user1", fills.id));
+        assert_eq!(registry.list("user1").len(), 1);
+
+        assert!(!request("ftp://example.com", Vec::new())
+            .validate()
+            .is_empty());
+        let mut short_secret = request("https://example.com", Vec::new());
+        short_secret.secret = "short".to_string();
+        assert_eq!(short_secret.validate().len(), 1);
+    }
+
+    #[derive(Clone, Default)]
+    struct Receiver {
+        attempts: Arc<Mutex<usize>>,
+        events: Arc<Mutex<Vec<(HeaderMap, Bytes)>>>,
+    }
+
+    /// 第一次请求返回 500，之后记录请求并返回 200
+    async fn receive(
+        State(receiver): State<Receiver>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> StatusCode {
+        let mut attempts = receiver.attempts.lock();
+        *attempts += 1;
+        if *attempts == 1 {
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+        receiver.events.lock().push((headers, body));
+        StatusCode::OK
+    }
+
+    #[tokio::test]
+    async fn test_dispatcher_signs_and_retries() {
+        let receiver = Receiver::default();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let app = Router::new()
+            .route("/hook", post(receive))
+            .with_state(receiver.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let engine = Arc::new(MatchingEngine::new());
+        let registry = Arc::new(WebhookRegistry::new(5));
+        registry
+            .register("taker", request(&url, Vec::new()))
+            .unwrap();
+        let config = WebhookConfig {
+            enabled: true,
+            retry_backoff_ms: 10,
+            ..Default::default()
+        };
+        let (stop, stopped) = oneshot::channel::<()>();
+        let handle = WebhookDispatcher::new(Arc::clone(&registry), config)
+            .unwrap()
+            .start(&engine, async move {
+                let _ = stopped.await;
+            });
+
+        let symbol = Symbol::new("BTC", "USDT");
+        let maker = Order::new(
+            symbol,
+            OrderSide::Sell,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            "maker".to_string(),
+        );
+        engine.submit_order(maker).await.unwrap();
+        let taker = Order::new(
+            symbol,
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(100.0),
+            "taker".to_string(),
+        );
+        engine.submit_order(taker).await.unwrap();
+
+        for _ in 0..100 {
+            if receiver.events.lock().len() >= 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        stop.send(()).unwrap();
+        handle.await.unwrap();
+
+        // 第一次投递失败后重试成功；挂单方未注册回调，只收到吃单方的成交和完全成交
+        let events = receiver.events.lock();
+        assert_eq!(events.len(), 2);
+        assert_eq!(*receiver.attempts.lock(), 3);
+        let mut kinds = Vec::new();
+        for (headers, body) in events.iter() {
+            let header = |name: &str| headers.get(name).unwrap().to_str().unwrap().to_string();
+            let timestamp: i64 = header(TIMESTAMP_HEADER).parse().unwrap();
+            assert!(verify_signature(
+                SECRET,
+                timestamp,
+                body,
+                &header(SIGNATURE_HEADER)
+            ));
+
+            let event: WebhookEvent = serde_json::from_slice(body).unwrap();
+            assert_eq!(event.event_id.to_string(), header(EVENT_ID_HEADER));
+            assert_eq!(event.user_id, "taker");
+            match event.payload {
+                WebhookPayload::Fill(fill) => {
+                    assert_eq!(fill.role, LiquidityRole::Taker);
+                    assert_eq!(fill.side, OrderSide::Buy);
+                    kinds.push("fill");
+                }
+                WebhookPayload::Order(order) => {
+                    assert_eq!(order.status, OrderStatus::Filled);
+                    kinds.push("order");
+                }
+            }
+        }
+        kinds.sort_unstable();
+        assert_eq!(kinds, vec!["fill", "order"]);
+    }
+}
@@ -1,44 +1,101 @@
+use crate::api::ApiError;
+use crate::api_keys::ApiKeyScope;
+use crate::auth::{bearer_token, constant_time_eq, Caller};
+use crate::config::WebSocketConfig;
+use crate::drop_copy::DropCopySubscription;
 use crate::matching_engine::MatchingEngine;
 use crate::types::*;
+use crate::ws_codec::WireEncoding;
+use crate::ws_replay::{ReplayCache, SequencedMessage};
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        State,
+        Path, Query, State,
     },
-    response::Response,
-    routing::get,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::{delete, get, post},
     Router,
 };
 use chrono::Utc;
-use futures_util::{sink::SinkExt, stream::StreamExt};
+use futures_util::{
+    sink::SinkExt,
+    stream::{SplitSink, SplitStream, StreamExt},
+};
+use metrics::gauge;
+use serde::{Deserialize, Serialize};
 use serde_json;
 use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
-use tracing::{debug, error, info};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 /// WebSocket 状态
 #[derive(Clone)]
 pub struct WebSocketState {
     pub engine: Arc<MatchingEngine>,
+    pub listen_keys: ListenKeyStore,
+    pub broadcaster: WebSocketBroadcaster,
+    pub replay: Arc<ReplayCache>,
+    pub config: WebSocketConfig,
 }
 
 /// WebSocket 订阅类型
 #[derive(Debug, Clone, PartialEq)]
 pub enum SubscriptionType {
     Trades,
+    AggTrades,
     OrderBook,
+    BookTicker,
     MarketData,
+    MarkPrice,
+    Liquidations,
     OrderUpdates,
     All,
 }
 
+/// 客户端文本帧最大长度，超出的帧直接拒绝，不做解析
+pub const MAX_CLIENT_MESSAGE_LEN: usize = 4096;
+
+/// 客户端发送的控制消息，以 `op` 字段区分
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// 应用层心跳，服务端以 `pong` 消息带回相同的 id
+    Ping {
+        #[serde(default)]
+        id: Option<u64>,
+    },
+    /// 重连后从指定序号（含）开始补发，序号已超出回放缓存时改为推送重同步通知和最新快照
+    Resume { from_seq: u64 },
+}
+
+/// 解析客户端文本帧
+pub fn parse_client_message(text: &str) -> Result<ClientMessage, String> {
+    if text.len() > MAX_CLIENT_MESSAGE_LEN {
+        return Err(format!(
+            "Client message exceeds {} bytes",
+            MAX_CLIENT_MESSAGE_LEN
+        ));
+    }
+    serde_json::from_str(text).map_err(|e| format!("Invalid client message: {}", e))
+}
+
 /// WebSocket 连接信息
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ConnectionInfo {
     pub id: Uuid,
     pub subscriptions: Vec<SubscriptionType>,
     pub symbols: Vec<Symbol>,
+    /// 私有数据流所属用户，公共连接为 None
+    pub user_id: Option<String>,
+    /// 推送消息编码
+    pub encoding: WireEncoding,
+    /// 市场数据和盘口的合并间隔，为空时逐条推送
+    pub conflation: Option<Duration>,
 }
 
 impl ConnectionInfo {
@@ -47,156 +104,760 @@ impl ConnectionInfo {
             id: Uuid::new_v4(),
             subscriptions: vec![SubscriptionType::All],
             symbols: vec![],
+            user_id: None,
+            encoding: WireEncoding::Json,
+            conflation: None,
+        }
+    }
+
+    /// 按订阅参数设置关注的交易对和合并间隔，超出 `config` 中的限额或参数无效时返回错误
+    pub fn configure(
+        mut self,
+        params: &SubscriptionParams,
+        config: &WebSocketConfig,
+    ) -> Result<Self, String> {
+        if let Some(symbols) = &params.symbols {
+            for symbol in symbols
+                .split(',')
+                .filter(|symbol| !symbol.trim().is_empty())
+            {
+                let symbol: Symbol = symbol
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("Invalid symbol {}", symbol))?;
+                if !self.symbols.contains(&symbol) {
+                    self.symbols.push(symbol);
+                }
+            }
+            if self.symbols.len() > config.max_symbols_per_connection {
+                return Err(format!(
+                    "At most {} symbols can be subscribed per connection",
+                    config.max_symbols_per_connection
+                ));
+            }
+        }
+
+        match params.conflation_ms {
+            None | Some(0) => {}
+            Some(interval) if interval < config.min_conflation_ms => {
+                return Err(format!(
+                    "Conflation interval must be at least {} ms",
+                    config.min_conflation_ms
+                ));
+            }
+            Some(interval) => self.conflation = Some(Duration::from_millis(interval)),
+        }
+        Ok(self)
+    }
+
+    /// 设置推送消息编码
+    pub fn with_encoding(mut self, encoding: WireEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// 创建只订阅指定频道的公共连接
+    pub fn with_subscription(subscription: SubscriptionType) -> Self {
+        Self {
+            subscriptions: vec![subscription],
+            ..Self::new()
+        }
+    }
+
+    /// 创建用户私有数据流连接，只推送该用户的订单更新、成交和余额变动
+    pub fn for_user(user_id: String) -> Self {
+        Self {
+            subscriptions: vec![SubscriptionType::OrderUpdates, SubscriptionType::Trades],
+            user_id: Some(user_id),
+            ..Self::new()
+        }
+    }
+
+    /// 连接是否可能收到该频道的消息，决定回放时需要检查哪些频道的缓存是否完整
+    fn wants_channel(&self, channel: &str) -> bool {
+        let public = self.user_id.is_none();
+        match channel {
+            "trades" | "otc_trades" => self.is_subscribed(&SubscriptionType::Trades),
+            "agg_trades" => public && self.is_subscribed(&SubscriptionType::AggTrades),
+            "order_updates" | "balances" => !public,
+            "book_ticker" => public && self.is_subscribed(&SubscriptionType::BookTicker),
+            "market_data" => self.is_subscribed(&SubscriptionType::MarketData),
+            "mark_price" => public && self.is_subscribed(&SubscriptionType::MarkPrice),
+            "liquidation" => !public || self.is_subscribed(&SubscriptionType::Liquidations),
+            "auction" => public,
+            _ => true,
         }
     }
+
+    fn is_subscribed(&self, subscription: &SubscriptionType) -> bool {
+        self.subscriptions.contains(&SubscriptionType::All)
+            || self.subscriptions.contains(subscription)
+    }
+}
+
+impl Default for ConnectionInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 用户数据流 listen key 存储
+#[derive(Clone, Default)]
+pub struct ListenKeyStore {
+    keys: Arc<tokio::sync::RwLock<HashMap<String, String>>>,
+}
+
+impl ListenKeyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 为用户生成新的 listen key
+    pub async fn create(&self, user_id: &str) -> String {
+        let listen_key = Uuid::new_v4().simple().to_string();
+        let mut keys = self.keys.write().await;
+        keys.insert(listen_key.clone(), user_id.to_string());
+        listen_key
+    }
+
+    /// 解析 listen key 对应的用户
+    pub async fn resolve(&self, listen_key: &str) -> Option<String> {
+        self.keys.read().await.get(listen_key).cloned()
+    }
+
+    /// 作废 listen key
+    pub async fn revoke(&self, listen_key: &str) -> bool {
+        self.keys.write().await.remove(listen_key).is_some()
+    }
+}
+
+/// 用户数据流连接参数
+#[derive(Debug, Deserialize)]
+pub struct UserStreamParams {
+    pub listen_key: String,
+}
+
+/// 推送编码参数，所有数据流通用
+#[derive(Debug, Deserialize)]
+pub struct EncodingParams {
+    /// `json`（默认）、`msgpack` 或 `cbor`
+    #[serde(default)]
+    pub encoding: Option<String>,
+}
+
+/// 公共数据流和私有数据流的订阅参数
+#[derive(Debug, Default, Deserialize)]
+pub struct SubscriptionParams {
+    /// 只推送这些交易对，逗号分隔（如 `BTCUSDT,ETH-USDT`），为空时推送全部交易对
+    #[serde(default)]
+    pub symbols: Option<String>,
+    /// 市场数据和盘口的合并间隔（毫秒），每个交易对每个间隔最多推送一次最新状态，0 或为空时逐条推送
+    #[serde(default)]
+    pub conflation_ms: Option<u64>,
+}
+
+/// Drop copy 连接参数
+#[derive(Debug, Deserialize)]
+pub struct DropCopyParams {
+    /// 从该序号（含）开始回放，为空时只接收新事件
+    #[serde(default)]
+    pub from_sequence: Option<u64>,
+    /// 访问令牌，也可以通过 `Authorization: Bearer <token>` 请求头传入
+    #[serde(default)]
+    pub token: Option<String>,
 }
 
 /// 创建 WebSocket 路由
-pub fn create_websocket_router(engine: Arc<MatchingEngine>) -> Router {
-    let state = WebSocketState { engine };
+///
+/// 路由为相对路径，由调用方挂载到 `server.ws_prefix` 下。连接会登记到 `broadcaster`，
+/// 由其心跳任务负责探活和淘汰空闲连接。需在 tokio 运行时中调用，回放缓存的后台任务随之启动
+pub fn create_websocket_router(
+    engine: Arc<MatchingEngine>,
+    broadcaster: WebSocketBroadcaster,
+    config: WebSocketConfig,
+) -> Router {
+    let state = WebSocketState {
+        replay: ReplayCache::start(&engine, config.replay_buffer_size),
+        engine,
+        listen_keys: ListenKeyStore::new(),
+        broadcaster,
+        config,
+    };
 
     Router::new()
-        .route("/ws", get(websocket_handler))
-        .route("/ws/trades", get(websocket_trades_handler))
-        .route("/ws/orderbook", get(websocket_orderbook_handler))
-        .route("/ws/market-data", get(websocket_market_data_handler))
+        .route("/", get(websocket_handler))
+        .route("/trades", get(websocket_trades_handler))
+        .route("/agg-trades", get(websocket_agg_trades_handler))
+        .route("/orderbook", get(websocket_orderbook_handler))
+        .route("/book-ticker", get(websocket_book_ticker_handler))
+        .route("/market-data", get(websocket_market_data_handler))
+        .route("/mark-price", get(websocket_mark_price_handler))
+        .route("/liquidations", get(websocket_liquidations_handler))
+        .route("/user", get(websocket_user_handler))
+        .route("/drop-copy", get(websocket_drop_copy_handler))
+        .route("/listen-key", post(create_listen_key))
+        .route("/listen-key/:listen_key", delete(revoke_listen_key))
         .with_state(state)
 }
 
 /// WebSocket 主处理器
-async fn websocket_handler(ws: WebSocketUpgrade, State(state): State<WebSocketState>) -> Response {
-    ws.on_upgrade(|socket| websocket_connection(socket, state, SubscriptionType::All))
+async fn websocket_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<WebSocketState>,
+    Query(encoding): Query<EncodingParams>,
+    Query(params): Query<SubscriptionParams>,
+) -> Response {
+    let connection_info = match ConnectionInfo::new().configure(&params, &state.config) {
+        Ok(connection_info) => connection_info,
+        Err(message) => return bad_request(message),
+    };
+    upgrade(ws, encoding, |socket, encoding| {
+        websocket_connection(socket, state, connection_info.with_encoding(encoding))
+    })
 }
 
 /// WebSocket 交易数据处理器
 async fn websocket_trades_handler(
     ws: WebSocketUpgrade,
     State(state): State<WebSocketState>,
+    Query(encoding): Query<EncodingParams>,
+    Query(params): Query<SubscriptionParams>,
+) -> Response {
+    let connection_info = match ConnectionInfo::with_subscription(SubscriptionType::Trades)
+        .configure(&params, &state.config)
+    {
+        Ok(connection_info) => connection_info,
+        Err(message) => return bad_request(message),
+    };
+    upgrade(ws, encoding, |socket, encoding| {
+        websocket_connection(socket, state, connection_info.with_encoding(encoding))
+    })
+}
+
+/// WebSocket 聚合成交处理器
+async fn websocket_agg_trades_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<WebSocketState>,
+    Query(encoding): Query<EncodingParams>,
+    Query(params): Query<SubscriptionParams>,
 ) -> Response {
-    ws.on_upgrade(|socket| websocket_connection(socket, state, SubscriptionType::Trades))
+    let connection_info = match ConnectionInfo::with_subscription(SubscriptionType::AggTrades)
+        .configure(&params, &state.config)
+    {
+        Ok(connection_info) => connection_info,
+        Err(message) => return bad_request(message),
+    };
+    upgrade(ws, encoding, |socket, encoding| {
+        websocket_connection(socket, state, connection_info.with_encoding(encoding))
+    })
 }
 
 /// WebSocket 订单簿数据处理器
 async fn websocket_orderbook_handler(
     ws: WebSocketUpgrade,
     State(state): State<WebSocketState>,
+    Query(encoding): Query<EncodingParams>,
+    Query(params): Query<SubscriptionParams>,
+) -> Response {
+    let connection_info = match ConnectionInfo::with_subscription(SubscriptionType::OrderBook)
+        .configure(&params, &state.config)
+    {
+        Ok(connection_info) => connection_info,
+        Err(message) => return bad_request(message),
+    };
+    upgrade(ws, encoding, |socket, encoding| {
+        websocket_connection(socket, state, connection_info.with_encoding(encoding))
+    })
+}
+
+/// WebSocket 盘口数据处理器
+async fn websocket_book_ticker_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<WebSocketState>,
+    Query(encoding): Query<EncodingParams>,
+    Query(params): Query<SubscriptionParams>,
 ) -> Response {
-    ws.on_upgrade(|socket| websocket_connection(socket, state, SubscriptionType::OrderBook))
+    let connection_info = match ConnectionInfo::with_subscription(SubscriptionType::BookTicker)
+        .configure(&params, &state.config)
+    {
+        Ok(connection_info) => connection_info,
+        Err(message) => return bad_request(message),
+    };
+    upgrade(ws, encoding, |socket, encoding| {
+        websocket_connection(socket, state, connection_info.with_encoding(encoding))
+    })
 }
 
 /// WebSocket 市场数据处理器
 async fn websocket_market_data_handler(
     ws: WebSocketUpgrade,
     State(state): State<WebSocketState>,
+    Query(encoding): Query<EncodingParams>,
+    Query(params): Query<SubscriptionParams>,
+) -> Response {
+    let connection_info = match ConnectionInfo::with_subscription(SubscriptionType::MarketData)
+        .configure(&params, &state.config)
+    {
+        Ok(connection_info) => connection_info,
+        Err(message) => return bad_request(message),
+    };
+    upgrade(ws, encoding, |socket, encoding| {
+        websocket_connection(socket, state, connection_info.with_encoding(encoding))
+    })
+}
+
+/// WebSocket 标记价格处理器
+async fn websocket_mark_price_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<WebSocketState>,
+    Query(encoding): Query<EncodingParams>,
+    Query(params): Query<SubscriptionParams>,
+) -> Response {
+    let connection_info = match ConnectionInfo::with_subscription(SubscriptionType::MarkPrice)
+        .configure(&params, &state.config)
+    {
+        Ok(connection_info) => connection_info,
+        Err(message) => return bad_request(message),
+    };
+    upgrade(ws, encoding, |socket, encoding| {
+        websocket_connection(socket, state, connection_info.with_encoding(encoding))
+    })
+}
+
+/// WebSocket 强平事件处理器
+async fn websocket_liquidations_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<WebSocketState>,
+    Query(encoding): Query<EncodingParams>,
+    Query(params): Query<SubscriptionParams>,
+) -> Response {
+    let connection_info = match ConnectionInfo::with_subscription(SubscriptionType::Liquidations)
+        .configure(&params, &state.config)
+    {
+        Ok(connection_info) => connection_info,
+        Err(message) => return bad_request(message),
+    };
+    upgrade(ws, encoding, |socket, encoding| {
+        websocket_connection(socket, state, connection_info.with_encoding(encoding))
+    })
+}
+
+/// WebSocket 用户私有数据流处理器
+async fn websocket_user_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<WebSocketState>,
+    Query(params): Query<UserStreamParams>,
+    Query(encoding): Query<EncodingParams>,
+    Query(subscription): Query<SubscriptionParams>,
+) -> Response {
+    let user_id = match state.listen_keys.resolve(&params.listen_key).await {
+        Some(user_id) => user_id,
+        None => {
+            warn!("Rejected user stream with unknown listen key");
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+    };
+
+    let connection_info =
+        match ConnectionInfo::for_user(user_id).configure(&subscription, &state.config) {
+            Ok(connection_info) => connection_info,
+            Err(message) => return bad_request(message),
+        };
+    upgrade(ws, encoding, |socket, encoding| {
+        websocket_connection(socket, state, connection_info.with_encoding(encoding))
+    })
+}
+
+/// 握手阶段的参数错误，以 `error` 消息返回 400
+fn bad_request(message: String) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(WebSocketMessage::Error { message }),
+    )
+        .into_response()
+}
+
+/// 协商推送编码并升级连接
+///
+/// `encoding` 参数优先，其次是客户端请求的子协议（`msgpack`、`cbor`、`json`），都没有时使用 JSON；
+/// 参数取值不支持时返回 400
+fn upgrade<F, Fut>(ws: WebSocketUpgrade, params: EncodingParams, callback: F) -> Response
+where
+    F: FnOnce(WebSocket, WireEncoding) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let requested = match params.encoding.as_deref() {
+        Some(name) => match WireEncoding::from_name(name) {
+            Some(encoding) => Some(encoding),
+            None => return bad_request(format!("Unsupported encoding {}", name)),
+        },
+        None => None,
+    };
+
+    ws.protocols(WireEncoding::SUBPROTOCOLS)
+        .on_upgrade(move |socket| {
+            let encoding = requested
+                .or_else(|| {
+                    socket
+                        .protocol()
+                        .and_then(|protocol| protocol.to_str().ok())
+                        .and_then(WireEncoding::from_name)
+                })
+                .unwrap_or_default();
+            callback(socket, encoding)
+        })
+}
+
+/// Drop copy 数据流处理器
+///
+/// 令牌不在 `drop_copy_tokens` 中时返回 401；未启用 drop copy 或请求的序号无法回放时返回 400。
+/// 升级前完成订阅，握手期间产生的事件不会丢失
+async fn websocket_drop_copy_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<WebSocketState>,
+    headers: HeaderMap,
+    Query(params): Query<DropCopyParams>,
+    Query(encoding): Query<EncodingParams>,
 ) -> Response {
-    ws.on_upgrade(|socket| websocket_connection(socket, state, SubscriptionType::MarketData))
+    let token = bearer_token(&headers).or(params.token.as_deref());
+    if !is_drop_copy_authorized(&state.config, token) {
+        warn!("Rejected drop copy connection with invalid token");
+        return ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            "unauthorized",
+            "Missing or invalid drop copy token",
+        )
+        .into_response();
+    }
+
+    let subscription = match state.engine.subscribe_drop_copy(params.from_sequence) {
+        Ok(subscription) => subscription,
+        Err(message) => return bad_request(message),
+    };
+
+    upgrade(ws, encoding, |socket, encoding| {
+        drop_copy_connection(socket, state, subscription, encoding)
+    })
+}
+
+/// 令牌与每个允许的令牌都做一次定长比较，耗时不泄露匹配位置
+fn is_drop_copy_authorized(config: &WebSocketConfig, token: Option<&str>) -> bool {
+    token.is_some_and(|token| {
+        config
+            .drop_copy_tokens
+            .iter()
+            .fold(false, |matched, allowed| {
+                constant_time_eq(allowed, token) | matched
+            })
+    })
+}
+
+/// 创建用户数据流 listen key，启用认证时只能为调用方自己创建
+async fn create_listen_key(
+    State(state): State<WebSocketState>,
+    caller: Caller,
+    Json(request): Json<CreateListenKeyRequest>,
+) -> Result<Json<CreateListenKeyResponse>, ApiError> {
+    if request.user_id.is_empty() {
+        return Err(ApiError::bad_request(
+            "invalid_user_id",
+            "User ID cannot be empty",
+        ));
+    }
+    caller.authorize(&request.user_id, ApiKeyScope::Read)?;
+
+    let listen_key = state.listen_keys.create(&request.user_id).await;
+    info!("Listen key created for user {}", request.user_id);
+
+    Ok(Json(CreateListenKeyResponse { listen_key }))
+}
+
+/// 作废用户数据流 listen key，启用认证时只能作废调用方自己的 listen key
+async fn revoke_listen_key(
+    State(state): State<WebSocketState>,
+    caller: Caller,
+    Path(listen_key): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let not_found = || ApiError::not_found("Listen key not found");
+    let user_id = state
+        .listen_keys
+        .resolve(&listen_key)
+        .await
+        .ok_or_else(not_found)?;
+    caller.authorize(&user_id, ApiKeyScope::Read)?;
+
+    if state.listen_keys.revoke(&listen_key).await {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(not_found())
+    }
 }
 
 /// WebSocket 连接处理
+///
+/// 转发任务只负责把消息放入连接的有界出站队列，由唯一的写任务负责写入 socket；
+/// 队列写满说明客户端消费过慢，连接会被断开
 async fn websocket_connection(
     socket: WebSocket,
     state: WebSocketState,
-    default_subscription: SubscriptionType,
+    connection_info: ConnectionInfo,
 ) {
-    let connection_info = ConnectionInfo::new();
     info!("WebSocket connection established: {}", connection_info.id);
 
-    // 订阅广播通道
-    let mut trade_receiver = state.engine.subscribe_trades();
-    let mut order_receiver = state.engine.subscribe_orders();
-    let mut market_data_receiver = state.engine.subscribe_market_data();
+    // 先订阅编号消息，之后的 resume 请求从回放缓存补齐订阅前的部分
+    let message_receiver = state.replay.subscribe();
+    let (resume_tx, resume_rx) = mpsc::channel(4);
 
-    let (mut sender, mut receiver) = socket.split();
+    let (sender, receiver) = socket.split();
+
+    // 每个连接一个有界出站队列，所有频道复用同一个写任务
+    let (outbound_tx, outbound_rx) = mpsc::channel::<Message>(state.config.outbound_queue_size);
+    let shutdown_rx = state
+        .broadcaster
+        .add_connection(connection_info.id, outbound_tx.clone())
+        .await;
+
+    let writer_task = spawn_writer(sender, outbound_rx, shutdown_rx);
 
     // 发送欢迎消息
     let welcome_msg = WebSocketMessage::Trade(Trade {
-        id: Uuid::new_v4(),
+        id: 0,
         symbol: Symbol::new("SYSTEM", "WELCOME"),
-        buy_order_id: Uuid::new_v4(),
-        sell_order_id: Uuid::new_v4(),
+        buy_order_id: 0,
+        sell_order_id: 0,
         quantity: 0.0,
         price: 0.0,
         timestamp: Utc::now(),
         buyer_id: "system".to_string(),
         seller_id: "system".to_string(),
+        trade_type: TradeType::Regular,
+        taker_side: None,
+        buy_tag: None,
+        sell_tag: None,
     });
+    enqueue_message(
+        &outbound_tx,
+        connection_info.id,
+        connection_info.encoding,
+        &MessageView::for_connection(&connection_info, &welcome_msg),
+    );
 
-    if let Ok(msg) = serde_json::to_string(&welcome_msg) {
-        let _ = sender.send(Message::Text(msg)).await;
+    // 已订阅编号消息后再取快照，快照包含序号不大于 snapshot_seq 的全部更新，先于增量入队
+    let snapshot_seq = state.replay.next_seq() - 1;
+    for message in subscription_snapshots(&state.engine, &connection_info) {
+        enqueue_message(
+            &outbound_tx,
+            connection_info.id,
+            connection_info.encoding,
+            &SnapshotMessage {
+                snapshot_seq,
+                message,
+            },
+        );
     }
 
-    // 创建任务来处理不同的消息流
-    let trade_task = tokio::spawn({
-        let state = state.clone();
-        let connection_info = connection_info.clone();
-        async move {
-            while let Ok(trade) = trade_receiver.recv().await {
-                if should_send_trade(&connection_info, &trade) {
-                    let msg = WebSocketMessage::Trade(trade);
-                    if let Ok(json) = serde_json::to_string(&msg) {
-                        if sender.send(Message::Text(json)).await.is_err() {
-                            break;
-                        }
-                    }
-                }
+    let forward_task = tokio::spawn(forward_messages(
+        message_receiver,
+        resume_rx,
+        state.replay.clone(),
+        state.engine.clone(),
+        connection_info.clone(),
+        outbound_tx.clone(),
+    ));
+
+    // 处理客户端消息
+    let client_task = spawn_reader(
+        receiver,
+        state.broadcaster.clone(),
+        connection_info.id,
+        connection_info.encoding,
+        outbound_tx,
+        Some(resume_tx),
+    );
+
+    // 等待任一任务完成后终止其余任务
+    let mut tasks = [writer_task, forward_task, client_task];
+    let _ = futures_util::future::select_all(tasks.iter_mut()).await;
+    for task in &tasks {
+        task.abort();
+    }
+
+    state
+        .broadcaster
+        .remove_connection(connection_info.id)
+        .await;
+    info!("WebSocket connection closed: {}", connection_info.id);
+}
+
+/// Drop copy 连接处理
+///
+/// 与普通连接共用写任务、心跳和慢消费者断开机制。连接因任何原因断开后，
+/// 客户端可以用最后收到的序号加一作为 `from_sequence` 重连续传
+async fn drop_copy_connection(
+    socket: WebSocket,
+    state: WebSocketState,
+    subscription: DropCopySubscription,
+    encoding: WireEncoding,
+) {
+    let connection_id = Uuid::new_v4();
+    info!(
+        "Drop copy connection established: {} from sequence {}",
+        connection_id, subscription.next_sequence
+    );
+
+    let (sender, receiver) = socket.split();
+    let (outbound_tx, outbound_rx) = mpsc::channel::<Message>(state.config.outbound_queue_size);
+    let shutdown_rx = state
+        .broadcaster
+        .add_connection(connection_id, outbound_tx.clone())
+        .await;
+
+    let mut tasks = [
+        spawn_writer(sender, outbound_rx, shutdown_rx),
+        tokio::spawn(forward_drop_copy(
+            state.engine.clone(),
+            subscription,
+            connection_id,
+            encoding,
+            outbound_tx.clone(),
+        )),
+        spawn_reader(
+            receiver,
+            state.broadcaster.clone(),
+            connection_id,
+            encoding,
+            outbound_tx,
+            None,
+        ),
+    ];
+    let _ = futures_util::future::select_all(tasks.iter_mut()).await;
+    for task in &tasks {
+        task.abort();
+    }
+
+    state.broadcaster.remove_connection(connection_id).await;
+    info!("Drop copy connection closed: {}", connection_id);
+}
+
+/// 将 drop copy 事件按序号转发到连接的出站队列
+///
+/// 接收端落后时从下一个待发送的序号重新订阅并回放，保证不丢不重；
+/// 回放缓冲区已不包含该序号时断开连接
+async fn forward_drop_copy(
+    engine: Arc<MatchingEngine>,
+    mut subscription: DropCopySubscription,
+    connection_id: Uuid,
+    encoding: WireEncoding,
+    outbound_tx: mpsc::Sender<Message>,
+) {
+    let mut next_sequence = subscription.next_sequence;
+    let mut pending = std::mem::take(&mut subscription.replay);
+
+    loop {
+        for event in pending.drain(..) {
+            if !enqueue_message(&outbound_tx, connection_id, encoding, &event) {
+                return;
             }
+            next_sequence = event.sequence + 1;
         }
-    });
 
-    let order_task = tokio::spawn({
-        let state = state.clone();
-        let connection_info = connection_info.clone();
-        async move {
-            while let Ok(order) = order_receiver.recv().await {
-                if should_send_order_update(&connection_info, &order) {
-                    let msg = WebSocketMessage::OrderUpdate(order);
-                    if let Ok(json) = serde_json::to_string(&msg) {
-                        if sender.send(Message::Text(json)).await.is_err() {
-                            break;
-                        }
+        match subscription.recv().await {
+            Ok(event) => pending.push(event),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!(
+                    "Drop copy connection {} lagged, skipped {} events, replaying from sequence {}",
+                    connection_id, skipped, next_sequence
+                );
+                match engine.subscribe_drop_copy(Some(next_sequence)) {
+                    Ok(resumed) => {
+                        subscription = resumed;
+                        pending = std::mem::take(&mut subscription.replay);
+                    }
+                    Err(e) => {
+                        warn!("Drop copy connection {} closed: {}", connection_id, e);
+                        return;
                     }
                 }
             }
+            Err(broadcast::error::RecvError::Closed) => return,
         }
-    });
+    }
+}
 
-    let market_data_task = tokio::spawn({
-        let state = state.clone();
-        let connection_info = connection_info.clone();
-        async move {
-            while let Ok(market_data) = market_data_receiver.recv().await {
-                if should_send_market_data(&connection_info, &market_data) {
-                    let msg = WebSocketMessage::MarketData(market_data);
-                    if let Ok(json) = serde_json::to_string(&msg) {
-                        if sender.send(Message::Text(json)).await.is_err() {
+/// 启动连接的写任务：把出站队列中的消息写入 socket
+fn spawn_writer(
+    mut sender: SplitSink<WebSocket, Message>,
+    mut outbound_rx: mpsc::Receiver<Message>,
+    mut shutdown_rx: oneshot::Receiver<()>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                message = outbound_rx.recv() => match message {
+                    Some(message) => {
+                        if sender.send(message).await.is_err() {
                             break;
                         }
                     }
+                    None => break,
+                },
+                // 被广播器移除（心跳超时或慢消费者），主动关闭连接
+                _ = &mut shutdown_rx => {
+                    let _ = sender.send(Message::Close(None)).await;
+                    break;
                 }
             }
         }
-    });
+    })
+}
 
-    // 处理客户端消息
-    let client_task = tokio::spawn(async move {
+/// 启动连接的读任务：处理客户端心跳和控制消息，客户端关闭或出错时结束
+///
+/// `resume_tx` 为空的连接不支持 `resume`，收到时回复错误
+fn spawn_reader(
+    mut receiver: SplitStream<WebSocket>,
+    broadcaster: WebSocketBroadcaster,
+    connection_id: Uuid,
+    encoding: WireEncoding,
+    outbound_tx: mpsc::Sender<Message>,
+    resume_tx: Option<mpsc::Sender<u64>>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
         while let Some(msg) = receiver.next().await {
+            // 任何客户端消息都视为连接存活
+            broadcaster.touch(connection_id).await;
+
             match msg {
                 Ok(Message::Text(text)) => {
                     debug!("Received WebSocket message: {}", text);
-                    // 这里可以处理客户端发送的订阅请求等
-                    // 例如：{"type": "subscribe", "channel": "trades", "symbol": "BTCUSDT"}
+                    let reply = match parse_client_message(&text) {
+                        Ok(ClientMessage::Ping { id }) => WebSocketMessage::Pong { id },
+                        Ok(ClientMessage::Resume { from_seq }) => match &resume_tx {
+                            // 补发由转发任务完成，保证与实时消息的顺序
+                            Some(resume_tx) => {
+                                if resume_tx.send(from_seq).await.is_err() {
+                                    break;
+                                }
+                                continue;
+                            }
+                            None => WebSocketMessage::Error {
+                                message: "Resume is not supported on this stream".to_string(),
+                            },
+                        },
+                        Err(message) => WebSocketMessage::Error { message },
+                    };
+                    enqueue_message(&outbound_tx, connection_id, encoding, &reply);
                 }
                 Ok(Message::Close(_)) => {
-                    info!("WebSocket connection closed: {}", connection_info.id);
+                    info!("WebSocket connection closed: {}", connection_id);
                     break;
                 }
                 Ok(Message::Ping(data)) => {
-                    if sender.send(Message::Pong(data)).await.is_err() {
-                        break;
-                    }
+                    // 出站队列写满时丢弃 Pong，慢消费者由数据流任务负责断开
+                    let _ = outbound_tx.try_send(Message::Pong(data));
                 }
                 Ok(Message::Pong(_)) => {
-                    // 忽略 pong 消息
+                    // 心跳响应，活跃时间已在上面更新
                 }
                 Err(e) => {
                     error!("WebSocket error: {}", e);
@@ -205,83 +866,508 @@ async fn websocket_connection(
                 _ => {}
             }
         }
-    });
+    })
+}
+
+/// 将编号消息转发到连接的出站队列
+///
+/// 客户端发送 `resume` 时从回放缓存补发指定序号之后的消息；接收端落后（`RecvError::Lagged`）时
+/// 同样从回放缓存补齐，缓存已不完整时推送重同步通知和最新快照，而不是断开连接。
+/// 已转发过的序号不会重复推送。设置了合并间隔的连接，市场数据和盘口按交易对只保留最新一条，
+/// 每个间隔推送一次，这些消息可能晚于序号更大的其他消息到达
+async fn forward_messages(
+    mut receiver: broadcast::Receiver<Arc<SequencedMessage>>,
+    mut resume_rx: mpsc::Receiver<u64>,
+    replay: Arc<ReplayCache>,
+    engine: Arc<MatchingEngine>,
+    connection_info: ConnectionInfo,
+    outbound_tx: mpsc::Sender<Message>,
+) {
+    // 已处理（转发、合并或过滤掉）的最大序号
+    let mut last_seq = 0;
+    let mut conflated: HashMap<(&'static str, Symbol), Arc<SequencedMessage>> = HashMap::new();
+    // 首次推送也等满一个间隔，使间隔内的更新都能合并
+    let period = connection_info.conflation.unwrap_or(Duration::from_secs(1));
+    let mut flush = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    flush.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
-    // 等待任一任务完成
-    tokio::select! {
-        _ = trade_task => {},
-        _ = order_task => {},
-        _ = market_data_task => {},
-        _ = client_task => {},
+    loop {
+        tokio::select! {
+            Some(from_seq) = resume_rx.recv() => {
+                info!(
+                    "WebSocket connection {} resuming from sequence {}",
+                    connection_info.id, from_seq
+                );
+                let skipped = replay.next_seq().saturating_sub(from_seq);
+                if !resume(&replay, &engine, &connection_info, &outbound_tx, from_seq, skipped, &mut last_seq) {
+                    break;
+                }
+            }
+            result = receiver.recv() => match result {
+                Ok(message) => {
+                    if message.seq <= last_seq {
+                        continue;
+                    }
+                    last_seq = message.seq;
+                    if !should_send_message(&connection_info, &message.message) {
+                        continue;
+                    }
+                    match conflation_key(&message) {
+                        Some(key) if connection_info.conflation.is_some() => {
+                            conflated.insert(key, message);
+                        }
+                        _ => {
+                            if !enqueue_sequenced(&outbound_tx, &connection_info, &message) {
+                                break;
+                            }
+                        }
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!(
+                        "WebSocket connection {} lagged, skipped {} messages, replaying from sequence {}",
+                        connection_info.id, skipped, last_seq + 1
+                    );
+                    if !resume(&replay, &engine, &connection_info, &outbound_tx, last_seq + 1, skipped, &mut last_seq) {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            _ = flush.tick(), if !conflated.is_empty() => {
+                let mut messages: Vec<_> = conflated.drain().map(|(_, message)| message).collect();
+                messages.sort_unstable_by_key(|message| message.seq);
+                if !messages.iter().all(|message| {
+                    enqueue_sequenced(&outbound_tx, &connection_info, message)
+                }) {
+                    break;
+                }
+            }
+        }
     }
+}
 
-    info!("WebSocket connection closed: {}", connection_info.id);
+/// 可合并消息的合并键：只有市场数据、盘口和标记价格按交易对合并
+fn conflation_key(message: &SequencedMessage) -> Option<(&'static str, Symbol)> {
+    match &message.message {
+        WebSocketMessage::MarketData(market_data) => Some((message.channel, market_data.symbol)),
+        WebSocketMessage::BookTicker(ticker) => Some((message.channel, ticker.symbol)),
+        WebSocketMessage::MarkPrice(mark_price) => Some((message.channel, mark_price.symbol)),
+        _ => None,
+    }
+}
+
+/// 从回放缓存补发序号不小于 `from_seq` 的消息，缓存已不完整时改为推送重同步通知和最新快照
+///
+/// 出站队列写满或已关闭时返回 false
+fn resume(
+    replay: &ReplayCache,
+    engine: &MatchingEngine,
+    connection_info: &ConnectionInfo,
+    outbound_tx: &mpsc::Sender<Message>,
+    from_seq: u64,
+    skipped: u64,
+    last_seq: &mut u64,
+) -> bool {
+    let Some(messages) = replay.replay(from_seq, |channel| connection_info.wants_channel(channel))
+    else {
+        return resync_messages(engine, connection_info, "all", skipped)
+            .iter()
+            .all(|message| {
+                enqueue_message(
+                    outbound_tx,
+                    connection_info.id,
+                    connection_info.encoding,
+                    message,
+                )
+            });
+    };
+
+    messages.iter().all(|message| {
+        *last_seq = (*last_seq).max(message.seq);
+        !should_send_message(connection_info, &message.message)
+            || enqueue_sequenced(outbound_tx, connection_info, message)
+    })
+}
+
+/// 构造重同步消息：先发送重同步通知，再附上连接订阅范围内的最新快照
+///
+/// 公共连接按订阅推送订单簿深度、市场数据、当前盘口和标记价格（盘口频道只推送当前盘口），私有数据流推送该用户当前的挂单
+fn resync_messages(
+    engine: &MatchingEngine,
+    connection_info: &ConnectionInfo,
+    channel: &str,
+    skipped: u64,
+) -> Vec<WebSocketMessage> {
+    let mut messages = vec![WebSocketMessage::Resync {
+        channel: channel.to_string(),
+        skipped,
+    }];
+
+    if let Some(user_id) = &connection_info.user_id {
+        messages.extend(
+            engine
+                .get_open_orders(user_id, None)
+                .into_iter()
+                .map(WebSocketMessage::OrderUpdate),
+        );
+        return messages;
+    }
+
+    for (symbol, market_data) in engine.get_all_market_data() {
+        if !connection_info.symbols.is_empty() && !connection_info.symbols.contains(&symbol) {
+            continue;
+        }
+
+        // 盘口只关心最新状态，盘口频道落后时补发各交易对当前盘口即可
+        if channel != "book_ticker" {
+            if connection_info.is_subscribed(&SubscriptionType::OrderBook) {
+                if let Some(depth) = engine.get_orderbook_depth(&symbol, None) {
+                    messages.push(WebSocketMessage::OrderBook(depth));
+                }
+            }
+
+            if connection_info.is_subscribed(&SubscriptionType::MarketData) {
+                messages.push(WebSocketMessage::MarketData(market_data));
+            }
+        }
+
+        if connection_info.is_subscribed(&SubscriptionType::BookTicker) {
+            if let Some(ticker) = engine.get_book_ticker(&symbol) {
+                messages.push(WebSocketMessage::BookTicker(ticker));
+            }
+        }
+    }
+
+    // 标记价格可能来自还没有订单簿的交易对，单独补发
+    if channel != "book_ticker" && connection_info.is_subscribed(&SubscriptionType::MarkPrice) {
+        messages.extend(
+            engine
+                .get_all_mark_prices()
+                .into_iter()
+                .filter(|mark_price| {
+                    connection_info.symbols.is_empty()
+                        || connection_info.symbols.contains(&mark_price.symbol)
+                })
+                .map(WebSocketMessage::MarkPrice),
+        );
+    }
+
+    messages
+}
+
+/// 推送给连接的消息视图：公共连接收到的成交类消息去掉双方用户ID和订单ID、强平事件只保留强平单，
+/// 私有数据流保留完整信息
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum MessageView<'a> {
+    Full(&'a WebSocketMessage),
+    Public(PublicMessage),
+}
+
+/// 成交类和强平消息的公开形式，`type` 与完整消息相同
+#[derive(Debug, Serialize)]
+#[serde(tag = "type")]
+enum PublicMessage {
+    #[serde(rename = "trade")]
+    Trade(PublicTrade),
+    #[serde(rename = "otc_trade")]
+    OtcTrade(PublicTrade),
+    #[serde(rename = "trade_bust")]
+    TradeBust(PublicTradeBust),
+    #[serde(rename = "liquidation")]
+    Liquidation(PublicLiquidation),
+}
+
+impl<'a> MessageView<'a> {
+    fn for_connection(connection_info: &ConnectionInfo, message: &'a WebSocketMessage) -> Self {
+        if connection_info.user_id.is_some() {
+            Self::Full(message)
+        } else {
+            Self::public(message)
+        }
+    }
+
+    fn public(message: &'a WebSocketMessage) -> Self {
+        match message {
+            WebSocketMessage::Trade(trade) => Self::Public(PublicMessage::Trade(trade.into())),
+            WebSocketMessage::OtcTrade(trade) => {
+                Self::Public(PublicMessage::OtcTrade(trade.into()))
+            }
+            WebSocketMessage::TradeBust(bust) => {
+                Self::Public(PublicMessage::TradeBust(bust.into()))
+            }
+            WebSocketMessage::Liquidation(event) => {
+                Self::Public(PublicMessage::Liquidation(event.into()))
+            }
+            _ => Self::Full(message),
+        }
+    }
+}
+
+/// 带序号的消息视图，序列化形式与 [`SequencedMessage`] 相同
+#[derive(Debug, Serialize)]
+struct SequencedView<'a> {
+    seq: u64,
+    #[serde(flatten)]
+    message: MessageView<'a>,
+}
+
+/// 按连接类型选择视图后将编号消息放入出站队列，返回值同 [`enqueue_message`]
+fn enqueue_sequenced(
+    outbound_tx: &mpsc::Sender<Message>,
+    connection_info: &ConnectionInfo,
+    message: &SequencedMessage,
+) -> bool {
+    enqueue_message(
+        outbound_tx,
+        connection_info.id,
+        connection_info.encoding,
+        &SequencedView {
+            seq: message.seq,
+            message: MessageView::for_connection(connection_info, &message.message),
+        },
+    )
+}
+
+/// 订阅时推送的快照，客户端只需应用 `seq` 大于 `snapshot_seq` 的增量
+#[derive(Debug, Serialize)]
+struct SnapshotMessage {
+    snapshot_seq: u64,
+    #[serde(flatten)]
+    message: WebSocketMessage,
+}
+
+/// 公共连接订阅了订单簿深度或市场数据时，建立连接后推送所关注交易对的当前状态；
+/// 未指定交易对时推送全部交易对
+fn subscription_snapshots(
+    engine: &MatchingEngine,
+    connection_info: &ConnectionInfo,
+) -> Vec<WebSocketMessage> {
+    let depth = connection_info.is_subscribed(&SubscriptionType::OrderBook);
+    let market_data = connection_info.is_subscribed(&SubscriptionType::MarketData);
+    if connection_info.user_id.is_some() || !(depth || market_data) {
+        return Vec::new();
+    }
+
+    let symbols = if connection_info.symbols.is_empty() {
+        let mut symbols: Vec<Symbol> = engine.get_all_market_data().into_keys().collect();
+        symbols.sort_by_key(|symbol| symbol.to_string());
+        symbols
+    } else {
+        connection_info.symbols.clone()
+    };
+
+    let mut messages = Vec::new();
+    for symbol in symbols {
+        if depth {
+            if let Some(orderbook) = engine.get_orderbook_depth(&symbol, None) {
+                messages.push(WebSocketMessage::OrderBook(orderbook));
+            }
+        }
+        if market_data {
+            if let Some(data) = engine.get_market_data(&symbol) {
+                messages.push(WebSocketMessage::MarketData(data));
+            }
+        }
+    }
+    messages
+}
+
+/// 将消息放入连接的出站队列
+///
+/// 队列已满（慢消费者）或已关闭时返回 false，调用方应断开连接
+fn enqueue_message<T: Serialize>(
+    outbound_tx: &mpsc::Sender<Message>,
+    connection_id: Uuid,
+    encoding: WireEncoding,
+    message: &T,
+) -> bool {
+    let frame = match encoding.encode(message) {
+        Ok(frame) => frame,
+        Err(e) => {
+            error!("Failed to serialize WebSocket message: {}", e);
+            return true;
+        }
+    };
+
+    match outbound_tx.try_send(frame) {
+        Ok(()) => true,
+        Err(mpsc::error::TrySendError::Full(_)) => {
+            warn!(
+                "WebSocket connection {} outbound queue full, disconnecting slow consumer",
+                connection_id
+            );
+            false
+        }
+        Err(mpsc::error::TrySendError::Closed(_)) => false,
+    }
+}
+
+/// 检查编号消息是否应该发送给连接
+fn should_send_message(connection_info: &ConnectionInfo, message: &WebSocketMessage) -> bool {
+    match message {
+        WebSocketMessage::Trade(trade) | WebSocketMessage::OtcTrade(trade) => {
+            should_send_trade(connection_info, trade)
+        }
+        WebSocketMessage::TradeBust(bust) => should_send_trade(connection_info, &bust.trade),
+        WebSocketMessage::AggTrade(agg_trade) => should_send_agg_trade(connection_info, agg_trade),
+        WebSocketMessage::OrderUpdate(order) => should_send_order_update(connection_info, order),
+        WebSocketMessage::BalanceUpdate(update) => {
+            should_send_balance_update(connection_info, update)
+        }
+        WebSocketMessage::SymbolStatus(status) => {
+            should_send_symbol_status(connection_info, status)
+        }
+        WebSocketMessage::AuctionIndicative(indicative) => {
+            should_send_auction_indicative(connection_info, indicative)
+        }
+        WebSocketMessage::BookTicker(ticker) => should_send_book_ticker(connection_info, ticker),
+        WebSocketMessage::MarketData(market_data) => {
+            should_send_market_data(connection_info, market_data)
+        }
+        WebSocketMessage::MarkPrice(mark_price) => {
+            should_send_mark_price(connection_info, mark_price)
+        }
+        WebSocketMessage::Liquidation(event) => should_send_liquidation(connection_info, event),
+        _ => true,
+    }
 }
 
 /// 检查是否应该发送交易数据
 fn should_send_trade(connection_info: &ConnectionInfo, trade: &Trade) -> bool {
-    if connection_info
-        .subscriptions
-        .contains(&SubscriptionType::All)
-        || connection_info
-            .subscriptions
-            .contains(&SubscriptionType::Trades)
+    if !connection_info.is_subscribed(&SubscriptionType::Trades) {
+        return false;
+    }
+
+    // 私有数据流只推送该用户参与的成交
+    if let Some(user_id) = &connection_info.user_id {
+        return trade.buyer_id == *user_id || trade.seller_id == *user_id;
+    }
+
+    // 如果没有指定特定交易对，发送所有交易
+    if connection_info.symbols.is_empty() {
+        return true;
+    }
+    // 否则只发送指定交易对的交易
+    connection_info.symbols.contains(&trade.symbol)
+}
+
+/// 检查是否应该发送聚合成交，私有数据流不推送
+fn should_send_agg_trade(connection_info: &ConnectionInfo, agg_trade: &AggTrade) -> bool {
+    if connection_info.user_id.is_some()
+        || !connection_info.is_subscribed(&SubscriptionType::AggTrades)
     {
-        // 如果没有指定特定交易对，发送所有交易
-        if connection_info.symbols.is_empty() {
-            return true;
-        }
-        // 否则只发送指定交易对的交易
-        connection_info.symbols.contains(&trade.symbol)
-    } else {
-        false
+        return false;
     }
+
+    connection_info.symbols.is_empty() || connection_info.symbols.contains(&agg_trade.symbol)
 }
 
 /// 检查是否应该发送订单更新
+///
+/// 订单更新只通过私有数据流推送给订单所属用户，公共频道不再广播
 fn should_send_order_update(connection_info: &ConnectionInfo, order: &Order) -> bool {
-    if connection_info
-        .subscriptions
-        .contains(&SubscriptionType::All)
-        || connection_info
-            .subscriptions
-            .contains(&SubscriptionType::OrderUpdates)
-    {
-        // 如果没有指定特定交易对，发送所有订单更新
-        if connection_info.symbols.is_empty() {
-            return true;
+    match &connection_info.user_id {
+        Some(user_id) => {
+            connection_info.is_subscribed(&SubscriptionType::OrderUpdates)
+                && order.user_id == *user_id
         }
-        // 否则只发送指定交易对的订单更新
-        connection_info.symbols.contains(&order.symbol)
-    } else {
-        false
+        None => false,
+    }
+}
+
+/// 检查是否应该发送余额变动，只推送给私有数据流的所属用户
+fn should_send_balance_update(connection_info: &ConnectionInfo, update: &BalanceUpdate) -> bool {
+    connection_info.user_id.as_deref() == Some(update.user_id.as_str())
+}
+
+/// 检查是否应该发送交易对状态变更
+///
+/// 状态变更（暂停、恢复等）推送给所有连接，只按连接关注的交易对过滤
+fn should_send_symbol_status(connection_info: &ConnectionInfo, status: &SymbolStatus) -> bool {
+    connection_info.symbols.is_empty() || connection_info.symbols.contains(&status.symbol)
+}
+
+/// 检查是否应该发送集合竞价参考价，私有数据流不推送
+fn should_send_auction_indicative(
+    connection_info: &ConnectionInfo,
+    indicative: &AuctionIndicative,
+) -> bool {
+    if connection_info.user_id.is_some() {
+        return false;
+    }
+
+    connection_info.symbols.is_empty() || connection_info.symbols.contains(&indicative.symbol)
+}
+
+/// 检查是否应该发送盘口变化，私有数据流不推送
+fn should_send_book_ticker(connection_info: &ConnectionInfo, ticker: &BookTicker) -> bool {
+    if connection_info.user_id.is_some()
+        || !connection_info.is_subscribed(&SubscriptionType::BookTicker)
+    {
+        return false;
     }
+
+    connection_info.symbols.is_empty() || connection_info.symbols.contains(&ticker.symbol)
+}
+
+/// 检查是否应该发送标记价格，私有数据流不推送
+fn should_send_mark_price(connection_info: &ConnectionInfo, mark_price: &MarkPrice) -> bool {
+    if connection_info.user_id.is_some()
+        || !connection_info.is_subscribed(&SubscriptionType::MarkPrice)
+    {
+        return false;
+    }
+
+    connection_info.symbols.is_empty() || connection_info.symbols.contains(&mark_price.symbol)
+}
+
+/// 检查是否应该发送强平事件
+///
+/// 私有数据流只推送该用户自己的完整强平事件，公共频道按强平单涉及的交易对过滤，
+/// 推送时只保留强平单（见 [`MessageView`]）
+fn should_send_liquidation(connection_info: &ConnectionInfo, event: &LiquidationEvent) -> bool {
+    if let Some(user_id) = &connection_info.user_id {
+        return event.user_id == *user_id;
+    }
+    if !connection_info.is_subscribed(&SubscriptionType::Liquidations) {
+        return false;
+    }
+
+    connection_info.symbols.is_empty()
+        || event
+            .orders
+            .iter()
+            .any(|order| connection_info.symbols.contains(&order.symbol))
 }
 
 /// 检查是否应该发送市场数据
 fn should_send_market_data(connection_info: &ConnectionInfo, market_data: &MarketData) -> bool {
-    if connection_info
-        .subscriptions
-        .contains(&SubscriptionType::All)
-        || connection_info
-            .subscriptions
-            .contains(&SubscriptionType::MarketData)
-    {
-        // 如果没有指定特定交易对，发送所有市场数据
-        if connection_info.symbols.is_empty() {
-            return true;
-        }
-        // 否则只发送指定交易对的市场数据
-        connection_info.symbols.contains(&market_data.symbol)
-    } else {
-        false
+    if !connection_info.is_subscribed(&SubscriptionType::MarketData) {
+        return false;
     }
+
+    // 如果没有指定特定交易对，发送所有市场数据
+    if connection_info.symbols.is_empty() {
+        return true;
+    }
+    // 否则只发送指定交易对的市场数据
+    connection_info.symbols.contains(&market_data.symbol)
+}
+
+/// 广播器中登记的连接
+struct ConnectionHandle {
+    sender: mpsc::Sender<Message>,
+    /// 最近一次收到客户端消息（含 Pong）的时间
+    last_seen: Instant,
+    /// 连接被移出广播器时随之释放，通知连接的写任务关闭 socket
+    _shutdown: oneshot::Sender<()>,
 }
 
 /// WebSocket 消息广播器
 pub struct WebSocketBroadcaster {
-    connections:
-        Arc<tokio::sync::RwLock<HashMap<Uuid, tokio::sync::mpsc::UnboundedSender<Message>>>>,
+    connections: Arc<tokio::sync::RwLock<HashMap<Uuid, ConnectionHandle>>>,
 }
 
 impl WebSocketBroadcaster {
@@ -291,45 +1377,132 @@ impl WebSocketBroadcaster {
         }
     }
 
+    /// 登记连接，返回的接收端在连接被移除（心跳超时、慢消费者等）时完成
     pub async fn add_connection(
         &self,
         id: Uuid,
-        sender: tokio::sync::mpsc::UnboundedSender<Message>,
-    ) {
+        sender: mpsc::Sender<Message>,
+    ) -> oneshot::Receiver<()> {
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
         let mut connections = self.connections.write().await;
-        connections.insert(id, sender);
+        connections.insert(
+            id,
+            ConnectionHandle {
+                sender,
+                last_seen: Instant::now(),
+                _shutdown: shutdown_tx,
+            },
+        );
+        update_connection_gauge(connections.len());
+        shutdown_rx
     }
 
     pub async fn remove_connection(&self, id: Uuid) {
         let mut connections = self.connections.write().await;
-        connections.remove(&id);
+        if connections.remove(&id).is_some() {
+            update_connection_gauge(connections.len());
+        }
+    }
+
+    /// 记录连接活跃（收到 Pong 或其他客户端消息）
+    pub async fn touch(&self, id: Uuid) {
+        if let Some(handle) = self.connections.write().await.get_mut(&id) {
+            handle.last_seen = Instant::now();
+        }
+    }
+
+    /// 移除全部连接，各连接的写任务随之发送关闭帧后退出，返回关闭的连接数
+    pub async fn close_all(&self) -> usize {
+        let mut connections = self.connections.write().await;
+        let count = connections.len();
+        connections.clear();
+        update_connection_gauge(0);
+        count
+    }
+
+    /// 当前连接数
+    pub async fn connection_count(&self) -> usize {
+        self.connections.read().await.len()
     }
 
     pub async fn broadcast(&self, message: Message) {
         let connections = self.connections.read().await;
         let mut to_remove = Vec::new();
 
-        for (id, sender) in connections.iter() {
-            if sender.send(message.clone()).is_err() {
-                to_remove.push(*id);
+        for (id, handle) in connections.iter() {
+            match handle.sender.try_send(message.clone()) {
+                Ok(()) => {}
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    warn!(
+                        "WebSocket connection {} outbound queue full, disconnecting slow consumer",
+                        id
+                    );
+                    to_remove.push(*id);
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => to_remove.push(*id),
             }
         }
 
-        // 移除失效的连接
+        // 移除失效的连接（包括慢消费者），释放发送端后写任务会随之退出
         if !to_remove.is_empty() {
             drop(connections);
             let mut connections = self.connections.write().await;
             for id in to_remove {
                 connections.remove(&id);
             }
+            update_connection_gauge(connections.len());
         }
     }
 
-    pub async fn broadcast_to_symbol(&self, message: Message, symbol: &Symbol) {
+    pub async fn broadcast_to_symbol(&self, message: Message, _symbol: &Symbol) {
         // 这里可以实现更复杂的过滤逻辑
         // 目前简化处理，广播给所有连接
         self.broadcast(message).await;
     }
+
+    /// 关闭并移除超过 `timeout` 未活跃的连接，返回被移除的连接ID
+    pub async fn evict_idle(&self, timeout: Duration) -> Vec<Uuid> {
+        let mut connections = self.connections.write().await;
+        let idle: Vec<Uuid> = connections
+            .iter()
+            .filter(|(_, handle)| handle.last_seen.elapsed() > timeout)
+            .map(|(id, _)| *id)
+            .collect();
+
+        for id in &idle {
+            connections.remove(id);
+            warn!(
+                "WebSocket connection {} evicted after heartbeat timeout",
+                id
+            );
+        }
+
+        if !idle.is_empty() {
+            update_connection_gauge(connections.len());
+        }
+        idle
+    }
+
+    /// 启动心跳任务：按间隔向所有连接发送 Ping，并关闭超时未回 Pong 的连接
+    pub fn start_heartbeat(&self, config: WebSocketConfig) -> tokio::task::JoinHandle<()> {
+        let broadcaster = self.clone();
+        let interval = Duration::from_secs(config.heartbeat_interval);
+        let timeout = Duration::from_secs(config.heartbeat_timeout);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                broadcaster.evict_idle(timeout).await;
+                broadcaster.broadcast(Message::Ping(Vec::new())).await;
+            }
+        })
+    }
+}
+
+/// 更新 WebSocket 连接数指标
+fn update_connection_gauge(count: usize) {
+    gauge!("matching_engine_websocket_connections").set(count as f64);
 }
 
 /// WebSocket 管理器
@@ -348,30 +1521,27 @@ impl WebSocketManager {
 
     pub async fn start_broadcasting(&self) {
         let mut trade_receiver = self.engine.subscribe_trades();
-        let mut order_receiver = self.engine.subscribe_orders();
         let mut market_data_receiver = self.engine.subscribe_market_data();
 
-        // 广播交易数据
-        tokio::spawn({
-            let broadcaster = self.broadcaster.clone();
-            async move {
-                while let Ok(trade) = trade_receiver.recv().await {
-                    let msg = WebSocketMessage::Trade(trade);
-                    if let Ok(json) = serde_json::to_string(&msg) {
-                        broadcaster.broadcast(Message::Text(json)).await;
-                    }
-                }
-            }
-        });
+        // 订单更新属于用户私有数据，只通过 /ws/user 推送，这里不做全量广播
 
-        // 广播订单更新
+        // 广播交易数据
         tokio::spawn({
             let broadcaster = self.broadcaster.clone();
             async move {
-                while let Ok(order) = order_receiver.recv().await {
-                    let msg = WebSocketMessage::OrderUpdate(order);
-                    if let Ok(json) = serde_json::to_string(&msg) {
-                        broadcaster.broadcast(Message::Text(json)).await;
+                loop {
+                    match trade_receiver.recv().await {
+                        Ok(trade) => {
+                            let msg = WebSocketMessage::Trade(trade);
+                            if let Ok(json) = serde_json::to_string(&MessageView::public(&msg)) {
+                                broadcaster.broadcast(Message::Text(json)).await;
+                            }
+                        }
+                        // 落后时跳过丢失的消息继续广播，而不是终止广播任务
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            warn!("Trade broadcast lagged, skipped {} messages", skipped);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
                     }
                 }
             }
@@ -381,10 +1551,19 @@ impl WebSocketManager {
         tokio::spawn({
             let broadcaster = self.broadcaster.clone();
             async move {
-                while let Ok(market_data) = market_data_receiver.recv().await {
-                    let msg = WebSocketMessage::MarketData(market_data);
-                    if let Ok(json) = serde_json::to_string(&msg) {
-                        broadcaster.broadcast(Message::Text(json)).await;
+                loop {
+                    match market_data_receiver.recv().await {
+                        Ok(market_data) => {
+                            let msg = WebSocketMessage::MarketData(market_data);
+                            if let Ok(json) = serde_json::to_string(&msg) {
+                                broadcaster.broadcast(Message::Text(json)).await;
+                            }
+                        }
+                        // 落后时跳过丢失的消息继续广播，而不是终止广播任务
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            warn!("MarketData broadcast lagged, skipped {} messages", skipped);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
                     }
                 }
             }
@@ -392,6 +1571,12 @@ impl WebSocketManager {
     }
 }
 
+impl Default for WebSocketBroadcaster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Clone for WebSocketBroadcaster {
     fn clone(&self) -> Self {
         Self {
@@ -404,6 +1589,26 @@ impl Clone for WebSocketBroadcaster {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_client_message() {
+        assert_eq!(
+            parse_client_message(r#"{"op": "ping", "id": 7}"#),
+            Ok(ClientMessage::Ping { id: Some(7) })
+        );
+        assert_eq!(
+            parse_client_message(r#"{"op": "ping"}"#),
+            Ok(ClientMessage::Ping { id: None })
+        );
+        assert_eq!(
+            parse_client_message(r#"{"op": "resume", "from_seq": 42}"#),
+            Ok(ClientMessage::Resume { from_seq: 42 })
+        );
+        assert!(parse_client_message(r#"{"op": "resume"}"#).is_err());
+        assert!(parse_client_message(r#"{"op": "subscribe"}"#).is_err());
+        assert!(parse_client_message("not json").is_err());
+        assert!(parse_client_message(&" ".repeat(MAX_CLIENT_MESSAGE_LEN + 1)).is_err());
+    }
+
     #[test]
     fn test_connection_info() {
         let info = ConnectionInfo::new();
@@ -411,19 +1616,58 @@ mod tests {
         assert!(info.symbols.is_empty());
     }
 
+    #[test]
+    fn test_connection_configure_limits() {
+        let config = WebSocketConfig {
+            max_symbols_per_connection: 2,
+            min_conflation_ms: 50,
+            ..WebSocketConfig::default()
+        };
+        let params = |symbols: &str, conflation_ms: Option<u64>| SubscriptionParams {
+            symbols: Some(symbols.to_string()),
+            conflation_ms,
+        };
+
+        let info = ConnectionInfo::new()
+            .configure(&params("BTCUSDT, BTC-USDT,ETH/USDT", Some(100)), &config)
+            .unwrap();
+        assert_eq!(
+            info.symbols,
+            vec![Symbol::new("BTC", "USDT"), Symbol::new("ETH", "USDT")]
+        );
+        assert_eq!(info.conflation, Some(Duration::from_millis(100)));
+
+        let info = ConnectionInfo::new()
+            .configure(&params("", Some(0)), &config)
+            .unwrap();
+        assert!(info.symbols.is_empty());
+        assert_eq!(info.conflation, None);
+
+        let configure = |symbols, conflation_ms| {
+            ConnectionInfo::new().configure(&params(symbols, conflation_ms), &config)
+        };
+        assert!(configure("BTCUSDT,ETHUSDT,BNBUSDT", None).is_err());
+        assert!(configure("NOT A SYMBOL", None).is_err());
+        assert!(configure("BTCUSDT", Some(10)).is_err());
+    }
+
     #[test]
     fn test_should_send_trade() {
         let mut info = ConnectionInfo::new();
         let trade = Trade {
-            id: Uuid::new_v4(),
+            id: 1,
             symbol: Symbol::new("BTC", "USDT"),
-            buy_order_id: Uuid::new_v4(),
-            sell_order_id: Uuid::new_v4(),
+            buy_order_id: 1,
+            sell_order_id: 2,
             quantity: 1.0,
             price: 50000.0,
             timestamp: Utc::now(),
             buyer_id: "buyer".to_string(),
             seller_id: "seller".to_string(),
+            trade_type: TradeType::Regular,
+            taker_side: None,
+            buy_tag: None,
+            sell_tag: None,
         };
 
         // 默认订阅所有
@@ -437,4 +1681,733 @@ mod tests {
         info.subscriptions = vec![SubscriptionType::OrderBook];
         assert!(!should_send_trade(&info, &trade));
     }
+
+    #[test]
+    fn test_should_send_order_update_only_to_owner() {
+        let order = Order::new(
+            Symbol::new("BTC", "USDT"),
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(50000.0),
+            "alice".to_string(),
+        );
+
+        // 公共连接不再收到任何用户的订单更新
+        assert!(!should_send_order_update(&ConnectionInfo::new(), &order));
+
+        assert!(should_send_order_update(
+            &ConnectionInfo::for_user("alice".to_string()),
+            &order
+        ));
+        assert!(!should_send_order_update(
+            &ConnectionInfo::for_user("bob".to_string()),
+            &order
+        ));
+    }
+
+    #[test]
+    fn test_user_stream_only_receives_own_fills() {
+        let trade = Trade {
+            id: 1,
+            symbol: Symbol::new("BTC", "USDT"),
+            buy_order_id: 1,
+            sell_order_id: 2,
+            quantity: 1.0,
+            price: 50000.0,
+            timestamp: Utc::now(),
+            buyer_id: "buyer".to_string(),
+            seller_id: "seller".to_string(),
+            trade_type: TradeType::Regular,
+            taker_side: None,
+            buy_tag: None,
+            sell_tag: None,
+        };
+
+        assert!(should_send_trade(
+            &ConnectionInfo::for_user("buyer".to_string()),
+            &trade
+        ));
+        assert!(should_send_trade(
+            &ConnectionInfo::for_user("seller".to_string()),
+            &trade
+        ));
+        assert!(!should_send_trade(
+            &ConnectionInfo::for_user("other".to_string()),
+            &trade
+        ));
+    }
+
+    #[test]
+    fn test_symbol_status_respects_symbol_filter() {
+        let status = SymbolStatus {
+            symbol: Symbol::new("BTC", "USDT"),
+            state: TradingState::Halted,
+            reason: None,
+            timestamp: Utc::now(),
+        };
+
+        let mut info = ConnectionInfo::with_subscription(SubscriptionType::Trades);
+        assert!(should_send_symbol_status(&info, &status));
+
+        info.symbols = vec![Symbol::new("ETH", "USDT")];
+        assert!(!should_send_symbol_status(&info, &status));
+    }
+
+    #[test]
+    fn test_auction_indicative_not_sent_to_user_stream() {
+        let indicative = AuctionIndicative {
+            symbol: Symbol::new("BTC", "USDT"),
+            price: Some(50000.0),
+            volume: 1.0,
+            imbalance: 0.0,
+            timestamp: Utc::now(),
+        };
+
+        let info = ConnectionInfo::with_subscription(SubscriptionType::MarketData);
+        assert!(should_send_auction_indicative(&info, &indicative));
+        assert!(!should_send_auction_indicative(
+            &ConnectionInfo::for_user("alice".to_string()),
+            &indicative
+        ));
+    }
+
+    fn liquidation_event() -> LiquidationEvent {
+        LiquidationEvent {
+            id: uuid::Uuid::new_v4(),
+            user_id: "alice".to_string(),
+            equity: 40.0,
+            maintenance_margin: 47.0,
+            cancelled_orders: vec![5],
+            orders: vec![LiquidationOrder {
+                order_id: 1,
+                symbol: Symbol::new("BTC", "USDT"),
+                side: OrderSide::Sell,
+                quantity: 10.0,
+                price: 89.5,
+                filled_quantity: 10.0,
+                error: None,
+            }],
+            liquidation_fee: 4.0,
+            insurance_fund_payout: 0.0,
+            remaining_equity: 36.0,
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_liquidation_routing() {
+        let event = liquidation_event();
+
+        let mut info = ConnectionInfo::with_subscription(SubscriptionType::Liquidations);
+        assert!(should_send_liquidation(&info, &event));
+        info.symbols = vec![Symbol::new("ETH", "USDT")];
+        assert!(!should_send_liquidation(&info, &event));
+        assert!(!should_send_liquidation(
+            &ConnectionInfo::with_subscription(SubscriptionType::Trades),
+            &event
+        ));
+
+        assert!(should_send_liquidation(
+            &ConnectionInfo::for_user("alice".to_string()),
+            &event
+        ));
+        assert!(!should_send_liquidation(
+            &ConnectionInfo::for_user("bob".to_string()),
+            &event
+        ));
+    }
+
+    #[test]
+    fn test_balance_updates_only_sent_to_owner() {
+        let accounts = crate::account::AccountManager::new();
+        let mut receiver = accounts.subscribe_balances();
+        accounts.deposit("alice", "USDT", 10.0, "dep-1").unwrap();
+        let update = receiver.try_recv().unwrap();
+
+        assert!(should_send_balance_update(
+            &ConnectionInfo::for_user("alice".to_string()),
+            &update
+        ));
+        assert!(!should_send_balance_update(
+            &ConnectionInfo::for_user("bob".to_string()),
+            &update
+        ));
+        assert!(!should_send_balance_update(&ConnectionInfo::new(), &update));
+    }
+
+    #[tokio::test]
+    async fn test_listen_key_store() {
+        let store = ListenKeyStore::new();
+        let listen_key = store.create("alice").await;
+
+        assert_eq!(store.resolve(&listen_key).await, Some("alice".to_string()));
+        assert!(store.revoke(&listen_key).await);
+        assert_eq!(store.resolve(&listen_key).await, None);
+        assert!(!store.revoke(&listen_key).await);
+    }
+
+    #[tokio::test]
+    async fn test_listen_key_revocation_requires_owner() {
+        use crate::auth::Claims;
+        use crate::config::ServerConfig;
+        use axum::body::Body;
+        use axum::http::{header, Request};
+        use jsonwebtoken::{encode, EncodingKey, Header};
+        use tower::ServiceExt;
+
+        let mut config = ServerConfig::default();
+        config.auth.enabled = true;
+        config.auth.jwt_secret = "secret".to_string();
+        config.auth.api_keys_path = std::env::temp_dir()
+            .join(format!("ws-keys-{}.json", Uuid::new_v4()))
+            .to_string_lossy()
+            .into_owned();
+        let router = crate::api::apply_server_layers(
+            create_websocket_router(
+                Arc::new(MatchingEngine::new()),
+                WebSocketBroadcaster::new(),
+                WebSocketConfig::default(),
+            ),
+            &config,
+        )
+        .unwrap();
+        let jwt = |user_id: &str| {
+            let claims = Claims {
+                sub: user_id.to_string(),
+                exp: Utc::now().timestamp() as u64 + 60,
+            };
+            encode(
+                &Header::default(),
+                &claims,
+                &EncodingKey::from_secret(b"secret"),
+            )
+            .unwrap()
+        };
+        let call = |request: axum::http::request::Builder, token: Option<String>, body: Body| {
+            let request = match token {
+                Some(token) => request.header(header::AUTHORIZATION, format!("Bearer {}", token)),
+                None => request,
+            };
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request.body(body).unwrap()).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&body).unwrap_or_default(),
+                )
+            }
+        };
+
+        let create = Request::post("/listen-key").header(header::CONTENT_TYPE, "application/json");
+        let (status, body) = call(
+            create,
+            Some(jwt("alice")),
+            Body::from(r#"{"user_id": "alice"}"#),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let uri = format!("/listen-key/{}", body["listen_key"].as_str().unwrap());
+
+        let (status, _) = call(Request::delete(&uri), None, Body::empty()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, body) = call(Request::delete(&uri), Some(jwt("bob")), Body::empty()).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["code"], "forbidden");
+        let (status, _) = call(Request::delete(&uri), Some(jwt("alice")), Body::empty()).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, body) = call(Request::delete(&uri), Some(jwt("alice")), Body::empty()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "not_found");
+    }
+
+    #[test]
+    fn test_drop_copy_token_check() {
+        let config = WebSocketConfig {
+            drop_copy_tokens: vec!["first".to_string(), "second".to_string()],
+            ..Default::default()
+        };
+        assert!(is_drop_copy_authorized(&config, Some("second")));
+        assert!(!is_drop_copy_authorized(&config, Some("secon")));
+        assert!(!is_drop_copy_authorized(&config, None));
+    }
+
+    #[tokio::test]
+    async fn test_evict_idle_connections() {
+        let broadcaster = WebSocketBroadcaster::new();
+        let (idle_tx, _idle_rx) = mpsc::channel(8);
+        let (active_tx, _active_rx) = mpsc::channel(8);
+        let idle_id = Uuid::new_v4();
+        let active_id = Uuid::new_v4();
+
+        let idle_shutdown = broadcaster.add_connection(idle_id, idle_tx).await;
+        let mut active_shutdown = broadcaster.add_connection(active_id, active_tx).await;
+        assert_eq!(broadcaster.connection_count().await, 2);
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        broadcaster.touch(active_id).await;
+
+        let evicted = broadcaster.evict_idle(Duration::from_millis(25)).await;
+        assert_eq!(evicted, vec![idle_id]);
+        assert_eq!(broadcaster.connection_count().await, 1);
+
+        // 被淘汰的连接会收到关闭通知，活跃连接不受影响
+        assert!(idle_shutdown.await.is_err());
+        assert!(active_shutdown.try_recv().is_err());
+        assert_eq!(broadcaster.connection_count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_disconnects_slow_consumer() {
+        let broadcaster = WebSocketBroadcaster::new();
+        let (slow_tx, _slow_rx) = mpsc::channel(1);
+        let (fast_tx, mut fast_rx) = mpsc::channel(8);
+        let slow_id = Uuid::new_v4();
+
+        let slow_shutdown = broadcaster.add_connection(slow_id, slow_tx).await;
+        let _fast_shutdown = broadcaster.add_connection(Uuid::new_v4(), fast_tx).await;
+
+        broadcaster.broadcast(Message::Text("1".to_string())).await;
+        broadcaster.broadcast(Message::Text("2".to_string())).await;
+
+        // 慢消费者队列写满后被移除
+        assert!(slow_shutdown.await.is_err());
+        assert_eq!(broadcaster.connection_count().await, 1);
+        assert!(matches!(fast_rx.recv().await, Some(Message::Text(text)) if text == "1"));
+        assert!(matches!(fast_rx.recv().await, Some(Message::Text(text)) if text == "2"));
+    }
+
+    /// 提交一笔卖单，返回带行情的引擎和该交易对的市场数据
+    async fn engine_with_market_data() -> (Arc<MatchingEngine>, MarketData) {
+        let engine = Arc::new(MatchingEngine::new());
+        let symbol = Symbol::new("BTC", "USDT");
+        engine
+            .submit_order(Order::new(
+                symbol,
+                OrderSide::Sell,
+                OrderType::Limit,
+                1.0,
+                Some(50000.0),
+                "seller".to_string(),
+            ))
+            .await
+            .unwrap();
+        let market_data = engine.get_market_data(&symbol).unwrap();
+        (engine, market_data)
+    }
+
+    async fn receive_json(
+        outbound_rx: &mut mpsc::Receiver<Message>,
+        count: usize,
+    ) -> Vec<serde_json::Value> {
+        let mut received = Vec::new();
+        while received.len() < count {
+            let Some(Message::Text(text)) = outbound_rx.recv().await else {
+                panic!("outbound queue closed early");
+            };
+            received.push(serde_json::from_str(&text).unwrap());
+        }
+        received
+    }
+
+    #[tokio::test]
+    async fn test_lagged_stream_resyncs_instead_of_disconnecting() {
+        let (engine, market_data) = engine_with_market_data().await;
+
+        // 发布数超过分发通道容量使连接落后，回放缓存只保留 2 条，落后的消息已无法补发
+        let replay = Arc::new(ReplayCache::new(2));
+        let receiver = replay.subscribe();
+        let total = 20_000;
+        for _ in 0..total {
+            replay.publish(
+                "market_data",
+                WebSocketMessage::MarketData(market_data.clone()),
+            );
+        }
+
+        let (outbound_tx, mut outbound_rx) = mpsc::channel(total);
+        let (_resume_tx, resume_rx) = mpsc::channel(1);
+        let task = tokio::spawn(forward_messages(
+            receiver,
+            resume_rx,
+            replay,
+            engine,
+            ConnectionInfo::new(),
+            outbound_tx,
+        ));
+
+        // 重同步通知 + 订单簿、市场数据、盘口快照，之后继续转发未丢失的消息
+        let received = receive_json(&mut outbound_rx, 5).await;
+        task.abort();
+        let types: Vec<&str> = received
+            .iter()
+            .map(|message| message["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                "resync",
+                "orderbook",
+                "market_data",
+                "bookTicker",
+                "market_data"
+            ]
+        );
+        let skipped = received[0]["skipped"].as_u64().unwrap();
+        assert!(skipped > 0);
+        assert_eq!(received[4]["seq"].as_u64().unwrap(), skipped + 1);
+    }
+
+    #[tokio::test]
+    async fn test_conflation_sends_latest_state_per_interval() {
+        let (engine, market_data) = engine_with_market_data().await;
+        let replay = Arc::new(ReplayCache::new(16));
+        let receiver = replay.subscribe();
+        for _ in 0..3 {
+            replay.publish(
+                "market_data",
+                WebSocketMessage::MarketData(market_data.clone()),
+            );
+        }
+        replay.publish(
+            "symbol_status",
+            WebSocketMessage::Error {
+                message: "not conflated".to_string(),
+            },
+        );
+
+        let connection_info = ConnectionInfo::new()
+            .configure(
+                &SubscriptionParams {
+                    symbols: None,
+                    conflation_ms: Some(50),
+                },
+                &WebSocketConfig::default(),
+            )
+            .unwrap();
+        let (outbound_tx, mut outbound_rx) = mpsc::channel(16);
+        let (_resume_tx, resume_rx) = mpsc::channel(1);
+        let task = tokio::spawn(forward_messages(
+            receiver,
+            resume_rx,
+            replay,
+            engine,
+            connection_info,
+            outbound_tx,
+        ));
+
+        // 不可合并的消息立即推送，市场数据等满间隔后只推送最新一条
+        let received = receive_json(&mut outbound_rx, 2).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(outbound_rx.try_recv().is_err());
+        task.abort();
+
+        assert_eq!(received[0]["type"], "error");
+        assert_eq!(received[1]["type"], "market_data");
+        assert_eq!(received[1]["seq"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_resume_replays_without_gaps_or_duplicates() {
+        let (engine, market_data) = engine_with_market_data().await;
+        let replay = Arc::new(ReplayCache::new(16));
+        for _ in 0..3 {
+            replay.publish(
+                "market_data",
+                WebSocketMessage::MarketData(market_data.clone()),
+            );
+        }
+
+        // 重连后的新连接从序号 2 开始补发
+        let receiver = replay.subscribe();
+        let (outbound_tx, mut outbound_rx) = mpsc::channel(16);
+        let (resume_tx, resume_rx) = mpsc::channel(1);
+        resume_tx.send(2).await.unwrap();
+        let task = tokio::spawn(forward_messages(
+            receiver,
+            resume_rx,
+            replay.clone(),
+            engine,
+            ConnectionInfo::new(),
+            outbound_tx,
+        ));
+        let mut received = receive_json(&mut outbound_rx, 2).await;
+
+        for _ in 0..2 {
+            replay.publish(
+                "market_data",
+                WebSocketMessage::MarketData(market_data.clone()),
+            );
+        }
+        received.extend(receive_json(&mut outbound_rx, 2).await);
+        assert!(outbound_rx.try_recv().is_err());
+        task.abort();
+
+        let seqs: Vec<u64> = received
+            .iter()
+            .map(|message| message["seq"].as_u64().unwrap())
+            .collect();
+        assert_eq!(seqs, vec![2, 3, 4, 5]);
+    }
+
+    #[tokio::test]
+    async fn test_drop_copy_replays_then_streams_in_sequence() {
+        let mut config = crate::config::EngineConfig::default();
+        config.drop_copy.enabled = true;
+        let engine = Arc::new(MatchingEngine::with_config(config));
+        let symbol = Symbol::new("BTC", "USDT");
+        for (side, user) in [(OrderSide::Sell, "seller"), (OrderSide::Buy, "buyer")] {
+            engine
+                .submit_order(Order::new(
+                    symbol,
+                    side,
+                    OrderType::Limit,
+                    1.0,
+                    Some(100.0),
+                    user.to_string(),
+                ))
+                .await
+                .unwrap();
+        }
+
+        let subscription = engine.subscribe_drop_copy(Some(2)).unwrap();
+        let replayed = subscription.replay.len();
+        let (outbound_tx, mut outbound_rx) = mpsc::channel(64);
+        let task = tokio::spawn(forward_drop_copy(
+            engine.clone(),
+            subscription,
+            Uuid::new_v4(),
+            WireEncoding::Json,
+            outbound_tx,
+        ));
+        engine
+            .submit_order(Order::new(
+                symbol,
+                OrderSide::Buy,
+                OrderType::Limit,
+                1.0,
+                Some(99.0),
+                "buyer".to_string(),
+            ))
+            .await
+            .unwrap();
+
+        let mut sequences = Vec::new();
+        while sequences.len() < replayed + 1 {
+            let Some(Message::Text(text)) = outbound_rx.recv().await else {
+                panic!("drop copy stream ended early");
+            };
+            let event: serde_json::Value = serde_json::from_str(&text).unwrap();
+            sequences.push(event["sequence"].as_u64().unwrap());
+        }
+        task.abort();
+
+        // drop copy 沿用事件总线的序号，盘口等其它事件占用的序号被跳过
+        assert!(sequences[0] >= 2);
+        assert!(sequences.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[tokio::test]
+    async fn test_user_stream_resync_sends_open_orders() {
+        let engine = MatchingEngine::new();
+        let order = Order::new(
+            Symbol::new("BTC", "USDT"),
+            OrderSide::Buy,
+            OrderType::Limit,
+            1.0,
+            Some(50000.0),
+            "alice".to_string(),
+        );
+        let order_id = order.id;
+        engine.submit_order(order).await.unwrap();
+
+        let messages = resync_messages(
+            &engine,
+            &ConnectionInfo::for_user("alice".to_string()),
+            "order_updates",
+            5,
+        );
+
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(
+            &messages[0],
+            WebSocketMessage::Resync { skipped: 5, .. }
+        ));
+        assert!(
+            matches!(&messages[1], WebSocketMessage::OrderUpdate(order) if order.id == order_id)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_public_connections_receive_anonymized_trades() {
+        let trade = Trade {
+            id: 7,
+            symbol: Symbol::new("BTC", "USDT"),
+            buy_order_id: 1,
+            sell_order_id: 2,
+            quantity: 1.0,
+            price: 100.0,
+            timestamp: Utc::now(),
+            buyer_id: "buyer".to_string(),
+            seller_id: "seller".to_string(),
+            trade_type: TradeType::Regular,
+            taker_side: Some(OrderSide::Buy),
+            buy_tag: None,
+            sell_tag: None,
+        };
+        let message = SequencedMessage {
+            seq: 3,
+            channel: "trades",
+            message: WebSocketMessage::Trade(trade),
+        };
+
+        let receive = |connection_info: ConnectionInfo| {
+            let (outbound_tx, mut outbound_rx) = mpsc::channel(1);
+            assert!(enqueue_sequenced(&outbound_tx, &connection_info, &message));
+            let Ok(Message::Text(text)) = outbound_rx.try_recv() else {
+                panic!("expected a text frame");
+            };
+            serde_json::from_str::<serde_json::Value>(&text).unwrap()
+        };
+
+        let public = receive(ConnectionInfo::new());
+        assert_eq!(public["seq"], 3);
+        assert_eq!(public["type"], "trade");
+        assert_eq!(public["id"], 7);
+        assert_eq!(public["taker_side"], "buy");
+        for field in ["buyer_id", "seller_id", "buy_order_id", "sell_order_id"] {
+            assert!(public.get(field).is_none(), "{} leaked", field);
+        }
+
+        let private = receive(ConnectionInfo::for_user("buyer".to_string()));
+        assert_eq!(private["type"], "trade");
+        assert_eq!(private["buyer_id"], "buyer");
+        assert_eq!(private["sell_order_id"], 2);
+    }
+
+    #[test]
+    fn test_public_liquidations_only_carry_orders() {
+        let message = WebSocketMessage::Liquidation(liquidation_event());
+
+        let public = serde_json::to_value(MessageView::for_connection(
+            &ConnectionInfo::new(),
+            &message,
+        ))
+        .unwrap();
+        assert_eq!(public["type"], "liquidation");
+        assert_eq!(public["orders"][0]["side"], "sell");
+        assert_eq!(public["orders"][0]["quantity"], 10.0);
+        assert_eq!(public["orders"][0]["price"], 89.5);
+        for field in [
+            "id",
+            "user_id",
+            "equity",
+            "cancelled_orders",
+            "remaining_equity",
+        ] {
+            assert!(public.get(field).is_none(), "{} leaked", field);
+        }
+        assert!(public["orders"][0].get("order_id").is_none());
+
+        let owner = ConnectionInfo::for_user("alice".to_string());
+        let private = serde_json::to_value(MessageView::for_connection(&owner, &message)).unwrap();
+        assert_eq!(private["user_id"], "alice");
+        assert_eq!(private["orders"][0]["order_id"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_subscription_snapshots_follow_subscription() {
+        let (engine, market_data) = engine_with_market_data().await;
+        let btc = Symbol::new("BTC", "USDT");
+        let types = |info: &ConnectionInfo| {
+            subscription_snapshots(&engine, info)
+                .iter()
+                .map(|message| serde_json::to_value(message).unwrap()["type"].clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            types(&ConnectionInfo::with_subscription(
+                SubscriptionType::OrderBook
+            )),
+            ["orderbook"]
+        );
+        assert_eq!(types(&ConnectionInfo::new()), ["orderbook", "market_data"]);
+        assert!(types(&ConnectionInfo::with_subscription(SubscriptionType::Trades)).is_empty());
+        assert!(types(&ConnectionInfo::for_user("seller".to_string())).is_empty());
+        let other = ConnectionInfo {
+            symbols: vec![Symbol::new("ETH", "USDT")],
+            ..ConnectionInfo::with_subscription(SubscriptionType::MarketData)
+        };
+        assert!(types(&other).is_empty());
+
+        let message = SnapshotMessage {
+            snapshot_seq: 7,
+            message: WebSocketMessage::MarketData(market_data),
+        };
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json["snapshot_seq"], 7);
+        assert_eq!(json["type"], "market_data");
+        assert_eq!(json["symbol"], serde_json::to_value(btc).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_book_ticker_pushed_on_top_of_book_change() {
+        let engine = MatchingEngine::new();
+        let symbol = Symbol::new("BTC", "USDT");
+        let mut receiver = engine.subscribe_book_ticker();
+
+        for (side, price) in [(OrderSide::Sell, 101.0), (OrderSide::Sell, 102.0)] {
+            engine
+                .submit_order(Order::new(
+                    symbol,
+                    side,
+                    OrderType::Limit,
+                    1.0,
+                    Some(price),
+                    "maker".to_string(),
+                ))
+                .await
+                .unwrap();
+        }
+        // 卖二档挂单不改变盘口，只推送一次
+        let ticker = receiver.try_recv().unwrap();
+        assert_eq!(ticker.ask_price, Some(101.0));
+        assert!(receiver.try_recv().is_err());
+
+        // 吃掉卖一后盘口移动到卖二
+        engine
+            .submit_order(Order::new(
+                symbol,
+                OrderSide::Buy,
+                OrderType::Limit,
+                1.0,
+                Some(101.0),
+                "taker".to_string(),
+            ))
+            .await
+            .unwrap();
+        let ticker = std::iter::from_fn(|| receiver.try_recv().ok())
+            .last()
+            .unwrap();
+        assert_eq!(ticker.ask_price, Some(102.0));
+        let current = engine.get_book_ticker(&symbol).unwrap();
+        assert_eq!(current.ask_price, Some(102.0));
+        assert_eq!(current.bid_price, None);
+
+        let info = ConnectionInfo::with_subscription(SubscriptionType::BookTicker);
+        assert!(should_send_book_ticker(&info, &ticker));
+        assert!(!should_send_book_ticker(
+            &ConnectionInfo::with_subscription(SubscriptionType::Trades),
+            &ticker
+        ));
+        assert!(!should_send_book_ticker(
+            &ConnectionInfo::for_user("maker".to_string()),
+            &ticker
+        ));
+    }
 }
@@ -0,0 +1,183 @@
+//! WebSocket 推送消息的编码
+//!
+//! 默认以 JSON 文本帧推送；客户端可以通过 `encoding` 参数或子协议协商 MessagePack、CBOR
+//! 二进制帧，深度等大消息的编码开销明显低于 JSON，体积也更小。二进制编码与 JSON 使用相同的字段名和结构。
+use axum::extract::ws::Message;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// 推送消息编码
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WireEncoding {
+    /// JSON 文本帧
+    #[default]
+    Json,
+    /// MessagePack 二进制帧，结构体编码为带字段名的 map
+    MessagePack,
+    /// CBOR 二进制帧
+    Cbor,
+}
+
+impl WireEncoding {
+    /// 服务端支持的 WebSocket 子协议，按优先级排列
+    pub const SUBPROTOCOLS: [&'static str; 3] = ["msgpack", "cbor", "json"];
+
+    /// 按 `encoding` 参数或子协议名解析编码，不区分大小写
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "msgpack" | "messagepack" => Some(Self::MessagePack),
+            "cbor" => Some(Self::Cbor),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::MessagePack => "msgpack",
+            Self::Cbor => "cbor",
+        }
+    }
+
+    /// 编码为 WebSocket 帧：JSON 为文本帧，其余为二进制帧
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Message, String> {
+        match self {
+            Self::Json => serde_json::to_string(value)
+                .map(Message::Text)
+                .map_err(|e| format!("Failed to encode JSON: {}", e)),
+            Self::MessagePack => rmp_serde::to_vec_named(value)
+                .map(Message::Binary)
+                .map_err(|e| format!("Failed to encode MessagePack: {}", e)),
+            Self::Cbor => {
+                let mut buffer = Vec::new();
+                ciborium::into_writer(value, &mut buffer)
+                    .map(|_| Message::Binary(buffer))
+                    .map_err(|e| format!("Failed to encode CBOR: {}", e))
+            }
+        }
+    }
+
+    /// 解码 `encode` 生成的帧，帧类型与编码不符时返回错误
+    pub fn decode<T: DeserializeOwned>(&self, message: &Message) -> Result<T, String> {
+        match (self, message) {
+            (Self::Json, Message::Text(text)) => {
+                serde_json::from_str(text).map_err(|e| format!("Failed to decode JSON: {}", e))
+            }
+            (Self::MessagePack, Message::Binary(bytes)) => rmp_serde::from_slice(bytes)
+                .map_err(|e| format!("Failed to decode MessagePack: {}", e)),
+            (Self::Cbor, Message::Binary(bytes)) => ciborium::from_reader(bytes.as_slice())
+                .map_err(|e| format!("Failed to decode CBOR: {}", e)),
+            _ => Err(format!(
+                "Unexpected WebSocket frame for {} encoding",
+                self.as_str()
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::*;
+    use crate::ws_replay::SequencedMessage;
+    use chrono::Utc;
+
+    fn sample_messages() -> Vec<WebSocketMessage> {
+        let symbol = Symbol::new("BTC", "USDT");
+        let level = |price: f64| PriceLevel {
+            price,
+            total_quantity: 1.5,
+            order_count: 2,
+        };
+        vec![
+            WebSocketMessage::OrderBook(OrderBookDepth {
+                symbol,
+                bids: vec![level(49999.5), level(49999.0)],
+                asks: vec![level(50000.5)],
+                timestamp: Utc::now(),
+            }),
+            WebSocketMessage::Trade(Trade {
+                id: 1,
+                symbol,
+                buy_order_id: 2,
+                sell_order_id: 3,
+                quantity: 0.25,
+                price: 50000.0,
+                timestamp: Utc::now(),
+                buyer_id: "buyer".to_string(),
+                seller_id: "seller".to_string(),
+                trade_type: TradeType::Regular,
+                taker_side: None,
+                buy_tag: None,
+                sell_tag: None,
+            }),
+            WebSocketMessage::OrderUpdate(Order::new(
+                symbol,
+                OrderSide::Buy,
+                OrderType::Limit,
+                1.0,
+                Some(50000.0),
+                "user".to_string(),
+            )),
+            WebSocketMessage::Resync {
+                channel: "all".to_string(),
+                skipped: 3,
+            },
+            WebSocketMessage::Pong { id: None },
+        ]
+    }
+
+    #[test]
+    fn test_encoding_round_trip() {
+        for encoding in [
+            WireEncoding::Json,
+            WireEncoding::MessagePack,
+            WireEncoding::Cbor,
+        ] {
+            for message in sample_messages() {
+                let frame = encoding.encode(&message).unwrap();
+                assert_eq!(
+                    matches!(frame, Message::Text(_)),
+                    encoding == WireEncoding::Json
+                );
+                let decoded: WebSocketMessage = encoding.decode(&frame).unwrap();
+                // 各编码解码后与 JSON 表示完全一致
+                assert_eq!(
+                    serde_json::to_value(&decoded).unwrap(),
+                    serde_json::to_value(&message).unwrap()
+                );
+            }
+
+            // 带序号的消息展开后解码为同样的消息，序号作为额外字段保留
+            let sequenced = SequencedMessage {
+                seq: 7,
+                channel: "trades",
+                message: sample_messages().remove(1),
+            };
+            let frame = encoding.encode(&sequenced).unwrap();
+            let value: serde_json::Value = encoding.decode(&frame).unwrap();
+            assert_eq!(value["seq"], 7);
+            assert_eq!(value["type"], "trade");
+            assert!(encoding.decode::<WebSocketMessage>(&frame).is_ok());
+        }
+
+        assert!(WireEncoding::Cbor
+            .decode::<WebSocketMessage>(&Message::Text("{}".to_string()))
+            .is_err());
+    }
+
+    #[test]
+    fn test_encoding_names() {
+        assert_eq!(
+            WireEncoding::from_name("MsgPack"),
+            Some(WireEncoding::MessagePack)
+        );
+        assert_eq!(WireEncoding::from_name("cbor"), Some(WireEncoding::Cbor));
+        assert_eq!(WireEncoding::from_name("json"), Some(WireEncoding::Json));
+        assert_eq!(WireEncoding::from_name("protobuf"), None);
+        for name in WireEncoding::SUBPROTOCOLS {
+            assert_eq!(WireEncoding::from_name(name).unwrap().as_str(), name);
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! WebSocket 推送的全局序号和回放缓存
+//!
+//! 引擎各广播通道的消息在这里统一编号后再分发给各连接，每个频道保留最近的若干条消息，
+//! 断线重连的客户端可以从最后收到的序号之后补齐，而不必整体重同步。
+use crate::matching_engine::MatchingEngine;
+use crate::types::WebSocketMessage;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+/// 编号后消息的分发通道容量，连接落后超过该数量时从回放缓存补齐
+const CHANNEL_CAPACITY: usize = 10000;
+
+/// 带全局序号的推送消息
+#[derive(Debug, Clone, Serialize)]
+pub struct SequencedMessage {
+    /// 所有频道共用、从 1 开始连续递增的序号
+    pub seq: u64,
+    /// 来源频道，同时是回放缓存的分区
+    #[serde(skip)]
+    pub channel: &'static str,
+    #[serde(flatten)]
+    pub message: WebSocketMessage,
+}
+
+/// 按频道划分的有界回放缓存
+///
+/// 序号分配、写入缓存和分发在同一把锁内完成，缓存中的消息与分发顺序一致。
+/// 各频道独立淘汰，成交等高频频道不会挤掉订单更新等低频频道的历史
+pub struct ReplayCache {
+    state: Mutex<ReplayState>,
+    sender: broadcast::Sender<Arc<SequencedMessage>>,
+}
+
+struct ReplayState {
+    next_seq: u64,
+    capacity: usize,
+    channels: HashMap<&'static str, ChannelBuffer>,
+}
+
+#[derive(Default)]
+struct ChannelBuffer {
+    messages: VecDeque<Arc<SequencedMessage>>,
+    /// 已被淘汰的最大序号
+    evicted_through: u64,
+}
+
+impl ReplayCache {
+    /// 创建回放缓存，`capacity` 为每个频道保留的消息数
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            state: Mutex::new(ReplayState {
+                next_seq: 1,
+                capacity,
+                channels: HashMap::new(),
+            }),
+            sender,
+        }
+    }
+
+    /// 创建回放缓存并订阅引擎的各广播通道，引擎释放后后台任务随之退出
+    pub fn start(engine: &MatchingEngine, capacity: usize) -> Arc<Self> {
+        let cache = Arc::new(Self::new(capacity));
+        cache.pump(engine.subscribe_trades(), "trades", WebSocketMessage::Trade);
+        cache.pump(
+            engine.subscribe_trade_busts(),
+            "trades",
+            WebSocketMessage::TradeBust,
+        );
+        cache.pump(
+            engine.subscribe_agg_trades(),
+            "agg_trades",
+            WebSocketMessage::AggTrade,
+        );
+        cache.pump(
+            engine.subscribe_orders(),
+            "order_updates",
+            WebSocketMessage::OrderUpdate,
+        );
+        cache.pump(
+            engine.subscribe_market_data(),
+            "market_data",
+            WebSocketMessage::MarketData,
+        );
+        cache.pump(
+            engine.subscribe_book_ticker(),
+            "book_ticker",
+            WebSocketMessage::BookTicker,
+        );
+        cache.pump(
+            engine.accounts().subscribe_balances(),
+            "balances",
+            WebSocketMessage::BalanceUpdate,
+        );
+        cache.pump(
+            engine.subscribe_symbol_status(),
+            "symbol_status",
+            WebSocketMessage::SymbolStatus,
+        );
+        cache.pump(
+            engine.subscribe_auction(),
+            "auction",
+            WebSocketMessage::AuctionIndicative,
+        );
+        cache.pump(
+            engine.subscribe_mark_prices(),
+            "mark_price",
+            WebSocketMessage::MarkPrice,
+        );
+        cache.pump(
+            engine.subscribe_otc_trades(),
+            "otc_trades",
+            WebSocketMessage::OtcTrade,
+        );
+        cache.pump(
+            engine.subscribe_liquidations(),
+            "liquidation",
+            WebSocketMessage::Liquidation,
+        );
+        cache.pump(
+            engine.subscribe_symbol_listings(),
+            "symbol_listing",
+            WebSocketMessage::SymbolListing,
+        );
+        cache
+    }
+
+    fn pump<T>(
+        self: &Arc<Self>,
+        mut receiver: broadcast::Receiver<T>,
+        channel: &'static str,
+        to_message: fn(T) -> WebSocketMessage,
+    ) where
+        T: Clone + Send + 'static,
+    {
+        let cache = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(item) => {
+                        cache.publish(channel, to_message(item));
+                    }
+                    Err(RecvError::Lagged(skipped)) => warn!(
+                        "WebSocket replay cache lagged on {} channel, {} messages lost",
+                        channel, skipped
+                    ),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// 为消息分配序号，写入频道缓存并分发给各连接，返回分配的序号
+    pub fn publish(&self, channel: &'static str, message: WebSocketMessage) -> u64 {
+        let mut state = self.state.lock();
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        let message = Arc::new(SequencedMessage {
+            seq,
+            channel,
+            message,
+        });
+
+        let capacity = state.capacity;
+        let buffer = state.channels.entry(channel).or_default();
+        if buffer.messages.len() >= capacity {
+            if let Some(evicted) = buffer.messages.pop_front() {
+                buffer.evicted_through = evicted.seq;
+            }
+        }
+        buffer.messages.push_back(Arc::clone(&message));
+
+        let _ = self.sender.send(message);
+        seq
+    }
+
+    /// 订阅之后分发的编号消息
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<SequencedMessage>> {
+        self.sender.subscribe()
+    }
+
+    /// 下一条消息将使用的序号
+    pub fn next_seq(&self) -> u64 {
+        self.state.lock().next_seq
+    }
+
+    /// 取出 `wants_channel` 选中的频道中序号不小于 `from_seq` 的消息，按序号排序
+    ///
+    /// 任一选中频道已淘汰了该序号之后的消息，或 `from_seq` 超过下一个序号（如服务重启前的序号）时返回 None，
+    /// 调用方需要改为整体重同步
+    pub fn replay(
+        &self,
+        from_seq: u64,
+        wants_channel: impl Fn(&str) -> bool,
+    ) -> Option<Vec<Arc<SequencedMessage>>> {
+        let state = self.state.lock();
+        if from_seq > state.next_seq {
+            return None;
+        }
+
+        let mut messages = Vec::new();
+        for (channel, buffer) in &state.channels {
+            if !wants_channel(channel) {
+                continue;
+            }
+            if buffer.evicted_through >= from_seq {
+                return None;
+            }
+            messages.extend(
+                buffer
+                    .messages
+                    .iter()
+                    .filter(|message| message.seq >= from_seq)
+                    .cloned(),
+            );
+        }
+        messages.sort_unstable_by_key(|message| message.seq);
+        Some(messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(message: &str) -> WebSocketMessage {
+        WebSocketMessage::Error {
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_replay_per_channel_eviction() {
+        let cache = ReplayCache::new(2);
+        let mut receiver = cache.subscribe();
+        for _ in 0..3 {
+            cache.publish("trades", error("trade"));
+        }
+        cache.publish("order_updates", error("order"));
+        assert_eq!(cache.next_seq(), 5);
+        assert_eq!(receiver.try_recv().unwrap().seq, 1);
+
+        // 成交频道已淘汰序号 1，只关注订单更新的连接仍可回放
+        assert!(cache.replay(1, |_| true).is_none());
+        let orders = cache
+            .replay(1, |channel| channel == "order_updates")
+            .unwrap();
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].seq, 4);
+
+        let seqs: Vec<u64> = cache
+            .replay(2, |_| true)
+            .unwrap()
+            .iter()
+            .map(|message| message.seq)
+            .collect();
+        assert_eq!(seqs, vec![2, 3, 4]);
+        assert!(cache.replay(5, |_| true).unwrap().is_empty());
+        assert!(cache.replay(6, |_| true).is_none());
+
+        let json = serde_json::to_value(&*orders[0]).unwrap();
+        assert_eq!(json["seq"], 4);
+        assert_eq!(json["type"], "error");
+        assert!(json.get("channel").is_none());
+    }
+}
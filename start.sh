@@ -11,7 +11,7 @@ fi
 
 # 启动后端服务器
 echo "📦 启动后端服务器 (端口 8888)..."
-cargo run &
+cargo run -- --simple &
 BACKEND_PID=$!
 
 # 等待后端服务器启动